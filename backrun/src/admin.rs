@@ -0,0 +1,90 @@
+use std::{
+    collections::BTreeMap,
+    sync::{Arc, Mutex},
+};
+
+use chrono::{DateTime, Utc};
+use jito_searcher_client::{admin_server::AdminHandler, runtime_params::ParamStore};
+use serde::Serialize;
+use serde_json::{json, Value};
+use solana_sdk::clock::Slot;
+use tokio::sync::watch;
+
+/// A pipeline's state as served by `GET /status`, published by its searcher loop every
+/// maintenance tick.
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct PipelineStatus {
+    pub block_engine_url: String,
+    /// The searcher loop is connected to the block engine and running.
+    pub running: bool,
+    pub highest_slot: Slot,
+    pub queued_opportunities: usize,
+    pub bundles_sent: u64,
+    pub tip_budget_remaining_lamports: Option<u64>,
+    /// Why the dead man switch halted submissions.
+    pub halted: Option<String>,
+    /// Whether this instance holds the HA lease, None without one.
+    pub lease_active: Option<bool>,
+    pub last_mempool_tx: Option<DateTime<Utc>>,
+    pub last_bundle_sent: Option<DateTime<Utc>>,
+    pub last_bundle_result: Option<DateTime<Utc>>,
+}
+
+/// The admin endpoint's view of the bot: pause and resume through the runtime params' operator
+/// pause, which a reload of the params file doesn't undo, drain through the same graceful
+/// shutdown as SIGTERM.
+pub struct BackrunAdmin {
+    runtime_params: ParamStore,
+    shutdown: Arc<watch::Sender<bool>>,
+    pipelines: Mutex<BTreeMap<String, PipelineStatus>>,
+}
+
+impl BackrunAdmin {
+    pub fn new(runtime_params: ParamStore, shutdown: Arc<watch::Sender<bool>>) -> Self {
+        Self {
+            runtime_params,
+            shutdown,
+            pipelines: Mutex::default(),
+        }
+    }
+
+    pub fn publish(&self, pipeline: &str, status: PipelineStatus) {
+        self.pipelines
+            .lock()
+            .unwrap()
+            .insert(pipeline.to_string(), status);
+    }
+
+    /// Marks the pipeline as no longer running, keeping the rest of its last status.
+    pub fn stopped(&self, pipeline: &str) {
+        if let Some(status) = self.pipelines.lock().unwrap().get_mut(pipeline) {
+            status.running = false;
+        }
+    }
+}
+
+impl AdminHandler for BackrunAdmin {
+    fn status(&self) -> Value {
+        let params = self.runtime_params.get();
+        json!({
+            "paused": params.paused,
+            "operator_paused": self.runtime_params.operator_paused(),
+            "draining": *self.shutdown.borrow(),
+            "runtime_params": params,
+            "pipelines": *self.pipelines.lock().unwrap(),
+        })
+    }
+
+    fn pause(&self) -> bool {
+        self.runtime_params.set_operator_paused(true)
+    }
+
+    fn resume(&self) -> bool {
+        self.runtime_params.set_operator_paused(false)
+    }
+
+    fn drain(&self) -> bool {
+        self.shutdown
+            .send_if_modified(|draining| !std::mem::replace(draining, true))
+    }
+}
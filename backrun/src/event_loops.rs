@@ -1,15 +1,26 @@
-use std::{sync::Arc, time::Duration};
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
 
-use futures_util::StreamExt;
+use futures_util::{future, stream, StreamExt};
 use jito_protos::{
     bundle::BundleResult,
+    convert::{try_versioned_tx_from_packet, PacketConversionError},
     searcher::{
         mempool_subscription, MempoolSubscription, PendingTxNotification,
         SubscribeBundleResultsRequest, WriteLockedAccountSubscriptionV0,
     },
 };
-use jito_searcher_client::get_searcher_client;
-use log::info;
+#[cfg(feature = "fault-injection")]
+use jito_searcher_client::fault_injection::faulted_message;
+use jito_searcher_client::{
+    auth_failover::FailoverSearcherClient,
+    client_headers::ClientHeaders,
+    grpc_trace::{trace_message, traced},
+    interest_filter::InterestFilter,
+};
+use log::{error, info};
 use solana_client::{
     nonblocking::pubsub_client::PubsubClient,
     rpc_config::{RpcBlockSubscribeConfig, RpcBlockSubscribeFilter},
@@ -22,13 +33,27 @@ use solana_sdk::{
     commitment_config::{CommitmentConfig, CommitmentLevel},
     pubkey::Pubkey,
     signature::Keypair,
+    transaction::VersionedTransaction,
 };
 use solana_transaction_status::{TransactionDetails, UiTransactionEncoding};
-use tokio::{sync::mpsc::Sender, time::sleep};
-use tonic::Streaming;
+use tokio::{
+    sync::{
+        mpsc::{Receiver, Sender},
+        watch,
+    },
+    task::spawn_blocking,
+    time::sleep,
+};
+use tonic::{Status, Streaming};
+
+use crate::watchdog::Heartbeat;
 
 // slot update subscription loop that attempts to maintain a connection to an RPC server
-pub async fn slot_subscribe_loop(pubsub_addr: String, slot_sender: Sender<Slot>) {
+pub async fn slot_subscribe_loop(
+    pubsub_addr: String,
+    slot_sender: Sender<Slot>,
+    heartbeat: Heartbeat,
+) {
     let mut connect_errors: u64 = 0;
     let mut slot_subscribe_errors: u64 = 0;
     let mut slot_subscribe_disconnect_errors: u64 = 0;
@@ -40,6 +65,7 @@ pub async fn slot_subscribe_loop(pubsub_addr: String, slot_sender: Sender<Slot>)
             Ok(pubsub_client) => match pubsub_client.slot_updates_subscribe().await {
                 Ok((mut slot_update_subscription, _unsubscribe_fn)) => {
                     while let Some(slot_update) = slot_update_subscription.next().await {
+                        heartbeat.beat();
                         if let SlotUpdate::FirstShredReceived { slot, timestamp: _ } = slot_update {
                             datapoint_info!("slot_subscribe_slot", ("slot", slot, i64));
                             if slot_sender.send(slot).await.is_err() {
@@ -81,6 +107,7 @@ pub async fn slot_subscribe_loop(pubsub_addr: String, slot_sender: Sender<Slot>)
 pub async fn block_subscribe_loop(
     pubsub_addr: String,
     block_receiver: Sender<rpc_response::Response<RpcBlockUpdate>>,
+    heartbeat: Heartbeat,
 ) {
     let mut connect_errors: u64 = 0;
     let mut block_subscribe_errors: u64 = 0;
@@ -107,6 +134,7 @@ pub async fn block_subscribe_loop(
             {
                 Ok((mut block_update_subscription, _unsubscribe_fn)) => {
                     while let Some(block_update) = block_update_subscription.next().await {
+                        heartbeat.beat();
                         datapoint_info!(
                             "block_subscribe_slot",
                             ("slot", block_update.context.slot, i64)
@@ -146,9 +174,11 @@ pub async fn block_subscribe_loop(
 // attempts to maintain connection to searcher service and stream pending transaction notifications over a channel
 pub async fn pending_tx_loop(
     block_engine_url: String,
-    auth_keypair: Arc<Keypair>,
+    auth_keypairs: Vec<Arc<Keypair>>,
+    headers: ClientHeaders,
     pending_tx_sender: Sender<PendingTxNotification>,
     backrun_pubkeys: Vec<Pubkey>,
+    heartbeat: Heartbeat,
 ) {
     let mut num_searcher_connection_errors: usize = 0;
     let mut num_pending_tx_sub_errors: usize = 0;
@@ -157,127 +187,210 @@ pub async fn pending_tx_loop(
 
     info!("backrun pubkeys: {:?}", backrun_pubkeys);
 
+    // kept across reconnects, so the loop stays on the keypair it failed over to
+    let mut searcher_client: Option<FailoverSearcherClient> = None;
     loop {
         sleep(Duration::from_secs(1)).await;
 
-        match get_searcher_client(&block_engine_url, &auth_keypair).await {
-            Ok(mut searcher_client) => {
-                match searcher_client
-                    .subscribe_mempool(MempoolSubscription {
-                        regions: vec![],
-                        msg: Some(mempool_subscription::Msg::WlaV0Sub(
-                            WriteLockedAccountSubscriptionV0 {
-                                accounts: backrun_pubkeys.iter().map(|pk| pk.to_string()).collect(),
-                            },
-                        )),
-                    })
-                    .await
-                {
-                    Ok(pending_tx_stream_response) => {
-                        let mut pending_tx_stream = pending_tx_stream_response.into_inner();
-                        while let Some(maybe_notification) = pending_tx_stream.next().await {
-                            match maybe_notification {
-                                Ok(notification) => {
-                                    if pending_tx_sender.send(notification).await.is_err() {
-                                        datapoint_error!(
-                                            "pending_tx_send_error",
-                                            ("errors", 1, i64)
-                                        );
-                                        return;
-                                    }
-                                }
-                                Err(e) => {
-                                    num_pending_tx_stream_errors += 1;
-                                    datapoint_error!(
-                                        "searcher_pending_tx_stream_error",
-                                        ("errors", num_pending_tx_stream_errors, i64),
-                                        ("error_str", e.to_string(), String)
-                                    );
-                                    break;
-                                }
+        let mut client = match searcher_client.take() {
+            Some(client) => client,
+            None => match FailoverSearcherClient::connect(
+                &block_engine_url,
+                auth_keypairs.clone(),
+                headers.clone(),
+            )
+            .await
+            {
+                Ok(client) => client,
+                Err(e) => {
+                    num_searcher_connection_errors += 1;
+                    datapoint_error!(
+                        "searcher_connection_error",
+                        ("errors", num_searcher_connection_errors, i64),
+                        ("error_str", e.to_string(), String)
+                    );
+                    continue;
+                }
+            },
+        };
+
+        let error = match traced(
+            "SubscribeMempool",
+            MempoolSubscription {
+                regions: vec![],
+                msg: Some(mempool_subscription::Msg::WlaV0Sub(
+                    WriteLockedAccountSubscriptionV0 {
+                        accounts: backrun_pubkeys.iter().map(|pk| pk.to_string()).collect(),
+                    },
+                )),
+            },
+            |request| client.client_mut().subscribe_mempool(request),
+        )
+        .await
+        {
+            Ok(pending_tx_stream_response) => {
+                let mut pending_tx_stream = pending_tx_stream_response.into_inner();
+                let mut stream_error = None;
+                while let Some(maybe_notification) = pending_tx_stream.next().await {
+                    #[cfg(feature = "fault-injection")]
+                    let Some(maybe_notification) =
+                        faulted_message("SubscribeMempool", maybe_notification).await
+                    else {
+                        continue;
+                    };
+                    match maybe_notification {
+                        Ok(notification) => {
+                            heartbeat.beat();
+                            trace_message("SubscribeMempool", &notification);
+                            if pending_tx_sender.send(notification).await.is_err() {
+                                datapoint_error!("pending_tx_send_error", ("errors", 1, i64));
+                                return;
                             }
                         }
-                        num_pending_tx_stream_disconnects += 1;
-                        datapoint_error!(
-                            "searcher_pending_tx_stream_disconnect",
-                            ("errors", num_pending_tx_stream_disconnects, i64),
-                        );
-                    }
-                    Err(e) => {
-                        num_pending_tx_sub_errors += 1;
-                        datapoint_error!(
-                            "searcher_pending_tx_sub_error",
-                            ("errors", num_pending_tx_sub_errors, i64),
-                            ("error_str", e.to_string(), String)
-                        );
+                        Err(e) => {
+                            num_pending_tx_stream_errors += 1;
+                            datapoint_error!(
+                                "searcher_pending_tx_stream_error",
+                                ("errors", num_pending_tx_stream_errors, i64),
+                                ("error_str", e.to_string(), String)
+                            );
+                            stream_error = Some(e);
+                            break;
+                        }
                     }
                 }
+                num_pending_tx_stream_disconnects += 1;
+                datapoint_error!(
+                    "searcher_pending_tx_stream_disconnect",
+                    ("errors", num_pending_tx_stream_disconnects, i64),
+                );
+                stream_error
             }
             Err(e) => {
-                num_searcher_connection_errors += 1;
+                num_pending_tx_sub_errors += 1;
                 datapoint_error!(
-                    "searcher_connection_error",
-                    ("errors", num_searcher_connection_errors, i64),
+                    "searcher_pending_tx_sub_error",
+                    ("errors", num_pending_tx_sub_errors, i64),
                     ("error_str", e.to_string(), String)
                 );
+                Some(e)
             }
+        };
+        if keep_searcher_client(&mut client, error.as_ref()).await {
+            searcher_client = Some(client);
         }
     }
 }
 
 pub async fn bundle_results_loop(
     block_engine_url: String,
-    auth_keypair: Arc<Keypair>,
+    auth_keypairs: Vec<Arc<Keypair>>,
+    headers: ClientHeaders,
     bundle_results_sender: Sender<BundleResult>,
+    heartbeat: Heartbeat,
 ) {
     let mut connection_errors: usize = 0;
     let mut response_errors: usize = 0;
 
+    // kept across reconnects, so the loop stays on the keypair it failed over to
+    let mut searcher_client: Option<FailoverSearcherClient> = None;
     loop {
         sleep(Duration::from_millis(1000)).await;
-        match get_searcher_client(&block_engine_url, &auth_keypair).await {
-            Ok(mut c) => match c
-                .subscribe_bundle_results(SubscribeBundleResultsRequest {})
-                .await
+        let mut client = match searcher_client.take() {
+            Some(client) => client,
+            None => match FailoverSearcherClient::connect(
+                &block_engine_url,
+                auth_keypairs.clone(),
+                headers.clone(),
+            )
+            .await
             {
-                Ok(resp) => {
-                    consume_bundle_results_stream(resp.into_inner(), &bundle_results_sender).await;
-                }
+                Ok(client) => client,
                 Err(e) => {
-                    response_errors += 1;
+                    connection_errors += 1;
                     datapoint_error!(
                         "searcher_bundle_results_error",
-                        ("errors", response_errors, i64),
+                        ("errors", connection_errors, i64),
                         ("msg", e.to_string(), String)
                     );
+                    continue;
                 }
             },
+        };
+
+        let error = match traced(
+            "SubscribeBundleResults",
+            SubscribeBundleResultsRequest {},
+            |request| client.client_mut().subscribe_bundle_results(request),
+        )
+        .await
+        {
+            Ok(resp) => {
+                consume_bundle_results_stream(resp.into_inner(), &bundle_results_sender, &heartbeat)
+                    .await
+            }
             Err(e) => {
-                connection_errors += 1;
+                response_errors += 1;
                 datapoint_error!(
                     "searcher_bundle_results_error",
-                    ("errors", connection_errors, i64),
+                    ("errors", response_errors, i64),
                     ("msg", e.to_string(), String)
                 );
+                Some(e)
             }
+        };
+        if keep_searcher_client(&mut client, error.as_ref()).await {
+            searcher_client = Some(client);
+        }
+    }
+}
+
+/// Fails over to the next auth keypair if the subscription ended with `error` because the active
+/// one got throttled or rejected. Returns false if no keypair is accepted anymore, in which case
+/// the loop connects from scratch.
+async fn keep_searcher_client(
+    searcher_client: &mut FailoverSearcherClient,
+    error: Option<&Status>,
+) -> bool {
+    let Some(status) = error else {
+        return true;
+    };
+    match searcher_client.handle_error(status).await {
+        Ok(_) => true,
+        Err(e) => {
+            datapoint_error!(
+                "searcher_auth_failover_error",
+                ("errors", 1, i64),
+                ("error_str", e.to_string(), String)
+            );
+            false
         }
     }
 }
 
+/// Forwards bundle results until the stream ends, returning the error it ended with if any.
 pub async fn consume_bundle_results_stream(
     mut stream: Streaming<BundleResult>,
     bundle_results_sender: &Sender<BundleResult>,
-) {
+    heartbeat: &Heartbeat,
+) -> Option<Status> {
     while let Some(maybe_msg) = stream.next().await {
+        #[cfg(feature = "fault-injection")]
+        let Some(maybe_msg) = faulted_message("SubscribeBundleResults", maybe_msg).await
+        else {
+            continue;
+        };
         match maybe_msg {
             Ok(msg) => {
+                heartbeat.beat();
+                trace_message("SubscribeBundleResults", &msg);
                 if let Err(e) = bundle_results_sender.send(msg).await {
                     datapoint_error!(
                         "searcher_bundle_results_error",
                         ("errors", 1, i64),
                         ("msg", e.to_string(), String)
                     );
-                    return;
+                    return None;
                 }
             }
             Err(e) => {
@@ -286,8 +399,81 @@ pub async fn consume_bundle_results_stream(
                     ("errors", 1, i64),
                     ("msg", e.to_string(), String)
                 );
-                return;
+                return Some(e);
+            }
+        }
+    }
+    None
+}
+
+/// The packets of a mempool notification converted to transactions.
+pub struct DecodedTransactions {
+    /// When the notification came off the stream, before it waited on decoding.
+    pub received_at: Instant,
+    /// Failures are kept so the searcher loop can count them and apply strict conversion.
+    pub transactions: Vec<Result<VersionedTransaction, PacketConversionError>>,
+}
+
+/// Deserializes mempool notifications on the blocking pool, so a burst of packets doesn't stall
+/// the I/O workers or the searcher loop. Up to `decode_threads` notifications are decoded at
+/// once and forwarded in the order they arrived.
+///
+/// Notifications that arrive while no connected leader is near, as `leader_nearby` tells, are
+/// dropped undecoded so nothing downstream evaluates them, and packets the interest filter rules
+/// out never get decoded.
+pub async fn decode_pending_tx_loop(
+    mut pending_tx_receiver: Receiver<PendingTxNotification>,
+    decoded_tx_sender: Sender<DecodedTransactions>,
+    leader_nearby: watch::Receiver<bool>,
+    mut interest_filter: Option<InterestFilter>,
+    decode_threads: usize,
+) {
+    let mut last_report = Instant::now();
+    let mut idle_notifications = 0;
+    let mut decoded = stream::poll_fn(|cx| pending_tx_receiver.poll_recv(cx))
+        .filter_map(|mut pending_tx_notification| {
+            if last_report.elapsed() >= Duration::from_secs(5) {
+                datapoint_info!(
+                    "backrun_leader_gate",
+                    ("leader_nearby", *leader_nearby.borrow(), bool),
+                    ("idle_notifications", idle_notifications, i64),
+                );
+                idle_notifications = 0;
+                if let Some(interest_filter) = &mut interest_filter {
+                    interest_filter.report("backrun_interest_filter");
+                }
+                last_report = Instant::now();
+            }
+            // block engine starts forwarding a few slots early, for super high activity accounts
+            // it might be ideal to wait until the leader slot is up
+            if !*leader_nearby.borrow() {
+                idle_notifications += 1;
+                return future::ready(None);
+            }
+            if let Some(interest_filter) = &mut interest_filter {
+                pending_tx_notification
+                    .transactions
+                    .retain(|packet| interest_filter.matches(packet));
+            }
+            future::ready(Some((Instant::now(), pending_tx_notification.transactions)))
+        })
+        .map(|(received_at, packets)| {
+            spawn_blocking(move || DecodedTransactions {
+                received_at,
+                transactions: packets.iter().map(try_versioned_tx_from_packet).collect(),
+            })
+        })
+        .buffered(decode_threads);
+
+    while let Some(result) = decoded.next().await {
+        match result {
+            Ok(decoded_txs) => {
+                if decoded_tx_sender.send(decoded_txs).await.is_err() {
+                    info!("searcher loop exited, stopping packet decoding");
+                    return;
+                }
             }
+            Err(e) => error!("packet decoding task failed: {e}"),
         }
     }
 }
@@ -1,31 +1,81 @@
+// tonic's Status and the RPC client's ClientError are both large, and errors wrap them as is
+#![allow(clippy::result_large_err)]
+
+mod admin;
 mod event_loops;
+mod pipeline;
+mod watchdog;
 
 use std::{
     collections::{hash_map::Entry, HashMap, HashSet},
-    path::PathBuf,
+    net::SocketAddr,
+    path::{Path, PathBuf},
     result,
     str::FromStr,
     sync::Arc,
-    time::{Duration, Instant},
+    time::{Duration, Instant, SystemTime},
 };
 
+use chrono::{DateTime, Utc};
 use clap::Parser;
 use env_logger::TimestampPrecision;
 use histogram::Histogram;
 use jito_protos::{
     bundle::BundleResult,
-    convert::versioned_tx_from_packet,
+    convert::PacketConversionError,
     searcher::{
         searcher_service_client::SearcherServiceClient, ConnectedLeadersRequest,
-        NextScheduledLeaderRequest, PendingTxNotification, SendBundleResponse,
+        NextScheduledLeaderRequest, SendBundleResponse,
     },
 };
+#[cfg(feature = "fault-injection")]
+use jito_searcher_client::fault_injection;
 use jito_searcher_client::{
-    get_searcher_client, send_bundle_no_wait, token_authenticator::ClientInterceptor,
+    admin_server::spawn_admin_server,
+    amount::parse_lamports,
+    assertions::SlotAssertion,
+    audit_log::{AuditLog, BundleSubmission},
+    auth_failover::{is_auth_keypair_failure, FailoverSearcherClient},
+    bundle_expiry::BlockhashExpiry,
+    client_headers::{ClientHeaderError, ClientHeaders},
+    clock_offset::{measure_clock_offset, ntp_sample, ClockOffset},
+    cluster::{check_cluster, Cluster, ClusterCheckError},
+    config_validation::{ConfigErrors, ConfigValidator, MIN_TIP_LAMPORTS},
+    congestion::{AuctionSignal, CongestionConfig, CongestionMonitor},
+    dead_man_switch::{DeadManSwitch, DeadManSwitchConfig, SubmissionOutcome},
+    echo_latency::{block_engine_region, EchoLatencyTracker},
+    ev_gate::{estimate_fee_lamports, ExpectedValueGate, ProfitEstimate},
+    grpc_trace::{self, traced},
+    interest_filter::InterestFilter,
+    leader_skip_rate::LeaderSkipTracker,
+    lease::{FileLeaseStore, Lease},
+    observation::ObservationStats,
+    opportunity_queue::{drop_reason, DropReason, Opportunity, OpportunityQueue},
+    packet_stats::{collect_converted, PacketConversionStats},
+    pool_registry::{Dex, PoolRegistry, PoolRegistryError, TokenPair},
+    priority::{PriorityClass, TipMultipliers},
+    redis_state::{RedisConfig, RedisState},
+    region_race::{first_accepted, RegionHysteresis, RegionRace},
+    rpc_latency::RpcLatencyTracker,
+    runtime_config::{pin_current_thread, RuntimeConfig},
+    runtime_params::ParamStore,
+    send_bundle_no_wait,
+    shadow::ShadowComparison,
+    simulation_policy::{AllowedSimulationError, SimulationPolicy},
+    state_snapshot::{load_snapshot, save_snapshot},
+    tip_budget::TipBudget,
+    tip_payment::{tip_accounts, TipShare, TipSplit},
+    token_authenticator::ClientInterceptor,
+    token_guard::{TokenGuard, TokenGuardConfig},
+    token_safety::{RiskWeights, TokenSafetyChecker},
+    tpu_fallback::{is_block_engine_unavailable, InclusionPath, TpuFallbackSender},
+    wasm_strategy::{InstructionSpec, StrategyEvent, WasmStrategy, WasmStrategyError},
+    write_lock_limiter::{WriteLockLimiter, WriteLockLimits},
     BlockEngineConnectionError,
 };
 use log::*;
 use rand::{rngs::ThreadRng, thread_rng, Rng};
+use serde::{Deserialize, Serialize};
 use solana_client::{
     client_error::ClientError,
     nonblocking::{pubsub_client::PubsubClientError, rpc_client::RpcClient},
@@ -39,23 +89,31 @@ use solana_sdk::{
     hash::Hash,
     pubkey::Pubkey,
     signature::{read_keypair_file, Keypair, Signature, Signer},
-    system_instruction::transfer,
     transaction::{Transaction, VersionedTransaction},
 };
 use spl_memo::build_memo;
 use thiserror::Error;
 use tokio::{
-    runtime::Builder,
-    sync::mpsc::{channel, Receiver},
-    time::interval,
+    signal::unix::{signal, SignalKind},
+    sync::{
+        mpsc::{channel, Receiver},
+        watch,
+    },
+    time::{interval, sleep},
 };
 use tonic::{codegen::InterceptedService, transport::Channel, Response, Status};
 
-use crate::event_loops::{
-    block_subscribe_loop, bundle_results_loop, pending_tx_loop, slot_subscribe_loop,
+use crate::{
+    admin::{BackrunAdmin, PipelineStatus},
+    event_loops::{
+        block_subscribe_loop, bundle_results_loop, decode_pending_tx_loop, pending_tx_loop,
+        slot_subscribe_loop, DecodedTransactions,
+    },
+    pipeline::{apply_strategy_config, PipelineConfig},
+    watchdog::{supervise, WatchdogConfig},
 };
 
-#[derive(Parser, Debug)]
+#[derive(Clone, Parser, Debug)]
 #[clap(author, version, about, long_about = None)]
 struct Args {
     /// URL of the block engine.
@@ -67,14 +125,29 @@ struct Args {
     #[arg(long, env)]
     backrun_accounts: Vec<Pubkey>,
 
+    /// Token pairs, as MINT:MINT, whose AMM pools are discovered on startup and backrun along
+    /// with --backrun-accounts.
+    #[arg(long, env, value_delimiter = ',')]
+    pool_pairs: Vec<TokenPair>,
+
+    /// Dexes --pool-pairs are discovered on.
+    #[arg(
+        long,
+        env,
+        value_delimiter = ',',
+        default_value = "raydium-amm-v4,orca-whirlpool,meteora-dlmm"
+    )]
+    pool_dexes: Vec<Dex>,
+
     /// Path to keypair file used to sign and pay for transactions
     #[arg(long, env)]
     payer_keypair: PathBuf,
 
-    /// Path to keypair file used to authenticate with the Jito Block Engine
+    /// Comma-separated paths to keypair files used to authenticate with the Jito Block Engine.
+    /// If the block engine rate limits or rejects one, the next is used.
     /// See: https://jito-labs.gitbook.io/mev/searcher-resources/getting-started#block-engine-api-key
-    #[arg(long, env)]
-    auth_keypair: PathBuf,
+    #[arg(long, env, value_delimiter = ',', required = true)]
+    auth_keypair: Vec<PathBuf>,
 
     /// RPC Websocket URL.
     /// See: https://solana.com/docs/rpc/websocket
@@ -86,15 +159,40 @@ struct Args {
     #[arg(long, env)]
     rpc_url: String,
 
+    /// Cluster --rpc-url must be on: mainnet-beta, testnet or devnet. Inferred from
+    /// --block-engine-url if not set, the check is skipped if neither names one.
+    #[arg(long, env)]
+    cluster: Option<Cluster>,
+
     /// Message to pass into the memo program as part of a bundle.
     #[arg(long, env, default_value = "jito backrun")]
     message: String,
 
+    /// WASM strategy module that builds the bundles instead of the memo backrun. It is handed
+    /// mempool transactions, slots and bundle results, and swapped for a new build when the file
+    /// changes.
+    #[arg(long, env)]
+    wasm_strategy: Option<PathBuf>,
+
+    /// Candidate WASM strategy to run in shadow: it gets the same events as the live strategy
+    /// and its bundles are compared against the live ones, but never sent.
+    #[arg(long, env)]
+    shadow_wasm_strategy: Option<PathBuf>,
+
     /// Tip payment program public key
     /// See: https://jito-foundation.gitbook.io/mev/mev-payment-and-distribution/on-chain-addresses
     #[arg(long, env)]
     tip_program_id: Pubkey,
 
+    /// Program that checks slot assertions. When set, every backrun transaction reverts if it
+    /// executes more than --slot-assertion-window slots after the opportunity was seen.
+    #[arg(long, env)]
+    slot_assertion_program_id: Option<Pubkey>,
+
+    /// Slots after the opportunity was seen that a backrun transaction may still execute in.
+    #[arg(long, env, default_value_t = 4)]
+    slot_assertion_window: u64,
+
     /// Comma-separated list of regions to request cross-region data from.
     /// If no region specified, then default to the currently connected block engine's region.
     /// Details: https://jito-labs.gitbook.io/mev/searcher-services/recommendations#cross-region
@@ -105,6 +203,393 @@ struct Args {
     /// Subscribe and print bundle results.
     #[arg(long, env, default_value_t = true)]
     subscribe_bundle_results: bool,
+
+    /// Restart the slot and block subscriptions if they are silent for this many seconds.
+    #[arg(long, env, default_value_t = 10)]
+    pubsub_stream_timeout_secs: u64,
+
+    /// Restart the mempool subscription if it is silent for this many seconds.
+    /// Quiet backrun accounts may need a larger value.
+    #[arg(long, env, default_value_t = 120)]
+    mempool_stream_timeout_secs: u64,
+
+    /// Restart the bundle results subscription if it is silent for this many seconds.
+    #[arg(long, env, default_value_t = 600)]
+    bundle_results_stream_timeout_secs: u64,
+
+    /// Exit on mempool packets that fail to convert to transactions instead of skipping them.
+    #[arg(long, env)]
+    strict_packet_conversion: bool,
+
+    /// Run subscriptions, strategies and every check as usual, but log the bundles that would
+    /// have been sent with their estimated outcome instead of sending them.
+    #[arg(long, env)]
+    observe_only: bool,
+
+    /// Lamports tipped per backrun bundle. Amounts take a unit, e.g. 0.00001sol or 10k lamports,
+    /// and are lamports without one.
+    #[arg(long, env, default_value_t = 10_000, value_parser = parse_lamports)]
+    tip_lamports: u64,
+
+    /// Pay a percentage of every tip to another account, as PUBKEY:PERCENT, e.g. a team
+    /// treasury at Treasury1111...:10. Comma-separated or repeated for several accounts. Tips too
+    /// small to leave the Jito tip account its 1000 lamport floor after the shares go to it whole.
+    #[arg(long, env, value_delimiter = ',')]
+    tip_share: Vec<TipShare>,
+
+    /// Expected profit per backrun in lamports. The example strategy has no pricing model, so
+    /// the estimate is supplied up front. Bundles are submitted without an estimate if not set.
+    #[arg(long, env, value_parser = parse_lamports)]
+    expected_profit_lamports: Option<u64>,
+
+    /// Skip bundles whose expected profit minus tip and fees is below this many lamports.
+    /// Disabled if not set.
+    #[arg(long, env, allow_hyphen_values = true)]
+    min_profit_margin_lamports: Option<i64>,
+
+    /// Skip bundles touching these mints, directly or through a token account.
+    #[arg(long, env, value_delimiter = ',')]
+    deny_mint: Vec<Pubkey>,
+
+    /// Skip bundles invoking these programs.
+    #[arg(long, env, value_delimiter = ',')]
+    deny_program: Vec<Pubkey>,
+
+    /// Skip bundles touching any mint not listed here. Any mint is allowed if not set.
+    #[arg(long, env, value_delimiter = ',')]
+    allow_mint: Vec<Pubkey>,
+
+    /// Skip bundles invoking any program not listed here, besides the system, compute budget,
+    /// token, associated token account and memo programs. Any program is allowed if not set.
+    #[arg(long, env, value_delimiter = ',')]
+    allow_program: Vec<Pubkey>,
+
+    /// Mints exempt from --deny-freeze-authority and --deny-mutable-metadata, such as USDC.
+    #[arg(long, env, value_delimiter = ',')]
+    trust_mint: Vec<Pubkey>,
+
+    /// Skip bundles touching mints that have a freeze authority.
+    #[arg(long, env)]
+    deny_freeze_authority: bool,
+
+    /// Skip bundles touching mints whose Metaplex metadata is still mutable.
+    #[arg(long, env)]
+    deny_mutable_metadata: bool,
+
+    /// Skip bundles touching mints with a rug pull risk score, from 0 to 100, over this. Scores
+    /// mint and freeze authorities, holder concentration and unlocked pool liquidity. Not scored
+    /// if not set.
+    #[arg(long, env)]
+    max_token_risk: Option<u8>,
+
+    /// Accounts holding locked LP tokens, such as lockers' vaults or their owners, for the
+    /// liquidity part of --max-token-risk.
+    #[arg(long, env, value_delimiter = ',')]
+    lp_locker: Vec<Pubkey>,
+
+    /// How long a mint's risk score is reused before it's scored again.
+    #[arg(long, env, default_value_t = 300)]
+    token_risk_ttl_secs: u64,
+
+    /// Simulate every transaction of a bundle through --rpc-url before sending it, and skip
+    /// bundles that fail or can't be simulated.
+    #[arg(long, env)]
+    require_simulation: bool,
+
+    /// Simulation errors that don't fail --require-simulation: error names such as AccountInUse
+    /// or ComputationalBudgetExceeded, or custom:<code> for a program's custom error.
+    #[arg(long, env, value_delimiter = ',')]
+    allowed_simulation_errors: Vec<AllowedSimulationError>,
+
+    /// Maximum number of bundles that may write-lock the same account within one leader window,
+    /// so the bot doesn't compete with itself in the state auction. Unlimited if not set.
+    #[arg(long, env)]
+    max_bundles_per_write_lock: Option<usize>,
+
+    /// Maximum number of bundles sent within one leader window. Unlimited if not set.
+    #[arg(long, env)]
+    max_bundles_per_leader_window: Option<usize>,
+
+    /// Priority class of the bundles this pipeline builds: bulk, normal or critical. Queued
+    /// bundles are sent in class order, so critical bundles go ahead of normal ones. WASM
+    /// strategies may set the class per bundle.
+    #[arg(long, env, default_value_t = PriorityClass::Normal)]
+    priority_class: PriorityClass,
+
+    /// Tips of critical bundles are multiplied by this.
+    #[arg(long, env, default_value_t = 2.0)]
+    critical_tip_multiplier: f64,
+
+    /// Tips of bulk bundles are multiplied by this.
+    #[arg(long, env, default_value_t = 0.5)]
+    bulk_tip_multiplier: f64,
+
+    /// Critical bundles may go over --max-bundles-per-leader-window by this many per leader
+    /// window.
+    #[arg(long, env, default_value_t = 0)]
+    critical_reserved_bundles: usize,
+
+    /// Hold back bulk bundles once this share of recent submissions is outbid or rate limited.
+    /// Congestion throttling is disabled if not set.
+    #[arg(long, env)]
+    congestion_throttle_rate: Option<f64>,
+
+    /// Hold back normal bundles too once this share of recent submissions is outbid or rate
+    /// limited.
+    #[arg(long, env, default_value_t = 0.8)]
+    congestion_pause_rate: f64,
+
+    /// Let held back bundles through again once the share drops to this.
+    #[arg(long, env, default_value_t = 0.3)]
+    congestion_resume_rate: f64,
+
+    /// Window the share is measured over.
+    #[arg(long, env, default_value_t = 30)]
+    congestion_window_secs: u64,
+
+    /// Submissions needed within the window before anything is held back.
+    #[arg(long, env, default_value_t = 20)]
+    congestion_min_samples: usize,
+
+    /// Drop mempool transactions that write-lock none of --backrun-accounts or
+    /// --interest-accounts before deserializing them, checking account keys against a bloom
+    /// filter. For subscriptions that deliver more than the strategy backruns, such as a program
+    /// subscription at full mempool volume.
+    #[arg(long, env)]
+    mempool_prefilter: bool,
+
+    /// Accounts the mempool prefilter lets through besides --backrun-accounts, such as the
+    /// accounts a WASM strategy trades on.
+    #[arg(long, env, value_delimiter = ',')]
+    interest_accounts: Vec<Pubkey>,
+
+    /// Share of unrelated transactions the prefilter's bloom filter lets through to the exact
+    /// check. Lower uses more memory.
+    #[arg(long, env, default_value_t = 0.01)]
+    prefilter_false_positive_rate: f64,
+
+    /// Subscribe to the payer's transactions too and measure how long submitted transactions
+    /// take to show up on the mempool stream, per region.
+    #[arg(long, env)]
+    echo_latency: bool,
+
+    /// Count a submitted transaction as missed if it hasn't shown up on the mempool stream
+    /// within this long.
+    #[arg(long, env, default_value_t = 30)]
+    echo_max_age_secs: u64,
+
+    /// Threads driving async tasks and network I/O. Defaults to one per core.
+    #[arg(long, env)]
+    worker_threads: Option<usize>,
+
+    /// Upper bound on the blocking thread pool, which mempool packets are decoded on. Defaults
+    /// to tokio's 512.
+    #[arg(long, env)]
+    max_blocking_threads: Option<usize>,
+
+    /// Mempool notifications decoded at once on the blocking pool, per pipeline.
+    #[arg(long, env, default_value_t = 2)]
+    decode_threads: usize,
+
+    /// Evaluate mempool transactions only while a connected leader's slot is at most this many
+    /// slots ahead, dropping them undecoded otherwise. 0 evaluates them in connected leaders'
+    /// own slots only.
+    #[arg(long, env, default_value_t = 0)]
+    leader_proximity_slots: u64,
+
+    /// Pin the thread running the searcher loops to this CPU core. Linux only.
+    #[arg(long, env)]
+    pin_searcher_loop_core: Option<usize>,
+
+    /// Append every submitted transaction to this JSONL audit log.
+    #[arg(long, env)]
+    audit_log: Option<PathBuf>,
+
+    /// Rotate the audit log once it reaches this many bytes.
+    #[arg(long, env, default_value_t = 100 * 1024 * 1024)]
+    audit_log_max_bytes: u64,
+
+    /// Number of rotated audit log files to keep.
+    #[arg(long, env, default_value_t = 10)]
+    audit_log_max_files: usize,
+
+    /// Sign every audit log entry with the first --auth-keypair and hash-link it to the entry
+    /// before, making the log tamper-evident. Check it with `jito-searcher-cli verify-audit-log`.
+    #[arg(long, env, requires = "audit_log")]
+    sign_audit_log: bool,
+
+    /// Debug mode: append the full request and response of every block engine call to this
+    /// JSONL file, with auth tokens and signed challenges redacted.
+    #[arg(long, env)]
+    trace_grpc: Option<PathBuf>,
+
+    /// Test only: JSON scenario of faults to inject into block engine calls and streams, to
+    /// exercise reconnection and retry logic.
+    #[cfg(feature = "fault-injection")]
+    #[arg(long, env)]
+    fault_scenario: Option<PathBuf>,
+
+    /// JSON file with a section of settings per strategy, such as
+    /// {"memo-backrun": {"message": "usdc backrun"}}, applied over the strategy's flags.
+    /// Sections for unknown strategies and unknown settings are rejected.
+    #[arg(long, env)]
+    strategy_config: Option<PathBuf>,
+
+    /// JSON file of named pipelines to run concurrently instead of a single backrun. Each
+    /// pipeline sets its own accounts, memo, tip, block engine, regions and tip budget.
+    #[arg(long, env)]
+    pipelines: Option<PathBuf>,
+
+    /// JSON file of tunables adjustable while running: `paused` and `tip_multiplier`. Read again
+    /// on SIGHUP, keeping the current values if it's invalid.
+    #[arg(long, env)]
+    runtime_params: Option<PathBuf>,
+
+    /// Address to serve the admin endpoint on, such as 127.0.0.1:9400: GET /status, and POST
+    /// /pause, /resume and /drain.
+    #[arg(long, env, requires = "admin_token")]
+    admin_addr: Option<SocketAddr>,
+
+    /// Bearer token admin requests must carry. Set it through the ADMIN_TOKEN environment
+    /// variable, the command line is visible to other users.
+    #[arg(long, env, hide_env_values = true)]
+    admin_token: Option<String>,
+
+    /// Maximum lamports tipped per --tip-budget-window-secs. Unlimited if not set.
+    #[arg(long, env, value_parser = parse_lamports)]
+    tip_budget_lamports: Option<u64>,
+
+    /// Window the tip budget applies to.
+    #[arg(long, env, default_value_t = 3_600)]
+    tip_budget_window_secs: u64,
+
+    /// Lease file shared with a standby instance. Only the instance holding the lease submits
+    /// bundles, the other keeps its streams connected and takes over when the lease expires.
+    #[arg(long, env, requires = "ha_instance_id")]
+    ha_lease_file: Option<PathBuf>,
+
+    /// Name this instance holds the lease under, unique within the HA pair.
+    #[arg(long, env)]
+    ha_instance_id: Option<String>,
+
+    /// How long the lease lasts without renewal. The standby checks the lease every quarter of
+    /// this, so it takes over up to 1.25 times this long after the active instance dies, or
+    /// within a quarter of it after the active instance shuts down and releases the lease. The
+    /// instances' clocks have to agree to within a quarter of this.
+    #[arg(long, env, default_value_t = 800)]
+    ha_lease_ttl_ms: u64,
+
+    /// Redis server, as redis://[:password@]host:port[/db], shared by a fleet of instances.
+    /// Opportunities another instance already backran are skipped, tip budgets are enforced
+    /// across the fleet and submitted bundles are recorded. Local only if not set.
+    #[arg(long, env)]
+    redis_url: Option<String>,
+
+    /// Prefix of every key written to Redis, to run several fleets against one server.
+    #[arg(long, env, default_value = "backrun")]
+    redis_key_prefix: String,
+
+    /// How long an opportunity claimed by one instance is skipped by the others.
+    #[arg(long, env, default_value_t = 60)]
+    dedupe_ttl_secs: u64,
+
+    /// Submitted bundles kept in the shared Redis list.
+    #[arg(long, env, default_value_t = 10_000)]
+    redis_max_bundles: usize,
+
+    /// File the searcher state is saved to on SIGTERM or SIGINT and restored from on start:
+    /// the leader schedule, latest blockhash and queued opportunities.
+    #[arg(long, env)]
+    state_snapshot: Option<PathBuf>,
+
+    /// Snapshots older than this are ignored on start, their blockhash and opportunities have
+    /// expired.
+    #[arg(long, env, default_value_t = 60)]
+    state_snapshot_max_age_secs: u64,
+
+    /// Also save the state snapshot this often, not just on shutdown, so a crash loses at most
+    /// this many seconds of queued opportunities.
+    #[arg(long, env)]
+    state_snapshot_interval_secs: Option<u64>,
+
+    /// NTP server, as host:port, the local clock offset is measured against. Submission
+    /// timestamps are corrected by the offset. Not measured if not set.
+    #[arg(long, env)]
+    ntp_server: Option<String>,
+
+    /// Seconds between clock offset measurements.
+    #[arg(long, env, default_value_t = 300)]
+    clock_check_interval_secs: u64,
+
+    /// Slots after being seen that an opportunity is dropped instead of sent.
+    #[arg(long, env, default_value_t = 2)]
+    opportunity_ttl_slots: u64,
+
+    /// Milliseconds after being built that a bundle is dropped instead of sent, even if it hasn't
+    /// expired yet. Not limited if not set.
+    #[arg(long, env)]
+    submit_deadline_ms: Option<u64>,
+
+    /// Maximum opportunities waiting to be sent, the lowest ranked are dropped beyond this.
+    #[arg(long, env, default_value_t = 1_000)]
+    max_queued_opportunities: usize,
+
+    /// Comma-separated block engine URLs, one per region, to race bundles to. Each bundle is sent
+    /// to the --race-fanout block engines whose Jito leaders come up first instead of
+    /// --block-engine-url. Disabled if not set.
+    #[arg(long, env, value_delimiter = ',')]
+    race_block_engine_urls: Vec<String>,
+
+    /// Number of block engines each raced bundle is sent to.
+    #[arg(long, env, default_value_t = 2)]
+    race_fanout: usize,
+
+    /// Slots sooner a block engine's leader must be than the raced ones' to switch to it.
+    #[arg(long, env, default_value_t = 4)]
+    race_switch_min_lead_slots: u64,
+
+    /// Schedule refreshes in a row a block engine must lead for before switching to it.
+    #[arg(long, env, default_value_t = 2)]
+    race_switch_confirmations: u32,
+
+    /// Extra KEY=VALUE metadata sent with every block engine call. Repeat for several headers.
+    #[arg(long, env)]
+    client_header: Vec<String>,
+
+    /// Identifies this strategy's traffic to the block engine, sent as the x-strategy-id header.
+    #[arg(long, env)]
+    strategy_id: Option<String>,
+
+    /// How backruns get included: block-engine, or tpu-fallback to also send them straight to
+    /// the upcoming leaders over QUIC when the block engine is unreachable.
+    #[arg(long, env, default_value = "block-engine")]
+    inclusion_path: InclusionPath,
+
+    /// Upcoming leader slots each TPU fallback transaction is sent to.
+    #[arg(long, env, default_value_t = 4)]
+    tpu_fallback_fanout_slots: u64,
+
+    /// Halt submissions after this many bundles in a row are rejected. Disabled if not set.
+    #[arg(long, env)]
+    max_consecutive_rejections: Option<usize>,
+
+    /// Halt submissions after this many bundles in a row fail simulation. Disabled if not set.
+    #[arg(long, env)]
+    max_consecutive_simulation_failures: Option<usize>,
+
+    /// Halt submissions if the payer balance drops by more than this many lamports within
+    /// --balance-drop-window-secs. Disabled if not set.
+    #[arg(long, env, value_parser = parse_lamports)]
+    max_balance_drop_lamports: Option<u64>,
+
+    /// Window the payer balance drop is measured over.
+    #[arg(long, env, default_value_t = 60)]
+    balance_drop_window_secs: u64,
+
+    /// File a tripped dead man switch is recorded in until acknowledged, so a restart stays
+    /// halted. Not kept across restarts if not set.
+    #[arg(long, env)]
+    halt_file: Option<PathBuf>,
 }
 
 #[derive(Debug, Error)]
@@ -119,14 +604,49 @@ enum BackrunError {
     PubSubError(#[from] PubsubClientError),
     #[error("BlockEngineConnectionError {0}")]
     BlockEngineConnectionError(#[from] BlockEngineConnectionError),
+    #[error("PacketConversionError {0}")]
+    PacketConversionError(#[from] PacketConversionError),
+    #[error("AuditLogError {0}")]
+    AuditLogError(std::io::Error),
+    #[error("WasmStrategyError {0}")]
+    WasmStrategyError(#[from] WasmStrategyError),
+    #[error("ClusterCheckError {0}")]
+    ClusterCheckError(#[from] ClusterCheckError),
+    #[error("PoolRegistryError {0}")]
+    PoolRegistryError(#[from] PoolRegistryError),
     #[error("Shutdown")]
     Shutdown,
 }
 
-#[derive(Clone)]
+#[derive(Clone, Serialize, Deserialize)]
 struct BundledTransactions {
     mempool_txs: Vec<VersionedTransaction>,
     backrun_txs: Vec<VersionedTransaction>,
+    tip_lamports: u64,
+    profit_estimate: Option<ProfitEstimate>,
+    /// Set by the strategy that asked for the bundle, to tell it when the bundle is dropped.
+    #[serde(default)]
+    id: Option<String>,
+    #[serde(default)]
+    expiry_slot: Option<Slot>,
+    /// Drop the bundle instead of sending it after this.
+    #[serde(default)]
+    deadline: Option<SystemTime>,
+    #[serde(default)]
+    priority: PriorityClass,
+}
+
+impl BundledTransactions {
+    /// Names the bundle in logs and dedupe keys: the mempool transaction it backruns, or its own
+    /// first transaction if a strategy sent it without one.
+    fn trigger_signature(&self) -> Signature {
+        self.mempool_txs
+            .iter()
+            .chain(self.backrun_txs.iter())
+            .next()
+            .map(|tx| tx.signatures[0])
+            .unwrap_or_default()
+    }
 }
 
 #[derive(Default)]
@@ -139,59 +659,274 @@ struct BlockStats {
     send_rt_per_packet: Histogram,
 }
 
+/// What the searcher loop saves on shutdown so a restart doesn't begin cold.
+#[derive(Serialize, Deserialize)]
+struct WarmState {
+    highest_slot: Slot,
+    blockhash: Hash,
+    leader_schedule: HashMap<Pubkey, HashSet<Slot>>,
+    opportunities: Vec<Opportunity<BundledTransactions>>,
+}
+
 type Result<T> = result::Result<T, BackrunError>;
 
+/// How long the shadow comparison waits for live bundle results before settling a trigger.
+const SHADOW_SETTLE: Duration = Duration::from_secs(30);
+
+/// What the bundles built for one event are signed and tipped with.
+struct BundleSigner<'a> {
+    keypair: &'a Keypair,
+    blockhash: Hash,
+    tip_accounts: &'a [Pubkey],
+    slot_assertion: Option<SlotAssertion>,
+    default_priority: PriorityClass,
+    tip_multipliers: TipMultipliers,
+    tip_split: &'a TipSplit,
+}
+
 fn build_bundles(
-    pending_tx_notification: PendingTxNotification,
-    keypair: &Keypair,
-    blockhash: &Hash,
-    tip_accounts: &[Pubkey],
+    mempool_txs: Vec<VersionedTransaction>,
+    signer: &BundleSigner,
     rng: &mut ThreadRng,
     message: &str,
+    tip_lamports: u64,
+    expected_profit_lamports: Option<u64>,
 ) -> Vec<BundledTransactions> {
-    pending_tx_notification
-        .transactions
+    let BundleSigner {
+        keypair,
+        blockhash,
+        tip_accounts,
+        slot_assertion,
+        default_priority: priority,
+        tip_multipliers,
+        tip_split,
+    } = *signer;
+    let tip_lamports = tip_multipliers.apply(priority, tip_lamports);
+    mempool_txs
         .into_iter()
-        .filter_map(|packet| {
-            let mempool_tx = versioned_tx_from_packet(&packet)?;
+        .map(|mempool_tx| {
             let tip_account = tip_accounts[rng.gen_range(0..tip_accounts.len())];
 
+            let mut instructions = vec![build_memo(
+                format!("{}: {:?}", message, mempool_tx.signatures[0].to_string()).as_bytes(),
+                &[],
+            )];
+            instructions.extend(tip_split.transfers(&keypair.pubkey(), &tip_account, tip_lamports));
+            if let Some(slot_assertion) = &slot_assertion {
+                instructions.insert(0, slot_assertion.instruction());
+            }
             let backrun_tx = VersionedTransaction::from(Transaction::new_signed_with_payer(
-                &[
-                    build_memo(
-                        format!("{}: {:?}", message, mempool_tx.signatures[0].to_string())
-                            .as_bytes(),
-                        &[],
-                    ),
-                    transfer(&keypair.pubkey(), &tip_account, 10_000),
-                ],
+                &instructions,
                 Some(&keypair.pubkey()),
                 &[keypair],
-                *blockhash,
+                blockhash,
             ));
-            Some(BundledTransactions {
+            // the mempool transaction pays its own fees
+            let profit_estimate =
+                expected_profit_lamports.map(|expected_profit_lamports| ProfitEstimate {
+                    expected_profit_lamports,
+                    tip_lamports,
+                    fee_lamports: estimate_fee_lamports(std::slice::from_ref(&backrun_tx)),
+                });
+            BundledTransactions {
                 mempool_txs: vec![mempool_tx],
                 backrun_txs: vec![backrun_tx],
+                tip_lamports,
+                profit_estimate,
+                id: None,
+                expiry_slot: None,
+                deadline: None,
+                priority,
+            }
+        })
+        .collect()
+}
+
+/// Hands `event` to the shadow strategy and records the bundles it would have sent.
+fn run_shadow(
+    shadow: &mut Option<(WasmStrategy, ShadowComparison)>,
+    event: &StrategyEvent,
+    trigger: Option<&VersionedTransaction>,
+    signer: &BundleSigner,
+    rng: &mut ThreadRng,
+) {
+    let Some((shadow_strategy, comparison)) = shadow else {
+        return;
+    };
+    let bundles = build_strategy_bundles(shadow_strategy, event, trigger, signer, rng);
+    let now = Instant::now();
+    for bundle in bundles {
+        comparison.record_shadow(
+            bundle.trigger_signature(),
+            bundle.tip_lamports,
+            bundle.profit_estimate.as_ref(),
+            now,
+        );
+    }
+}
+
+/// Hands `event` to the WASM strategy and signs the bundles it asks for. `trigger` is the
+/// mempool transaction the event is about. Bundles the strategy got wrong are logged and skipped.
+fn build_strategy_bundles(
+    wasm_strategy: &mut WasmStrategy,
+    event: &StrategyEvent,
+    trigger: Option<&VersionedTransaction>,
+    signer: &BundleSigner,
+    rng: &mut ThreadRng,
+) -> Vec<BundledTransactions> {
+    let BundleSigner {
+        keypair,
+        blockhash,
+        tip_accounts,
+        slot_assertion,
+        default_priority,
+        tip_multipliers,
+        tip_split,
+    } = *signer;
+    let specs = match wasm_strategy.on_event(event) {
+        Ok(specs) => specs,
+        Err(e) => {
+            warn!("wasm strategy failed: {e}");
+            return Vec::new();
+        }
+    };
+    specs
+        .into_iter()
+        .filter_map(|spec| {
+            let mut instructions = match spec
+                .instructions
+                .iter()
+                .map(InstructionSpec::to_instruction)
+                .collect::<result::Result<Vec<_>, _>>()
+            {
+                Ok(instructions) => instructions,
+                Err(e) => {
+                    warn!("skipping wasm strategy bundle: {e}");
+                    return None;
+                }
+            };
+            if let Some(slot_assertion) = &slot_assertion {
+                instructions.insert(0, slot_assertion.instruction());
+            }
+            let priority = spec.priority.unwrap_or(default_priority);
+            let tip_lamports = tip_multipliers.apply(priority, spec.tip_lamports);
+            let tip_account = tip_accounts[rng.gen_range(0..tip_accounts.len())];
+            instructions.extend(tip_split.transfers(&keypair.pubkey(), &tip_account, tip_lamports));
+            let mut backrun_tx =
+                Transaction::new_with_payer(&instructions, Some(&keypair.pubkey()));
+            if let Err(e) = backrun_tx.try_sign(&[keypair], blockhash) {
+                warn!("skipping wasm strategy bundle: {e}");
+                return None;
+            }
+            let backrun_tx = VersionedTransaction::from(backrun_tx);
+            let profit_estimate = spec
+                .expected_profit_lamports
+                .map(|expected_profit_lamports| ProfitEstimate {
+                    expected_profit_lamports,
+                    tip_lamports,
+                    fee_lamports: estimate_fee_lamports(std::slice::from_ref(&backrun_tx)),
+                });
+            Some(BundledTransactions {
+                mempool_txs: trigger
+                    .filter(|_| spec.include_trigger)
+                    .cloned()
+                    .into_iter()
+                    .collect(),
+                backrun_txs: vec![backrun_tx],
+                tip_lamports,
+                profit_estimate,
+                id: spec.id,
+                expiry_slot: spec.expiry_slot,
+                deadline: spec
+                    .deadline_unix_ms
+                    .map(|ms| SystemTime::UNIX_EPOCH + Duration::from_millis(ms)),
+                priority,
             })
         })
         .collect()
 }
 
+/// Queues the bundles to expire `ttl_slots` after `current_slot` and be dropped `submit_deadline`
+/// after `now`, or earlier if the strategy asked for it or the blockhash they're signed with
+/// expires first. Higher priority classes are popped first.
+fn queue_bundles(
+    opportunity_queue: &mut OpportunityQueue<BundledTransactions>,
+    bundles: impl IntoIterator<Item = BundledTransactions>,
+    current_slot: Slot,
+    now: SystemTime,
+    ttl_slots: u64,
+    blockhash_last_valid_slot: Option<Slot>,
+    submit_deadline: Option<Duration>,
+) {
+    let expiry_slot = current_slot + ttl_slots;
+    let expiry_slot = blockhash_last_valid_slot.map_or(expiry_slot, |slot| slot.min(expiry_slot));
+    let deadline = submit_deadline.map(|submit_deadline| now + submit_deadline);
+    for mut bundle in bundles {
+        let expiry_slot = bundle
+            .expiry_slot
+            .map_or(expiry_slot, |slot| slot.min(expiry_slot));
+        bundle.deadline = match (bundle.deadline, deadline) {
+            (Some(requested), Some(deadline)) => Some(requested.min(deadline)),
+            (requested, deadline) => requested.or(deadline),
+        };
+        opportunity_queue.push(
+            Opportunity {
+                expected_profit_lamports: bundle
+                    .profit_estimate
+                    .map(|e| e.net_lamports())
+                    .unwrap_or_default(),
+                deadline: bundle.deadline,
+                priority: bundle.priority.rank(),
+                payload: bundle,
+                expiry_slot,
+            },
+            current_slot,
+            now,
+        );
+    }
+}
+
+/// Moves the bundles whose deadline passed by `now` to `dropped`.
+fn drop_late_bundles(
+    pipeline: &str,
+    bundles: &mut Vec<BundledTransactions>,
+    now: SystemTime,
+    dropped: &mut Vec<(BundledTransactions, DropReason)>,
+) {
+    let (late, on_time): (Vec<_>, Vec<_>) = std::mem::take(bundles)
+        .into_iter()
+        .partition(|b| b.deadline.is_some_and(|deadline| deadline < now));
+    *bundles = on_time;
+    if !late.is_empty() {
+        datapoint_info!("backrun_deadline_missed", "pipeline" => pipeline, ("bundles", late.len(), i64));
+        dropped.extend(late.into_iter().map(|b| (b, DropReason::DeadlineMissed)));
+    }
+}
+
 async fn send_bundles(
     searcher_client: &mut SearcherServiceClient<InterceptedService<Channel, ClientInterceptor>>,
+    rpc_latency: &RpcLatencyTracker,
     bundles: &[BundledTransactions],
 ) -> Result<Vec<result::Result<Response<SendBundleResponse>, Status>>> {
     let mut futs = Vec::with_capacity(bundles.len());
     for b in bundles {
         let mut searcher_client = searcher_client.clone();
+        let rpc_latency = rpc_latency.clone();
         let txs = b
             .mempool_txs
             .clone()
             .into_iter()
-            .chain(b.backrun_txs.clone().into_iter())
-            .collect::<Vec<VersionedTransaction>>();
-        let task =
-            tokio::spawn(async move { send_bundle_no_wait(&txs, &mut searcher_client).await });
+            .chain(b.backrun_txs.clone())
+            .map(|tx| bincode::serialize(&tx).expect("serializes"))
+            .collect::<Vec<Vec<u8>>>();
+        let task = tokio::spawn(async move {
+            rpc_latency
+                .time(
+                    "send_bundle",
+                    send_bundle_no_wait(&txs, &mut searcher_client),
+                )
+                .await
+        });
         futs.push(task);
     }
 
@@ -200,36 +935,54 @@ async fn send_bundles(
     Ok(send_bundle_responses)
 }
 
-fn generate_tip_accounts(tip_program_pubkey: &Pubkey) -> Vec<Pubkey> {
-    let tip_pda_0 = Pubkey::find_program_address(&[b"TIP_ACCOUNT_0"], tip_program_pubkey).0;
-    let tip_pda_1 = Pubkey::find_program_address(&[b"TIP_ACCOUNT_1"], tip_program_pubkey).0;
-    let tip_pda_2 = Pubkey::find_program_address(&[b"TIP_ACCOUNT_2"], tip_program_pubkey).0;
-    let tip_pda_3 = Pubkey::find_program_address(&[b"TIP_ACCOUNT_3"], tip_program_pubkey).0;
-    let tip_pda_4 = Pubkey::find_program_address(&[b"TIP_ACCOUNT_4"], tip_program_pubkey).0;
-    let tip_pda_5 = Pubkey::find_program_address(&[b"TIP_ACCOUNT_5"], tip_program_pubkey).0;
-    let tip_pda_6 = Pubkey::find_program_address(&[b"TIP_ACCOUNT_6"], tip_program_pubkey).0;
-    let tip_pda_7 = Pubkey::find_program_address(&[b"TIP_ACCOUNT_7"], tip_program_pubkey).0;
-
-    vec![
-        tip_pda_0, tip_pda_1, tip_pda_2, tip_pda_3, tip_pda_4, tip_pda_5, tip_pda_6, tip_pda_7,
-    ]
+/// Sends each bundle to the block engines with the soonest leaders. The copies share signatures,
+/// so at most one of them executes.
+async fn race_bundles(
+    race: &RegionRace,
+    rpc_latency: &RpcLatencyTracker,
+    bundles: &[BundledTransactions],
+) -> Vec<result::Result<Response<SendBundleResponse>, Status>> {
+    let futs = bundles.iter().map(|b| async move {
+        let txs: Vec<Vec<u8>> = b
+            .mempool_txs
+            .iter()
+            .chain(b.backrun_txs.iter())
+            .map(|tx| bincode::serialize(tx).expect("serializes"))
+            .collect();
+        rpc_latency
+            .time("race_bundle", async {
+                first_accepted(race.send(&txs).await)
+            })
+            .await
+    });
+    futures_util::future::join_all(futs).await
 }
 
 async fn maintenance_tick(
     searcher_client: &mut SearcherServiceClient<InterceptedService<Channel, ClientInterceptor>>,
+    rpc_latency: &RpcLatencyTracker,
     rpc_client: &RpcClient,
     leader_schedule: &mut HashMap<Pubkey, HashSet<Slot>>,
     blockhash: &mut Hash,
+    blockhash_last_valid_slot: &mut Option<Slot>,
     regions: Vec<String>,
 ) -> Result<()> {
-    *blockhash = rpc_client
-        .get_latest_blockhash_with_commitment(CommitmentConfig {
-            commitment: CommitmentLevel::Confirmed,
-        })
-        .await?
-        .0;
-    let new_leader_schedule = searcher_client
-        .get_connected_leaders(ConnectedLeadersRequest {})
+    let expiry = BlockhashExpiry::fetch(rpc_client).await?;
+    let epoch_info = rpc_client
+        .get_epoch_info_with_commitment(CommitmentConfig::processed())
+        .await?;
+    *blockhash = expiry.blockhash;
+    *blockhash_last_valid_slot =
+        Some(expiry.last_valid_slot(epoch_info.absolute_slot, epoch_info.block_height));
+    let new_leader_schedule = rpc_latency
+        .time(
+            "get_connected_leaders",
+            traced(
+                "GetConnectedLeaders",
+                ConnectedLeadersRequest {},
+                |request| searcher_client.get_connected_leaders(request),
+            ),
+        )
         .await?
         .into_inner()
         .connected_validators
@@ -246,8 +999,15 @@ async fn maintenance_tick(
         *leader_schedule = new_leader_schedule;
     }
 
-    let next_scheduled_leader = searcher_client
-        .get_next_scheduled_leader(NextScheduledLeaderRequest { regions })
+    let next_scheduled_leader = rpc_latency
+        .time(
+            "get_next_scheduled_leader",
+            traced(
+                "GetNextScheduledLeader",
+                NextScheduledLeaderRequest { regions },
+                |request| searcher_client.get_next_scheduled_leader(request),
+            ),
+        )
         .await?
         .into_inner();
     info!(
@@ -494,147 +1254,1620 @@ fn print_block_stats(
     block_signatures.retain(|slot, _| *slot > block.context.slot - KEEP_SIGS_SLOTS);
 }
 
-#[allow(clippy::too_many_arguments)]
+async fn is_blockhash_valid(
+    rpc_client: &RpcClient,
+    hash: &Hash,
+    checked: &mut HashMap<Hash, bool>,
+) -> result::Result<bool, ClientError> {
+    if let Some(valid) = checked.get(hash) {
+        return Ok(*valid);
+    }
+    let valid = rpc_client
+        .is_blockhash_valid(hash, CommitmentConfig::processed())
+        .await?;
+    checked.insert(*hash, valid);
+    Ok(valid)
+}
+
+/// Checks opportunities restored from a snapshot before they're queued again, returning the
+/// current slot and the ones that can still land: not past their expiry slot or deadline, and
+/// with every transaction's blockhash still valid.
+async fn revalidate_opportunities(
+    rpc_client: &RpcClient,
+    opportunities: Vec<Opportunity<BundledTransactions>>,
+    now: SystemTime,
+    valid_blockhashes: &mut HashMap<Hash, bool>,
+) -> result::Result<(Slot, Vec<Opportunity<BundledTransactions>>), ClientError> {
+    let current_slot = rpc_client
+        .get_slot_with_commitment(CommitmentConfig::processed())
+        .await?;
+    let mut valid = vec![];
+    'opportunities: for opportunity in opportunities {
+        let bundle = &opportunity.payload;
+        if let Some(reason) = drop_reason(
+            opportunity.expiry_slot,
+            opportunity.deadline,
+            current_slot,
+            now,
+        ) {
+            debug!(
+                "dropping restored backrun of {}: {reason}",
+                bundle.trigger_signature()
+            );
+            continue;
+        }
+        for tx in bundle.mempool_txs.iter().chain(bundle.backrun_txs.iter()) {
+            if !is_blockhash_valid(rpc_client, tx.message.recent_blockhash(), valid_blockhashes)
+                .await?
+            {
+                debug!(
+                    "dropping restored backrun of {}: blockhash expired",
+                    bundle.trigger_signature()
+                );
+                continue 'opportunities;
+            }
+        }
+        valid.push(opportunity);
+    }
+    Ok((current_slot, valid))
+}
+
+/// The state saved at `path`, unless there's none or it's older than `max_age`.
+fn load_warm_state(path: &Path, max_age: Duration) -> Option<WarmState> {
+    match load_snapshot::<WarmState>(path) {
+        Ok(Some((state, age))) if age <= max_age => {
+            info!(
+                "warm start from {path:?} saved {age:?} ago, {} queued opportunities",
+                state.opportunities.len()
+            );
+            Some(state)
+        }
+        Ok(Some((_, age))) => {
+            info!("cold start, {path:?} was saved {age:?} ago");
+            None
+        }
+        Ok(None) => None,
+        Err(e) => {
+            warn!("cold start, failed to load {path:?}: {e}");
+            None
+        }
+    }
+}
+
 async fn run_searcher_loop(
-    block_engine_url: String,
-    auth_keypair: Arc<Keypair>,
-    keypair: &Keypair,
-    rpc_url: String,
-    regions: Vec<String>,
-    message: String,
-    tip_program_pubkey: Pubkey,
-    mut slot_receiver: Receiver<Slot>,
-    mut block_receiver: Receiver<rpc_response::Response<RpcBlockUpdate>>,
-    mut bundle_results_receiver: Receiver<BundleResult>,
-    mut pending_tx_receiver: Receiver<PendingTxNotification>,
+    pipeline: String,
+    context: PipelineContext,
+    config: SearcherConfig,
+    streams: SearcherStreams,
 ) -> Result<()> {
+    let PipelineContext {
+        payer_keypair,
+        auth_keypairs,
+        headers,
+        clock_offset,
+        runtime_params,
+        admin,
+        lease,
+        shared_state,
+        mut shutdown,
+    } = context;
+    let keypair: &Keypair = &payer_keypair;
+    let SearcherConfig {
+        connection:
+            SearcherConnection {
+                block_engine_url,
+                rpc_url,
+                regions,
+                mut race,
+                tpu_fallback,
+            },
+        strategy:
+            SearcherStrategy {
+                message,
+                mut wasm_strategy,
+                mut shadow,
+                tip_program_pubkey,
+                slot_assertion,
+                tip_lamports,
+                priority_class,
+                tip_multipliers,
+                tip_split,
+                expected_profit_lamports,
+            },
+        limits:
+            SearcherLimits {
+                mut ev_gate,
+                mut token_guard,
+                mut simulation_policy,
+                write_lock_limits,
+                mut congestion,
+                opportunity_ttl_slots,
+                submit_deadline,
+                max_queued_opportunities,
+                mut tip_budget,
+                dedupe_ttl,
+                max_shared_bundles,
+                leader_proximity_slots,
+            },
+        sinks:
+            SearcherSinks {
+                mut echo_latency,
+                mut audit_log,
+                state_snapshot,
+                state_snapshot_max_age,
+                state_snapshot_interval,
+            },
+        safety:
+            SearcherSafety {
+                dead_man_switch,
+                observe_only,
+                strict_packet_conversion,
+            },
+    } = config;
+    let SearcherStreams {
+        mut slot_receiver,
+        mut block_receiver,
+        mut bundle_results_receiver,
+        mut decoded_tx_receiver,
+        leader_nearby,
+    } = streams;
+
     let mut leader_schedule: HashMap<Pubkey, HashSet<Slot>> = HashMap::new();
     let mut block_stats: HashMap<Slot, BlockStats> = HashMap::new();
     let mut block_signatures: HashMap<Slot, HashSet<Signature>> = HashMap::new();
+    let mut leader_skips = LeaderSkipTracker::default();
+    let mut packet_stats = PacketConversionStats::default();
+    let mut observation = ObservationStats::default();
+    let mut opportunity_queue = OpportunityQueue::new(max_queued_opportunities);
+
+    let mut highest_slot = 0;
+    let mut warm_blockhash = None;
+    let mut restored_opportunities = vec![];
+    if let Some(state) = state_snapshot
+        .as_deref()
+        .and_then(|path| load_warm_state(path, state_snapshot_max_age))
+    {
+        highest_slot = state.highest_slot;
+        warm_blockhash = Some(state.blockhash);
+        leader_schedule = state.leader_schedule;
+        restored_opportunities = state.opportunities;
+    }
 
-    let mut searcher_client = get_searcher_client(&block_engine_url, &auth_keypair).await?;
+    let mut searcher_client =
+        FailoverSearcherClient::connect(&block_engine_url, auth_keypairs, headers).await?;
+    let rpc_latency = RpcLatencyTracker::default();
 
     let mut rng = thread_rng();
 
-    let tip_accounts = generate_tip_accounts(&tip_program_pubkey);
+    let tip_accounts = tip_accounts(&tip_program_pubkey);
     info!("tip accounts: {:?}", tip_accounts);
 
-    let rpc_client = RpcClient::new(rpc_url);
-    let mut blockhash = rpc_client
-        .get_latest_blockhash_with_commitment(CommitmentConfig {
-            commitment: CommitmentLevel::Confirmed,
-        })
-        .await?
-        .0;
+    // every bundle write-locks the payer and a tip account, those can't conflict
+    let mut write_lock_limiter = write_lock_limits.map(|mut limits| {
+        limits.ignored_accounts.insert(keypair.pubkey());
+        limits.ignored_accounts.extend(tip_accounts.iter().copied());
+        WriteLockLimiter::new(limits)
+    });
 
-    let mut highest_slot = 0;
-    let mut is_leader_slot = false;
+    let rpc_client = RpcClient::new(rpc_url);
+    // a periodic snapshot may be from before a crash, nothing restored is used until checked
+    let mut valid_blockhashes = HashMap::new();
+    if let Some(hash) = warm_blockhash {
+        match is_blockhash_valid(&rpc_client, &hash, &mut valid_blockhashes).await {
+            Ok(true) => {}
+            Ok(false) => warm_blockhash = None,
+            Err(e) => {
+                warn!("pipeline {pipeline} failed to check the restored blockhash: {e}");
+                warm_blockhash = None;
+            }
+        }
+    }
+    if !restored_opportunities.is_empty() {
+        let restored = restored_opportunities.len();
+        match revalidate_opportunities(
+            &rpc_client,
+            restored_opportunities,
+            clock_offset.now(),
+            &mut valid_blockhashes,
+        )
+        .await
+        {
+            Ok((current_slot, opportunities)) => {
+                info!(
+                    "pipeline {pipeline} resuming {} of {restored} restored opportunities",
+                    opportunities.len()
+                );
+                highest_slot = highest_slot.max(current_slot);
+                for opportunity in opportunities {
+                    opportunity_queue.push(opportunity, highest_slot, clock_offset.now());
+                }
+            }
+            Err(e) => warn!(
+                "pipeline {pipeline} dropping {restored} restored opportunities, failed to revalidate them: {e}"
+            ),
+        }
+    }
+    let mut blockhash = match warm_blockhash {
+        Some(blockhash) => blockhash,
+        None => {
+            rpc_client
+                .get_latest_blockhash_with_commitment(CommitmentConfig {
+                    commitment: CommitmentLevel::Confirmed,
+                })
+                .await?
+                .0
+        }
+    };
+    // known from the first maintenance tick on, which runs right away
+    let mut blockhash_last_valid_slot = None;
 
+    let mut last_snapshot = Instant::now();
+    let mut bundles_sent = 0;
+    let (mut last_mempool_tx, mut last_bundle_sent, mut last_bundle_result) = (None, None, None);
     let mut tick = interval(Duration::from_secs(5));
     loop {
+        let tip_multipliers = runtime_params.get().tip_multipliers(tip_multipliers);
         tokio::select! {
             _ = tick.tick() => {
-                maintenance_tick(&mut searcher_client, &rpc_client, &mut leader_schedule, &mut blockhash, regions.clone()).await?;
+                let result = maintenance_tick(searcher_client.client_mut(), &rpc_latency, &rpc_client, &mut leader_schedule, &mut blockhash, &mut blockhash_last_valid_slot, regions.clone()).await;
+                let failed_over = match &result {
+                    Err(BackrunError::GrpcError(status)) => searcher_client.handle_error(status).await?,
+                    _ => false,
+                };
+                if !failed_over {
+                    result?;
+                }
+                leader_skips.schedule(&leader_schedule);
+                leader_skips.report("backrun_leader_skip_rate");
+                if let Some(race) = &mut race {
+                    race.refresh_schedule(&leader_skips.skip_rates()).await;
+                    race.report("backrun_region_race");
+                }
+                if dead_man_switch.watches_balance() {
+                    match rpc_client.get_balance(&keypair.pubkey()).await {
+                        Ok(lamports) => dead_man_switch.record_balance(lamports, Instant::now()),
+                        Err(e) => warn!("failed to fetch payer balance: {e}"),
+                    }
+                }
+                dead_man_switch.report("backrun_dead_man_switch");
+                runtime_params.report("backrun_runtime_params");
+                admin.publish(&pipeline, PipelineStatus {
+                    block_engine_url: block_engine_url.clone(),
+                    running: true,
+                    highest_slot,
+                    queued_opportunities: opportunity_queue.len(),
+                    bundles_sent,
+                    tip_budget_remaining_lamports: tip_budget.as_mut().map(|tip_budget| tip_budget.remaining(Instant::now())),
+                    halted: dead_man_switch.halted().map(|anomaly| anomaly.to_string()),
+                    lease_active: lease.as_ref().map(Lease::is_active),
+                    last_mempool_tx,
+                    last_bundle_sent,
+                    last_bundle_result,
+                });
+                if let Some(lease) = &lease {
+                    lease.report("backrun_ha_lease");
+                }
+                if let Some(tip_budget) = &mut tip_budget {
+                    tip_budget.report("backrun_tip_budget", &pipeline);
+                }
+                if let Some(shared_state) = &shared_state {
+                    shared_state.report("backrun_shared_state");
+                }
+                if let Some(wasm_strategy) = &mut wasm_strategy {
+                    match wasm_strategy.reload_if_changed() {
+                        Ok(true) => info!("pipeline {pipeline} reloaded wasm strategy"),
+                        Ok(false) => {}
+                        Err(e) => warn!("pipeline {pipeline} failed to reload wasm strategy, keeping the running one: {e}"),
+                    }
+                    wasm_strategy.report("backrun_wasm_strategy");
+                }
+                if let Some((shadow_strategy, comparison)) = &mut shadow {
+                    match shadow_strategy.reload_if_changed() {
+                        Ok(true) => info!("pipeline {pipeline} reloaded shadow strategy"),
+                        Ok(false) => {}
+                        Err(e) => warn!("pipeline {pipeline} failed to reload shadow strategy, keeping the running one: {e}"),
+                    }
+                    shadow_strategy.report("backrun_shadow_strategy");
+                    comparison.settle(Instant::now());
+                    let stats = comparison.stats();
+                    if stats.live.triggers + stats.shadow.triggers > 0 {
+                        info!(
+                            "pipeline {pipeline} shadow comparison: live bundled {} triggers for {} tip and {} expected net, shadow {} for {} tip and {} expected net, {} in common, live landed {}, shadow matched {} of them",
+                            stats.live.triggers, stats.live.tip_lamports, stats.live.expected_net_lamports,
+                            stats.shadow.triggers, stats.shadow.tip_lamports, stats.shadow.expected_net_lamports,
+                            stats.both, stats.live_landed, stats.shadow_matched_landed,
+                        );
+                    }
+                    comparison.report("backrun_shadow");
+                }
+                clock_offset.report("backrun_clock_offset");
+                packet_stats.report("backrun_packet_conversion");
+                if observe_only {
+                    observation.report("backrun_observed");
+                }
+                opportunity_queue.report("backrun_opportunity_queue");
+                if let (Some(path), Some(snapshot_interval)) = (&state_snapshot, state_snapshot_interval) {
+                    if last_snapshot.elapsed() >= snapshot_interval {
+                        last_snapshot = Instant::now();
+                        let state = WarmState {
+                            highest_slot,
+                            blockhash,
+                            leader_schedule: leader_schedule.clone(),
+                            opportunities: opportunity_queue.queued(),
+                        };
+                        if let Err(e) = save_snapshot(path, &state) {
+                            warn!("pipeline {pipeline} failed to save state to {path:?}: {e}");
+                        }
+                    }
+                }
+                rpc_latency.report("backrun_block_engine_latency");
+                if let Some(ev_gate) = &mut ev_gate {
+                    ev_gate.report("backrun_ev_gate");
+                }
+                if let Some(token_guard) = &mut token_guard {
+                    token_guard.report("backrun_token_guard");
+                }
+                if let Some(simulation_policy) = &mut simulation_policy {
+                    simulation_policy.report("backrun_simulation_policy");
+                }
+                if let Some(write_lock_limiter) = &mut write_lock_limiter {
+                    write_lock_limiter.report("backrun_write_lock_limiter");
+                }
+                if let Some(congestion) = &mut congestion {
+                    congestion.report("backrun_congestion");
+                }
+                if let Some(echo_latency) = &mut echo_latency {
+                    echo_latency.expire(Instant::now());
+                    echo_latency.report("backrun_echo_latency");
+                }
             }
             maybe_bundle_result = bundle_results_receiver.recv() => {
                 let bundle_result: BundleResult = maybe_bundle_result.ok_or(BackrunError::Shutdown)?;
+                last_bundle_result = Some(DateTime::<Utc>::from(clock_offset.now()));
                 info!("received bundle_result: [bundle_id={:?}, result={:?}]", bundle_result.bundle_id, bundle_result.result);
+                if let Some(outcome) = SubmissionOutcome::from_bundle_result(&bundle_result) {
+                    dead_man_switch.record_outcome(outcome);
+                }
+                if let (Some(congestion), Some(signal)) = (&mut congestion, AuctionSignal::from_bundle_result(&bundle_result)) {
+                    congestion.record(signal, Instant::now());
+                }
+                let slot_assertion = slot_assertion.map(|(program_id, window_slots)| SlotAssertion::window(program_id, highest_slot, window_slots));
+                let signer = BundleSigner { keypair, blockhash, tip_accounts: &tip_accounts, slot_assertion, default_priority: priority_class, tip_multipliers, tip_split: &tip_split };
+                if let Some(wasm_strategy) = &mut wasm_strategy {
+                    let bundles = build_strategy_bundles(wasm_strategy, &StrategyEvent::bundle_result(&bundle_result), None, &signer, &mut rng);
+                    queue_bundles(&mut opportunity_queue, bundles, highest_slot, clock_offset.now(), opportunity_ttl_slots, blockhash_last_valid_slot, submit_deadline);
+                }
+                if let Some((_, comparison)) = &mut shadow {
+                    comparison.record_result(&bundle_result);
+                }
+                run_shadow(&mut shadow, &StrategyEvent::bundle_result(&bundle_result), None, &signer, &mut rng);
             }
-            maybe_pending_tx_notification = pending_tx_receiver.recv() => {
-                // block engine starts forwarding a few slots early, for super high activity accounts
-                // it might be ideal to wait until the leader slot is up
-                if is_leader_slot {
-                    let pending_tx_notification = maybe_pending_tx_notification.ok_or(BackrunError::Shutdown)?;
-                    let bundles = build_bundles(pending_tx_notification, keypair, &blockhash, &tip_accounts, &mut rng, &message);
-                    if !bundles.is_empty() {
-                        let now = Instant::now();
-                        let results = send_bundles(&mut searcher_client, &bundles).await?;
-                        let send_elapsed = now.elapsed().as_micros() as u64;
-                        let send_rt_pp_us = send_elapsed / bundles.len() as u64;
-
-                        match block_stats.entry(highest_slot) {
-                            Entry::Occupied(mut entry) => {
-                                let stats = entry.get_mut();
-                                stats.bundles_sent.extend(bundles.into_iter().zip(results.into_iter()));
-                                stats.send_elapsed += send_elapsed;
-                                let _ = stats.send_rt_per_packet.increment(send_rt_pp_us);
+            maybe_decoded_txs = decoded_tx_receiver.recv() => {
+                let decoded_txs = maybe_decoded_txs.ok_or(BackrunError::Shutdown)?;
+                last_mempool_tx = Some(DateTime::<Utc>::from(clock_offset.now()));
+                let received_at = decoded_txs.received_at;
+                let mut mempool_txs = collect_converted(decoded_txs.transactions, strict_packet_conversion, &mut packet_stats)?;
+                if let Some(echo_latency) = &mut echo_latency {
+                    // the payer's transactions are subscribed to for the echo, they're not backrun
+                    mempool_txs.retain(|tx| {
+                        let ours = tx.message.static_account_keys().first() == Some(&keypair.pubkey());
+                        if ours {
+                            echo_latency.record_seen(&tx.signatures[0], received_at);
+                        }
+                        !ours
+                    });
+                }
+                // the shadow strategy runs after sending, off the live bundles' critical path
+                let shadow_txs = if shadow.is_some() { mempool_txs.clone() } else { Vec::new() };
+                let slot_assertion = slot_assertion.map(|(program_id, window_slots)| SlotAssertion::window(program_id, highest_slot, window_slots));
+                let signer = BundleSigner { keypair, blockhash, tip_accounts: &tip_accounts, slot_assertion, default_priority: priority_class, tip_multipliers, tip_split: &tip_split };
+                let mut bundles = match &mut wasm_strategy {
+                    Some(wasm_strategy) => {
+                        let mut bundles = Vec::new();
+                        for mempool_tx in mempool_txs {
+                            let event = StrategyEvent::mempool_tx(highest_slot, &mempool_tx);
+                            bundles.extend(build_strategy_bundles(wasm_strategy, &event, Some(&mempool_tx), &signer, &mut rng));
+                        }
+                        bundles
+                    }
+                    None => build_bundles(mempool_txs, &signer, &mut rng, &message, tip_lamports, expected_profit_lamports),
+                };
+                if let Some(ev_gate) = &mut ev_gate {
+                    bundles.retain(|b| ev_gate.allows(&format!("backrun of {}", b.trigger_signature()), b.profit_estimate.as_ref()));
+                }
+                queue_bundles(&mut opportunity_queue, bundles.drain(..), highest_slot, clock_offset.now(), opportunity_ttl_slots, blockhash_last_valid_slot, submit_deadline);
+                // best first, so it gets the write locks when opportunities overlap
+                while let Some(opportunity) = opportunity_queue.pop_best(highest_slot, clock_offset.now()) {
+                    bundles.push(opportunity.payload);
+                }
+                let mut dropped: Vec<_> = opportunity_queue.take_dropped().into_iter().map(|(o, reason)| (o.payload, reason)).collect();
+                if let Some(token_guard) = &mut token_guard {
+                    // accounts that can't be fetched fail the check, so an RPC outage blocks rather than lets through
+                    if let Err(e) = token_guard.resolve(&rpc_client, bundles.iter().flat_map(|b| b.mempool_txs.iter().chain(b.backrun_txs.iter()))).await {
+                        warn!("pipeline {pipeline} token guard failed to fetch accounts: {e}");
+                    }
+                    bundles.retain(|b| match token_guard.check(b.mempool_txs.iter().chain(b.backrun_txs.iter())) {
+                        Ok(()) => true,
+                        Err(e) => {
+                            info!("skipping backrun of {}: {e}", b.trigger_signature());
+                            false
+                        }
+                    });
+                }
+                if let Some(simulation_policy) = &mut simulation_policy {
+                    let results = futures_util::future::join_all(bundles.iter().map(|b| {
+                        let txs: Vec<_> = b.mempool_txs.iter().chain(b.backrun_txs.iter()).cloned().collect();
+                        let simulation_policy = &*simulation_policy;
+                        let rpc_client = &rpc_client;
+                        async move { simulation_policy.simulate(rpc_client, &txs).await }
+                    }))
+                    .await;
+                    let mut results = results.into_iter();
+                    bundles.retain(|b| {
+                        let result = results.next().expect("one result per bundle");
+                        simulation_policy.record(&result);
+                        match result {
+                            Ok(_) => true,
+                            Err(e) => {
+                                info!("skipping backrun of {}: {e}", b.trigger_signature());
+                                false
                             }
-                            Entry::Vacant(entry) => {
-                                let mut send_rt_per_packet = Histogram::new();
-                                let _ = send_rt_per_packet.increment(send_rt_pp_us);
-                                entry.insert(BlockStats {
-                                    bundles_sent: bundles.into_iter().zip(results.into_iter()).collect(),
-                                    send_elapsed,
-                                    send_rt_per_packet
+                        }
+                    });
+                }
+                // the checks above may have taken long enough to miss deadlines, drop those before
+                // they take write locks, budget or claims from bundles that can still make it
+                drop_late_bundles(&pipeline, &mut bundles, clock_offset.now(), &mut dropped);
+                if let Some(congestion) = &mut congestion {
+                    let now = Instant::now();
+                    bundles.retain(|b| {
+                        let admitted = congestion.admits(b.priority, now);
+                        if !admitted {
+                            debug!("holding back {} backrun of {}, auction {}", b.priority, b.trigger_signature(), congestion.level());
+                        }
+                        admitted
+                    });
+                }
+                if !bundles.is_empty() {
+                    if let Err(anomaly) = dead_man_switch.check(bundles.len()) {
+                        debug!("dropping {} bundles, submissions halted: {anomaly}", bundles.len());
+                        bundles.clear();
+                    }
+                }
+                if !bundles.is_empty() && runtime_params.get().paused {
+                    debug!("paused, dropping {} bundles", bundles.len());
+                    bundles.clear();
+                }
+                if lease.as_ref().is_some_and(|lease| !lease.is_active()) {
+                    debug!("standby, dropping {} bundles", bundles.len());
+                    bundles.clear();
+                }
+                if observe_only {
+                    // before the write locks and budgets, nothing observed is sent so it mustn't
+                    // use them up or hold back live instances through the fleet's claims
+                    for bundle in bundles.drain(..) {
+                        let fee_lamports = estimate_fee_lamports(&bundle.backrun_txs);
+                        observation.record(bundle.tip_lamports, fee_lamports, bundle.profit_estimate.as_ref());
+                        let expected = match &bundle.profit_estimate {
+                            Some(estimate) => format!("expected profit {}, net {}", estimate.expected_profit_lamports, estimate.net_lamports()),
+                            None => "no profit estimate".to_string(),
+                        };
+                        info!("pipeline {pipeline} observe-only, would send {} backrun of {}: {} transactions, tip {}, fees {fee_lamports}, {expected}", bundle.priority, bundle.trigger_signature(), bundle.mempool_txs.len() + bundle.backrun_txs.len(), bundle.tip_lamports);
+                    }
+                }
+                if let Some(write_lock_limiter) = &mut write_lock_limiter {
+                    bundles.retain(|b| {
+                        let txs: Vec<_> = b.mempool_txs.iter().chain(b.backrun_txs.iter()).cloned().collect();
+                        match write_lock_limiter.try_acquire(highest_slot, &txs, b.priority) {
+                            Ok(()) => true,
+                            Err(e) => {
+                                info!("skipping backrun of {}: {e}", b.trigger_signature());
+                                false
+                            }
+                        }
+                    });
+                }
+                if let Some(tip_budget) = &mut tip_budget {
+                    let now = Instant::now();
+                    bundles.retain(|b| {
+                        let fits = tip_budget.try_spend(b.tip_lamports, now);
+                        if !fits {
+                            debug!("skipping backrun of {}, pipeline {pipeline} tip budget spent", b.trigger_signature());
+                        }
+                        fits
+                    });
+                }
+                if let Some(shared_state) = &shared_state {
+                    // skip what another instance already backran, then charge the fleet's
+                    // budget. Redis being down shouldn't stop submissions, but it shouldn't
+                    // let the fleet overspend either. Each is one round trip for the whole batch
+                    let keys: Vec<_> = bundles.iter().map(|b| format!("{pipeline}:{}", b.trigger_signature())).collect();
+                    match shared_state.claim_all(&keys, dedupe_ttl).await {
+                        Ok(claims) => {
+                            let mut claims = claims.into_iter();
+                            bundles.retain(|b| {
+                                let claimed = claims.next().unwrap_or(true);
+                                if !claimed {
+                                    debug!("skipping backrun of {}, already submitted by another instance", b.trigger_signature());
+                                }
+                                claimed
+                            });
+                        }
+                        Err(e) => warn!("failed to claim {} backruns, sending anyway: {e}", keys.len()),
+                    }
+                    if let Some(tip_budget) = &tip_budget {
+                        let lamports: Vec<_> = bundles.iter().map(|b| b.tip_lamports).collect();
+                        match shared_state.try_spend_all(&pipeline, &lamports, tip_budget.max_lamports(), tip_budget.window()).await {
+                            Ok(fits) => {
+                                let mut fits = fits.into_iter();
+                                bundles.retain(|b| {
+                                    let fits = fits.next().unwrap_or(false);
+                                    if !fits {
+                                        debug!("skipping backrun of {}, pipeline {pipeline} fleet tip budget spent", b.trigger_signature());
+                                    }
+                                    fits
                                 });
                             }
+                            Err(e) => {
+                                warn!("failed to charge fleet tip budget, skipping {} backruns: {e}", bundles.len());
+                                bundles.clear();
+                            }
+                        }
+                    }
+                }
+                // Redis may have been slow enough for bundles to miss their deadline after all
+                drop_late_bundles(&pipeline, &mut bundles, clock_offset.now(), &mut dropped);
+                if !bundles.is_empty() {
+                    let now = Instant::now();
+                    let submitted_at = DateTime::<Utc>::from(clock_offset.now());
+                    let results = match &race {
+                        Some(race) => race_bundles(race, &rpc_latency, &bundles).await,
+                        None => send_bundles(searcher_client.client_mut(), &rpc_latency, &bundles).await?,
+                    };
+                    bundles_sent += results.iter().filter(|result| result.is_ok()).count() as u64;
+                    last_bundle_sent = Some(submitted_at);
+                    let regions: Vec<String> = match &race {
+                        Some(race) => race.leading_targets().iter().map(|target| if target.region.is_empty() { block_engine_region(&target.block_engine_url) } else { target.region.clone() }).collect(),
+                        None => vec![block_engine_region(&block_engine_url)],
+                    };
+                    if let Some(echo_latency) = &mut echo_latency {
+                        for (bundle, _) in bundles.iter().zip(results.iter()).filter(|(_, result)| result.is_ok()) {
+                            for tx in &bundle.backrun_txs {
+                                for region in &regions {
+                                    echo_latency.record_submitted(region, tx.signatures[0], now);
+                                }
+                            }
+                        }
+                    }
+                    if let Some((_, comparison)) = &mut shadow {
+                        for (bundle, result) in bundles.iter().zip(results.iter()) {
+                            let uuid = result.as_ref().ok().map(|r| r.get_ref().uuid.as_str());
+                            comparison.record_live(bundle.trigger_signature(), uuid, bundle.tip_lamports, bundle.profit_estimate.as_ref(), now);
+                        }
+                    }
+                    if let Some(audit_log) = &mut audit_log {
+                        let next_leader = leader_schedule.iter().flat_map(|(leader, slots)| slots.iter().map(move |slot| (*slot, *leader))).filter(|(slot, _)| *slot >= highest_slot).min();
+                        for (bundle, result) in bundles.iter().zip(results.iter()) {
+                            let uuid = result.as_ref().ok().map(|r| r.get_ref().uuid.as_str());
+                            let submission = BundleSubmission {
+                                submitted_at,
+                                submitted_slot: highest_slot,
+                                slots_to_leader: next_leader.map(|(slot, _)| slot - highest_slot),
+                                leader: next_leader.map(|(_, leader)| leader),
+                                regions: &regions,
+                                rejection: result.as_ref().err().map(|status| status.message()),
+                            };
+                            let txs: Vec<_> = bundle.mempool_txs.iter().chain(bundle.backrun_txs.iter()).cloned().collect();
+                            audit_log.record_bundle(uuid, &txs, &submission).map_err(BackrunError::AuditLogError)?;
+                        }
+                    }
+                    if let Some(shared_state) = &shared_state {
+                        let records: Vec<_> = bundles.iter().zip(results.iter()).map(|(bundle, result)| {
+                            let uuid = result.as_ref().ok().map(|r| r.get_ref().uuid.clone());
+                            let signatures = bundle.mempool_txs.iter().chain(bundle.backrun_txs.iter()).map(|tx| tx.signatures[0]).collect();
+                            (uuid, signatures)
+                        }).collect();
+                        // nothing sent next depends on the records, keep them off the loop
+                        let shared_state = shared_state.clone();
+                        let submitted_slot = highest_slot;
+                        tokio::spawn(async move {
+                            if let Err(e) = shared_state.record_bundles(&records, submitted_slot, max_shared_bundles).await {
+                                warn!("failed to record bundles in redis: {e}");
+                            }
+                        });
+                    }
+                    if let Some(tpu_fallback) = &tpu_fallback {
+                        for (bundle, result) in bundles.iter().zip(results.iter()) {
+                            if !matches!(result, Err(status) if is_block_engine_unavailable(status)) {
+                                continue;
+                            }
+                            let txs: Vec<_> = bundle.mempool_txs.iter().chain(bundle.backrun_txs.iter()).cloned().collect();
+                            match tpu_fallback.send(&txs).await {
+                                Ok(()) => info!("block engine unavailable, sent backrun of {} to leader TPUs", bundle.trigger_signature()),
+                                Err(e) => warn!("TPU fallback send failed: {e}"),
+                            }
+                        }
+                    }
+                    for status in results.iter().filter_map(|r| r.as_ref().err()) {
+                        if !is_auth_keypair_failure(status) {
+                            dead_man_switch.record_outcome(SubmissionOutcome::Rejected);
+                        }
+                        if let (Some(congestion), Some(signal)) = (&mut congestion, AuctionSignal::from_send_error(status)) {
+                            congestion.record(signal, Instant::now());
+                        }
+                    }
+                    if let Some(status) = results.iter().filter_map(|r| r.as_ref().err()).find(|status| is_auth_keypair_failure(status)) {
+                        searcher_client.handle_error(status).await?;
+                    }
+                    let send_elapsed = now.elapsed().as_micros() as u64;
+                    let send_rt_pp_us = send_elapsed / bundles.len() as u64;
+
+                    match block_stats.entry(highest_slot) {
+                        Entry::Occupied(mut entry) => {
+                            let stats = entry.get_mut();
+                            stats.bundles_sent.extend(bundles.into_iter().zip(results.into_iter()));
+                            stats.send_elapsed += send_elapsed;
+                            let _ = stats.send_rt_per_packet.increment(send_rt_pp_us);
+                        }
+                        Entry::Vacant(entry) => {
+                            let mut send_rt_per_packet = Histogram::new();
+                            let _ = send_rt_per_packet.increment(send_rt_pp_us);
+                            entry.insert(BlockStats {
+                                bundles_sent: bundles.into_iter().zip(results.into_iter()).collect(),
+                                send_elapsed,
+                                send_rt_per_packet
+                            });
                         }
                     }
                 }
+                for mempool_tx in &shadow_txs {
+                    let event = StrategyEvent::mempool_tx(highest_slot, mempool_tx);
+                    run_shadow(&mut shadow, &event, Some(mempool_tx), &signer, &mut rng);
+                }
+                // after sending so telling the strategy doesn't delay the bundles that made it
+                for (bundle, reason) in dropped {
+                    debug!("dropped backrun of {}: {reason}", bundle.trigger_signature());
+                    if let Some(wasm_strategy) = &mut wasm_strategy {
+                        let event = StrategyEvent::BundleDropped { id: bundle.id.clone(), trigger_signature: bundle.trigger_signature().to_string(), reason: reason.to_string() };
+                        let bundles = build_strategy_bundles(wasm_strategy, &event, None, &signer, &mut rng);
+                        queue_bundles(&mut opportunity_queue, bundles, highest_slot, clock_offset.now(), opportunity_ttl_slots, blockhash_last_valid_slot, submit_deadline);
+                    }
+                }
+            }
+            Ok(()) = shutdown.changed() => {
+                if let Some(path) = &state_snapshot {
+                    let state = WarmState {
+                        highest_slot,
+                        blockhash,
+                        leader_schedule,
+                        opportunities: opportunity_queue.drain(),
+                    };
+                    match save_snapshot(path, &state) {
+                        Ok(()) => info!("pipeline {pipeline} saved state to {path:?}, {} queued opportunities", state.opportunities.len()),
+                        Err(e) => warn!("pipeline {pipeline} failed to save state to {path:?}: {e}"),
+                    }
+                }
+                return Ok(());
             }
             maybe_slot = slot_receiver.recv() => {
                 highest_slot = maybe_slot.ok_or(BackrunError::Shutdown)?;
-                is_leader_slot = leader_schedule.iter().any(|(_, slots)| slots.contains(&highest_slot));
+                let nearby_slots = highest_slot..=highest_slot + leader_proximity_slots;
+                let nearby = leader_schedule.values().any(|slots| nearby_slots.clone().any(|slot| slots.contains(&slot)));
+                leader_nearby.send_if_modified(|leader_nearby| std::mem::replace(leader_nearby, nearby) != nearby);
+                let slot_assertion = slot_assertion.map(|(program_id, window_slots)| SlotAssertion::window(program_id, highest_slot, window_slots));
+                let signer = BundleSigner { keypair, blockhash, tip_accounts: &tip_accounts, slot_assertion, default_priority: priority_class, tip_multipliers, tip_split: &tip_split };
+                if let Some(wasm_strategy) = &mut wasm_strategy {
+                    let bundles = build_strategy_bundles(wasm_strategy, &StrategyEvent::Slot { slot: highest_slot }, None, &signer, &mut rng);
+                    queue_bundles(&mut opportunity_queue, bundles, highest_slot, clock_offset.now(), opportunity_ttl_slots, blockhash_last_valid_slot, submit_deadline);
+                }
+                run_shadow(&mut shadow, &StrategyEvent::Slot { slot: highest_slot }, None, &signer, &mut rng);
             }
             maybe_block = block_receiver.recv() => {
                 let block = maybe_block.ok_or(BackrunError::Shutdown)?;
+                leader_skips.block(block.value.slot);
                 print_block_stats(&mut block_stats, block, &leader_schedule, &mut block_signatures);
             }
         }
     }
 }
 
+fn client_headers(args: &Args) -> result::Result<ClientHeaders, ClientHeaderError> {
+    let mut headers = ClientHeaders::default().with_client_name(env!("CARGO_PKG_NAME"))?;
+    if let Some(strategy_id) = &args.strategy_id {
+        headers = headers.with_strategy_id(strategy_id)?;
+    }
+    for pair in &args.client_header {
+        headers.insert_pair(pair)?;
+    }
+    Ok(headers)
+}
+
+fn validate_args(args: &Args) -> result::Result<(), ConfigErrors> {
+    let mut validator = ConfigValidator::new();
+    validator
+        .url(
+            "--block-engine-url",
+            &args.block_engine_url,
+            &["http", "https"],
+        )
+        .url("--pubsub-url", &args.pubsub_url, &["ws", "wss"])
+        .url("--rpc-url", &args.rpc_url, &["http", "https"])
+        .keypair_file("--payer-keypair", &args.payer_keypair)
+        .regions("--regions", &args.regions)
+        .range(
+            "--tip-lamports",
+            args.tip_lamports,
+            MIN_TIP_LAMPORTS..=u64::MAX,
+        )
+        .range(
+            "--pubsub-stream-timeout-secs",
+            args.pubsub_stream_timeout_secs,
+            1..=u64::MAX,
+        )
+        .range(
+            "--mempool-stream-timeout-secs",
+            args.mempool_stream_timeout_secs,
+            1..=u64::MAX,
+        )
+        .range(
+            "--bundle-results-stream-timeout-secs",
+            args.bundle_results_stream_timeout_secs,
+            1..=u64::MAX,
+        );
+    // with pipelines, the accounts come from each pipeline's source
+    if args.pipelines.is_none() && args.pool_pairs.is_empty() {
+        validator.not_empty("--backrun-accounts", &args.backrun_accounts);
+    }
+    if !args.pool_pairs.is_empty() {
+        validator.not_empty("--pool-dexes", &args.pool_dexes);
+    }
+    validator.range(
+        "--max-queued-opportunities",
+        args.max_queued_opportunities,
+        1..=usize::MAX,
+    );
+    if let Err(e) = client_headers(args) {
+        validator.problem("--client-header", e.to_string());
+    }
+    for (i, url) in args.race_block_engine_urls.iter().enumerate() {
+        validator.url(
+            &format!("--race-block-engine-urls[{i}]"),
+            url,
+            &["http", "https"],
+        );
+    }
+    if !args.race_block_engine_urls.is_empty() {
+        validator.range(
+            "--race-fanout",
+            args.race_fanout,
+            1..=args.race_block_engine_urls.len(),
+        );
+        validator.range(
+            "--race-switch-confirmations",
+            args.race_switch_confirmations,
+            1..=u32::MAX,
+        );
+    }
+    for (i, path) in args.auth_keypair.iter().enumerate() {
+        validator.keypair_file(&format!("--auth-keypair[{i}]"), path);
+    }
+    match TipSplit::new(args.tip_share.clone(), MIN_TIP_LAMPORTS) {
+        Ok(tip_split) => {
+            if let Err(e) = tip_split.check_floor(args.tip_lamports) {
+                validator.problem("--tip-lamports", e.to_string());
+            }
+        }
+        Err(e) => {
+            validator.problem("--tip-share", e.to_string());
+        }
+    }
+    if let Some(max_bundles) = args.max_bundles_per_write_lock {
+        validator.range("--max-bundles-per-write-lock", max_bundles, 1..=usize::MAX);
+    }
+    if args.echo_latency {
+        validator.range("--echo-max-age-secs", args.echo_max_age_secs, 1..=u64::MAX);
+    }
+    if let Some(worker_threads) = args.worker_threads {
+        validator.range("--worker-threads", worker_threads, 1..=usize::MAX);
+    }
+    let max_blocking_threads = args.max_blocking_threads.unwrap_or(512);
+    validator
+        .range(
+            "--max-blocking-threads",
+            max_blocking_threads,
+            1..=usize::MAX,
+        )
+        .range(
+            "--decode-threads",
+            args.decode_threads,
+            1..=max_blocking_threads,
+        );
+    if cfg!(not(target_os = "linux")) && args.pin_searcher_loop_core.is_some() {
+        validator.problem("--pin-searcher-loop-core", "only supported on linux");
+    }
+    validator
+        .range(
+            "--critical-tip-multiplier",
+            args.critical_tip_multiplier,
+            1.0..=100.0,
+        )
+        .range(
+            "--bulk-tip-multiplier",
+            args.bulk_tip_multiplier,
+            0.01..=1.0,
+        );
+    if let Some(throttle_rate) = args.congestion_throttle_rate {
+        validator
+            .range(
+                "--congestion-resume-rate",
+                args.congestion_resume_rate,
+                0.0..=throttle_rate,
+            )
+            .range(
+                "--congestion-throttle-rate",
+                throttle_rate,
+                args.congestion_resume_rate..=args.congestion_pause_rate,
+            )
+            .range(
+                "--congestion-pause-rate",
+                args.congestion_pause_rate,
+                throttle_rate..=1.0,
+            )
+            .range(
+                "--congestion-window-secs",
+                args.congestion_window_secs,
+                1..=u64::MAX,
+            )
+            .range(
+                "--congestion-min-samples",
+                args.congestion_min_samples,
+                1..=usize::MAX,
+            );
+    }
+    if args.mempool_prefilter {
+        validator.range(
+            "--prefilter-false-positive-rate",
+            args.prefilter_false_positive_rate,
+            0.000_001..=0.5,
+        );
+    } else if !args.interest_accounts.is_empty() {
+        validator.problem("--interest-accounts", "requires --mempool-prefilter");
+    }
+    if args.critical_reserved_bundles > 0 && args.max_bundles_per_leader_window.is_none() {
+        validator.problem(
+            "--critical-reserved-bundles",
+            "only applies with --max-bundles-per-leader-window",
+        );
+    }
+    for (field, threshold) in [
+        (
+            "--max-consecutive-rejections",
+            args.max_consecutive_rejections,
+        ),
+        (
+            "--max-consecutive-simulation-failures",
+            args.max_consecutive_simulation_failures,
+        ),
+    ] {
+        if let Some(threshold) = threshold {
+            validator.range(field, threshold, 1..=usize::MAX);
+        }
+    }
+    if args.max_balance_drop_lamports.is_some() {
+        validator.range(
+            "--balance-drop-window-secs",
+            args.balance_drop_window_secs,
+            1..=u64::MAX,
+        );
+    }
+    if let Some(ntp_server) = &args.ntp_server {
+        if !ntp_server
+            .rsplit_once(':')
+            .is_some_and(|(host, port)| !host.is_empty() && port.parse::<u16>().is_ok())
+        {
+            validator.problem(
+                "--ntp-server",
+                format!("expected host:port, got {ntp_server:?}"),
+            );
+        }
+        validator.range(
+            "--clock-check-interval-secs",
+            args.clock_check_interval_secs,
+            1..=u64::MAX,
+        );
+    }
+    if let Some(redis_url) = &args.redis_url {
+        if let Err(e) = RedisConfig::from_url(redis_url) {
+            validator.problem("--redis-url", e.to_string());
+        }
+        validator.range("--dedupe-ttl-secs", args.dedupe_ttl_secs, 1..=u64::MAX);
+        validator.range(
+            "--redis-max-bundles",
+            args.redis_max_bundles,
+            1..=usize::MAX,
+        );
+    }
+    if let Some(max_token_risk) = args.max_token_risk {
+        validator.range("--max-token-risk", max_token_risk, 0..=100);
+        validator.range(
+            "--token-risk-ttl-secs",
+            args.token_risk_ttl_secs,
+            1..=u64::MAX,
+        );
+    }
+    if !args.require_simulation && !args.allowed_simulation_errors.is_empty() {
+        validator.problem(
+            "--allowed-simulation-errors",
+            "requires --require-simulation",
+        );
+    }
+    if args.state_snapshot.is_some() {
+        validator.range(
+            "--state-snapshot-max-age-secs",
+            args.state_snapshot_max_age_secs,
+            1..=u64::MAX,
+        );
+        if let Some(interval_secs) = args.state_snapshot_interval_secs {
+            validator.range(
+                "--state-snapshot-interval-secs",
+                interval_secs,
+                1..=u64::MAX,
+            );
+        }
+    } else if args.state_snapshot_interval_secs.is_some() {
+        validator.problem(
+            "--state-snapshot-interval-secs",
+            "requires --state-snapshot",
+        );
+    }
+    if args.ha_lease_file.is_some() {
+        validator.range("--ha-lease-ttl-ms", args.ha_lease_ttl_ms, 100..=60_000);
+    }
+    if args.tip_budget_lamports.is_some() {
+        validator.range(
+            "--tip-budget-window-secs",
+            args.tip_budget_window_secs,
+            1..=u64::MAX,
+        );
+    }
+    if args.min_profit_margin_lamports.is_some() && args.expected_profit_lamports.is_none() {
+        validator.problem(
+            "--min-profit-margin-lamports",
+            "requires --expected-profit-lamports, every bundle would pass unestimated",
+        );
+    }
+    validator.finish()
+}
+
+/// Each pipeline's arguments: the command line with the pipeline's settings applied. Pipelines
+/// write separate audit logs and state snapshots, prefixed with their name.
+fn load_pipelines(args: &Args, path: &Path) -> result::Result<Vec<(String, Args)>, String> {
+    let config = PipelineConfig::load(path).map_err(|e| e.to_string())?;
+    let mut pipelines = Vec::with_capacity(config.pipelines.len());
+    for pipeline in &config.pipelines {
+        let mut pipeline_args = pipeline.apply(args).map_err(|e| e.to_string())?;
+        pipeline_args.pipelines = None;
+        for path in [
+            &mut pipeline_args.audit_log,
+            &mut pipeline_args.state_snapshot,
+        ]
+        .into_iter()
+        .flatten()
+        {
+            let file_name = path
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or_default();
+            path.set_file_name(format!("{}-{file_name}", pipeline.name));
+        }
+        validate_args(&pipeline_args).map_err(|e| format!("pipeline {}: {e}", pipeline.name))?;
+        pipelines.push((pipeline.name.clone(), pipeline_args));
+    }
+    Ok(pipelines)
+}
+
 fn main() -> Result<()> {
     env_logger::builder()
         .format_timestamp(Some(TimestampPrecision::Micros))
         .init();
-    let args: Args = Args::parse();
+    let mut args: Args = Args::parse();
+    if let Some(path) = args.strategy_config.clone() {
+        args = apply_strategy_config(&args, &path).unwrap_or_else(|e| {
+            error!("{e}");
+            std::process::exit(1);
+        });
+    }
+    if let Err(e) = validate_args(&args) {
+        error!("{e}");
+        std::process::exit(1);
+    }
 
     let payer_keypair = Arc::new(read_keypair_file(&args.payer_keypair).expect("parse kp file"));
-    let auth_keypair = Arc::new(read_keypair_file(&args.auth_keypair).expect("parse kp file"));
+    let auth_keypairs: Vec<Arc<Keypair>> = args
+        .auth_keypair
+        .iter()
+        .map(|path| Arc::new(read_keypair_file(path).expect("parse kp file")))
+        .collect();
+
+    let headers = client_headers(&args).expect("validated client headers");
 
-    set_host_id(auth_keypair.pubkey().to_string());
+    // one store for every pipeline, so a change applies to all of them
+    let runtime_params = match &args.runtime_params {
+        Some(path) => ParamStore::load(path).unwrap_or_else(|e| {
+            error!("{e}");
+            std::process::exit(1);
+        }),
+        None => ParamStore::default(),
+    };
 
-    let runtime = Builder::new_multi_thread().enable_all().build().unwrap();
+    if let Some(path) = &args.trace_grpc {
+        grpc_trace::enable(path).expect("opens gRPC trace file");
+        warn!("tracing block engine calls to {path:?}");
+    }
+    #[cfg(feature = "fault-injection")]
+    if let Some(path) = &args.fault_scenario {
+        let scenario = fault_injection::FaultScenario::load(path).expect("loads fault scenario");
+        fault_injection::install(scenario);
+        warn!("injecting faults from {path:?}");
+    }
+    if args.observe_only {
+        warn!("observe-only, bundles are logged instead of sent");
+    }
+
+    let pipelines = args.pipelines.as_ref().map(|path| {
+        load_pipelines(&args, path).unwrap_or_else(|e| {
+            error!("{e}");
+            std::process::exit(1);
+        })
+    });
+
+    set_host_id(auth_keypairs[0].pubkey().to_string());
+
+    let runtime = RuntimeConfig {
+        worker_threads: args.worker_threads,
+        max_blocking_threads: args.max_blocking_threads,
+    }
+    .build()
+    .unwrap();
+    // the searcher loops run on this thread, see below
+    if let Some(core) = args.pin_searcher_loop_core {
+        match pin_current_thread(core) {
+            Ok(()) => info!("pinned the searcher loops to core {core}"),
+            Err(e) => {
+                error!("failed to pin the searcher loops to core {core}: {e}");
+                std::process::exit(1);
+            }
+        }
+    }
     runtime.block_on(async move {
-        let (slot_sender, slot_receiver) = channel(100);
-        let (block_sender, block_receiver) = channel(100);
-        let (bundle_results_sender, bundle_results_receiver) = channel(100);
-        let (pending_tx_sender, pending_tx_receiver) = channel(100);
-
-        tokio::spawn(slot_subscribe_loop(args.pubsub_url.clone(), slot_sender));
-        tokio::spawn(block_subscribe_loop(args.pubsub_url.clone(), block_sender));
-        tokio::spawn(pending_tx_loop(
-            args.block_engine_url.clone(),
-            auth_keypair.clone(),
-            pending_tx_sender,
-            args.backrun_accounts,
+        let clock_offset = ClockOffset::default();
+        if let Some(ntp_server) = args.ntp_server.clone() {
+            let clock_offset = clock_offset.clone();
+            let check_interval = Duration::from_secs(args.clock_check_interval_secs);
+            tokio::spawn(async move {
+                loop {
+                    match measure_clock_offset(8, Duration::from_millis(250), || {
+                        ntp_sample(&ntp_server)
+                    })
+                    .await
+                    {
+                        Ok(estimate) => {
+                            info!(
+                                "clock offset {}us, jitter {}us",
+                                estimate.offset_us, estimate.jitter_us
+                            );
+                            clock_offset.update(&estimate);
+                        }
+                        Err(e) => warn!("failed to measure clock offset against {ntp_server}: {e}"),
+                    }
+                    sleep(check_interval).await;
+                }
+            });
+        }
+
+        let lease = args.ha_lease_file.as_ref().map(|path| {
+            let instance_id = args.ha_instance_id.clone().expect("required by clap");
+            let (lease, _renewal) = Lease::spawn_renewal(
+                Arc::new(FileLeaseStore::new(path)),
+                instance_id,
+                Duration::from_millis(args.ha_lease_ttl_ms),
+            );
+            lease
+        });
+
+        // the searcher loops save their state and return on the first signal, a second one
+        // exits right away
+        let (shutdown_sender, shutdown) = watch::channel(false);
+        let shutdown_sender = Arc::new(shutdown_sender);
+        let signalled_shutdown = shutdown_sender.clone();
+        let mut terminate = signal(SignalKind::terminate()).expect("installs SIGTERM handler");
+        let mut interrupt = signal(SignalKind::interrupt()).expect("installs SIGINT handler");
+        tokio::spawn(async move {
+            tokio::select! {
+                _ = terminate.recv() => {}
+                _ = interrupt.recv() => {}
+            }
+            info!("shutting down, signal again to exit immediately");
+            let _ = signalled_shutdown.send(true);
+            tokio::select! {
+                _ = terminate.recv() => {}
+                _ = interrupt.recv() => {}
+            }
+            std::process::exit(1);
+        });
+
+        if args.runtime_params.is_some() {
+            let reloaded = runtime_params.clone();
+            let mut hangups = signal(SignalKind::hangup()).expect("installs SIGHUP handler");
+            tokio::spawn(async move {
+                while hangups.recv().await.is_some() {
+                    match reloaded.reload() {
+                        Ok(true) => {}
+                        Ok(false) => info!("runtime params unchanged"),
+                        Err(e) => warn!("keeping the current runtime params: {e}"),
+                    }
+                }
+            });
+        }
+
+        let admin = Arc::new(BackrunAdmin::new(runtime_params.clone(), shutdown_sender));
+        if let Some(addr) = args.admin_addr {
+            let token = args.admin_token.as_deref().expect("required by clap");
+            match spawn_admin_server(addr, token, admin.clone()) {
+                Ok(addr) => info!("serving the admin endpoint on {addr}"),
+                Err(e) => {
+                    error!("{e}");
+                    std::process::exit(1);
+                }
+            }
+        }
+
+        let shared_state = args.redis_url.as_ref().map(|url| {
+            let config = RedisConfig::from_url(url).expect("validated redis url");
+            Arc::new(RedisState::new(config, args.redis_key_prefix.clone()))
+        });
+
+        let context = PipelineContext {
+            payer_keypair,
+            auth_keypairs,
+            headers,
+            clock_offset,
+            runtime_params,
+            admin,
+            lease,
+            shared_state,
+            shutdown,
+        };
+        let lease = context.lease.clone();
+        let result = match pipelines {
+            None => run_pipeline("default".to_string(), args, context).await,
+            Some(pipelines) => {
+                info!("running {} pipelines", pipelines.len());
+                // the searcher loops hold a ThreadRng so they can't be spawned, they run
+                // concurrently on this task while their streams run on the runtime's workers
+                let results = futures_util::future::join_all(pipelines.into_iter().map(
+                    |(name, pipeline_args)| run_pipeline(name, pipeline_args, context.clone()),
+                ))
+                .await;
+                for result in results {
+                    if let Err(e) = result {
+                        error!("pipeline failed: {e}");
+                    }
+                }
+                Ok(())
+            }
+        };
+        // the searcher loops have stopped submitting, the standby needn't wait out the ttl
+        if let Some(lease) = lease {
+            match lease.release().await {
+                Ok(()) => info!("{} released the lease", lease.holder()),
+                Err(e) => warn!("failed to release the lease: {e}"),
+            }
+        }
+        result
+    })
+}
+
+/// What every pipeline shares: the keypairs and headers its clients use, and the process wide
+/// clock offset, standby lease and fleet state.
+#[derive(Clone)]
+struct PipelineContext {
+    payer_keypair: Arc<Keypair>,
+    auth_keypairs: Vec<Arc<Keypair>>,
+    headers: ClientHeaders,
+    clock_offset: ClockOffset,
+    runtime_params: ParamStore,
+    admin: Arc<BackrunAdmin>,
+    lease: Option<Lease>,
+    shared_state: Option<Arc<RedisState>>,
+    shutdown: watch::Receiver<bool>,
+}
+
+/// How one pipeline builds, limits, records and guards its bundles, set from its [`Args`].
+struct SearcherConfig {
+    connection: SearcherConnection,
+    strategy: SearcherStrategy,
+    limits: SearcherLimits,
+    sinks: SearcherSinks,
+    safety: SearcherSafety,
+}
+
+/// Where the pipeline sends bundles and reads chain state from.
+struct SearcherConnection {
+    block_engine_url: String,
+    rpc_url: String,
+    regions: Vec<String>,
+    race: Option<RegionRace>,
+    tpu_fallback: Option<TpuFallbackSender>,
+}
+
+/// What the pipeline's backruns contain and how they're tipped.
+struct SearcherStrategy {
+    message: String,
+    wasm_strategy: Option<WasmStrategy>,
+    shadow: Option<(WasmStrategy, ShadowComparison)>,
+    tip_program_pubkey: Pubkey,
+    /// The slot assertion program and how many slots after the current one a bundle may land.
+    slot_assertion: Option<(Pubkey, u64)>,
+    tip_lamports: u64,
+    priority_class: PriorityClass,
+    tip_multipliers: TipMultipliers,
+    tip_split: TipSplit,
+    expected_profit_lamports: Option<u64>,
+}
+
+/// What keeps bundles from being sent, and how long queued ones stay sendable.
+struct SearcherLimits {
+    ev_gate: Option<ExpectedValueGate>,
+    token_guard: Option<TokenGuard>,
+    simulation_policy: Option<SimulationPolicy>,
+    write_lock_limits: Option<WriteLockLimits>,
+    congestion: Option<CongestionMonitor>,
+    opportunity_ttl_slots: u64,
+    submit_deadline: Option<Duration>,
+    max_queued_opportunities: usize,
+    tip_budget: Option<TipBudget>,
+    dedupe_ttl: Duration,
+    max_shared_bundles: usize,
+    leader_proximity_slots: u64,
+}
+
+/// Where the pipeline records what it sent and saves its state.
+struct SearcherSinks {
+    echo_latency: Option<EchoLatencyTracker>,
+    audit_log: Option<AuditLog>,
+    state_snapshot: Option<PathBuf>,
+    state_snapshot_max_age: Duration,
+    state_snapshot_interval: Option<Duration>,
+}
+
+/// The switches that stop or restrict submission.
+struct SearcherSafety {
+    dead_man_switch: DeadManSwitch,
+    observe_only: bool,
+    strict_packet_conversion: bool,
+}
+
+/// The streams the searcher loop consumes, and the leader proximity it publishes for decoding.
+struct SearcherStreams {
+    slot_receiver: Receiver<Slot>,
+    block_receiver: Receiver<rpc_response::Response<RpcBlockUpdate>>,
+    bundle_results_receiver: Receiver<BundleResult>,
+    decoded_tx_receiver: Receiver<DecodedTransactions>,
+    leader_nearby: watch::Sender<bool>,
+}
+
+/// Runs one pipeline: its pubsub and block engine streams, and the searcher loop consuming them.
+async fn run_pipeline(pipeline: String, args: Args, context: PipelineContext) -> Result<()> {
+    let PipelineContext {
+        payer_keypair,
+        auth_keypairs,
+        headers,
+        ..
+    } = &context;
+
+    let expected_cluster = args
+        .cluster
+        .or_else(|| Cluster::from_block_engine_url(&args.block_engine_url));
+    if let Some(expected_cluster) = expected_cluster {
+        check_cluster(&RpcClient::new(args.rpc_url.clone()), expected_cluster).await?;
+        info!("pipeline {pipeline} rpc is on {expected_cluster}");
+    }
+    let mut backrun_accounts = args.backrun_accounts.clone();
+    if !args.pool_pairs.is_empty() {
+        let mut pool_registry = PoolRegistry::new(args.pool_pairs.clone(), args.pool_dexes.clone());
+        let rpc_client = RpcClient::new(args.rpc_url.clone());
+        pool_registry.discover(&rpc_client).await?;
+        for pool in pool_registry.pools() {
+            info!(
+                "pipeline {pipeline} backrunning {} pool {} of {}",
+                pool.layout.dex(),
+                pool.address,
+                pool.pair()
+            );
+        }
+        for pair in &args.pool_pairs {
+            if pool_registry.pools_for(*pair).next().is_none() {
+                warn!("pipeline {pipeline} found no pools for {pair}");
+            }
+        }
+        backrun_accounts.extend(pool_registry.pools().map(|pool| pool.address));
+        pool_registry.report("backrun_pool_registry");
+    }
+    if args.echo_latency {
+        backrun_accounts.push(payer_keypair.pubkey());
+    }
+    let interest_filter = args.mempool_prefilter.then(|| {
+        InterestFilter::new(
+            backrun_accounts
+                .iter()
+                .chain(&args.interest_accounts)
+                .copied(),
+            args.prefilter_false_positive_rate,
+        )
+    });
+
+    let (slot_sender, slot_receiver) = channel(100);
+    let (block_sender, block_receiver) = channel(100);
+    let (bundle_results_sender, bundle_results_receiver) = channel(100);
+    let (pending_tx_sender, pending_tx_receiver) = channel(100);
+    let (decoded_tx_sender, decoded_tx_receiver) = channel(100);
+    let (leader_nearby, leader_nearby_receiver) = watch::channel(false);
+
+    let pubsub_watchdog = WatchdogConfig::new(Duration::from_secs(args.pubsub_stream_timeout_secs));
+    let pubsub_url = args.pubsub_url.clone();
+    tokio::spawn(supervise(
+        "slot_subscribe",
+        pubsub_watchdog.clone(),
+        move |heartbeat| slot_subscribe_loop(pubsub_url.clone(), slot_sender.clone(), heartbeat),
+    ));
+    let pubsub_url = args.pubsub_url.clone();
+    tokio::spawn(supervise(
+        "block_subscribe",
+        pubsub_watchdog,
+        move |heartbeat| block_subscribe_loop(pubsub_url.clone(), block_sender.clone(), heartbeat),
+    ));
+
+    let block_engine_url = args.block_engine_url.clone();
+    let pending_tx_auth_keypairs = auth_keypairs.clone();
+    let pending_tx_headers = headers.clone();
+    tokio::spawn(supervise(
+        "pending_tx",
+        WatchdogConfig::new(Duration::from_secs(args.mempool_stream_timeout_secs)),
+        move |heartbeat| {
+            pending_tx_loop(
+                block_engine_url.clone(),
+                pending_tx_auth_keypairs.clone(),
+                pending_tx_headers.clone(),
+                pending_tx_sender.clone(),
+                backrun_accounts.clone(),
+                heartbeat,
+            )
+        },
+    ));
+    tokio::spawn(decode_pending_tx_loop(
+        pending_tx_receiver,
+        decoded_tx_sender,
+        leader_nearby_receiver,
+        interest_filter,
+        args.decode_threads,
+    ));
+
+    if args.subscribe_bundle_results {
+        let block_engine_url = args.block_engine_url.clone();
+        let bundle_results_auth_keypairs = auth_keypairs.clone();
+        let bundle_results_headers = headers.clone();
+        tokio::spawn(supervise(
+            "bundle_results",
+            WatchdogConfig::new(Duration::from_secs(args.bundle_results_stream_timeout_secs)),
+            move |heartbeat| {
+                bundle_results_loop(
+                    block_engine_url.clone(),
+                    bundle_results_auth_keypairs.clone(),
+                    bundle_results_headers.clone(),
+                    bundle_results_sender.clone(),
+                    heartbeat,
+                )
+            },
         ));
+    }
 
-        if args.subscribe_bundle_results {
-            tokio::spawn(bundle_results_loop(
-                args.block_engine_url.clone(),
-                auth_keypair.clone(),
-                bundle_results_sender,
-            ));
+    let write_lock_limits = (args.max_bundles_per_write_lock.is_some()
+        || args.max_bundles_per_leader_window.is_some())
+    .then(|| WriteLockLimits {
+        max_bundles_per_account: args.max_bundles_per_write_lock.unwrap_or(usize::MAX),
+        account_limits: HashMap::new(),
+        max_bundles_per_window: args.max_bundles_per_leader_window,
+        critical_reserve: args.critical_reserved_bundles,
+        ignored_accounts: HashSet::new(),
+    });
+    let token_guard_config = TokenGuardConfig {
+        denied_mints: args.deny_mint.iter().copied().collect(),
+        denied_programs: args.deny_program.iter().copied().collect(),
+        allowed_mints: (!args.allow_mint.is_empty())
+            .then(|| args.allow_mint.iter().copied().collect()),
+        allowed_programs: (!args.allow_program.is_empty())
+            .then(|| args.allow_program.iter().copied().collect()),
+        trusted_mints: args.trust_mint.iter().copied().collect(),
+        deny_freeze_authority: args.deny_freeze_authority,
+        deny_mutable_metadata: args.deny_mutable_metadata,
+    };
+    let token_guard =
+        (token_guard_config.is_enabled() || args.max_token_risk.is_some()).then(|| {
+            let token_guard = TokenGuard::new(token_guard_config);
+            match args.max_token_risk {
+                Some(max_score) => token_guard.with_risk_limit(
+                    TokenSafetyChecker::new(
+                        RiskWeights::default(),
+                        args.lp_locker.iter().copied().collect(),
+                        Duration::from_secs(args.token_risk_ttl_secs),
+                    ),
+                    max_score,
+                ),
+                None => token_guard,
+            }
+        });
+    let congestion = args.congestion_throttle_rate.map(|throttle_rate| {
+        CongestionMonitor::new(CongestionConfig {
+            window: Duration::from_secs(args.congestion_window_secs),
+            min_samples: args.congestion_min_samples,
+            throttle_rate,
+            pause_rate: args.congestion_pause_rate,
+            resume_rate: args.congestion_resume_rate,
+        })
+    });
+    let echo_latency = args
+        .echo_latency
+        .then(|| EchoLatencyTracker::new(Duration::from_secs(args.echo_max_age_secs)));
+    let audit_log = args.audit_log.as_ref().map(|path| {
+        let audit_log = AuditLog::open(path, args.audit_log_max_bytes, args.audit_log_max_files)
+            .expect("opens audit log");
+        match args.sign_audit_log {
+            true => audit_log
+                .with_signer(auth_keypairs[0].clone())
+                .expect("reads the audit log's last entry"),
+            false => audit_log,
+        }
+    });
+    let dead_man_switch = DeadManSwitch::new(DeadManSwitchConfig {
+        max_consecutive_rejections: args.max_consecutive_rejections,
+        max_consecutive_simulation_failures: args.max_consecutive_simulation_failures,
+        max_balance_drop_lamports: args.max_balance_drop_lamports,
+        balance_window: Duration::from_secs(args.balance_drop_window_secs),
+    });
+    let dead_man_switch = match &args.halt_file {
+        Some(path) => dead_man_switch
+            .with_halt_file(path.clone())
+            .expect("reads the halt file"),
+        None => dead_man_switch,
+    };
+    // SIGUSR1 is the operator ack that resumes submissions after the switch trips
+    let acked_switch = dead_man_switch.clone();
+    let mut ack_signals = signal(SignalKind::user_defined1()).expect("installs SIGUSR1 handler");
+    tokio::spawn(async move {
+        while ack_signals.recv().await.is_some() {
+            acked_switch.acknowledge();
         }
+    });
 
-        let result = run_searcher_loop(
-            args.block_engine_url,
-            auth_keypair,
-            &payer_keypair,
-            args.rpc_url,
-            args.regions,
-            args.message,
-            args.tip_program_id,
-            slot_receiver,
-            block_receiver,
-            bundle_results_receiver,
-            pending_tx_receiver,
+    let race = if args.race_block_engine_urls.is_empty() {
+        None
+    } else {
+        Some(
+            RegionRace::connect(
+                &args.race_block_engine_urls,
+                &auth_keypairs[0],
+                args.race_fanout,
+                RegionHysteresis {
+                    min_lead_slots: args.race_switch_min_lead_slots,
+                    confirmations: args.race_switch_confirmations,
+                },
+                headers.clone(),
+            )
+            .await
+            .expect("connects to race block engines"),
         )
-        .await;
-        error!("searcher loop exited result: {result:?}");
+    };
 
-        Ok(())
-    })
+    let tpu_fallback = match args.inclusion_path {
+        InclusionPath::BlockEngine => None,
+        InclusionPath::TpuFallback => Some(
+            TpuFallbackSender::connect(
+                args.rpc_url.clone(),
+                &args.pubsub_url,
+                args.tpu_fallback_fanout_slots,
+            )
+            .await
+            .expect("connects TPU client"),
+        ),
+    };
+
+    let wasm_strategy = args
+        .wasm_strategy
+        .as_deref()
+        .map(WasmStrategy::load)
+        .transpose()?;
+    let shadow = args
+        .shadow_wasm_strategy
+        .as_deref()
+        .map(WasmStrategy::load)
+        .transpose()?
+        .map(|shadow_strategy| (shadow_strategy, ShadowComparison::new(SHADOW_SETTLE)));
+
+    let tip_budget = args.tip_budget_lamports.map(|max_lamports| {
+        TipBudget::new(
+            max_lamports,
+            Duration::from_secs(args.tip_budget_window_secs),
+        )
+    });
+
+    let config = SearcherConfig {
+        connection: SearcherConnection {
+            block_engine_url: args.block_engine_url,
+            rpc_url: args.rpc_url,
+            regions: args.regions,
+            race,
+            tpu_fallback,
+        },
+        strategy: SearcherStrategy {
+            message: args.message,
+            wasm_strategy,
+            shadow,
+            tip_program_pubkey: args.tip_program_id,
+            slot_assertion: args
+                .slot_assertion_program_id
+                .map(|program_id| (program_id, args.slot_assertion_window)),
+            tip_lamports: args.tip_lamports,
+            priority_class: args.priority_class,
+            tip_multipliers: TipMultipliers {
+                bulk: args.bulk_tip_multiplier,
+                critical: args.critical_tip_multiplier,
+                scale: 1.0,
+            },
+            tip_split: TipSplit::new(args.tip_share, MIN_TIP_LAMPORTS)
+                .expect("validated tip shares"),
+            expected_profit_lamports: args.expected_profit_lamports,
+        },
+        limits: SearcherLimits {
+            ev_gate: args.min_profit_margin_lamports.map(ExpectedValueGate::new),
+            token_guard,
+            simulation_policy: args
+                .require_simulation
+                .then(|| SimulationPolicy::new(args.allowed_simulation_errors.iter().cloned())),
+            write_lock_limits,
+            congestion,
+            opportunity_ttl_slots: args.opportunity_ttl_slots,
+            submit_deadline: args.submit_deadline_ms.map(Duration::from_millis),
+            max_queued_opportunities: args.max_queued_opportunities,
+            tip_budget,
+            dedupe_ttl: Duration::from_secs(args.dedupe_ttl_secs),
+            max_shared_bundles: args.redis_max_bundles,
+            leader_proximity_slots: args.leader_proximity_slots,
+        },
+        sinks: SearcherSinks {
+            echo_latency,
+            audit_log,
+            state_snapshot: args.state_snapshot,
+            state_snapshot_max_age: Duration::from_secs(args.state_snapshot_max_age_secs),
+            state_snapshot_interval: args.state_snapshot_interval_secs.map(Duration::from_secs),
+        },
+        safety: SearcherSafety {
+            dead_man_switch,
+            observe_only: args.observe_only,
+            strict_packet_conversion: args.strict_packet_conversion,
+        },
+    };
+    let streams = SearcherStreams {
+        slot_receiver,
+        block_receiver,
+        bundle_results_receiver,
+        decoded_tx_receiver,
+        leader_nearby,
+    };
+    let admin = context.admin.clone();
+    let result = run_searcher_loop(pipeline.clone(), context, config, streams).await;
+    error!("pipeline {pipeline} searcher loop exited result: {result:?}");
+    admin.stopped(&pipeline);
+
+    Ok(())
 }
@@ -0,0 +1,242 @@
+use std::{collections::HashSet, fs, io, path::Path, str::FromStr};
+
+use jito_searcher_client::{
+    priority::PriorityClass,
+    simulation_policy::AllowedSimulationError,
+    strategy_config::{StrategyConfig, StrategyConfigError, StrategyConfigFile},
+    wasm_strategy::WasmStrategyConfig,
+};
+use serde::Deserialize;
+use solana_sdk::pubkey::Pubkey;
+use thiserror::Error;
+
+use crate::Args;
+
+#[derive(Debug, Error)]
+pub enum PipelineError {
+    #[error("failed to read pipeline config: {0}")]
+    Io(#[from] io::Error),
+    #[error("invalid pipeline config: {0}")]
+    Parse(#[from] serde_json::Error),
+    #[error("pipeline {0:?}: invalid account {1:?}")]
+    InvalidAccount(String, String),
+    #[error("pipeline {0:?}: {1}")]
+    InvalidSimulationError(String, String),
+    #[error("duplicate pipeline name {0:?}")]
+    DuplicateName(String),
+    #[error("no pipelines configured")]
+    Empty,
+}
+
+/// Named pipelines run concurrently by one process, each with its own streams, block engine
+/// connection, dead man switch and tip budget.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PipelineConfig {
+    pub pipelines: Vec<Pipeline>,
+}
+
+/// Wires a mempool source, a strategy, a tip policy and a sink. Anything not set falls back to
+/// the command line flags.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Pipeline {
+    pub name: String,
+    pub source: Source,
+    pub strategy: Option<Strategy>,
+    #[serde(default)]
+    pub tip: TipPolicy,
+    /// Priority class of the pipeline's bundles, see --priority-class.
+    pub priority: Option<PriorityClass>,
+    #[serde(default)]
+    pub sink: Sink,
+    pub budget: Option<Budget>,
+    pub simulation: Option<Simulation>,
+}
+
+/// Mempool transactions the pipeline sees.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Source {
+    /// Write-locked accounts to subscribe to.
+    pub accounts: Vec<String>,
+}
+
+/// The strategy a pipeline runs, tagged with its `kind` and taking the same settings as the
+/// strategy's section in --strategy-config.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(tag = "kind", rename_all = "kebab-case")]
+pub enum Strategy {
+    /// Backruns every transaction with a memo, the only strategy built into this example.
+    MemoBackrun(MemoBackrunConfig),
+    /// Builds bundles with a WASM module, see --wasm-strategy.
+    Wasm(WasmStrategyConfig),
+}
+
+impl Strategy {
+    /// Runs this strategy with its settings applied over the command line's.
+    fn apply(&self, args: &mut Args) {
+        match self {
+            Self::MemoBackrun(config) => {
+                args.wasm_strategy = None;
+                apply_memo_backrun(config, args);
+            }
+            Self::Wasm(config) => apply_wasm(config, args),
+        }
+    }
+}
+
+/// Settings of the memo backrun, its `memo-backrun` section in --strategy-config.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct MemoBackrunConfig {
+    /// Replaces --message if set.
+    pub message: Option<String>,
+}
+
+impl StrategyConfig for MemoBackrunConfig {
+    const SECTION: &'static str = "memo-backrun";
+}
+
+/// Sections --strategy-config may have.
+const STRATEGY_SECTIONS: [&str; 2] = [MemoBackrunConfig::SECTION, WasmStrategyConfig::SECTION];
+
+fn apply_memo_backrun(config: &MemoBackrunConfig, args: &mut Args) {
+    if let Some(message) = &config.message {
+        args.message = message.clone();
+    }
+}
+
+fn apply_wasm(config: &WasmStrategyConfig, args: &mut Args) {
+    args.wasm_strategy = Some(config.path.clone());
+    if config.shadow_path.is_some() {
+        args.shadow_wasm_strategy = config.shadow_path.clone();
+    }
+}
+
+/// The command line arguments with the strategy sections of the file at `path` applied. A
+/// `wasm` section runs the WASM strategy like --wasm-strategy, and pipelines start from these
+/// settings before applying their own.
+pub fn apply_strategy_config(args: &Args, path: &Path) -> Result<Args, StrategyConfigError> {
+    let config = StrategyConfigFile::load(path)?;
+    config.check_sections(&STRATEGY_SECTIONS)?;
+    let mut args = args.clone();
+    if let Some(memo_backrun) = config.section::<MemoBackrunConfig>()? {
+        apply_memo_backrun(&memo_backrun, &mut args);
+    }
+    if let Some(wasm) = config.section::<WasmStrategyConfig>()? {
+        apply_wasm(&wasm, &mut args);
+    }
+    Ok(args)
+}
+
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TipPolicy {
+    pub lamports: Option<u64>,
+    pub expected_profit_lamports: Option<u64>,
+    pub min_profit_margin_lamports: Option<i64>,
+}
+
+/// Where bundles go.
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Sink {
+    pub block_engine_url: Option<String>,
+    pub regions: Option<Vec<String>>,
+}
+
+/// Lamports the pipeline may tip per window.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Budget {
+    pub max_tip_lamports: u64,
+    #[serde(default = "default_budget_window_secs")]
+    pub window_secs: u64,
+}
+
+/// Simulation the pipeline's bundles must pass, see --require-simulation. A pipeline can require
+/// simulation when the flag isn't set, but can't opt out when it is.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Simulation {
+    #[serde(default)]
+    pub required: bool,
+    /// Replaces --allowed-simulation-errors if set.
+    pub allowed_errors: Option<Vec<String>>,
+}
+
+fn default_budget_window_secs() -> u64 {
+    3_600
+}
+
+impl PipelineConfig {
+    pub fn load(path: &Path) -> Result<Self, PipelineError> {
+        let config: Self = serde_json::from_slice(&fs::read(path)?)?;
+        if config.pipelines.is_empty() {
+            return Err(PipelineError::Empty);
+        }
+        let mut names = HashSet::new();
+        for pipeline in &config.pipelines {
+            if !names.insert(pipeline.name.as_str()) {
+                return Err(PipelineError::DuplicateName(pipeline.name.clone()));
+            }
+        }
+        Ok(config)
+    }
+}
+
+impl Pipeline {
+    /// The command line arguments with this pipeline's settings applied.
+    pub fn apply(&self, args: &Args) -> Result<Args, PipelineError> {
+        let mut args = args.clone();
+        args.backrun_accounts = self
+            .source
+            .accounts
+            .iter()
+            .map(|account| {
+                Pubkey::from_str(account)
+                    .map_err(|_| PipelineError::InvalidAccount(self.name.clone(), account.clone()))
+            })
+            .collect::<Result<_, _>>()?;
+        if let Some(strategy) = &self.strategy {
+            strategy.apply(&mut args);
+        }
+        if let Some(lamports) = self.tip.lamports {
+            args.tip_lamports = lamports;
+        }
+        if let Some(priority) = self.priority {
+            args.priority_class = priority;
+        }
+        if self.tip.expected_profit_lamports.is_some() {
+            args.expected_profit_lamports = self.tip.expected_profit_lamports;
+        }
+        if self.tip.min_profit_margin_lamports.is_some() {
+            args.min_profit_margin_lamports = self.tip.min_profit_margin_lamports;
+        }
+        if let Some(block_engine_url) = &self.sink.block_engine_url {
+            args.block_engine_url = block_engine_url.clone();
+        }
+        if let Some(regions) = &self.sink.regions {
+            args.regions = regions.clone();
+        }
+        if let Some(budget) = &self.budget {
+            args.tip_budget_lamports = Some(budget.max_tip_lamports);
+            args.tip_budget_window_secs = budget.window_secs;
+        }
+        if let Some(simulation) = &self.simulation {
+            args.require_simulation |= simulation.required;
+            if let Some(allowed_errors) = &simulation.allowed_errors {
+                args.allowed_simulation_errors = allowed_errors
+                    .iter()
+                    .map(|error| {
+                        AllowedSimulationError::from_str(error).map_err(|e| {
+                            PipelineError::InvalidSimulationError(self.name.clone(), e)
+                        })
+                    })
+                    .collect::<Result<_, _>>()?;
+            }
+        }
+        Ok(args)
+    }
+}
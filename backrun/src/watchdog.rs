@@ -0,0 +1,126 @@
+use std::{
+    cmp::min,
+    future::Future,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use log::{info, warn};
+use solana_metrics::{datapoint_error, datapoint_info};
+use tokio::time::sleep;
+
+/// Records when a long-running stream last delivered a message.
+/// Cloned into the stream's event loop, which calls [`Heartbeat::beat`] on every message.
+#[derive(Clone)]
+pub struct Heartbeat {
+    last_message_ms: Arc<AtomicU64>,
+}
+
+impl Heartbeat {
+    pub fn new() -> Self {
+        Self {
+            last_message_ms: Arc::new(AtomicU64::new(now_ms())),
+        }
+    }
+
+    pub fn beat(&self) {
+        self.last_message_ms.store(now_ms(), Ordering::Relaxed);
+    }
+
+    /// How long the stream has been silent.
+    pub fn silence(&self) -> Duration {
+        Duration::from_millis(now_ms().saturating_sub(self.last_message_ms.load(Ordering::Relaxed)))
+    }
+}
+
+impl Default for Heartbeat {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct WatchdogConfig {
+    /// A stream silent for longer than this gets torn down and recreated.
+    pub silence_threshold: Duration,
+    /// How often the supervisor checks the heartbeat.
+    pub check_interval: Duration,
+    /// Backoff before the first restart, doubled on each consecutive restart.
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+}
+
+impl WatchdogConfig {
+    pub fn new(silence_threshold: Duration) -> Self {
+        Self {
+            silence_threshold,
+            check_interval: Duration::from_secs(1),
+            initial_backoff: Duration::from_secs(1),
+            max_backoff: Duration::from_secs(60),
+        }
+    }
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as u64
+}
+
+// supervises a stream loop, restarting it whenever it exits or goes silent for longer than the
+// configured threshold. consecutive restarts back off exponentially; the backoff resets once the
+// stream has stayed healthy for a full silence threshold.
+pub async fn supervise<F, Fut>(name: &'static str, config: WatchdogConfig, mut spawn_stream: F)
+where
+    F: FnMut(Heartbeat) -> Fut,
+    Fut: Future<Output = ()> + Send + 'static,
+{
+    let mut restarts: u64 = 0;
+    let mut backoff = config.initial_backoff;
+
+    loop {
+        let heartbeat = Heartbeat::new();
+        let mut handle = tokio::spawn(spawn_stream(heartbeat.clone()));
+        let mut healthy_for = Duration::ZERO;
+
+        let reason = loop {
+            sleep(config.check_interval).await;
+            if handle.is_finished() {
+                break "exited";
+            }
+            if heartbeat.silence() > config.silence_threshold {
+                handle.abort();
+                break "silent";
+            }
+            healthy_for += config.check_interval;
+            if healthy_for >= config.silence_threshold && backoff != config.initial_backoff {
+                info!("{name} stream healthy again, resetting watchdog backoff");
+                backoff = config.initial_backoff;
+            }
+        };
+        // make sure an aborted task is gone before the replacement connects
+        let _ = (&mut handle).await;
+
+        restarts += 1;
+        warn!(
+            "{name} stream {reason} (silent for {:?}), restarting in {backoff:?}",
+            heartbeat.silence()
+        );
+        datapoint_error!(
+            "stream_watchdog_restart",
+            ("stream", name, String),
+            ("reason", reason, String),
+            ("restarts", restarts, i64),
+            ("silence_ms", heartbeat.silence().as_millis(), i64),
+            ("backoff_ms", backoff.as_millis(), i64),
+        );
+
+        sleep(backoff).await;
+        backoff = min(backoff * 2, config.max_backoff);
+        datapoint_info!("stream_watchdog_respawn", ("stream", name, String));
+    }
+}
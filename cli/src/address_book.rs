@@ -0,0 +1,149 @@
+use std::{
+    collections::BTreeMap,
+    env, fs, io,
+    path::{Path, PathBuf},
+    str::FromStr,
+    sync::OnceLock,
+};
+
+use solana_sdk::pubkey::Pubkey;
+
+use crate::error::{CliError, CliResult};
+
+/// Table of the config file holding the address book.
+const RECIPIENTS_TABLE: &str = "recipients";
+
+static ADDRESS_BOOK: OnceLock<AddressBook> = OnceLock::new();
+
+/// Named addresses from the `recipients` table of the config file, so a pubkey flag can be given
+/// as `@treasury` instead of a pasted base58 key:
+///
+/// ```toml
+/// [recipients]
+/// treasury = "GKxp..."
+/// ```
+#[derive(Debug, Default)]
+pub struct AddressBook {
+    path: PathBuf,
+    /// The whole config file, so saving keeps tables other than `recipients`.
+    config: toml::Table,
+    addresses: BTreeMap<String, Pubkey>,
+}
+
+impl AddressBook {
+    /// Reads the address book from `path`, or from `~/.config/jito-searcher/config.toml` if not
+    /// given. A missing file is an empty address book.
+    pub fn load(path: Option<&Path>) -> CliResult<Self> {
+        let path = match path {
+            Some(path) => path.to_path_buf(),
+            None => default_config_path(),
+        };
+        let config: toml::Table = match fs::read_to_string(&path) {
+            Ok(contents) => toml::from_str(&contents)
+                .map_err(|e| CliError::Config(format!("{}: {e}", path.display())))?,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => toml::Table::new(),
+            Err(e) => return Err(e.into()),
+        };
+        let mut addresses = BTreeMap::new();
+        if let Some(recipients) = config.get(RECIPIENTS_TABLE) {
+            let recipients = recipients.as_table().ok_or_else(|| {
+                CliError::Config(format!(
+                    "{}: {RECIPIENTS_TABLE} must be a table",
+                    path.display()
+                ))
+            })?;
+            for (name, address) in recipients {
+                let address = address
+                    .as_str()
+                    .and_then(|address| Pubkey::from_str(address).ok())
+                    .ok_or_else(|| {
+                        CliError::Config(format!(
+                            "{}: {RECIPIENTS_TABLE}.{name} is not a base58 pubkey",
+                            path.display()
+                        ))
+                    })?;
+                addresses.insert(name.clone(), address);
+            }
+        }
+        Ok(Self {
+            path,
+            config,
+            addresses,
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn addresses(&self) -> &BTreeMap<String, Pubkey> {
+        &self.addresses
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Pubkey> {
+        self.addresses.get(name)
+    }
+
+    /// Adds or replaces a named address. Names are limited to letters, digits, `-` and `_` so
+    /// they can't be confused with a key or a flag.
+    pub fn insert(&mut self, name: &str, address: Pubkey) -> CliResult<Option<Pubkey>> {
+        if name.is_empty()
+            || !name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+        {
+            return Err(CliError::Config(format!(
+                "invalid address name {name:?}, use letters, digits, - and _"
+            )));
+        }
+        Ok(self.addresses.insert(name.to_string(), address))
+    }
+
+    pub fn remove(&mut self, name: &str) -> Option<Pubkey> {
+        self.addresses.remove(name)
+    }
+
+    /// Writes the address book back to the config file. Other tables are kept, comments aren't.
+    pub fn save(&mut self) -> CliResult<()> {
+        let recipients: toml::Table = self
+            .addresses
+            .iter()
+            .map(|(name, address)| (name.clone(), toml::Value::String(address.to_string())))
+            .collect();
+        self.config
+            .insert(RECIPIENTS_TABLE.to_string(), toml::Value::Table(recipients));
+        let contents = toml::to_string_pretty(&self.config)
+            .map_err(|e| CliError::Other(format!("failed to serialize config: {e}")))?;
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&self.path, contents)?;
+        Ok(())
+    }
+
+    /// Makes this the address book `@name` arguments resolve against. Must be called before the
+    /// arguments are parsed.
+    pub fn install(self) {
+        ADDRESS_BOOK.set(self).expect("address book installed once");
+    }
+}
+
+fn default_config_path() -> PathBuf {
+    let home = env::var_os("HOME").unwrap_or_default();
+    PathBuf::from(home).join(".config/jito-searcher/config.toml")
+}
+
+/// Value parser for pubkey flags: a base58 pubkey, or `@name` for an entry of the installed
+/// address book.
+pub fn parse_address(arg: &str) -> Result<Pubkey, String> {
+    let Some(name) = arg.strip_prefix('@') else {
+        return Pubkey::from_str(arg).map_err(|e| format!("invalid pubkey {arg}: {e}"));
+    };
+    let book = ADDRESS_BOOK.get().ok_or("address book not loaded")?;
+    book.get(name).copied().ok_or_else(|| {
+        format!(
+            "no address named {name} in {}, see the addresses command",
+            book.path().display()
+        )
+    })
+}
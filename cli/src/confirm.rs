@@ -0,0 +1,66 @@
+use std::io::{self, BufRead, IsTerminal, Write};
+
+use jito_searcher_client::price_feed::format_lamports;
+use solana_sdk::pubkey::Pubkey;
+
+use crate::error::{CliError, CliResult};
+
+/// What a command is about to spend, shown before asking to go ahead.
+pub struct Spend<'a> {
+    pub action: &'a str,
+    pub recipient: Option<&'a Pubkey>,
+    pub amount_lamports: u64,
+    pub tip_lamports: u64,
+}
+
+impl Spend<'_> {
+    fn total_lamports(&self) -> u64 {
+        self.amount_lamports.saturating_add(self.tip_lamports)
+    }
+}
+
+/// Asks on the terminal before spending more than `threshold_lamports`, unless `assume_yes` is
+/// set. Without a terminal to ask on it refuses instead, so a script can't spend a large amount
+/// by accident either.
+pub struct SpendConfirmation {
+    pub threshold_lamports: u64,
+    pub assume_yes: bool,
+}
+
+impl SpendConfirmation {
+    pub fn confirm(&self, spend: &Spend) -> CliResult<()> {
+        if self.assume_yes || spend.total_lamports() <= self.threshold_lamports {
+            return Ok(());
+        }
+        let mut stderr = io::stderr();
+        writeln!(stderr, "{}:", spend.action)?;
+        if let Some(recipient) = spend.recipient {
+            writeln!(stderr, "  recipient: {recipient}")?;
+        }
+        writeln!(
+            stderr,
+            "  amount:    {}",
+            format_lamports(spend.amount_lamports, None)
+        )?;
+        writeln!(
+            stderr,
+            "  tip:       {}",
+            format_lamports(spend.tip_lamports, None)
+        )?;
+        if !io::stdin().is_terminal() {
+            return Err(CliError::Config(format!(
+                "spending {} is above --confirm-above-lamports, pass --yes to go ahead without \
+                 a prompt",
+                format_lamports(spend.total_lamports(), None)
+            )));
+        }
+        write!(stderr, "Continue? [y/N] ")?;
+        stderr.flush()?;
+        let mut answer = String::new();
+        io::stdin().lock().read_line(&mut answer)?;
+        match answer.trim().to_ascii_lowercase().as_str() {
+            "y" | "yes" => Ok(()),
+            _ => Err(CliError::Other("cancelled".into())),
+        }
+    }
+}
@@ -0,0 +1,243 @@
+use std::{
+    path::Path,
+    sync::Arc,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use futures_util::StreamExt;
+use jito_searcher_client::{get_searcher_client, BlockEngineConnectionError};
+use solana_client::{
+    nonblocking::{pubsub_client::PubsubClient, rpc_client::RpcClient},
+    rpc_config::RpcSimulateTransactionConfig,
+};
+use solana_sdk::{
+    commitment_config::CommitmentConfig,
+    message::Message,
+    native_token::lamports_to_sol,
+    pubkey::Pubkey,
+    signature::{read_keypair_file, Signer},
+    transaction::Transaction,
+};
+use spl_memo::build_memo;
+use tokio::time::timeout;
+use tonic::Code;
+
+pub struct DoctorConfig<'a> {
+    pub block_engine_url: &'a str,
+    pub keypair_path: Option<&'a Path>,
+    pub rpc_url: String,
+    pub ws_url: Option<String>,
+    pub payer: Option<&'a Path>,
+    pub min_payer_balance: u64,
+    pub max_clock_skew: Duration,
+}
+
+enum CheckOutcome {
+    Passed(String),
+    Skipped(String),
+    Failed { problem: String, fix: String },
+}
+
+impl CheckOutcome {
+    fn failed(problem: impl ToString, fix: impl ToString) -> Self {
+        CheckOutcome::Failed {
+            problem: problem.to_string(),
+            fix: fix.to_string(),
+        }
+    }
+}
+
+/// Runs every setup check, printing the outcome and a suggested fix for failures.
+/// Returns the number of failed checks.
+pub async fn run_doctor(config: DoctorConfig<'_>) -> usize {
+    let rpc_client = RpcClient::new_with_commitment(config.rpc_url, CommitmentConfig::confirmed());
+    let (auth_keypair_pubkey, auth) =
+        check_auth_keypair(config.block_engine_url, config.keypair_path).await;
+    let outcomes = [
+        ("block engine auth", auth),
+        (
+            "payer balance",
+            check_payer_balance(&rpc_client, config.payer, config.min_payer_balance).await,
+        ),
+        (
+            "rpc simulateTransaction",
+            check_simulate(&rpc_client, auth_keypair_pubkey).await,
+        ),
+        ("rpc pubsub", check_pubsub(config.ws_url.as_deref()).await),
+        (
+            "clock skew",
+            check_clock_skew(&rpc_client, config.max_clock_skew).await,
+        ),
+    ];
+
+    let mut failed = 0;
+    for (name, outcome) in outcomes {
+        match outcome {
+            CheckOutcome::Passed(detail) => println!("[ok]   {name}: {detail}"),
+            CheckOutcome::Skipped(reason) => println!("[skip] {name}: {reason}"),
+            CheckOutcome::Failed { problem, fix } => {
+                failed += 1;
+                println!("[fail] {name}: {problem}");
+                println!("       fix: {fix}");
+            }
+        }
+    }
+    failed
+}
+
+async fn check_auth_keypair(
+    block_engine_url: &str,
+    keypair_path: Option<&Path>,
+) -> (Option<Pubkey>, CheckOutcome) {
+    let Some(keypair_path) = keypair_path else {
+        return (
+            None,
+            CheckOutcome::Skipped("--keypair-path not set, only --transport json-rpc works".into()),
+        );
+    };
+    let keypair = match read_keypair_file(keypair_path) {
+        Ok(keypair) => Arc::new(keypair),
+        Err(e) => {
+            return (
+                None,
+                CheckOutcome::failed(
+                    format!("can't read {}: {e}", keypair_path.display()),
+                    "point --keypair-path at a JSON keypair file, or create one with `solana-keygen new`",
+                ),
+            )
+        }
+    };
+    let pubkey = keypair.pubkey();
+    let outcome = match get_searcher_client(block_engine_url, &keypair).await {
+        Ok(_) => CheckOutcome::Passed(format!("{pubkey} is approved")),
+        Err(BlockEngineConnectionError::ClientError(status))
+            if matches!(
+                status.code(),
+                Code::Unauthenticated | Code::PermissionDenied
+            ) =>
+        {
+            CheckOutcome::failed(
+                format!("block engine rejected {pubkey}: {}", status.message()),
+                "request access for this public key: https://jito-labs.gitbook.io/mev/searcher-resources/getting-started#block-engine-api-key",
+            )
+        }
+        Err(e) => CheckOutcome::failed(
+            format!("can't connect to {block_engine_url}: {e}"),
+            "check --block-engine-url against https://jito-labs.gitbook.io/mev/searcher-resources/block-engine#connection-details",
+        ),
+    };
+    (Some(pubkey), outcome)
+}
+
+async fn check_payer_balance(
+    rpc_client: &RpcClient,
+    payer: Option<&Path>,
+    min_balance: u64,
+) -> CheckOutcome {
+    let Some(payer) = payer else {
+        return CheckOutcome::Skipped("pass --payer to check it".into());
+    };
+    let payer = match read_keypair_file(payer) {
+        Ok(payer) => payer.pubkey(),
+        Err(e) => {
+            return CheckOutcome::failed(
+                format!("can't read {}: {e}", payer.display()),
+                "point --payer at a JSON keypair file",
+            )
+        }
+    };
+    match rpc_client.get_balance(&payer).await {
+        Ok(balance) if balance >= min_balance => {
+            CheckOutcome::Passed(format!("{payer} has {} SOL", lamports_to_sol(balance)))
+        }
+        Ok(balance) => CheckOutcome::failed(
+            format!(
+                "{payer} has {} SOL, less than the {} SOL minimum",
+                lamports_to_sol(balance),
+                lamports_to_sol(min_balance)
+            ),
+            format!(
+                "transfer at least {} SOL to {payer}",
+                lamports_to_sol(min_balance - balance)
+            ),
+        ),
+        Err(e) => CheckOutcome::failed(
+            format!("can't read balance: {e}"),
+            "check --rpc-url is reachable",
+        ),
+    }
+}
+
+async fn check_simulate(rpc_client: &RpcClient, fee_payer: Option<Pubkey>) -> CheckOutcome {
+    let fee_payer = fee_payer.unwrap_or_else(Pubkey::new_unique);
+    let message = Message::new(&[build_memo(b"doctor", &[])], Some(&fee_payer));
+    let tx = Transaction::new_unsigned(message);
+    let config = RpcSimulateTransactionConfig {
+        sig_verify: false,
+        replace_recent_blockhash: true,
+        ..RpcSimulateTransactionConfig::default()
+    };
+    // only the method's availability matters, the transaction itself may fail
+    match rpc_client.simulate_transaction_with_config(&tx, config).await {
+        Ok(_) => CheckOutcome::Passed("supported".into()),
+        Err(e) => CheckOutcome::failed(
+            format!("simulation request failed: {e}"),
+            "use an RPC node that serves simulateTransaction, public and rate-limited nodes often don't",
+        ),
+    }
+}
+
+async fn check_pubsub(ws_url: Option<&str>) -> CheckOutcome {
+    let Some(ws_url) = ws_url else {
+        return CheckOutcome::Skipped("pass --ws-url to check it".into());
+    };
+    let fix = "enable the RPC node's websocket port (usually rpc port + 1) and pass it as --ws-url";
+    let pubsub_client = match PubsubClient::new(ws_url).await {
+        Ok(pubsub_client) => pubsub_client,
+        Err(e) => return CheckOutcome::failed(format!("can't connect to {ws_url}: {e}"), fix),
+    };
+    let (mut slots, unsubscribe) = match pubsub_client.slot_subscribe().await {
+        Ok(subscription) => subscription,
+        Err(e) => return CheckOutcome::failed(format!("slotSubscribe failed: {e}"), fix),
+    };
+    let outcome = match timeout(Duration::from_secs(5), slots.next()).await {
+        Ok(Some(slot_info)) => CheckOutcome::Passed(format!("received slot {}", slot_info.slot)),
+        Ok(None) => CheckOutcome::failed("slot subscription closed", fix),
+        Err(_) => CheckOutcome::failed("no slot notification within 5s", fix),
+    };
+    drop(slots);
+    unsubscribe().await;
+    outcome
+}
+
+async fn check_clock_skew(rpc_client: &RpcClient, max_skew: Duration) -> CheckOutcome {
+    let block_time = match rpc_client.get_slot().await {
+        Ok(slot) => rpc_client.get_block_time(slot).await,
+        Err(e) => Err(e),
+    };
+    let block_time = match block_time {
+        Ok(block_time) => block_time,
+        Err(e) => {
+            return CheckOutcome::failed(
+                format!("can't read the latest block time: {e}"),
+                "check --rpc-url is reachable",
+            )
+        }
+    };
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs() as i64;
+    let skew = now - block_time;
+    if skew.unsigned_abs() <= max_skew.as_secs() {
+        CheckOutcome::Passed(format!("{skew}s from the latest block time"))
+    } else {
+        CheckOutcome::failed(
+            format!(
+                "local clock is {skew}s off the latest block time, max is {}s",
+                max_skew.as_secs()
+            ),
+            "sync the system clock with NTP, e.g. enable systemd-timesyncd or chrony",
+        )
+    }
+}
@@ -0,0 +1,266 @@
+use std::io;
+
+use jito_searcher_client::{
+    balance_watch::BalanceWatchError, bundle_builder::BundleBuildError,
+    bundle_store::BundleStoreError, clock_offset::ClockCheckError, cluster::ClusterCheckError,
+    config_validation::ConfigErrors, decoder_plugin::DecoderPluginError,
+    gateway_clients::GatewayClientsError, idl_decoder::IdlError,
+    instruction_template::TemplateError, json_rpc::JsonRpcError, pool_registry::PoolRegistryError,
+    record_sink::RecordSinkError, recording::RecordingError, sweep::SweepError,
+    tip_payment::TipSplitError, token_fees::TokenFeeError, transaction_decoder::DecodeError,
+    BlockEngineConnectionError, BundleRejectionError,
+};
+use solana_client::client_error::ClientError;
+use thiserror::Error;
+use tonic::{transport, Code, Status};
+
+/// Exit codes returned by every subcommand. Scripts depend on these values, so existing codes
+/// must never be renumbered.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[repr(i32)]
+pub enum ExitCode {
+    Ok = 0,
+    /// Anything not covered below.
+    Error = 1,
+    /// The block engine rejected the auth keypair.
+    AuthFailure = 2,
+    /// The block engine rejected the bundle: lost the auction, failed simulation or errored.
+    BundleRejected = 3,
+    /// A bundle or request didn't complete in time.
+    Timeout = 4,
+    /// Invalid arguments, keypair files or input data.
+    Config = 5,
+    /// The RPC node returned an error.
+    Rpc = 6,
+    /// The block engine couldn't be reached or returned an error.
+    BlockEngine = 7,
+    /// Reading or writing a local file failed.
+    Io = 8,
+}
+
+#[derive(Debug, Error)]
+pub enum CliError {
+    #[error("authentication failed: {0}")]
+    AuthFailure(Status),
+    #[error("bundle rejected: {0}")]
+    BundleRejected(BundleRejectionError),
+    #[error("timed out: {0}")]
+    Timeout(String),
+    #[error("config error: {0}")]
+    Config(String),
+    #[error("rpc error: {0}")]
+    Rpc(#[from] ClientError),
+    #[error("block engine error: {0}")]
+    BlockEngine(Status),
+    #[error("block engine connection error: {0}")]
+    Transport(#[from] transport::Error),
+    #[error("block engine json-rpc error: {0}")]
+    JsonRpc(#[from] JsonRpcError),
+    #[error("io error: {0}")]
+    Io(#[from] io::Error),
+    #[error("{0}")]
+    Other(String),
+}
+
+pub type CliResult<T> = Result<T, CliError>;
+
+impl CliError {
+    pub fn exit_code(&self) -> ExitCode {
+        match self {
+            CliError::AuthFailure(_) => ExitCode::AuthFailure,
+            CliError::BundleRejected(_) => ExitCode::BundleRejected,
+            CliError::Timeout(_) => ExitCode::Timeout,
+            CliError::Config(_) => ExitCode::Config,
+            CliError::Rpc(_) => ExitCode::Rpc,
+            CliError::BlockEngine(_) | CliError::Transport(_) | CliError::JsonRpc(_) => {
+                ExitCode::BlockEngine
+            }
+            CliError::Io(_) => ExitCode::Io,
+            CliError::Other(_) => ExitCode::Error,
+        }
+    }
+}
+
+impl From<Status> for CliError {
+    fn from(status: Status) -> Self {
+        match status.code() {
+            Code::Unauthenticated | Code::PermissionDenied => CliError::AuthFailure(status),
+            Code::DeadlineExceeded => CliError::Timeout(status.message().to_string()),
+            _ => CliError::BlockEngine(status),
+        }
+    }
+}
+
+impl From<BlockEngineConnectionError> for CliError {
+    fn from(e: BlockEngineConnectionError) -> Self {
+        match e {
+            BlockEngineConnectionError::TransportError(e) => e.into(),
+            BlockEngineConnectionError::ClientError(status) => status.into(),
+        }
+    }
+}
+
+impl From<BundleRejectionError> for CliError {
+    fn from(e: BundleRejectionError) -> Self {
+        match e {
+            BundleRejectionError::NotLanded => CliError::Timeout(e.to_string()),
+            e => CliError::BundleRejected(e),
+        }
+    }
+}
+
+impl From<BundleBuildError> for CliError {
+    fn from(e: BundleBuildError) -> Self {
+        CliError::Config(e.to_string())
+    }
+}
+
+impl From<ConfigErrors> for CliError {
+    fn from(e: ConfigErrors) -> Self {
+        CliError::Config(e.to_string())
+    }
+}
+
+impl From<TipSplitError> for CliError {
+    fn from(e: TipSplitError) -> Self {
+        CliError::Config(e.to_string())
+    }
+}
+
+impl From<DecodeError> for CliError {
+    fn from(e: DecodeError) -> Self {
+        CliError::Config(e.to_string())
+    }
+}
+
+impl From<ClusterCheckError> for CliError {
+    fn from(e: ClusterCheckError) -> Self {
+        match e {
+            ClusterCheckError::Rpc(e) => e.into(),
+            e => CliError::Config(e.to_string()),
+        }
+    }
+}
+
+impl From<ClockCheckError> for CliError {
+    fn from(e: ClockCheckError) -> Self {
+        match e {
+            ClockCheckError::Io(e) => e.into(),
+            ClockCheckError::Rpc(e) => e.into(),
+            ClockCheckError::Timeout(..) => CliError::Timeout(e.to_string()),
+            e => CliError::Other(e.to_string()),
+        }
+    }
+}
+
+impl From<PoolRegistryError> for CliError {
+    fn from(e: PoolRegistryError) -> Self {
+        match e {
+            PoolRegistryError::Rpc(e) => e.into(),
+        }
+    }
+}
+
+impl From<BundleStoreError> for CliError {
+    fn from(e: BundleStoreError) -> Self {
+        match e {
+            BundleStoreError::Io(e) => e.into(),
+            BundleStoreError::Rpc(e) => e.into(),
+            e => CliError::Config(e.to_string()),
+        }
+    }
+}
+
+impl From<RecordSinkError> for CliError {
+    fn from(e: RecordSinkError) -> Self {
+        match e {
+            RecordSinkError::Io(e) => e.into(),
+            RecordSinkError::MissingCredentials(_) => CliError::Config(e.to_string()),
+            e => CliError::Other(e.to_string()),
+        }
+    }
+}
+
+impl From<BalanceWatchError> for CliError {
+    fn from(e: BalanceWatchError) -> Self {
+        match e {
+            BalanceWatchError::Io(e) => e.into(),
+            e => CliError::Config(e.to_string()),
+        }
+    }
+}
+
+impl From<GatewayClientsError> for CliError {
+    fn from(e: GatewayClientsError) -> Self {
+        match e {
+            GatewayClientsError::Io(e) => e.into(),
+            e => CliError::Config(e.to_string()),
+        }
+    }
+}
+
+impl From<TemplateError> for CliError {
+    fn from(e: TemplateError) -> Self {
+        match e {
+            TemplateError::Io(e) => e.into(),
+            e => CliError::Config(e.to_string()),
+        }
+    }
+}
+
+impl From<SweepError> for CliError {
+    fn from(e: SweepError) -> Self {
+        match e {
+            SweepError::Rpc(e) | SweepError::TokenFee(TokenFeeError::Rpc(e)) => e.into(),
+            e => CliError::Config(e.to_string()),
+        }
+    }
+}
+
+impl From<RecordingError> for CliError {
+    fn from(e: RecordingError) -> Self {
+        match e {
+            RecordingError::Io(e) => e.into(),
+            e => CliError::Config(e.to_string()),
+        }
+    }
+}
+
+impl From<IdlError> for CliError {
+    fn from(e: IdlError) -> Self {
+        match e {
+            IdlError::Rpc(e) => e.into(),
+            e => CliError::Config(e.to_string()),
+        }
+    }
+}
+
+impl From<DecoderPluginError> for CliError {
+    fn from(e: DecoderPluginError) -> Self {
+        match e {
+            DecoderPluginError::Idl(e) => e.into(),
+            e => CliError::Config(e.to_string()),
+        }
+    }
+}
+
+impl From<Box<dyn std::error::Error>> for CliError {
+    fn from(e: Box<dyn std::error::Error>) -> Self {
+        let e = match e.downcast::<BundleRejectionError>() {
+            Ok(e) => return (*e).into(),
+            Err(e) => e,
+        };
+        let e = match e.downcast::<Status>() {
+            Ok(status) => return (*status).into(),
+            Err(e) => e,
+        };
+        let e = match e.downcast::<JsonRpcError>() {
+            Ok(e) => return (*e).into(),
+            Err(e) => e,
+        };
+        match e.downcast::<ClientError>() {
+            Ok(e) => (*e).into(),
+            Err(e) => CliError::Other(e.to_string()),
+        }
+    }
+}
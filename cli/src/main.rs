@@ -1,33 +1,125 @@
-use std::{env, path::PathBuf, sync::Arc, time::Duration};
+// tonic's Status and the RPC client's ClientError are both large, and errors wrap them as is
+#![allow(clippy::result_large_err)]
 
-use clap::{Parser, Subcommand};
+mod address_book;
+mod confirm;
+mod doctor;
+mod error;
+
+use std::{
+    cmp::Reverse,
+    collections::{BTreeMap, HashMap, HashSet},
+    env,
+    fs::{self, File},
+    io::{BufReader, BufWriter, Write},
+    net::SocketAddr,
+    path::{Path, PathBuf},
+    process,
+    str::FromStr,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use chrono::{NaiveDate, NaiveTime, Utc};
+use clap::{CommandFactory, Parser, Subcommand, ValueEnum};
 use env_logger::TimestampPrecision;
 use futures_util::StreamExt;
 use jito_protos::{
     convert::versioned_tx_from_packet,
     searcher::{
         mempool_subscription, searcher_service_client::SearcherServiceClient,
-        ConnectedLeadersRegionedRequest, GetTipAccountsRequest, MempoolSubscription,
-        NextScheduledLeaderRequest, PendingTxNotification, ProgramSubscriptionV0,
-        SubscribeBundleResultsRequest, WriteLockedAccountSubscriptionV0,
+        ConnectedLeadersRegionedRequest, ConnectedLeadersRequest, GetRegionsRequest,
+        GetTipAccountsRequest, MempoolSubscription, NextScheduledLeaderRequest,
+        NextScheduledLeaderResponse, ProgramSubscriptionV0, SubscribeBundleResultsRequest,
+        WriteLockedAccountSubscriptionV0,
     },
+    strategy_gateway::strategy_gateway_server::StrategyGatewayServer,
 };
+#[cfg(unix)]
+use jito_searcher_client::uds_ipc;
 use jito_searcher_client::{
-    get_searcher_client, send_bundle_with_confirmation, token_authenticator::ClientInterceptor,
+    account_setup::{associated_token_accounts, create_missing_accounts, plan_account_setup},
+    amount::parse_lamports,
+    audit_log::{audit_log_files, read_audit_log, read_audit_rejections, verify_audit_chain},
+    balance_watch::{AlertKind, BalanceWatchConfig, BalanceWatcher},
+    bench::run_hot_path,
+    block_position::PositionStats,
+    bundle_builder::{BundleBuilder, TransactionSpec},
+    bundle_expiry::PreSignedBundle,
+    bundle_simulation::simulate_bundle_conflicts,
+    bundle_store::BundleStore,
+    canary::{send_canary, CanaryConfig, CanaryOutcome, CanaryProbe},
+    capture_window::{CaptureWindow, SlotRange},
+    client_headers::ClientHeaders,
+    clock_offset::{measure_clock_offset, ntp_sample, rpc_sample, ClockOffsetEstimate},
+    cluster::{check_cluster, Cluster},
+    compute_profile::{profile_bundle, TransactionProfile},
+    config_validation::{ConfigValidator, MIN_TIP_LAMPORTS},
+    daily_report::{DailyReport, ReportFormat},
+    decoder_plugin::{DecoderRegistry, PluginSpec},
+    efficiency::{landed_bundle_efficiency, EfficiencySummary},
+    gateway_clients::GatewayClients,
+    get_searcher_client_with_headers,
+    grpc_trace::{self, trace_message, traced},
+    instruction_template::{BundleTemplate, Placeholders},
+    json_rpc::JsonRpcBundleClient,
+    landing_model::{tip_lamports, LandingModel, LandingObservation},
+    leader_stats::{self, LandingStats},
+    load_shedding::QueueLimits,
+    pool_registry::{Dex, PoolRegistry, TokenPair},
+    post_conditions::{report_violations, verify_post_conditions, PostCondition},
+    price_feed::{format_lamports, SolPriceFeed},
+    record_sink::{RecordSink, SinkUri},
+    recording::{convert, Recorded, RecordingFormat, RecordingReader},
+    rpc_fallback::{
+        send_transactions_via_rpc, with_priority_fee, AuctionLossTracker, RpcFallbackConfig,
+    },
+    rpc_latency::RpcLatencyTracker,
+    runtime_config::RuntimeConfig,
+    send_bundle_no_wait, send_bundle_with_confirmation,
+    strategy_gateway::{GatewayConfig, StrategyGatewayService},
+    sweep::{execute_sweep, plan_sweep, SweepConfig, SweptToken, TokenFloat},
+    tip_monitor::TipAccountMonitor,
+    tip_payment::{tip_accounts, TipShare, TipSplit, TIP_PAYMENT_PROGRAM_ID},
+    token_authenticator::ClientInterceptor,
+    transaction_decoder::{decode_transaction, describe_transaction, program_name},
+    validator_metadata::fetch_validator_metadata,
+    wsol, BundleRejectionError,
+};
+use log::{error, info, warn};
+use solana_client::{
+    nonblocking::rpc_client::RpcClient, rpc_config::RpcTransactionConfig,
+    rpc_request::MAX_GET_SIGNATURE_STATUSES_QUERY_ITEMS,
 };
-use log::info;
-use solana_client::nonblocking::rpc_client::RpcClient;
 use solana_sdk::{
+    clock::Slot,
     commitment_config::CommitmentConfig,
+    hash::Hash,
+    instruction::Instruction,
+    native_token::LAMPORTS_PER_SOL,
     pubkey::Pubkey,
-    signature::{read_keypair_file, Signature, Signer},
-    system_instruction::{self, transfer},
+    signature::{read_keypair_file, Keypair, Signature, Signer},
+    system_instruction::transfer,
     transaction::{Transaction, VersionedTransaction},
 };
+use solana_transaction_status::UiTransactionEncoding;
 use spl_memo::build_memo;
-use std::str::FromStr;
-use tokio::time::{sleep, timeout};
-use tonic::{codegen::InterceptedService, transport::Channel, Streaming};
+use tokio::{
+    sync::mpsc::unbounded_channel,
+    time::{interval, sleep},
+};
+use tonic::{
+    codegen::InterceptedService,
+    transport::{Channel, Server},
+};
+
+use crate::{
+    address_book::{parse_address, AddressBook},
+    confirm::{Spend, SpendConfirmation},
+    doctor::{run_doctor, DoctorConfig},
+    error::{CliError, CliResult, ExitCode},
+};
+
 #[derive(Parser, Debug)]
 #[clap(author, version, about, long_about = None)]
 struct Args {
@@ -36,10 +128,16 @@ struct Args {
     #[arg(long, env)]
     block_engine_url: String,
 
-    /// Path to keypair file used to authenticate with the Jito Block Engine
+    /// Path to keypair file used to authenticate with the Jito Block Engine. Required with
+    /// --transport grpc.
     /// See: https://jito-labs.gitbook.io/mev/searcher-resources/getting-started#block-engine-api-key
     #[arg(long, env)]
-    keypair_path: PathBuf,
+    keypair_path: Option<PathBuf>,
+
+    /// How to talk to the block engine. json-rpc uses the public bundle API, which needs no
+    /// approved keypair but only supports send-bundle and tip-accounts.
+    #[arg(long, env, value_enum, default_value_t = Transport::Grpc)]
+    transport: Transport,
 
     /// Comma-separated list of regions to request cross-region data from.
     /// If no region specified, then default to the currently connected block engine's region.
@@ -48,12 +146,100 @@ struct Args {
     #[arg(long, env, value_delimiter = ',')]
     regions: Vec<String>,
 
+    /// Pyth SOL/USD price account used to show USD values next to lamport amounts.
+    /// Mainnet: H6ARHf6YXhGYeQfUzQNGk6rDNnLBQKrenN712K4AQJEG
+    #[arg(long, env, value_parser = parse_address)]
+    usd_price_account: Option<Pubkey>,
+
+    /// How long a fetched SOL/USD price is reused before reading the price account again.
+    #[arg(long, env, default_value_t = 30)]
+    usd_price_ttl_secs: u64,
+
+    /// Extra KEY=VALUE metadata sent with every block engine call. Repeat for several headers.
+    #[arg(long, env)]
+    client_header: Vec<String>,
+
+    /// Debug mode: append the full request and response of every block engine call to this
+    /// JSONL file, with auth tokens and signed challenges redacted.
+    #[arg(long, env)]
+    trace_grpc: Option<PathBuf>,
+
+    /// Config file holding the address book that pubkey flags resolve @name against.
+    /// Defaults to ~/.config/jito-searcher/config.toml
+    #[arg(long, env = "SEARCHER_CONFIG")]
+    config: Option<PathBuf>,
+
+    /// Cluster the --rpc-url of bundle sending commands must be on: mainnet-beta, testnet or
+    /// devnet. Inferred from --block-engine-url if not set, the check is skipped if neither
+    /// names one
+    #[arg(long, env)]
+    cluster: Option<Cluster>,
+
+    /// Ask before a command spends more than this, counting transfers, tips and rent.
+    /// Accepts units, e.g. 0.01sol
+    #[arg(long, env, default_value_t = 10_000_000, value_parser = parse_lamports)]
+    confirm_above_lamports: u64,
+
+    /// Don't ask before spending, for scripts. Without it, spending above
+    /// --confirm-above-lamports fails when there's no terminal to ask on
+    #[arg(long)]
+    yes: bool,
+
+    /// Threads driving async tasks and network I/O, e.g. for strategy-gateway. Defaults to one
+    /// per core.
+    #[arg(long, env)]
+    worker_threads: Option<usize>,
+
+    /// Upper bound on the blocking thread pool. Defaults to tokio's 512.
+    #[arg(long, env)]
+    max_blocking_threads: Option<usize>,
+
     /// Subcommand to run
     #[command(subcommand)]
     command: Commands,
 }
 
+impl Args {
+    fn expected_cluster(&self) -> Option<Cluster> {
+        self.cluster
+            .or_else(|| Cluster::from_block_engine_url(&self.block_engine_url))
+    }
+
+    fn spend_confirmation(&self) -> SpendConfirmation {
+        SpendConfirmation {
+            threshold_lamports: self.confirm_above_lamports,
+            assume_yes: self.yes,
+        }
+    }
+
+    fn runtime_config(&self) -> CliResult<RuntimeConfig> {
+        let mut validator = ConfigValidator::new();
+        if let Some(worker_threads) = self.worker_threads {
+            validator.range("--worker-threads", worker_threads, 1..=usize::MAX);
+        }
+        if let Some(max_blocking_threads) = self.max_blocking_threads {
+            validator.range(
+                "--max-blocking-threads",
+                max_blocking_threads,
+                1..=usize::MAX,
+            );
+        }
+        validator.finish()?;
+        Ok(RuntimeConfig {
+            worker_threads: self.worker_threads,
+            max_blocking_threads: self.max_blocking_threads,
+        })
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+enum Transport {
+    Grpc,
+    JsonRpc,
+}
+
 #[derive(Debug, Subcommand)]
+#[allow(clippy::large_enum_variant)]
 enum Commands {
     /// Print out information on the next scheduled leader
     NextScheduledLeader,
@@ -70,168 +256,2891 @@ enum Commands {
     /// Prints out information about the tip accounts
     TipAccounts,
 
-    /// Sends a 1 lamport bundle
+    /// Sends a transfer bundle with a separate tip transaction
     SendBundle {
         /// RPC URL
         #[clap(long, required = true)]
         rpc_url: String,
-        /// Filepath to keypair that can afford the transaction payments with 1 lamport tip
+        /// Filepath to keypair that can afford the transfer, transaction fees and tip
         #[clap(long, required = true)]
         payer: PathBuf,
         /// Message you'd like the bundle to say
+        #[clap(long, required_unless_present = "instructions")]
+        message: Option<String>,
+        /// Account receiving the transfer
+        #[clap(long, required_unless_present = "instructions", value_parser = parse_address)]
+        recipient: Option<Pubkey>,
+        /// Bundle spec file of raw instructions to send in place of the transfer and memo, as
+        /// program id, accounts and hex, base58 or base64 data with {payer}, {tip_account} and
+        /// {ata:MINT} placeholders. Each of its transactions goes before the tip's
+        #[clap(long, conflicts_with_all = ["message", "recipient"])]
+        instructions: Option<PathBuf>,
+        /// Amount to transfer to the recipient, e.g. 0.5sol or 1.2k lamports. Lamports if no
+        /// unit is given
+        #[clap(long, default_value_t = 1_000, value_parser = parse_lamports)]
+        transfer_lamports: u64,
+        /// Amount to tip, in lamports unless a unit is given
+        #[clap(long, required = true, value_parser = parse_lamports)]
+        lamports: u64,
+        /// One of the tip accounts, see https://jito-foundation.gitbook.io/mev/mev-payment-and-distribution/on-chain-addresses
+        #[clap(long, required = true, value_parser = parse_address)]
+        tip_account: Pubkey,
+        /// Pay a percentage of the tip to another account instead, as PUBKEY:PERCENT. The tip
+        /// account must still get at least 1000 lamports
+        #[clap(long, value_delimiter = ',')]
+        tip_share: Vec<TipShare>,
+        /// Number of times to submit the bundle before giving up
+        #[clap(long, default_value_t = 1)]
+        max_bundle_attempts: usize,
+        /// After this many consecutive auction losses, send the non-tip transactions over RPC
+        /// instead, giving up atomicity for inclusion. Disabled if not set.
+        #[clap(long, value_parser = clap::builder::RangedU64ValueParser::<usize>::new().range(1..))]
+        rpc_fallback_after: Option<usize>,
+        /// Skip preflight checks on RPC fallback sends
+        #[clap(long)]
+        fallback_skip_preflight: bool,
+        /// Max retries the RPC node makes for fallback sends
+        #[clap(long)]
+        fallback_max_retries: Option<usize>,
+        /// Priority fee in micro-lamports per compute unit for fallback sends
+        #[clap(long, default_value_t = 10_000)]
+        fallback_compute_unit_price: u64,
+        /// Pre-sign the bundle and hold it until a Jito leader is within this many slots
+        #[clap(long)]
+        wait_for_leader: Option<u64>,
+        /// While waiting, re-sign the bundle once its blockhash is this close to expiring
+        #[clap(long, default_value_t = 20)]
+        expiry_margin_blocks: u64,
+        /// Simulate the bundle first and print which transactions depend on state changed by
+        /// earlier ones, and the lamport and token balance changes
+        #[clap(long)]
+        check_conflicts: bool,
+        /// Simulate the bundle first and print the compute units each instruction consumes
+        #[clap(long)]
+        profile_compute: bool,
+        /// Comma-separated conditions checked once the bundle lands, as KIND:ACCOUNT:VALUE with
+        /// KIND one of min-lamports, lamports-delta, min-tokens or token-delta
+        #[clap(long, value_delimiter = ',')]
+        post_condition: Vec<PostCondition>,
+        /// Deprecated and ignored, the bundle is the transfer or --instructions followed by the
+        /// tip transaction
+        #[clap(long, hide = true)]
+        num_txs: Option<usize>,
+    },
+
+    /// Watches the balances of the payer, tip reserve and strategy token accounts, alerting when
+    /// any drops below its threshold and optionally topping up the payer from a treasury
+    WatchBalances {
         #[clap(long, required = true)]
-        message: String,
-        /// Number of transactions in the bundle (must be <= 5)
+        rpc_url: String,
+        /// JSON file listing the accounts to watch, their thresholds and the top-up policy
         #[clap(long, required = true)]
-        num_txs: usize,
-        /// Amount of lamports to tip in each transaction
+        accounts: PathBuf,
+        /// Seconds between balance checks
+        #[clap(long, default_value_t = 10, value_parser = clap::value_parser!(u64).range(1..))]
+        interval_secs: u64,
+        /// Filepath to the keypair of the treasury top-ups are sent from, enables top-ups
+        #[clap(long)]
+        treasury: Option<PathBuf>,
+    },
+
+    /// Tracks tip account balances to estimate per-slot tip volume and payouts
+    TipMonitor {
         #[clap(long, required = true)]
-        lamports: u64,
-        /// One of the tip accounts, see https://jito-foundation.gitbook.io/mev/mev-payment-and-distribution/on-chain-addresses
+        rpc_url: String,
+        /// Seconds between balance snapshots
+        #[clap(long, default_value_t = 2, value_parser = clap::value_parser!(u64).range(1..))]
+        interval_secs: u64,
+    },
+
+    /// Creates missing associated token accounts ahead of time so bundles don't pay for
+    /// creating them in the critical path
+    CreateTokenAccounts {
+        /// RPC URL
         #[clap(long, required = true)]
-        tip_account: Pubkey,
+        rpc_url: String,
+        /// Filepath to the keypair paying for transaction fees and rent
+        #[clap(long, required = true)]
+        payer: PathBuf,
+        /// Wallet owning the token accounts, defaults to the payer
+        #[clap(long, value_parser = parse_address)]
+        owner: Option<Pubkey>,
+        /// Comma-separated mints to create token accounts for
+        #[clap(long, value_delimiter = ',', required = true, value_parser = parse_address)]
+        mints: Vec<Pubkey>,
+        /// Print what would be created and the rent it costs without sending anything
+        #[clap(long)]
+        dry_run: bool,
+    },
+
+    /// Wraps SOL into the payer's wSOL associated token account, creating it if needed
+    WrapSol {
+        /// RPC URL
+        #[clap(long, required = true)]
+        rpc_url: String,
+        /// Filepath to the keypair whose SOL is wrapped, also pays fees and rent
+        #[clap(long, required = true)]
+        payer: PathBuf,
+        /// Amount to wrap, e.g. 0.5sol
+        #[clap(long, required = true, value_parser = parse_lamports)]
+        amount: u64,
+    },
+
+    /// Unwraps all of the payer's wSOL by closing its wSOL associated token account, reclaiming
+    /// the rent too
+    UnwrapSol {
+        /// RPC URL
+        #[clap(long, required = true)]
+        rpc_url: String,
+        /// Filepath to the keypair owning the wSOL account
+        #[clap(long, required = true)]
+        payer: PathBuf,
+    },
+
+    /// Moves SOL and tokens above a float from hot wallets to a treasury, keeping the funds
+    /// exposed in bot wallets bounded
+    Sweep {
+        /// RPC URL
+        #[clap(long, required = true)]
+        rpc_url: String,
+        /// Comma-separated filepaths to the keypairs of the hot wallets to sweep, each pays its
+        /// own sweep
+        #[clap(long, value_delimiter = ',', required = true)]
+        wallets: Vec<PathBuf>,
+        /// Address profits are swept to
+        #[clap(long, required = true, value_parser = parse_address)]
+        treasury: Pubkey,
+        /// SOL each wallet keeps for fees and tips, e.g. 2sol
+        #[clap(long, required = true, value_parser = parse_lamports)]
+        float: u64,
+        /// Smallest SOL sweep worth sending
+        #[clap(long, default_value = "0.01sol", value_parser = parse_lamports)]
+        min_sweep: u64,
+        /// Comma-separated tokens to sweep as MINT:AMOUNT, AMOUNT being what each wallet keeps
+        /// in token units
+        #[clap(long, value_delimiter = ',')]
+        tokens: Vec<TokenFloat>,
+        /// Sweep again every this many seconds instead of once
+        #[clap(long, value_parser = clap::value_parser!(u64).range(1..))]
+        interval_secs: Option<u64>,
+        /// Print what would be swept without sending anything
+        #[clap(long)]
+        dry_run: bool,
+    },
+
+    /// Fits a landing probability curve over tip percentile and slots to leader from the
+    /// bundles recorded in backrun audit logs
+    LandingModel {
+        /// RPC URL, used to check which bundles landed
+        #[clap(long, required = true)]
+        rpc_url: String,
+        /// Comma-separated audit log files to read bundles from
+        #[clap(long, value_delimiter = ',', required = true)]
+        audit_log: Vec<PathBuf>,
+        /// Tip payment program the bundles tipped through
+        #[clap(long, default_value_t = TIP_PAYMENT_PROGRAM_ID, value_parser = parse_address)]
+        tip_program_id: Pubkey,
+        /// File to write the fitted model to as JSON
+        #[clap(long, required = true)]
+        output: PathBuf,
+    },
+
+    /// Compares compute units consumed against tip paid and profit for the landed bundles in
+    /// backrun audit logs, per audit log
+    EfficiencyReport {
+        /// RPC URL, used to fetch the landed transactions
+        #[clap(long, required = true)]
+        rpc_url: String,
+        /// Comma-separated audit log files to read bundles from. Rotated files are reported
+        /// together with the file they were rotated from
+        #[clap(long, value_delimiter = ',', required = true)]
+        audit_log: Vec<PathBuf>,
+        /// Fee payer of the searcher's transactions, whose balance change is the profit
+        #[clap(long, required = true, value_parser = parse_address)]
+        payer: Pubkey,
+        /// Tip payment program the bundles tipped through
+        #[clap(long, default_value_t = TIP_PAYMENT_PROGRAM_ID, value_parser = parse_address)]
+        tip_program_id: Pubkey,
+    },
+
+    /// Imports the bundles in backrun audit logs into a bundle store, checks which landed and
+    /// prints the most recent with the errors from their transaction logs
+    History {
+        /// RPC URL, used to check which bundles landed and fetch their logs
+        #[clap(long, required = true)]
+        rpc_url: String,
+        /// Comma-separated audit log files to import bundles from
+        #[clap(long, value_delimiter = ',')]
+        audit_log: Vec<PathBuf>,
+        /// Bundle store file, created if it doesn't exist
+        #[clap(long, default_value = "bundles.jsonl")]
+        store: PathBuf,
+        /// Number of most recent bundles to print
+        #[clap(long, default_value_t = 20)]
+        limit: usize,
+        /// Tip payment program the bundles tipped through
+        #[clap(long, default_value_t = TIP_PAYMENT_PROGRAM_ID, value_parser = parse_address)]
+        tip_program_id: Pubkey,
+    },
+
+    /// Checks the hash chain and signatures of a backrun audit log written with
+    /// --sign-audit-log, its rotated files included
+    VerifyAuditLog {
+        /// Active audit log file, its rotated files are read from next to it
+        #[clap(long, required = true)]
+        audit_log: PathBuf,
+        /// Key every entry must be signed with, the backrun's first auth keypair
+        #[clap(long, value_parser = parse_address)]
+        signer: Option<Pubkey>,
+    },
+
+    /// Reconstructs bundle store records of tips the payer paid before the store was kept,
+    /// from its transaction history on chain
+    Backfill {
+        /// RPC URL, used to walk the payer's transaction history
+        #[clap(long, required = true)]
+        rpc_url: String,
+        /// Fee payer of the searcher's tipping transactions
+        #[clap(long, required = true, value_parser = parse_address)]
+        payer: Pubkey,
+        /// Bundle store file
+        #[clap(long, default_value = "bundles.jsonl")]
+        store: PathBuf,
+        /// Stop at transactions before this day, as YYYY-MM-DD, UTC
+        #[clap(long)]
+        since: Option<NaiveDate>,
+        /// Most transactions of the payer to scan, newest first
+        #[clap(long, default_value_t = 10_000)]
+        max_transactions: usize,
+        /// Tip payment program the bundles tipped through
+        #[clap(long, default_value_t = TIP_PAYMENT_PROGRAM_ID, value_parser = parse_address)]
+        tip_program_id: Pubkey,
+    },
+
+    /// Ranks leaders and block engine regions by how often they got this searcher's bundles on
+    /// chain, from the bundle store
+    LeaderStats {
+        /// Bundle store file, resolve it with `history` first
+        #[clap(long, default_value = "bundles.jsonl")]
+        store: PathBuf,
+        /// Only bundles from this audit log, as named in `history`
+        #[clap(long)]
+        source: Option<String>,
+        /// Leaders and regions with fewer resolved bundles than this are left out
+        #[clap(long, default_value_t = 5)]
+        min_bundles: usize,
+        /// Leaders to list
+        #[clap(long, default_value_t = 20)]
+        limit: usize,
+        /// RPC URL, used to look up validator names
+        #[clap(long)]
+        rpc_url: Option<String>,
+    },
+
+    /// Renders summary reports from the bundle store
+    Report {
+        #[command(subcommand)]
+        command: ReportCommand,
+    },
+
+    /// Calls each block engine method repeatedly and prints latency percentiles and error rates
+    LatencyReport {
+        /// Calls per method
+        #[clap(long, default_value_t = 20)]
+        samples: usize,
+        /// Milliseconds between rounds of calls
+        #[clap(long, default_value_t = 100)]
+        interval_ms: u64,
+    },
+
+    /// Sends a small self-transfer bundle with a tip every --interval-secs and records whether
+    /// and when it lands, probing the whole path from auth to confirmation
+    Canary {
+        /// RPC URL, used for the blockhash and to follow each canary until confirmed
+        #[clap(long, required = true)]
+        rpc_url: String,
+        /// Filepath to the keypair paying the transfer, tip and fees
+        #[clap(long, required = true)]
+        payer: PathBuf,
+        /// Amount to tip, in lamports unless a unit is given
+        #[clap(long, default_value_t = MIN_TIP_LAMPORTS, value_parser = parse_lamports)]
+        tip_lamports: u64,
+        /// Tip payment program whose tip accounts are tipped in turn
+        #[clap(long, default_value_t = TIP_PAYMENT_PROGRAM_ID, value_parser = parse_address)]
+        tip_program_id: Pubkey,
+        /// Seconds from one canary to the next, or until the last one ends if it takes longer
+        #[clap(long, default_value_t = 60)]
+        interval_secs: u64,
+        /// Seconds to wait for a canary to be confirmed before counting it as not landed
+        #[clap(long, default_value_t = 60)]
+        land_timeout_secs: u64,
+        /// Stop after this many canaries. Runs until stopped if not set
+        #[clap(long)]
+        count: Option<u64>,
+        /// File to append each canary to as a JSON line
+        #[clap(long)]
+        output: Option<PathBuf>,
+    },
+
+    /// Prints signatures of pending transactions from the mempool
+    Watch {
+        #[command(flatten)]
+        mempool: MempoolArgs,
+        #[command(flatten)]
+        decoders: DecoderArgs,
+    },
+
+    /// Records pending transactions from the mempool to a JSON lines file or another sink
+    RecordMempool {
+        #[command(flatten)]
+        mempool: MempoolArgs,
+        /// Where to record transactions: a file path to append to, s3://bucket/key,
+        /// kafka://broker:9092/topic or nats://server:4222/subject
+        #[clap(long, required = true)]
+        output: SinkUri,
+    },
+
+    /// Converts a mempool recording between JSON lines and protobuf, upgrading older schema
+    /// versions to the current one
+    ConvertRecording {
+        /// Recording to read, in either format and any schema version
+        #[clap(long)]
+        input: PathBuf,
+        /// File to write the converted recording to
+        #[clap(long)]
+        output: PathBuf,
+        /// jsonl or protobuf
+        #[clap(long, default_value_t = RecordingFormat::Protobuf)]
+        format: RecordingFormat,
+    },
+
+    /// Simulates transactions and prints the compute units each instruction and the programs it
+    /// calls consume, to find what to cut from bundles over their compute limits
+    ProfileCompute {
+        /// RPC URL to simulate on
+        #[clap(long, required = true)]
+        rpc_url: String,
+        /// Comma-separated base64 or base58 serialized transactions, profiled one by one
+        #[clap(long, value_delimiter = ',', required = true)]
+        transactions: Vec<String>,
+    },
+
+    /// Decodes a transaction and prints its header, accounts, instructions and compute budget
+    DecodeTx {
+        /// Base64 or base58 serialized transaction
+        #[clap(long, required_unless_present = "signature")]
+        transaction: Option<String>,
+        /// Signature of a landed transaction to fetch from RPC instead
+        #[clap(long, requires = "rpc_url")]
+        signature: Option<Signature>,
+        /// RPC URL used to fetch the transaction by signature
+        #[clap(long)]
+        rpc_url: Option<String>,
+        #[command(flatten)]
+        decoders: DecoderArgs,
+        /// Fetch the IDL published on-chain for invoked programs without a decoder
+        #[clap(long, requires = "rpc_url")]
+        fetch_idl: bool,
+    },
+
+    /// Fetches an account and decodes its data with the owning program's decoder plugin or
+    /// Anchor IDL
+    DecodeAccount {
+        /// Account to decode
+        #[clap(long, required = true, value_parser = parse_address)]
+        address: Pubkey,
+        /// RPC URL
+        #[clap(long, required = true)]
+        rpc_url: String,
+        /// Decoders for the owner, the IDL it published on-chain is used if none is given
+        #[command(flatten)]
+        decoders: DecoderArgs,
+    },
+
+    /// Discovers the AMM pools trading token pairs and prints their reserves
+    Pools {
+        /// RPC URL, must allow getProgramAccounts
+        #[clap(long, required = true)]
+        rpc_url: String,
+        /// Token pairs as MINT:MINT
+        #[clap(long = "pair", required = true, value_delimiter = ',')]
+        pairs: Vec<TokenPair>,
+        /// Dexes to search
+        #[clap(
+            long,
+            value_delimiter = ',',
+            default_value = "raydium-amm-v4,orca-whirlpool,meteora-dlmm"
+        )]
+        dexes: Vec<Dex>,
+    },
+
+    /// Checks the auth keypair, payer balance, RPC capabilities and clock, printing fixes for
+    /// anything that fails
+    Doctor {
+        /// RPC URL
+        #[clap(long, required = true)]
+        rpc_url: String,
+        /// RPC websocket URL, checks pubsub if set
+        #[clap(long)]
+        ws_url: Option<String>,
+        /// Filepath to the keypair paying for bundles, checks its balance if set
+        #[clap(long)]
+        payer: Option<PathBuf>,
+        /// Minimum payer balance, in lamports unless a unit is given
+        #[clap(long, default_value_t = 10_000_000, value_parser = parse_lamports)]
+        min_payer_balance: u64,
+        /// Maximum allowed difference between the local clock and the latest block time
+        #[clap(long, default_value_t = 10)]
+        max_clock_skew_secs: u64,
+    },
+
+    /// Measures the local clock's offset and jitter against an NTP server and, if --rpc-url is
+    /// set, the latest block time
+    ClockCheck {
+        /// NTP server as host:port
+        #[clap(long, default_value = "pool.ntp.org:123")]
+        ntp_server: String,
+        /// RPC URL, also compares against the latest block time if set
+        #[clap(long)]
+        rpc_url: Option<String>,
+        /// Number of samples taken from each source
+        #[clap(long, default_value_t = 8)]
+        samples: usize,
+        /// Fails if the NTP offset is larger than this many milliseconds
+        #[clap(long, default_value_t = 50)]
+        max_offset_ms: u64,
+    },
+
+    /// Serves a gRPC gateway for local strategy processes to stream mempool events from and
+    /// bundles through, with tips, budgets and confirmations handled here
+    StrategyGateway {
+        /// Address to serve the gateway on
+        #[clap(long, default_value = "127.0.0.1:50061")]
+        listen: SocketAddr,
+        /// RPC URL, used for the tip transactions' blockhash
+        #[clap(long, required = true)]
+        rpc_url: String,
+        /// Filepath to the keypair paying the tips strategies ask for
+        #[clap(long, required = true)]
+        payer: PathBuf,
+        /// Largest tip a single bundle may ask for
+        #[clap(long, default_value_t = 1_000_000, value_parser = parse_lamports)]
+        max_tip_lamports: u64,
+        /// Lamports each strategy may tip within --tip-budget-window-secs. Unlimited if not set.
+        #[clap(long, value_parser = parse_lamports, conflicts_with = "clients")]
+        tip_budget_lamports: Option<u64>,
+        #[clap(long, default_value_t = 60)]
+        tip_budget_window_secs: u64,
+        /// Pay a percentage of every tip to another account, as PUBKEY:PERCENT. Tips too small
+        /// to leave the tip account 1000 lamports go to it whole
+        #[clap(long, value_delimiter = ',')]
+        tip_share: Vec<TipShare>,
+        /// How long an idempotency key is remembered after its bundle was sent
+        #[clap(long, default_value_t = 300)]
+        idempotency_ttl_secs: u64,
+        /// Bulk priority bundles are refused once this many bundles are queued
+        #[clap(long, default_value_t = 64)]
+        max_queued_bulk: usize,
+        /// Normal priority bundles are refused once this many bundles are queued
+        #[clap(long, default_value_t = 256)]
+        max_queued_normal: usize,
+        /// Critical priority bundles are refused once this many bundles are queued
+        #[clap(long, default_value_t = 1024)]
+        max_queued_critical: usize,
+        /// JSON file listing the clients allowed to connect, with their tokens and quotas.
+        /// Anyone may connect if not set
+        #[clap(long)]
+        clients: Option<PathBuf>,
+    },
+
+    /// Times the hot path on this host: bundle build and sign, bundle serialization, interest
+    /// filter and packet decode throughput. `cargo bench -p jito-searcher-client` runs the same
+    /// workloads under criterion
+    Bench {
+        /// Mempool packets per filter and decode iteration
+        #[clap(long, default_value_t = 1024)]
+        batch_size: usize,
+        /// How long to run each benchmark for
+        #[clap(long, default_value_t = 3)]
+        secs: u64,
+    },
+
+    /// Lists and edits the named addresses pubkey flags accept as @name
+    Addresses {
+        #[command(subcommand)]
+        command: AddressesCommand,
+    },
+
+    /// Serves bundle submission on a Unix domain socket for strategies on the same host, see
+    /// jito_searcher_client::uds_ipc for the framing
+    #[cfg(unix)]
+    ServeIpc {
+        /// Socket path, replaced if it already exists
+        #[clap(long, required = true)]
+        socket: PathBuf,
+        /// How long an idempotency key is remembered after its bundle was sent
+        #[clap(long, default_value_t = 300)]
+        idempotency_ttl_secs: u64,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+enum ReportCommand {
+    /// Summarizes a day of bundles: sent and landed, tips spent, PnL, top rejection reasons and
+    /// the best and worst hours
+    Daily {
+        /// Bundle store file, resolve it with `history` first
+        #[clap(long, default_value = "bundles.jsonl")]
+        store: PathBuf,
+        /// Comma-separated audit log files to count the bundles the block engine rejected from
+        #[clap(long, value_delimiter = ',')]
+        audit_log: Vec<PathBuf>,
+        /// Day to report on as YYYY-MM-DD, UTC. Defaults to today
+        #[clap(long)]
+        date: Option<NaiveDate>,
+        /// terminal, markdown or html
+        #[clap(long, default_value_t = ReportFormat::Terminal)]
+        format: ReportFormat,
+        /// File to write the report to instead of printing it
+        #[clap(long)]
+        output: Option<PathBuf>,
     },
+
+    /// Shows where landed bundles executed in their blocks: how often at the top, the spread
+    /// of their positions and how profit varied with position
+    Positions {
+        /// Bundle store file, resolve it with `history` first
+        #[clap(long, default_value = "bundles.jsonl")]
+        store: PathBuf,
+        /// Only bundles from this audit log, as named in `history`
+        #[clap(long)]
+        source: Option<String>,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+enum AddressesCommand {
+    /// Prints every named address
+    List,
+    /// Adds a named address
+    Add {
+        /// Name to refer to the address by, as @name
+        name: String,
+        /// Base58 pubkey
+        address: Pubkey,
+        /// Replace the address if the name is already taken
+        #[clap(long)]
+        force: bool,
+    },
+    /// Removes a named address
+    Remove { name: String },
+}
+
+#[derive(Debug, clap::Args)]
+struct DecoderArgs {
+    /// Comma-separated Anchor IDL files, or directories of them, to decode instructions and
+    /// accounts of the programs they describe with
+    #[clap(long, value_delimiter = ',')]
+    idl: Vec<PathBuf>,
+    /// Comma-separated <PROGRAM_ID>=<PATH> decoder plugins for programs without an IDL: shared
+    /// libraries, or .wasm modules when built with the wasm feature. Plugins run in-process,
+    /// only load ones you trust
+    #[clap(long, value_delimiter = ',')]
+    decoder_plugin: Vec<PluginSpec>,
+}
+
+#[derive(Debug, clap::Args)]
+struct MempoolArgs {
+    /// Comma-separated write-locked accounts to subscribe to
+    #[clap(long, value_delimiter = ',', required_unless_present = "programs", value_parser = parse_address)]
+    accounts: Vec<Pubkey>,
+    /// Comma-separated programs to subscribe to, used instead of --accounts
+    #[clap(long, value_delimiter = ',', value_parser = parse_address)]
+    programs: Vec<Pubkey>,
+    /// Comma-separated inclusive slot ranges to capture, e.g. 250000000-250000100.
+    /// Captures everything if not set.
+    #[clap(long, value_delimiter = ',')]
+    slot_ranges: Vec<SlotRange>,
+    /// Only capture while the next Jito leader is at most this many slots away
+    #[clap(long)]
+    max_slots_to_leader: Option<u64>,
 }
 
 async fn print_next_leader_info(
     client: &mut SearcherServiceClient<InterceptedService<Channel, ClientInterceptor>>,
     regions: Vec<String>,
-) {
-    let next_leader = client
-        .get_next_scheduled_leader(NextScheduledLeaderRequest { regions })
-        .await
-        .expect("gets next scheduled leader")
-        .into_inner();
+) -> CliResult<()> {
+    let next_leader = traced(
+        "GetNextScheduledLeader",
+        NextScheduledLeaderRequest { regions },
+        |request| client.get_next_scheduled_leader(request),
+    )
+    .await?
+    .into_inner();
     println!(
         "next jito-solana slot in {} slots for leader {:?}",
         next_leader.next_leader_slot - next_leader.current_slot,
         next_leader.next_leader_identity
     );
+    Ok(())
 }
 
-#[tokio::main]
-async fn main() {
-    env_logger::builder()
-        .format_timestamp(Some(TimestampPrecision::Micros))
-        .init();
+/// Loads the IDLs and decoder plugins given on the command line.
+fn load_decoders(args: &DecoderArgs) -> CliResult<DecoderRegistry> {
+    let mut registry = DecoderRegistry::default();
+    for path in &args.idl {
+        for program_id in registry.idls_mut().load_path(path)? {
+            info!("loaded IDL for {program_id} from {}", path.display());
+        }
+    }
+    for plugin in &args.decoder_plugin {
+        // SAFETY: the user vouches for the plugins they pass
+        unsafe { registry.load(plugin.program_id, &plugin.path) }?;
+        info!(
+            "loaded decoder plugin for {} from {}",
+            plugin.program_id,
+            plugin.path.display()
+        );
+    }
+    Ok(registry)
+}
+
+async fn decode_tx(
+    transaction: Option<String>,
+    signature: Option<Signature>,
+    rpc_url: Option<String>,
+    decoders: &DecoderArgs,
+    fetch_idl: bool,
+) -> CliResult<()> {
+    let mut registry = load_decoders(decoders)?;
+    let tx = match (transaction, signature, rpc_url.clone()) {
+        (Some(transaction), _, _) => decode_transaction(&transaction)?,
+        (None, Some(signature), Some(rpc_url)) => {
+            let rpc_client = RpcClient::new(rpc_url);
+            let confirmed_tx = rpc_client
+                .get_transaction_with_config(
+                    &signature,
+                    RpcTransactionConfig {
+                        encoding: Some(UiTransactionEncoding::Base64),
+                        commitment: Some(CommitmentConfig::confirmed()),
+                        max_supported_transaction_version: Some(0),
+                    },
+                )
+                .await?;
+            println!("slot: {}", confirmed_tx.slot);
+            if let Some(meta) = &confirmed_tx.transaction.meta {
+                println!("status: {:?}", meta.status);
+            }
+            confirmed_tx
+                .transaction
+                .transaction
+                .decode()
+                .ok_or_else(|| CliError::Other("rpc returned an undecodable transaction".into()))?
+        }
+        _ => unreachable!("clap requires --transaction or --signature with --rpc-url"),
+    };
+    print!("{}", describe_transaction(&tx)?);
+
+    if let (true, Some(rpc_url)) = (fetch_idl, rpc_url) {
+        let rpc_client = RpcClient::new(rpc_url);
+        let keys = tx.message.static_account_keys();
+        let programs: HashSet<Pubkey> = tx
+            .message
+            .instructions()
+            .iter()
+            .filter_map(|ix| keys.get(ix.program_id_index as usize).copied())
+            .collect();
+        for program_id in programs {
+            if registry.contains(&program_id) {
+                continue;
+            }
+            if let Err(e) = registry.idls_mut().fetch(&rpc_client, &program_id).await {
+                info!("no IDL for {program_id}: {e}");
+            }
+        }
+    }
+    let decoded = registry.describe_transaction(&tx);
+    if !decoded.is_empty() {
+        print!("decoded instructions:\n{decoded}");
+    }
+    Ok(())
+}
+
+async fn decode_account(
+    address: &Pubkey,
+    rpc_url: String,
+    decoders: &DecoderArgs,
+) -> CliResult<()> {
+    let mut registry = load_decoders(decoders)?;
+    let rpc_client = RpcClient::new_with_commitment(rpc_url, CommitmentConfig::confirmed());
+    let account = rpc_client.get_account(address).await?;
+    println!("owner: {}", account.owner);
+    println!("lamports: {}", account.lamports);
+    println!("data: {} bytes", account.data.len());
+    if !registry.contains(&account.owner) {
+        registry
+            .idls_mut()
+            .fetch(&rpc_client, &account.owner)
+            .await?;
+    }
+    match registry.decode_account(&account.owner, &account.data) {
+        Some(decoded) => {
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&decoded?).expect("serializes")
+            );
+            Ok(())
+        }
+        None => Err(CliError::Other(format!(
+            "the decoder of {} doesn't recognize the data",
+            account.owner
+        ))),
+    }
+}
+
+async fn create_token_accounts(
+    rpc_url: String,
+    payer: &Path,
+    owner: Option<Pubkey>,
+    mints: &[Pubkey],
+    dry_run: bool,
+    confirmation: &SpendConfirmation,
+) -> CliResult<()> {
+    let payer_keypair = read_keypair(payer)?;
+    let owner = owner.unwrap_or_else(|| payer_keypair.pubkey());
+    let rpc_client = RpcClient::new_with_commitment(rpc_url, CommitmentConfig::confirmed());
 
-    let tip_lamports = 1500000;
-    let rpc_url = "https://api.mainnet-beta.solana.com";
-    let block_engine_url = "https://amsterdam.mainnet.block-engine.jito.wtf";
-    let keypair_path = "./jito-keypair.json";
-    let keypair = Arc::new(read_keypair_file(keypair_path).expect("reads keypair at path"));
-    let payer = "./my-keypair.json";
-    let payer_keypair = read_keypair_file(&payer).expect("reads keypair at path");
-
-    let mut client = get_searcher_client(block_engine_url, &keypair)
-        .await
-        .expect("connects to searcher client");
-
-    // let tip_accounts = client
-    //     .get_tip_accounts(GetTipAccountsRequest {})
-    //     .await
-    //     .expect("gets connected leaders")
-    //     .into_inner();
-    // info!("{:?}", tip_accounts);
-
-    let rpc_client =
-        RpcClient::new_with_commitment(rpc_url.to_string(), CommitmentConfig::confirmed());
-    let balance = rpc_client
-        .get_balance(&payer_keypair.pubkey())
-        .await
-        .expect("reads balance");
+    let accounts =
+        associated_token_accounts(&rpc_client, &payer_keypair.pubkey(), &owner, mints).await?;
+    let plan = plan_account_setup(&rpc_client, accounts).await?;
+    for address in &plan.existing {
+        println!("exists: {address}");
+    }
+    for account in &plan.missing {
+        println!("missing: {}", account.address);
+    }
+    println!(
+        "rent for {} missing accounts: {}",
+        plan.missing.len(),
+        format_lamports(plan.rent_lamports, None)
+    );
+    if dry_run || plan.missing.is_empty() {
+        return Ok(());
+    }
+    confirmation.confirm(&Spend {
+        action: "create token accounts",
+        recipient: None,
+        amount_lamports: plan.rent_lamports,
+        tip_lamports: 0,
+    })?;
+    for signature in create_missing_accounts(&rpc_client, &payer_keypair, &plan).await? {
+        println!("created in {signature}");
+    }
+    Ok(())
+}
 
+async fn wrap_sol(
+    rpc_url: String,
+    payer: &Path,
+    lamports: u64,
+    confirmation: &SpendConfirmation,
+) -> CliResult<()> {
+    let payer_keypair = read_keypair(payer)?;
+    let payer = payer_keypair.pubkey();
+    let rpc_client = RpcClient::new_with_commitment(rpc_url, CommitmentConfig::confirmed());
+    confirmation.confirm(&Spend {
+        action: "wrap SOL",
+        recipient: Some(&wsol::wsol_address(&payer)),
+        amount_lamports: lamports,
+        tip_lamports: 0,
+    })?;
+    let blockhash = rpc_client.get_latest_blockhash().await?;
+    let tx = Transaction::new_signed_with_payer(
+        &wsol::wrap_sol(&payer, &payer, lamports),
+        Some(&payer),
+        &[&payer_keypair],
+        blockhash,
+    );
+    let signature = rpc_client.send_and_confirm_transaction(&tx).await?;
+    println!(
+        "wrapped {} into {} in {signature}",
+        format_lamports(lamports, None),
+        wsol::wsol_address(&payer)
+    );
+    Ok(())
+}
+
+async fn unwrap_sol(rpc_url: String, payer: &Path) -> CliResult<()> {
+    let payer_keypair = read_keypair(payer)?;
+    let payer = payer_keypair.pubkey();
+    let rpc_client = RpcClient::new_with_commitment(rpc_url, CommitmentConfig::confirmed());
+    let wsol_account = wsol::wsol_address(&payer);
+    let Some(account) = rpc_client
+        .get_account_with_commitment(&wsol_account, rpc_client.commitment())
+        .await?
+        .value
+    else {
+        return Err(CliError::Other(format!(
+            "{payer} has no wSOL account {wsol_account}"
+        )));
+    };
+    let blockhash = rpc_client.get_latest_blockhash().await?;
+    let tx = Transaction::new_signed_with_payer(
+        &[wsol::unwrap_sol(&payer, &payer)],
+        Some(&payer),
+        &[&payer_keypair],
+        blockhash,
+    );
+    let signature = rpc_client.send_and_confirm_transaction(&tx).await?;
+    println!(
+        "closed {wsol_account}, returning {} in {signature}",
+        format_lamports(account.lamports, None)
+    );
+    Ok(())
+}
+
+/// Sweeps every wallet once, carrying on past wallets that fail and failing at the end if any
+/// did.
+async fn sweep(
+    rpc_client: &RpcClient,
+    wallets: &[Keypair],
+    config: &SweepConfig,
+    dry_run: bool,
+    confirmation: &SpendConfirmation,
+) -> CliResult<()> {
+    let mut failed = 0;
+    for wallet in wallets {
+        if let Err(e) = sweep_wallet(rpc_client, wallet, config, dry_run, confirmation).await {
+            error!("failed to sweep {}: {e}", wallet.pubkey());
+            failed += 1;
+        }
+    }
+    match failed {
+        0 => Ok(()),
+        failed => Err(CliError::Other(format!(
+            "{failed} of {} wallets failed to sweep",
+            wallets.len()
+        ))),
+    }
+}
+
+async fn sweep_wallet(
+    rpc_client: &RpcClient,
+    wallet: &Keypair,
+    config: &SweepConfig,
+    dry_run: bool,
+    confirmation: &SpendConfirmation,
+) -> CliResult<()> {
+    let plan = plan_sweep(rpc_client, &wallet.pubkey(), config).await?;
+    if plan.transfers.is_empty() {
+        info!("{}: nothing above the float", plan.wallet);
+        return Ok(());
+    }
+    for transfer in &plan.transfers {
+        match transfer.mint {
+            Some(mint) => info!(
+                "{}: sweeping {} of {} units of {mint}",
+                plan.wallet, transfer.amount, transfer.balance
+            ),
+            None => info!(
+                "{}: sweeping {} of {}",
+                plan.wallet,
+                format_lamports(transfer.amount, None),
+                format_lamports(transfer.balance, None)
+            ),
+        }
+    }
+    if plan.rent_lamports > 0 {
+        info!(
+            "{}: creating treasury token accounts for {}",
+            plan.wallet,
+            format_lamports(plan.rent_lamports, None)
+        );
+    }
+    if dry_run {
+        return Ok(());
+    }
+    confirmation.confirm(&Spend {
+        action: "sweep to treasury",
+        recipient: Some(&config.treasury),
+        amount_lamports: plan.lamports() + plan.rent_lamports,
+        tip_lamports: 0,
+    })?;
+    let signatures = execute_sweep(rpc_client, wallet, &plan).await?;
+    let signatures: Vec<_> = signatures.iter().map(ToString::to_string).collect();
+    println!("swept {} in {}", plan.wallet, signatures.join(", "));
+    Ok(())
+}
+
+async fn clock_check(
+    ntp_server: &str,
+    rpc_url: Option<String>,
+    samples: usize,
+    max_offset_ms: u64,
+) -> CliResult<()> {
+    let sample_interval = Duration::from_millis(250);
+    let print_estimate = |source: &str, estimate: &ClockOffsetEstimate| {
+        println!(
+            "{source}: offset {:+.3}ms, jitter {:.3}ms, min round trip {:.3}ms over {} samples",
+            estimate.offset_us as f64 / 1_000.0,
+            estimate.jitter_us as f64 / 1_000.0,
+            estimate.min_round_trip.as_micros() as f64 / 1_000.0,
+            estimate.samples
+        );
+    };
+
+    let ntp = measure_clock_offset(samples, sample_interval, || ntp_sample(ntp_server)).await?;
+    print_estimate(ntp_server, &ntp);
+    if let Some(rpc_url) = rpc_url {
+        let rpc_client = RpcClient::new(rpc_url);
+        let rpc =
+            measure_clock_offset(samples, sample_interval, || rpc_sample(&rpc_client)).await?;
+        // block times are whole seconds, only a large offset means anything
+        print_estimate("block time", &rpc);
+    }
+
+    if ntp.offset_us.unsigned_abs() > max_offset_ms * 1_000 {
+        return Err(CliError::Other(format!(
+            "local clock is {:+.3}ms off, max is {max_offset_ms}ms; sync it with NTP, e.g. chrony",
+            ntp.offset_us as f64 / 1_000.0
+        )));
+    }
+    Ok(())
+}
+
+async fn list_pools(rpc_url: String, pairs: &[TokenPair], dexes: &[Dex]) -> CliResult<()> {
+    let rpc_client = RpcClient::new_with_commitment(rpc_url, CommitmentConfig::confirmed());
+    let mut registry = PoolRegistry::new(pairs.to_vec(), dexes.to_vec());
+    registry.discover(&rpc_client).await?;
+    registry.refresh(&rpc_client).await?;
+    for pair in pairs {
+        println!("{pair}");
+        let mut pools: Vec<_> = registry.pools_for(*pair).collect();
+        if pools.is_empty() {
+            println!("  no pools found");
+            continue;
+        }
+        let (mint, _) = pair.mints();
+        // deepest first
+        pools.sort_by_key(|pool| Reverse(pool.reserves_of(&mint).map(|(reserve, _)| reserve)));
+        for pool in pools {
+            let reserves = match pool.reserves_of(&mint) {
+                Some((reserve, other)) => format!("{reserve} / {other}"),
+                None => "reserves unavailable".to_string(),
+            };
+            println!(
+                "  {:<16} {:<44} {reserves}",
+                pool.layout.dex().to_string(),
+                pool.address
+            );
+        }
+    }
+    Ok(())
+}
+
+fn bench(batch_size: usize, duration: Duration) {
+    println!(
+        "{:<20} {:>12} {:>12} {:>12} {:>16}",
+        "benchmark", "mean", "p50", "p99", "items/s"
+    );
+    for result in run_hot_path(batch_size, duration) {
+        println!(
+            "{:<20} {:>12?} {:>12?} {:>12?} {:>16.0}",
+            result.name,
+            result.mean,
+            result.p50,
+            result.p99,
+            result.items_per_sec()
+        );
+    }
+}
+
+async fn fit_landing_model(
+    rpc_url: String,
+    audit_logs: &[PathBuf],
+    tip_program_id: &Pubkey,
+    output: &Path,
+) -> CliResult<()> {
+    let rpc_client = RpcClient::new_with_commitment(rpc_url, CommitmentConfig::confirmed());
+    let tip_accounts: HashSet<Pubkey> = tip_accounts(tip_program_id).into_iter().collect();
+
+    let mut bundles = vec![];
+    for path in audit_logs {
+        bundles.extend(read_audit_log(path)?);
+    }
+    // bundles logged before slots were recorded can't be placed on the curve
+    bundles.retain(|bundle| bundle.slots_to_leader.is_some() && !bundle.transactions.is_empty());
+
+    // the last transaction is the searcher's, if it landed the bundle did
+    let signatures: Vec<Signature> = bundles
+        .iter()
+        .map(|bundle| bundle.transactions.last().unwrap().signatures[0])
+        .collect();
+    let mut landed = Vec::with_capacity(signatures.len());
+    for chunk in signatures.chunks(MAX_GET_SIGNATURE_STATUSES_QUERY_ITEMS) {
+        let statuses = rpc_client
+            .get_signature_statuses_with_history(chunk)
+            .await?
+            .value;
+        landed.extend(
+            statuses
+                .iter()
+                .map(|status| status.as_ref().is_some_and(|s| s.err.is_none())),
+        );
+    }
+
+    let observations: Vec<LandingObservation> = bundles
+        .iter()
+        .zip(landed)
+        .map(|(bundle, landed)| LandingObservation {
+            tip_lamports: tip_lamports(&bundle.transactions, &tip_accounts),
+            slots_to_leader: bundle.slots_to_leader.unwrap_or_default(),
+            landed,
+        })
+        .collect();
+    let Some(model) = LandingModel::fit(&observations) else {
+        return Err(CliError::Other(
+            "no bundles with slot data in the audit logs".into(),
+        ));
+    };
+
+    let num_landed = observations.iter().filter(|o| o.landed).count();
+    println!(
+        "{} bundles, {num_landed} landed ({:.1}%)",
+        observations.len(),
+        num_landed as f64 * 100.0 / observations.len() as f64
+    );
+    let slots = [0, 1, 2, 4, 8];
+    println!(
+        "{:>10} {:>14} {}",
+        "percentile",
+        "tip",
+        slots.map(|s| format!("{:>8}", format!("+{s}"))).join("")
+    );
+    for percentile in [10, 25, 50, 75, 90, 99] {
+        let tip = model.tip_percentiles[percentile];
+        let probabilities: String = slots
+            .iter()
+            .map(|s| {
+                format!(
+                    "{:>7.1}%",
+                    model.estimate_landing_probability(tip, *s) * 100.0
+                )
+            })
+            .collect();
+        println!("{percentile:>10} {tip:>14} {probabilities}");
+    }
+    model.save(output)?;
+    println!("model written to {}", output.display());
+    Ok(())
+}
+
+/// An audit log's name without the rotation suffix, so `audit.jsonl.1` counts as `audit.jsonl`.
+fn audit_log_name(path: &Path) -> String {
+    let name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    match name.rsplit_once('.') {
+        Some((base, suffix)) if suffix.parse::<usize>().is_ok() => base.to_string(),
+        _ => name,
+    }
+}
+
+fn print_canary(probe: &CanaryProbe) {
+    let ms = |ms: Option<u64>| ms.map_or("-".to_string(), |ms| format!("{ms}ms"));
+    let error = match &probe.outcome {
+        CanaryOutcome::AuthFailed(e) | CanaryOutcome::SendFailed(e) | CanaryOutcome::Failed(e) => {
+            format!(": {e}")
+        }
+        CanaryOutcome::NotLanded | CanaryOutcome::Landed => String::new(),
+    };
+    println!(
+        "canary {} {}{error}, sent in {}ms, processed after {}, confirmed after {}, slot {} of leader {}",
+        probe.sequence,
+        probe.outcome.name(),
+        probe.send_ms,
+        ms(probe.processed_ms),
+        ms(probe.confirmed_ms),
+        probe
+            .landed_slot
+            .map_or("-".to_string(), |slot| slot.to_string()),
+        probe.leader.as_deref().unwrap_or("-"),
+    );
+}
+
+fn verify_audit_log(path: &Path, expected_signer: Option<&Pubkey>) -> CliResult<()> {
+    let files = audit_log_files(path);
+    let report = verify_audit_chain(&files)?;
+    if let Some(broken) = &report.broken {
+        return Err(CliError::Other(format!(
+            "audit log chain broken at {}:{}: {}, after {} verified entries",
+            broken.path.display(),
+            broken.line,
+            broken.reason,
+            report.verified
+        )));
+    }
+    let Some(last_hash) = report.last_hash else {
+        return Err(CliError::Other(format!(
+            "{} has no signed entries",
+            path.display()
+        )));
+    };
+    if let Some(expected_signer) = expected_signer {
+        if let Some(signer) = report.signers.iter().find(|s| *s != expected_signer) {
+            return Err(CliError::Other(format!(
+                "entries are signed by {signer}, not {expected_signer}"
+            )));
+        }
+    }
+    let signers: Vec<String> = report.signers.iter().map(Pubkey::to_string).collect();
+    println!(
+        "verified {} entries in {} files, signed by {}",
+        report.verified,
+        files.len(),
+        signers.join(", ")
+    );
+    if report.unsigned > 0 {
+        println!("{} unsigned entries precede the chain", report.unsigned);
+    }
+    if !report.starts_at_genesis {
+        println!("the chain continues from entries in files rotated away");
+    }
+    println!("last hash {last_hash}");
+    Ok(())
+}
+
+async fn efficiency_report(
+    rpc_url: String,
+    audit_logs: &[PathBuf],
+    payer: &Pubkey,
+    tip_program_id: &Pubkey,
+) -> CliResult<()> {
+    let rpc_client = RpcClient::new_with_commitment(rpc_url, CommitmentConfig::confirmed());
+    let tip_accounts: HashSet<Pubkey> = tip_accounts(tip_program_id).into_iter().collect();
+
+    // pipelines write one audit log each, so a log stands for a strategy
+    let mut strategies: BTreeMap<String, Vec<_>> = BTreeMap::new();
+    for path in audit_logs {
+        strategies
+            .entry(audit_log_name(path))
+            .or_default()
+            .extend(read_audit_log(path)?);
+    }
+
+    println!(
+        "{:<24} {:>8} {:>12} {:>14} {:>14} {:>10} {:>12} {:>12}",
+        "audit log", "landed", "avg cu", "avg tip", "avg net", "tip share", "tip/cu", "gross/cu"
+    );
+    for (name, mut bundles) in strategies {
+        bundles.retain(|bundle| !bundle.transactions.is_empty());
+        // the last transaction is the searcher's, if it landed the bundle did
+        let signatures: Vec<Signature> = bundles
+            .iter()
+            .map(|bundle| bundle.transactions.last().unwrap().signatures[0])
+            .collect();
+        let mut landed = Vec::with_capacity(signatures.len());
+        for chunk in signatures.chunks(MAX_GET_SIGNATURE_STATUSES_QUERY_ITEMS) {
+            let statuses = rpc_client
+                .get_signature_statuses_with_history(chunk)
+                .await?
+                .value;
+            landed.extend(
+                statuses
+                    .iter()
+                    .map(|status| status.as_ref().is_some_and(|s| s.err.is_none())),
+            );
+        }
+
+        let mut efficiencies = vec![];
+        for (bundle, _) in bundles.iter().zip(landed).filter(|(_, landed)| *landed) {
+            match landed_bundle_efficiency(&rpc_client, bundle, payer, &tip_accounts).await {
+                Ok(efficiency) => efficiencies.push(efficiency),
+                Err(e) => warn!("skipping bundle {}: {e}", bundle.bundle_uuid),
+            }
+        }
+        if efficiencies.is_empty() {
+            println!("{name:<24} {:>8}", 0);
+            continue;
+        }
+        let summary = EfficiencySummary::from_bundles(&efficiencies);
+        let n = summary.bundles as f64;
+        println!(
+            "{name:<24} {:>8} {:>12.0} {:>14.0} {:>14.0} {:>9.1}% {:>12.4} {:>12.4}",
+            summary.bundles,
+            summary.compute_units as f64 / n,
+            summary.tip_lamports as f64 / n,
+            summary.net_lamports as f64 / n,
+            summary.tip_share() * 100.0,
+            summary.median_tip_lamports_per_cu,
+            summary.median_gross_lamports_per_cu,
+        );
+    }
+    Ok(())
+}
+
+async fn history(
+    rpc_url: String,
+    audit_logs: &[PathBuf],
+    store_path: &Path,
+    limit: usize,
+    tip_program_id: &Pubkey,
+) -> CliResult<()> {
+    let rpc_client = RpcClient::new_with_commitment(rpc_url, CommitmentConfig::confirmed());
+    let tip_accounts: HashSet<Pubkey> = tip_accounts(tip_program_id).into_iter().collect();
+    let mut store = BundleStore::open(store_path)?;
+    for path in audit_logs {
+        let bundles = read_audit_log(path)?;
+        let imported = store.import(&audit_log_name(path), &bundles, &tip_accounts);
+        info!("imported {imported} bundles from {}", path.display());
+    }
+    let resolved = store.resolve(&rpc_client).await?;
+    store.save()?;
+    info!("resolved {resolved} bundles");
+
+    println!(
+        "{:<38} {:<24} {:<8} {:>12} {:>12}",
+        "bundle", "audit log", "status", "submitted", "landed"
+    );
+    let slot = |slot: Option<Slot>| slot.map(|slot| slot.to_string()).unwrap_or_default();
+    for record in store.records().iter().rev().take(limit) {
+        println!(
+            "{:<38} {:<24} {:<8} {:>12} {:>12}",
+            record.bundle_uuid,
+            record.source,
+            record.status,
+            slot(record.submitted_slot),
+            slot(record.landed_slot),
+        );
+        for error in record.errors() {
+            println!("    {error}");
+        }
+    }
+    Ok(())
+}
+
+async fn backfill(
+    rpc_url: String,
+    payer: &Pubkey,
+    store_path: &Path,
+    since: Option<NaiveDate>,
+    max_transactions: usize,
+    tip_program_id: &Pubkey,
+) -> CliResult<()> {
+    let rpc_client = RpcClient::new_with_commitment(rpc_url, CommitmentConfig::confirmed());
+    let tip_accounts: HashSet<Pubkey> = tip_accounts(tip_program_id).into_iter().collect();
+    let mut store = BundleStore::open(store_path)?;
+    let since = since.map(|day| day.and_time(NaiveTime::MIN).and_utc());
+    let backfilled = store
+        .backfill(&rpc_client, payer, &tip_accounts, since, max_transactions)
+        .await?;
+    store.save()?;
     info!(
-        "payer public key: {:?} lamports: {balance:?}",
-        payer_keypair.pubkey(),
+        "backfilled {backfilled} bundles into {}",
+        store.path().display()
     );
+    Ok(())
+}
 
-    let mut bundle_results_subscription = client
-        .subscribe_bundle_results(SubscribeBundleResultsRequest {})
-        .await
-        .expect("subscribe to bundle results")
-        .into_inner();
+fn daily_report(
+    store_path: &Path,
+    audit_logs: &[PathBuf],
+    date: Option<NaiveDate>,
+    format: ReportFormat,
+    output: Option<&Path>,
+) -> CliResult<()> {
+    let store = BundleStore::open(store_path)?;
+    let mut rejections = vec![];
+    for path in audit_logs {
+        rejections.extend(read_audit_rejections(path)?);
+    }
+    let date = date.unwrap_or_else(|| Utc::now().date_naive());
+    let report = DailyReport::build(date, store.records(), &rejections).render(format);
+    match output {
+        Some(path) => {
+            fs::write(path, report)?;
+            info!("wrote {format} report to {}", path.display());
+        }
+        None => print!("{report}"),
+    }
+    Ok(())
+}
+
+fn convert_recording(input: &Path, output: &Path, format: RecordingFormat) -> CliResult<()> {
+    if input == output {
+        return Err(CliError::Config(
+            "--output must differ from --input".to_string(),
+        ));
+    }
+    let reader = RecordingReader::new(BufReader::new(File::open(input)?))?;
+    match (reader.schema_version(), reader.writer()) {
+        (Some(version), Some(writer)) => info!(
+            "reading {} recording, schema version {version}, written by {writer}",
+            reader.format()
+        ),
+        _ => info!("reading {} recording", reader.format()),
+    }
+    let (converted, _) = convert(reader, BufWriter::new(File::create(output)?), format)?;
+    info!(
+        "converted {converted} records to {format} in {}",
+        output.display()
+    );
+    Ok(())
+}
 
-    // // wait for jito-solana leader slot
-    // let mut is_leader_slot = false;
-    // while !is_leader_slot {
-    //     let next_leader = client
-    //         .get_next_scheduled_leader(NextScheduledLeaderRequest {
-    //             regions: regions.clone(),
-    //         })
-    //         .await
-    //         .expect("gets next scheduled leader")
-    //         .into_inner();
-    //     let num_slots = next_leader.next_leader_slot - next_leader.current_slot;
-    //     is_leader_slot = num_slots <= 2;
-    //     info!(
-    //         "next jito leader slot in {num_slots} slots in {}",
-    //         next_leader.next_leader_region
-    //     );
-    //     sleep(Duration::from_millis(500)).await;
-    // }
-
-    // let tip_accounts = tip_accounts.accounts;
-    // let tip_account = Pubkey::from_str(tip_accounts[0].as_str()).unwrap();
-    let tip_account  = Pubkey::from_str("HFqU5x63VTqvQss8hp11i4wVV8bD44PvwucfZ2bU7gRe").unwrap();
-
-    let recipient_pubkey =
-        Pubkey::from_str("GKxpQ3ZSMNSbCDs1RrqhxuTTUfn8xx6faDzTss38mkw3").unwrap();
-    let lamports_to_send = 1_000; // 1 SOL
-
-    // build + sign the transactions
-    let blockhash = rpc_client
-        .get_latest_blockhash()
-        .await
-        .expect("get blockhash");
-
-    let transfer_instruction = system_instruction::transfer(
-        &payer_keypair.pubkey(), // From (payer)
-        &recipient_pubkey,       // To (recipient)
-        lamports_to_send,        // Amount
-    );
-
-    let jito_tip_instruction = transfer(&payer_keypair.pubkey(), &tip_account, tip_lamports);
-
-    let mut transaction = Transaction::new_with_payer(
-        &[transfer_instruction, jito_tip_instruction],
-        Some(&payer_keypair.pubkey()),
-    );
-    transaction.sign(&[&payer_keypair], blockhash);
-    let wire_transaction = bincode::serialize(&transaction).unwrap();
-    let transaction_signature = transaction.signatures[0];
-
-    println!("Sending transaction: {:?}", transaction_signature);
-    // print date and time
-    let now = chrono::Utc::now();
-    println!("Current time is: {}", now);
-
-    let txs: &Vec<Signature> = &vec![transaction.signatures[0]];
-    //let signatures= [wire_transaction];
-
-    let vec_of_vecs: Vec<Vec<u8>> = vec![wire_transaction];
-    let signatures: &[Vec<u8>] = &vec_of_vecs;
-
-    let bundleResult = send_bundle_with_confirmation(
-        &txs,
-        &signatures,
-        &rpc_client,
-        &mut client,
-        &mut bundle_results_subscription,
+async fn leader_stats(
+    store_path: &Path,
+    source: Option<&str>,
+    min_bundles: usize,
+    limit: usize,
+    rpc_url: Option<String>,
+) -> CliResult<()> {
+    let store = BundleStore::open(store_path)?;
+    let records: Vec<_> = store
+        .records()
+        .iter()
+        .filter(|record| source.is_none_or(|source| record.source == source))
+        .collect();
+    let names: HashMap<String, String> = match rpc_url {
+        Some(rpc_url) => fetch_validator_metadata(&RpcClient::new(rpc_url))
+            .await?
+            .into_values()
+            .filter_map(|validator| Some((validator.identity, validator.name?)))
+            .collect(),
+        None => HashMap::new(),
+    };
+
+    let print = |title: &str, stats: Vec<LandingStats>, limit: usize| {
+        println!(
+            "{title:<44} {:>8} {:>8} {:>7} {:>8} {:>9} {:>6}",
+            "bundles", "included", "rate", "dropped", "failed", "top"
+        );
+        for stats in stats
+            .iter()
+            .filter(|stats| stats.bundles >= min_bundles)
+            .take(limit)
+        {
+            let key = match names.get(&stats.key) {
+                Some(name) => format!("{} ({name})", stats.key),
+                None => stats.key.clone(),
+            };
+            println!(
+                "{key:<44} {:>8} {:>8} {:>6.1}% {:>8} {:>9} {:>6}",
+                stats.bundles,
+                stats.included(),
+                stats.inclusion_rate() * 100.0,
+                stats.dropped,
+                stats.failed,
+                stats.top_of_block,
+            );
+        }
+    };
+    print(
+        "leader",
+        leader_stats::by_leader(records.iter().copied()),
+        limit,
+    );
+    println!();
+    print(
+        "region",
+        leader_stats::by_region(records.iter().copied()),
+        usize::MAX,
+    );
+    Ok(())
+}
+
+fn position_report(store_path: &Path, source: Option<&str>) -> CliResult<()> {
+    let store = BundleStore::open(store_path)?;
+    let records = store
+        .records()
+        .iter()
+        .filter(|record| source.is_none_or(|source| record.source == source));
+    let Some(stats) = PositionStats::build(records) else {
+        println!("no landed bundles with a known block position, run `history` first");
+        return Ok(());
+    };
+    println!("Landed bundles     {}", stats.bundles);
+    println!(
+        "Top of block       {} ({:.1}%)",
+        stats.top_of_block,
+        stats.top_of_block_rate() * 100.0
+    );
+    println!(
+        "Block index        p50 {}, p90 {}, max {}",
+        stats.p50_index, stats.p90_index, stats.max_index
+    );
+    println!(
+        "Mean depth         {:.1}% into the block",
+        stats.mean_depth * 100.0
+    );
+    let mean_net = |net: Option<f64>| match net {
+        Some(net) => format!("{:.6} SOL", net / LAMPORTS_PER_SOL as f64),
+        None => "-".to_string(),
+    };
+    println!(
+        "Mean PnL           {} at the top, {} below",
+        mean_net(stats.top_of_block_mean_net_lamports),
+        mean_net(stats.rest_mean_net_lamports)
+    );
+    println!("\n{:<10} {:>8} {:>7}", "index", "bundles", "share");
+    for bucket in &stats.buckets {
+        let range = match bucket.end {
+            Some(end) if end == bucket.start + 1 => bucket.start.to_string(),
+            Some(end) => format!("{}-{}", bucket.start, end - 1),
+            None => format!("{}+", bucket.start),
+        };
+        println!(
+            "{range:<10} {:>8} {:>6.1}%",
+            bucket.bundles,
+            bucket.bundles as f64 / stats.bundles as f64 * 100.0
+        );
+    }
+    Ok(())
+}
+
+// streams pending transactions to `on_transaction` while the capture window is open
+async fn capture_mempool(
+    client: &mut SearcherServiceClient<InterceptedService<Channel, ClientInterceptor>>,
+    regions: Vec<String>,
+    mempool: MempoolArgs,
+    mut on_transaction: impl FnMut(Slot, &VersionedTransaction) -> CliResult<()>,
+) -> CliResult<()> {
+    let window = CaptureWindow {
+        slot_ranges: mempool.slot_ranges,
+        max_slots_to_leader: mempool.max_slots_to_leader,
+    };
+    let msg = if mempool.programs.is_empty() {
+        mempool_subscription::Msg::WlaV0Sub(WriteLockedAccountSubscriptionV0 {
+            accounts: mempool.accounts.iter().map(|pk| pk.to_string()).collect(),
+        })
+    } else {
+        mempool_subscription::Msg::ProgramV0Sub(ProgramSubscriptionV0 {
+            programs: mempool.programs.iter().map(|pk| pk.to_string()).collect(),
+        })
+    };
+    let mut pending_transactions = traced(
+        "SubscribeMempool",
+        MempoolSubscription {
+            regions: regions.clone(),
+            msg: Some(msg),
+        },
+        |request| client.subscribe_mempool(request),
     )
-    .await;
+    .await?
+    .into_inner();
 
-    // check if the bundle was successful
-    match bundleResult {
-        Ok(_) => {
-            println!("Bundle sent successfully");
+    let mut next_leader = NextScheduledLeaderResponse::default();
+    let mut skipped: u64 = 0;
+    let mut tick = interval(Duration::from_secs(1));
+    loop {
+        tokio::select! {
+            _ = tick.tick() => {
+                next_leader = traced(
+                    "GetNextScheduledLeader",
+                    NextScheduledLeaderRequest {
+                        regions: regions.clone(),
+                    },
+                    |request| client.get_next_scheduled_leader(request),
+                )
+                .await?
+                .into_inner();
+                if window.is_finished(next_leader.current_slot) {
+                    info!("past all capture slot ranges, stopping");
+                    break;
+                }
+            }
+            maybe_notification = pending_transactions.next() => {
+                let notification = match maybe_notification {
+                    Some(Ok(notification)) => {
+                        trace_message("SubscribeMempool", &notification);
+                        notification
+                    }
+                    Some(Err(e)) => return Err(e.into()),
+                    None => {
+                        info!("pending transaction stream closed");
+                        break;
+                    }
+                };
+                if !window.is_open(next_leader.current_slot, next_leader.next_leader_slot) {
+                    skipped += notification.transactions.len() as u64;
+                    continue;
+                }
+                for tx in notification.transactions.iter().filter_map(versioned_tx_from_packet) {
+                    on_transaction(next_leader.current_slot, &tx)?;
+                }
+            }
         }
+    }
+    info!("skipped {skipped} transactions outside the capture window");
+    Ok(())
+}
+
+fn transfer_instructions(
+    payer: &Keypair,
+    recipient: &Pubkey,
+    transfer_lamports: u64,
+    message: &str,
+) -> [Instruction; 2] {
+    [
+        transfer(&payer.pubkey(), recipient, transfer_lamports),
+        build_memo(message.as_bytes(), &[]),
+    ]
+}
+
+/// The transactions send-bundle sends ahead of the tip: the instructions of the `--instructions`
+/// spec, or else the transfer and memo.
+fn send_bundle_transactions(
+    payer: &Keypair,
+    recipient: Option<Pubkey>,
+    transfer_lamports: u64,
+    message: Option<&str>,
+    instructions: Option<&Path>,
+    tip_account: &Pubkey,
+) -> CliResult<Vec<Vec<Instruction>>> {
+    if let Some(path) = instructions {
+        let placeholders = Placeholders::new(payer.pubkey()).with_tip_account(*tip_account);
+        return Ok(BundleTemplate::load(path)?.resolve(&placeholders)?);
+    }
+    let (Some(recipient), Some(message)) = (recipient, message) else {
+        unreachable!("clap requires --recipient and --message without --instructions");
+    };
+    Ok(vec![transfer_instructions(
+        payer,
+        &recipient,
+        transfer_lamports,
+        message,
+    )
+    .to_vec()])
+}
+
+// the tip lives in its own transaction so the rest of the bundle can be sent without it if the
+// fallback kicks in
+fn transfer_bundle(
+    payer: &Arc<Keypair>,
+    transactions: &[Vec<Instruction>],
+    tip_account: &Pubkey,
+    tip_lamports: u64,
+    tip_split: &TipSplit,
+) -> BundleBuilder {
+    transactions
+        .iter()
+        .fold(BundleBuilder::new(), |bundle_builder, instructions| {
+            bundle_builder
+                .add_transaction(TransactionSpec::new(instructions.clone(), payer.clone()))
+        })
+        .add_transaction(TransactionSpec::new(
+            tip_split.transfers(&payer.pubkey(), tip_account, tip_lamports),
+            payer.clone(),
+        ))
+}
+
+/// Simulates the bundle and prints which transactions depend on state changed by earlier ones,
+/// and the balance changes it makes.
+fn print_compute_profiles(profiles: &[TransactionProfile]) {
+    for (i, profile) in profiles.iter().enumerate() {
+        match profile.units_consumed {
+            Some(units) => println!(
+                "tx {i}: {units} of {} compute units ({:.1}%)",
+                profile.unit_limit,
+                units as f64 * 100.0 / profile.unit_limit.max(1) as f64
+            ),
+            None => println!(
+                "tx {i}: compute units consumed unknown, limit {}",
+                profile.unit_limit
+            ),
+        }
+        for instruction in &profile.instructions {
+            for invocation in &instruction.invocations {
+                let program = invocation.program_id.to_string();
+                let name = program_name(&invocation.program_id).unwrap_or_default();
+                let units = invocation
+                    .consumed_units
+                    .map_or("builtin".to_string(), |units| units.to_string());
+                let label = match invocation.depth {
+                    1 => format!("  ix {} {program}", instruction.index),
+                    depth => format!("{:width$}-> {program}", "", width = 2 * depth + 1),
+                };
+                let failed = if invocation.failed { " (failed)" } else { "" };
+                println!(
+                    "{}",
+                    format!("{label:<60} {units:>8} {name}{failed}").trim_end()
+                );
+            }
+        }
+        if let Some(units) = profile.unattributed_units().filter(|units| *units > 0) {
+            println!("  {:<58} {units:>8}", "builtin programs and overhead");
+        }
+        if profile.logs_truncated {
+            println!("  logs truncated, later instructions are missing");
+        }
+        if let Some(error) = &profile.error {
+            println!("  failed simulation: {error}");
+        }
+        if profile.exceeds_limit() {
+            println!("  ran out of compute, raise the compute unit limit or split the transaction");
+        }
+    }
+}
+
+/// Verifies the post-conditions of a landed bundle, failing if any didn't hold.
+async fn check_post_conditions(
+    rpc_client: &RpcClient,
+    signatures: &[Signature],
+    conditions: &[PostCondition],
+) -> CliResult<()> {
+    let violations = verify_post_conditions(rpc_client, signatures, conditions).await?;
+    if violations.is_empty() {
+        if !conditions.is_empty() {
+            println!("all {} post-conditions held", conditions.len());
+        }
+        return Ok(());
+    }
+    let bundle = signatures
+        .first()
+        .map(ToString::to_string)
+        .unwrap_or_default();
+    report_violations(&bundle, &violations);
+    Err(CliError::Other(format!(
+        "{} of {} post-conditions didn't hold",
+        violations.len(),
+        conditions.len()
+    )))
+}
+
+async fn print_bundle_conflicts(
+    rpc_client: &RpcClient,
+    payer: &Keypair,
+    build_bundle: impl Fn(&Hash) -> Vec<VersionedTransaction>,
+) -> CliResult<()> {
+    // every transaction pays fees from the payer, that dependency is expected
+    let ignored = HashSet::from([payer.pubkey()]);
+    let (blockhash, _) = rpc_client
+        .get_latest_blockhash_with_commitment(CommitmentConfig::confirmed())
+        .await?;
+    let simulation =
+        simulate_bundle_conflicts(rpc_client, &build_bundle(&blockhash), &ignored).await?;
+    for (i, err) in simulation.errors.iter().enumerate() {
+        if let Some(err) = err {
+            println!("tx {i} failed simulation: {err}");
+        }
+    }
+    for conflict in &simulation.conflicts {
+        println!("conflict: {conflict}");
+    }
+    if simulation.conflicts.is_empty() {
+        println!("no conflicts between bundle transactions");
+    }
+    for (account, delta) in &simulation.report.lamport_deltas {
+        println!("{account}: {delta:+} lamports");
+    }
+    for delta in &simulation.report.token_deltas {
+        println!(
+            "{} (mint {}, owner {}): {:+} tokens",
+            delta.token_account, delta.mint, delta.owner, delta.amount
+        );
+    }
+    Ok(())
+}
+
+/// Runs the commands the public JSON-RPC bundle API supports, for searchers without an approved
+/// auth keypair.
+async fn run_json_rpc(args: Args) -> CliResult<()> {
+    let client = JsonRpcBundleClient::new(&args.block_engine_url);
+    let confirmation = args.spend_confirmation();
+    let expected_cluster = args.expected_cluster();
+    match args.command {
+        Commands::TipAccounts => {
+            info!("{:?}", client.get_tip_accounts().await?);
+            Ok(())
+        }
+        Commands::SendBundle {
+            rpc_url,
+            payer,
+            message,
+            recipient,
+            transfer_lamports,
+            lamports,
+            tip_account,
+            tip_share,
+            max_bundle_attempts,
+            rpc_fallback_after,
+            wait_for_leader,
+            check_conflicts,
+            profile_compute,
+            post_condition,
+            instructions,
+            ..
+        } => {
+            if rpc_fallback_after.is_some() || wait_for_leader.is_some() {
+                return Err(CliError::Config(
+                    "--rpc-fallback-after and --wait-for-leader need --transport grpc".into(),
+                ));
+            }
+            let tip_split = TipSplit::new(tip_share, MIN_TIP_LAMPORTS)?;
+            tip_split.check_floor(lamports)?;
+            confirmation.confirm(&Spend {
+                action: "send bundle",
+                recipient: recipient.as_ref(),
+                amount_lamports: recipient.map_or(0, |_| transfer_lamports),
+                tip_lamports: lamports,
+            })?;
+            let payer_keypair = Arc::new(read_keypair(&payer)?);
+            let rpc_client = RpcClient::new_with_commitment(rpc_url, CommitmentConfig::confirmed());
+            check_rpc_cluster(&rpc_client, expected_cluster).await?;
+            let transactions = send_bundle_transactions(
+                &payer_keypair,
+                recipient,
+                transfer_lamports,
+                message.as_deref(),
+                instructions.as_deref(),
+                &tip_account,
+            )?;
+            let bundle_builder = post_condition.into_iter().fold(
+                transfer_bundle(
+                    &payer_keypair,
+                    &transactions,
+                    &tip_account,
+                    lamports,
+                    &tip_split,
+                ),
+                BundleBuilder::with_post_condition,
+            );
+            bundle_builder.validate()?;
+            let build_bundle = |blockhash: &Hash| -> Vec<VersionedTransaction> {
+                bundle_builder.build(blockhash).expect("builds bundle")
+            };
+            if check_conflicts {
+                print_bundle_conflicts(&rpc_client, &payer_keypair, build_bundle).await?;
+            }
+            if profile_compute {
+                let blockhash = rpc_client.get_latest_blockhash().await?;
+                print_compute_profiles(
+                    &profile_bundle(&rpc_client, &build_bundle(&blockhash)).await?,
+                );
+            }
+
+            let mut last_error = CliError::Other("no bundle attempts made".into());
+            for attempt in 1..=max_bundle_attempts {
+                let bundle = PreSignedBundle::build(&rpc_client, build_bundle).await?;
+                let signatures = bundle.signatures();
+                let wire_transactions: Vec<Vec<u8>> = bundle
+                    .transactions
+                    .iter()
+                    .map(|tx| bincode::serialize(tx).expect("serializes"))
+                    .collect();
+                info!("attempt {attempt}/{max_bundle_attempts}, sending bundle: {signatures:?}");
+                match client
+                    .send_bundle_with_confirmation(&signatures, &wire_transactions, &rpc_client)
+                    .await
+                {
+                    Ok(()) => {
+                        println!("Bundle sent successfully");
+                        return check_post_conditions(
+                            &rpc_client,
+                            &signatures,
+                            bundle_builder.post_conditions(),
+                        )
+                        .await;
+                    }
+                    Err(e) => {
+                        println!("Bundle failed: {:?}", e);
+                        last_error = e.into();
+                    }
+                }
+            }
+            Err(last_error)
+        }
+        _ => Err(CliError::Config(
+            "only send-bundle and tip-accounts support --transport json-rpc".into(),
+        )),
+    }
+}
+
+fn manage_addresses(config: Option<&Path>, command: &AddressesCommand) -> CliResult<()> {
+    let mut book = AddressBook::load(config)?;
+    match command {
+        AddressesCommand::List => {
+            if book.addresses().is_empty() {
+                println!("no named addresses in {}", book.path().display());
+            }
+            for (name, address) in book.addresses() {
+                println!("@{name:<24} {address}");
+            }
+            return Ok(());
+        }
+        AddressesCommand::Add {
+            name,
+            address,
+            force,
+        } => {
+            if let Some(existing) = book.get(name).filter(|existing| *existing != address) {
+                if !force {
+                    return Err(CliError::Config(format!(
+                        "@{name} is already {existing}, pass --force to replace it"
+                    )));
+                }
+            }
+            book.insert(name, *address)?;
+            println!("@{name} = {address}");
+        }
+        AddressesCommand::Remove { name } => {
+            let Some(address) = book.remove(name) else {
+                return Err(CliError::Config(format!(
+                    "no address named {name} in {}",
+                    book.path().display()
+                )));
+            };
+            println!("removed @{name} ({address})");
+        }
+    }
+    book.save()?;
+    info!("saved {}", book.path().display());
+    Ok(())
+}
+
+/// Sends `lamports` from the treasury to `recipient` in a single transaction bundle that also
+/// tips, returning the bundle's uuid.
+async fn send_top_up(
+    client: &mut SearcherServiceClient<InterceptedService<Channel, ClientInterceptor>>,
+    rpc_client: &RpcClient,
+    treasury: &Keypair,
+    recipient: &Pubkey,
+    lamports: u64,
+    tip_account: &Pubkey,
+    tip_lamports: u64,
+) -> CliResult<String> {
+    let treasury_balance = rpc_client.get_balance(&treasury.pubkey()).await?;
+    if treasury_balance < lamports.saturating_add(tip_lamports) {
+        return Err(CliError::Other(format!(
+            "treasury {} holds {}, not enough for the top-up and tip",
+            treasury.pubkey(),
+            format_lamports(treasury_balance, None)
+        )));
+    }
+    let blockhash = rpc_client.get_latest_blockhash().await?;
+    let tx = VersionedTransaction::from(Transaction::new_signed_with_payer(
+        &[
+            transfer(&treasury.pubkey(), recipient, lamports),
+            transfer(&treasury.pubkey(), tip_account, tip_lamports),
+        ],
+        Some(&treasury.pubkey()),
+        &[treasury],
+        blockhash,
+    ));
+    let response = send_bundle_no_wait(&[bincode::serialize(&tx).expect("serializes")], client)
+        .await?
+        .into_inner();
+    Ok(response.uuid)
+}
+
+/// Fails if the RPC node isn't on the cluster bundles are sent to, see [`check_cluster`].
+async fn check_rpc_cluster(rpc_client: &RpcClient, expected: Option<Cluster>) -> CliResult<()> {
+    let Some(expected) = expected else {
+        warn!("block engine cluster unknown, not checking the rpc node's cluster, see --cluster");
+        return Ok(());
+    };
+    check_cluster(rpc_client, expected).await?;
+    Ok(())
+}
+
+fn read_keypair(path: &Path) -> CliResult<Keypair> {
+    read_keypair_file(path)
+        .map_err(|e| CliError::Config(format!("failed to read keypair {}: {e}", path.display())))
+}
+
+/// Loads the address book before the real parse so pubkey flags can resolve @name. The first
+/// pass only looks for --config, everything else is validated by the second.
+fn load_address_book() -> CliResult<()> {
+    let config = Args::command()
+        .ignore_errors(true)
+        .try_get_matches()
+        .ok()
+        .and_then(|matches| matches.get_one::<PathBuf>("config").cloned());
+    AddressBook::load(config.as_deref())?.install();
+    Ok(())
+}
+
+fn main() {
+    if let Err(e) = load_address_book() {
+        eprintln!("{e}");
+        process::exit(e.exit_code() as i32);
+    }
+    let args = match Args::try_parse() {
+        Ok(args) => args,
+        Err(e) => {
+            // clap exits with 2 on usage errors, which is taken by auth failures
+            let _ = e.print();
+            let exit_code = if e.use_stderr() {
+                ExitCode::Config
+            } else {
+                ExitCode::Ok
+            };
+            process::exit(exit_code as i32);
+        }
+    };
+    if env::var("RUST_LOG").is_err() {
+        env::set_var("RUST_LOG", "info")
+    }
+    env_logger::builder()
+        .format_timestamp(Some(TimestampPrecision::Micros))
+        .init();
+
+    let runtime = match args.runtime_config() {
+        Ok(config) => config.build().expect("builds tokio runtime"),
         Err(e) => {
-            println!("Bundle failed: {:?}", e);
+            error!("{e}");
+            process::exit(e.exit_code() as i32);
+        }
+    };
+    if let Err(e) = runtime.block_on(run(args)) {
+        error!("{e}");
+        process::exit(e.exit_code() as i32);
+    }
+}
+
+async fn run(args: Args) -> CliResult<()> {
+    if let Commands::SendBundle {
+        num_txs: Some(_), ..
+    } = &args.command
+    {
+        warn!("--num-txs is deprecated and ignored, the bundle is built from the transfer or --instructions");
+    }
+    // commands that don't talk to the block engine
+    if let Commands::Addresses { command } = &args.command {
+        return manage_addresses(args.config.as_deref(), command);
+    }
+    if let Commands::Bench { batch_size, secs } = &args.command {
+        bench(*batch_size, Duration::from_secs(*secs));
+        return Ok(());
+    }
+    if let Commands::DecodeTx {
+        transaction,
+        signature,
+        rpc_url,
+        decoders,
+        fetch_idl,
+    } = &args.command
+    {
+        return decode_tx(
+            transaction.clone(),
+            *signature,
+            rpc_url.clone(),
+            decoders,
+            *fetch_idl,
+        )
+        .await;
+    }
+    if let Commands::ProfileCompute {
+        rpc_url,
+        transactions,
+    } = &args.command
+    {
+        let transactions = transactions
+            .iter()
+            .map(|tx| decode_transaction(tx))
+            .collect::<Result<Vec<_>, _>>()?;
+        let rpc_client =
+            RpcClient::new_with_commitment(rpc_url.clone(), CommitmentConfig::confirmed());
+        print_compute_profiles(&profile_bundle(&rpc_client, &transactions).await?);
+        return Ok(());
+    }
+    if let Commands::DecodeAccount {
+        address,
+        rpc_url,
+        decoders,
+    } = &args.command
+    {
+        return decode_account(address, rpc_url.clone(), decoders).await;
+    }
+    if let Commands::Pools {
+        rpc_url,
+        pairs,
+        dexes,
+    } = &args.command
+    {
+        return list_pools(rpc_url.clone(), pairs, dexes).await;
+    }
+    if let Commands::CreateTokenAccounts {
+        rpc_url,
+        payer,
+        owner,
+        mints,
+        dry_run,
+    } = &args.command
+    {
+        return create_token_accounts(
+            rpc_url.clone(),
+            payer,
+            *owner,
+            mints,
+            *dry_run,
+            &args.spend_confirmation(),
+        )
+        .await;
+    }
+    if let Commands::WrapSol {
+        rpc_url,
+        payer,
+        amount,
+    } = &args.command
+    {
+        return wrap_sol(rpc_url.clone(), payer, *amount, &args.spend_confirmation()).await;
+    }
+    if let Commands::UnwrapSol { rpc_url, payer } = &args.command {
+        return unwrap_sol(rpc_url.clone(), payer).await;
+    }
+    if let Commands::Sweep {
+        rpc_url,
+        wallets,
+        treasury,
+        float,
+        min_sweep,
+        tokens,
+        interval_secs,
+        dry_run,
+    } = &args.command
+    {
+        let rpc_client =
+            RpcClient::new_with_commitment(rpc_url.clone(), CommitmentConfig::confirmed());
+        let mut swept_tokens = vec![];
+        for token in tokens {
+            swept_tokens.push(SweptToken::fetch(&rpc_client, token).await?);
         }
+        let config = SweepConfig {
+            treasury: *treasury,
+            float_lamports: *float,
+            min_sweep_lamports: *min_sweep,
+            tokens: swept_tokens,
+        };
+        let wallets = wallets
+            .iter()
+            .map(|path| read_keypair(path))
+            .collect::<CliResult<Vec<_>>>()?;
+        let confirmation = args.spend_confirmation();
+        let Some(interval_secs) = interval_secs else {
+            return sweep(&rpc_client, &wallets, &config, *dry_run, &confirmation).await;
+        };
+        let mut ticker = interval(Duration::from_secs(*interval_secs));
+        loop {
+            ticker.tick().await;
+            if let Err(e) = sweep(&rpc_client, &wallets, &config, *dry_run, &confirmation).await {
+                error!("sweep failed: {e}");
+            }
+        }
+    }
+    if let Commands::LandingModel {
+        rpc_url,
+        audit_log,
+        tip_program_id,
+        output,
+    } = &args.command
+    {
+        return fit_landing_model(rpc_url.clone(), audit_log, tip_program_id, output).await;
+    }
+    if let Commands::EfficiencyReport {
+        rpc_url,
+        audit_log,
+        payer,
+        tip_program_id,
+    } = &args.command
+    {
+        return efficiency_report(rpc_url.clone(), audit_log, payer, tip_program_id).await;
+    }
+    if let Commands::History {
+        rpc_url,
+        audit_log,
+        store,
+        limit,
+        tip_program_id,
+    } = &args.command
+    {
+        return history(rpc_url.clone(), audit_log, store, *limit, tip_program_id).await;
+    }
+    if let Commands::VerifyAuditLog { audit_log, signer } = &args.command {
+        return verify_audit_log(audit_log, signer.as_ref());
+    }
+    if let Commands::Backfill {
+        rpc_url,
+        payer,
+        store,
+        since,
+        max_transactions,
+        tip_program_id,
+    } = &args.command
+    {
+        return backfill(
+            rpc_url.clone(),
+            payer,
+            store,
+            *since,
+            *max_transactions,
+            tip_program_id,
+        )
+        .await;
+    }
+    if let Commands::LeaderStats {
+        store,
+        source,
+        min_bundles,
+        limit,
+        rpc_url,
+    } = &args.command
+    {
+        return leader_stats(
+            store,
+            source.as_deref(),
+            *min_bundles,
+            *limit,
+            rpc_url.clone(),
+        )
+        .await;
+    }
+    if let Commands::Report { command } = &args.command {
+        return match command {
+            ReportCommand::Daily {
+                store,
+                audit_log,
+                date,
+                format,
+                output,
+            } => daily_report(store, audit_log, *date, *format, output.as_deref()),
+            ReportCommand::Positions { store, source } => position_report(store, source.as_deref()),
+        };
+    }
+    if let Commands::ConvertRecording {
+        input,
+        output,
+        format,
+    } = &args.command
+    {
+        return convert_recording(input, output, *format);
+    }
+    if let Commands::ClockCheck {
+        ntp_server,
+        rpc_url,
+        samples,
+        max_offset_ms,
+    } = &args.command
+    {
+        return clock_check(ntp_server, rpc_url.clone(), *samples, *max_offset_ms).await;
+    }
+    if let Commands::Doctor {
+        rpc_url,
+        ws_url,
+        payer,
+        min_payer_balance,
+        max_clock_skew_secs,
+    } = &args.command
+    {
+        let failed = run_doctor(DoctorConfig {
+            block_engine_url: &args.block_engine_url,
+            keypair_path: args.keypair_path.as_deref(),
+            rpc_url: rpc_url.clone(),
+            ws_url: ws_url.clone(),
+            payer: payer.as_deref(),
+            min_payer_balance: *min_payer_balance,
+            max_clock_skew: Duration::from_secs(*max_clock_skew_secs),
+        })
+        .await;
+        if failed > 0 {
+            return Err(CliError::Other(format!("{failed} checks failed")));
+        }
+        return Ok(());
+    }
+
+    let mut validator = ConfigValidator::new();
+    validator
+        .url(
+            "--block-engine-url",
+            &args.block_engine_url,
+            &["http", "https"],
+        )
+        .regions("--regions", &args.regions);
+    match (args.transport, &args.keypair_path) {
+        (Transport::Grpc, Some(path)) => {
+            validator.keypair_file("--keypair-path", path);
+        }
+        (Transport::Grpc, None) => {
+            validator.problem("--keypair-path", "required with --transport grpc");
+        }
+        (Transport::JsonRpc, _) => {}
+    }
+    validator.finish()?;
+
+    if args.transport == Transport::JsonRpc {
+        return run_json_rpc(args).await;
     }
-    
+    let keypair = Arc::new(read_keypair(
+        args.keypair_path.as_deref().expect("validated"),
+    )?);
+    let price_feed = args.usd_price_account.map(|price_account| {
+        SolPriceFeed::new(price_account, Duration::from_secs(args.usd_price_ttl_secs))
+    });
+    let mut headers = ClientHeaders::default()
+        .with_client_name(env!("CARGO_PKG_NAME"))
+        .expect("valid client name");
+    for pair in &args.client_header {
+        headers
+            .insert_pair(pair)
+            .map_err(|e| CliError::Config(format!("--client-header: {e}")))?;
+    }
+    if let Some(path) = &args.trace_grpc {
+        grpc_trace::enable(path)?;
+        info!("tracing block engine calls to {}", path.display());
+    }
+    let mut client =
+        get_searcher_client_with_headers(&args.block_engine_url, &keypair, headers).await?;
+    let confirmation = args.spend_confirmation();
+    let expected_cluster = args.expected_cluster();
+
+    match args.command {
+        Commands::NextScheduledLeader => {
+            print_next_leader_info(&mut client, args.regions).await?;
+        }
+        Commands::ConnectedLeaders => {
+            let connected_leaders = traced(
+                "GetConnectedLeadersRegioned",
+                ConnectedLeadersRegionedRequest {
+                    regions: args.regions,
+                },
+                |request| client.get_connected_leaders_regioned(request),
+            )
+            .await?
+            .into_inner();
+            info!("{connected_leaders:?}");
+        }
+        Commands::ConnectedLeadersInfo { rpc_url } => {
+            let connected_leaders_response = traced(
+                "GetConnectedLeadersRegioned",
+                ConnectedLeadersRegionedRequest {
+                    regions: args.regions,
+                },
+                |request| client.get_connected_leaders_regioned(request),
+            )
+            .await?
+            .into_inner();
+            let connected_validators = connected_leaders_response.connected_validators;
+
+            let rpc_client = RpcClient::new(rpc_url);
+            let validator_metadata = fetch_validator_metadata(&rpc_client).await?;
+
+            let total_activated_stake: u64 = validator_metadata
+                .values()
+                .map(|validator| validator.activated_stake)
+                .sum();
+
+            let mut total_activated_connected_stake = 0;
+            for validator in validator_metadata.values() {
+                if connected_validators.contains_key(&validator.identity) && !validator.delinquent {
+                    total_activated_connected_stake += validator.activated_stake;
+                    info!(
+                        "connected_leader: {}, name: {}, website: {}, commission: {}%, stake: {:.2}%",
+                        validator.identity,
+                        validator.display_name(),
+                        validator.website.as_deref().unwrap_or("-"),
+                        validator
+                            .commission
+                            .map(|c| c.to_string())
+                            .unwrap_or_else(|| "-".into()),
+                        (validator.activated_stake * 100) as f64 / total_activated_stake as f64
+                    );
+                }
+            }
+            info!(
+                "total stake for block engine: {:.2}%",
+                (total_activated_connected_stake * 100) as f64 / total_activated_stake as f64
+            );
+        }
+        Commands::WatchBalances {
+            rpc_url,
+            accounts,
+            interval_secs,
+            treasury,
+        } => {
+            let mut watcher = BalanceWatcher::new(BalanceWatchConfig::load(&accounts)?)?;
+            let treasury = match (watcher.top_up_config(), treasury) {
+                (Some(top_up), Some(treasury)) => {
+                    let treasury = read_keypair(&treasury)?;
+                    confirmation.confirm(&Spend {
+                        action: "top up automatically, per top-up",
+                        recipient: watcher.top_up_address().as_ref(),
+                        amount_lamports: top_up.max_lamports,
+                        tip_lamports: top_up.tip_lamports,
+                    })?;
+                    Some(treasury)
+                }
+                (None, Some(_)) => {
+                    return Err(CliError::Config(
+                        "--treasury needs a top_up section in the accounts config".into(),
+                    ))
+                }
+                (Some(_), None) => {
+                    warn!("no --treasury given, only alerting on low balances");
+                    None
+                }
+                (None, None) => None,
+            };
+            let rpc_client = RpcClient::new_with_commitment(rpc_url, CommitmentConfig::confirmed());
+            check_rpc_cluster(&rpc_client, expected_cluster).await?;
+            let tip_accounts = tip_accounts(&TIP_PAYMENT_PROGRAM_ID);
+            info!("watching {} accounts", watcher.addresses().len());
+
+            let mut tick = interval(Duration::from_secs(interval_secs));
+            loop {
+                tick.tick().await;
+                let accounts = match rpc_client
+                    .get_multiple_accounts_with_commitment(
+                        watcher.addresses(),
+                        CommitmentConfig::confirmed(),
+                    )
+                    .await
+                {
+                    Ok(response) => response.value,
+                    Err(e) => {
+                        warn!("failed to fetch watched accounts: {e}");
+                        continue;
+                    }
+                };
+                for alert in watcher.observe(&accounts) {
+                    match alert.kind {
+                        AlertKind::Recovered => info!("{alert}"),
+                        AlertKind::Low | AlertKind::Missing => error!("{alert}"),
+                    }
+                }
 
-    let now = chrono::Utc::now();
-    println!("Current time is: {}", now);
-}
\ No newline at end of file
+                let (Some(treasury), Some((recipient, lamports))) =
+                    (&treasury, watcher.top_up_due(Instant::now()))
+                else {
+                    continue;
+                };
+                let tip_lamports = watcher.top_up_config().expect("due").tip_lamports;
+                watcher.record_top_up(Instant::now());
+                let tip_account = tip_accounts[rand::random::<usize>() % tip_accounts.len()];
+                match send_top_up(
+                    &mut client,
+                    &rpc_client,
+                    treasury,
+                    &recipient,
+                    lamports,
+                    &tip_account,
+                    tip_lamports,
+                )
+                .await
+                {
+                    Ok(uuid) => info!(
+                        "sent top-up of {} to {recipient} from treasury, bundle {uuid}",
+                        format_lamports(lamports, None)
+                    ),
+                    Err(e) => error!("failed to top up {recipient}: {e}"),
+                }
+            }
+        }
+        Commands::TipMonitor {
+            rpc_url,
+            interval_secs,
+        } => {
+            let tip_accounts: Vec<Pubkey> =
+                traced("GetTipAccounts", GetTipAccountsRequest {}, |request| {
+                    client.get_tip_accounts(request)
+                })
+                .await?
+                .into_inner()
+                .accounts
+                .iter()
+                .map(|account| {
+                    Pubkey::from_str(account)
+                        .map_err(|e| CliError::Other(format!("invalid tip account {account}: {e}")))
+                })
+                .collect::<CliResult<_>>()?;
+            info!("monitoring tip accounts: {tip_accounts:?}");
+
+            let rpc_client = RpcClient::new_with_commitment(rpc_url, CommitmentConfig::confirmed());
+            let mut monitor = TipAccountMonitor::default();
+            let mut tick = interval(Duration::from_secs(interval_secs));
+            loop {
+                tick.tick().await;
+                let response = match rpc_client
+                    .get_multiple_accounts_with_commitment(
+                        &tip_accounts,
+                        CommitmentConfig::confirmed(),
+                    )
+                    .await
+                {
+                    Ok(response) => response,
+                    Err(e) => {
+                        warn!("failed to fetch tip accounts: {e}");
+                        continue;
+                    }
+                };
+                let balances: Vec<(Pubkey, u64)> = tip_accounts
+                    .iter()
+                    .zip(response.value.iter())
+                    .map(|(pubkey, account)| {
+                        (*pubkey, account.as_ref().map(|a| a.lamports).unwrap_or(0))
+                    })
+                    .collect();
+                let Some(sample) = monitor.observe(response.context.slot, &balances) else {
+                    continue;
+                };
+                println!(
+                    "slot {}: {} lamports tipped over {} slots ({:.0}/slot, running avg {:.0}/slot)",
+                    response.context.slot,
+                    sample.inflow_lamports,
+                    sample.slots_elapsed,
+                    sample.lamports_per_slot(),
+                    monitor.average_lamports_per_slot(),
+                );
+                for (pubkey, lamports) in &sample.payouts {
+                    println!("  payout from {pubkey}: {lamports} lamports");
+                }
+            }
+        }
+        Commands::LatencyReport {
+            samples,
+            interval_ms,
+        } => {
+            let rpc_latency = RpcLatencyTracker::default();
+            for _ in 0..samples {
+                let regions = args.regions.clone();
+                let _ = rpc_latency
+                    .time(
+                        "get_next_scheduled_leader",
+                        traced(
+                            "GetNextScheduledLeader",
+                            NextScheduledLeaderRequest {
+                                regions: regions.clone(),
+                            },
+                            |request| client.get_next_scheduled_leader(request),
+                        ),
+                    )
+                    .await;
+                let _ = rpc_latency
+                    .time(
+                        "get_connected_leaders",
+                        traced(
+                            "GetConnectedLeaders",
+                            ConnectedLeadersRequest {},
+                            |request| client.get_connected_leaders(request),
+                        ),
+                    )
+                    .await;
+                let _ = rpc_latency
+                    .time(
+                        "get_connected_leaders_regioned",
+                        traced(
+                            "GetConnectedLeadersRegioned",
+                            ConnectedLeadersRegionedRequest { regions },
+                            |request| client.get_connected_leaders_regioned(request),
+                        ),
+                    )
+                    .await;
+                let _ = rpc_latency
+                    .time(
+                        "get_tip_accounts",
+                        traced("GetTipAccounts", GetTipAccountsRequest {}, |request| {
+                            client.get_tip_accounts(request)
+                        }),
+                    )
+                    .await;
+                let _ = rpc_latency
+                    .time(
+                        "get_regions",
+                        traced("GetRegions", GetRegionsRequest {}, |request| {
+                            client.get_regions(request)
+                        }),
+                    )
+                    .await;
+                sleep(Duration::from_millis(interval_ms)).await;
+            }
+
+            println!(
+                "{:<32} {:>6} {:>8} {:>10} {:>10} {:>10} {:>10}",
+                "method", "calls", "errors", "p50 ms", "p90 ms", "p99 ms", "max ms"
+            );
+            for summary in rpc_latency.summaries() {
+                println!(
+                    "{:<32} {:>6} {:>7.1}% {:>10.1} {:>10.1} {:>10.1} {:>10.1}",
+                    summary.method,
+                    summary.calls,
+                    summary.error_rate() * 100.0,
+                    summary.p50_us as f64 / 1_000.0,
+                    summary.p90_us as f64 / 1_000.0,
+                    summary.p99_us as f64 / 1_000.0,
+                    summary.max_us as f64 / 1_000.0,
+                );
+            }
+        }
+        Commands::Canary {
+            rpc_url,
+            payer,
+            tip_lamports,
+            tip_program_id,
+            interval_secs,
+            land_timeout_secs,
+            count,
+            output,
+        } => {
+            let rpc_client = RpcClient::new_with_commitment(rpc_url, CommitmentConfig::confirmed());
+            check_rpc_cluster(&rpc_client, expected_cluster).await?;
+            let config = CanaryConfig {
+                payer: Arc::new(read_keypair(&payer)?),
+                tip_accounts: tip_accounts(&tip_program_id),
+                tip_lamports,
+                land_timeout: Duration::from_secs(land_timeout_secs),
+            };
+            let mut output = output
+                .map(|path| fs::OpenOptions::new().create(true).append(true).open(path))
+                .transpose()?;
+            let (mut sent, mut landed) = (0, 0);
+            while count.is_none_or(|count| sent < count) {
+                let started = Instant::now();
+                match send_canary(&mut client, &rpc_client, &config, sent).await {
+                    Ok(probe) => {
+                        print_canary(&probe);
+                        probe.report();
+                        landed += u64::from(probe.landed());
+                        if let Some(output) = &mut output {
+                            let line = serde_json::to_string(&probe).expect("serializes");
+                            writeln!(output, "{line}")?;
+                        }
+                    }
+                    Err(e) => warn!("canary {sent} not sent: {e}"),
+                }
+                sent += 1;
+                if count != Some(sent) {
+                    let interval = Duration::from_secs(interval_secs);
+                    sleep(interval.saturating_sub(started.elapsed())).await;
+                }
+            }
+            println!("{landed} of {sent} canaries landed");
+            if landed == 0 {
+                return Err(CliError::Timeout(format!(
+                    "none of the {sent} canaries landed"
+                )));
+            }
+        }
+        Commands::Watch { mempool, decoders } => {
+            let registry = load_decoders(&decoders)?;
+            capture_mempool(&mut client, args.regions, mempool, |slot, tx| {
+                println!("slot {slot} tx sig: {:?}", tx.signatures[0]);
+                print!("{}", registry.describe_transaction(tx));
+                Ok(())
+            })
+            .await?;
+        }
+        Commands::RecordMempool { mempool, output } => {
+            let sink = RecordSink::open(&output).await?;
+            let (sender, receiver) = unbounded_channel();
+            // the sink writes off the capture loop, which stops once the sink fails
+            let writer = tokio::spawn(sink.run(receiver));
+            let captured = capture_mempool(&mut client, args.regions, mempool, |slot, tx| {
+                let record = Recorded::mempool_transaction(Utc::now(), slot, tx.clone()).to_json();
+                sender
+                    .send(record.to_string().into_bytes())
+                    .map_err(|_| CliError::Other("record sink stopped".to_string()))
+            })
+            .await;
+            drop(sender);
+            let recorded = writer
+                .await
+                .map_err(|e| CliError::Other(format!("record sink panicked: {e}")))??;
+            captured?;
+            info!("recorded {recorded} transactions to {output}");
+        }
+        Commands::DecodeTx { .. }
+        | Commands::DecodeAccount { .. }
+        | Commands::ProfileCompute { .. }
+        | Commands::Pools { .. }
+        | Commands::Doctor { .. }
+        | Commands::ClockCheck { .. }
+        | Commands::CreateTokenAccounts { .. }
+        | Commands::WrapSol { .. }
+        | Commands::UnwrapSol { .. }
+        | Commands::Sweep { .. }
+        | Commands::LandingModel { .. }
+        | Commands::EfficiencyReport { .. }
+        | Commands::History { .. }
+        | Commands::VerifyAuditLog { .. }
+        | Commands::Backfill { .. }
+        | Commands::LeaderStats { .. }
+        | Commands::Report { .. }
+        | Commands::ConvertRecording { .. }
+        | Commands::Bench { .. }
+        | Commands::Addresses { .. } => {
+            unreachable!("handled before connecting")
+        }
+        Commands::TipAccounts => {
+            let tip_accounts = traced("GetTipAccounts", GetTipAccountsRequest {}, |request| {
+                client.get_tip_accounts(request)
+            })
+            .await?
+            .into_inner();
+            info!("{:?}", tip_accounts);
+        }
+        Commands::StrategyGateway {
+            listen,
+            rpc_url,
+            payer,
+            max_tip_lamports,
+            tip_budget_lamports,
+            tip_budget_window_secs,
+            tip_share,
+            idempotency_ttl_secs,
+            max_queued_bulk,
+            max_queued_normal,
+            max_queued_critical,
+            clients,
+        } => {
+            let tip_accounts = traced("GetTipAccounts", GetTipAccountsRequest {}, |request| {
+                client.get_tip_accounts(request)
+            })
+            .await?
+            .into_inner()
+            .accounts
+            .iter()
+            .map(|account| {
+                Pubkey::from_str(account)
+                    .map_err(|e| CliError::Other(format!("invalid tip account {account}: {e}")))
+            })
+            .collect::<CliResult<_>>()?;
+            let config = GatewayConfig {
+                payer: Arc::new(read_keypair(&payer)?),
+                tip_accounts,
+                max_tip_lamports,
+                tip_budget: tip_budget_lamports
+                    .map(|lamports| (lamports, Duration::from_secs(tip_budget_window_secs))),
+                tip_split: TipSplit::new(tip_share, MIN_TIP_LAMPORTS)?,
+                idempotency_ttl: Duration::from_secs(idempotency_ttl_secs),
+                queue_limits: QueueLimits {
+                    bulk: max_queued_bulk,
+                    normal: max_queued_normal,
+                    critical: max_queued_critical,
+                },
+                clients: clients.as_deref().map(GatewayClients::load).transpose()?,
+            };
+            let rpc_client = RpcClient::new_with_commitment(rpc_url, CommitmentConfig::confirmed());
+            check_rpc_cluster(&rpc_client, expected_cluster).await?;
+            let gateway = StrategyGatewayService::new(client, rpc_client, config).await?;
+            info!("serving strategy gateway on {listen}");
+            Server::builder()
+                .add_service(StrategyGatewayServer::new(gateway))
+                .serve(listen)
+                .await?;
+        }
+        #[cfg(unix)]
+        Commands::ServeIpc {
+            socket,
+            idempotency_ttl_secs,
+        } => {
+            info!("serving bundle submission on {}", socket.display());
+            let idempotency_ttl = Duration::from_secs(idempotency_ttl_secs);
+            uds_ipc::serve(&socket, idempotency_ttl, move |transactions| {
+                let mut client = client.clone();
+                Box::pin(async move {
+                    send_bundle_no_wait(&transactions, &mut client)
+                        .await
+                        .map(|response| response.into_inner().uuid)
+                        .map_err(|status| status.message().to_string())
+                })
+            })
+            .await?;
+        }
+        Commands::SendBundle {
+            rpc_url,
+            payer,
+            message,
+            recipient,
+            transfer_lamports,
+            lamports,
+            tip_account,
+            tip_share,
+            max_bundle_attempts,
+            rpc_fallback_after,
+            fallback_skip_preflight,
+            fallback_max_retries,
+            fallback_compute_unit_price,
+            wait_for_leader,
+            expiry_margin_blocks,
+            check_conflicts,
+            profile_compute,
+            post_condition,
+            instructions,
+            num_txs: _,
+        } => {
+            let payer_keypair = Arc::new(read_keypair(&payer)?);
+            let rpc_client = RpcClient::new_with_commitment(rpc_url, CommitmentConfig::confirmed());
+            check_rpc_cluster(&rpc_client, expected_cluster).await?;
+            let balance = rpc_client.get_balance(&payer_keypair.pubkey()).await?;
+
+            let sol_usd = match &price_feed {
+                Some(price_feed) => match price_feed.sol_usd(&rpc_client).await {
+                    Ok(price) => Some(price),
+                    Err(e) => {
+                        warn!("failed to read SOL/USD price: {e}");
+                        None
+                    }
+                },
+                None => None,
+            };
+            info!(
+                "payer public key: {:?} balance: {}, tip: {}",
+                payer_keypair.pubkey(),
+                format_lamports(balance, sol_usd),
+                format_lamports(lamports, sol_usd),
+            );
+            let tip_split = TipSplit::new(tip_share, MIN_TIP_LAMPORTS)?;
+            tip_split.check_floor(lamports)?;
+            confirmation.confirm(&Spend {
+                action: "send bundle",
+                recipient: recipient.as_ref(),
+                amount_lamports: recipient.map_or(0, |_| transfer_lamports),
+                tip_lamports: lamports,
+            })?;
+
+            let mut bundle_results_subscription = traced(
+                "SubscribeBundleResults",
+                SubscribeBundleResultsRequest {},
+                |request| client.subscribe_bundle_results(request),
+            )
+            .await?
+            .into_inner();
+
+            let fallback_config =
+                rpc_fallback_after.map(|auction_losses_before_fallback| RpcFallbackConfig {
+                    auction_losses_before_fallback,
+                    skip_preflight: fallback_skip_preflight,
+                    max_retries: fallback_max_retries,
+                    compute_unit_price: fallback_compute_unit_price,
+                });
+            let mut auction_losses = AuctionLossTracker::default();
+
+            let transactions = send_bundle_transactions(
+                &payer_keypair,
+                recipient,
+                transfer_lamports,
+                message.as_deref(),
+                instructions.as_deref(),
+                &tip_account,
+            )?;
+            let bundle_builder = post_condition.into_iter().fold(
+                transfer_bundle(
+                    &payer_keypair,
+                    &transactions,
+                    &tip_account,
+                    lamports,
+                    &tip_split,
+                ),
+                BundleBuilder::with_post_condition,
+            );
+            bundle_builder.validate()?;
+            let build_bundle = |blockhash: &Hash| -> Vec<VersionedTransaction> {
+                bundle_builder.build(blockhash).expect("builds bundle")
+            };
+
+            if check_conflicts {
+                print_bundle_conflicts(&rpc_client, &payer_keypair, build_bundle).await?;
+            }
+            if profile_compute {
+                let blockhash = rpc_client.get_latest_blockhash().await?;
+                print_compute_profiles(
+                    &profile_bundle(&rpc_client, &build_bundle(&blockhash)).await?,
+                );
+            }
+
+            let mut last_error = CliError::Other("no bundle attempts made".into());
+            for attempt in 1..=max_bundle_attempts {
+                // build + sign the transactions
+                let mut bundle = PreSignedBundle::build(&rpc_client, build_bundle).await?;
+
+                if let Some(max_slots_to_leader) = wait_for_leader {
+                    // wait for jito-solana leader slot, keeping the pre-signed bundle fresh
+                    loop {
+                        let next_leader = traced(
+                            "GetNextScheduledLeader",
+                            NextScheduledLeaderRequest {
+                                regions: args.regions.clone(),
+                            },
+                            |request| client.get_next_scheduled_leader(request),
+                        )
+                        .await?
+                        .into_inner();
+                        let num_slots = next_leader.next_leader_slot - next_leader.current_slot;
+                        if num_slots <= max_slots_to_leader {
+                            break;
+                        }
+                        info!(
+                            "next jito leader slot in {num_slots} slots in {}",
+                            next_leader.next_leader_region
+                        );
+                        bundle
+                            .refresh_if_expiring(&rpc_client, expiry_margin_blocks, build_bundle)
+                            .await?;
+                        sleep(Duration::from_millis(500)).await;
+                    }
+                }
+
+                let signatures = bundle.signatures();
+                let wire_transactions: Vec<Vec<u8>> = bundle
+                    .transactions
+                    .iter()
+                    .map(|tx| bincode::serialize(tx).expect("serializes"))
+                    .collect();
+
+                info!("attempt {attempt}/{max_bundle_attempts}, sending bundle: {signatures:?}");
+                let bundle_result = send_bundle_with_confirmation(
+                    &signatures,
+                    &wire_transactions,
+                    &rpc_client,
+                    &mut client,
+                    &mut bundle_results_subscription,
+                )
+                .await;
+
+                let e = match bundle_result {
+                    Ok(_) => {
+                        println!("Bundle sent successfully");
+                        return check_post_conditions(
+                            &rpc_client,
+                            &signatures,
+                            bundle_builder.post_conditions(),
+                        )
+                        .await;
+                    }
+                    Err(e) => e,
+                };
+                println!("Bundle failed: {:?}", e);
+                let fallback_due = fallback_config.as_ref().map(|fallback_config| {
+                    auction_losses.record(e.downcast_ref::<BundleRejectionError>(), fallback_config)
+                });
+                last_error = e.into();
+
+                let (Some(fallback_config), Some(true)) = (&fallback_config, fallback_due) else {
+                    continue;
+                };
+
+                warn!("bundle keeps losing the auction, sending transactions over RPC without tip");
+                let fallback_txs: Vec<VersionedTransaction> = transactions
+                    .iter()
+                    .map(|instructions| {
+                        VersionedTransaction::from(Transaction::new_signed_with_payer(
+                            &with_priority_fee(instructions, fallback_config.compute_unit_price),
+                            Some(&payer_keypair.pubkey()),
+                            &[payer_keypair.as_ref()],
+                            bundle.blockhash,
+                        ))
+                    })
+                    .collect();
+                for result in
+                    send_transactions_via_rpc(&rpc_client, &fallback_txs, fallback_config).await
+                {
+                    let signature = result?;
+                    println!("Fallback transaction sent: {signature}");
+                }
+                return Ok(());
+            }
+            return Err(last_error);
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,83 @@
+/*
+ * C ABI of the Jito searcher client, see ffi/README.md.
+ *
+ * Functions returning int32_t return JITO_OK or one of the negative JITO_ERR_* codes, with the
+ * reason available from jito_last_error() on the same thread. Existing functions, codes and
+ * struct layouts never change within an ABI version.
+ */
+#ifndef JITO_SEARCHER_H
+#define JITO_SEARCHER_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+#define JITO_ABI_VERSION 1
+
+#define JITO_OK 0
+#define JITO_ERR_INVALID_ARGUMENT -1
+#define JITO_ERR_BLOCK_ENGINE -2
+#define JITO_ERR_BUFFER_TOO_SMALL -3
+
+#define JITO_OUTCOME_ACCEPTED 0
+#define JITO_OUTCOME_PROCESSED 1
+#define JITO_OUTCOME_FINALIZED 2
+#define JITO_OUTCOME_REJECTED 3
+#define JITO_OUTCOME_DROPPED 4
+
+/* Bundle uuids are 36 characters, the buffers leave room for the NUL terminator. */
+#define JITO_BUNDLE_ID_LEN 64
+#define JITO_MESSAGE_LEN 256
+
+typedef struct JitoClient JitoClient;
+
+typedef struct JitoBundleResult {
+    char bundle_id[JITO_BUNDLE_ID_LEN];
+    int32_t outcome;
+    /* 0 unless accepted or processed. */
+    uint64_t slot;
+    /* Why the bundle was rejected or dropped, truncated, empty otherwise. */
+    char message[JITO_MESSAGE_LEN];
+} JitoBundleResult;
+
+/* The JITO_ABI_VERSION the library was built with. Check it before calling anything else. */
+uint32_t jito_abi_version(void);
+
+/*
+ * Connects to the block engine, authenticates with the keypair file and subscribes to bundle
+ * results. Blocks until done. Returns NULL on failure.
+ */
+JitoClient *jito_client_new(const char *block_engine_url, const char *auth_keypair_path);
+
+void jito_client_free(JitoClient *client);
+
+/*
+ * Sends num_txs bincode serialized transactions as a bundle, without waiting for it to land.
+ * Writes the NUL terminated bundle uuid to uuid_out. Safe to call from several threads.
+ */
+int32_t jito_submit_bundle(JitoClient *client,
+                           const uint8_t *const *txs,
+                           const size_t *tx_lens,
+                           size_t num_txs,
+                           char *uuid_out,
+                           size_t uuid_out_len);
+
+/*
+ * Takes the next bundle result without blocking. Returns 1 if one was written to out, 0 if none
+ * is waiting, or a JITO_ERR_* code. New results are dropped while JITO_MAX_QUEUED_RESULTS are
+ * waiting to be polled.
+ */
+#define JITO_MAX_QUEUED_RESULTS 10000
+int32_t jito_poll_bundle_result(JitoClient *client, JitoBundleResult *out);
+
+/* The last error on this thread, valid until the next call into the library. Never NULL. */
+const char *jito_last_error(void);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* JITO_SEARCHER_H */
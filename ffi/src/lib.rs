@@ -0,0 +1,269 @@
+//! C ABI of the searcher client, for trading systems in other languages to link directly. The
+//! declarations live in `include/jito_searcher.h`, keep both in sync.
+
+use std::{
+    cell::RefCell,
+    ffi::{c_char, CStr, CString},
+    ptr, slice,
+    sync::{
+        mpsc::{sync_channel, Receiver, TrySendError},
+        Arc, Mutex,
+    },
+};
+
+use futures_util::StreamExt;
+use jito_protos::{
+    bundle::{bundle_result::Result as BundleResultType, rejected::Reason, BundleResult},
+    searcher::{searcher_service_client::SearcherServiceClient, SubscribeBundleResultsRequest},
+};
+use jito_searcher_client::{
+    get_searcher_client, send_bundle_no_wait, token_authenticator::ClientInterceptor,
+};
+use log::warn;
+use solana_sdk::signature::read_keypair_file;
+use tokio::runtime::Runtime;
+use tonic::{codegen::InterceptedService, transport::Channel};
+
+pub const JITO_ABI_VERSION: u32 = 1;
+
+pub const JITO_OK: i32 = 0;
+pub const JITO_ERR_INVALID_ARGUMENT: i32 = -1;
+pub const JITO_ERR_BLOCK_ENGINE: i32 = -2;
+pub const JITO_ERR_BUFFER_TOO_SMALL: i32 = -3;
+
+pub const JITO_OUTCOME_ACCEPTED: i32 = 0;
+pub const JITO_OUTCOME_PROCESSED: i32 = 1;
+pub const JITO_OUTCOME_FINALIZED: i32 = 2;
+pub const JITO_OUTCOME_REJECTED: i32 = 3;
+pub const JITO_OUTCOME_DROPPED: i32 = 4;
+
+pub const JITO_BUNDLE_ID_LEN: usize = 64;
+pub const JITO_MESSAGE_LEN: usize = 256;
+pub const JITO_MAX_QUEUED_RESULTS: usize = 10_000;
+
+#[repr(C)]
+pub struct JitoBundleResult {
+    pub bundle_id: [c_char; JITO_BUNDLE_ID_LEN],
+    pub outcome: i32,
+    pub slot: u64,
+    pub message: [c_char; JITO_MESSAGE_LEN],
+}
+
+pub struct JitoClient {
+    runtime: Runtime,
+    client: SearcherServiceClient<InterceptedService<Channel, ClientInterceptor>>,
+    results: Mutex<Receiver<BundleResult>>,
+}
+
+thread_local! {
+    static LAST_ERROR: RefCell<CString> = RefCell::new(CString::default());
+}
+
+/// Records `message` for `jito_last_error` and returns `code`.
+fn fail(code: i32, message: impl ToString) -> i32 {
+    let message = message.to_string().replace('\0', " ");
+    LAST_ERROR.with(|e| *e.borrow_mut() = CString::new(message).expect("no NUL bytes"));
+    code
+}
+
+/// Copies `s` into `out` NUL terminated, truncating it to fit.
+fn copy_truncated(s: &str, out: &mut [c_char]) {
+    let len = s.len().min(out.len() - 1);
+    for (dst, src) in out.iter_mut().zip(&s.as_bytes()[..len]) {
+        *dst = *src as c_char;
+    }
+    out[len] = 0;
+}
+
+/// # Safety
+/// `s` must be NULL or a NUL terminated string.
+unsafe fn str_arg<'a>(s: *const c_char, name: &str) -> Result<&'a str, i32> {
+    if s.is_null() {
+        return Err(fail(JITO_ERR_INVALID_ARGUMENT, format!("{name} is NULL")));
+    }
+    CStr::from_ptr(s)
+        .to_str()
+        .map_err(|_| fail(JITO_ERR_INVALID_ARGUMENT, format!("{name} isn't UTF-8")))
+}
+
+#[no_mangle]
+pub extern "C" fn jito_abi_version() -> u32 {
+    JITO_ABI_VERSION
+}
+
+/// # Safety
+/// Both arguments must be NULL or NUL terminated strings.
+#[no_mangle]
+pub unsafe extern "C" fn jito_client_new(
+    block_engine_url: *const c_char,
+    auth_keypair_path: *const c_char,
+) -> *mut JitoClient {
+    let (Ok(block_engine_url), Ok(auth_keypair_path)) = (
+        str_arg(block_engine_url, "block_engine_url"),
+        str_arg(auth_keypair_path, "auth_keypair_path"),
+    ) else {
+        return ptr::null_mut();
+    };
+    let auth_keypair = match read_keypair_file(auth_keypair_path) {
+        Ok(keypair) => Arc::new(keypair),
+        Err(e) => {
+            fail(
+                JITO_ERR_INVALID_ARGUMENT,
+                format!("failed to read {auth_keypair_path}: {e}"),
+            );
+            return ptr::null_mut();
+        }
+    };
+    let runtime = match Runtime::new() {
+        Ok(runtime) => runtime,
+        Err(e) => {
+            fail(
+                JITO_ERR_BLOCK_ENGINE,
+                format!("failed to start runtime: {e}"),
+            );
+            return ptr::null_mut();
+        }
+    };
+    let connected = runtime.block_on(async {
+        let mut client = get_searcher_client(block_engine_url, &auth_keypair)
+            .await
+            .map_err(|e| e.to_string())?;
+        let stream = client
+            .subscribe_bundle_results(SubscribeBundleResultsRequest {})
+            .await
+            .map_err(|status| status.to_string())?
+            .into_inner();
+        Ok::<_, String>((client, stream))
+    });
+    let (client, mut stream) = match connected {
+        Ok(connected) => connected,
+        Err(e) => {
+            fail(JITO_ERR_BLOCK_ENGINE, e);
+            return ptr::null_mut();
+        }
+    };
+
+    // queued here until polled, the oldest are kept when the caller falls behind
+    let (sender, receiver) = sync_channel(JITO_MAX_QUEUED_RESULTS);
+    runtime.spawn(async move {
+        while let Some(result) = stream.next().await {
+            match result {
+                Ok(result) => match sender.try_send(result) {
+                    Ok(()) | Err(TrySendError::Full(_)) => {}
+                    Err(TrySendError::Disconnected(_)) => return,
+                },
+                Err(status) => {
+                    warn!("bundle results stream failed: {status}");
+                    return;
+                }
+            }
+        }
+    });
+
+    Box::into_raw(Box::new(JitoClient {
+        runtime,
+        client,
+        results: Mutex::new(receiver),
+    }))
+}
+
+/// # Safety
+/// `client` must be NULL or returned by `jito_client_new` and not freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn jito_client_free(client: *mut JitoClient) {
+    if !client.is_null() {
+        drop(Box::from_raw(client));
+    }
+}
+
+/// # Safety
+/// `client` must be live, `txs` and `tx_lens` must point to `num_txs` buffers and their lengths,
+/// and `uuid_out` to `uuid_out_len` writable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn jito_submit_bundle(
+    client: *mut JitoClient,
+    txs: *const *const u8,
+    tx_lens: *const usize,
+    num_txs: usize,
+    uuid_out: *mut c_char,
+    uuid_out_len: usize,
+) -> i32 {
+    if client.is_null() || txs.is_null() || tx_lens.is_null() || uuid_out.is_null() {
+        return fail(JITO_ERR_INVALID_ARGUMENT, "NULL argument");
+    }
+    if num_txs == 0 {
+        return fail(JITO_ERR_INVALID_ARGUMENT, "bundle has no transactions");
+    }
+    let client = &*client;
+    let transactions: Vec<Vec<u8>> = slice::from_raw_parts(txs, num_txs)
+        .iter()
+        .zip(slice::from_raw_parts(tx_lens, num_txs))
+        .map(|(tx, len)| slice::from_raw_parts(*tx, *len).to_vec())
+        .collect();
+    let mut searcher_client = client.client.clone();
+    let uuid = match client
+        .runtime
+        .block_on(send_bundle_no_wait(&transactions, &mut searcher_client))
+    {
+        Ok(response) => response.into_inner().uuid,
+        Err(status) => return fail(JITO_ERR_BLOCK_ENGINE, status),
+    };
+    if uuid.len() >= uuid_out_len {
+        return fail(
+            JITO_ERR_BUFFER_TOO_SMALL,
+            format!("uuid {uuid} doesn't fit in {uuid_out_len} bytes"),
+        );
+    }
+    copy_truncated(&uuid, slice::from_raw_parts_mut(uuid_out, uuid_out_len));
+    JITO_OK
+}
+
+/// # Safety
+/// `client` must be live and `out` must point to a writable `JitoBundleResult`.
+#[no_mangle]
+pub unsafe extern "C" fn jito_poll_bundle_result(
+    client: *mut JitoClient,
+    out: *mut JitoBundleResult,
+) -> i32 {
+    if client.is_null() || out.is_null() {
+        return fail(JITO_ERR_INVALID_ARGUMENT, "NULL argument");
+    }
+    let Ok(result) = (*client).results.lock().expect("not poisoned").try_recv() else {
+        return 0;
+    };
+    let (outcome, slot, message) = match result.result {
+        Some(BundleResultType::Accepted(accepted)) => {
+            (JITO_OUTCOME_ACCEPTED, accepted.slot, String::new())
+        }
+        Some(BundleResultType::Processed(processed)) => {
+            (JITO_OUTCOME_PROCESSED, processed.slot, String::new())
+        }
+        Some(BundleResultType::Finalized(_)) => (JITO_OUTCOME_FINALIZED, 0, String::new()),
+        Some(BundleResultType::Rejected(rejected)) => {
+            let message = match rejected.reason {
+                Some(Reason::StateAuctionBidRejected(r)) => r.msg.unwrap_or_default(),
+                Some(Reason::WinningBatchBidRejected(r)) => r.msg.unwrap_or_default(),
+                Some(Reason::SimulationFailure(r)) => r.msg.unwrap_or_default(),
+                Some(Reason::InternalError(r)) => r.msg,
+                Some(Reason::DroppedBundle(r)) => r.msg,
+                None => String::new(),
+            };
+            (JITO_OUTCOME_REJECTED, 0, message)
+        }
+        Some(BundleResultType::Dropped(dropped)) => {
+            (JITO_OUTCOME_DROPPED, 0, format!("{:?}", dropped.reason()))
+        }
+        None => (JITO_OUTCOME_DROPPED, 0, String::new()),
+    };
+    let out = &mut *out;
+    copy_truncated(&result.bundle_id, &mut out.bundle_id);
+    out.outcome = outcome;
+    out.slot = slot;
+    copy_truncated(&message, &mut out.message);
+    1
+}
+
+#[no_mangle]
+pub extern "C" fn jito_last_error() -> *const c_char {
+    LAST_ERROR.with(|e| e.borrow().as_ptr())
+}
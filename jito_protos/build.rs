@@ -12,8 +12,10 @@ fn main() {
                 "protos/relayer.proto",
                 "protos/searcher.proto",
                 "protos/shared.proto",
+                "gateway/strategy_gateway.proto",
+                "recording/recording.proto",
             ],
-            &["protos"],
+            &["protos", "gateway", "recording"],
         )
         .unwrap();
 }
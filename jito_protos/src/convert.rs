@@ -10,6 +10,7 @@ use solana_sdk::{
     packet::{Meta, PacketFlags},
     transaction::VersionedTransaction,
 };
+use thiserror::Error;
 
 use crate::{
     packet::{
@@ -91,16 +92,42 @@ pub fn proto_packet_batch_to_packets(
         .map(|proto_packet| proto_packet_to_packet(&proto_packet))
 }
 
-/// Converts a protobuf packet to a VersionedTransaction
-pub fn versioned_tx_from_packet(p: &ProtoPacket) -> Option<VersionedTransaction> {
+#[derive(Debug, Error)]
+pub enum PacketConversionError {
+    #[error("packet has {0} bytes, more than the {PACKET_DATA_SIZE} byte maximum")]
+    Oversized(usize),
+    #[error("packet meta size {meta_size} exceeds {data_len} bytes of data")]
+    Malformed { meta_size: usize, data_len: usize },
+    #[error("failed to deserialize transaction: {0}")]
+    Deserialize(#[from] bincode::Error),
+}
+
+/// Converts a protobuf packet to a VersionedTransaction, reporting why conversion failed
+pub fn try_versioned_tx_from_packet(
+    p: &ProtoPacket,
+) -> Result<VersionedTransaction, PacketConversionError> {
+    if p.data.len() > PACKET_DATA_SIZE {
+        return Err(PacketConversionError::Oversized(p.data.len()));
+    }
     let mut data = [0; PACKET_DATA_SIZE];
-    let copy_len = min(data.len(), p.data.len());
-    data[..copy_len].copy_from_slice(&p.data[..copy_len]);
+    data[..p.data.len()].copy_from_slice(&p.data);
     let mut packet = Packet::new(data, Default::default());
     if let Some(meta) = &p.meta {
+        if meta.size as usize > p.data.len() {
+            return Err(PacketConversionError::Malformed {
+                meta_size: meta.size as usize,
+                data_len: p.data.len(),
+            });
+        }
         packet.meta_mut().size = meta.size as usize;
     }
-    packet.deserialize_slice(..).ok()
+    Ok(packet.deserialize_slice(..)?)
+}
+
+/// Converts a protobuf packet to a VersionedTransaction
+/// NOTE: packets that fail to convert are dropped, see [`try_versioned_tx_from_packet`]
+pub fn versioned_tx_from_packet(p: &ProtoPacket) -> Option<VersionedTransaction> {
+    try_versioned_tx_from_packet(p).ok()
 }
 
 /// Coverts a VersionedTransaction to packet
@@ -115,8 +142,8 @@ pub fn packet_from_versioned_tx(tx: VersionedTransaction) -> Packet {
 }
 
 /// Converts a VersionedTransaction to a protobuf packet
-pub fn proto_packet_from_versioned_tx(tx: &Vec<u8>) -> ProtoPacket {
-    let data: Vec<u8> = tx.clone();
+pub fn proto_packet_from_versioned_tx(tx: &[u8]) -> ProtoPacket {
+    let data: Vec<u8> = tx.to_vec();
     //let data = serialize(tx).expect("serializes");
     let size = data.len() as u64;
     ProtoPacket {
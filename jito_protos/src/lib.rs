@@ -31,3 +31,11 @@ pub mod shared {
 pub mod auth {
     tonic::include_proto!("auth");
 }
+
+pub mod strategy_gateway {
+    tonic::include_proto!("strategy_gateway");
+}
+
+pub mod recording {
+    tonic::include_proto!("recording");
+}
@@ -0,0 +1,432 @@
+//! Python bindings for the searcher client, built into a wheel with maturin. Calls block the
+//! calling thread on a runtime shared by every client, with the GIL released while they wait.
+
+// tonic's Status is large, and the closures handing it over to Python return it as is
+#![allow(clippy::result_large_err)]
+
+use std::{
+    str::FromStr,
+    sync::{Arc, Mutex, OnceLock},
+};
+
+use futures_util::StreamExt;
+use jito_protos::{
+    bundle::{
+        bundle_result::Result as BundleResultType, rejected::Reason,
+        BundleResult as ProtoBundleResult,
+    },
+    searcher::{
+        mempool_subscription, searcher_service_client::SearcherServiceClient,
+        GetTipAccountsRequest, MempoolSubscription, PendingTxNotification, ProgramSubscriptionV0,
+        SubscribeBundleResultsRequest, WriteLockedAccountSubscriptionV0,
+    },
+};
+use jito_searcher_client::{
+    bundle_builder::{self, TransactionSpec},
+    get_searcher_client, send_bundle_no_wait, send_bundle_with_confirmation,
+    tip_payment::{TipConfig, TipPlacement, TipSplit},
+    token_authenticator::ClientInterceptor,
+    BundleRejectionError,
+};
+use pyo3::{
+    create_exception,
+    exceptions::{PyException, PyValueError},
+    prelude::*,
+    types::PyBytes,
+};
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::{
+    hash::Hash,
+    instruction,
+    pubkey::Pubkey,
+    signature::{self, read_keypair_file, Signer},
+    transaction::VersionedTransaction,
+};
+use tokio::runtime::Runtime;
+use tonic::{codegen::InterceptedService, transport::Channel, Status, Streaming};
+
+create_exception!(jito_searcher, JitoError, PyException);
+create_exception!(jito_searcher, BlockEngineError, JitoError);
+create_exception!(jito_searcher, BundleRejected, JitoError);
+
+type Client = SearcherServiceClient<InterceptedService<Channel, ClientInterceptor>>;
+
+fn runtime() -> &'static Runtime {
+    static RUNTIME: OnceLock<Runtime> = OnceLock::new();
+    RUNTIME.get_or_init(|| Runtime::new().expect("starts tokio runtime"))
+}
+
+fn parse_pubkey(s: &str) -> PyResult<Pubkey> {
+    Pubkey::from_str(s).map_err(|_| PyValueError::new_err(format!("invalid pubkey {s:?}")))
+}
+
+fn block_engine_error(e: impl ToString) -> PyErr {
+    BlockEngineError::new_err(e.to_string())
+}
+
+fn to_py_bytes(py: Python<'_>, items: Vec<Vec<u8>>) -> Vec<Py<PyBytes>> {
+    items
+        .iter()
+        .map(|item| PyBytes::new(py, item).unbind())
+        .collect()
+}
+
+#[pyclass(frozen, module = "jito_searcher")]
+#[derive(Clone)]
+struct Keypair(Arc<signature::Keypair>);
+
+#[pymethods]
+impl Keypair {
+    /// Reads a keypair file as written by `solana-keygen`.
+    #[staticmethod]
+    fn from_file(path: &str) -> PyResult<Self> {
+        read_keypair_file(path)
+            .map(|keypair| Self(Arc::new(keypair)))
+            .map_err(|e| PyValueError::new_err(format!("failed to read keypair {path:?}: {e}")))
+    }
+
+    /// Takes the 64 byte secret and public key.
+    #[staticmethod]
+    fn from_bytes(bytes: &[u8]) -> PyResult<Self> {
+        signature::Keypair::from_bytes(bytes)
+            .map(|keypair| Self(Arc::new(keypair)))
+            .map_err(|e| PyValueError::new_err(format!("invalid keypair: {e}")))
+    }
+
+    #[getter]
+    fn pubkey(&self) -> String {
+        self.0.pubkey().to_string()
+    }
+}
+
+#[pyclass(frozen, module = "jito_searcher")]
+#[derive(Clone)]
+struct AccountMeta(instruction::AccountMeta);
+
+#[pymethods]
+impl AccountMeta {
+    #[new]
+    #[pyo3(signature = (pubkey, is_signer = false, is_writable = false))]
+    fn new(pubkey: &str, is_signer: bool, is_writable: bool) -> PyResult<Self> {
+        Ok(Self(instruction::AccountMeta {
+            pubkey: parse_pubkey(pubkey)?,
+            is_signer,
+            is_writable,
+        }))
+    }
+}
+
+#[pyclass(frozen, module = "jito_searcher")]
+#[derive(Clone)]
+struct Instruction(instruction::Instruction);
+
+#[pymethods]
+impl Instruction {
+    #[new]
+    fn new(program_id: &str, accounts: Vec<AccountMeta>, data: Vec<u8>) -> PyResult<Self> {
+        Ok(Self(instruction::Instruction {
+            program_id: parse_pubkey(program_id)?,
+            accounts: accounts.into_iter().map(|meta| meta.0).collect(),
+            data,
+        }))
+    }
+}
+
+/// Builds and signs a bundle, see `bundle_builder::BundleBuilder`.
+#[pyclass(module = "jito_searcher")]
+#[derive(Default)]
+struct BundleBuilder(bundle_builder::BundleBuilder);
+
+#[pymethods]
+impl BundleBuilder {
+    #[new]
+    fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a transaction paid by `payer` and also signed by `signers`.
+    #[pyo3(signature = (instructions, payer, signers = Vec::new()))]
+    fn add_transaction(
+        &mut self,
+        instructions: Vec<Instruction>,
+        payer: Keypair,
+        signers: Vec<Keypair>,
+    ) {
+        let spec = signers.into_iter().fold(
+            TransactionSpec::new(instructions.into_iter().map(|ix| ix.0).collect(), payer.0),
+            |spec, signer| spec.with_signer(signer.0),
+        );
+        self.0 = std::mem::take(&mut self.0).add_transaction(spec);
+    }
+
+    /// Has `fee_payer` pay the fees of every transaction, and the tip, in place of each
+    /// transaction's payer, which then signs only if its instructions require it.
+    fn with_fee_payer(&mut self, fee_payer: Keypair) {
+        self.0 = std::mem::take(&mut self.0).with_fee_payer(fee_payer.0);
+    }
+
+    /// Tips `lamports` to `tip_account` from the fee payer of the last transaction.
+    fn with_tip(&mut self, tip_account: &str, lamports: u64) -> PyResult<()> {
+        let tip = TipConfig {
+            tip_account: parse_pubkey(tip_account)?,
+            lamports,
+            placement: TipPlacement::LastTransaction,
+            split: TipSplit::default(),
+        };
+        self.0 = std::mem::take(&mut self.0).with_tip(tip);
+        Ok(())
+    }
+
+    /// The signed transactions, bincode serialized, ready for `SearcherClient.send_bundle`.
+    fn build(&self, py: Python<'_>, blockhash: &str) -> PyResult<Vec<Py<PyBytes>>> {
+        let blockhash = Hash::from_str(blockhash)
+            .map_err(|_| PyValueError::new_err(format!("invalid blockhash {blockhash:?}")))?;
+        let transactions = self
+            .0
+            .build(&blockhash)
+            .map_err(|e| PyValueError::new_err(e.to_string()))?
+            .iter()
+            .map(|tx| bincode::serialize(tx).expect("serializes"))
+            .collect();
+        Ok(to_py_bytes(py, transactions))
+    }
+}
+
+#[pyclass(frozen, get_all, module = "jito_searcher")]
+struct BundleResult {
+    bundle_id: String,
+    /// One of accepted, processed, finalized, rejected or dropped.
+    outcome: String,
+    slot: Option<u64>,
+    /// Why the bundle was rejected or dropped.
+    message: Option<String>,
+}
+
+impl From<ProtoBundleResult> for BundleResult {
+    fn from(result: ProtoBundleResult) -> Self {
+        let (outcome, slot, message) = match result.result {
+            Some(BundleResultType::Accepted(accepted)) => ("accepted", Some(accepted.slot), None),
+            Some(BundleResultType::Processed(processed)) => {
+                ("processed", Some(processed.slot), None)
+            }
+            Some(BundleResultType::Finalized(_)) => ("finalized", None, None),
+            Some(BundleResultType::Rejected(rejected)) => {
+                let message = match rejected.reason {
+                    Some(Reason::StateAuctionBidRejected(r)) => r.msg,
+                    Some(Reason::WinningBatchBidRejected(r)) => r.msg,
+                    Some(Reason::SimulationFailure(r)) => r.msg,
+                    Some(Reason::InternalError(r)) => Some(r.msg),
+                    Some(Reason::DroppedBundle(r)) => Some(r.msg),
+                    None => None,
+                };
+                ("rejected", None, message)
+            }
+            Some(BundleResultType::Dropped(dropped)) => {
+                ("dropped", None, Some(format!("{:?}", dropped.reason())))
+            }
+            None => ("dropped", None, None),
+        };
+        Self {
+            bundle_id: result.bundle_id,
+            outcome: outcome.to_string(),
+            slot,
+            message,
+        }
+    }
+}
+
+/// Iterates over bundle results until the stream ends.
+#[pyclass(frozen, module = "jito_searcher")]
+struct BundleResultStream(Mutex<Streaming<ProtoBundleResult>>);
+
+#[pymethods]
+impl BundleResultStream {
+    fn __iter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
+
+    fn __next__(&self, py: Python<'_>) -> PyResult<Option<BundleResult>> {
+        let mut stream = self.0.lock().expect("not poisoned");
+        let stream = &mut *stream;
+        match py.allow_threads(|| runtime().block_on(stream.next())) {
+            Some(Ok(result)) => Ok(Some(result.into())),
+            Some(Err(status)) => Err(block_engine_error(status)),
+            None => Ok(None),
+        }
+    }
+}
+
+/// Iterates over mempool notifications, each a list of bincode serialized transactions.
+#[pyclass(frozen, module = "jito_searcher")]
+struct MempoolStream(Mutex<Streaming<PendingTxNotification>>);
+
+#[pymethods]
+impl MempoolStream {
+    fn __iter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
+
+    fn __next__(&self, py: Python<'_>) -> PyResult<Option<Vec<Py<PyBytes>>>> {
+        let mut stream = self.0.lock().expect("not poisoned");
+        let stream = &mut *stream;
+        match py.allow_threads(|| runtime().block_on(stream.next())) {
+            Some(Ok(notification)) => {
+                let transactions = notification
+                    .transactions
+                    .into_iter()
+                    .map(|packet| packet.data)
+                    .collect();
+                Ok(Some(to_py_bytes(py, transactions)))
+            }
+            Some(Err(status)) => Err(block_engine_error(status)),
+            None => Ok(None),
+        }
+    }
+}
+
+/// Why sending with confirmation failed, converted before leaving the runtime since the client
+/// returns errors that can't cross threads.
+enum SendError {
+    Rejected(String),
+    BlockEngine(String),
+    Other(String),
+}
+
+/// An authenticated connection to a block engine.
+#[pyclass(frozen, module = "jito_searcher")]
+struct SearcherClient(Client);
+
+#[pymethods]
+impl SearcherClient {
+    /// Connects to `block_engine_url` and authenticates with `auth_keypair`.
+    #[new]
+    fn new(py: Python<'_>, block_engine_url: &str, auth_keypair: Keypair) -> PyResult<Self> {
+        py.allow_threads(|| {
+            runtime().block_on(get_searcher_client(block_engine_url, &auth_keypair.0))
+        })
+        .map(Self)
+        .map_err(block_engine_error)
+    }
+
+    fn get_tip_accounts(&self, py: Python<'_>) -> PyResult<Vec<String>> {
+        let mut client = self.0.clone();
+        py.allow_threads(|| runtime().block_on(client.get_tip_accounts(GetTipAccountsRequest {})))
+            .map(|response| response.into_inner().accounts)
+            .map_err(block_engine_error)
+    }
+
+    /// Sends bincode serialized transactions as a bundle and returns its uuid.
+    fn send_bundle(&self, py: Python<'_>, transactions: Vec<Vec<u8>>) -> PyResult<String> {
+        let mut client = self.0.clone();
+        py.allow_threads(|| runtime().block_on(send_bundle_no_wait(&transactions, &mut client)))
+            .map(|response| response.into_inner().uuid)
+            .map_err(block_engine_error)
+    }
+
+    /// Sends a bundle and waits for it to land, raising `BundleRejected` if it doesn't.
+    fn send_bundle_with_confirmation(
+        &self,
+        py: Python<'_>,
+        transactions: Vec<Vec<u8>>,
+        rpc_url: String,
+    ) -> PyResult<()> {
+        let signatures = transactions
+            .iter()
+            .map(|tx| {
+                bincode::deserialize::<VersionedTransaction>(tx)
+                    .map(|tx| tx.signatures[0])
+                    .map_err(|e| PyValueError::new_err(format!("invalid transaction: {e}")))
+            })
+            .collect::<PyResult<Vec<_>>>()?;
+        let mut client = self.0.clone();
+        let result = py.allow_threads(|| {
+            runtime().block_on(async {
+                let mut bundle_results = client
+                    .subscribe_bundle_results(SubscribeBundleResultsRequest {})
+                    .await
+                    .map_err(|status| SendError::BlockEngine(status.to_string()))?
+                    .into_inner();
+                send_bundle_with_confirmation(
+                    &signatures,
+                    &transactions,
+                    &RpcClient::new(rpc_url),
+                    &mut client,
+                    &mut bundle_results,
+                )
+                .await
+                .map_err(|e| {
+                    if let Some(e) = e.downcast_ref::<BundleRejectionError>() {
+                        SendError::Rejected(e.to_string())
+                    } else if let Some(status) = e.downcast_ref::<Status>() {
+                        SendError::BlockEngine(status.to_string())
+                    } else {
+                        SendError::Other(e.to_string())
+                    }
+                })
+            })
+        });
+        result.map_err(|e| match e {
+            SendError::Rejected(e) => BundleRejected::new_err(e),
+            SendError::BlockEngine(e) => BlockEngineError::new_err(e),
+            SendError::Other(e) => JitoError::new_err(e),
+        })
+    }
+
+    fn subscribe_bundle_results(&self, py: Python<'_>) -> PyResult<BundleResultStream> {
+        let mut client = self.0.clone();
+        py.allow_threads(|| {
+            runtime().block_on(client.subscribe_bundle_results(SubscribeBundleResultsRequest {}))
+        })
+        .map(|response| BundleResultStream(Mutex::new(response.into_inner())))
+        .map_err(block_engine_error)
+    }
+
+    /// Subscribes to pending transactions write-locking `accounts`, or invoking `programs`.
+    #[pyo3(signature = (accounts = Vec::new(), programs = Vec::new(), regions = Vec::new()))]
+    fn subscribe_mempool(
+        &self,
+        py: Python<'_>,
+        accounts: Vec<String>,
+        programs: Vec<String>,
+        regions: Vec<String>,
+    ) -> PyResult<MempoolStream> {
+        let msg = match (accounts.is_empty(), programs.is_empty()) {
+            (false, true) => {
+                mempool_subscription::Msg::WlaV0Sub(WriteLockedAccountSubscriptionV0 { accounts })
+            }
+            (true, false) => {
+                mempool_subscription::Msg::ProgramV0Sub(ProgramSubscriptionV0 { programs })
+            }
+            _ => {
+                return Err(PyValueError::new_err(
+                    "subscribe to either accounts or programs",
+                ))
+            }
+        };
+        let mut client = self.0.clone();
+        py.allow_threads(|| {
+            runtime().block_on(client.subscribe_mempool(MempoolSubscription {
+                regions,
+                msg: Some(msg),
+            }))
+        })
+        .map(|response| MempoolStream(Mutex::new(response.into_inner())))
+        .map_err(block_engine_error)
+    }
+}
+
+#[pymodule]
+fn jito_searcher(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<Keypair>()?;
+    m.add_class::<AccountMeta>()?;
+    m.add_class::<Instruction>()?;
+    m.add_class::<BundleBuilder>()?;
+    m.add_class::<BundleResult>()?;
+    m.add_class::<BundleResultStream>()?;
+    m.add_class::<MempoolStream>()?;
+    m.add_class::<SearcherClient>()?;
+    m.add("JitoError", m.py().get_type::<JitoError>())?;
+    m.add("BlockEngineError", m.py().get_type::<BlockEngineError>())?;
+    m.add("BundleRejected", m.py().get_type::<BundleRejected>())?;
+    Ok(())
+}
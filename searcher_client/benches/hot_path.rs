@@ -0,0 +1,37 @@
+//! Hot path benchmarks: `cargo bench -p jito-searcher-client`. Criterion keeps the last run under
+//! `target/criterion` and reports regressions against it.
+
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion, Throughput};
+use jito_searcher_client::bench::HotPathFixture;
+
+const BATCH_SIZE: usize = 1024;
+
+fn bundles(c: &mut Criterion) {
+    let fixture = HotPathFixture::new(BATCH_SIZE);
+    let bundle = fixture.build_bundle();
+    let mut group = c.benchmark_group("bundle");
+    group.throughput(Throughput::Elements(1));
+    group.bench_function("build_sign", |b| b.iter(|| fixture.build_bundle()));
+    group.bench_function("serialize", |b| {
+        b.iter(|| HotPathFixture::serialize_bundle(&bundle))
+    });
+    group.finish();
+}
+
+fn packets(c: &mut Criterion) {
+    let fixture = HotPathFixture::new(BATCH_SIZE);
+    let mut group = c.benchmark_group("packets");
+    group.throughput(Throughput::Elements(BATCH_SIZE as u64));
+    group.bench_function("filter", |b| {
+        b.iter_batched_ref(
+            || fixture.interest_filter(),
+            |filter| fixture.filter_packets(filter),
+            BatchSize::SmallInput,
+        )
+    });
+    group.bench_function("decode", |b| b.iter(|| fixture.decode_packets()));
+    group.finish();
+}
+
+criterion_group!(benches, bundles, packets);
+criterion_main!(benches);
@@ -0,0 +1,117 @@
+use solana_client::{
+    client_error::{ClientErrorKind, Result as ClientResult},
+    nonblocking::rpc_client::RpcClient,
+};
+use solana_sdk::{
+    instruction::Instruction,
+    program_pack::Pack,
+    pubkey::Pubkey,
+    signature::{Keypair, Signature, Signer},
+    transaction::Transaction,
+};
+use spl_associated_token_account::{
+    get_associated_token_address_with_program_id,
+    instruction::create_associated_token_account_idempotent,
+};
+
+/// Instructions sent per setup transaction, small enough to stay under the size limit.
+const MAX_CREATE_INSTRUCTIONS_PER_TX: usize = 4;
+
+/// An account a strategy needs before it starts sending bundles, along with the instructions
+/// that create it.
+#[derive(Clone, Debug)]
+pub struct RequiredAccount {
+    pub address: Pubkey,
+    pub space: usize,
+    pub create_instructions: Vec<Instruction>,
+}
+
+#[derive(Clone, Debug)]
+pub struct AccountSetupPlan {
+    pub existing: Vec<Pubkey>,
+    pub missing: Vec<RequiredAccount>,
+    /// Lamports needed to make every missing account rent exempt.
+    pub rent_lamports: u64,
+}
+
+/// The associated token account of `owner` for each mint, created idempotently so a
+/// concurrent creation doesn't fail the setup. Works for both token programs by reading each
+/// mint's owner.
+pub async fn associated_token_accounts(
+    rpc_client: &RpcClient,
+    payer: &Pubkey,
+    owner: &Pubkey,
+    mints: &[Pubkey],
+) -> ClientResult<Vec<RequiredAccount>> {
+    let mint_accounts = rpc_client.get_multiple_accounts(mints).await?;
+    mints
+        .iter()
+        .zip(mint_accounts)
+        .map(|(mint, account)| {
+            let token_program = account
+                .map(|a| a.owner)
+                .ok_or_else(|| ClientErrorKind::Custom(format!("mint {mint} doesn't exist")))?;
+            Ok(RequiredAccount {
+                address: get_associated_token_address_with_program_id(owner, mint, &token_program),
+                space: spl_token::state::Account::LEN,
+                create_instructions: vec![create_associated_token_account_idempotent(
+                    payer,
+                    owner,
+                    mint,
+                    &token_program,
+                )],
+            })
+        })
+        .collect()
+}
+
+/// Splits `accounts` into those that exist and those that still need creating, and prices the
+/// rent for the missing ones.
+pub async fn plan_account_setup(
+    rpc_client: &RpcClient,
+    accounts: Vec<RequiredAccount>,
+) -> ClientResult<AccountSetupPlan> {
+    let addresses: Vec<Pubkey> = accounts.iter().map(|a| a.address).collect();
+    let fetched = rpc_client.get_multiple_accounts(&addresses).await?;
+
+    let mut plan = AccountSetupPlan {
+        existing: vec![],
+        missing: vec![],
+        rent_lamports: 0,
+    };
+    for (account, fetched) in accounts.into_iter().zip(fetched) {
+        if fetched.is_some() {
+            plan.existing.push(account.address);
+            continue;
+        }
+        plan.rent_lamports += rpc_client
+            .get_minimum_balance_for_rent_exemption(account.space)
+            .await?;
+        plan.missing.push(account);
+    }
+    Ok(plan)
+}
+
+/// Sends the create instructions for every missing account, a few accounts per transaction.
+pub async fn create_missing_accounts(
+    rpc_client: &RpcClient,
+    payer: &Keypair,
+    plan: &AccountSetupPlan,
+) -> ClientResult<Vec<Signature>> {
+    let mut signatures = vec![];
+    for accounts in plan.missing.chunks(MAX_CREATE_INSTRUCTIONS_PER_TX) {
+        let instructions: Vec<Instruction> = accounts
+            .iter()
+            .flat_map(|a| a.create_instructions.iter().cloned())
+            .collect();
+        let blockhash = rpc_client.get_latest_blockhash().await?;
+        let tx = Transaction::new_signed_with_payer(
+            &instructions,
+            Some(&payer.pubkey()),
+            &[payer],
+            blockhash,
+        );
+        signatures.push(rpc_client.send_and_confirm_transaction(&tx).await?);
+    }
+    Ok(signatures)
+}
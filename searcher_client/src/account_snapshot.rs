@@ -0,0 +1,229 @@
+use std::collections::{HashMap, HashSet};
+
+use serde::{Deserialize, Serialize};
+use solana_account_decoder::UiAccountEncoding;
+use solana_client::{
+    client_error::ClientError, nonblocking::rpc_client::RpcClient,
+    rpc_config::RpcAccountInfoConfig, rpc_request::MAX_MULTIPLE_ACCOUNTS,
+};
+use solana_metrics::datapoint_info;
+use solana_sdk::{
+    account::Account, address_lookup_table::state::AddressLookupTable, clock::Slot, pubkey::Pubkey,
+    transaction::VersionedTransaction,
+};
+use thiserror::Error;
+
+use crate::local_simulator::LocalSimulator;
+
+#[derive(Debug, Error)]
+pub enum AccountSnapshotError {
+    #[error("rpc error: {0}")]
+    Rpc(#[from] ClientError),
+}
+
+/// An account as of the slot it was fetched at. None if it didn't exist.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FetchedAccount {
+    pub account: Option<Account>,
+    pub slot: Slot,
+}
+
+/// The accounts a bundle touches, including those loaded through lookup tables, to seed a
+/// [`LocalSimulator`] with. Serializable, so it can be saved with `state_snapshot` and replayed.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct AccountSnapshot {
+    /// Oldest slot any of the accounts was fetched at.
+    pub slot: Slot,
+    pub accounts: HashMap<Pubkey, FetchedAccount>,
+}
+
+impl AccountSnapshot {
+    pub fn seed(&self, simulator: &mut LocalSimulator) {
+        for (pubkey, fetched) in &self.accounts {
+            if let Some(account) = &fetched.account {
+                simulator.set_account(*pubkey, account.clone());
+            }
+        }
+    }
+
+    fn update_slot(&mut self) {
+        self.slot = self
+            .accounts
+            .values()
+            .map(|fetched| fetched.slot)
+            .min()
+            .unwrap_or_default();
+    }
+}
+
+/// Builds [`AccountSnapshot`]s from RPC, caching accounts so bundles touching the same pools
+/// only fetch what's gone stale.
+///
+/// RPC nodes only serve current state, so the slot a snapshot is asked for is the oldest state
+/// accepted: accounts are fetched with it as the minimum context slot, and cached ones are reused
+/// while they're at most `max_age_slots` older than it. Accounts fetched in different calls may
+/// come from different slots, check `AccountSnapshot::slot` when that matters.
+#[derive(Debug)]
+pub struct SnapshotFetcher {
+    max_age_slots: u64,
+    cache: HashMap<Pubkey, FetchedAccount>,
+    cache_hits: u64,
+    fetched: u64,
+}
+
+impl SnapshotFetcher {
+    pub fn new(max_age_slots: u64) -> Self {
+        Self {
+            max_age_slots,
+            cache: HashMap::new(),
+            cache_hits: 0,
+            fetched: 0,
+        }
+    }
+
+    /// Fetches every account the transactions load as of at least `slot`. Lookup tables are
+    /// fetched first to learn the addresses they load. Tables that don't exist or don't parse
+    /// are left for the simulator to report.
+    pub async fn snapshot(
+        &mut self,
+        rpc_client: &RpcClient,
+        transactions: &[VersionedTransaction],
+        slot: Slot,
+    ) -> Result<AccountSnapshot, AccountSnapshotError> {
+        self.prune(slot);
+        let lookups: Vec<_> = transactions
+            .iter()
+            .filter_map(|tx| tx.message.address_table_lookups())
+            .flatten()
+            .collect();
+        let tables: HashSet<Pubkey> = lookups.iter().map(|lookup| lookup.account_key).collect();
+        self.fetch_stale(rpc_client, &tables, slot).await?;
+
+        let mut keys: HashSet<Pubkey> = transactions
+            .iter()
+            .flat_map(|tx| tx.message.static_account_keys().iter().copied())
+            .collect();
+        for lookup in lookups {
+            let Some(table_account) = self
+                .cache
+                .get(&lookup.account_key)
+                .and_then(|fetched| fetched.account.as_ref())
+            else {
+                continue;
+            };
+            let Ok(table) = AddressLookupTable::deserialize(&table_account.data) else {
+                continue;
+            };
+            keys.extend(
+                lookup
+                    .writable_indexes
+                    .iter()
+                    .chain(lookup.readonly_indexes.iter())
+                    .filter_map(|i| table.addresses.get(*i as usize)),
+            );
+        }
+        self.fetch_stale(rpc_client, &keys, slot).await?;
+
+        let mut snapshot = AccountSnapshot {
+            slot,
+            accounts: keys
+                .into_iter()
+                .chain(tables)
+                .filter_map(|key| Some((key, self.cache.get(&key)?.clone())))
+                .collect(),
+        };
+        snapshot.update_slot();
+        Ok(snapshot)
+    }
+
+    /// Refetches the accounts in `snapshot` that are stale as of `slot` and returns the ones whose
+    /// state changed. Accounts still fresh in the cache aren't fetched again.
+    pub async fn refresh(
+        &mut self,
+        rpc_client: &RpcClient,
+        snapshot: &mut AccountSnapshot,
+        slot: Slot,
+    ) -> Result<Vec<Pubkey>, AccountSnapshotError> {
+        self.prune(slot);
+        let keys: HashSet<Pubkey> = snapshot.accounts.keys().copied().collect();
+        self.fetch_stale(rpc_client, &keys, slot).await?;
+        let mut changed = Vec::new();
+        for key in keys {
+            let Some(fetched) = self.cache.get(&key) else {
+                continue;
+            };
+            let previous = snapshot.accounts.insert(key, fetched.clone());
+            if previous.map(|previous| previous.account).as_ref() != Some(&fetched.account) {
+                changed.push(key);
+            }
+        }
+        snapshot.update_slot();
+        Ok(changed)
+    }
+
+    fn is_fresh(&self, fetched: &FetchedAccount, slot: Slot) -> bool {
+        fetched.slot.saturating_add(self.max_age_slots) >= slot
+    }
+
+    /// Forgets accounts too old to be reused at `slot`.
+    fn prune(&mut self, slot: Slot) {
+        let max_age_slots = self.max_age_slots;
+        self.cache
+            .retain(|_, fetched| fetched.slot.saturating_add(max_age_slots) >= slot);
+    }
+
+    async fn fetch_stale(
+        &mut self,
+        rpc_client: &RpcClient,
+        keys: &HashSet<Pubkey>,
+        slot: Slot,
+    ) -> Result<(), AccountSnapshotError> {
+        let stale: Vec<Pubkey> = keys
+            .iter()
+            .filter(|key| {
+                !self
+                    .cache
+                    .get(key)
+                    .is_some_and(|fetched| self.is_fresh(fetched, slot))
+            })
+            .copied()
+            .collect();
+        self.cache_hits += (keys.len() - stale.len()) as u64;
+        for chunk in stale.chunks(MAX_MULTIPLE_ACCOUNTS) {
+            let response = rpc_client
+                .get_multiple_accounts_with_config(
+                    chunk,
+                    RpcAccountInfoConfig {
+                        encoding: Some(UiAccountEncoding::Base64Zstd),
+                        commitment: Some(rpc_client.commitment()),
+                        min_context_slot: Some(slot),
+                        data_slice: None,
+                    },
+                )
+                .await?;
+            for (pubkey, account) in chunk.iter().zip(response.value) {
+                self.cache.insert(
+                    *pubkey,
+                    FetchedAccount {
+                        account,
+                        slot: response.context.slot,
+                    },
+                );
+            }
+            self.fetched += chunk.len() as u64;
+        }
+        Ok(())
+    }
+
+    /// Reports the counters and resets them.
+    pub fn report(&mut self, name: &'static str) {
+        datapoint_info!(
+            name,
+            ("cache_hits", self.cache_hits, i64),
+            ("fetched", self.fetched, i64),
+            ("cached_accounts", self.cache.len(), i64),
+        );
+        self.cache_hits = 0;
+        self.fetched = 0;
+    }
+}
@@ -0,0 +1,150 @@
+use std::{convert::Infallible, net::SocketAddr, sync::Arc};
+
+use hyper::{
+    header::{AUTHORIZATION, CONTENT_TYPE, WWW_AUTHENTICATE},
+    server::conn::AddrStream,
+    service::{make_service_fn, service_fn},
+    Body, Method, Request, Response, Server, StatusCode,
+};
+use log::{error, warn};
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use solana_metrics::datapoint_info;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum AdminServerError {
+    #[error("failed to bind admin endpoint: {0}")]
+    Bind(#[from] hyper::Error),
+    #[error("the admin token must not be empty")]
+    EmptyToken,
+}
+
+/// What the admin endpoint controls. Implemented by a running bot, which keeps whatever `status`
+/// reports up to date.
+pub trait AdminHandler: Send + Sync + 'static {
+    /// Served as the JSON body of `GET /status`.
+    fn status(&self) -> Value;
+    /// Stops sending bundles, returning false if already paused.
+    fn pause(&self) -> bool;
+    /// Sends bundles again, returning false if not paused.
+    fn resume(&self) -> bool;
+    /// Stops taking on new work and shuts down gracefully, returning false if already draining.
+    fn drain(&self) -> bool;
+}
+
+/// Serves the admin endpoint on `addr` in the background, returning the address it's bound to:
+///
+/// - `GET /status`: the handler's status
+/// - `POST /pause`, `POST /resume` and `POST /drain`: the handler's actions, answered with
+///   `{"changed": bool}`
+///
+/// Every request must carry `Authorization: Bearer <token>`. Control actions are logged and
+/// reported as `admin_request` metrics.
+pub fn spawn_admin_server(
+    addr: SocketAddr,
+    token: &str,
+    handler: Arc<dyn AdminHandler>,
+) -> Result<SocketAddr, AdminServerError> {
+    if token.is_empty() {
+        return Err(AdminServerError::EmptyToken);
+    }
+    // compared by digest so the comparison takes as long whatever the token presented
+    let token_digest: Arc<[u8]> = Sha256::digest(token.as_bytes()).to_vec().into();
+    let make_service = make_service_fn(move |connection: &AddrStream| {
+        let remote = connection.remote_addr();
+        let token_digest = token_digest.clone();
+        let handler = handler.clone();
+        async move {
+            Ok::<_, Infallible>(service_fn(move |request| {
+                let response = handle(&request, remote, &token_digest, handler.as_ref());
+                async move { Ok::<_, Infallible>(response) }
+            }))
+        }
+    });
+    let server = Server::try_bind(&addr)?.serve(make_service);
+    let local_addr = server.local_addr();
+    tokio::spawn(async move {
+        if let Err(e) = server.await {
+            error!("admin endpoint failed: {e}");
+        }
+    });
+    Ok(local_addr)
+}
+
+fn handle(
+    request: &Request<Body>,
+    remote: SocketAddr,
+    token_digest: &[u8],
+    handler: &dyn AdminHandler,
+) -> Response<Body> {
+    if !authorized(request, token_digest) {
+        warn!(
+            "rejected unauthenticated admin request {} {} from {remote}",
+            request.method(),
+            request.uri().path()
+        );
+        let mut response = json_response(
+            StatusCode::UNAUTHORIZED,
+            json!({"error": "missing or invalid bearer token"}),
+        );
+        response
+            .headers_mut()
+            .insert(WWW_AUTHENTICATE, "Bearer".parse().expect("valid header"));
+        return response;
+    }
+
+    let path = request.uri().path();
+    let action: fn(&dyn AdminHandler) -> bool = match path {
+        "/status" if request.method() == Method::GET => {
+            return json_response(StatusCode::OK, handler.status());
+        }
+        "/pause" => |handler| handler.pause(),
+        "/resume" => |handler| handler.resume(),
+        "/drain" => |handler| handler.drain(),
+        "/status" => return method_not_allowed("GET"),
+        _ => return json_response(StatusCode::NOT_FOUND, json!({"error": "not found"})),
+    };
+    if request.method() != Method::POST {
+        return method_not_allowed("POST");
+    }
+    let changed = action(handler);
+    warn!("admin {path} from {remote}, changed: {changed}");
+    datapoint_info!(
+        "admin_request",
+        ("action", path, String),
+        ("changed", changed, bool),
+    );
+    json_response(StatusCode::OK, json!({ "changed": changed }))
+}
+
+fn authorized(request: &Request<Body>, token_digest: &[u8]) -> bool {
+    let Some(token) = request
+        .headers()
+        .get(AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+    else {
+        return false;
+    };
+    Sha256::digest(token.trim().as_bytes())
+        .iter()
+        .zip(token_digest)
+        .fold(0, |difference, (a, b)| difference | (a ^ b))
+        == 0
+}
+
+fn method_not_allowed(allowed: &str) -> Response<Body> {
+    json_response(
+        StatusCode::METHOD_NOT_ALLOWED,
+        json!({ "error": format!("use {allowed}") }),
+    )
+}
+
+fn json_response(status: StatusCode, body: Value) -> Response<Body> {
+    Response::builder()
+        .status(status)
+        .header(CONTENT_TYPE, "application/json")
+        .body(Body::from(body.to_string()))
+        .expect("valid response")
+}
@@ -0,0 +1,279 @@
+use std::str::FromStr;
+
+use thiserror::Error;
+
+/// Decimals of SOL: one SOL is 10^9 lamports.
+const SOL_DECIMALS: u32 = 9;
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum AmountParseError {
+    #[error("empty amount")]
+    Empty,
+    #[error("invalid number {0:?}")]
+    InvalidNumber(String),
+    #[error("unknown unit {0:?}, use sol or lamports")]
+    UnknownUnit(String),
+    #[error("{amount} has more decimal places than the {decimals} the unit allows")]
+    TooPrecise { amount: String, decimals: u32 },
+    #[error("{0} is too large")]
+    Overflow(String),
+}
+
+/// A decimal number as typed, kept exact: `digits` scaled down by `10^scale`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct Decimal {
+    digits: u128,
+    scale: u32,
+}
+
+impl Decimal {
+    /// Parses `1_000`, `0.5` or `1.2k`. `k`, `m` and `b` multiply by a thousand, million and
+    /// billion. Returns the number and whatever follows it.
+    fn parse_prefix(s: &str) -> Result<(Self, &str), AmountParseError> {
+        let end = s
+            .find(|c: char| !(c.is_ascii_digit() || c == '.' || c == '_'))
+            .unwrap_or(s.len());
+        let (number, rest) = s.split_at(end);
+        let invalid = || AmountParseError::InvalidNumber(s.to_string());
+        let number = number.replace('_', "");
+        let (whole, fraction) = number.split_once('.').unwrap_or((&number, ""));
+        // 10^38 is the largest power of ten a u128 holds
+        if (whole.is_empty() && fraction.is_empty())
+            || fraction.contains('.')
+            || fraction.len() > 38
+        {
+            return Err(invalid());
+        }
+        let overflow = || AmountParseError::Overflow(s.to_string());
+        let mut digits: u128 = 0;
+        for c in whole.chars().chain(fraction.chars()) {
+            digits = digits
+                .checked_mul(10)
+                .and_then(|d| d.checked_add(c.to_digit(10).expect("ascii digit") as u128))
+                .ok_or_else(overflow)?;
+        }
+        let mut decimal = Self {
+            digits,
+            scale: fraction.len() as u32,
+        };
+        let rest = rest.trim_start();
+        let magnitude = match rest.chars().next() {
+            Some('k' | 'K') => Some(3),
+            Some('m' | 'M') => Some(6),
+            Some('b' | 'B') => Some(9),
+            _ => None,
+        };
+        let rest = match magnitude {
+            Some(exponent) => {
+                decimal = decimal.shift(exponent).ok_or_else(overflow)?;
+                rest[1..].trim_start()
+            }
+            None => rest,
+        };
+        Ok((decimal, rest))
+    }
+
+    /// Multiplies by `10^exponent`.
+    fn shift(self, exponent: u32) -> Option<Self> {
+        if exponent <= self.scale {
+            return Some(Self {
+                digits: self.digits,
+                scale: self.scale - exponent,
+            });
+        }
+        Some(Self {
+            digits: self
+                .digits
+                .checked_mul(10u128.checked_pow(exponent - self.scale)?)?,
+            scale: 0,
+        })
+    }
+
+    /// The amount in units of `10^-decimals`, failing if it has more decimal places than that.
+    fn to_units(self, decimals: u32, amount: &str) -> Result<u64, AmountParseError> {
+        let overflow = || AmountParseError::Overflow(amount.to_string());
+        let shifted = self.shift(decimals).ok_or_else(overflow)?;
+        if shifted.scale > 0 {
+            // trailing zeros don't add precision
+            let excess = 10u128.pow(shifted.scale);
+            if shifted.digits % excess != 0 {
+                return Err(AmountParseError::TooPrecise {
+                    amount: amount.to_string(),
+                    decimals,
+                });
+            }
+            return u64::try_from(shifted.digits / excess).map_err(|_| overflow());
+        }
+        u64::try_from(shifted.digits).map_err(|_| overflow())
+    }
+}
+
+/// A lamport amount typed for humans: `1000`, `1_000`, `1.2k lamports`, `0.5sol` or `0.5 SOL`.
+/// A number without a unit is lamports, so plain lamport values keep working.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Lamports(pub u64);
+
+impl FromStr for Lamports {
+    type Err = AmountParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        if s.is_empty() {
+            return Err(AmountParseError::Empty);
+        }
+        let (decimal, unit) = Decimal::parse_prefix(s)?;
+        let decimals = match unit.to_ascii_lowercase().as_str() {
+            "" | "lamport" | "lamports" => 0,
+            "sol" => SOL_DECIMALS,
+            _ => return Err(AmountParseError::UnknownUnit(unit.to_string())),
+        };
+        decimal.to_units(decimals, s).map(Lamports)
+    }
+}
+
+/// Value parser for lamport flags, see [`Lamports`].
+pub fn parse_lamports(s: &str) -> Result<u64, AmountParseError> {
+    Lamports::from_str(s).map(|lamports| lamports.0)
+}
+
+/// A token amount in UI units, `1.5` or `2k`, converted to base units once the mint's decimals
+/// are known, e.g. from [`crate::token_fees::MintTransferInfo::fetch`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct UiTokenAmount(Decimal);
+
+impl UiTokenAmount {
+    /// The amount in the mint's base units. Fails if it has more decimal places than the mint.
+    pub fn to_base_units(&self, decimals: u8) -> Result<u64, AmountParseError> {
+        self.0.to_units(decimals as u32, &self.to_string())
+    }
+}
+
+impl FromStr for UiTokenAmount {
+    type Err = AmountParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        if s.is_empty() {
+            return Err(AmountParseError::Empty);
+        }
+        match Decimal::parse_prefix(s)? {
+            (decimal, "") => Ok(Self(decimal)),
+            _ => Err(AmountParseError::InvalidNumber(s.to_string())),
+        }
+    }
+}
+
+impl std::fmt::Display for UiTokenAmount {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let Decimal { digits, scale } = self.0;
+        let divisor = 10u128.pow(scale);
+        if scale == 0 {
+            return write!(f, "{digits}");
+        }
+        write!(
+            f,
+            "{}.{:0width$}",
+            digits / divisor,
+            digits % divisor,
+            width = scale as usize
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use crate::amount::{parse_lamports, AmountParseError, UiTokenAmount};
+
+    #[test]
+    fn test_parse_lamports() {
+        assert_eq!(parse_lamports("1000"), Ok(1_000));
+        assert_eq!(parse_lamports(" 1_000 "), Ok(1_000));
+        assert_eq!(parse_lamports("1.2k lamports"), Ok(1_200));
+        assert_eq!(parse_lamports("2M Lamport"), Ok(2_000_000));
+        assert_eq!(parse_lamports("0.5sol"), Ok(500_000_000));
+        assert_eq!(parse_lamports("0.5 SOL"), Ok(500_000_000));
+        assert_eq!(parse_lamports(".000000001 sol"), Ok(1));
+        assert_eq!(parse_lamports("1.50"), Err(too_precise("1.50", 0)));
+        // trailing zeros don't add precision
+        assert_eq!(parse_lamports("1.000"), Ok(1));
+        assert_eq!(parse_lamports("1.0000000000 sol"), Ok(1_000_000_000));
+    }
+
+    #[test]
+    fn test_parse_lamports_invalid() {
+        assert_eq!(parse_lamports(" "), Err(AmountParseError::Empty));
+        for amount in ["-1", "-0.5 sol", ".", "_", "k", "1.2.3", "sol"] {
+            assert_eq!(
+                parse_lamports(amount),
+                Err(AmountParseError::InvalidNumber(amount.to_string())),
+                "{amount}"
+            );
+        }
+        assert_eq!(
+            parse_lamports("1 eth"),
+            Err(AmountParseError::UnknownUnit("eth".to_string()))
+        );
+        assert_eq!(
+            parse_lamports("1kb"),
+            Err(AmountParseError::UnknownUnit("b".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_too_many_decimals() {
+        assert_eq!(
+            parse_lamports("0.0000000001 sol"),
+            Err(too_precise("0.0000000001 sol", 9))
+        );
+        assert_eq!(parse_lamports("1.0005k"), Err(too_precise("1.0005k", 0)));
+        let amount = format!("0.{}1", "0".repeat(38));
+        assert_eq!(
+            parse_lamports(&amount),
+            Err(AmountParseError::InvalidNumber(amount.clone()))
+        );
+    }
+
+    #[test]
+    fn test_overflow() {
+        assert_eq!(parse_lamports("18446744073709551615"), Ok(u64::MAX));
+        for amount in [
+            "18446744073709551616",
+            "18446744073709551.616k",
+            "18446744074 sol",
+            "1000000000000000000000000000000000000000",
+            "100b sol",
+        ] {
+            assert_eq!(
+                parse_lamports(amount),
+                Err(AmountParseError::Overflow(amount.to_string())),
+                "{amount}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_ui_token_amount() {
+        let amount = UiTokenAmount::from_str("1.5k").unwrap();
+        assert_eq!(amount.to_string(), "1500");
+        assert_eq!(amount.to_base_units(6), Ok(1_500_000_000));
+
+        let amount = UiTokenAmount::from_str("0.05").unwrap();
+        assert_eq!(amount.to_string(), "0.05");
+        assert_eq!(amount.to_base_units(2), Ok(5));
+        assert_eq!(amount.to_base_units(1), Err(too_precise("0.05", 1)));
+
+        assert_eq!(
+            UiTokenAmount::from_str("1 sol"),
+            Err(AmountParseError::InvalidNumber("1 sol".to_string()))
+        );
+    }
+
+    fn too_precise(amount: &str, decimals: u32) -> AmountParseError {
+        AmountParseError::TooPrecise {
+            amount: amount.to_string(),
+            decimals,
+        }
+    }
+}
@@ -0,0 +1,409 @@
+use std::{fmt::Display, str::FromStr};
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use serde_json::{Map, Value};
+use solana_sdk::{
+    instruction::{AccountMeta, Instruction},
+    pubkey::Pubkey,
+};
+use thiserror::Error;
+
+use crate::idl_decoder::{
+    to_snake_case, Idl, IdlCompoundType, IdlError, IdlField, IdlFields, IdlInstruction, IdlType,
+    IdlTypeDefTy,
+};
+
+#[derive(Debug, Error)]
+pub enum AnchorError {
+    #[error(transparent)]
+    Idl(#[from] IdlError),
+    #[error("IDL doesn't say which program it's for")]
+    MissingProgramId,
+    #[error("no instruction {0:?} in the IDL")]
+    UnknownInstruction(String),
+    #[error("missing account {0:?}")]
+    MissingAccount(String),
+    #[error("{0:?} isn't one of the instruction's accounts")]
+    UnknownAccount(String),
+    #[error("missing arg {0:?}")]
+    MissingArg(String),
+    #[error("invalid arg {0:?}: {1}")]
+    InvalidArg(String, String),
+}
+
+/// Builds instructions of an Anchor program from its IDL, with the discriminator, the args laid
+/// out as the program reads them and the account metas in its order, so calling it doesn't
+/// take hand-rolled byte layouts. Both the legacy IDL format and the one introduced in Anchor
+/// 0.30 are read.
+#[derive(Clone, Debug)]
+pub struct AnchorProgram {
+    program_id: Pubkey,
+    idl: Idl,
+}
+
+impl AnchorProgram {
+    pub fn new(program_id: Pubkey, idl: Idl) -> Self {
+        Self { program_id, idl }
+    }
+
+    /// For the program the IDL says it's for.
+    pub fn from_idl(idl: Idl) -> Result<Self, AnchorError> {
+        let program_id = idl.program_id().ok_or(AnchorError::MissingProgramId)?;
+        Ok(Self::new(program_id, idl))
+    }
+
+    pub fn program_id(&self) -> Pubkey {
+        self.program_id
+    }
+
+    pub fn idl(&self) -> &Idl {
+        &self.idl
+    }
+
+    /// Builds a call to `method` with `accounts` naming the key passed as each of its accounts.
+    /// `args` is a JSON object of the instruction's args in the form [`IdlRegistry`] decodes
+    /// them to: integers as numbers or strings, pubkeys in base58, bytes in base64, options as
+    /// null, enums as the variant name or `{"Variant": fields}` and structs as objects.
+    ///
+    /// Methods, accounts and args may be named as in the IDL or in snake_case. Accounts the IDL
+    /// gives an address for may be left out, as may optional accounts, which are then passed
+    /// as the program id the way Anchor expects. PDAs aren't derived and have to be named.
+    /// Remaining accounts can be appended to the instruction's accounts.
+    ///
+    /// [`IdlRegistry`]: crate::idl_decoder::IdlRegistry
+    pub fn instruction(
+        &self,
+        method: &str,
+        args: &Value,
+        accounts: &[(&str, Pubkey)],
+    ) -> Result<Instruction, AnchorError> {
+        let ix = self.find_instruction(method)?;
+        let mut metas = vec![];
+        flatten_accounts(&ix.accounts, &mut metas);
+        if let Some((name, _)) = accounts
+            .iter()
+            .find(|(name, _)| !metas.iter().any(|meta| same_name(account_name(meta), name)))
+        {
+            return Err(AnchorError::UnknownAccount(name.to_string()));
+        }
+
+        let accounts = metas
+            .into_iter()
+            .map(|meta| {
+                let name = account_name(meta);
+                let flag = |new: &str, legacy: &str| {
+                    meta.get(new)
+                        .or_else(|| meta.get(legacy))
+                        .and_then(Value::as_bool)
+                        .unwrap_or(false)
+                };
+                let given = accounts
+                    .iter()
+                    .find(|(given, _)| same_name(name, given))
+                    .map(|(_, pubkey)| *pubkey);
+                let fixed = meta
+                    .get("address")
+                    .and_then(Value::as_str)
+                    .and_then(|address| Pubkey::from_str(address).ok());
+                match given.or(fixed) {
+                    Some(pubkey) => Ok(AccountMeta {
+                        pubkey,
+                        is_signer: flag("signer", "isSigner"),
+                        is_writable: flag("writable", "isMut"),
+                    }),
+                    None if flag("optional", "isOptional") => {
+                        Ok(AccountMeta::new_readonly(self.program_id, false))
+                    }
+                    None => Err(AnchorError::MissingAccount(name.to_string())),
+                }
+            })
+            .collect::<Result<_, _>>()?;
+
+        Ok(Instruction {
+            program_id: self.program_id,
+            accounts,
+            data: self.encode_args(ix, args)?,
+        })
+    }
+
+    /// The data of a call to `method`: its discriminator followed by `args`, given as for
+    /// [`Self::instruction`].
+    pub fn instruction_data(&self, method: &str, args: &Value) -> Result<Vec<u8>, AnchorError> {
+        self.encode_args(self.find_instruction(method)?, args)
+    }
+
+    fn find_instruction(&self, method: &str) -> Result<&IdlInstruction, AnchorError> {
+        self.idl
+            .instructions
+            .iter()
+            .find(|ix| same_name(&ix.name, method))
+            .ok_or_else(|| AnchorError::UnknownInstruction(method.to_string()))
+    }
+
+    fn encode_args(&self, ix: &IdlInstruction, args: &Value) -> Result<Vec<u8>, AnchorError> {
+        let mut data = ix.discriminator().to_vec();
+        let empty = Value::Object(Map::new());
+        let args = match args {
+            Value::Null => &empty,
+            args => args,
+        };
+        encode_named_fields(&self.idl, &ix.args, args, "", &mut data)?;
+        Ok(data)
+    }
+}
+
+fn same_name(a: &str, b: &str) -> bool {
+    a == b || to_snake_case(a) == to_snake_case(b)
+}
+
+fn account_name(meta: &Value) -> &str {
+    meta.get("name")
+        .and_then(Value::as_str)
+        .unwrap_or("unnamed")
+}
+
+/// Accounts in order, legacy IDLs nest them in named groups.
+fn flatten_accounts<'a>(accounts: &'a [Value], flattened: &mut Vec<&'a Value>) {
+    for account in accounts {
+        match account.get("accounts").and_then(Value::as_array) {
+            Some(group) => flatten_accounts(group, flattened),
+            None => flattened.push(account),
+        }
+    }
+}
+
+fn join(path: &str, name: &str) -> String {
+    match path {
+        "" => name.to_string(),
+        path => format!("{path}.{name}"),
+    }
+}
+
+fn invalid(path: &str, reason: impl Display) -> AnchorError {
+    AnchorError::InvalidArg(path.to_string(), reason.to_string())
+}
+
+fn encode_named_fields(
+    idl: &Idl,
+    fields: &[IdlField],
+    value: &Value,
+    path: &str,
+    out: &mut Vec<u8>,
+) -> Result<(), AnchorError> {
+    let object = value
+        .as_object()
+        .ok_or_else(|| invalid(path, "expected an object"))?;
+    if let Some(key) = object
+        .keys()
+        .find(|key| !fields.iter().any(|field| same_name(&field.name, key)))
+    {
+        return Err(invalid(&join(path, key), "no such field"));
+    }
+    for field in fields {
+        let path = join(path, &field.name);
+        let value = object
+            .get(&field.name)
+            .or_else(|| {
+                object
+                    .iter()
+                    .find(|(key, _)| same_name(&field.name, key))
+                    .map(|(_, value)| value)
+            })
+            .unwrap_or(&Value::Null);
+        let optional = matches!(
+            field.ty,
+            IdlType::Compound(IdlCompoundType::Option(_) | IdlCompoundType::COption(_))
+        );
+        if value.is_null() && !optional {
+            return Err(AnchorError::MissingArg(path));
+        }
+        encode_type(idl, &field.ty, value, &path, out)?;
+    }
+    Ok(())
+}
+
+fn encode_fields(
+    idl: &Idl,
+    fields: &IdlFields,
+    value: &Value,
+    path: &str,
+    out: &mut Vec<u8>,
+) -> Result<(), AnchorError> {
+    match fields {
+        IdlFields::Named(fields) => encode_named_fields(idl, fields, value, path, out),
+        IdlFields::Tuple(types) => {
+            let values = elements(value, Some(types.len()), path)?;
+            for (i, (ty, value)) in types.iter().zip(values).enumerate() {
+                encode_type(idl, ty, value, &format!("{path}[{i}]"), out)?;
+            }
+            Ok(())
+        }
+    }
+}
+
+fn encode_type_def(
+    idl: &Idl,
+    ty: &IdlTypeDefTy,
+    value: &Value,
+    path: &str,
+    out: &mut Vec<u8>,
+) -> Result<(), AnchorError> {
+    match ty {
+        IdlTypeDefTy::Struct { fields: None } => Ok(()),
+        IdlTypeDefTy::Struct {
+            fields: Some(fields),
+        } => encode_fields(idl, fields, value, path, out),
+        IdlTypeDefTy::Enum { variants } => {
+            let (name, fields) = match value {
+                Value::String(name) => (name, None),
+                Value::Object(object) if object.len() == 1 => {
+                    let (name, fields) = object.iter().next().expect("one entry");
+                    (name, Some(fields))
+                }
+                _ => {
+                    return Err(invalid(
+                        path,
+                        "expected a variant name or {\"Variant\": fields}",
+                    ))
+                }
+            };
+            let index = variants
+                .iter()
+                .position(|variant| same_name(&variant.name, name))
+                .ok_or_else(|| invalid(path, format!("no variant {name:?}")))?;
+            out.push(index as u8);
+            match (&variants[index].fields, fields) {
+                (None, None) => Ok(()),
+                (Some(variant_fields), Some(fields)) => {
+                    encode_fields(idl, variant_fields, fields, &join(path, name), out)
+                }
+                (None, Some(_)) => Err(invalid(path, format!("variant {name:?} has no fields"))),
+                (Some(_), None) => Err(invalid(path, format!("variant {name:?} needs fields"))),
+            }
+        }
+        IdlTypeDefTy::Type { alias } => encode_type(idl, alias, value, path, out),
+    }
+}
+
+fn elements<'a>(
+    value: &'a Value,
+    len: Option<usize>,
+    path: &str,
+) -> Result<&'a Vec<Value>, AnchorError> {
+    let values = value
+        .as_array()
+        .ok_or_else(|| invalid(path, "expected an array"))?;
+    match len {
+        Some(len) if values.len() != len => Err(invalid(
+            path,
+            format!("expected {len} elements, got {}", values.len()),
+        )),
+        _ => Ok(values),
+    }
+}
+
+/// Integers are taken as JSON numbers or, past what those hold exactly, strings.
+fn number<T: FromStr>(value: &Value, path: &str) -> Result<T, AnchorError>
+where
+    T::Err: Display,
+{
+    let text = match value {
+        Value::Number(number) => number.to_string(),
+        Value::String(text) => text.clone(),
+        _ => return Err(invalid(path, "expected a number")),
+    };
+    text.parse().map_err(|e| invalid(path, e))
+}
+
+fn encode_type(
+    idl: &Idl,
+    ty: &IdlType,
+    value: &Value,
+    path: &str,
+    out: &mut Vec<u8>,
+) -> Result<(), AnchorError> {
+    let primitive = match ty {
+        IdlType::Primitive(primitive) => primitive.as_str(),
+        IdlType::Compound(IdlCompoundType::Vec(ty)) => {
+            let values = elements(value, None, path)?;
+            out.extend((values.len() as u32).to_le_bytes());
+            for (i, value) in values.iter().enumerate() {
+                encode_type(idl, ty, value, &format!("{path}[{i}]"), out)?;
+            }
+            return Ok(());
+        }
+        IdlType::Compound(IdlCompoundType::Option(ty)) => {
+            if value.is_null() {
+                out.push(0);
+                return Ok(());
+            }
+            out.push(1);
+            return encode_type(idl, ty, value, path, out);
+        }
+        IdlType::Compound(IdlCompoundType::COption(ty)) => {
+            if value.is_null() {
+                out.extend(0u32.to_le_bytes());
+                return Ok(());
+            }
+            out.extend(1u32.to_le_bytes());
+            return encode_type(idl, ty, value, path, out);
+        }
+        IdlType::Compound(IdlCompoundType::Array(ty, len)) => {
+            for (i, value) in elements(value, Some(*len), path)?.iter().enumerate() {
+                encode_type(idl, ty, value, &format!("{path}[{i}]"), out)?;
+            }
+            return Ok(());
+        }
+        IdlType::Compound(IdlCompoundType::Defined(defined)) => {
+            let ty = idl.type_def(defined.name())?;
+            return encode_type_def(idl, ty, value, path, out);
+        }
+    };
+    match primitive {
+        "bool" => {
+            let value = value
+                .as_bool()
+                .ok_or_else(|| invalid(path, "expected a bool"))?;
+            out.push(value as u8);
+        }
+        "u8" => out.extend(number::<u8>(value, path)?.to_le_bytes()),
+        "i8" => out.extend(number::<i8>(value, path)?.to_le_bytes()),
+        "u16" => out.extend(number::<u16>(value, path)?.to_le_bytes()),
+        "i16" => out.extend(number::<i16>(value, path)?.to_le_bytes()),
+        "u32" => out.extend(number::<u32>(value, path)?.to_le_bytes()),
+        "i32" => out.extend(number::<i32>(value, path)?.to_le_bytes()),
+        "u64" => out.extend(number::<u64>(value, path)?.to_le_bytes()),
+        "i64" => out.extend(number::<i64>(value, path)?.to_le_bytes()),
+        "u128" => out.extend(number::<u128>(value, path)?.to_le_bytes()),
+        "i128" => out.extend(number::<i128>(value, path)?.to_le_bytes()),
+        "f32" => out.extend(number::<f32>(value, path)?.to_le_bytes()),
+        "f64" => out.extend(number::<f64>(value, path)?.to_le_bytes()),
+        "publicKey" | "pubkey" => {
+            let pubkey = value
+                .as_str()
+                .ok_or_else(|| invalid(path, "expected a base58 pubkey"))?;
+            out.extend(
+                Pubkey::from_str(pubkey)
+                    .map_err(|e| invalid(path, e))?
+                    .to_bytes(),
+            );
+        }
+        "string" => {
+            let text = value
+                .as_str()
+                .ok_or_else(|| invalid(path, "expected a string"))?;
+            out.extend((text.len() as u32).to_le_bytes());
+            out.extend(text.as_bytes());
+        }
+        "bytes" => {
+            let bytes = value
+                .as_str()
+                .ok_or_else(|| invalid(path, "expected base64"))
+                .and_then(|encoded| STANDARD.decode(encoded).map_err(|e| invalid(path, e)))?;
+            out.extend((bytes.len() as u32).to_le_bytes());
+            out.extend(bytes);
+        }
+        other => return Err(IdlError::UnknownType(other.to_string()).into()),
+    }
+    Ok(())
+}
@@ -0,0 +1,168 @@
+use solana_client::{
+    client_error::{ClientErrorKind, Result as ClientResult},
+    nonblocking::rpc_client::RpcClient,
+};
+use solana_sdk::{
+    clock::Slot,
+    instruction::{AccountMeta, Instruction},
+    pubkey::Pubkey,
+    sysvar,
+};
+
+/// Instructions understood by the assertion program. Each assertion is a tag byte followed by
+/// its little-endian arguments, and the program fails the transaction when the assertion
+/// doesn't hold. The program isn't part of this repo, `program_id` should point at a deployed
+/// checker or guard program implementing this layout.
+#[repr(u8)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AssertionTag {
+    /// Accounts: [clock sysvar]. Args: min_slot: u64, max_slot: u64.
+    SlotInRange = 0,
+    /// Accounts: [token account]. Args: min_amount: u64.
+    TokenBalanceAtLeast = 1,
+    /// Accounts: [account]. Args: min_lamports: u64.
+    LamportsAtLeast = 2,
+}
+
+fn assertion_instruction(
+    program_id: Pubkey,
+    tag: AssertionTag,
+    args: &[u64],
+    account: Pubkey,
+) -> Instruction {
+    let mut data = Vec::with_capacity(1 + args.len() * 8);
+    data.push(tag as u8);
+    for arg in args {
+        data.extend_from_slice(&arg.to_le_bytes());
+    }
+    Instruction::new_with_bytes(
+        program_id,
+        &data,
+        vec![AccountMeta::new_readonly(account, false)],
+    )
+}
+
+/// Reverts the transaction if it executes outside a slot range, so a stale opportunity can't
+/// land late even if bundles stop being limited to the current leader.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SlotAssertion {
+    pub program_id: Pubkey,
+    pub min_slot: Slot,
+    pub max_slot: Slot,
+}
+
+impl SlotAssertion {
+    /// Allows execution from `current_slot` up to `window_slots` slots after it.
+    pub fn window(program_id: Pubkey, current_slot: Slot, window_slots: u64) -> Self {
+        Self {
+            program_id,
+            min_slot: current_slot,
+            max_slot: current_slot.saturating_add(window_slots),
+        }
+    }
+
+    pub fn instruction(&self) -> Instruction {
+        assertion_instruction(
+            self.program_id,
+            AssertionTag::SlotInRange,
+            &[self.min_slot, self.max_slot],
+            sysvar::clock::id(),
+        )
+    }
+}
+
+/// Reverts the transaction unless a token account (SPL Token or Token-2022) holds at least
+/// `min_amount` once the preceding instructions have run. Placed after a swap it acts as a
+/// min-out check that covers the whole bundle.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TokenBalanceAssertion {
+    pub program_id: Pubkey,
+    pub token_account: Pubkey,
+    pub min_amount: u64,
+}
+
+impl TokenBalanceAssertion {
+    /// Requires the balance to have grown by at least `min_out` from `balance_before`.
+    pub fn min_out(
+        program_id: Pubkey,
+        token_account: Pubkey,
+        balance_before: u64,
+        min_out: u64,
+    ) -> Self {
+        Self {
+            program_id,
+            token_account,
+            min_amount: balance_before.saturating_add(min_out),
+        }
+    }
+
+    /// Reads the current balance of `token_account` and requires it to grow by at least
+    /// `min_out`. The balance may change between the read and execution, in which case the
+    /// assertion errs on the side of reverting if it went down.
+    pub async fn fetch_min_out(
+        rpc_client: &RpcClient,
+        program_id: Pubkey,
+        token_account: Pubkey,
+        min_out: u64,
+    ) -> ClientResult<Self> {
+        let balance = rpc_client.get_token_account_balance(&token_account).await?;
+        let balance_before = balance.amount.parse().map_err(|_| {
+            ClientErrorKind::Custom(format!("invalid token amount {}", balance.amount))
+        })?;
+        Ok(Self::min_out(
+            program_id,
+            token_account,
+            balance_before,
+            min_out,
+        ))
+    }
+
+    pub fn instruction(&self) -> Instruction {
+        assertion_instruction(
+            self.program_id,
+            AssertionTag::TokenBalanceAtLeast,
+            &[self.min_amount],
+            self.token_account,
+        )
+    }
+}
+
+/// Reverts the transaction unless an account holds at least `min_lamports`, for bundles that
+/// settle in SOL.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LamportsAssertion {
+    pub program_id: Pubkey,
+    pub account: Pubkey,
+    pub min_lamports: u64,
+}
+
+impl LamportsAssertion {
+    /// Requires the balance to have grown by at least `min_out` from `balance_before`. Fees and
+    /// tips paid by the account between the read and the assertion count against it.
+    pub fn min_out(program_id: Pubkey, account: Pubkey, balance_before: u64, min_out: u64) -> Self {
+        Self {
+            program_id,
+            account,
+            min_lamports: balance_before.saturating_add(min_out),
+        }
+    }
+
+    pub async fn fetch_min_out(
+        rpc_client: &RpcClient,
+        program_id: Pubkey,
+        account: Pubkey,
+        min_out: u64,
+    ) -> ClientResult<Self> {
+        let balance_before = rpc_client.get_balance(&account).await?;
+        Ok(Self::min_out(program_id, account, balance_before, min_out))
+    }
+
+    pub fn instruction(&self) -> Instruction {
+        assertion_instruction(
+            self.program_id,
+            AssertionTag::LamportsAtLeast,
+            &[self.min_lamports],
+            self.account,
+        )
+    }
+}
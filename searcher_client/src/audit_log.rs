@@ -0,0 +1,793 @@
+use std::{
+    collections::HashMap,
+    fs::{self, File, OpenOptions},
+    io::{self, BufRead, BufReader, BufWriter, Read, Seek, SeekFrom, Write},
+    path::{Path, PathBuf},
+    str::FromStr,
+    sync::Arc,
+};
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use serde_json::json;
+use solana_sdk::{
+    clock::Slot,
+    hash::{hashv, Hash},
+    pubkey::Pubkey,
+    signature::{Keypair, Signature, Signer},
+    transaction::VersionedTransaction,
+};
+
+/// Start of the chain fields, which signed entries end with.
+const CHAIN_FIELDS: &str = ",\"chain_prev\":";
+
+/// How a bundle was submitted, logged with each of its transactions.
+#[derive(Clone, Debug)]
+pub struct BundleSubmission<'a> {
+    pub submitted_at: DateTime<Utc>,
+    pub submitted_slot: Slot,
+    /// How far the next Jito leader was at submission.
+    pub slots_to_leader: Option<u64>,
+    /// The next Jito leader at submission, the one the bundle was aimed at.
+    pub leader: Option<Pubkey>,
+    /// Block engine regions the bundle was sent through.
+    pub regions: &'a [String],
+    /// The reason the block engine gave for not accepting the bundle.
+    pub rejection: Option<&'a str>,
+}
+
+/// Append-only JSONL log with one line per signed transaction submitted, for audit and
+/// compliance. Rotates to `<path>.1`, `<path>.2`, ... once the active file reaches `max_bytes`,
+/// keeping at most `max_files` rotated files.
+///
+/// With a signer, every entry is also hash-linked to the one before it and signed, making the
+/// log tamper-evident: editing, removing or reordering entries breaks the chain, see
+/// [`verify_audit_chain`].
+pub struct AuditLog {
+    path: PathBuf,
+    max_bytes: u64,
+    max_files: usize,
+    writer: BufWriter<File>,
+    written: u64,
+    signer: Option<Arc<Keypair>>,
+    /// Hash of the last entry written, the default hash before the first one.
+    last_hash: Hash,
+}
+
+impl AuditLog {
+    pub fn open(path: impl Into<PathBuf>, max_bytes: u64, max_files: usize) -> io::Result<Self> {
+        let path = path.into();
+        let (writer, written) = open_append(&path)?;
+        Ok(Self {
+            path,
+            max_bytes,
+            max_files,
+            writer,
+            written,
+            signer: None,
+            last_hash: Hash::default(),
+        })
+    }
+
+    /// Signs every entry from now on with `signer`, chained to the last entry already in the
+    /// log, in the active file or the last rotated one. A log without signed entries starts a
+    /// new chain.
+    pub fn with_signer(mut self, signer: Arc<Keypair>) -> io::Result<Self> {
+        let mut last_line = last_line(&self.path)?;
+        if last_line.is_none() && self.max_files > 0 {
+            last_line = last_line_if_exists(&rotated_path(&self.path, 1))?;
+        }
+        if let Some(line) = last_line {
+            if let Some((_, chain)) = split_chain(&line)? {
+                self.last_hash = chain.hash()?;
+            }
+        }
+        self.signer = Some(signer);
+        Ok(self)
+    }
+
+    /// Records every transaction in a bundle. `bundle_uuid` is None if the block engine didn't
+    /// accept the bundle. Nothing is written if any of the transactions isn't signed.
+    pub fn record_bundle(
+        &mut self,
+        bundle_uuid: Option<&str>,
+        transactions: &[VersionedTransaction],
+        submission: &BundleSubmission,
+    ) -> io::Result<()> {
+        let signatures = transactions
+            .iter()
+            .enumerate()
+            .map(|(index, tx)| {
+                tx.signatures.first().ok_or_else(|| {
+                    invalid_data(format!(
+                        "transaction {index} of the bundle has no signatures"
+                    ))
+                })
+            })
+            .collect::<io::Result<Vec<&Signature>>>()?;
+        for (index, (tx, signature)) in transactions.iter().zip(&signatures).enumerate() {
+            let num_signers = tx.message.header().num_required_signatures as usize;
+            let signers: Vec<String> = tx
+                .message
+                .static_account_keys()
+                .iter()
+                .take(num_signers)
+                .map(|pubkey| pubkey.to_string())
+                .collect();
+            let serialized = bincode::serialize(tx)
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+            let record = json!({
+                "submitted_at": submission.submitted_at.to_rfc3339(),
+                "bundle_uuid": bundle_uuid,
+                "bundle_index": index,
+                "submitted_slot": submission.submitted_slot,
+                "slots_to_leader": submission.slots_to_leader,
+                "leader": submission.leader.map(|leader| leader.to_string()),
+                "regions": submission.regions,
+                "rejection": submission.rejection,
+                "signature": signature.to_string(),
+                "signers": signers,
+                "transaction": STANDARD.encode(serialized),
+            });
+            let mut line = record.to_string();
+            if let Some(signer) = self.signer.clone() {
+                line = self.chain(&signer, line, &signatures);
+            }
+            line.push('\n');
+            self.writer.write_all(line.as_bytes())?;
+            self.written += line.len() as u64;
+        }
+        self.writer.flush()?;
+
+        if self.written >= self.max_bytes {
+            self.rotate()?;
+        }
+        Ok(())
+    }
+
+    /// Appends the bundle hash and the chain fields to a record: the previous entry's hash, the
+    /// hash of that and the record, the signer and its signature of the hash.
+    fn chain(&mut self, signer: &Keypair, mut line: String, signatures: &[&Signature]) -> String {
+        // a hash of the transaction signatures rather than the block engine's bundle id, so
+        // rejected bundles, which never get one, can be matched up too
+        let signatures: Vec<&[u8]> = signatures
+            .iter()
+            .map(|signature| signature.as_ref())
+            .collect();
+        line.pop();
+        line.push_str(&format!(",\"bundle_hash\":\"{}\"}}", hashv(&signatures)));
+        let hash = hashv(&[self.last_hash.as_ref(), line.as_bytes()]);
+        let signature = signer.sign_message(hash.as_ref());
+        line.pop();
+        line.push_str(&format!(
+            "{CHAIN_FIELDS}\"{}\",\"chain_hash\":\"{hash}\",\"chain_signer\":\"{}\",\"chain_signature\":\"{signature}\"}}",
+            self.last_hash,
+            signer.pubkey()
+        ));
+        self.last_hash = hash;
+        line
+    }
+
+    fn rotate(&mut self) -> io::Result<()> {
+        self.writer.flush()?;
+        if self.max_files == 0 {
+            fs::remove_file(&self.path)?;
+        } else {
+            let oldest = rotated_path(&self.path, self.max_files);
+            if oldest.exists() {
+                fs::remove_file(&oldest)?;
+            }
+            for n in (1..self.max_files).rev() {
+                let from = rotated_path(&self.path, n);
+                if from.exists() {
+                    fs::rename(&from, rotated_path(&self.path, n + 1))?;
+                }
+            }
+            fs::rename(&self.path, rotated_path(&self.path, 1))?;
+        }
+        (self.writer, self.written) = open_append(&self.path)?;
+        Ok(())
+    }
+}
+
+/// A bundle read back from an audit log.
+#[derive(Clone, Debug)]
+pub struct AuditedBundle {
+    pub bundle_uuid: String,
+    pub submitted_at: Option<DateTime<Utc>>,
+    pub submitted_slot: Option<Slot>,
+    pub slots_to_leader: Option<u64>,
+    /// The leader the bundle was aimed at, None for records written before it was logged.
+    pub leader: Option<String>,
+    pub regions: Vec<String>,
+    pub transactions: Vec<VersionedTransaction>,
+}
+
+/// A bundle the block engine didn't accept, read back from an audit log.
+#[derive(Clone, Debug)]
+pub struct AuditedRejection {
+    pub submitted_at: Option<DateTime<Utc>>,
+    /// None for records written before reasons were logged.
+    pub reason: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct AuditRecord {
+    bundle_uuid: Option<String>,
+    bundle_index: usize,
+    submitted_at: Option<DateTime<Utc>>,
+    submitted_slot: Option<Slot>,
+    slots_to_leader: Option<u64>,
+    #[serde(default)]
+    leader: Option<String>,
+    #[serde(default)]
+    regions: Vec<String>,
+    #[serde(default)]
+    rejection: Option<String>,
+    transaction: String,
+}
+
+/// Reads the bundles the block engine accepted from an audit log, in submission order.
+/// Records written before slots were logged have them set to None.
+pub fn read_audit_log(path: &Path) -> io::Result<Vec<AuditedBundle>> {
+    let invalid = |e: String| io::Error::new(io::ErrorKind::InvalidData, e);
+    let mut bundles: Vec<AuditedBundle> = vec![];
+    let mut positions: HashMap<String, usize> = HashMap::new();
+    let mut records: Vec<(usize, usize, VersionedTransaction)> = vec![];
+
+    for line in BufReader::new(File::open(path)?).lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let record: AuditRecord =
+            serde_json::from_str(&line).map_err(|e| invalid(e.to_string()))?;
+        let Some(bundle_uuid) = record.bundle_uuid else {
+            continue;
+        };
+        let bytes = STANDARD
+            .decode(&record.transaction)
+            .map_err(|e| invalid(e.to_string()))?;
+        let tx: VersionedTransaction =
+            bincode::deserialize(&bytes).map_err(|e| invalid(e.to_string()))?;
+
+        let position = *positions.entry(bundle_uuid.clone()).or_insert_with(|| {
+            bundles.push(AuditedBundle {
+                bundle_uuid,
+                submitted_at: record.submitted_at,
+                submitted_slot: record.submitted_slot,
+                slots_to_leader: record.slots_to_leader,
+                leader: record.leader,
+                regions: record.regions,
+                transactions: vec![],
+            });
+            bundles.len() - 1
+        });
+        records.push((position, record.bundle_index, tx));
+    }
+
+    records.sort_by_key(|(position, index, _)| (*position, *index));
+    for (position, _, tx) in records {
+        bundles[position].transactions.push(tx);
+    }
+    Ok(bundles)
+}
+
+/// Reads the bundles the block engine didn't accept from an audit log, in submission order.
+pub fn read_audit_rejections(path: &Path) -> io::Result<Vec<AuditedRejection>> {
+    let mut rejections = vec![];
+    for line in BufReader::new(File::open(path)?).lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let record: AuditRecord = serde_json::from_str(&line)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        // every transaction is a record, the first stands for the bundle
+        if record.bundle_uuid.is_none() && record.bundle_index == 0 {
+            rejections.push(AuditedRejection {
+                submitted_at: record.submitted_at,
+                reason: record.rejection,
+            });
+        }
+    }
+    Ok(rejections)
+}
+
+#[derive(Deserialize)]
+struct ChainFields {
+    chain_prev: String,
+    chain_hash: String,
+    chain_signer: String,
+    chain_signature: String,
+}
+
+impl ChainFields {
+    fn hash(&self) -> io::Result<Hash> {
+        Hash::from_str(&self.chain_hash).map_err(invalid_data)
+    }
+
+    /// The previous entry's hash, the entry's hash, the signer and the signature, or why one of
+    /// them doesn't parse.
+    fn parse(&self) -> Result<(Hash, Hash, Pubkey, Signature), &'static str> {
+        let prev = Hash::from_str(&self.chain_prev).map_err(|_| "malformed chain_prev")?;
+        let hash = Hash::from_str(&self.chain_hash).map_err(|_| "malformed chain_hash")?;
+        let signer = Pubkey::from_str(&self.chain_signer).map_err(|_| "malformed chain_signer")?;
+        let signature =
+            Signature::from_str(&self.chain_signature).map_err(|_| "malformed chain_signature")?;
+        Ok((prev, hash, signer, signature))
+    }
+}
+
+/// Splits a signed entry into the record its chain hash covers and its chain fields, None for
+/// an unsigned entry.
+fn split_chain(line: &str) -> io::Result<Option<(String, ChainFields)>> {
+    let Some(start) = line.rfind(CHAIN_FIELDS) else {
+        return Ok(None);
+    };
+    let chain = serde_json::from_str(&format!("{{{}", &line[start + 1..])).map_err(invalid_data)?;
+    Ok(Some((format!("{}}}", &line[..start]), chain)))
+}
+
+/// Where a hash chain stops verifying.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ChainBreak {
+    pub path: PathBuf,
+    /// 1-based.
+    pub line: usize,
+    pub reason: String,
+}
+
+/// The outcome of checking the hash chain of an audit log.
+#[derive(Clone, Debug, Default)]
+pub struct AuditChainReport {
+    /// Signed entries whose hash, link and signature check out.
+    pub verified: usize,
+    /// Entries written before signing was turned on, ahead of the chain.
+    pub unsigned: usize,
+    /// Keys the verified entries were signed with, in the order they first appear.
+    pub signers: Vec<Pubkey>,
+    /// Whether the first signed entry starts the chain, rather than continuing from entries in
+    /// files rotated away.
+    pub starts_at_genesis: bool,
+    /// Hash of the last verified entry. Recording it elsewhere from time to time also makes
+    /// entries removed from the end of the log detectable.
+    pub last_hash: Option<Hash>,
+    pub broken: Option<ChainBreak>,
+}
+
+/// Checks the hash chain of a signed audit log, given its files oldest first: every signed
+/// entry must hash to its `chain_hash`, link to the entry before it and carry a valid signature.
+/// Stops at the first entry that doesn't, at entries that don't parse, and at unsigned entries
+/// once the chain started. Only failing to read the files is an error.
+pub fn verify_audit_chain(paths: &[PathBuf]) -> io::Result<AuditChainReport> {
+    let mut report = AuditChainReport::default();
+    for path in paths {
+        for (index, line) in BufReader::new(File::open(path)?).lines().enumerate() {
+            let broken = |reason: &str| ChainBreak {
+                path: path.clone(),
+                line: index + 1,
+                reason: reason.to_string(),
+            };
+            let line = match line {
+                Ok(line) => line,
+                Err(e) if e.kind() == io::ErrorKind::InvalidData => {
+                    report.broken = Some(broken("entry isn't valid UTF-8"));
+                    return Ok(report);
+                }
+                Err(e) => return Err(e),
+            };
+            if line.trim().is_empty() {
+                continue;
+            }
+            let Ok(split) = split_chain(&line) else {
+                report.broken = Some(broken("chain fields don't parse"));
+                return Ok(report);
+            };
+            let Some((record, chain)) = split else {
+                if report.last_hash.is_some() {
+                    report.broken = Some(broken("entry isn't signed"));
+                    return Ok(report);
+                }
+                report.unsigned += 1;
+                continue;
+            };
+            let (prev, chain_hash, signer, signature) = match chain.parse() {
+                Ok(fields) => fields,
+                Err(reason) => {
+                    report.broken = Some(broken(reason));
+                    return Ok(report);
+                }
+            };
+            let hash = hashv(&[prev.as_ref(), record.as_bytes()]);
+            let reason = match report.last_hash {
+                Some(last_hash) if prev != last_hash => Some("doesn't link to the entry before it"),
+                _ if chain_hash != hash => Some("hash doesn't match the entry"),
+                _ if !signature.verify(signer.as_ref(), hash.as_ref()) => {
+                    Some("signature doesn't verify")
+                }
+                _ => None,
+            };
+            if let Some(reason) = reason {
+                report.broken = Some(broken(reason));
+                return Ok(report);
+            }
+            if report.last_hash.is_none() {
+                report.starts_at_genesis = prev == Hash::default();
+            }
+            if !report.signers.contains(&signer) {
+                report.signers.push(signer);
+            }
+            report.verified += 1;
+            report.last_hash = Some(hash);
+        }
+    }
+    Ok(report)
+}
+
+/// The files of an audit log oldest first: its rotated files, then the active one.
+pub fn audit_log_files(path: &Path) -> Vec<PathBuf> {
+    let mut files: Vec<PathBuf> = (1..)
+        .map(|n| rotated_path(path, n))
+        .take_while(|rotated| rotated.exists())
+        .collect();
+    files.reverse();
+    files.push(path.to_path_buf());
+    files
+}
+
+fn invalid_data(e: impl ToString) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, e.to_string())
+}
+
+fn last_line_if_exists(path: &Path) -> io::Result<Option<String>> {
+    match last_line(path) {
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+        result => result,
+    }
+}
+
+/// The last non-empty line of a file, read backwards from its end.
+fn last_line(path: &Path) -> io::Result<Option<String>> {
+    const CHUNK: u64 = 8 * 1024;
+    let mut file = File::open(path)?;
+    let mut end = file.metadata()?.len();
+    let mut tail: Vec<u8> = vec![];
+    while end > 0 {
+        let start = end.saturating_sub(CHUNK);
+        let mut chunk = vec![0; (end - start) as usize];
+        file.seek(SeekFrom::Start(start))?;
+        file.read_exact(&mut chunk)?;
+        chunk.extend(tail);
+        tail = chunk;
+        end = start;
+        let content = tail.trim_ascii_end();
+        if let Some(newline) = content.iter().rposition(|byte| *byte == b'\n') {
+            return Ok(Some(
+                String::from_utf8_lossy(&content[newline + 1..]).into_owned(),
+            ));
+        }
+    }
+    let content = tail.trim_ascii_end();
+    Ok((!content.is_empty()).then(|| String::from_utf8_lossy(content).into_owned()))
+}
+
+fn rotated_path(path: &Path, n: usize) -> PathBuf {
+    let mut rotated = path.as_os_str().to_owned();
+    rotated.push(format!(".{n}"));
+    PathBuf::from(rotated)
+}
+
+fn open_append(path: &Path) -> io::Result<(BufWriter<File>, u64)> {
+    let file = OpenOptions::new().create(true).append(true).open(path)?;
+    let written = file.metadata()?.len();
+    Ok((BufWriter::new(file), written))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        fs, io,
+        path::{Path, PathBuf},
+        sync::Arc,
+    };
+
+    use chrono::Utc;
+    use solana_sdk::{
+        hash::Hash,
+        pubkey::Pubkey,
+        signature::{Keypair, Signer},
+        system_instruction,
+        transaction::{Transaction, VersionedTransaction},
+    };
+
+    use crate::audit_log::{
+        audit_log_files, read_audit_log, read_audit_rejections, verify_audit_chain, AuditLog,
+        BundleSubmission,
+    };
+
+    /// A fresh path under the temp dir, with whatever a previous run left there removed.
+    fn temp_log(name: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("audit_log_{}_{name}", std::process::id()));
+        remove_log(&path);
+        path
+    }
+
+    fn remove_log(path: &Path) {
+        for file in audit_log_files(path) {
+            let _ = fs::remove_file(file);
+        }
+    }
+
+    fn transfer(payer: &Keypair) -> VersionedTransaction {
+        let ix = system_instruction::transfer(&payer.pubkey(), &Pubkey::new_unique(), 1);
+        VersionedTransaction::from(Transaction::new_signed_with_payer(
+            &[ix],
+            Some(&payer.pubkey()),
+            &[payer],
+            Hash::new_unique(),
+        ))
+    }
+
+    fn submission(rejection: Option<&str>) -> BundleSubmission<'_> {
+        BundleSubmission {
+            submitted_at: Utc::now(),
+            submitted_slot: 10,
+            slots_to_leader: Some(2),
+            leader: None,
+            regions: &[],
+            rejection,
+        }
+    }
+
+    #[test]
+    fn test_record_and_read_back() {
+        let path = temp_log("read_back");
+        let payer = Keypair::new();
+        let bundle = vec![transfer(&payer), transfer(&payer)];
+        let rejected = vec![transfer(&payer)];
+
+        let mut log = AuditLog::open(&path, u64::MAX, 1).unwrap();
+        log.record_bundle(Some("uuid"), &bundle, &submission(None))
+            .unwrap();
+        log.record_bundle(None, &rejected, &submission(Some("too late")))
+            .unwrap();
+
+        let bundles = read_audit_log(&path).unwrap();
+        assert_eq!(bundles.len(), 1);
+        assert_eq!(bundles[0].bundle_uuid, "uuid");
+        assert_eq!(bundles[0].submitted_slot, Some(10));
+        assert_eq!(bundles[0].transactions, bundle);
+        let rejections = read_audit_rejections(&path).unwrap();
+        assert_eq!(rejections.len(), 1);
+        assert_eq!(rejections[0].reason.as_deref(), Some("too late"));
+        remove_log(&path);
+    }
+
+    #[test]
+    fn test_unsigned_transaction() {
+        let path = temp_log("unsigned");
+        let payer = Keypair::new();
+        let mut unsigned = transfer(&payer);
+        unsigned.signatures.clear();
+
+        let mut log = AuditLog::open(&path, u64::MAX, 1)
+            .unwrap()
+            .with_signer(payer.into())
+            .unwrap();
+        let error = log
+            .record_bundle(
+                Some("uuid"),
+                &[transfer(&Keypair::new()), unsigned],
+                &submission(None),
+            )
+            .unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+        // nothing of the bundle is written
+        assert!(read_audit_log(&path).unwrap().is_empty());
+        remove_log(&path);
+    }
+
+    #[test]
+    fn test_rotation() {
+        let path = temp_log("rotation");
+        let payer = Keypair::new();
+
+        let mut log = AuditLog::open(&path, 1, 2).unwrap();
+        for _ in 0..4 {
+            log.record_bundle(Some("uuid"), &[transfer(&payer)], &submission(None))
+                .unwrap();
+        }
+        // the active file and the two newest rotated ones are kept
+        let files = audit_log_files(&path);
+        assert_eq!(files.len(), 3);
+        assert!(read_audit_log(&files[2]).unwrap().is_empty());
+        assert_eq!(read_audit_log(&files[0]).unwrap().len(), 1);
+        remove_log(&path);
+    }
+
+    /// A signed log of `bundles` bundles of two transactions each, after one unsigned entry.
+    fn signed_log(name: &str, signer: &Keypair, bundles: usize) -> PathBuf {
+        let path = temp_log(name);
+        let payer = Keypair::new();
+        let mut log = AuditLog::open(&path, u64::MAX, 1).unwrap();
+        log.record_bundle(Some("unsigned"), &[transfer(&payer)], &submission(None))
+            .unwrap();
+        let mut log = log.with_signer(signer.insecure_clone().into()).unwrap();
+        for _ in 0..bundles {
+            log.record_bundle(
+                Some("uuid"),
+                &[transfer(&payer), transfer(&payer)],
+                &submission(None),
+            )
+            .unwrap();
+        }
+        path
+    }
+
+    fn edit_lines(path: &Path, edit: impl FnOnce(&mut Vec<String>)) {
+        let mut lines: Vec<String> = fs::read_to_string(path)
+            .unwrap()
+            .lines()
+            .map(str::to_string)
+            .collect();
+        edit(&mut lines);
+        fs::write(path, lines.join("\n") + "\n").unwrap();
+    }
+
+    #[test]
+    fn test_verify_audit_chain() {
+        let signer = Keypair::new();
+        let path = signed_log("chain", &signer, 2);
+
+        let report = verify_audit_chain(&audit_log_files(&path)).unwrap();
+        assert_eq!(report.unsigned, 1);
+        assert_eq!(report.verified, 4);
+        assert_eq!(report.signers, vec![signer.pubkey()]);
+        assert!(report.starts_at_genesis);
+        assert_eq!(report.broken, None);
+
+        // reopening continues the chain
+        let mut log = AuditLog::open(&path, u64::MAX, 1)
+            .unwrap()
+            .with_signer(signer.into())
+            .unwrap();
+        log.record_bundle(
+            Some("uuid"),
+            &[transfer(&Keypair::new())],
+            &submission(None),
+        )
+        .unwrap();
+        let next = verify_audit_chain(&audit_log_files(&path)).unwrap();
+        assert_eq!(next.verified, 5);
+        assert_eq!(next.broken, None);
+        assert_ne!(next.last_hash, report.last_hash);
+        remove_log(&path);
+    }
+
+    #[test]
+    fn test_verify_audit_chain_across_rotation() {
+        let signer = Arc::new(Keypair::new());
+        let path = temp_log("chain_rotation");
+        let mut log = AuditLog::open(&path, 1, 3)
+            .unwrap()
+            .with_signer(signer.clone())
+            .unwrap();
+        for _ in 0..3 {
+            log.record_bundle(Some("uuid"), &[transfer(&signer)], &submission(None))
+                .unwrap();
+        }
+
+        let files = audit_log_files(&path);
+        let report = verify_audit_chain(&files).unwrap();
+        assert_eq!((report.verified, report.broken), (3, None));
+        assert!(report.starts_at_genesis);
+        // without the oldest file the chain continues from entries rotated away
+        let report = verify_audit_chain(&files[1..]).unwrap();
+        assert_eq!((report.verified, report.broken), (2, None));
+        assert!(!report.starts_at_genesis);
+        remove_log(&path);
+    }
+
+    #[test]
+    fn test_verify_audit_chain_tampering() {
+        // what's done to the log's lines, and the line and reason the chain breaks at
+        type Tamper = fn(&mut Vec<String>);
+        let signer = Keypair::new();
+        let tamper: [(&str, Tamper, usize, &str); 8] = [
+            (
+                "edited",
+                |lines| {
+                    lines[2] =
+                        lines[2].replacen("\"submitted_slot\":10", "\"submitted_slot\":11", 1)
+                },
+                3,
+                "hash doesn't match the entry",
+            ),
+            (
+                "removed",
+                |lines| {
+                    lines.remove(2);
+                },
+                3,
+                "doesn't link to the entry before it",
+            ),
+            (
+                "reordered",
+                |lines| lines.swap(2, 3),
+                3,
+                "doesn't link to the entry before it",
+            ),
+            (
+                "unsigned",
+                |lines| {
+                    let start = lines[3].rfind(",\"chain_prev\":").unwrap();
+                    lines[3] = format!("{}}}", &lines[3][..start]);
+                },
+                4,
+                "entry isn't signed",
+            ),
+            (
+                "bad_signature",
+                |lines| {
+                    let start = lines[2].rfind("\"chain_signature\":").unwrap();
+                    lines[2] = format!(
+                        "{}\"chain_signature\":\"{}\"}}",
+                        &lines[2][..start],
+                        Keypair::new().sign_message(b"forged")
+                    );
+                },
+                3,
+                "signature doesn't verify",
+            ),
+            (
+                "resigned",
+                |lines| {
+                    let start = lines[2].rfind("\"chain_signer\":").unwrap();
+                    let signature_start = lines[2].rfind(",\"chain_signature\":").unwrap();
+                    lines[2] = format!(
+                        "{}\"chain_signer\":\"{}\"{}",
+                        &lines[2][..start],
+                        Pubkey::new_unique(),
+                        &lines[2][signature_start..]
+                    );
+                },
+                3,
+                "signature doesn't verify",
+            ),
+            (
+                "malformed_signature",
+                |lines| {
+                    let start = lines[2].rfind("\"chain_signature\":").unwrap();
+                    lines[2] = format!("{}\"chain_signature\":\"forged\"}}", &lines[2][..start]);
+                },
+                3,
+                "malformed chain_signature",
+            ),
+            (
+                "truncated",
+                |lines| {
+                    let end = lines[3].len() - 2;
+                    lines[3].truncate(end);
+                },
+                4,
+                "chain fields don't parse",
+            ),
+        ];
+        for (name, edit, line, reason) in tamper {
+            let path = signed_log(&format!("tamper_{name}"), &signer, 2);
+            edit_lines(&path, edit);
+            let report = verify_audit_chain(&audit_log_files(&path)).unwrap();
+            let broken = report
+                .broken
+                .unwrap_or_else(|| panic!("{name} not detected"));
+            assert_eq!(
+                (broken.line, broken.reason.as_str()),
+                (line, reason),
+                "{name}"
+            );
+            remove_log(&path);
+        }
+    }
+}
@@ -0,0 +1,140 @@
+use std::sync::Arc;
+
+use jito_protos::searcher::searcher_service_client::SearcherServiceClient;
+use log::{info, warn};
+use solana_metrics::datapoint_warn;
+use solana_sdk::{
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+};
+use tonic::{codegen::InterceptedService, transport::Channel, Code, Status};
+
+use crate::{
+    client_headers::ClientHeaders, get_searcher_client_with_headers,
+    token_authenticator::ClientInterceptor, BlockEngineConnectionError,
+    BlockEngineConnectionResult,
+};
+
+/// True if the status means the block engine throttled or rejected the auth keypair, as opposed
+/// to the request itself being bad.
+pub fn is_auth_keypair_failure(status: &Status) -> bool {
+    matches!(
+        status.code(),
+        Code::ResourceExhausted | Code::Unauthenticated | Code::PermissionDenied
+    )
+}
+
+/// A searcher client that authenticates with the first working keypair out of several approved
+/// ones, moving on to the next when the active keypair gets rate limited or rejected.
+pub struct FailoverSearcherClient {
+    block_engine_url: String,
+    auth_keypairs: Vec<Arc<Keypair>>,
+    headers: ClientHeaders,
+    active: usize,
+    client: SearcherServiceClient<InterceptedService<Channel, ClientInterceptor>>,
+}
+
+impl FailoverSearcherClient {
+    /// Connects with the first keypair the block engine accepts.
+    pub async fn connect(
+        block_engine_url: &str,
+        auth_keypairs: Vec<Arc<Keypair>>,
+        headers: ClientHeaders,
+    ) -> BlockEngineConnectionResult<Self> {
+        assert!(!auth_keypairs.is_empty(), "at least one auth keypair");
+        let (active, client) = connect_from(block_engine_url, &auth_keypairs, &headers, 0).await?;
+        Ok(Self {
+            block_engine_url: block_engine_url.to_string(),
+            auth_keypairs,
+            headers,
+            active,
+            client,
+        })
+    }
+
+    pub fn client(&self) -> &SearcherServiceClient<InterceptedService<Channel, ClientInterceptor>> {
+        &self.client
+    }
+
+    pub fn client_mut(
+        &mut self,
+    ) -> &mut SearcherServiceClient<InterceptedService<Channel, ClientInterceptor>> {
+        &mut self.client
+    }
+
+    pub fn into_client(
+        self,
+    ) -> SearcherServiceClient<InterceptedService<Channel, ClientInterceptor>> {
+        self.client
+    }
+
+    pub fn active_pubkey(&self) -> Pubkey {
+        self.auth_keypairs[self.active].pubkey()
+    }
+
+    /// Switches to the next keypair if `status` is an auth failure. Returns true if the client
+    /// was replaced, in which case the failed request can be retried.
+    pub async fn handle_error(&mut self, status: &Status) -> BlockEngineConnectionResult<bool> {
+        if !is_auth_keypair_failure(status) || self.auth_keypairs.len() == 1 {
+            return Ok(false);
+        }
+        let failed = self.active_pubkey();
+        warn!(
+            "auth keypair {failed} failed with {:?}: {}, failing over",
+            status.code(),
+            status.message()
+        );
+        datapoint_warn!(
+            "searcher_auth_failover",
+            ("failed_pubkey", failed.to_string(), String),
+            ("code", status.code() as i64, i64),
+        );
+
+        let next = (self.active + 1) % self.auth_keypairs.len();
+        let (active, client) = connect_from(
+            &self.block_engine_url,
+            &self.auth_keypairs,
+            &self.headers,
+            next,
+        )
+        .await?;
+        self.active = active;
+        self.client = client;
+        Ok(true)
+    }
+}
+
+/// Tries each keypair once, starting at `start` and wrapping around.
+async fn connect_from(
+    block_engine_url: &str,
+    auth_keypairs: &[Arc<Keypair>],
+    headers: &ClientHeaders,
+    start: usize,
+) -> BlockEngineConnectionResult<(
+    usize,
+    SearcherServiceClient<InterceptedService<Channel, ClientInterceptor>>,
+)> {
+    let mut last_error = None;
+    for offset in 0..auth_keypairs.len() {
+        let index = (start + offset) % auth_keypairs.len();
+        let keypair = &auth_keypairs[index];
+        match get_searcher_client_with_headers(block_engine_url, keypair, headers.clone()).await {
+            Ok(client) => {
+                info!("authenticated with keypair {}", keypair.pubkey());
+                return Ok((index, client));
+            }
+            Err(BlockEngineConnectionError::ClientError(status))
+                if is_auth_keypair_failure(&status) =>
+            {
+                warn!(
+                    "block engine rejected auth keypair {}: {}",
+                    keypair.pubkey(),
+                    status.message()
+                );
+                last_error = Some(BlockEngineConnectionError::ClientError(status));
+            }
+            Err(e) => return Err(e),
+        }
+    }
+    Err(last_error.expect("at least one auth keypair"))
+}
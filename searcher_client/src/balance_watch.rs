@@ -0,0 +1,327 @@
+use std::{
+    collections::HashSet,
+    fmt::{self, Display, Formatter},
+    fs, io,
+    path::Path,
+    str::FromStr,
+    time::{Duration, Instant},
+};
+
+use serde::Deserialize;
+use solana_metrics::datapoint_info;
+use solana_sdk::{account::Account, pubkey::Pubkey};
+use spl_token_2022::{extension::StateWithExtensions, state::Account as TokenAccount};
+use thiserror::Error;
+
+use crate::config_validation::MIN_TIP_LAMPORTS;
+
+#[derive(Debug, Error)]
+pub enum BalanceWatchError {
+    #[error("failed to read balance watch config: {0}")]
+    Io(#[from] io::Error),
+    #[error("invalid balance watch config: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("invalid balance watch config: {0}")]
+    Invalid(String),
+}
+
+/// An account to watch and the balance it must stay above: lamports for wallets such as the
+/// payer or tip reserve, base units of the token for token accounts.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct WatchedAccount {
+    /// Used in alerts and metrics.
+    pub name: String,
+    pub address: String,
+    #[serde(default)]
+    pub min_lamports: Option<u64>,
+    #[serde(default)]
+    pub min_token_amount: Option<u64>,
+}
+
+/// Refilling a watched wallet from a treasury when it runs low.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TopUpConfig {
+    /// Name of the watched account to refill, which must have `min_lamports`.
+    pub account: String,
+    /// Balance a top-up refills the account to.
+    pub target_lamports: u64,
+    /// Most lamports sent in one top-up.
+    pub max_lamports: u64,
+    /// Least time between top-ups, so one that hasn't landed yet isn't repeated.
+    #[serde(default = "default_cooldown_secs")]
+    pub cooldown_secs: u64,
+    /// Tip of the top-up bundle.
+    #[serde(default = "default_tip_lamports")]
+    pub tip_lamports: u64,
+}
+
+fn default_cooldown_secs() -> u64 {
+    300
+}
+
+fn default_tip_lamports() -> u64 {
+    MIN_TIP_LAMPORTS
+}
+
+/// Accounts to watch, read from a JSON file such as:
+///
+/// ```json
+/// {
+///   "accounts": [
+///     {"name": "payer", "address": "7Y8pfYB9sK8Lq6eZ4dtN2iYwBTtzTnYWrwhzWkzNfWzm", "min_lamports": 500000000},
+///     {"name": "usdc inventory", "address": "3wyAj7Rt1TWVPZVteFJPLa26JmLvdb1CAKEFZm3NY75E", "min_token_amount": 100000000}
+///   ],
+///   "top_up": {"account": "payer", "target_lamports": 2000000000, "max_lamports": 1000000000}
+/// }
+/// ```
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct BalanceWatchConfig {
+    pub accounts: Vec<WatchedAccount>,
+    #[serde(default)]
+    pub top_up: Option<TopUpConfig>,
+}
+
+impl BalanceWatchConfig {
+    pub fn load(path: &Path) -> Result<Self, BalanceWatchError> {
+        let config: Self = serde_json::from_str(&fs::read_to_string(path)?)?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    pub fn validate(&self) -> Result<(), BalanceWatchError> {
+        let invalid = |e: String| Err(BalanceWatchError::Invalid(e));
+        if self.accounts.is_empty() {
+            return invalid("no accounts to watch".to_string());
+        }
+        let mut names = HashSet::new();
+        for account in &self.accounts {
+            if !names.insert(account.name.as_str()) {
+                return invalid(format!("account name {:?} is used twice", account.name));
+            }
+            if let Err(e) = Pubkey::from_str(&account.address) {
+                return invalid(format!("{}: invalid address: {e}", account.name));
+            }
+            if account.min_lamports.is_some() == account.min_token_amount.is_some() {
+                return invalid(format!(
+                    "{}: set exactly one of min_lamports and min_token_amount",
+                    account.name
+                ));
+            }
+        }
+        let Some(top_up) = &self.top_up else {
+            return Ok(());
+        };
+        let Some(min_lamports) = self
+            .accounts
+            .iter()
+            .find(|account| account.name == top_up.account)
+            .and_then(|account| account.min_lamports)
+        else {
+            return invalid(format!(
+                "top_up account {:?} isn't a watched account with min_lamports",
+                top_up.account
+            ));
+        };
+        if top_up.target_lamports <= min_lamports {
+            return invalid(format!(
+                "top_up target_lamports {} must be above the account's min_lamports {min_lamports}",
+                top_up.target_lamports
+            ));
+        }
+        if top_up.max_lamports == 0 {
+            return invalid("top_up max_lamports must be above 0".to_string());
+        }
+        if top_up.tip_lamports < MIN_TIP_LAMPORTS {
+            return invalid(format!(
+                "top_up tip_lamports {} is below the minimum tip of {MIN_TIP_LAMPORTS}",
+                top_up.tip_lamports
+            ));
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AlertKind {
+    /// Dropped below its threshold.
+    Low,
+    /// Back at or above its threshold.
+    Recovered,
+    /// A watched token account that doesn't exist or isn't a token account.
+    Missing,
+}
+
+/// A watched account that crossed its threshold.
+#[derive(Clone, Debug)]
+pub struct BalanceAlert {
+    pub kind: AlertKind,
+    pub name: String,
+    pub address: Pubkey,
+    pub balance: u64,
+    pub threshold: u64,
+    /// Whether the amounts are lamports rather than token base units.
+    pub lamports: bool,
+}
+
+impl Display for BalanceAlert {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let unit = if self.lamports {
+            "lamports"
+        } else {
+            "token units"
+        };
+        match self.kind {
+            AlertKind::Low => write!(
+                f,
+                "{} ({}) is low: {} {unit}, below {}",
+                self.name, self.address, self.balance, self.threshold
+            ),
+            AlertKind::Recovered => write!(
+                f,
+                "{} ({}) recovered: {} {unit}",
+                self.name, self.address, self.balance
+            ),
+            AlertKind::Missing => {
+                write!(f, "{} ({}) isn't a token account", self.name, self.address)
+            }
+        }
+    }
+}
+
+/// Watches account balances against their thresholds. Alerts are raised when an account
+/// crosses its threshold, not on every check while it stays low, and every balance is reported
+/// as a `balance_watch` metric.
+pub struct BalanceWatcher {
+    config: BalanceWatchConfig,
+    addresses: Vec<Pubkey>,
+    balances: Vec<Option<u64>>,
+    /// Accounts below their threshold or missing, by position.
+    alerting: HashSet<usize>,
+    last_top_up: Option<Instant>,
+}
+
+impl BalanceWatcher {
+    pub fn new(config: BalanceWatchConfig) -> Result<Self, BalanceWatchError> {
+        config.validate()?;
+        let addresses = config
+            .accounts
+            .iter()
+            .map(|account| Pubkey::from_str(&account.address).expect("validated"))
+            .collect();
+        Ok(Self {
+            balances: vec![None; config.accounts.len()],
+            config,
+            addresses,
+            alerting: HashSet::new(),
+            last_top_up: None,
+        })
+    }
+
+    /// The watched accounts' addresses, in the order `observe` takes them.
+    pub fn addresses(&self) -> &[Pubkey] {
+        &self.addresses
+    }
+
+    /// Checks freshly fetched accounts, given in the order of `addresses`, against their
+    /// thresholds. A wallet that doesn't exist holds 0 lamports.
+    pub fn observe(&mut self, accounts: &[Option<Account>]) -> Vec<BalanceAlert> {
+        let mut alerts = vec![];
+        for (position, account) in accounts.iter().enumerate().take(self.addresses.len()) {
+            let watched = &self.config.accounts[position];
+            let (balance, threshold) = match (watched.min_lamports, watched.min_token_amount) {
+                (Some(min_lamports), _) => (
+                    Some(account.as_ref().map_or(0, |account| account.lamports)),
+                    min_lamports,
+                ),
+                (None, Some(min_amount)) => (
+                    account.as_ref().and_then(|account| {
+                        let state = StateWithExtensions::<TokenAccount>::unpack(&account.data);
+                        state.ok().map(|state| state.base.amount)
+                    }),
+                    min_amount,
+                ),
+                (None, None) => unreachable!("validated"),
+            };
+            self.balances[position] = balance;
+            datapoint_info!(
+                "balance_watch",
+                "account" => watched.name.clone(),
+                ("balance", balance.unwrap_or_default(), i64),
+                ("threshold", threshold, i64),
+                ("low", balance.is_none_or(|balance| balance < threshold), bool),
+            );
+
+            let kind = match balance {
+                None => AlertKind::Missing,
+                Some(balance) if balance < threshold => AlertKind::Low,
+                Some(_) => AlertKind::Recovered,
+            };
+            let was_alerting = self.alerting.contains(&position);
+            let alert = match kind {
+                AlertKind::Recovered if was_alerting => self.alerting.remove(&position),
+                AlertKind::Low | AlertKind::Missing => self.alerting.insert(position),
+                AlertKind::Recovered => false,
+            };
+            if alert {
+                alerts.push(BalanceAlert {
+                    kind,
+                    name: watched.name.clone(),
+                    address: self.addresses[position],
+                    balance: balance.unwrap_or_default(),
+                    threshold,
+                    lamports: watched.min_lamports.is_some(),
+                });
+            }
+        }
+        alerts
+    }
+
+    pub fn top_up_config(&self) -> Option<&TopUpConfig> {
+        self.config.top_up.as_ref()
+    }
+
+    /// Position of the account top-ups refill.
+    fn top_up_position(&self) -> Option<usize> {
+        let top_up = self.config.top_up.as_ref()?;
+        self.config
+            .accounts
+            .iter()
+            .position(|account| account.name == top_up.account)
+    }
+
+    /// The address of the account top-ups refill.
+    pub fn top_up_address(&self) -> Option<Pubkey> {
+        Some(self.addresses[self.top_up_position()?])
+    }
+
+    /// The account to top up and how many lamports to send it, if it's below its threshold and
+    /// the cooldown since the last top-up has passed.
+    pub fn top_up_due(&self, now: Instant) -> Option<(Pubkey, u64)> {
+        let top_up = self.config.top_up.as_ref()?;
+        let cooling_down = self.last_top_up.is_some_and(|last| {
+            now.saturating_duration_since(last) < Duration::from_secs(top_up.cooldown_secs)
+        });
+        if cooling_down {
+            return None;
+        }
+        let position = self.top_up_position()?;
+        let balance = self.balances[position]?;
+        let min_lamports = self.config.accounts[position].min_lamports?;
+        if balance >= min_lamports {
+            return None;
+        }
+        let lamports = top_up
+            .target_lamports
+            .saturating_sub(balance)
+            .min(top_up.max_lamports);
+        Some((self.addresses[position], lamports))
+    }
+
+    /// Starts the cooldown after a top-up was sent.
+    pub fn record_top_up(&mut self, now: Instant) {
+        self.last_top_up = Some(now);
+    }
+}
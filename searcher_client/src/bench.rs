@@ -0,0 +1,207 @@
+use std::{
+    hint::black_box,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use histogram::Histogram;
+use jito_protos::{
+    bundle::Bundle,
+    convert::{proto_packet_from_versioned_tx, try_versioned_tx_from_packet},
+    packet::Packet as ProtoPacket,
+    searcher::SendBundleRequest,
+};
+use prost::Message;
+use solana_sdk::{
+    hash::Hash,
+    pubkey::Pubkey,
+    signature::{keypair_from_seed, Keypair, Signer},
+    system_instruction::transfer,
+    transaction::{Transaction, VersionedTransaction},
+};
+
+use crate::{
+    bundle_builder::{BundleBuilder, TransactionSpec},
+    interest_filter::InterestFilter,
+    tip_payment::{TipConfig, TipPlacement, TipSplit},
+};
+
+/// Fixed inputs for the hot path benchmarks: a batch of mempool packets, a quarter of them
+/// writing to watched accounts, and the keys to build a backrun bundle with. Keys are derived
+/// from fixed seeds so every run measures the same work.
+pub struct HotPathFixture {
+    pub packets: Vec<ProtoPacket>,
+    /// The first packet, decoded, to backrun.
+    trigger: VersionedTransaction,
+    watched: Vec<Pubkey>,
+    payer: Arc<Keypair>,
+    tip_account: Pubkey,
+    blockhash: Hash,
+}
+
+impl HotPathFixture {
+    pub fn new(batch_size: usize) -> Self {
+        let payer = Arc::new(keypair(0));
+        let watched: Vec<Pubkey> = (0..64).map(|i| keypair(i + 1).pubkey()).collect();
+        let blockhash = Hash::new_from_array([7; 32]);
+        let packets: Vec<ProtoPacket> = (0..batch_size.max(1))
+            .map(|i| {
+                let sender = keypair(100 + (i % 100) as u8);
+                let recipient = if i % 4 == 0 {
+                    watched[i % watched.len()]
+                } else {
+                    Pubkey::new_from_array([(i % 251) as u8 + 1; 32])
+                };
+                let tx = Transaction::new_signed_with_payer(
+                    &[transfer(&sender.pubkey(), &recipient, 1_000 + i as u64)],
+                    Some(&sender.pubkey()),
+                    &[&sender],
+                    blockhash,
+                );
+                let data = bincode::serialize(&VersionedTransaction::from(tx)).expect("serializes");
+                proto_packet_from_versioned_tx(&data)
+            })
+            .collect();
+        Self {
+            trigger: try_versioned_tx_from_packet(&packets[0]).expect("decodes"),
+            packets,
+            watched,
+            payer,
+            tip_account: keypair(250).pubkey(),
+            blockhash,
+        }
+    }
+
+    pub fn interest_filter(&self) -> InterestFilter {
+        InterestFilter::new(self.watched.iter().copied(), 0.01)
+    }
+
+    /// Checks every packet against `filter`, returning how many passed.
+    pub fn filter_packets(&self, filter: &mut InterestFilter) -> usize {
+        self.packets
+            .iter()
+            .filter(|packet| filter.matches(packet))
+            .count()
+    }
+
+    /// Decodes every packet, returning how many decoded.
+    pub fn decode_packets(&self) -> usize {
+        self.packets
+            .iter()
+            .filter(|packet| try_versioned_tx_from_packet(packet).is_ok())
+            .count()
+    }
+
+    /// Builds and signs a backrun bundle for the first packet: its transaction followed by a
+    /// transfer that tips in the same transaction.
+    pub fn build_bundle(&self) -> Vec<VersionedTransaction> {
+        let backrun = BundleBuilder::new()
+            .add_transaction(TransactionSpec::new(
+                vec![transfer(&self.payer.pubkey(), &self.payer.pubkey(), 1)],
+                self.payer.clone(),
+            ))
+            .with_tip(TipConfig {
+                tip_account: self.tip_account,
+                lamports: 10_000,
+                placement: TipPlacement::LastTransaction,
+                split: TipSplit::default(),
+            })
+            .build(&self.blockhash)
+            .expect("valid bundle");
+        let mut bundle = vec![self.trigger.clone()];
+        bundle.extend(backrun);
+        bundle
+    }
+
+    /// Serializes `bundle` the way it's sent: each transaction to bytes, wrapped in a
+    /// `SendBundleRequest` and encoded.
+    pub fn serialize_bundle(bundle: &[VersionedTransaction]) -> Vec<u8> {
+        let packets = bundle
+            .iter()
+            .map(|tx| proto_packet_from_versioned_tx(&bincode::serialize(tx).expect("serializes")))
+            .collect();
+        SendBundleRequest {
+            bundle: Some(Bundle {
+                header: None,
+                packets,
+            }),
+        }
+        .encode_to_vec()
+    }
+}
+
+fn keypair(seed: u8) -> Keypair {
+    keypair_from_seed(&[seed; 32]).expect("32 byte seed")
+}
+
+/// Timings of one benchmark, per iteration.
+#[derive(Clone, Debug)]
+pub struct BenchResult {
+    pub name: &'static str,
+    pub iterations: u64,
+    /// Packets or bundles handled per iteration.
+    pub items_per_iteration: u64,
+    pub mean: Duration,
+    pub p50: Duration,
+    pub p99: Duration,
+}
+
+impl BenchResult {
+    pub fn items_per_sec(&self) -> f64 {
+        self.items_per_iteration as f64 / self.mean.as_secs_f64().max(f64::EPSILON)
+    }
+}
+
+/// Runs `iteration` repeatedly for about `duration`, after a short warm up.
+fn time(
+    name: &'static str,
+    items_per_iteration: u64,
+    duration: Duration,
+    mut iteration: impl FnMut(),
+) -> BenchResult {
+    let warm_up = Instant::now();
+    while warm_up.elapsed() < duration / 10 {
+        iteration();
+    }
+    let mut latency_ns = Histogram::new();
+    let mut iterations = 0;
+    let start = Instant::now();
+    while start.elapsed() < duration {
+        let iteration_start = Instant::now();
+        iteration();
+        let _ = latency_ns.increment(iteration_start.elapsed().as_nanos() as u64);
+        iterations += 1;
+    }
+    let percentile = |p| Duration::from_nanos(latency_ns.percentile(p).unwrap_or_default());
+    BenchResult {
+        name,
+        iterations,
+        items_per_iteration,
+        mean: start.elapsed() / iterations.max(1) as u32,
+        p50: percentile(50.0),
+        p99: percentile(99.0),
+    }
+}
+
+/// A quick, dependency free run of the same workloads as the criterion benchmarks in
+/// `benches/hot_path.rs`, giving each `duration`. Criterion gives steadier numbers and
+/// compares against the last run; this is for checking a production host.
+pub fn run_hot_path(batch_size: usize, duration: Duration) -> Vec<BenchResult> {
+    let fixture = HotPathFixture::new(batch_size);
+    let bundle = fixture.build_bundle();
+    let mut filter = fixture.interest_filter();
+    vec![
+        time("build_sign_bundle", 1, duration, || {
+            black_box(fixture.build_bundle());
+        }),
+        time("serialize_bundle", 1, duration, || {
+            black_box(HotPathFixture::serialize_bundle(&bundle));
+        }),
+        time("filter_packets", batch_size as u64, duration, || {
+            black_box(fixture.filter_packets(&mut filter));
+        }),
+        time("decode_packets", batch_size as u64, duration, || {
+            black_box(fixture.decode_packets());
+        }),
+    ]
+}
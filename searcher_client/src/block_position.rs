@@ -0,0 +1,95 @@
+use crate::bundle_store::BundleRecord;
+
+/// Upper bounds of the block index ranges bundles are counted in, the last range unbounded.
+const BUCKET_BOUNDS: [usize; 4] = [1, 5, 20, 100];
+
+/// Bundles whose first transaction's block index is in `start..end`, `end` None for no bound.
+#[derive(Clone, Copy, Debug)]
+pub struct PositionBucket {
+    pub start: usize,
+    pub end: Option<usize>,
+    pub bundles: usize,
+}
+
+/// Where landed bundles executed in their blocks, counted by their first transaction's index
+/// among the block's non-vote transactions, and how their profit varied with it.
+#[derive(Clone, Debug)]
+pub struct PositionStats {
+    /// Landed bundles with a known position.
+    pub bundles: usize,
+    pub top_of_block: usize,
+    pub p50_index: usize,
+    pub p90_index: usize,
+    pub max_index: usize,
+    /// Mean position as a share of the block, 0 at the top and 1 at the bottom.
+    pub mean_depth: f64,
+    pub buckets: Vec<PositionBucket>,
+    /// Mean fee payer balance change of bundles at the top of their blocks, and of the rest.
+    pub top_of_block_mean_net_lamports: Option<f64>,
+    pub rest_mean_net_lamports: Option<f64>,
+}
+
+impl PositionStats {
+    /// Stats over the records with a known position, None if there are none.
+    pub fn build<'a>(records: impl IntoIterator<Item = &'a BundleRecord>) -> Option<Self> {
+        let mut indexes = vec![];
+        let mut depth = 0.0;
+        let (mut top_net, mut rest_net) = (vec![], vec![]);
+        for record in records {
+            let Some(index) = record.block_index() else {
+                continue;
+            };
+            indexes.push(index);
+            if let Some(transactions) = record.block_transactions.filter(|count| *count > 1) {
+                depth += index as f64 / (transactions - 1) as f64;
+            }
+            if let Some(net) = record.net_lamports() {
+                match index {
+                    0 => top_net.push(net),
+                    _ => rest_net.push(net),
+                }
+            }
+        }
+        if indexes.is_empty() {
+            return None;
+        }
+        indexes.sort_unstable();
+        let percentile = |p: usize| indexes[(indexes.len() - 1) * p / 100];
+
+        let mut start = 0;
+        let mut buckets = vec![];
+        for end in BUCKET_BOUNDS.map(Some).into_iter().chain([None]) {
+            let bundles = indexes
+                .iter()
+                .filter(|index| **index >= start && end.is_none_or(|end| **index < end))
+                .count();
+            buckets.push(PositionBucket {
+                start,
+                end,
+                bundles,
+            });
+            start = end.unwrap_or_default();
+        }
+
+        Some(Self {
+            bundles: indexes.len(),
+            top_of_block: indexes.iter().filter(|index| **index == 0).count(),
+            p50_index: percentile(50),
+            p90_index: percentile(90),
+            max_index: indexes[indexes.len() - 1],
+            mean_depth: depth / indexes.len() as f64,
+            buckets,
+            top_of_block_mean_net_lamports: mean(&top_net),
+            rest_mean_net_lamports: mean(&rest_net),
+        })
+    }
+
+    /// The share of bundles that executed at the top of their blocks.
+    pub fn top_of_block_rate(&self) -> f64 {
+        self.top_of_block as f64 / self.bundles as f64
+    }
+}
+
+fn mean(values: &[i64]) -> Option<f64> {
+    (!values.is_empty()).then(|| values.iter().sum::<i64>() as f64 / values.len() as f64)
+}
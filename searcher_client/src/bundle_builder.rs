@@ -0,0 +1,355 @@
+use std::sync::Arc;
+
+use solana_sdk::{
+    hash::Hash,
+    instruction::Instruction,
+    message::Message,
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+    transaction::{Transaction, VersionedTransaction},
+};
+use thiserror::Error;
+
+use crate::{
+    assertions::SlotAssertion,
+    bundle_optimizer::{optimize_transactions, OptimizationStats, OptimizerLimits},
+    ephemeral_account::EphemeralAccount,
+    post_conditions::PostCondition,
+    tip_payment::{TipConfig, TipPlacement, TipSplitError},
+};
+
+/// Maximum number of transactions the block engine accepts in a bundle.
+pub const MAX_BUNDLE_TRANSACTIONS: usize = 5;
+
+#[derive(Debug, Error)]
+pub enum BundleBuildError {
+    #[error("bundle has no transactions")]
+    Empty,
+    #[error("bundle has {0} transactions, max is {MAX_BUNDLE_TRANSACTIONS}")]
+    TooManyTransactions(usize),
+    #[error("transaction {tx_index} is missing signer {pubkey}")]
+    MissingSigner { tx_index: usize, pubkey: Pubkey },
+    #[error(
+        "transaction {tx_index} was given signer {pubkey} which none of its instructions require"
+    )]
+    UnexpectedSigner { tx_index: usize, pubkey: Pubkey },
+    #[error(transparent)]
+    Tip(#[from] TipSplitError),
+}
+
+/// One transaction in a bundle, with its own fee payer and signers.
+#[derive(Clone)]
+pub struct TransactionSpec {
+    pub instructions: Vec<Instruction>,
+    /// Pays the fees, unless `fee_payer` is set. Then it's only the strategy wallet the
+    /// instructions act for, and signs if they require it.
+    pub payer: Arc<Keypair>,
+    /// Signers other than the payer.
+    pub signers: Vec<Arc<Keypair>>,
+    /// A funded wallet paying the fees in place of `payer`, so one fee payer can serve many
+    /// strategy wallets.
+    pub fee_payer: Option<Arc<Keypair>>,
+}
+
+impl TransactionSpec {
+    pub fn new(instructions: Vec<Instruction>, payer: Arc<Keypair>) -> Self {
+        Self {
+            instructions,
+            payer,
+            signers: vec![],
+            fee_payer: None,
+        }
+    }
+
+    pub fn with_signer(mut self, signer: Arc<Keypair>) -> Self {
+        self.signers.push(signer);
+        self
+    }
+
+    /// Has `fee_payer` pay the fees in place of the payer.
+    pub fn with_fee_payer(mut self, fee_payer: Arc<Keypair>) -> Self {
+        self.fee_payer = Some(fee_payer);
+        self
+    }
+
+    /// The wallet paying the transaction's fees, and any tip placed in it.
+    pub fn fee_payer(&self) -> &Arc<Keypair> {
+        self.fee_payer.as_ref().unwrap_or(&self.payer)
+    }
+
+    fn message(&self) -> Message {
+        Message::new(&self.instructions, Some(&self.fee_payer().pubkey()))
+    }
+
+    /// The fee payer followed by the payer and any other signers, without duplicates.
+    fn keypairs(&self) -> Vec<&Keypair> {
+        let mut keypairs: Vec<&Keypair> = vec![self.fee_payer()];
+        for signer in [&self.payer].into_iter().chain(&self.signers) {
+            if !keypairs.iter().any(|k| k.pubkey() == signer.pubkey()) {
+                keypairs.push(signer);
+            }
+        }
+        keypairs
+    }
+
+    /// The keypairs of the signatures `message` requires, in its order, None for any missing.
+    /// A payer the instructions don't need a signature from is left out.
+    fn signing_keypairs(&self, message: &Message) -> Vec<(Pubkey, Option<&Keypair>)> {
+        let keypairs = self.keypairs();
+        message.account_keys[..message.header.num_required_signatures as usize]
+            .iter()
+            .map(|pubkey| {
+                let keypair = keypairs.iter().find(|k| k.pubkey() == *pubkey).copied();
+                (*pubkey, keypair)
+            })
+            .collect()
+    }
+}
+
+/// Builds and signs a bundle where every transaction can be paid for and signed by different
+/// wallets. Each transaction is signed by the keypairs its message requires, in the order it
+/// lists them.
+#[derive(Clone, Default)]
+pub struct BundleBuilder {
+    transactions: Vec<TransactionSpec>,
+    fee_payer: Option<Arc<Keypair>>,
+    tip: Option<TipConfig>,
+    slot_assertion: Option<SlotAssertion>,
+    post_assertions: Vec<Instruction>,
+    post_conditions: Vec<PostCondition>,
+    ephemeral_accounts: Vec<EphemeralAccount>,
+}
+
+impl BundleBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add_transaction(mut self, mut transaction: TransactionSpec) -> Self {
+        if transaction.fee_payer.is_none() {
+            transaction.fee_payer = self.fee_payer.clone();
+        }
+        self.transactions.push(transaction);
+        self
+    }
+
+    /// Has `fee_payer` pay the fees of every transaction without a fee payer of its own, added
+    /// before or after, and tips placed in the last transaction.
+    pub fn with_fee_payer(mut self, fee_payer: Arc<Keypair>) -> Self {
+        for transaction in &mut self.transactions {
+            if transaction.fee_payer.is_none() {
+                transaction.fee_payer = Some(fee_payer.clone());
+            }
+        }
+        self.fee_payer = Some(fee_payer);
+        self
+    }
+
+    /// Adds a tip to the bundle, placed as configured when the bundle is built.
+    pub fn with_tip(mut self, tip: TipConfig) -> Self {
+        self.tip = Some(tip);
+        self
+    }
+
+    /// Prepends a slot assertion to every transaction, so each reverts on its own if included
+    /// outside the expected slots.
+    pub fn with_slot_assertion(mut self, assertion: SlotAssertion) -> Self {
+        self.slot_assertion = Some(assertion);
+        self
+    }
+
+    /// Appends an assertion, such as a `TokenBalanceAssertion`, to the last transaction so it
+    /// checks the state left by the whole bundle. If it fails the bundle reverts.
+    pub fn with_post_assertion(mut self, assertion: Instruction) -> Self {
+        self.post_assertions.push(assertion);
+        self
+    }
+
+    /// Declares something the bundle is expected to leave behind, checked once it lands with
+    /// [`verify_post_conditions`] instead of on-chain like a post assertion.
+    ///
+    /// [`verify_post_conditions`]: crate::post_conditions::verify_post_conditions
+    pub fn with_post_condition(mut self, condition: PostCondition) -> Self {
+        self.post_conditions.push(condition);
+        self
+    }
+
+    /// Opens `account` at the start of the first transaction, paid for by its fee payer, and
+    /// closes it back to them at the end of the last, before any post assertions and tip. The
+    /// account's base is added as a signer of the transactions that need it.
+    pub fn with_ephemeral_account(mut self, account: EphemeralAccount) -> Self {
+        self.ephemeral_accounts.push(account);
+        self
+    }
+
+    pub fn post_conditions(&self) -> &[PostCondition] {
+        &self.post_conditions
+    }
+
+    pub fn transactions(&self) -> &[TransactionSpec] {
+        &self.transactions
+    }
+
+    /// Runs [`optimize_transactions`] over the bundle's transactions, leaving room in each for
+    /// the slot assertion, ephemeral accounts, post assertions and tip added when it's built.
+    pub fn optimize(mut self, limits: &OptimizerLimits) -> (Self, OptimizationStats) {
+        let first_payer = self
+            .transactions
+            .first()
+            .map(|spec| spec.fee_payer().pubkey())
+            .unwrap_or_default();
+        let mut reserved: Vec<Instruction> = self
+            .slot_assertion
+            .iter()
+            .map(SlotAssertion::instruction)
+            .chain(self.ephemeral_accounts.iter().flat_map(|account| {
+                let mut instructions = account.open(&first_payer);
+                instructions.push(account.close(&first_payer));
+                instructions
+            }))
+            .chain(self.post_assertions.iter().cloned())
+            .collect();
+        let last_payer = self
+            .transactions
+            .last()
+            .map(|spec| spec.fee_payer().pubkey());
+        if let (Some(tip), Some(tipper)) = (&self.tip, last_payer) {
+            if !matches!(tip.placement, TipPlacement::SeparateTransaction(_)) {
+                reserved.extend(tip.instructions(&tipper));
+            }
+        }
+        let (transactions, stats) =
+            optimize_transactions(std::mem::take(&mut self.transactions), limits, &reserved);
+        self.transactions = transactions;
+        (self, stats)
+    }
+
+    /// The transactions with the ephemeral accounts, assertions and tip applied.
+    fn prepared_transactions(&self) -> Vec<TransactionSpec> {
+        let mut transactions = self.transactions.clone();
+        if !self.ephemeral_accounts.is_empty() {
+            self.add_ephemeral_accounts(&mut transactions);
+        }
+        if let Some(assertion) = &self.slot_assertion {
+            for spec in &mut transactions {
+                spec.instructions.insert(0, assertion.instruction());
+            }
+        }
+        if let Some(last) = transactions.last_mut() {
+            last.instructions
+                .extend(self.post_assertions.iter().cloned());
+        }
+        let Some(tip) = &self.tip else {
+            return transactions;
+        };
+        match &tip.placement {
+            TipPlacement::SeparateTransaction(payer) => {
+                let mut instructions = tip.instructions(&payer.pubkey());
+                if let Some(assertion) = &self.slot_assertion {
+                    instructions.insert(0, assertion.instruction());
+                }
+                transactions.push(TransactionSpec::new(instructions, payer.clone()));
+            }
+            TipPlacement::LastTransaction | TipPlacement::Cpi { .. } => {
+                if let Some(last) = transactions.last_mut() {
+                    let tipper = last.fee_payer().pubkey();
+                    last.instructions.extend(tip.instructions(&tipper));
+                }
+            }
+        }
+        transactions
+    }
+
+    fn add_ephemeral_accounts(&self, transactions: &mut [TransactionSpec]) {
+        let Some(first_payer) = transactions.first().map(|spec| spec.fee_payer().pubkey()) else {
+            return;
+        };
+        let opens: Vec<Instruction> = self
+            .ephemeral_accounts
+            .iter()
+            .flat_map(|account| account.open(&first_payer))
+            .collect();
+        transactions[0].instructions.splice(0..0, opens);
+        if let Some(last) = transactions.last_mut() {
+            last.instructions.extend(
+                self.ephemeral_accounts
+                    .iter()
+                    .map(|account| account.close(&first_payer)),
+            );
+        }
+        for spec in transactions {
+            for account in &self.ephemeral_accounts {
+                let base = account.base();
+                let required = spec.instructions.iter().any(|instruction| {
+                    instruction
+                        .accounts
+                        .iter()
+                        .any(|meta| meta.is_signer && meta.pubkey == base.pubkey())
+                });
+                if required && !spec.keypairs().iter().any(|k| k.pubkey() == base.pubkey()) {
+                    spec.signers.push(base.clone());
+                }
+            }
+        }
+    }
+
+    /// Checks the bundle size, the tip, and that each transaction was given exactly the signers
+    /// its instructions require. A payer that isn't the fee payer may go unused.
+    pub fn validate(&self) -> Result<(), BundleBuildError> {
+        if self.transactions.is_empty() {
+            return Err(BundleBuildError::Empty);
+        }
+        if let Some(tip) = &self.tip {
+            tip.validate()?;
+        }
+        let transactions = self.prepared_transactions();
+        if transactions.len() > MAX_BUNDLE_TRANSACTIONS {
+            return Err(BundleBuildError::TooManyTransactions(transactions.len()));
+        }
+
+        for (tx_index, spec) in transactions.iter().enumerate() {
+            let message = spec.message();
+            let signing_keypairs = spec.signing_keypairs(&message);
+
+            if let Some((pubkey, _)) = signing_keypairs
+                .iter()
+                .find(|(_, keypair)| keypair.is_none())
+            {
+                return Err(BundleBuildError::MissingSigner {
+                    tx_index,
+                    pubkey: *pubkey,
+                });
+            }
+            if let Some(keypair) = spec.signers.iter().find(|k| {
+                !signing_keypairs
+                    .iter()
+                    .any(|(pubkey, _)| *pubkey == k.pubkey())
+            }) {
+                return Err(BundleBuildError::UnexpectedSigner {
+                    tx_index,
+                    pubkey: keypair.pubkey(),
+                });
+            }
+        }
+        Ok(())
+    }
+
+    pub fn build(&self, blockhash: &Hash) -> Result<Vec<VersionedTransaction>, BundleBuildError> {
+        self.validate()?;
+        Ok(self
+            .prepared_transactions()
+            .iter()
+            .map(|spec| {
+                let message = spec.message();
+                let keypairs: Vec<&Keypair> = spec
+                    .signing_keypairs(&message)
+                    .into_iter()
+                    .filter_map(|(_, keypair)| keypair)
+                    .collect();
+                let mut tx = Transaction::new_unsigned(message);
+                tx.sign(&keypairs, *blockhash);
+                VersionedTransaction::from(tx)
+            })
+            .collect())
+    }
+}
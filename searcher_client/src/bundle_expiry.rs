@@ -0,0 +1,134 @@
+use log::info;
+use solana_client::{client_error::Result as ClientResult, nonblocking::rpc_client::RpcClient};
+use solana_metrics::datapoint_info;
+use solana_sdk::{
+    clock::Slot, commitment_config::CommitmentConfig, hash::Hash, signature::Signature,
+    transaction::VersionedTransaction,
+};
+
+/// A blockhash along with the last block height transactions signed with it can land at.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BlockhashExpiry {
+    pub blockhash: Hash,
+    pub last_valid_block_height: u64,
+}
+
+impl BlockhashExpiry {
+    /// Fetches the latest confirmed blockhash.
+    pub async fn fetch(rpc_client: &RpcClient) -> ClientResult<Self> {
+        let (blockhash, last_valid_block_height) = rpc_client
+            .get_latest_blockhash_with_commitment(CommitmentConfig::confirmed())
+            .await?;
+        Ok(Self {
+            blockhash,
+            last_valid_block_height,
+        })
+    }
+
+    pub fn blocks_remaining(&self, block_height: u64) -> u64 {
+        self.last_valid_block_height.saturating_sub(block_height)
+    }
+
+    /// The last slot transactions signed with the blockhash can land in, given the cluster's
+    /// current slot and block height. Skipped slots produce no block, so this is the earliest
+    /// the blockhash can expire at.
+    pub fn last_valid_slot(&self, slot: Slot, block_height: u64) -> Slot {
+        slot.saturating_add(self.blocks_remaining(block_height))
+    }
+}
+
+/// A bundle signed ahead of submission along with the block height its blockhash expires at.
+/// Expiry is tracked in block height reported by the cluster rather than wall-clock time, so a
+/// skewed local clock can't make a bundle look fresher than it is.
+#[derive(Clone, Debug)]
+pub struct PreSignedBundle {
+    pub transactions: Vec<VersionedTransaction>,
+    pub blockhash: Hash,
+    pub last_valid_block_height: u64,
+    /// Number of times this bundle has been rebuilt with a fresh blockhash.
+    pub rebuilds: u64,
+}
+
+impl PreSignedBundle {
+    /// Fetches a blockhash and signs the bundle with it.
+    pub async fn build<F>(rpc_client: &RpcClient, build_transactions: F) -> ClientResult<Self>
+    where
+        F: Fn(&Hash) -> Vec<VersionedTransaction>,
+    {
+        let BlockhashExpiry {
+            blockhash,
+            last_valid_block_height,
+        } = BlockhashExpiry::fetch(rpc_client).await?;
+        Ok(Self {
+            transactions: build_transactions(&blockhash),
+            blockhash,
+            last_valid_block_height,
+            rebuilds: 0,
+        })
+    }
+
+    pub fn signatures(&self) -> Vec<Signature> {
+        self.transactions
+            .iter()
+            .map(|tx| tx.signatures[0])
+            .collect()
+    }
+
+    pub fn blocks_remaining(&self, block_height: u64) -> u64 {
+        self.last_valid_block_height.saturating_sub(block_height)
+    }
+
+    /// Re-signs the bundle with a fresh blockhash if it expires within `margin_blocks`.
+    /// Returns true if the bundle was rebuilt.
+    pub async fn refresh_if_expiring<F>(
+        &mut self,
+        rpc_client: &RpcClient,
+        margin_blocks: u64,
+        build_transactions: F,
+    ) -> ClientResult<bool>
+    where
+        F: Fn(&Hash) -> Vec<VersionedTransaction>,
+    {
+        let block_height = rpc_client
+            .get_block_height_with_commitment(CommitmentConfig::confirmed())
+            .await?;
+        let blocks_remaining = self.blocks_remaining(block_height);
+        if blocks_remaining > margin_blocks {
+            return Ok(false);
+        }
+
+        let rebuilds = self.rebuilds + 1;
+        *self = Self::build(rpc_client, build_transactions).await?;
+        self.rebuilds = rebuilds;
+
+        info!(
+            "rebuilt pre-signed bundle {blocks_remaining} blocks before expiry, rebuilds: {rebuilds}"
+        );
+        datapoint_info!(
+            "presigned_bundle_rebuild",
+            ("blocks_remaining", blocks_remaining, i64),
+            ("rebuilds", rebuilds, i64),
+        );
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use solana_sdk::hash::Hash;
+
+    use crate::bundle_expiry::BlockhashExpiry;
+
+    #[test]
+    fn test_last_valid_slot() {
+        let expiry = BlockhashExpiry {
+            blockhash: Hash::new_unique(),
+            last_valid_block_height: 1_150,
+        };
+        assert_eq!(expiry.blocks_remaining(1_000), 150);
+        assert_eq!(expiry.last_valid_slot(5_000, 1_000), 5_150);
+        // an expired blockhash is valid until the current slot at the latest
+        assert_eq!(expiry.blocks_remaining(1_200), 0);
+        assert_eq!(expiry.last_valid_slot(5_000, 1_200), 5_000);
+    }
+}
@@ -0,0 +1,315 @@
+use std::collections::HashSet;
+
+use solana_sdk::{
+    borsh0_10::try_from_slice_unchecked,
+    compute_budget::{self, ComputeBudgetInstruction},
+    instruction::Instruction,
+    message::Message,
+    packet::PACKET_DATA_SIZE,
+    pubkey::Pubkey,
+    signature::Signer,
+    transaction::Transaction,
+};
+
+use crate::bundle_builder::TransactionSpec;
+
+/// Compute units the runtime allows a transaction.
+pub const MAX_COMPUTE_UNIT_LIMIT: u32 = 1_400_000;
+/// Compute units the runtime gives each instruction without a compute unit limit instruction.
+pub const DEFAULT_INSTRUCTION_COMPUTE_UNIT_LIMIT: u32 = 200_000;
+
+/// What a merged transaction may grow to.
+#[derive(Clone, Debug)]
+pub struct OptimizerLimits {
+    /// Serialized size, signatures included.
+    pub max_transaction_bytes: usize,
+    pub max_compute_units: u32,
+}
+
+impl Default for OptimizerLimits {
+    fn default() -> Self {
+        Self {
+            max_transaction_bytes: PACKET_DATA_SIZE,
+            max_compute_units: MAX_COMPUTE_UNIT_LIMIT,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct OptimizationStats {
+    pub merged_transactions: usize,
+    pub removed_ata_creates: usize,
+    pub removed_compute_budget_instructions: usize,
+}
+
+/// The compute budget instructions of a transaction, combined.
+#[derive(Clone, Copy, Debug, Default)]
+struct Budget {
+    unit_limit: Option<u32>,
+    unit_price_micro_lamports: Option<u64>,
+    heap_frame_bytes: Option<u32>,
+    loaded_accounts_data_size_limit: Option<u32>,
+}
+
+impl Budget {
+    /// Takes the compute budget instructions out of `instructions`. The last of each kind wins.
+    fn split(instructions: Vec<Instruction>) -> (Self, Vec<Instruction>, usize) {
+        let mut budget = Self::default();
+        let mut found = 0;
+        let mut rest = Vec::with_capacity(instructions.len());
+        for ix in instructions {
+            let parsed = (ix.program_id == compute_budget::id())
+                .then(|| try_from_slice_unchecked::<ComputeBudgetInstruction>(&ix.data).ok())
+                .flatten();
+            match parsed {
+                Some(ComputeBudgetInstruction::SetComputeUnitLimit(units)) => {
+                    budget.unit_limit = Some(units)
+                }
+                Some(ComputeBudgetInstruction::SetComputeUnitPrice(price)) => {
+                    budget.unit_price_micro_lamports = Some(price)
+                }
+                Some(ComputeBudgetInstruction::RequestHeapFrame(bytes)) => {
+                    budget.heap_frame_bytes = Some(bytes)
+                }
+                Some(ComputeBudgetInstruction::SetLoadedAccountsDataSizeLimit(bytes)) => {
+                    budget.loaded_accounts_data_size_limit = Some(bytes)
+                }
+                _ => {
+                    rest.push(ix);
+                    continue;
+                }
+            }
+            found += 1;
+        }
+        (budget, rest, found)
+    }
+
+    /// Compute units a transaction with `instructions` besides the budget gets.
+    fn compute_units(&self, instructions: usize) -> u32 {
+        self.unit_limit.unwrap_or(
+            (instructions as u32)
+                .saturating_mul(DEFAULT_INSTRUCTION_COMPUTE_UNIT_LIMIT)
+                .min(MAX_COMPUTE_UNIT_LIMIT),
+        )
+    }
+
+    /// The budget of two transactions run as one: their compute adds up, everything else takes
+    /// the larger of the two so neither gets less than it asked for.
+    fn merge(&self, instructions: usize, other: &Budget, other_instructions: usize) -> Self {
+        let explicit = self.unit_limit.is_some() || other.unit_limit.is_some();
+        Self {
+            unit_limit: explicit.then(|| {
+                self.compute_units(instructions)
+                    .saturating_add(other.compute_units(other_instructions))
+            }),
+            unit_price_micro_lamports: self
+                .unit_price_micro_lamports
+                .max(other.unit_price_micro_lamports),
+            heap_frame_bytes: self.heap_frame_bytes.max(other.heap_frame_bytes),
+            loaded_accounts_data_size_limit: self
+                .loaded_accounts_data_size_limit
+                .max(other.loaded_accounts_data_size_limit),
+        }
+    }
+
+    fn instructions(&self) -> Vec<Instruction> {
+        let mut instructions = vec![];
+        if let Some(units) = self.unit_limit {
+            instructions.push(ComputeBudgetInstruction::set_compute_unit_limit(units));
+        }
+        if let Some(price) = self.unit_price_micro_lamports {
+            instructions.push(ComputeBudgetInstruction::set_compute_unit_price(price));
+        }
+        if let Some(bytes) = self.heap_frame_bytes {
+            instructions.push(ComputeBudgetInstruction::request_heap_frame(bytes));
+        }
+        if let Some(bytes) = self.loaded_accounts_data_size_limit {
+            instructions.push(ComputeBudgetInstruction::set_loaded_accounts_data_size_limit(bytes));
+        }
+        instructions
+    }
+}
+
+/// A transaction spec with its compute budget pulled out.
+struct Candidate {
+    spec: TransactionSpec,
+    budget: Budget,
+}
+
+impl Candidate {
+    fn into_spec(self) -> TransactionSpec {
+        let mut spec = self.spec;
+        let mut instructions = self.budget.instructions();
+        instructions.append(&mut spec.instructions);
+        spec.instructions = instructions;
+        spec
+    }
+
+    /// Accounts the instructions read and write.
+    fn accounts(&self) -> (HashSet<Pubkey>, HashSet<Pubkey>) {
+        let mut reads = HashSet::new();
+        let mut writes = HashSet::new();
+        for ix in &self.spec.instructions {
+            reads.insert(ix.program_id);
+            for meta in &ix.accounts {
+                if meta.is_writable {
+                    writes.insert(meta.pubkey);
+                } else {
+                    reads.insert(meta.pubkey);
+                }
+            }
+        }
+        (reads, writes)
+    }
+
+    /// True if running the two in either order gives the same result.
+    fn commutes_with(&self, other: &Candidate) -> bool {
+        let (reads, writes) = self.accounts();
+        let (other_reads, other_writes) = other.accounts();
+        writes.is_disjoint(&other_writes)
+            && writes.is_disjoint(&other_reads)
+            && reads.is_disjoint(&other_writes)
+    }
+
+    /// The two as one transaction, if they share a payer and fee payer and the result fits the
+    /// limits with `reserved` instructions added later.
+    fn merged(
+        &self,
+        other: &Candidate,
+        limits: &OptimizerLimits,
+        reserved: &[Instruction],
+    ) -> Option<Candidate> {
+        if self.spec.payer.pubkey() != other.spec.payer.pubkey()
+            || self.spec.fee_payer().pubkey() != other.spec.fee_payer().pubkey()
+        {
+            return None;
+        }
+        let compute_units = self
+            .budget
+            .compute_units(self.spec.instructions.len())
+            .saturating_add(other.budget.compute_units(other.spec.instructions.len()));
+        if compute_units > limits.max_compute_units {
+            return None;
+        }
+        let budget = self.budget.merge(
+            self.spec.instructions.len(),
+            &other.budget,
+            other.spec.instructions.len(),
+        );
+        let instructions: Vec<Instruction> = self
+            .spec
+            .instructions
+            .iter()
+            .chain(other.spec.instructions.iter())
+            .cloned()
+            .collect();
+        let mut spec = TransactionSpec::new(instructions, self.spec.payer.clone());
+        spec.fee_payer = self.spec.fee_payer.clone();
+        for signer in self.spec.signers.iter().chain(other.spec.signers.iter()) {
+            if !spec.signers.iter().any(|s| s.pubkey() == signer.pubkey()) {
+                spec = spec.with_signer(signer.clone());
+            }
+        }
+        let candidate = Candidate { spec, budget };
+        (candidate.serialized_size(reserved) <= limits.max_transaction_bytes).then_some(candidate)
+    }
+
+    fn serialized_size(&self, reserved: &[Instruction]) -> usize {
+        let instructions: Vec<Instruction> = self
+            .budget
+            .instructions()
+            .into_iter()
+            .chain(self.spec.instructions.iter().cloned())
+            .chain(reserved.iter().cloned())
+            .collect();
+        let message = Message::new(&instructions, Some(&self.spec.fee_payer().pubkey()));
+        // unsigned transactions carry placeholder signatures of the full size
+        bincode::serialized_size(&Transaction::new_unsigned(message))
+            .map_or(usize::MAX, |size| size as usize)
+    }
+}
+
+/// Takes out creations of associated token accounts an earlier instruction in the bundle
+/// already creates. Only idempotent creations are removed, a plain `Create` of an existing
+/// account fails, and the bundle's author may rely on that.
+fn remove_redundant_ata_creates(transactions: &mut [TransactionSpec]) -> usize {
+    let mut created = HashSet::new();
+    let mut removed = 0;
+    for spec in transactions {
+        spec.instructions.retain(|ix| {
+            if ix.program_id != spl_associated_token_account::id() {
+                return true;
+            }
+            let Some(account) = ix.accounts.get(1).map(|meta| meta.pubkey) else {
+                return true;
+            };
+            let idempotent = ix.data == [1];
+            if !created.insert(account) && idempotent {
+                removed += 1;
+                return false;
+            }
+            true
+        });
+    }
+    removed
+}
+
+/// Shrinks a bundle without changing what it does: removes associated token account creations
+/// made redundant by earlier ones, combines compute budget instructions, and merges transactions
+/// with the same payer when the result fits in one transaction. A later transaction is moved up
+/// to merge only if it touches no account the transactions it skips over write, or writes none
+/// they read. Bundles land all or nothing, so merging doesn't change atomicity.
+///
+/// `reserved` are instructions added to every transaction afterwards, such as a slot assertion
+/// or tip, counted when checking sizes. Merged transactions keep the higher priority fee of the
+/// two, and explicit compute unit limits add up.
+pub fn optimize_transactions(
+    transactions: Vec<TransactionSpec>,
+    limits: &OptimizerLimits,
+    reserved: &[Instruction],
+) -> (Vec<TransactionSpec>, OptimizationStats) {
+    let mut stats = OptimizationStats::default();
+    let mut transactions = transactions;
+    stats.removed_ata_creates = remove_redundant_ata_creates(&mut transactions);
+
+    let mut candidates: Vec<Candidate> = transactions
+        .into_iter()
+        .filter_map(|mut spec| {
+            let (budget, instructions, found) = Budget::split(spec.instructions);
+            stats.removed_compute_budget_instructions += found - budget.instructions().len();
+            spec.instructions = instructions;
+            // a transaction left with only compute budget instructions does nothing
+            (!spec.instructions.is_empty()).then_some(Candidate { spec, budget })
+        })
+        .collect();
+
+    let mut i = 0;
+    while i < candidates.len() {
+        let mut j = i + 1;
+        while j < candidates.len() {
+            let movable = candidates[i + 1..j]
+                .iter()
+                .all(|skipped| skipped.commutes_with(&candidates[j]));
+            let merged = movable
+                .then(|| candidates[i].merged(&candidates[j], limits, reserved))
+                .flatten();
+            match merged {
+                Some(merged) => {
+                    stats.removed_compute_budget_instructions +=
+                        (candidates[i].budget.instructions().len()
+                            + candidates[j].budget.instructions().len())
+                        .saturating_sub(merged.budget.instructions().len());
+                    candidates[i] = merged;
+                    candidates.remove(j);
+                    stats.merged_transactions += 1;
+                }
+                None => j += 1,
+            }
+        }
+        i += 1;
+    }
+    (
+        candidates.into_iter().map(Candidate::into_spec).collect(),
+        stats,
+    )
+}
@@ -0,0 +1,353 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fmt,
+};
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use serde_json::{json, Map, Value};
+use solana_account_decoder::{UiAccount, UiAccountEncoding};
+use solana_client::{
+    client_error::{ClientErrorKind, Result as ClientResult},
+    nonblocking::rpc_client::RpcClient,
+    rpc_config::{RpcSimulateTransactionAccountsConfig, RpcSimulateTransactionConfig},
+    rpc_request::RpcRequest,
+    rpc_response::{Response, RpcSimulateTransactionResult},
+};
+use solana_sdk::{
+    account::Account, pubkey::Pubkey, transaction::TransactionError,
+    transaction::VersionedTransaction,
+};
+
+use crate::simulation_report::SimulationReport;
+
+/// An account changed by one bundle transaction and used by a later one, which means the later
+/// transaction's outcome depends on the earlier one landing first.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BundleConflict {
+    pub account: Pubkey,
+    /// Index of the transaction that changes the account.
+    pub writer: usize,
+    /// Index of the later transaction that uses it.
+    pub reader: usize,
+    /// Instructions of the later transaction that reference the account.
+    pub reader_instructions: Vec<usize>,
+    /// True if the later transaction also writes the account.
+    pub reader_writes: bool,
+}
+
+impl fmt::Display for BundleConflict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "tx {} {} {} changed by tx {} (instructions {:?})",
+            self.reader,
+            if self.reader_writes {
+                "writes"
+            } else {
+                "reads"
+            },
+            self.account,
+            self.writer,
+            self.reader_instructions
+        )
+    }
+}
+
+/// Result of simulating every transaction of a bundle.
+#[derive(Clone, Debug, Default)]
+pub struct BundleSimulation {
+    /// Simulation error of each transaction, None if it succeeded.
+    pub errors: Vec<Option<TransactionError>>,
+    /// Accounts each transaction changed in simulation.
+    pub changed_accounts: Vec<HashSet<Pubkey>>,
+    pub conflicts: Vec<BundleConflict>,
+    /// Balance changes summed over the transactions. Each one is simulated on its own, so
+    /// changes an earlier transaction makes to a later one's accounts aren't reflected.
+    pub report: SimulationReport,
+}
+
+impl BundleSimulation {
+    /// Transactions that failed in simulation even though an earlier transaction changes state
+    /// they use, so they may well succeed once the bundle executes in order.
+    pub fn failures_explained_by_conflicts(&self) -> Vec<usize> {
+        self.errors
+            .iter()
+            .enumerate()
+            .filter(|(i, err)| err.is_some() && self.conflicts.iter().any(|c| c.reader == *i))
+            .map(|(i, _)| i)
+            .collect()
+    }
+}
+
+/// Simulates each transaction of a bundle and reports which accounts changed by one transaction
+/// are used by a later one. Every transaction is simulated against the current cluster state,
+/// not the state left by the transactions before it, since plain RPC nodes can't simulate on top
+/// of a fork. Accounts loaded through address lookup tables aren't inspected.
+pub async fn simulate_bundle_conflicts(
+    rpc_client: &RpcClient,
+    transactions: &[VersionedTransaction],
+    ignored_accounts: &HashSet<Pubkey>,
+) -> ClientResult<BundleSimulation> {
+    let writable: Vec<Vec<Pubkey>> = transactions.iter().map(writable_accounts).collect();
+    let addresses: Vec<Pubkey> = writable
+        .iter()
+        .flatten()
+        .copied()
+        .collect::<HashSet<_>>()
+        .into_iter()
+        .collect();
+    let before: HashMap<Pubkey, Option<Account>> = addresses
+        .iter()
+        .copied()
+        .zip(rpc_client.get_multiple_accounts(&addresses).await?)
+        .collect();
+
+    let mut simulation = BundleSimulation::default();
+    for (tx, writable) in transactions.iter().zip(writable.iter()) {
+        let config = RpcSimulateTransactionConfig {
+            sig_verify: false,
+            replace_recent_blockhash: true,
+            accounts: Some(RpcSimulateTransactionAccountsConfig {
+                encoding: Some(UiAccountEncoding::Base64),
+                addresses: writable.iter().map(|a| a.to_string()).collect(),
+            }),
+            ..RpcSimulateTransactionConfig::default()
+        };
+        let result = rpc_client
+            .simulate_transaction_with_config(tx, config)
+            .await?
+            .value;
+
+        let after: Vec<Option<Account>> = result
+            .accounts
+            .unwrap_or_default()
+            .into_iter()
+            .map(|after| after.and_then(|a| a.decode()))
+            .collect();
+        let changed = writable
+            .iter()
+            .zip(after.iter())
+            .filter(|(account, after)| before.get(*account).cloned().flatten() != **after)
+            .map(|(account, _)| *account)
+            .collect();
+        if result.err.is_none() {
+            simulation.report.merge(&SimulationReport::from_states(
+                writable.iter().zip(&after).map(|(account, after)| {
+                    (
+                        *account,
+                        before.get(account).and_then(Option::as_ref),
+                        after.as_ref(),
+                    )
+                }),
+            ));
+        }
+        simulation.errors.push(result.err);
+        simulation.changed_accounts.push(changed);
+    }
+
+    simulation.conflicts =
+        find_conflicts(transactions, &simulation.changed_accounts, ignored_accounts);
+    Ok(simulation)
+}
+
+/// Pairs each changed account with every later transaction referencing it.
+pub fn find_conflicts(
+    transactions: &[VersionedTransaction],
+    changed_accounts: &[HashSet<Pubkey>],
+    ignored_accounts: &HashSet<Pubkey>,
+) -> Vec<BundleConflict> {
+    let mut conflicts = vec![];
+    for (writer, changed) in changed_accounts.iter().enumerate() {
+        for (reader, tx) in transactions.iter().enumerate().skip(writer + 1) {
+            let keys = tx.message.static_account_keys();
+            for (key_index, account) in keys.iter().enumerate() {
+                if !changed.contains(account) || ignored_accounts.contains(account) {
+                    continue;
+                }
+                let reader_instructions = tx
+                    .message
+                    .instructions()
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, ix)| {
+                        ix.program_id_index as usize == key_index
+                            || ix.accounts.contains(&(key_index as u8))
+                    })
+                    .map(|(i, _)| i)
+                    .collect();
+                conflicts.push(BundleConflict {
+                    account: *account,
+                    writer,
+                    reader,
+                    reader_instructions,
+                    reader_writes: tx.message.is_maybe_writable(key_index),
+                });
+            }
+        }
+    }
+    conflicts
+}
+
+fn writable_accounts(tx: &VersionedTransaction) -> Vec<Pubkey> {
+    tx.message
+        .static_account_keys()
+        .iter()
+        .enumerate()
+        .filter(|(i, _)| tx.message.is_maybe_writable(*i))
+        .map(|(_, key)| *key)
+        .collect()
+}
+
+/// Account state substituted for on-chain state when simulating, to evaluate a transaction
+/// against state that doesn't exist yet.
+#[derive(Clone, Debug, Default)]
+pub struct AccountOverrides {
+    accounts: HashMap<Pubkey, Account>,
+}
+
+impl AccountOverrides {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The state the cluster would be in if `victim` landed first: simulates it and overrides
+    /// every account it writes with the result.
+    pub async fn victim_first(
+        rpc_client: &RpcClient,
+        victim: &VersionedTransaction,
+    ) -> ClientResult<Self> {
+        let writable = writable_accounts(victim);
+        let config = RpcSimulateTransactionConfig {
+            sig_verify: false,
+            replace_recent_blockhash: true,
+            accounts: Some(RpcSimulateTransactionAccountsConfig {
+                encoding: Some(UiAccountEncoding::Base64),
+                addresses: writable.iter().map(|a| a.to_string()).collect(),
+            }),
+            ..RpcSimulateTransactionConfig::default()
+        };
+        let result = rpc_client
+            .simulate_transaction_with_config(victim, config)
+            .await?
+            .value;
+        if let Some(err) = result.err {
+            return Err(ClientErrorKind::Custom(format!("victim fails simulation: {err}")).into());
+        }
+
+        let mut overrides = Self::new();
+        for (pubkey, account) in writable.iter().zip(result.accounts.unwrap_or_default()) {
+            if let Some(account) = account.and_then(|a| a.decode::<Account>()) {
+                overrides.set_account(*pubkey, account);
+            }
+        }
+        Ok(overrides)
+    }
+
+    /// Loads the on-chain state of accounts not overridden yet, so they can be modified with
+    /// `account_mut`. Accounts that don't exist are skipped.
+    pub async fn load(&mut self, rpc_client: &RpcClient, pubkeys: &[Pubkey]) -> ClientResult<()> {
+        let missing: Vec<Pubkey> = pubkeys
+            .iter()
+            .filter(|pubkey| !self.accounts.contains_key(pubkey))
+            .copied()
+            .collect();
+        let accounts = rpc_client.get_multiple_accounts(&missing).await?;
+        for (pubkey, account) in missing.into_iter().zip(accounts) {
+            if let Some(account) = account {
+                self.accounts.insert(pubkey, account);
+            }
+        }
+        Ok(())
+    }
+
+    pub fn set_account(&mut self, pubkey: Pubkey, account: Account) -> &mut Self {
+        self.accounts.insert(pubkey, account);
+        self
+    }
+
+    /// An overridden or loaded account, to change its lamports or data.
+    pub fn account_mut(&mut self, pubkey: &Pubkey) -> Option<&mut Account> {
+        self.accounts.get_mut(pubkey)
+    }
+
+    pub fn get(&self, pubkey: &Pubkey) -> Option<&Account> {
+        self.accounts.get(pubkey)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.accounts.is_empty()
+    }
+
+    fn to_json(&self) -> Value {
+        let accounts: Map<String, Value> = self
+            .accounts
+            .iter()
+            .map(|(pubkey, account)| {
+                let account =
+                    UiAccount::encode(pubkey, account, UiAccountEncoding::Base64, None, None);
+                (
+                    pubkey.to_string(),
+                    serde_json::to_value(account).expect("serializes account"),
+                )
+            })
+            .collect();
+        Value::Object(accounts)
+    }
+}
+
+/// Simulates a transaction on top of `overrides` and returns the post-simulation state of
+/// `return_accounts`. Overrides are sent in an `accountOverrides` field of the simulateTransaction
+/// config, which stock RPC nodes silently ignore. Overridden accounts the transaction doesn't write
+/// are read back to catch that, and an error is returned if they still hold on-chain state.
+pub async fn simulate_with_overrides(
+    rpc_client: &RpcClient,
+    transaction: &VersionedTransaction,
+    overrides: &AccountOverrides,
+    return_accounts: &[Pubkey],
+) -> ClientResult<RpcSimulateTransactionResult> {
+    let writable: HashSet<Pubkey> = writable_accounts(transaction).into_iter().collect();
+    let probes: Vec<Pubkey> = overrides
+        .accounts
+        .keys()
+        .filter(|pubkey| !writable.contains(pubkey))
+        .copied()
+        .collect();
+    let addresses: Vec<String> = return_accounts
+        .iter()
+        .chain(probes.iter())
+        .map(|pubkey| pubkey.to_string())
+        .collect();
+
+    let serialized = bincode::serialize(transaction)
+        .map_err(|e| ClientErrorKind::Custom(format!("failed to serialize transaction: {e}")))?;
+    let config = json!({
+        "sigVerify": false,
+        "replaceRecentBlockhash": true,
+        "encoding": "base64",
+        "commitment": rpc_client.commitment().commitment,
+        "accounts": { "encoding": "base64", "addresses": addresses },
+        "accountOverrides": overrides.to_json(),
+    });
+    let mut result = rpc_client
+        .send::<Response<RpcSimulateTransactionResult>>(
+            RpcRequest::SimulateTransaction,
+            json!([STANDARD.encode(serialized), config]),
+        )
+        .await?
+        .value;
+
+    if let Some(mut accounts) = result.accounts.take() {
+        let returned = accounts.split_off(return_accounts.len().min(accounts.len()));
+        for (pubkey, account) in probes.iter().zip(returned) {
+            let account = account.and_then(|a| a.decode::<Account>());
+            if account.as_ref() != overrides.get(pubkey) {
+                return Err(ClientErrorKind::Custom(format!(
+                    "RPC node ignored the override of {pubkey}, it doesn't support account overrides"
+                ))
+                .into());
+            }
+        }
+        result.accounts = Some(accounts);
+    }
+    Ok(result)
+}
@@ -0,0 +1,621 @@
+use std::{
+    collections::{hash_map::Entry, HashMap, HashSet},
+    fmt::{self, Display, Formatter},
+    fs::{self, File},
+    io::{self, BufRead, BufReader, BufWriter, Write},
+    path::{Path, PathBuf},
+};
+
+use chrono::{DateTime, Utc};
+use log::{debug, info, warn};
+use serde::{Deserialize, Serialize};
+use solana_client::{
+    client_error::ClientError,
+    nonblocking::rpc_client::RpcClient,
+    rpc_client::GetConfirmedSignaturesForAddress2Config,
+    rpc_config::{RpcBlockConfig, RpcTransactionConfig},
+    rpc_request::MAX_GET_SIGNATURE_STATUSES_QUERY_ITEMS,
+};
+use solana_sdk::{
+    clock::Slot, commitment_config::CommitmentConfig, pubkey::Pubkey, signature::Signature,
+};
+use solana_transaction_status::{
+    EncodedConfirmedTransactionWithStatusMeta, EncodedTransaction, RewardType, TransactionDetails,
+    UiTransactionEncoding,
+};
+use thiserror::Error;
+
+use crate::{audit_log::AuditedBundle, landing_model::tip_lamports};
+
+/// Slots after submission a bundle may still land in, after which its blockhash has expired.
+const LANDING_WINDOW_SLOTS: Slot = 150;
+
+/// Source of records reconstructed from on-chain history rather than imported from audit logs.
+pub const BACKFILL_SOURCE: &str = "backfill";
+
+/// Signatures requested per page of an address's history, the most RPC nodes return.
+const SIGNATURES_PER_PAGE: usize = 1_000;
+
+#[derive(Debug, Error)]
+pub enum BundleStoreError {
+    #[error("failed to access bundle store: {0}")]
+    Io(#[from] io::Error),
+    #[error("invalid bundle store record: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("rpc error: {0}")]
+    Rpc(#[from] ClientError),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BundleStatus {
+    /// Not found on chain yet, but could still land.
+    Pending,
+    Landed,
+    /// On chain with the searcher's transaction failed.
+    Failed,
+    /// Never landed and can't anymore.
+    Dropped,
+}
+
+impl Display for BundleStatus {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            BundleStatus::Pending => write!(f, "pending"),
+            BundleStatus::Landed => write!(f, "landed"),
+            BundleStatus::Failed => write!(f, "failed"),
+            BundleStatus::Dropped => write!(f, "dropped"),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct TransactionRecord {
+    pub signature: String,
+    /// The transaction's error once it's found on chain, if it failed.
+    #[serde(default)]
+    pub error: Option<String>,
+    /// Program logs, fetched once the bundle is on chain.
+    #[serde(default)]
+    pub logs: Option<Vec<String>>,
+    /// How much the fee payer's balance changed, fees and tips included, once it's on chain.
+    #[serde(default)]
+    pub fee_payer_change: Option<i64>,
+    /// Position among its block's non-vote transactions, 0 at the top, once it's on chain.
+    #[serde(default)]
+    pub block_index: Option<usize>,
+}
+
+/// What's known about a submitted bundle.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct BundleRecord {
+    /// The block engine's uuid for the bundle. Backfilled records, whose uuid isn't on chain,
+    /// use their tipping transaction's signature.
+    pub bundle_uuid: String,
+    /// The audit log the bundle was imported from, which names the pipeline that sent it.
+    pub source: String,
+    #[serde(default)]
+    pub submitted_at: Option<DateTime<Utc>>,
+    pub submitted_slot: Option<Slot>,
+    pub slots_to_leader: Option<u64>,
+    /// The next Jito leader at submission, the one the bundle was aimed at.
+    #[serde(default)]
+    pub leader: Option<String>,
+    /// Block engine regions the bundle was sent through.
+    #[serde(default)]
+    pub regions: Vec<String>,
+    pub status: BundleStatus,
+    pub landed_slot: Option<Slot>,
+    /// The leader of the block the bundle landed in.
+    #[serde(default)]
+    pub landed_leader: Option<String>,
+    /// Lamports the bundle transfers to the tip accounts, only paid if it lands.
+    #[serde(default)]
+    pub tip_lamports: u64,
+    /// Non-vote transactions in the block the bundle landed in.
+    #[serde(default)]
+    pub block_transactions: Option<usize>,
+    pub transactions: Vec<TransactionRecord>,
+}
+
+impl BundleRecord {
+    pub fn from_audited(
+        source: &str,
+        bundle: &AuditedBundle,
+        tip_accounts: &HashSet<Pubkey>,
+    ) -> Self {
+        Self {
+            bundle_uuid: bundle.bundle_uuid.clone(),
+            source: source.to_string(),
+            submitted_at: bundle.submitted_at,
+            submitted_slot: bundle.submitted_slot,
+            slots_to_leader: bundle.slots_to_leader,
+            leader: bundle.leader.clone(),
+            regions: bundle.regions.clone(),
+            status: BundleStatus::Pending,
+            landed_slot: None,
+            landed_leader: None,
+            tip_lamports: tip_lamports(&bundle.transactions, tip_accounts),
+            block_transactions: None,
+            transactions: bundle
+                .transactions
+                .iter()
+                .map(|tx| TransactionRecord {
+                    signature: tx.signatures[0].to_string(),
+                    error: None,
+                    logs: None,
+                    fee_payer_change: None,
+                    block_index: None,
+                })
+                .collect(),
+        }
+    }
+
+    /// The searcher's profit or loss after fees and tip: how much the fee payer of the last
+    /// transaction, the searcher's, gained. None until the bundle is found on chain.
+    pub fn net_lamports(&self) -> Option<i64> {
+        self.transactions.last()?.fee_payer_change
+    }
+
+    /// Where the bundle executed in its block: its first transaction's position among the
+    /// block's non-vote transactions. None until it's found on chain.
+    pub fn block_index(&self) -> Option<usize> {
+        self.transactions.first()?.block_index
+    }
+
+    /// The leader the bundle's outcome is down to: the one that included it if it's on chain,
+    /// otherwise the one it was aimed at.
+    pub fn outcome_leader(&self) -> Option<&str> {
+        self.landed_leader.as_deref().or(self.leader.as_deref())
+    }
+
+    /// Whether the bundle executed before any other non-vote transaction in its block.
+    pub fn top_of_block(&self) -> Option<bool> {
+        self.block_index().map(|index| index == 0)
+    }
+
+    /// The transactions' errors and the error lines from their logs, in order.
+    pub fn errors(&self) -> Vec<String> {
+        let mut errors = vec![];
+        for (index, tx) in self.transactions.iter().enumerate() {
+            if let Some(error) = &tx.error {
+                errors.push(format!("tx {index}: {error}"));
+            }
+            errors.extend(
+                log_errors(tx.logs.as_deref().unwrap_or_default())
+                    .into_iter()
+                    .map(|line| format!("tx {index}: {line}")),
+            );
+        }
+        errors
+    }
+}
+
+/// The lines of a transaction's logs that report an error: failed programs, Anchor and program
+/// logged errors, and panics.
+pub fn log_errors(logs: &[String]) -> Vec<String> {
+    logs.iter()
+        .filter_map(|line| {
+            let message = line.strip_prefix("Program log: ").unwrap_or(line);
+            let is_error = line.contains(" failed: ")
+                || message.starts_with("Error")
+                || message.starts_with("AnchorError")
+                || message.contains("panicked at");
+            is_error.then(|| message.to_string())
+        })
+        .collect()
+}
+
+/// Submitted bundles and what became of them, kept as JSONL with one record per bundle.
+/// Bundles are imported from backrun audit logs, then `resolve` checks whether they landed and
+/// attaches their transactions' logs. `save` rewrites the file through a temporary file, so a
+/// crash leaves the previous version.
+pub struct BundleStore {
+    path: PathBuf,
+    records: Vec<BundleRecord>,
+    positions: HashMap<String, usize>,
+}
+
+impl BundleStore {
+    /// Opens the store at `path`, empty if the file doesn't exist yet.
+    pub fn open(path: impl Into<PathBuf>) -> Result<Self, BundleStoreError> {
+        let path = path.into();
+        let mut store = Self {
+            path,
+            records: vec![],
+            positions: HashMap::new(),
+        };
+        let file = match File::open(&store.path) {
+            Ok(file) => file,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(store),
+            Err(e) => return Err(e.into()),
+        };
+        for line in BufReader::new(file).lines() {
+            let line = line?;
+            if !line.trim().is_empty() {
+                store.insert(serde_json::from_str(&line)?);
+            }
+        }
+        Ok(store)
+    }
+
+    pub fn save(&self) -> Result<(), BundleStoreError> {
+        let mut tmp_path = self.path.clone().into_os_string();
+        tmp_path.push(".tmp");
+        let mut writer = BufWriter::new(File::create(&tmp_path)?);
+        for record in &self.records {
+            serde_json::to_writer(&mut writer, record)?;
+            writer.write_all(b"\n")?;
+        }
+        writer.flush()?;
+        drop(writer);
+        fs::rename(&tmp_path, &self.path)?;
+        Ok(())
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Every record, in the order bundles were imported.
+    pub fn records(&self) -> &[BundleRecord] {
+        &self.records
+    }
+
+    pub fn get(&self, bundle_uuid: &str) -> Option<&BundleRecord> {
+        self.positions
+            .get(bundle_uuid)
+            .map(|position| &self.records[*position])
+    }
+
+    /// Adds or replaces the record with the same bundle uuid.
+    pub fn insert(&mut self, record: BundleRecord) {
+        match self.positions.get(&record.bundle_uuid) {
+            Some(position) => self.records[*position] = record,
+            None => {
+                self.positions
+                    .insert(record.bundle_uuid.clone(), self.records.len());
+                self.records.push(record);
+            }
+        }
+    }
+
+    /// Adds the bundles not already in the store, returning how many were new. `source` names
+    /// where they came from, such as the audit log's name, and transfers to `tip_accounts` count
+    /// as their tips.
+    pub fn import(
+        &mut self,
+        source: &str,
+        bundles: &[AuditedBundle],
+        tip_accounts: &HashSet<Pubkey>,
+    ) -> usize {
+        let mut imported = 0;
+        for bundle in bundles {
+            if bundle.transactions.is_empty() || self.positions.contains_key(&bundle.bundle_uuid) {
+                continue;
+            }
+            self.insert(BundleRecord::from_audited(source, bundle, tip_accounts));
+            imported += 1;
+        }
+        imported
+    }
+
+    /// Reconstructs records of bundles that landed before the store was kept, from `payer`'s
+    /// history on chain: each transaction `payer` paid the fees of that transfers to
+    /// `tip_accounts` is taken to be a bundle's tipping transaction. The other transactions in
+    /// its bundle aren't known, so the record holds only this one. History is walked back from
+    /// the newest transaction, stopping at transactions before `since` or after scanning
+    /// `max_transactions`, and transactions already in the store are skipped. Returns how many
+    /// records were added, oldest first.
+    pub async fn backfill(
+        &mut self,
+        rpc_client: &RpcClient,
+        payer: &Pubkey,
+        tip_accounts: &HashSet<Pubkey>,
+        since: Option<DateTime<Utc>>,
+        max_transactions: usize,
+    ) -> Result<usize, BundleStoreError> {
+        let known: HashSet<String> = self
+            .records
+            .iter()
+            .flat_map(|record| record.transactions.iter())
+            .map(|tx| tx.signature.clone())
+            .collect();
+        let mut backfilled = vec![];
+        let mut scanned = 0;
+        let mut before = None;
+        'pages: while scanned < max_transactions {
+            let page = rpc_client
+                .get_signatures_for_address_with_config(
+                    payer,
+                    GetConfirmedSignaturesForAddress2Config {
+                        before,
+                        until: None,
+                        limit: Some(SIGNATURES_PER_PAGE.min(max_transactions - scanned)),
+                        commitment: Some(CommitmentConfig::confirmed()),
+                    },
+                )
+                .await?;
+            let Some(last) = page.last() else {
+                break;
+            };
+            before = last.signature.parse().ok();
+            for status in &page {
+                let block_time = status
+                    .block_time
+                    .and_then(|time| DateTime::from_timestamp(time, 0));
+                if let (Some(since), Some(block_time)) = (since, block_time) {
+                    if block_time < since {
+                        break 'pages;
+                    }
+                }
+                scanned += 1;
+                if known.contains(&status.signature) {
+                    continue;
+                }
+                let Ok(signature) = status.signature.parse::<Signature>() else {
+                    continue;
+                };
+                let tx = rpc_client
+                    .get_transaction_with_config(&signature, transaction_config())
+                    .await?;
+                if let Some(record) = backfilled_record(&tx, payer, tip_accounts, block_time) {
+                    debug!("backfilled tip transaction {signature} in slot {}", tx.slot);
+                    backfilled.push(record);
+                }
+            }
+            info!(
+                "scanned {scanned} transactions of {payer}, {} paid tips",
+                backfilled.len()
+            );
+            if before.is_none() {
+                break;
+            }
+        }
+        fetch_positions(rpc_client, backfilled.iter_mut()).await;
+        let count = backfilled.len();
+        for record in backfilled.into_iter().rev() {
+            self.insert(record);
+        }
+        Ok(count)
+    }
+
+    /// Checks whether pending bundles landed, by their last transaction, the searcher's. For
+    /// bundles found on chain, every transaction's error, logs, fee payer balance change and
+    /// position in the block are fetched and attached.
+    /// Bundles not found once their blockhash has expired are marked dropped. Returns how many
+    /// records changed.
+    pub async fn resolve(&mut self, rpc_client: &RpcClient) -> Result<usize, BundleStoreError> {
+        let current_slot = rpc_client.get_slot().await?;
+        let pending: Vec<usize> = self
+            .records
+            .iter()
+            .enumerate()
+            .filter(|(_, record)| record.status == BundleStatus::Pending)
+            .map(|(position, _)| position)
+            .collect();
+
+        let mut changed = HashSet::new();
+        for chunk in pending.chunks(MAX_GET_SIGNATURE_STATUSES_QUERY_ITEMS) {
+            let signatures: Vec<Signature> = chunk
+                .iter()
+                .filter_map(|position| {
+                    let last = self.records[*position].transactions.last()?;
+                    last.signature.parse().ok()
+                })
+                .collect();
+            if signatures.len() != chunk.len() {
+                // records are only imported with transactions and valid signatures
+                continue;
+            }
+            let statuses = rpc_client
+                .get_signature_statuses_with_history(&signatures)
+                .await?
+                .value;
+            for (position, status) in chunk.iter().zip(statuses) {
+                let record = &mut self.records[*position];
+                match status {
+                    Some(status) => {
+                        record.status = match status.err {
+                            None => BundleStatus::Landed,
+                            Some(_) => BundleStatus::Failed,
+                        };
+                        record.landed_slot = Some(status.slot);
+                        fetch_logs(rpc_client, record).await?;
+                        changed.insert(*position);
+                    }
+                    // records from before slots were logged are old enough to have expired
+                    None if record.submitted_slot.is_none_or(|slot| {
+                        slot.saturating_add(LANDING_WINDOW_SLOTS) < current_slot
+                    }) =>
+                    {
+                        record.status = BundleStatus::Dropped;
+                        changed.insert(*position);
+                    }
+                    None => {}
+                }
+            }
+        }
+        // also positions bundles that landed before positions and leaders were kept
+        let unpositioned = self.records.iter_mut().enumerate().filter(|(_, record)| {
+            record.landed_slot.is_some()
+                && (record.block_transactions.is_none() || record.landed_leader.is_none())
+        });
+        let (positions, unpositioned): (Vec<usize>, Vec<&mut BundleRecord>) = unpositioned.unzip();
+        let positioned = fetch_positions(rpc_client, unpositioned.into_iter()).await;
+        changed.extend(
+            positions
+                .into_iter()
+                .zip(positioned)
+                .filter_map(|(position, positioned)| positioned.then_some(position)),
+        );
+        Ok(changed.len())
+    }
+}
+
+/// Attaches each landed record's position in its block and the block's leader, fetching each
+/// block once. Blocks that can't be fetched, such as ones the RPC node no longer keeps, leave
+/// their records without them. Returns whether each record was positioned.
+async fn fetch_positions<'a>(
+    rpc_client: &RpcClient,
+    records: impl Iterator<Item = &'a mut BundleRecord>,
+) -> Vec<bool> {
+    let mut blocks: HashMap<Slot, Option<LandedBlock>> = HashMap::new();
+    let mut positioned = vec![];
+    for record in records {
+        let Some(slot) = record.landed_slot else {
+            positioned.push(false);
+            continue;
+        };
+        if let Entry::Vacant(entry) = blocks.entry(slot) {
+            let block = match fetch_block(rpc_client, slot).await {
+                Ok(block) => Some(block),
+                Err(e) => {
+                    warn!("failed to fetch block {slot} to position its bundles: {e}");
+                    None
+                }
+            };
+            entry.insert(block);
+        }
+        let Some(block) = &blocks[&slot] else {
+            positioned.push(false);
+            continue;
+        };
+        record.block_transactions = Some(block.indexes.len());
+        record.landed_leader = block.leader.clone();
+        for tx in &mut record.transactions {
+            tx.block_index = block.indexes.get(&tx.signature).copied();
+        }
+        positioned.push(true);
+    }
+    positioned
+}
+
+struct LandedBlock {
+    /// The validator that produced the block, paid its fees.
+    leader: Option<String>,
+    /// Positions of the non-vote transactions, by signature.
+    indexes: HashMap<String, usize>,
+}
+
+async fn fetch_block(rpc_client: &RpcClient, slot: Slot) -> Result<LandedBlock, ClientError> {
+    let block = rpc_client
+        .get_block_with_config(
+            slot,
+            RpcBlockConfig {
+                encoding: None,
+                transaction_details: Some(TransactionDetails::Accounts),
+                rewards: Some(true),
+                commitment: Some(CommitmentConfig::confirmed()),
+                max_supported_transaction_version: Some(0),
+            },
+        )
+        .await?;
+    let leader =
+        block.rewards.into_iter().flatten().find_map(|reward| {
+            (reward.reward_type == Some(RewardType::Fee)).then_some(reward.pubkey)
+        });
+    let vote_program = solana_sdk::vote::program::id().to_string();
+    let indexes = block
+        .transactions
+        .unwrap_or_default()
+        .into_iter()
+        .filter_map(|tx| match tx.transaction {
+            EncodedTransaction::Accounts(accounts)
+                if !accounts
+                    .account_keys
+                    .iter()
+                    .any(|account| account.pubkey == vote_program) =>
+            {
+                accounts.signatures.into_iter().next()
+            }
+            _ => None,
+        })
+        .enumerate()
+        .map(|(index, signature)| (signature, index))
+        .collect();
+    Ok(LandedBlock { leader, indexes })
+}
+
+async fn fetch_logs(
+    rpc_client: &RpcClient,
+    record: &mut BundleRecord,
+) -> Result<(), BundleStoreError> {
+    for tx in &mut record.transactions {
+        let Ok(signature) = tx.signature.parse::<Signature>() else {
+            continue;
+        };
+        let confirmed = rpc_client
+            .get_transaction_with_config(&signature, transaction_config())
+            .await?;
+        attach_meta(tx, &confirmed);
+    }
+    Ok(())
+}
+
+fn transaction_config() -> RpcTransactionConfig {
+    RpcTransactionConfig {
+        encoding: Some(UiTransactionEncoding::Base64),
+        commitment: Some(CommitmentConfig::confirmed()),
+        max_supported_transaction_version: Some(0),
+    }
+}
+
+/// Sets a transaction's error, logs and fee payer balance change from its status on chain.
+fn attach_meta(tx: &mut TransactionRecord, confirmed: &EncodedConfirmedTransactionWithStatusMeta) {
+    if let Some(meta) = &confirmed.transaction.meta {
+        tx.error = meta.err.as_ref().map(|e| e.to_string());
+        tx.logs = Option::from(meta.log_messages.clone());
+        tx.fee_payer_change = meta
+            .pre_balances
+            .first()
+            .zip(meta.post_balances.first())
+            .map(|(pre, post)| *post as i64 - *pre as i64);
+    }
+}
+
+/// The record of a landed bundle whose tipping transaction is `confirmed`, None if `payer`
+/// didn't pay its fees or it didn't tip.
+fn backfilled_record(
+    confirmed: &EncodedConfirmedTransactionWithStatusMeta,
+    payer: &Pubkey,
+    tip_accounts: &HashSet<Pubkey>,
+    block_time: Option<DateTime<Utc>>,
+) -> Option<BundleRecord> {
+    let transaction = confirmed.transaction.transaction.decode()?;
+    if transaction.message.static_account_keys().first() != Some(payer) {
+        return None;
+    }
+    let tip_lamports = tip_lamports(std::slice::from_ref(&transaction), tip_accounts);
+    if tip_lamports == 0 {
+        return None;
+    }
+    let signature = transaction.signatures.first()?.to_string();
+    let mut tx = TransactionRecord {
+        signature: signature.clone(),
+        error: None,
+        logs: None,
+        fee_payer_change: None,
+        block_index: None,
+    };
+    attach_meta(&mut tx, confirmed);
+    Some(BundleRecord {
+        bundle_uuid: signature,
+        source: BACKFILL_SOURCE.to_string(),
+        // landing time, the closest to submission on chain
+        submitted_at: block_time,
+        submitted_slot: Some(confirmed.slot),
+        slots_to_leader: None,
+        leader: None,
+        regions: vec![],
+        status: match tx.error {
+            None => BundleStatus::Landed,
+            Some(_) => BundleStatus::Failed,
+        },
+        landed_slot: Some(confirmed.slot),
+        landed_leader: None,
+        tip_lamports,
+        block_transactions: None,
+        transactions: vec![tx],
+    })
+}
@@ -0,0 +1,226 @@
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use chrono::{DateTime, Utc};
+use jito_protos::searcher::searcher_service_client::SearcherServiceClient;
+use log::{info, warn};
+use serde::Serialize;
+use solana_client::{client_error::Result as ClientResult, nonblocking::rpc_client::RpcClient};
+use solana_metrics::datapoint_info;
+use solana_sdk::{
+    clock::Slot,
+    commitment_config::CommitmentConfig,
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+    system_instruction,
+    transaction::{Transaction, VersionedTransaction},
+};
+use solana_transaction_status::TransactionConfirmationStatus;
+use tokio::time::sleep;
+use tonic::{codegen::InterceptedService, transport::Channel, Code};
+
+use crate::{send_bundle_no_wait, token_authenticator::ClientInterceptor};
+
+const STATUS_POLL_INTERVAL: Duration = Duration::from_millis(400);
+
+pub struct CanaryConfig {
+    /// Pays the self-transfer and the tip.
+    pub payer: Arc<Keypair>,
+    /// Tips go to each in turn.
+    pub tip_accounts: Vec<Pubkey>,
+    pub tip_lamports: u64,
+    /// How long to wait for the bundle to be confirmed before calling it lost.
+    pub land_timeout: Duration,
+}
+
+/// How far a canary bundle got.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case", tag = "outcome", content = "error")]
+pub enum CanaryOutcome {
+    /// The block engine refused the searcher's auth.
+    AuthFailed(String),
+    /// The block engine didn't take the bundle.
+    SendFailed(String),
+    /// Taken by the block engine, but not confirmed within the land timeout.
+    NotLanded,
+    /// Landed, but the transaction failed.
+    Failed(String),
+    Landed,
+}
+
+impl CanaryOutcome {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::AuthFailed(_) => "auth_failed",
+            Self::SendFailed(_) => "send_failed",
+            Self::NotLanded => "not_landed",
+            Self::Failed(_) => "failed",
+            Self::Landed => "landed",
+        }
+    }
+}
+
+/// One canary bundle: when it was sent, how long each step of its path took and how it ended.
+#[derive(Clone, Debug, Serialize)]
+pub struct CanaryProbe {
+    pub sequence: u64,
+    pub sent_at: DateTime<Utc>,
+    /// The RPC's slot when the bundle was sent.
+    pub sent_slot: Slot,
+    #[serde(flatten)]
+    pub outcome: CanaryOutcome,
+    pub uuid: Option<String>,
+    /// Round trip of the `SendBundle` call, auth included.
+    pub send_ms: u64,
+    pub landed_slot: Option<Slot>,
+    /// Leader of the slot the bundle landed in.
+    pub leader: Option<String>,
+    /// From sending the bundle until the RPC had it processed, then confirmed.
+    pub processed_ms: Option<u64>,
+    pub confirmed_ms: Option<u64>,
+}
+
+impl CanaryProbe {
+    pub fn landed(&self) -> bool {
+        self.outcome == CanaryOutcome::Landed
+    }
+
+    pub fn report(&self) {
+        datapoint_info!(
+            "canary",
+            ("sequence", self.sequence, i64),
+            ("landed", self.landed(), bool),
+            ("outcome", self.outcome.name(), String),
+            ("send_ms", self.send_ms, i64),
+            (
+                "processed_ms",
+                self.processed_ms.map_or(-1, |ms| ms as i64),
+                i64
+            ),
+            (
+                "confirmed_ms",
+                self.confirmed_ms.map_or(-1, |ms| ms as i64),
+                i64
+            ),
+            (
+                "slots_to_land",
+                self.landed_slot
+                    .map_or(-1, |slot| slot.saturating_sub(self.sent_slot) as i64),
+                i64
+            ),
+        );
+    }
+}
+
+/// Sends a bundle of one transaction moving `1 + sequence` lamports from the payer to itself and
+/// tipping, then follows it until confirmed, failed or the land timeout passes. Exercises the
+/// whole path a real bundle takes, at the cost of the tip and a transaction fee when it lands.
+pub async fn send_canary(
+    client: &mut SearcherServiceClient<InterceptedService<Channel, ClientInterceptor>>,
+    rpc_client: &RpcClient,
+    config: &CanaryConfig,
+    sequence: u64,
+) -> ClientResult<CanaryProbe> {
+    let payer = &config.payer;
+    let mut instructions = vec![system_instruction::transfer(
+        &payer.pubkey(),
+        &payer.pubkey(),
+        // unique per probe so two sent with the same blockhash have different signatures
+        1 + sequence,
+    )];
+    if let Some(tip_account) = config
+        .tip_accounts
+        .get(sequence as usize % config.tip_accounts.len().max(1))
+    {
+        instructions.push(system_instruction::transfer(
+            &payer.pubkey(),
+            tip_account,
+            config.tip_lamports,
+        ));
+    }
+    let blockhash = rpc_client.get_latest_blockhash().await?;
+    let sent_slot = rpc_client
+        .get_slot_with_commitment(CommitmentConfig::processed())
+        .await?;
+    let tx = Transaction::new_signed_with_payer(
+        &instructions,
+        Some(&payer.pubkey()),
+        &[payer.as_ref()],
+        blockhash,
+    );
+    let signature = tx.signatures[0];
+    let serialized = bincode::serialize(&VersionedTransaction::from(tx)).expect("serializes");
+
+    let mut probe = CanaryProbe {
+        sequence,
+        sent_at: Utc::now(),
+        sent_slot,
+        outcome: CanaryOutcome::NotLanded,
+        uuid: None,
+        send_ms: 0,
+        landed_slot: None,
+        leader: None,
+        processed_ms: None,
+        confirmed_ms: None,
+    };
+    let sent = Instant::now();
+    let response = send_bundle_no_wait(&[serialized], client).await;
+    probe.send_ms = sent.elapsed().as_millis() as u64;
+    match response {
+        Ok(response) => probe.uuid = Some(response.into_inner().uuid),
+        Err(status) => {
+            let message = status.message().to_string();
+            probe.outcome = match status.code() {
+                Code::Unauthenticated | Code::PermissionDenied => {
+                    CanaryOutcome::AuthFailed(message)
+                }
+                _ => CanaryOutcome::SendFailed(message),
+            };
+            return Ok(probe);
+        }
+    }
+
+    while sent.elapsed() < config.land_timeout {
+        sleep(STATUS_POLL_INTERVAL).await;
+        let status = match rpc_client.get_signature_statuses(&[signature]).await {
+            Ok(response) => response.value.into_iter().next().flatten(),
+            Err(e) => {
+                warn!("failed to check canary {sequence}: {e}");
+                continue;
+            }
+        };
+        let Some(status) = status else {
+            continue;
+        };
+        let elapsed_ms = sent.elapsed().as_millis() as u64;
+        probe.processed_ms.get_or_insert(elapsed_ms);
+        if probe.landed_slot.replace(status.slot) != Some(status.slot) {
+            probe.leader = rpc_client
+                .get_slot_leaders(status.slot, 1)
+                .await
+                .ok()
+                .and_then(|leaders| leaders.first().map(Pubkey::to_string));
+        }
+        if let Some(err) = status.err {
+            probe.outcome = CanaryOutcome::Failed(err.to_string());
+            return Ok(probe);
+        }
+        if matches!(
+            status.confirmation_status,
+            Some(
+                TransactionConfirmationStatus::Confirmed | TransactionConfirmationStatus::Finalized
+            )
+        ) {
+            probe.confirmed_ms = Some(elapsed_ms);
+            probe.outcome = CanaryOutcome::Landed;
+            info!(
+                "canary {sequence} landed in slot {} after {elapsed_ms}ms",
+                status.slot
+            );
+            return Ok(probe);
+        }
+    }
+    Ok(probe)
+}
@@ -0,0 +1,57 @@
+use std::{ops::RangeInclusive, str::FromStr};
+
+use solana_sdk::clock::Slot;
+
+/// An inclusive slot range given on the command line as `START-END`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SlotRange(pub RangeInclusive<Slot>);
+
+impl FromStr for SlotRange {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (start, end) = s
+            .split_once('-')
+            .ok_or_else(|| format!("expected START-END, got {s}"))?;
+        let start: Slot = start.trim().parse().map_err(|e| format!("{start}: {e}"))?;
+        let end: Slot = end.trim().parse().map_err(|e| format!("{end}: {e}"))?;
+        if start > end {
+            return Err(format!("range start {start} is after end {end}"));
+        }
+        Ok(Self(start..=end))
+    }
+}
+
+/// Decides when mempool traffic is worth capturing: only inside the configured slot ranges
+/// and/or only while a connected Jito leader is close. With nothing configured the window is
+/// always open.
+#[derive(Clone, Debug, Default)]
+pub struct CaptureWindow {
+    pub slot_ranges: Vec<SlotRange>,
+    /// Only capture while the next Jito leader slot is at most this many slots away.
+    pub max_slots_to_leader: Option<u64>,
+}
+
+impl CaptureWindow {
+    pub fn is_open(&self, current_slot: Slot, next_leader_slot: Slot) -> bool {
+        let in_range = self.slot_ranges.is_empty()
+            || self
+                .slot_ranges
+                .iter()
+                .any(|range| range.0.contains(&current_slot));
+        let leader_close = self
+            .max_slots_to_leader
+            .map(|max| next_leader_slot.saturating_sub(current_slot) <= max)
+            .unwrap_or(true);
+        in_range && leader_close
+    }
+
+    /// True once the current slot is past every configured range, so capturing can stop.
+    pub fn is_finished(&self, current_slot: Slot) -> bool {
+        !self.slot_ranges.is_empty()
+            && self
+                .slot_ranges
+                .iter()
+                .all(|range| current_slot > *range.0.end())
+    }
+}
@@ -0,0 +1,85 @@
+use thiserror::Error;
+use tonic::metadata::{AsciiMetadataKey, AsciiMetadataValue, MetadataMap};
+
+pub const CLIENT_NAME_HEADER: &str = "x-client-name";
+pub const CLIENT_VERSION_HEADER: &str = "x-client-version";
+pub const STRATEGY_ID_HEADER: &str = "x-strategy-id";
+
+/// Headers the client sets itself and can't be overridden.
+const RESERVED_HEADERS: &[&str] = &["authorization"];
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum ClientHeaderError {
+    #[error("expected KEY=VALUE, got {0:?}")]
+    Malformed(String),
+    #[error("invalid header name {0:?}")]
+    InvalidKey(String),
+    #[error("invalid value for header {0:?}, must be printable ascii")]
+    InvalidValue(String),
+    #[error("header {0:?} is set by the client")]
+    Reserved(String),
+}
+
+/// Metadata attached to every SearcherService call, so block engine operators can tell clients,
+/// versions and strategies apart. Defaults to this crate's name and version.
+#[derive(Clone, Debug)]
+pub struct ClientHeaders {
+    headers: Vec<(AsciiMetadataKey, AsciiMetadataValue)>,
+}
+
+impl Default for ClientHeaders {
+    fn default() -> Self {
+        let mut headers = Self { headers: vec![] };
+        headers
+            .insert(CLIENT_NAME_HEADER, env!("CARGO_PKG_NAME"))
+            .expect("valid client name");
+        headers
+            .insert(CLIENT_VERSION_HEADER, env!("CARGO_PKG_VERSION"))
+            .expect("valid client version");
+        headers
+    }
+}
+
+impl ClientHeaders {
+    /// Sets a header, replacing any previous value.
+    pub fn insert(&mut self, key: &str, value: &str) -> Result<&mut Self, ClientHeaderError> {
+        let key = key.trim().to_ascii_lowercase();
+        if RESERVED_HEADERS.contains(&key.as_str()) {
+            return Err(ClientHeaderError::Reserved(key));
+        }
+        let metadata_key: AsciiMetadataKey = key
+            .parse()
+            .map_err(|_| ClientHeaderError::InvalidKey(key.clone()))?;
+        let metadata_value: AsciiMetadataValue = value
+            .trim()
+            .parse()
+            .map_err(|_| ClientHeaderError::InvalidValue(key.clone()))?;
+        self.headers.retain(|(k, _)| *k != metadata_key);
+        self.headers.push((metadata_key, metadata_value));
+        Ok(self)
+    }
+
+    /// Sets a header given as `KEY=VALUE`.
+    pub fn insert_pair(&mut self, pair: &str) -> Result<&mut Self, ClientHeaderError> {
+        let (key, value) = pair
+            .split_once('=')
+            .ok_or_else(|| ClientHeaderError::Malformed(pair.to_string()))?;
+        self.insert(key, value)
+    }
+
+    pub fn with_client_name(mut self, name: &str) -> Result<Self, ClientHeaderError> {
+        self.insert(CLIENT_NAME_HEADER, name)?;
+        Ok(self)
+    }
+
+    pub fn with_strategy_id(mut self, strategy_id: &str) -> Result<Self, ClientHeaderError> {
+        self.insert(STRATEGY_ID_HEADER, strategy_id)?;
+        Ok(self)
+    }
+
+    pub fn apply(&self, metadata: &mut MetadataMap) {
+        for (key, value) in &self.headers {
+            metadata.insert(key.clone(), value.clone());
+        }
+    }
+}
@@ -0,0 +1,228 @@
+use std::{
+    future::Future,
+    io,
+    sync::{
+        atomic::{AtomicI64, Ordering},
+        Arc,
+    },
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use solana_client::{client_error::ClientError, nonblocking::rpc_client::RpcClient};
+use solana_metrics::datapoint_info;
+use thiserror::Error;
+use tokio::{
+    net::UdpSocket,
+    time::{sleep, timeout},
+};
+
+/// Seconds between the NTP epoch (1900) and the unix epoch.
+const NTP_UNIX_EPOCH_DELTA_SECS: i64 = 2_208_988_800;
+const NTP_PACKET_SIZE: usize = 48;
+/// LI 0, version 4, mode 3 (client).
+const NTP_CLIENT_HEADER: u8 = 0x23;
+const NTP_SERVER_MODE: u8 = 4;
+const NTP_TIMEOUT: Duration = Duration::from_secs(2);
+
+#[derive(Debug, Error)]
+pub enum ClockCheckError {
+    #[error("io error {0}")]
+    Io(#[from] io::Error),
+    #[error("no response from {0} within {1:?}")]
+    Timeout(String, Duration),
+    #[error("invalid NTP response from {0}: {1}")]
+    InvalidResponse(String, String),
+    #[error("rpc error {0}")]
+    Rpc(#[from] ClientError),
+    #[error("no samples taken")]
+    NoSamples,
+}
+
+/// One measurement of the local clock against the reference.
+#[derive(Clone, Copy, Debug)]
+pub struct ClockSample {
+    /// Reference time minus local time. Positive when the local clock is behind.
+    pub offset_us: i64,
+    pub round_trip: Duration,
+}
+
+/// Offset and jitter over several samples.
+#[derive(Clone, Copy, Debug)]
+pub struct ClockOffsetEstimate {
+    /// Median offset of the samples, reference minus local.
+    pub offset_us: i64,
+    /// Standard deviation of the sample offsets.
+    pub jitter_us: u64,
+    pub min_round_trip: Duration,
+    pub samples: usize,
+}
+
+impl ClockOffsetEstimate {
+    pub fn from_samples(samples: &[ClockSample]) -> Option<Self> {
+        if samples.is_empty() {
+            return None;
+        }
+        let mut offsets: Vec<i64> = samples.iter().map(|s| s.offset_us).collect();
+        offsets.sort_unstable();
+        let offset_us = offsets[offsets.len() / 2];
+        let mean = offsets.iter().sum::<i64>() as f64 / offsets.len() as f64;
+        let variance = offsets
+            .iter()
+            .map(|o| (*o as f64 - mean).powi(2))
+            .sum::<f64>()
+            / offsets.len() as f64;
+        Some(Self {
+            offset_us,
+            jitter_us: variance.sqrt() as u64,
+            min_round_trip: samples
+                .iter()
+                .map(|s| s.round_trip)
+                .min()
+                .unwrap_or_default(),
+            samples: samples.len(),
+        })
+    }
+}
+
+/// Takes `samples` measurements `interval` apart with `sample`, e.g.
+/// `measure_clock_offset(8, interval, || ntp_sample("pool.ntp.org:123"))`. Failed samples are
+/// skipped, the last error is returned if none succeed.
+pub async fn measure_clock_offset<F, Fut>(
+    samples: usize,
+    interval: Duration,
+    mut sample: F,
+) -> Result<ClockOffsetEstimate, ClockCheckError>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<ClockSample, ClockCheckError>>,
+{
+    let mut taken = Vec::with_capacity(samples);
+    let mut last_error = ClockCheckError::NoSamples;
+    for i in 0..samples {
+        if i > 0 {
+            sleep(interval).await;
+        }
+        match sample().await {
+            Ok(sample) => taken.push(sample),
+            Err(e) => last_error = e,
+        }
+    }
+    ClockOffsetEstimate::from_samples(&taken).ok_or(last_error)
+}
+
+/// Queries an NTP server given as `host:port` once (SNTP, RFC 4330).
+pub async fn ntp_sample(server: &str) -> Result<ClockSample, ClockCheckError> {
+    let socket = UdpSocket::bind("0.0.0.0:0").await?;
+    socket.connect(server).await?;
+
+    let mut request = [0u8; NTP_PACKET_SIZE];
+    request[0] = NTP_CLIENT_HEADER;
+    let sent_at = unix_now_us();
+    request[40..48].copy_from_slice(&to_ntp_timestamp(sent_at).to_be_bytes());
+    socket.send(&request).await?;
+
+    let mut response = [0u8; NTP_PACKET_SIZE];
+    let received = timeout(NTP_TIMEOUT, socket.recv(&mut response))
+        .await
+        .map_err(|_| ClockCheckError::Timeout(server.to_string(), NTP_TIMEOUT))??;
+    let received_at = unix_now_us();
+
+    let invalid = |reason: &str| ClockCheckError::InvalidResponse(server.into(), reason.into());
+    if received < NTP_PACKET_SIZE {
+        return Err(invalid("short packet"));
+    }
+    if response[0] & 0b111 != NTP_SERVER_MODE {
+        return Err(invalid("not a server response"));
+    }
+    // stratum 0 is a kiss-of-death, the server asks us to back off
+    if response[1] == 0 {
+        return Err(invalid("kiss-of-death"));
+    }
+
+    let server_received = from_ntp_timestamp(read_u64(&response[32..40]));
+    let server_sent = from_ntp_timestamp(read_u64(&response[40..48]));
+    let offset_us = ((server_received - sent_at) + (server_sent - received_at)) / 2;
+    let round_trip_us = (received_at - sent_at) - (server_sent - server_received);
+    Ok(ClockSample {
+        offset_us,
+        round_trip: Duration::from_micros(round_trip_us.max(0) as u64),
+    })
+}
+
+/// Compares the local clock with the block time of the latest slot. Block times have one second
+/// resolution and trail the slot by however long it took to be voted on, so this only catches
+/// gross skew.
+pub async fn rpc_sample(rpc_client: &RpcClient) -> Result<ClockSample, ClockCheckError> {
+    let sent_at = unix_now_us();
+    let slot = rpc_client.get_slot().await?;
+    let block_time = rpc_client.get_block_time(slot).await?;
+    let received_at = unix_now_us();
+    let local_us = sent_at + (received_at - sent_at) / 2;
+    Ok(ClockSample {
+        offset_us: block_time * 1_000_000 - local_us,
+        round_trip: Duration::from_micros((received_at - sent_at).max(0) as u64),
+    })
+}
+
+/// Latest measured offset of the local clock, shared between clones so a background task can
+/// keep it up to date while timing code reads corrected time.
+#[derive(Clone, Debug, Default)]
+pub struct ClockOffset {
+    offset_us: Arc<AtomicI64>,
+    jitter_us: Arc<AtomicI64>,
+}
+
+impl ClockOffset {
+    pub fn update(&self, estimate: &ClockOffsetEstimate) {
+        self.offset_us.store(estimate.offset_us, Ordering::Relaxed);
+        self.jitter_us
+            .store(estimate.jitter_us as i64, Ordering::Relaxed);
+    }
+
+    pub fn offset_us(&self) -> i64 {
+        self.offset_us.load(Ordering::Relaxed)
+    }
+
+    /// Local time corrected by the measured offset. Same as `SystemTime::now()` until the
+    /// first measurement.
+    pub fn now(&self) -> SystemTime {
+        let offset_us = self.offset_us();
+        let now = SystemTime::now();
+        if offset_us >= 0 {
+            now + Duration::from_micros(offset_us as u64)
+        } else {
+            now - Duration::from_micros(offset_us.unsigned_abs())
+        }
+    }
+
+    pub fn report(&self, name: &'static str) {
+        datapoint_info!(
+            name,
+            ("offset_us", self.offset_us(), i64),
+            ("jitter_us", self.jitter_us.load(Ordering::Relaxed), i64),
+        );
+    }
+}
+
+fn unix_now_us() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_micros() as i64
+}
+
+fn read_u64(bytes: &[u8]) -> u64 {
+    u64::from_be_bytes(bytes.try_into().expect("8 bytes"))
+}
+
+fn to_ntp_timestamp(unix_us: i64) -> u64 {
+    let secs = (unix_us / 1_000_000 + NTP_UNIX_EPOCH_DELTA_SECS) as u64;
+    let fraction = ((unix_us % 1_000_000) as u64 * (1 << 32)) / 1_000_000;
+    (secs << 32) | fraction
+}
+
+fn from_ntp_timestamp(timestamp: u64) -> i64 {
+    let secs = (timestamp >> 32) as i64 - NTP_UNIX_EPOCH_DELTA_SECS;
+    let fraction_us = ((timestamp & 0xffff_ffff) * 1_000_000) >> 32;
+    secs * 1_000_000 + fraction_us as i64
+}
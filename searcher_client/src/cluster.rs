@@ -0,0 +1,122 @@
+use std::{fmt, str::FromStr};
+
+use serde::{Deserialize, Serialize};
+use solana_client::{client_error::ClientError, nonblocking::rpc_client::RpcClient};
+use solana_sdk::hash::Hash;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum ClusterCheckError {
+    #[error("rpc error: {0}")]
+    Rpc(#[from] ClientError),
+    #[error(
+        "the rpc node is on {rpc_cluster} (genesis hash {genesis_hash}) but bundles go to a \
+         {expected} block engine, point --rpc-url at a {expected} node or change the block engine"
+    )]
+    Mismatch {
+        genesis_hash: Hash,
+        rpc_cluster: ClusterName,
+        expected: Cluster,
+    },
+}
+
+/// The public clusters Jito runs block engines for.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Cluster {
+    MainnetBeta,
+    Testnet,
+    Devnet,
+}
+
+impl Cluster {
+    pub const ALL: [Cluster; 3] = [Cluster::MainnetBeta, Cluster::Testnet, Cluster::Devnet];
+
+    pub fn genesis_hash(&self) -> Hash {
+        let hash = match self {
+            Cluster::MainnetBeta => "5eykt4UsFv8P8NJdTREpY1vzqKqZKvdpKuc147dw2N9d",
+            Cluster::Testnet => "4uhcVJyU9pJkvQyS88uRDiswHXSCkY3zQawwpjk2NsNY",
+            Cluster::Devnet => "EtWTRABZaYq6iMfeYKouRu166VU2xqa1wcaWoxPkrZBG",
+        };
+        Hash::from_str(hash).expect("valid genesis hash")
+    }
+
+    pub fn from_genesis_hash(genesis_hash: &Hash) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|cluster| cluster.genesis_hash() == *genesis_hash)
+    }
+
+    /// The cluster a Jito block engine URL serves, from the `mainnet`, `testnet` or `devnet`
+    /// label in its host, e.g. `frankfurt.mainnet.block-engine.jito.wtf`. None for hosts
+    /// without one, such as a local relayer.
+    pub fn from_block_engine_url(url: &str) -> Option<Self> {
+        let host = url.split("://").nth(1).unwrap_or(url);
+        let host = host.split(['/', ':']).next().unwrap_or_default();
+        host.split('.').find_map(|label| match label {
+            "mainnet" | "mainnet-beta" => Some(Cluster::MainnetBeta),
+            "testnet" => Some(Cluster::Testnet),
+            "devnet" => Some(Cluster::Devnet),
+            _ => None,
+        })
+    }
+}
+
+impl fmt::Display for Cluster {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Cluster::MainnetBeta => "mainnet-beta",
+            Cluster::Testnet => "testnet",
+            Cluster::Devnet => "devnet",
+        })
+    }
+}
+
+impl FromStr for Cluster {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "mainnet-beta" | "mainnet" => Ok(Cluster::MainnetBeta),
+            "testnet" => Ok(Cluster::Testnet),
+            "devnet" => Ok(Cluster::Devnet),
+            _ => Err(format!(
+                "unknown cluster {s}, expected mainnet-beta, testnet or devnet"
+            )),
+        }
+    }
+}
+
+/// A cluster identified by genesis hash, or one that isn't public, like a local validator.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ClusterName {
+    Known(Cluster),
+    Unknown,
+}
+
+impl fmt::Display for ClusterName {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ClusterName::Known(cluster) => cluster.fmt(f),
+            ClusterName::Unknown => f.write_str("an unknown cluster"),
+        }
+    }
+}
+
+/// Fetches the RPC node's genesis hash and fails if it isn't `expected`'s, so a testnet RPC
+/// paired with a mainnet block engine fails up front instead of every bundle failing with
+/// blockhash or account errors. Returns the RPC node's cluster.
+pub async fn check_cluster(
+    rpc_client: &RpcClient,
+    expected: Cluster,
+) -> Result<Cluster, ClusterCheckError> {
+    let genesis_hash = rpc_client.get_genesis_hash().await?;
+    match Cluster::from_genesis_hash(&genesis_hash) {
+        Some(cluster) if cluster == expected => Ok(cluster),
+        rpc_cluster => Err(ClusterCheckError::Mismatch {
+            genesis_hash,
+            rpc_cluster: rpc_cluster.map_or(ClusterName::Unknown, ClusterName::Known),
+            expected,
+        }),
+    }
+}
@@ -0,0 +1,218 @@
+use std::str::FromStr;
+
+use solana_client::{
+    client_error::Result as ClientResult, nonblocking::rpc_client::RpcClient,
+    rpc_config::RpcSimulateTransactionConfig,
+};
+use solana_sdk::{
+    compute_budget,
+    instruction::InstructionError,
+    pubkey::Pubkey,
+    transaction::{TransactionError, VersionedTransaction},
+};
+
+use crate::{
+    bundle_optimizer::{DEFAULT_INSTRUCTION_COMPUTE_UNIT_LIMIT, MAX_COMPUTE_UNIT_LIMIT},
+    transaction_decoder::ComputeBudgetSettings,
+};
+
+/// One program invocation while an instruction ran: the instruction's own program, or a
+/// program it called.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Invocation {
+    pub program_id: Pubkey,
+    /// 1 for the instruction's own program, 2 for programs it calls and so on.
+    pub depth: usize,
+    /// Units the invocation consumed, those of the programs it called included. None for
+    /// builtin programs, which don't log their consumption.
+    pub consumed_units: Option<u64>,
+    pub failed: bool,
+}
+
+/// Compute used by one top-level instruction of a transaction.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct InstructionProfile {
+    pub index: usize,
+    /// The instruction's own program first, then every program it called in the order they
+    /// ran.
+    pub invocations: Vec<Invocation>,
+}
+
+impl InstructionProfile {
+    pub fn program_id(&self) -> Pubkey {
+        self.invocations[0].program_id
+    }
+
+    /// Units the whole instruction consumed, None if its program is a builtin.
+    pub fn consumed_units(&self) -> Option<u64> {
+        self.invocations[0].consumed_units
+    }
+
+    pub fn failed(&self) -> bool {
+        self.invocations[0].failed
+    }
+}
+
+/// Where a simulated transaction spent its compute, per instruction.
+#[derive(Clone, Debug)]
+pub struct TransactionProfile {
+    pub instructions: Vec<InstructionProfile>,
+    /// Units the whole transaction consumed, as reported by the simulation.
+    pub units_consumed: Option<u64>,
+    /// Units the transaction may use, requested or the runtime's default.
+    pub unit_limit: u32,
+    pub error: Option<TransactionError>,
+    /// The node cut the logs short, so later instructions are missing.
+    pub logs_truncated: bool,
+}
+
+impl TransactionProfile {
+    /// Units consumed but not logged by any instruction, spent by builtin programs such as the
+    /// system and compute budget programs.
+    pub fn unattributed_units(&self) -> Option<u64> {
+        let attributed: u64 = self
+            .instructions
+            .iter()
+            .filter_map(InstructionProfile::consumed_units)
+            .sum();
+        Some(self.units_consumed?.saturating_sub(attributed))
+    }
+
+    /// True if the transaction ran out of compute: it used all it may, or failed for lack of it.
+    pub fn exceeds_limit(&self) -> bool {
+        self.units_consumed
+            .is_some_and(|units| units >= self.unit_limit as u64)
+            || matches!(
+                self.error,
+                Some(TransactionError::InstructionError(
+                    _,
+                    InstructionError::ComputationalBudgetExceeded
+                ))
+            )
+    }
+}
+
+/// The compute unit limit of a transaction: the one it requests, otherwise the runtime's default
+/// for each instruction that isn't a compute budget instruction.
+pub fn compute_unit_limit(tx: &VersionedTransaction) -> u32 {
+    if let Some(unit_limit) = ComputeBudgetSettings::from_transaction(tx).unit_limit {
+        return unit_limit.min(MAX_COMPUTE_UNIT_LIMIT);
+    }
+    let keys = tx.message.static_account_keys();
+    let instructions = tx
+        .message
+        .instructions()
+        .iter()
+        .filter(|ix| keys.get(ix.program_id_index as usize) != Some(&compute_budget::id()))
+        .count() as u32;
+    instructions
+        .saturating_mul(DEFAULT_INSTRUCTION_COMPUTE_UNIT_LIMIT)
+        .min(MAX_COMPUTE_UNIT_LIMIT)
+}
+
+/// Splits a transaction's program logs into its top-level instructions, reading the invoke,
+/// consumed and success or failure lines the runtime logs for every program. Returns the
+/// instructions and whether the logs were truncated.
+pub fn parse_compute_logs(logs: &[String]) -> (Vec<InstructionProfile>, bool) {
+    let mut instructions: Vec<InstructionProfile> = vec![];
+    // positions in the current instruction's invocations of the programs still running
+    let mut running: Vec<usize> = vec![];
+    let mut truncated = false;
+    for line in logs {
+        if line == "Log truncated" {
+            truncated = true;
+            break;
+        }
+        let Some(rest) = line.strip_prefix("Program ") else {
+            continue;
+        };
+        let Some((program, event)) = rest.split_once(' ') else {
+            continue;
+        };
+        let Ok(program_id) = Pubkey::from_str(program) else {
+            continue;
+        };
+        if let Some(depth) = event
+            .strip_prefix("invoke [")
+            .and_then(|depth| depth.strip_suffix(']'))
+            .and_then(|depth| depth.parse::<usize>().ok())
+        {
+            if depth == 1 {
+                running.clear();
+                instructions.push(InstructionProfile {
+                    index: instructions.len(),
+                    invocations: vec![],
+                });
+            }
+            let Some(instruction) = instructions.last_mut() else {
+                continue;
+            };
+            running.push(instruction.invocations.len());
+            instruction.invocations.push(Invocation {
+                program_id,
+                depth,
+                consumed_units: None,
+                failed: false,
+            });
+            continue;
+        }
+        let Some(invocation) = running
+            .last()
+            .and_then(|position| instructions.last_mut()?.invocations.get_mut(*position))
+            .filter(|invocation| invocation.program_id == program_id)
+        else {
+            continue;
+        };
+        if let Some(consumed) = event.strip_prefix("consumed ") {
+            invocation.consumed_units = consumed
+                .split_once(' ')
+                .and_then(|(units, _)| units.parse().ok());
+        } else if event == "success" {
+            running.pop();
+        } else if event.starts_with("failed") {
+            invocation.failed = true;
+            running.pop();
+        }
+    }
+    (instructions, truncated)
+}
+
+/// Simulates `tx` against the current cluster state and breaks down the compute it used per
+/// instruction. Signatures aren't verified and the blockhash is replaced, so unsigned and stale
+/// transactions can be profiled too.
+pub async fn profile_transaction(
+    rpc_client: &RpcClient,
+    tx: &VersionedTransaction,
+) -> ClientResult<TransactionProfile> {
+    let config = RpcSimulateTransactionConfig {
+        sig_verify: false,
+        replace_recent_blockhash: true,
+        ..RpcSimulateTransactionConfig::default()
+    };
+    let result = rpc_client
+        .simulate_transaction_with_config(tx, config)
+        .await?
+        .value;
+    let (instructions, logs_truncated) =
+        parse_compute_logs(result.logs.as_deref().unwrap_or_default());
+    Ok(TransactionProfile {
+        instructions,
+        units_consumed: result.units_consumed,
+        unit_limit: compute_unit_limit(tx),
+        error: result.err,
+        logs_truncated,
+    })
+}
+
+/// Profiles each transaction of a bundle, see [`profile_transaction`]. Every transaction is
+/// simulated against the current cluster state, not the state left by the ones before it.
+pub async fn profile_bundle(
+    rpc_client: &RpcClient,
+    transactions: &[VersionedTransaction],
+) -> ClientResult<Vec<TransactionProfile>> {
+    let mut profiles = vec![];
+    for tx in transactions {
+        profiles.push(profile_transaction(rpc_client, tx).await?);
+    }
+    Ok(profiles)
+}
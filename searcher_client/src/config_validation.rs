@@ -0,0 +1,134 @@
+use std::{fmt, ops::RangeInclusive, path::Path};
+
+use solana_sdk::signature::read_keypair_file;
+use thiserror::Error;
+use tonic::codegen::http::Uri;
+
+/// Block engine regions accepted in `regions` lists.
+/// See: https://jito-labs.gitbook.io/mev/searcher-resources/block-engine#connection-details
+pub const KNOWN_REGIONS: &[&str] = &["amsterdam", "frankfurt", "ny", "tokyo", "slc"];
+
+/// The minimum tip the block engine accepts.
+pub const MIN_TIP_LAMPORTS: u64 = 1_000;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ConfigProblem {
+    /// Path of the offending field, e.g. `--auth-keypair[1]`.
+    pub field: String,
+    pub message: String,
+}
+
+impl fmt::Display for ConfigProblem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.field, self.message)
+    }
+}
+
+/// Every problem found in a config, so they can all be fixed in one go.
+#[derive(Debug, Error)]
+pub struct ConfigErrors(pub Vec<ConfigProblem>);
+
+impl fmt::Display for ConfigErrors {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{} config problems:", self.0.len())?;
+        for problem in &self.0 {
+            writeln!(f, "  {problem}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Collects config problems instead of stopping at the first one.
+#[derive(Debug, Default)]
+pub struct ConfigValidator {
+    problems: Vec<ConfigProblem>,
+}
+
+impl ConfigValidator {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn problem(&mut self, field: impl Into<String>, message: impl Into<String>) -> &mut Self {
+        self.problems.push(ConfigProblem {
+            field: field.into(),
+            message: message.into(),
+        });
+        self
+    }
+
+    /// Checks `value` is a URL with a host and one of the given schemes.
+    pub fn url(&mut self, field: &str, value: &str, schemes: &[&str]) -> &mut Self {
+        let uri = match value.parse::<Uri>() {
+            Ok(uri) => uri,
+            Err(e) => return self.problem(field, format!("invalid url {value:?}: {e}")),
+        };
+        match uri.scheme_str() {
+            Some(scheme) if schemes.contains(&scheme) => {}
+            scheme => {
+                return self.problem(
+                    field,
+                    format!(
+                        "url {value:?} has scheme {scheme:?}, expected one of {}",
+                        schemes.join(", ")
+                    ),
+                )
+            }
+        }
+        if uri.host().unwrap_or_default().is_empty() {
+            self.problem(field, format!("url {value:?} has no host"));
+        }
+        self
+    }
+
+    /// Checks the file exists and holds a keypair.
+    pub fn keypair_file(&mut self, field: &str, path: &Path) -> &mut Self {
+        if let Err(e) = read_keypair_file(path) {
+            self.problem(field, format!("can't read keypair {}: {e}", path.display()));
+        }
+        self
+    }
+
+    pub fn range<T>(&mut self, field: &str, value: T, range: RangeInclusive<T>) -> &mut Self
+    where
+        T: PartialOrd + fmt::Display,
+    {
+        if !range.contains(&value) {
+            self.problem(
+                field,
+                format!("{value} is outside {}..={}", range.start(), range.end()),
+            );
+        }
+        self
+    }
+
+    pub fn regions(&mut self, field: &str, regions: &[String]) -> &mut Self {
+        for (i, region) in regions.iter().enumerate() {
+            if !KNOWN_REGIONS.contains(&region.as_str()) {
+                self.problem(
+                    format!("{field}[{i}]"),
+                    format!(
+                        "unknown region {region:?}, expected one of {}",
+                        KNOWN_REGIONS.join(", ")
+                    ),
+                );
+            }
+        }
+        self
+    }
+
+    pub fn not_empty<T>(&mut self, field: &str, values: &[T]) -> &mut Self {
+        if values.is_empty() {
+            self.problem(field, "at least one value is required");
+        }
+        self
+    }
+
+    pub fn finish(self) -> Result<(), ConfigErrors> {
+        if self.problems.is_empty() {
+            Ok(())
+        } else {
+            Err(ConfigErrors(self.problems))
+        }
+    }
+}
@@ -0,0 +1,187 @@
+use std::{
+    collections::VecDeque,
+    fmt::{self, Display, Formatter},
+    time::{Duration, Instant},
+};
+
+use jito_protos::bundle::{
+    bundle_result::Result as BundleResultType, rejected::Reason, BundleResult,
+};
+use log::{info, warn};
+use solana_metrics::datapoint_info;
+use tonic::{Code, Status};
+
+use crate::priority::PriorityClass;
+
+/// What a submission says about how contested the auction is.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AuctionSignal {
+    /// The bundle won its auction.
+    Won,
+    /// The bundle was outbid in the state or batch auction.
+    Outbid,
+    /// The block engine rate limited the bundle or was too busy to answer.
+    Overloaded,
+}
+
+impl AuctionSignal {
+    /// Classifies a result from the bundle results stream. Simulation failures and drops say
+    /// nothing about the auction, so they return None.
+    pub fn from_bundle_result(bundle_result: &BundleResult) -> Option<Self> {
+        match bundle_result.result.as_ref()? {
+            BundleResultType::Accepted(_)
+            | BundleResultType::Processed(_)
+            | BundleResultType::Finalized(_) => Some(Self::Won),
+            BundleResultType::Rejected(rejected) => match rejected.reason {
+                Some(Reason::StateAuctionBidRejected(_))
+                | Some(Reason::WinningBatchBidRejected(_)) => Some(Self::Outbid),
+                _ => None,
+            },
+            BundleResultType::Dropped(_) => None,
+        }
+    }
+
+    /// Classifies a failed `send_bundle`. Only rate limiting and timeouts count, other errors
+    /// are about the bundle or the connection.
+    pub fn from_send_error(status: &Status) -> Option<Self> {
+        match status.code() {
+            Code::ResourceExhausted | Code::DeadlineExceeded => Some(Self::Overloaded),
+            _ => None,
+        }
+    }
+}
+
+/// How far submissions are held back. Critical bundles are never held back.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub enum CongestionLevel {
+    #[default]
+    Clear,
+    /// Bulk bundles are held back.
+    Throttled,
+    /// Bulk and normal bundles are held back.
+    Paused,
+}
+
+impl Display for CongestionLevel {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            CongestionLevel::Clear => write!(f, "clear"),
+            CongestionLevel::Throttled => write!(f, "throttled"),
+            CongestionLevel::Paused => write!(f, "paused"),
+        }
+    }
+}
+
+/// Share of recent submissions that were outbid or rate limited at which each level starts.
+#[derive(Clone, Debug)]
+pub struct CongestionConfig {
+    /// Submissions older than this are forgotten.
+    pub window: Duration,
+    /// Below this many recent submissions the auction is assumed clear.
+    pub min_samples: usize,
+    pub throttle_rate: f64,
+    pub pause_rate: f64,
+    /// Levels only drop back to clear once the rate is at or below this.
+    pub resume_rate: f64,
+}
+
+/// Tracks the rejection rate of recent submissions and holds back low priority bundles while
+/// the auction looks congested, so the tip budget isn't spent on bids that mostly lose.
+///
+/// Held back bundles aren't sent, so while paused the rate is fed mostly by critical bundles.
+/// Once too few submissions are left in the window the monitor clears and lets everything
+/// through again, which probes whether the auction recovered.
+#[derive(Debug)]
+pub struct CongestionMonitor {
+    config: CongestionConfig,
+    /// When each submission was seen and whether it lost.
+    signals: VecDeque<(Instant, bool)>,
+    level: CongestionLevel,
+    held_back: u64,
+    level_changes: u64,
+}
+
+impl CongestionMonitor {
+    pub fn new(config: CongestionConfig) -> Self {
+        Self {
+            config,
+            signals: VecDeque::new(),
+            level: CongestionLevel::Clear,
+            held_back: 0,
+            level_changes: 0,
+        }
+    }
+
+    pub fn record(&mut self, signal: AuctionSignal, now: Instant) {
+        self.signals.push_back((now, signal != AuctionSignal::Won));
+        self.update(now);
+    }
+
+    /// Share of submissions within the window that lost, None if there are too few.
+    pub fn rejection_rate(&mut self, now: Instant) -> Option<f64> {
+        while let Some((at, _)) = self.signals.front() {
+            if now.saturating_duration_since(*at) <= self.config.window {
+                break;
+            }
+            self.signals.pop_front();
+        }
+        if self.signals.is_empty() || self.signals.len() < self.config.min_samples {
+            return None;
+        }
+        let lost = self.signals.iter().filter(|(_, lost)| *lost).count();
+        Some(lost as f64 / self.signals.len() as f64)
+    }
+
+    pub fn level(&self) -> CongestionLevel {
+        self.level
+    }
+
+    fn update(&mut self, now: Instant) {
+        let level = match self.rejection_rate(now) {
+            None => CongestionLevel::Clear,
+            Some(rate) if rate >= self.config.pause_rate => CongestionLevel::Paused,
+            Some(rate) if rate >= self.config.throttle_rate => CongestionLevel::Throttled,
+            Some(rate) if rate <= self.config.resume_rate => CongestionLevel::Clear,
+            // between resume and throttle, step down from paused but don't clear yet
+            Some(_) => self.level.min(CongestionLevel::Throttled),
+        };
+        if level != self.level {
+            if level > self.level {
+                warn!("auction congested, submissions {level}");
+            } else {
+                info!("auction congestion eased, submissions {level}");
+            }
+            self.level = level;
+            self.level_changes += 1;
+        }
+    }
+
+    /// Whether a bundle of `class` may be sent now, counting it as held back if not.
+    pub fn admits(&mut self, class: PriorityClass, now: Instant) -> bool {
+        self.update(now);
+        let admitted = match class {
+            PriorityClass::Critical => true,
+            PriorityClass::Normal => self.level < CongestionLevel::Paused,
+            PriorityClass::Bulk => self.level == CongestionLevel::Clear,
+        };
+        if !admitted {
+            self.held_back += 1;
+        }
+        admitted
+    }
+
+    /// Reports the level and counters and resets the counters.
+    pub fn report(&mut self, name: &'static str) {
+        let rate = self.rejection_rate(Instant::now());
+        datapoint_info!(
+            name,
+            ("level", self.level.to_string(), String),
+            ("rejection_rate", rate.unwrap_or_default(), f64),
+            ("samples", self.signals.len(), i64),
+            ("held_back", self.held_back, i64),
+            ("level_changes", self.level_changes, i64),
+        );
+        self.held_back = 0;
+        self.level_changes = 0;
+    }
+}
@@ -0,0 +1,364 @@
+use std::{
+    collections::HashMap,
+    fmt::{self, Display, Formatter, Write},
+    str::FromStr,
+};
+
+use chrono::{NaiveDate, Timelike};
+use solana_sdk::native_token::LAMPORTS_PER_SOL;
+
+use crate::{
+    audit_log::AuditedRejection,
+    bundle_store::{log_errors, BundleRecord, BundleStatus},
+};
+
+/// Rejection reasons listed in a report.
+const TOP_REASONS: usize = 5;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ReportFormat {
+    Terminal,
+    Markdown,
+    Html,
+}
+
+impl Display for ReportFormat {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            ReportFormat::Terminal => write!(f, "terminal"),
+            ReportFormat::Markdown => write!(f, "markdown"),
+            ReportFormat::Html => write!(f, "html"),
+        }
+    }
+}
+
+impl FromStr for ReportFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "terminal" => Ok(ReportFormat::Terminal),
+            "markdown" => Ok(ReportFormat::Markdown),
+            "html" => Ok(ReportFormat::Html),
+            _ => Err(format!(
+                "invalid report format {s:?}, expected terminal, markdown or html"
+            )),
+        }
+    }
+}
+
+/// Bundles submitted within an hour of the day, UTC.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct HourStats {
+    pub sent: usize,
+    pub landed: usize,
+    pub tip_lamports: u64,
+    pub net_lamports: i64,
+}
+
+/// A day of bundles: how many were sent and landed, what they cost and earned, why the rest
+/// didn't land and how each hour went. Built from the bundle store's records and the rejections
+/// in audit logs, the store should be resolved first so landed bundles have their results.
+#[derive(Clone, Debug)]
+pub struct DailyReport {
+    pub date: NaiveDate,
+    /// Bundles submitted, rejected ones included.
+    pub sent: usize,
+    /// Bundles the block engine didn't accept.
+    pub rejected: usize,
+    pub landed: usize,
+    /// Landed bundles that executed before any other non-vote transaction in their block.
+    pub top_of_block: usize,
+    /// On chain with the searcher's transaction failed.
+    pub failed: usize,
+    /// Accepted but never landed.
+    pub dropped: usize,
+    /// Not resolved yet.
+    pub pending: usize,
+    /// Tips of landed bundles.
+    pub tip_lamports: u64,
+    /// The searcher's balance change over the bundles found on chain, after fees and tips.
+    pub net_lamports: i64,
+    /// Why bundles didn't land, most common first.
+    pub rejection_reasons: Vec<(String, usize)>,
+    pub hours: [HourStats; 24],
+}
+
+impl DailyReport {
+    /// Aggregates the records and rejections submitted on `date`, UTC. Records without a
+    /// submission time, from audit logs written before it was read back, are left out.
+    pub fn build(
+        date: NaiveDate,
+        records: &[BundleRecord],
+        rejections: &[AuditedRejection],
+    ) -> Self {
+        let mut report = Self {
+            date,
+            sent: 0,
+            rejected: 0,
+            landed: 0,
+            top_of_block: 0,
+            failed: 0,
+            dropped: 0,
+            pending: 0,
+            tip_lamports: 0,
+            net_lamports: 0,
+            rejection_reasons: vec![],
+            hours: [HourStats::default(); 24],
+        };
+        let mut reasons: HashMap<String, usize> = HashMap::new();
+
+        for record in records {
+            let Some(submitted_at) = record.submitted_at.filter(|at| at.date_naive() == date)
+            else {
+                continue;
+            };
+            let hour = &mut report.hours[submitted_at.hour() as usize];
+            report.sent += 1;
+            hour.sent += 1;
+            let net = record.net_lamports().unwrap_or_default();
+            report.net_lamports += net;
+            hour.net_lamports += net;
+            match record.status {
+                BundleStatus::Landed => {
+                    report.landed += 1;
+                    if record.top_of_block() == Some(true) {
+                        report.top_of_block += 1;
+                    }
+                    report.tip_lamports += record.tip_lamports;
+                    hour.landed += 1;
+                    hour.tip_lamports += record.tip_lamports;
+                }
+                BundleStatus::Failed => {
+                    report.failed += 1;
+                    *reasons.entry(failure_reason(record)).or_default() += 1;
+                }
+                BundleStatus::Dropped => {
+                    report.dropped += 1;
+                    *reasons
+                        .entry("dropped without landing".to_string())
+                        .or_default() += 1;
+                }
+                BundleStatus::Pending => report.pending += 1,
+            }
+        }
+        for rejection in rejections {
+            let Some(submitted_at) = rejection.submitted_at.filter(|at| at.date_naive() == date)
+            else {
+                continue;
+            };
+            report.sent += 1;
+            report.rejected += 1;
+            report.hours[submitted_at.hour() as usize].sent += 1;
+            let reason = match &rejection.reason {
+                Some(reason) => format!("rejected: {reason}"),
+                None => "rejected".to_string(),
+            };
+            *reasons.entry(reason).or_default() += 1;
+        }
+
+        let mut reasons: Vec<_> = reasons.into_iter().collect();
+        reasons.sort_by(|(a, a_count), (b, b_count)| b_count.cmp(a_count).then(a.cmp(b)));
+        reasons.truncate(TOP_REASONS);
+        report.rejection_reasons = reasons;
+        report
+    }
+
+    /// The hour with bundles sent that earned the most, and the one that earned the least.
+    pub fn best_and_worst_hours(&self) -> Option<(usize, usize)> {
+        let active = || (0..24).filter(|hour| self.hours[*hour].sent > 0);
+        let best = active().max_by_key(|hour| self.hours[*hour].net_lamports)?;
+        let worst = active().min_by_key(|hour| self.hours[*hour].net_lamports)?;
+        Some((best, worst))
+    }
+
+    pub fn render(&self, format: ReportFormat) -> String {
+        match format {
+            ReportFormat::Terminal => self.render_terminal(),
+            ReportFormat::Markdown => self.render_markdown(),
+            ReportFormat::Html => self.render_html(),
+        }
+    }
+
+    fn summary(&self) -> Vec<(&'static str, String)> {
+        let landing_rate = match self.sent {
+            0 => 0.0,
+            sent => self.landed as f64 / sent as f64 * 100.0,
+        };
+        let mut summary = vec![
+            ("Bundles sent", self.sent.to_string()),
+            ("Landed", format!("{} ({landing_rate:.1}%)", self.landed)),
+            ("Top of block", self.top_of_block.to_string()),
+            ("Failed on chain", self.failed.to_string()),
+            ("Dropped", self.dropped.to_string()),
+            ("Rejected", self.rejected.to_string()),
+            ("Pending", self.pending.to_string()),
+            (
+                "Tips spent",
+                format!("{} SOL", sol(self.tip_lamports as i64)),
+            ),
+            ("PnL", format!("{} SOL", sol(self.net_lamports))),
+        ];
+        if let Some((best, worst)) = self.best_and_worst_hours() {
+            let hour = |hour: usize| {
+                format!(
+                    "{hour:02}:00 UTC, {} SOL over {} bundles",
+                    sol(self.hours[hour].net_lamports),
+                    self.hours[hour].sent
+                )
+            };
+            summary.push(("Best hour", hour(best)));
+            summary.push(("Worst hour", hour(worst)));
+        }
+        summary
+    }
+
+    fn render_terminal(&self) -> String {
+        let mut out = String::new();
+        let _ = writeln!(out, "Daily report for {}", self.date);
+        for (label, value) in self.summary() {
+            let _ = writeln!(out, "  {label:<16} {value}");
+        }
+        let _ = writeln!(out, "\nTop rejection reasons");
+        if self.rejection_reasons.is_empty() {
+            let _ = writeln!(out, "  none");
+        }
+        for (reason, count) in &self.rejection_reasons {
+            let _ = writeln!(out, "  {count:>6}  {reason}");
+        }
+        let _ = writeln!(out, "\nHours (UTC)");
+        let _ = writeln!(
+            out,
+            "  {:<6} {:>8} {:>8} {:>14} {:>14}",
+            "hour", "sent", "landed", "tips (SOL)", "pnl (SOL)"
+        );
+        for (hour, stats) in self.active_hours() {
+            let _ = writeln!(
+                out,
+                "  {:<6} {:>8} {:>8} {:>14} {:>14}",
+                format!("{hour:02}:00"),
+                stats.sent,
+                stats.landed,
+                sol(stats.tip_lamports as i64),
+                sol(stats.net_lamports),
+            );
+        }
+        out
+    }
+
+    fn render_markdown(&self) -> String {
+        let mut out = String::new();
+        let _ = writeln!(out, "# Daily report for {}\n", self.date);
+        let _ = writeln!(out, "| | |\n|---|---|");
+        for (label, value) in self.summary() {
+            let _ = writeln!(out, "| {label} | {value} |");
+        }
+        let _ = writeln!(out, "\n## Top rejection reasons\n");
+        if self.rejection_reasons.is_empty() {
+            let _ = writeln!(out, "None.");
+        } else {
+            let _ = writeln!(out, "| Bundles | Reason |\n|---:|---|");
+            for (reason, count) in &self.rejection_reasons {
+                let _ = writeln!(out, "| {count} | {} |", reason.replace('|', "\\|"));
+            }
+        }
+        let _ = writeln!(out, "\n## Hours (UTC)\n");
+        let _ = writeln!(
+            out,
+            "| Hour | Sent | Landed | Tips (SOL) | PnL (SOL) |\n|---|---:|---:|---:|---:|"
+        );
+        for (hour, stats) in self.active_hours() {
+            let _ = writeln!(
+                out,
+                "| {hour:02}:00 | {} | {} | {} | {} |",
+                stats.sent,
+                stats.landed,
+                sol(stats.tip_lamports as i64),
+                sol(stats.net_lamports),
+            );
+        }
+        out
+    }
+
+    fn render_html(&self) -> String {
+        let mut out = String::new();
+        let title = format!("Daily report for {}", self.date);
+        let _ = writeln!(
+            out,
+            "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{title}</title>\n\
+             <style>body {{ font-family: sans-serif; }} table {{ border-collapse: collapse; }} \
+             td, th {{ border: 1px solid #ccc; padding: 4px 8px; }} \
+             td.num {{ text-align: right; }}</style>\n</head>\n<body>\n<h1>{title}</h1>"
+        );
+        let _ = writeln!(out, "<table>");
+        for (label, value) in self.summary() {
+            let _ = writeln!(
+                out,
+                "<tr><th>{label}</th><td>{}</td></tr>",
+                escape_html(&value)
+            );
+        }
+        let _ = writeln!(out, "</table>\n<h2>Top rejection reasons</h2>");
+        if self.rejection_reasons.is_empty() {
+            let _ = writeln!(out, "<p>None.</p>");
+        } else {
+            let _ = writeln!(out, "<table>\n<tr><th>Bundles</th><th>Reason</th></tr>");
+            for (reason, count) in &self.rejection_reasons {
+                let _ = writeln!(
+                    out,
+                    "<tr><td class=\"num\">{count}</td><td>{}</td></tr>",
+                    escape_html(reason)
+                );
+            }
+            let _ = writeln!(out, "</table>");
+        }
+        let _ = writeln!(
+            out,
+            "<h2>Hours (UTC)</h2>\n<table>\n<tr><th>Hour</th><th>Sent</th><th>Landed</th>\
+             <th>Tips (SOL)</th><th>PnL (SOL)</th></tr>"
+        );
+        for (hour, stats) in self.active_hours() {
+            let _ = writeln!(
+                out,
+                "<tr><td>{hour:02}:00</td><td class=\"num\">{}</td><td class=\"num\">{}</td>\
+                 <td class=\"num\">{}</td><td class=\"num\">{}</td></tr>",
+                stats.sent,
+                stats.landed,
+                sol(stats.tip_lamports as i64),
+                sol(stats.net_lamports),
+            );
+        }
+        let _ = writeln!(out, "</table>\n</body>\n</html>");
+        out
+    }
+
+    fn active_hours(&self) -> impl Iterator<Item = (usize, &HourStats)> {
+        self.hours
+            .iter()
+            .enumerate()
+            .filter(|(_, stats)| stats.sent > 0)
+    }
+}
+
+/// Why a bundle failed on chain: the first error its logs show, or its transaction's error.
+fn failure_reason(record: &BundleRecord) -> String {
+    let log_error = record.transactions.iter().find_map(|tx| {
+        log_errors(tx.logs.as_deref().unwrap_or_default())
+            .into_iter()
+            .next()
+    });
+    log_error
+        .or_else(|| record.transactions.iter().find_map(|tx| tx.error.clone()))
+        .unwrap_or_else(|| "failed".to_string())
+}
+
+fn sol(lamports: i64) -> String {
+    format!("{:.6}", lamports as f64 / LAMPORTS_PER_SOL as f64)
+}
+
+fn escape_html(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
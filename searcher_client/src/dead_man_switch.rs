@@ -0,0 +1,361 @@
+use std::{
+    collections::VecDeque,
+    fs, io,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use jito_protos::bundle::{
+    bundle_result::Result as BundleResultType, rejected::Reason, BundleResult,
+};
+use log::{error, warn};
+use serde::{Deserialize, Serialize};
+use solana_metrics::{datapoint_info, datapoint_warn};
+use thiserror::Error;
+
+/// Reason submissions were halted.
+#[derive(Clone, Debug, Error, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Anomaly {
+    #[error("{0} consecutive bundle rejections")]
+    ConsecutiveRejections(usize),
+    #[error("{0} consecutive simulation failures")]
+    ConsecutiveSimulationFailures(usize),
+    #[error("payer balance dropped {lamports} lamports within {window:?}")]
+    BalanceDrain { lamports: u64, window: Duration },
+}
+
+/// Thresholds that trip the switch, None disables a check.
+#[derive(Clone, Debug)]
+pub struct DeadManSwitchConfig {
+    pub max_consecutive_rejections: Option<usize>,
+    pub max_consecutive_simulation_failures: Option<usize>,
+    /// Maximum lamports the payer balance may drop within `balance_window`.
+    pub max_balance_drop_lamports: Option<u64>,
+    pub balance_window: Duration,
+}
+
+/// What happened to a submitted bundle, as far as the switch is concerned.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SubmissionOutcome {
+    Accepted,
+    Rejected,
+    /// A rejection caused by the bundle failing simulation.
+    SimulationFailure,
+}
+
+impl SubmissionOutcome {
+    /// Classifies a result from the bundle results stream. Dropped bundles were accepted by the
+    /// block engine and say nothing about the bot's health, so they return None.
+    pub fn from_bundle_result(bundle_result: &BundleResult) -> Option<Self> {
+        match bundle_result.result.as_ref()? {
+            BundleResultType::Accepted(_)
+            | BundleResultType::Processed(_)
+            | BundleResultType::Finalized(_) => Some(Self::Accepted),
+            BundleResultType::Rejected(rejected) => match rejected.reason {
+                Some(Reason::SimulationFailure(_)) => Some(Self::SimulationFailure),
+                _ => Some(Self::Rejected),
+            },
+            BundleResultType::Dropped(_) => None,
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct State {
+    halted: Option<Anomaly>,
+    consecutive_rejections: usize,
+    consecutive_simulation_failures: usize,
+    balances: VecDeque<(Instant, u64)>,
+    blocked_bundles: u64,
+}
+
+/// Stops all submissions once an anomaly is detected. The switch stays tripped until an operator
+/// acknowledges it, a run of good results doesn't resume sending on its own, and with a halt file
+/// neither does a restart. Clones share state, so one can be handed to whatever receives the
+/// operator's ack.
+#[derive(Clone, Debug)]
+pub struct DeadManSwitch {
+    config: DeadManSwitchConfig,
+    state: Arc<Mutex<State>>,
+    halt_file: Option<PathBuf>,
+}
+
+impl DeadManSwitch {
+    pub fn new(config: DeadManSwitchConfig) -> Self {
+        Self {
+            config,
+            state: Arc::default(),
+            halt_file: None,
+        }
+    }
+
+    /// Writes the anomaly to `path` when the switch trips and removes it on the operator's ack,
+    /// starting out halted if the file is there from before a restart.
+    pub fn with_halt_file(mut self, path: PathBuf) -> io::Result<Self> {
+        match fs::read(&path) {
+            Ok(bytes) => {
+                let anomaly: Anomaly = serde_json::from_slice(&bytes)
+                    .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+                error!(
+                    "dead man switch tripped before the restart, halting submissions until acknowledged: {anomaly}"
+                );
+                self.state.lock().unwrap().halted = Some(anomaly);
+            }
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+            Err(e) => return Err(e),
+        }
+        self.halt_file = Some(path);
+        Ok(self)
+    }
+
+    /// The anomaly submissions are halted for, if any.
+    pub fn halted(&self) -> Option<Anomaly> {
+        self.state.lock().unwrap().halted.clone()
+    }
+
+    /// Returns an error if submissions are halted, counting `bundles` as blocked.
+    pub fn check(&self, bundles: usize) -> Result<(), Anomaly> {
+        let mut state = self.state.lock().unwrap();
+        match &state.halted {
+            Some(anomaly) => {
+                let anomaly = anomaly.clone();
+                state.blocked_bundles += bundles as u64;
+                Err(anomaly)
+            }
+            None => Ok(()),
+        }
+    }
+
+    pub fn record_outcome(&self, outcome: SubmissionOutcome) {
+        let mut state = self.state.lock().unwrap();
+        match outcome {
+            SubmissionOutcome::Accepted => {
+                state.consecutive_rejections = 0;
+                state.consecutive_simulation_failures = 0;
+            }
+            SubmissionOutcome::Rejected => {
+                state.consecutive_rejections += 1;
+            }
+            SubmissionOutcome::SimulationFailure => {
+                state.consecutive_rejections += 1;
+                state.consecutive_simulation_failures += 1;
+            }
+        }
+
+        if let Some(max) = self.config.max_consecutive_simulation_failures {
+            if state.consecutive_simulation_failures >= max {
+                let anomaly =
+                    Anomaly::ConsecutiveSimulationFailures(state.consecutive_simulation_failures);
+                self.trip(&mut state, anomaly);
+            }
+        }
+        if let Some(max) = self.config.max_consecutive_rejections {
+            if state.consecutive_rejections >= max {
+                let anomaly = Anomaly::ConsecutiveRejections(state.consecutive_rejections);
+                self.trip(&mut state, anomaly);
+            }
+        }
+    }
+
+    /// True if the payer balance should be fed in through `record_balance`.
+    pub fn watches_balance(&self) -> bool {
+        self.config.max_balance_drop_lamports.is_some()
+    }
+
+    /// Records the payer balance, tripping the switch if it dropped by more than allowed since
+    /// the highest balance seen within the window.
+    pub fn record_balance(&self, lamports: u64, now: Instant) {
+        let Some(max_drop) = self.config.max_balance_drop_lamports else {
+            return;
+        };
+        let mut state = self.state.lock().unwrap();
+        state.balances.push_back((now, lamports));
+        while let Some((at, _)) = state.balances.front() {
+            if now.duration_since(*at) <= self.config.balance_window {
+                break;
+            }
+            state.balances.pop_front();
+        }
+
+        let peak = state
+            .balances
+            .iter()
+            .map(|(_, lamports)| *lamports)
+            .max()
+            .unwrap_or(lamports);
+        let dropped = peak.saturating_sub(lamports);
+        if dropped > max_drop {
+            let anomaly = Anomaly::BalanceDrain {
+                lamports: dropped,
+                window: self.config.balance_window,
+            };
+            self.trip(&mut state, anomaly);
+        }
+    }
+
+    /// Operator ack: clears the halt along with the counters and balance history that caused it,
+    /// and removes the halt file. Returns the anomaly that was cleared.
+    pub fn acknowledge(&self) -> Option<Anomaly> {
+        let mut state = self.state.lock().unwrap();
+        if let Some(path) = &self.halt_file {
+            match fs::remove_file(path) {
+                Err(e) if e.kind() != io::ErrorKind::NotFound => {
+                    error!("failed to remove halt file {}: {e}", path.display());
+                }
+                _ => {}
+            }
+        }
+        let cleared = state.halted.take();
+        state.consecutive_rejections = 0;
+        state.consecutive_simulation_failures = 0;
+        state.balances.clear();
+        match &cleared {
+            Some(anomaly) => {
+                warn!("dead man switch acknowledged, resuming submissions after: {anomaly}");
+                datapoint_warn!(
+                    "dead_man_switch_acknowledged",
+                    ("reason", anomaly.to_string(), String),
+                );
+            }
+            None => warn!("dead man switch acknowledged while not halted"),
+        }
+        cleared
+    }
+
+    /// Emits the switch state and resets the blocked bundle counter.
+    pub fn report(&self, name: &'static str) {
+        let mut state = self.state.lock().unwrap();
+        datapoint_info!(
+            name,
+            ("halted", state.halted.is_some(), bool),
+            ("consecutive_rejections", state.consecutive_rejections, i64),
+            (
+                "consecutive_simulation_failures",
+                state.consecutive_simulation_failures,
+                i64
+            ),
+            ("blocked_bundles", state.blocked_bundles, i64),
+        );
+        state.blocked_bundles = 0;
+    }
+
+    fn trip(&self, state: &mut State, anomaly: Anomaly) {
+        if state.halted.is_some() {
+            return;
+        }
+        error!("dead man switch tripped, halting submissions: {anomaly}");
+        datapoint_warn!(
+            "dead_man_switch_tripped",
+            ("reason", anomaly.to_string(), String),
+        );
+        if let Some(path) = &self.halt_file {
+            if let Err(e) = write_halt_file(path, &anomaly) {
+                error!(
+                    "failed to write halt file {}, a restart will resume submissions: {e}",
+                    path.display()
+                );
+            }
+        }
+        state.halted = Some(anomaly);
+    }
+}
+
+/// Replaces the halt file only once the new one is fully written.
+fn write_halt_file(path: &Path, anomaly: &Anomaly) -> io::Result<()> {
+    let mut tmp_path = path.as_os_str().to_owned();
+    tmp_path.push(".tmp");
+    fs::write(&tmp_path, serde_json::to_vec(anomaly)?)?;
+    fs::rename(&tmp_path, path)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        fs,
+        time::{Duration, Instant},
+    };
+
+    use crate::dead_man_switch::{Anomaly, DeadManSwitch, DeadManSwitchConfig, SubmissionOutcome};
+
+    fn config() -> DeadManSwitchConfig {
+        DeadManSwitchConfig {
+            max_consecutive_rejections: Some(3),
+            max_consecutive_simulation_failures: Some(2),
+            max_balance_drop_lamports: Some(1_000),
+            balance_window: Duration::from_secs(60),
+        }
+    }
+
+    #[test]
+    fn test_trip_and_acknowledge() {
+        let switch = DeadManSwitch::new(config());
+        switch.record_outcome(SubmissionOutcome::Rejected);
+        switch.record_outcome(SubmissionOutcome::Rejected);
+        switch.record_outcome(SubmissionOutcome::Accepted);
+        switch.record_outcome(SubmissionOutcome::Rejected);
+        switch.record_outcome(SubmissionOutcome::SimulationFailure);
+        assert_eq!(switch.check(1), Ok(()));
+
+        switch.record_outcome(SubmissionOutcome::SimulationFailure);
+        assert_eq!(
+            switch.check(1),
+            Err(Anomaly::ConsecutiveSimulationFailures(2))
+        );
+        // good results don't clear the halt
+        switch.record_outcome(SubmissionOutcome::Accepted);
+        assert!(switch.halted().is_some());
+
+        assert_eq!(
+            switch.acknowledge(),
+            Some(Anomaly::ConsecutiveSimulationFailures(2))
+        );
+        assert_eq!(switch.check(1), Ok(()));
+    }
+
+    #[test]
+    fn test_balance_drain() {
+        let switch = DeadManSwitch::new(config());
+        let start = Instant::now();
+        switch.record_balance(10_000, start);
+        switch.record_balance(9_500, start + Duration::from_secs(30));
+        assert_eq!(switch.halted(), None);
+        // the peak it dropped from has left the window
+        switch.record_balance(8_800, start + Duration::from_secs(70));
+        assert_eq!(switch.halted(), None);
+        switch.record_balance(8_000, start + Duration::from_secs(80));
+        assert_eq!(
+            switch.halted(),
+            Some(Anomaly::BalanceDrain {
+                lamports: 1_500,
+                window: Duration::from_secs(60)
+            })
+        );
+    }
+
+    #[test]
+    fn test_halt_file_survives_restart() {
+        let path = std::env::temp_dir().join(format!("halt_{}.json", std::process::id()));
+        let _ = fs::remove_file(&path);
+
+        let switch = DeadManSwitch::new(config())
+            .with_halt_file(path.clone())
+            .unwrap();
+        for _ in 0..3 {
+            switch.record_outcome(SubmissionOutcome::Rejected);
+        }
+        assert!(path.exists());
+
+        let restarted = DeadManSwitch::new(config())
+            .with_halt_file(path.clone())
+            .unwrap();
+        assert_eq!(restarted.check(1), Err(Anomaly::ConsecutiveRejections(3)));
+        restarted.acknowledge();
+        assert!(!path.exists());
+
+        let restarted = DeadManSwitch::new(config())
+            .with_halt_file(path.clone())
+            .unwrap();
+        assert_eq!(restarted.check(1), Ok(()));
+    }
+}
@@ -0,0 +1,405 @@
+use std::{
+    collections::HashMap,
+    fmt::Write as _,
+    path::{Path, PathBuf},
+    slice,
+    str::FromStr,
+    sync::Arc,
+};
+
+use libloading::{Library, Symbol};
+use serde_json::Value;
+use solana_sdk::{pubkey::Pubkey, transaction::VersionedTransaction};
+use thiserror::Error;
+
+use crate::idl_decoder::{DecodedAccount, IdlError, IdlRegistry};
+
+/// Version of the C ABI below a dynamic library plugin must report.
+pub const DECODER_ABI_VERSION: u32 = 1;
+
+/// Plugin return code: the output buffer holds the decoded JSON.
+const DECODED: i32 = 0;
+/// Plugin return code: not an instruction or account the plugin knows.
+const NOT_RECOGNIZED: i32 = 1;
+
+// instruction accounts are passed to plugins as packed 32 byte keys
+const _: () = assert!(std::mem::size_of::<Pubkey>() == 32);
+
+#[derive(Debug, Error)]
+pub enum DecoderPluginError {
+    #[error("failed to load plugin: {0}")]
+    Load(String),
+    #[error("plugin ABI version {0}, expected {DECODER_ABI_VERSION}")]
+    AbiVersion(u32),
+    #[error("plugin failed with code {0}")]
+    Plugin(i32),
+    #[error("wasm plugin failed: {0}")]
+    Wasm(String),
+    #[error("plugin returned invalid JSON: {0}")]
+    InvalidJson(#[from] serde_json::Error),
+    #[error("{0}")]
+    Idl(#[from] IdlError),
+}
+
+/// Decodes the instructions and accounts of one program into JSON, for programs without an
+/// Anchor IDL. Implemented in-process, or loaded as a plugin with [`DecoderRegistry::load`].
+pub trait ProgramDecoder: Send + Sync {
+    /// None if `data` isn't an instruction this decoder knows.
+    fn decode_instruction(
+        &self,
+        data: &[u8],
+        accounts: &[Pubkey],
+    ) -> Result<Option<Value>, DecoderPluginError>;
+
+    /// None if `data` isn't an account this decoder knows.
+    fn decode_account(&self, data: &[u8]) -> Result<Option<Value>, DecoderPluginError>;
+}
+
+type AbiVersionFn = unsafe extern "C" fn() -> u32;
+type DecodeInstructionFn = unsafe extern "C" fn(
+    data: *const u8,
+    data_len: usize,
+    accounts: *const u8,
+    num_accounts: usize,
+    out: *mut *mut u8,
+    out_len: *mut usize,
+) -> i32;
+type DecodeAccountFn = unsafe extern "C" fn(
+    data: *const u8,
+    data_len: usize,
+    out: *mut *mut u8,
+    out_len: *mut usize,
+) -> i32;
+type FreeFn = unsafe extern "C" fn(out: *mut u8, out_len: usize);
+
+/// A decoder in a shared library exporting, with C linkage:
+///
+/// ```c
+/// uint32_t jito_decoder_abi_version(void);
+/// // accounts are num_accounts 32 byte keys back to back
+/// int32_t jito_decode_instruction(const uint8_t *data, size_t data_len, const uint8_t *accounts,
+///                                 size_t num_accounts, uint8_t **out, size_t *out_len);
+/// int32_t jito_decode_account(const uint8_t *data, size_t data_len, uint8_t **out,
+///                             size_t *out_len);
+/// void jito_decoder_free(uint8_t *out, size_t out_len);
+/// ```
+///
+/// The decode functions return 0 with UTF-8 JSON in `out`, which is handed back to
+/// `jito_decoder_free`, 1 if they don't recognize the data, anything else on failure.
+pub struct DynamicLibraryDecoder {
+    // the symbols below point into the library, it must outlive them
+    _library: Library,
+    decode_instruction: DecodeInstructionFn,
+    decode_account: DecodeAccountFn,
+    free: FreeFn,
+}
+
+impl DynamicLibraryDecoder {
+    /// Loads the library at `path`.
+    ///
+    /// # Safety
+    /// Loading runs the library's initializers and calls into it run arbitrary code: only load
+    /// plugins you trust, built against this ABI.
+    pub unsafe fn load(path: &Path) -> Result<Self, DecoderPluginError> {
+        let load_error = |e: libloading::Error| DecoderPluginError::Load(e.to_string());
+        let library = Library::new(path).map_err(load_error)?;
+        let abi_version: Symbol<AbiVersionFn> = library
+            .get(b"jito_decoder_abi_version\0")
+            .map_err(load_error)?;
+        let version = abi_version();
+        if version != DECODER_ABI_VERSION {
+            return Err(DecoderPluginError::AbiVersion(version));
+        }
+        let decode_instruction = *library
+            .get::<DecodeInstructionFn>(b"jito_decode_instruction\0")
+            .map_err(load_error)?;
+        let decode_account = *library
+            .get::<DecodeAccountFn>(b"jito_decode_account\0")
+            .map_err(load_error)?;
+        let free = *library
+            .get::<FreeFn>(b"jito_decoder_free\0")
+            .map_err(load_error)?;
+        Ok(Self {
+            _library: library,
+            decode_instruction,
+            decode_account,
+            free,
+        })
+    }
+
+    fn output(
+        &self,
+        code: i32,
+        out: *mut u8,
+        out_len: usize,
+    ) -> Result<Option<Value>, DecoderPluginError> {
+        match code {
+            DECODED => {
+                let json = if out.is_null() {
+                    &[][..]
+                } else {
+                    // SAFETY: the plugin handed us out_len bytes at out until we free them
+                    unsafe { slice::from_raw_parts(out, out_len) }
+                };
+                let value = serde_json::from_slice(json);
+                if !out.is_null() {
+                    // SAFETY: out came from this plugin and isn't used after this
+                    unsafe { (self.free)(out, out_len) };
+                }
+                Ok(Some(value?))
+            }
+            NOT_RECOGNIZED => Ok(None),
+            code => Err(DecoderPluginError::Plugin(code)),
+        }
+    }
+}
+
+impl ProgramDecoder for DynamicLibraryDecoder {
+    fn decode_instruction(
+        &self,
+        data: &[u8],
+        accounts: &[Pubkey],
+    ) -> Result<Option<Value>, DecoderPluginError> {
+        let mut out = std::ptr::null_mut();
+        let mut out_len = 0;
+        // SAFETY: Pubkey is a transparent [u8; 32], the slices outlive the call
+        let code = unsafe {
+            (self.decode_instruction)(
+                data.as_ptr(),
+                data.len(),
+                accounts.as_ptr() as *const u8,
+                accounts.len(),
+                &mut out,
+                &mut out_len,
+            )
+        };
+        self.output(code, out, out_len)
+    }
+
+    fn decode_account(&self, data: &[u8]) -> Result<Option<Value>, DecoderPluginError> {
+        let mut out = std::ptr::null_mut();
+        let mut out_len = 0;
+        // SAFETY: the slice outlives the call
+        let code =
+            unsafe { (self.decode_account)(data.as_ptr(), data.len(), &mut out, &mut out_len) };
+        self.output(code, out, out_len)
+    }
+}
+
+// SAFETY: plugins must be thread safe, the ABI has no per-thread state
+unsafe impl Send for DynamicLibraryDecoder {}
+unsafe impl Sync for DynamicLibraryDecoder {}
+
+/// A decoder compiled to WASM, see [`WasmPlugin`](crate::wasm_plugin::WasmPlugin) for the
+/// calling convention. Exports `decode_instruction(data, accounts)`, with accounts as 32 byte
+/// keys back to back, and `decode_account(data)`, both returning JSON or nothing.
+#[cfg(feature = "wasm")]
+pub struct WasmDecoder {
+    plugin: std::sync::Mutex<crate::wasm_plugin::WasmPlugin>,
+}
+
+#[cfg(feature = "wasm")]
+impl WasmDecoder {
+    pub fn load(path: &Path) -> Result<Self, DecoderPluginError> {
+        let plugin = crate::wasm_plugin::WasmPlugin::load(path)
+            .map_err(|e| DecoderPluginError::Load(e.to_string()))?;
+        Ok(Self {
+            plugin: std::sync::Mutex::new(plugin),
+        })
+    }
+
+    fn call(
+        &self,
+        export: &'static str,
+        inputs: &[&[u8]],
+    ) -> Result<Option<Value>, DecoderPluginError> {
+        let output = self
+            .plugin
+            .lock()
+            .unwrap()
+            .call(export, inputs)
+            .map_err(|e| DecoderPluginError::Wasm(e.to_string()))?;
+        Ok(output
+            .map(|json| serde_json::from_slice(&json))
+            .transpose()?)
+    }
+}
+
+#[cfg(feature = "wasm")]
+impl ProgramDecoder for WasmDecoder {
+    fn decode_instruction(
+        &self,
+        data: &[u8],
+        accounts: &[Pubkey],
+    ) -> Result<Option<Value>, DecoderPluginError> {
+        let accounts: Vec<u8> = accounts.iter().flat_map(|key| key.to_bytes()).collect();
+        self.call("decode_instruction", &[data, &accounts])
+    }
+
+    fn decode_account(&self, data: &[u8]) -> Result<Option<Value>, DecoderPluginError> {
+        self.call("decode_account", &[data])
+    }
+}
+
+/// A plugin to load for a program, parsed from `<PROGRAM_ID>=<PATH>`.
+#[derive(Clone, Debug)]
+pub struct PluginSpec {
+    pub program_id: Pubkey,
+    pub path: PathBuf,
+}
+
+impl FromStr for PluginSpec {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (program_id, path) = s
+            .split_once('=')
+            .ok_or_else(|| format!("expected <PROGRAM_ID>=<PATH>, got {s:?}"))?;
+        Ok(Self {
+            program_id: Pubkey::from_str(program_id)
+                .map_err(|e| format!("invalid program id {program_id:?}: {e}"))?,
+            path: path.into(),
+        })
+    }
+}
+
+/// Decoders by program: registered plugins first, then Anchor IDLs.
+#[derive(Default)]
+pub struct DecoderRegistry {
+    plugins: HashMap<Pubkey, Arc<dyn ProgramDecoder>>,
+    idls: IdlRegistry,
+}
+
+impl DecoderRegistry {
+    pub fn new(idls: IdlRegistry) -> Self {
+        Self {
+            plugins: HashMap::new(),
+            idls,
+        }
+    }
+
+    pub fn register(&mut self, program_id: Pubkey, decoder: Arc<dyn ProgramDecoder>) {
+        self.plugins.insert(program_id, decoder);
+    }
+
+    /// Loads the plugin at `path` for `program_id`: WASM if it ends in `.wasm`, a shared
+    /// library otherwise.
+    ///
+    /// # Safety
+    /// See [`DynamicLibraryDecoder::load`].
+    pub unsafe fn load(
+        &mut self,
+        program_id: Pubkey,
+        path: &Path,
+    ) -> Result<(), DecoderPluginError> {
+        let decoder: Arc<dyn ProgramDecoder> = if path.extension().is_some_and(|e| e == "wasm") {
+            #[cfg(feature = "wasm")]
+            {
+                Arc::new(WasmDecoder::load(path)?)
+            }
+            #[cfg(not(feature = "wasm"))]
+            {
+                return Err(DecoderPluginError::Load(
+                    "built without the wasm feature".into(),
+                ));
+            }
+        } else {
+            Arc::new(DynamicLibraryDecoder::load(path)?)
+        };
+        self.register(program_id, decoder);
+        Ok(())
+    }
+
+    pub fn idls(&self) -> &IdlRegistry {
+        &self.idls
+    }
+
+    pub fn idls_mut(&mut self) -> &mut IdlRegistry {
+        &mut self.idls
+    }
+
+    /// Whether instructions and accounts of `program_id` can be decoded.
+    pub fn contains(&self, program_id: &Pubkey) -> bool {
+        self.plugins.contains_key(program_id) || self.idls.contains(program_id)
+    }
+
+    /// Decodes an instruction into JSON. None if no decoder knows it.
+    pub fn decode_instruction(
+        &self,
+        program_id: &Pubkey,
+        data: &[u8],
+        accounts: &[Pubkey],
+    ) -> Option<Result<Value, DecoderPluginError>> {
+        if let Some(plugin) = self.plugins.get(program_id) {
+            return plugin.decode_instruction(data, accounts).transpose();
+        }
+        let decoded = self.idls.decode_instruction(program_id, data, accounts)?;
+        Some(
+            decoded
+                .map(|decoded| {
+                    serde_json::json!({
+                        "instruction": decoded.name,
+                        "accounts": decoded.accounts
+                            .iter()
+                            .map(|(name, key)| (name.clone(), Value::String(key.to_string())))
+                            .collect::<serde_json::Map<_, _>>(),
+                        "args": decoded.args,
+                    })
+                })
+                .map_err(DecoderPluginError::from),
+        )
+    }
+
+    /// Decodes account data owned by `owner` into JSON. None if no decoder knows it.
+    pub fn decode_account(
+        &self,
+        owner: &Pubkey,
+        data: &[u8],
+    ) -> Option<Result<Value, DecoderPluginError>> {
+        if let Some(plugin) = self.plugins.get(owner) {
+            return plugin.decode_account(data).transpose();
+        }
+        let decoded = self.idls.decode_account(owner, data)?;
+        Some(
+            decoded
+                .map(|DecodedAccount { name, fields, .. }| {
+                    serde_json::json!({ "account": name, "fields": fields })
+                })
+                .map_err(DecoderPluginError::from),
+        )
+    }
+
+    /// Like [`IdlRegistry::describe_transaction`], with plugin output for programs that have
+    /// one.
+    pub fn describe_transaction(&self, tx: &VersionedTransaction) -> String {
+        let keys = tx.message.static_account_keys();
+        let mut out = String::new();
+        for (index, ix) in tx.message.instructions().iter().enumerate() {
+            let Some(program_id) = keys.get(ix.program_id_index as usize) else {
+                continue;
+            };
+            if !self.plugins.contains_key(program_id) {
+                if let Some(described) = self.idls.describe_instruction(tx, index) {
+                    out.push_str(&described);
+                }
+                continue;
+            }
+            let accounts: Vec<Pubkey> = ix
+                .accounts
+                .iter()
+                .map(|index| keys.get(*index as usize).copied().unwrap_or_default())
+                .collect();
+            match self.decode_instruction(program_id, &ix.data, &accounts) {
+                Some(Ok(decoded)) => {
+                    let _ = writeln!(out, "  [{index}] {program_id} (plugin)");
+                    let _ = writeln!(out, "      {decoded}");
+                }
+                Some(Err(e)) => {
+                    let _ = writeln!(out, "  [{index}] {program_id}: failed to decode: {e}");
+                }
+                None => {}
+            }
+        }
+        out
+    }
+}
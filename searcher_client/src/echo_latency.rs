@@ -0,0 +1,141 @@
+use std::{
+    collections::{BTreeMap, HashMap, VecDeque},
+    time::{Duration, Instant},
+};
+
+use histogram::Histogram;
+use solana_metrics::datapoint_info;
+use solana_sdk::signature::Signature;
+
+/// The region a block engine URL is in, from the first label of its host, e.g. `frankfurt` for
+/// `https://frankfurt.mainnet.block-engine.jito.wtf`. The whole host for URLs without one.
+pub fn block_engine_region(url: &str) -> String {
+    let host = url.split("://").nth(1).unwrap_or(url);
+    let host = host.split(['/', ':']).next().unwrap_or_default();
+    match host.split_once('.') {
+        Some((region, _)) if host.contains("block-engine") => region.to_string(),
+        _ => host.to_string(),
+    }
+}
+
+#[derive(Default)]
+struct RegionEchoStats {
+    latency_us: Histogram,
+    submitted: u64,
+    echoed: u64,
+    missed: u64,
+}
+
+/// Echo counts and submit to seen latency percentiles for one region.
+#[derive(Clone, Debug)]
+pub struct EchoLatencySummary {
+    pub region: String,
+    pub submitted: u64,
+    pub echoed: u64,
+    /// Submissions that didn't show up on the mempool stream within the tracker's max age.
+    pub missed: u64,
+    pub p50_us: u64,
+    pub p90_us: u64,
+    pub p99_us: u64,
+    pub max_us: u64,
+}
+
+/// Measures how long our own transactions take to come back on the mempool stream after they
+/// were submitted, per region they were submitted to. A cheap end-to-end probe of the path
+/// through the block engine, tracked over time.
+///
+/// A sighting doesn't say which copy of a transaction was seen, so a transaction submitted to
+/// several regions records the same sighting for each of them.
+pub struct EchoLatencyTracker {
+    max_age: Duration,
+    pending: HashMap<Signature, Vec<(String, Instant)>>,
+    /// Signatures in submission order, to expire the ones that never show up.
+    submitted: VecDeque<(Instant, Signature)>,
+    regions: BTreeMap<String, RegionEchoStats>,
+}
+
+impl EchoLatencyTracker {
+    /// Submissions not seen within `max_age` are counted as missed and forgotten.
+    pub fn new(max_age: Duration) -> Self {
+        Self {
+            max_age,
+            pending: HashMap::new(),
+            submitted: VecDeque::new(),
+            regions: BTreeMap::new(),
+        }
+    }
+
+    pub fn record_submitted(&mut self, region: &str, signature: Signature, now: Instant) {
+        self.regions
+            .entry(region.to_string())
+            .or_default()
+            .submitted += 1;
+        self.pending
+            .entry(signature)
+            .or_default()
+            .push((region.to_string(), now));
+        self.submitted.push_back((now, signature));
+    }
+
+    /// Records a transaction seen on the mempool stream, returning whether it was one of ours.
+    pub fn record_seen(&mut self, signature: &Signature, seen_at: Instant) -> bool {
+        let Some(submissions) = self.pending.remove(signature) else {
+            return false;
+        };
+        for (region, submitted_at) in submissions {
+            let stats = self.regions.entry(region).or_default();
+            let latency = seen_at.saturating_duration_since(submitted_at);
+            let _ = stats.latency_us.increment(latency.as_micros() as u64);
+            stats.echoed += 1;
+        }
+        true
+    }
+
+    /// Counts submissions older than the max age as missed.
+    pub fn expire(&mut self, now: Instant) {
+        while let Some((submitted_at, signature)) = self.submitted.front().copied() {
+            if now.saturating_duration_since(submitted_at) < self.max_age {
+                break;
+            }
+            self.submitted.pop_front();
+            for (region, _) in self.pending.remove(&signature).unwrap_or_default() {
+                self.regions.entry(region).or_default().missed += 1;
+            }
+        }
+    }
+
+    pub fn summaries(&self) -> Vec<EchoLatencySummary> {
+        self.regions
+            .iter()
+            .map(|(region, stats)| EchoLatencySummary {
+                region: region.clone(),
+                submitted: stats.submitted,
+                echoed: stats.echoed,
+                missed: stats.missed,
+                p50_us: stats.latency_us.percentile(50.0).unwrap_or_default(),
+                p90_us: stats.latency_us.percentile(90.0).unwrap_or_default(),
+                p99_us: stats.latency_us.percentile(99.0).unwrap_or_default(),
+                max_us: stats.latency_us.maximum().unwrap_or_default(),
+            })
+            .collect()
+    }
+
+    /// Reports one datapoint per region and resets the stats. Submissions still waiting to be
+    /// seen are kept.
+    pub fn report(&mut self, name: &'static str) {
+        for summary in self.summaries() {
+            datapoint_info!(
+                name,
+                ("region", summary.region, String),
+                ("submitted", summary.submitted, i64),
+                ("echoed", summary.echoed, i64),
+                ("missed", summary.missed, i64),
+                ("p50_us", summary.p50_us, i64),
+                ("p90_us", summary.p90_us, i64),
+                ("p99_us", summary.p99_us, i64),
+                ("max_us", summary.max_us, i64),
+            );
+        }
+        self.regions.clear();
+    }
+}
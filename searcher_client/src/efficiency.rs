@@ -0,0 +1,124 @@
+use std::collections::HashSet;
+
+use solana_client::{
+    client_error::{ClientErrorKind, Result as ClientResult},
+    nonblocking::rpc_client::RpcClient,
+    rpc_config::RpcTransactionConfig,
+};
+use solana_sdk::{commitment_config::CommitmentConfig, pubkey::Pubkey};
+use solana_transaction_status::UiTransactionEncoding;
+
+use crate::{audit_log::AuditedBundle, landing_model::tip_lamports};
+
+/// What a landed bundle cost in compute and tip, and what it made.
+#[derive(Clone, Debug)]
+pub struct BundleEfficiency {
+    pub bundle_uuid: String,
+    /// Consumed by every transaction in the bundle, the victim's included, since the auction
+    /// weighs the whole bundle.
+    pub compute_units: u64,
+    pub tip_lamports: u64,
+    /// Change in the payer's balance over the transactions it paid for, after tip and fees.
+    pub net_lamports: i64,
+}
+
+impl BundleEfficiency {
+    /// What the bundle made before paying the tip.
+    pub fn gross_lamports(&self) -> i64 {
+        self.net_lamports + self.tip_lamports as i64
+    }
+
+    pub fn tip_lamports_per_cu(&self) -> f64 {
+        self.tip_lamports as f64 / self.compute_units.max(1) as f64
+    }
+
+    pub fn gross_lamports_per_cu(&self) -> f64 {
+        self.gross_lamports() as f64 / self.compute_units.max(1) as f64
+    }
+}
+
+/// Totals over a set of landed bundles.
+#[derive(Clone, Debug, Default)]
+pub struct EfficiencySummary {
+    pub bundles: usize,
+    pub compute_units: u64,
+    pub tip_lamports: u64,
+    pub net_lamports: i64,
+    /// Median over the bundles, so a few huge ones don't hide the typical bid.
+    pub median_tip_lamports_per_cu: f64,
+    pub median_gross_lamports_per_cu: f64,
+}
+
+impl EfficiencySummary {
+    pub fn from_bundles(bundles: &[BundleEfficiency]) -> Self {
+        Self {
+            bundles: bundles.len(),
+            compute_units: bundles.iter().map(|b| b.compute_units).sum(),
+            tip_lamports: bundles.iter().map(|b| b.tip_lamports).sum(),
+            net_lamports: bundles.iter().map(|b| b.net_lamports).sum(),
+            median_tip_lamports_per_cu: median(
+                bundles.iter().map(BundleEfficiency::tip_lamports_per_cu),
+            ),
+            median_gross_lamports_per_cu: median(
+                bundles.iter().map(BundleEfficiency::gross_lamports_per_cu),
+            ),
+        }
+    }
+
+    /// Share of the gross profit bid away in tips, 0 if there was no profit.
+    pub fn tip_share(&self) -> f64 {
+        let gross = self.net_lamports + self.tip_lamports as i64;
+        if gross <= 0 {
+            return 0.0;
+        }
+        self.tip_lamports as f64 / gross as f64
+    }
+}
+
+fn median(values: impl Iterator<Item = f64>) -> f64 {
+    let mut values: Vec<f64> = values.collect();
+    if values.is_empty() {
+        return 0.0;
+    }
+    values.sort_by(f64::total_cmp);
+    values[values.len() / 2]
+}
+
+/// Fetches every transaction of a bundle that landed and works out its efficiency. `payer` is
+/// the searcher's fee payer, transactions it didn't pay for count toward compute only.
+pub async fn landed_bundle_efficiency(
+    rpc_client: &RpcClient,
+    bundle: &AuditedBundle,
+    payer: &Pubkey,
+    tip_accounts: &HashSet<Pubkey>,
+) -> ClientResult<BundleEfficiency> {
+    let mut efficiency = BundleEfficiency {
+        bundle_uuid: bundle.bundle_uuid.clone(),
+        compute_units: 0,
+        tip_lamports: tip_lamports(&bundle.transactions, tip_accounts),
+        net_lamports: 0,
+    };
+    for tx in &bundle.transactions {
+        let signature = tx.signatures[0];
+        let meta = rpc_client
+            .get_transaction_with_config(
+                &signature,
+                RpcTransactionConfig {
+                    encoding: Some(UiTransactionEncoding::Base64),
+                    commitment: Some(CommitmentConfig::confirmed()),
+                    max_supported_transaction_version: Some(0),
+                },
+            )
+            .await?
+            .transaction
+            .meta
+            .ok_or_else(|| ClientErrorKind::Custom(format!("{signature} has no status meta")))?;
+        efficiency.compute_units += Option::<u64>::from(meta.compute_units_consumed).unwrap_or(0);
+        if tx.message.static_account_keys().first() == Some(payer) {
+            let before = meta.pre_balances.first().copied().unwrap_or_default();
+            let after = meta.post_balances.first().copied().unwrap_or_default();
+            efficiency.net_lamports += after as i64 - before as i64;
+        }
+    }
+    Ok(efficiency)
+}
@@ -0,0 +1,174 @@
+use std::sync::Arc;
+
+use solana_sdk::{
+    instruction::Instruction,
+    program_pack::Pack,
+    pubkey::{Pubkey, PubkeyError},
+    rent::Rent,
+    signature::{Keypair, Signer},
+    system_instruction, system_program,
+};
+use spl_token::{instruction as token_instruction, state::Account as TokenAccount};
+
+/// What an ephemeral account holds, which decides how it's set up and closed.
+#[derive(Clone, Debug)]
+enum Kind {
+    /// Lamports only, emptied with a transfer the base signs.
+    System { lamports: u64 },
+    /// A token account with the base as its authority, closed by the token program.
+    TokenAccount { mint: Pubkey },
+    /// State of another program, closed with the program's own instruction.
+    Program { close: Instruction },
+}
+
+/// An account at an address derived from a base wallet and a seed with `create_with_seed`,
+/// opened in a bundle's first transaction and closed in its last by
+/// [`BundleBuilder::with_ephemeral_account`], so temporary state has its rent back once the
+/// bundle lands and needs no keypair of its own. Only the base signs for it.
+///
+/// The address only depends on the base, seed and owner, so bundles in flight at the same time
+/// need different seeds: a bundle whose account already exists reverts.
+///
+/// [`BundleBuilder::with_ephemeral_account`]: crate::bundle_builder::BundleBuilder::with_ephemeral_account
+#[derive(Clone, Debug)]
+pub struct EphemeralAccount {
+    base: Arc<Keypair>,
+    seed: String,
+    owner: Pubkey,
+    address: Pubkey,
+    space: u64,
+    kind: Kind,
+}
+
+impl EphemeralAccount {
+    /// A system account funded with `lamports` on top of its rent. Closing it moves back what
+    /// it was opened with, so the bundle shouldn't send it more.
+    pub fn system(base: Arc<Keypair>, seed: &str, lamports: u64) -> Result<Self, PubkeyError> {
+        Self::new(
+            base,
+            seed,
+            system_program::id(),
+            0,
+            Kind::System { lamports },
+        )
+    }
+
+    /// A token account of `mint` with the base as its authority. Closing it fails unless it's
+    /// been emptied, or holds wSOL.
+    pub fn token_account(
+        base: Arc<Keypair>,
+        seed: &str,
+        mint: Pubkey,
+    ) -> Result<Self, PubkeyError> {
+        Self::new(
+            base,
+            seed,
+            spl_token::id(),
+            TokenAccount::LEN as u64,
+            Kind::TokenAccount { mint },
+        )
+    }
+
+    /// `space` bytes owned by `owner` for the program to keep state in. Only the owner can
+    /// close the account, so `close` is the program's instruction doing so, sending the rent
+    /// wherever it should go.
+    pub fn program_owned(
+        base: Arc<Keypair>,
+        seed: &str,
+        owner: Pubkey,
+        space: u64,
+        close: Instruction,
+    ) -> Result<Self, PubkeyError> {
+        Self::new(base, seed, owner, space, Kind::Program { close })
+    }
+
+    fn new(
+        base: Arc<Keypair>,
+        seed: &str,
+        owner: Pubkey,
+        space: u64,
+        kind: Kind,
+    ) -> Result<Self, PubkeyError> {
+        let address = Pubkey::create_with_seed(&base.pubkey(), seed, &owner)?;
+        Ok(Self {
+            base,
+            seed: seed.to_string(),
+            owner,
+            address,
+            space,
+            kind,
+        })
+    }
+
+    pub fn address(&self) -> Pubkey {
+        self.address
+    }
+
+    pub fn seed(&self) -> &str {
+        &self.seed
+    }
+
+    /// The wallet the address is derived from, which signs the instructions opening and
+    /// closing the account.
+    pub fn base(&self) -> &Arc<Keypair> {
+        &self.base
+    }
+
+    /// Lamports the account is opened with, which all come back when it's closed.
+    pub fn lamports(&self) -> u64 {
+        let rent = Rent::default().minimum_balance(self.space as usize);
+        match self.kind {
+            Kind::System { lamports } => rent.saturating_add(lamports),
+            Kind::TokenAccount { .. } | Kind::Program { .. } => rent,
+        }
+    }
+
+    /// Creates the account, paid for by `payer`, and sets up a token account.
+    pub fn open(&self, payer: &Pubkey) -> Vec<Instruction> {
+        let mut instructions = vec![system_instruction::create_account_with_seed(
+            payer,
+            &self.address,
+            &self.base.pubkey(),
+            &self.seed,
+            self.lamports(),
+            self.space,
+            &self.owner,
+        )];
+        if let Kind::TokenAccount { mint } = &self.kind {
+            instructions.push(
+                token_instruction::initialize_account3(
+                    &spl_token::id(),
+                    &self.address,
+                    mint,
+                    &self.base.pubkey(),
+                )
+                .expect("valid token program"),
+            );
+        }
+        instructions
+    }
+
+    /// Closes the account, sending its lamports to `destination`. A program owned account's own
+    /// close instruction decides where they go instead.
+    pub fn close(&self, destination: &Pubkey) -> Instruction {
+        match &self.kind {
+            Kind::System { .. } => system_instruction::transfer_with_seed(
+                &self.address,
+                &self.base.pubkey(),
+                self.seed.clone(),
+                &self.owner,
+                destination,
+                self.lamports(),
+            ),
+            Kind::TokenAccount { .. } => token_instruction::close_account(
+                &spl_token::id(),
+                &self.address,
+                destination,
+                &self.base.pubkey(),
+                &[],
+            )
+            .expect("valid token program"),
+            Kind::Program { close } => close.clone(),
+        }
+    }
+}
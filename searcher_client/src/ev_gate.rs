@@ -0,0 +1,104 @@
+use log::info;
+use serde::{Deserialize, Serialize};
+use solana_metrics::datapoint_info;
+use solana_sdk::{fee::FeeStructure, transaction::VersionedTransaction};
+
+use crate::transaction_decoder::ComputeBudgetSettings;
+
+/// Compute unit limit the runtime assumes per instruction when a transaction doesn't set one.
+const DEFAULT_INSTRUCTION_COMPUTE_UNIT_LIMIT: u64 = 200_000;
+const MICRO_LAMPORTS_PER_LAMPORT: u64 = 1_000_000;
+
+/// A strategy's estimate of what submitting a bundle is worth.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct ProfitEstimate {
+    pub expected_profit_lamports: u64,
+    pub tip_lamports: u64,
+    pub fee_lamports: u64,
+}
+
+impl ProfitEstimate {
+    /// Expected profit minus tip and fees, negative if the bundle loses money.
+    pub fn net_lamports(&self) -> i64 {
+        self.expected_profit_lamports as i64 - self.tip_lamports as i64 - self.fee_lamports as i64
+    }
+}
+
+/// Signature and priority fees the bundle's transactions pay if they land.
+pub fn estimate_fee_lamports(transactions: &[VersionedTransaction]) -> u64 {
+    let lamports_per_signature = FeeStructure::default().lamports_per_signature;
+    transactions
+        .iter()
+        .map(|tx| {
+            let signature_fee =
+                tx.message.header().num_required_signatures as u64 * lamports_per_signature;
+            let budget = ComputeBudgetSettings::from_transaction(tx);
+            let unit_limit = budget.unit_limit.map(u64::from).unwrap_or(
+                tx.message.instructions().len() as u64 * DEFAULT_INSTRUCTION_COMPUTE_UNIT_LIMIT,
+            );
+            let priority_fee = budget
+                .unit_price_micro_lamports
+                .unwrap_or(0)
+                .saturating_mul(unit_limit)
+                .div_ceil(MICRO_LAMPORTS_PER_LAMPORT);
+            signature_fee + priority_fee
+        })
+        .sum()
+}
+
+/// Refuses bundles whose expected profit doesn't cover the tip and fees by at least
+/// `min_margin_lamports`.
+#[derive(Clone, Debug)]
+pub struct ExpectedValueGate {
+    min_margin_lamports: i64,
+    passed: u64,
+    skipped: u64,
+    unestimated: u64,
+}
+
+impl ExpectedValueGate {
+    pub fn new(min_margin_lamports: i64) -> Self {
+        Self {
+            min_margin_lamports,
+            passed: 0,
+            skipped: 0,
+            unestimated: 0,
+        }
+    }
+
+    /// Returns true if the bundle should be submitted. Bundles without an estimate are let
+    /// through and counted separately.
+    pub fn allows(&mut self, label: &str, estimate: Option<&ProfitEstimate>) -> bool {
+        let Some(estimate) = estimate else {
+            self.unestimated += 1;
+            return true;
+        };
+        let net_lamports = estimate.net_lamports();
+        if net_lamports >= self.min_margin_lamports {
+            self.passed += 1;
+            return true;
+        }
+        self.skipped += 1;
+        info!(
+            "skipping {label}: expected profit {} - tip {} - fees {} = {net_lamports} lamports, below margin {}",
+            estimate.expected_profit_lamports,
+            estimate.tip_lamports,
+            estimate.fee_lamports,
+            self.min_margin_lamports
+        );
+        false
+    }
+
+    /// Reports the counters and resets them.
+    pub fn report(&mut self, name: &'static str) {
+        datapoint_info!(
+            name,
+            ("passed", self.passed, i64),
+            ("skipped", self.skipped, i64),
+            ("unestimated", self.unestimated, i64),
+        );
+        self.passed = 0;
+        self.skipped = 0;
+        self.unestimated = 0;
+    }
+}
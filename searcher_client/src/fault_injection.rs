@@ -0,0 +1,250 @@
+use std::{
+    fs,
+    future::Future,
+    io,
+    path::Path,
+    sync::{Mutex, OnceLock},
+    time::Duration,
+};
+
+use log::warn;
+use serde::Deserialize;
+use thiserror::Error;
+use tonic::{Code, Status};
+
+static INJECTOR: OnceLock<Mutex<FaultInjector>> = OnceLock::new();
+
+#[derive(Debug, Error)]
+pub enum FaultInjectionError {
+    #[error("failed to read fault scenario: {0}")]
+    Io(#[from] io::Error),
+    #[error("invalid fault scenario: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("call rule for {0} can't drop, only stream messages can be dropped")]
+    DropOnCall(String),
+    #[error("rule for {0} has every set to 0")]
+    ZeroEvery(String),
+}
+
+/// gRPC codes a rule can fail with, the transient ones retry and reconnection logic handles.
+#[derive(Clone, Copy, Debug, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FaultCode {
+    Unavailable,
+    DeadlineExceeded,
+    ResourceExhausted,
+    Aborted,
+    Internal,
+    Unknown,
+    Cancelled,
+}
+
+impl From<FaultCode> for Code {
+    fn from(code: FaultCode) -> Self {
+        match code {
+            FaultCode::Unavailable => Code::Unavailable,
+            FaultCode::DeadlineExceeded => Code::DeadlineExceeded,
+            FaultCode::ResourceExhausted => Code::ResourceExhausted,
+            FaultCode::Aborted => Code::Aborted,
+            FaultCode::Internal => Code::Internal,
+            FaultCode::Unknown => Code::Unknown,
+            FaultCode::Cancelled => Code::Cancelled,
+        }
+    }
+}
+
+/// What a rule does when it fires, e.g. `"drop"`, `{"delay_ms": 500}` or
+/// `{"error": "unavailable"}`.
+#[derive(Clone, Copy, Debug, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Fault {
+    /// Drops a stream message.
+    Drop,
+    /// Holds back a call's response, or a stream message, for this long.
+    DelayMs(u64),
+    /// Fails the call, or the stream in place of a message, with this code.
+    Error(FaultCode),
+}
+
+/// Fires `fault` on every `every`th call or message of `method` after the first `after`, at
+/// most `count` times. Counting is per rule, so a scenario plays out the same way every run.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct FaultRule {
+    pub method: String,
+    pub fault: Fault,
+    #[serde(default)]
+    pub after: u64,
+    #[serde(default = "default_every")]
+    pub every: u64,
+    /// Unlimited if unset.
+    #[serde(default)]
+    pub count: Option<u64>,
+}
+
+fn default_every() -> u64 {
+    1
+}
+
+/// A fault injection scenario, read from a JSON file such as:
+///
+/// ```json
+/// {
+///   "calls": [
+///     {"method": "SendBundle", "fault": {"error": "unavailable"}, "every": 3},
+///     {"method": "GetTipAccounts", "fault": {"delay_ms": 2000}, "count": 1}
+///   ],
+///   "stream_messages": [
+///     {"method": "SubscribeMempool", "fault": "drop", "every": 10},
+///     {"method": "SubscribeMempool", "fault": {"error": "unavailable"}, "after": 499, "count": 1}
+///   ]
+/// }
+/// ```
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct FaultScenario {
+    /// Rules for unary calls and stream opens, matched by gRPC method.
+    #[serde(default)]
+    pub calls: Vec<FaultRule>,
+    /// Rules for messages received on a stream, matched by the method that opened it.
+    #[serde(default)]
+    pub stream_messages: Vec<FaultRule>,
+}
+
+impl FaultScenario {
+    pub fn load(path: &Path) -> Result<Self, FaultInjectionError> {
+        let scenario: Self = serde_json::from_str(&fs::read_to_string(path)?)?;
+        scenario.validate()?;
+        Ok(scenario)
+    }
+
+    fn validate(&self) -> Result<(), FaultInjectionError> {
+        for rule in self.calls.iter().chain(&self.stream_messages) {
+            if rule.every == 0 {
+                return Err(FaultInjectionError::ZeroEvery(rule.method.clone()));
+            }
+        }
+        match self
+            .calls
+            .iter()
+            .find(|rule| matches!(rule.fault, Fault::Drop))
+        {
+            Some(rule) => Err(FaultInjectionError::DropOnCall(rule.method.clone())),
+            None => Ok(()),
+        }
+    }
+}
+
+struct RuleState {
+    rule: FaultRule,
+    seen: u64,
+    fired: u64,
+}
+
+impl RuleState {
+    fn new(rule: FaultRule) -> Self {
+        Self {
+            rule,
+            seen: 0,
+            fired: 0,
+        }
+    }
+
+    /// Counts a call or message of `method`, returning the fault if the rule fires on it.
+    fn next(&mut self, method: &str) -> Option<Fault> {
+        if self.rule.method != method {
+            return None;
+        }
+        self.seen += 1;
+        let exhausted = self.rule.count.is_some_and(|count| self.fired >= count);
+        if exhausted || self.seen <= self.rule.after {
+            return None;
+        }
+        if !(self.seen - self.rule.after).is_multiple_of(self.rule.every) {
+            return None;
+        }
+        self.fired += 1;
+        Some(self.rule.fault)
+    }
+}
+
+struct FaultInjector {
+    calls: Vec<RuleState>,
+    stream_messages: Vec<RuleState>,
+}
+
+/// Every rule matching `method` counts it, and the first one firing wins.
+fn next_fault(rules: &mut [RuleState], method: &str) -> Option<Fault> {
+    let mut fault = None;
+    for rule in rules {
+        let fired = rule.next(method);
+        fault = fault.or(fired);
+    }
+    fault
+}
+
+/// Starts injecting the scenario's faults into block engine calls and streams. Test only: meant
+/// for exercising reconnection and retry logic against a real or local block engine.
+pub fn install(scenario: FaultScenario) {
+    let injector = FaultInjector {
+        calls: scenario.calls.into_iter().map(RuleState::new).collect(),
+        stream_messages: scenario
+            .stream_messages
+            .into_iter()
+            .map(RuleState::new)
+            .collect(),
+    };
+    if INJECTOR.set(Mutex::new(injector)).is_err() {
+        warn!("fault injection already installed, ignoring scenario");
+    }
+}
+
+fn call_fault(method: &str) -> Option<Fault> {
+    let mut injector = INJECTOR.get()?.lock().unwrap();
+    next_fault(&mut injector.calls, method)
+}
+
+fn message_fault(method: &str) -> Option<Fault> {
+    let mut injector = INJECTOR.get()?.lock().unwrap();
+    next_fault(&mut injector.stream_messages, method)
+}
+
+fn injected_status(method: &str, code: FaultCode) -> Status {
+    Status::new(code.into(), format!("injected fault on {method}"))
+}
+
+/// Runs `call`, a call to `method`, failing it or delaying its response if a rule fires.
+pub async fn faulted<T, Fut>(method: &str, call: Fut) -> Result<T, Status>
+where
+    Fut: Future<Output = Result<T, Status>>,
+{
+    match call_fault(method) {
+        Some(Fault::Error(code)) => Err(injected_status(method, code)),
+        Some(Fault::DelayMs(ms)) => {
+            let result = call.await;
+            tokio::time::sleep(Duration::from_millis(ms)).await;
+            result
+        }
+        Some(Fault::Drop) | None => call.await,
+    }
+}
+
+/// Passes on `item`, received on the stream opened by `method`, unless a rule fires: `None`
+/// drops it, an injected error replaces it and delays hold it back.
+pub async fn faulted_message<T>(
+    method: &str,
+    item: Result<T, Status>,
+) -> Option<Result<T, Status>> {
+    if item.is_err() {
+        return Some(item);
+    }
+    match message_fault(method) {
+        Some(Fault::Drop) => None,
+        Some(Fault::Error(code)) => Some(Err(injected_status(method, code))),
+        Some(Fault::DelayMs(ms)) => {
+            tokio::time::sleep(Duration::from_millis(ms)).await;
+            Some(item)
+        }
+        None => Some(item),
+    }
+}
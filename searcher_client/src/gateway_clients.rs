@@ -0,0 +1,330 @@
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    fs, io,
+    path::Path,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    time::{Duration, Instant},
+};
+
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use solana_metrics::datapoint_info;
+use thiserror::Error;
+
+use crate::tip_budget::TipBudget;
+
+#[derive(Debug, Error)]
+pub enum GatewayClientsError {
+    #[error("failed to read gateway clients: {0}")]
+    Io(#[from] io::Error),
+    #[error("invalid gateway clients: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("invalid gateway clients: {0}")]
+    Invalid(String),
+}
+
+/// A client allowed to use the gateway and the quotas it's held to, unlimited where not set.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ClientConfig {
+    /// Names the client in logs and metrics.
+    pub name: String,
+    /// Presented by the client as `authorization: Bearer <token>`.
+    pub token: String,
+    /// Bundle requests per second, whether they're sent or not.
+    pub max_qps: Option<u32>,
+    /// Bundles sent per minute.
+    pub max_bundles_per_minute: Option<u32>,
+    /// Lamports the client may tip within `tip_budget_window_secs`.
+    pub tip_budget_lamports: Option<u64>,
+    #[serde(default = "default_tip_budget_window_secs")]
+    pub tip_budget_window_secs: u64,
+}
+
+fn default_tip_budget_window_secs() -> u64 {
+    60
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ClientsFile {
+    clients: Vec<ClientConfig>,
+}
+
+/// A request or bundle over one of its client's quotas.
+#[derive(Clone, Copy, Debug, Error, PartialEq, Eq)]
+pub enum QuotaExceeded {
+    #[error("over the quota of {max} requests per second")]
+    Qps { max: u32, retry_after: Duration },
+    #[error("over the quota of {max} bundles per minute")]
+    BundlesPerMinute { max: u32, retry_after: Duration },
+    #[error("spent the tip budget of {max_lamports} lamports")]
+    TipBudget { max_lamports: u64 },
+}
+
+impl QuotaExceeded {
+    /// When the quota has room again, for the rate quotas.
+    pub fn retry_after(&self) -> Option<Duration> {
+        match self {
+            Self::Qps { retry_after, .. } | Self::BundlesPerMinute { retry_after, .. } => {
+                Some(*retry_after)
+            }
+            Self::TipBudget { .. } => None,
+        }
+    }
+}
+
+/// Up to `max` events within a sliding window.
+#[derive(Debug)]
+struct RateWindow {
+    max: u32,
+    window: Duration,
+    events: VecDeque<Instant>,
+}
+
+impl RateWindow {
+    fn new(max: u32, window: Duration) -> Self {
+        Self {
+            max,
+            window,
+            events: VecDeque::new(),
+        }
+    }
+
+    /// Whether another event fits, or how long until one does.
+    fn check(&mut self, now: Instant) -> Result<(), Duration> {
+        while let Some(at) = self.events.front() {
+            if now.duration_since(*at) < self.window {
+                break;
+            }
+            self.events.pop_front();
+        }
+        if self.events.len() < self.max as usize {
+            return Ok(());
+        }
+        let oldest = self.events.front().copied().unwrap_or(now);
+        Err(self.window.saturating_sub(now.duration_since(oldest)))
+    }
+}
+
+#[derive(Debug)]
+struct Quotas {
+    requests: Option<RateWindow>,
+    bundles: Option<RateWindow>,
+    tips: Option<TipBudget>,
+}
+
+impl Quotas {
+    fn check_request(&mut self, now: Instant) -> Result<(), QuotaExceeded> {
+        if let Some(requests) = &mut self.requests {
+            let max = requests.max;
+            requests
+                .check(now)
+                .map_err(|retry_after| QuotaExceeded::Qps { max, retry_after })?;
+            requests.events.push_back(now);
+        }
+        Ok(())
+    }
+
+    fn charge_bundle(&mut self, tip_lamports: u64, now: Instant) -> Result<(), QuotaExceeded> {
+        if let Some(bundles) = &mut self.bundles {
+            let max = bundles.max;
+            bundles
+                .check(now)
+                .map_err(|retry_after| QuotaExceeded::BundlesPerMinute { max, retry_after })?;
+        }
+        if let Some(tips) = &mut self.tips {
+            if !tips.try_spend(tip_lamports, now) {
+                return Err(QuotaExceeded::TipBudget {
+                    max_lamports: tips.max_lamports(),
+                });
+            }
+        }
+        if let Some(bundles) = &mut self.bundles {
+            bundles.events.push_back(now);
+        }
+        Ok(())
+    }
+
+    fn refund_bundle(&mut self, tip_lamports: u64, charged_at: Instant) {
+        if let Some(bundles) = &mut self.bundles {
+            if let Some(i) = bundles.events.iter().rposition(|at| *at == charged_at) {
+                bundles.events.remove(i);
+            }
+        }
+        if let Some(tips) = &mut self.tips {
+            tips.refund(tip_lamports, charged_at);
+        }
+    }
+}
+
+/// An authenticated client of the gateway, holding what it used of its quotas.
+#[derive(Debug)]
+pub struct GatewayClient {
+    name: String,
+    quotas: Mutex<Quotas>,
+    requests: AtomicU64,
+    bundles: AtomicU64,
+    tip_lamports: AtomicU64,
+    quota_exceeded: AtomicU64,
+}
+
+impl GatewayClient {
+    fn new(config: &ClientConfig) -> Self {
+        Self {
+            name: config.name.clone(),
+            quotas: Mutex::new(Quotas {
+                requests: config
+                    .max_qps
+                    .map(|max| RateWindow::new(max, Duration::from_secs(1))),
+                bundles: config
+                    .max_bundles_per_minute
+                    .map(|max| RateWindow::new(max, Duration::from_secs(60))),
+                tips: config.tip_budget_lamports.map(|max_lamports| {
+                    TipBudget::new(
+                        max_lamports,
+                        Duration::from_secs(config.tip_budget_window_secs),
+                    )
+                }),
+            }),
+            requests: AtomicU64::default(),
+            bundles: AtomicU64::default(),
+            tip_lamports: AtomicU64::default(),
+            quota_exceeded: AtomicU64::default(),
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Counts a bundle request against the client's requests per second.
+    pub fn check_request(&self, now: Instant) -> Result<(), QuotaExceeded> {
+        self.requests.fetch_add(1, Ordering::Relaxed);
+        let result = self.quotas.lock().unwrap().check_request(now);
+        if result.is_err() {
+            self.quota_exceeded.fetch_add(1, Ordering::Relaxed);
+        }
+        result
+    }
+
+    /// Charges a bundle about to be sent with `tip_lamports` to the client's quotas, or nothing
+    /// if it's over one of them.
+    pub fn try_send(&self, tip_lamports: u64, now: Instant) -> Result<(), QuotaExceeded> {
+        let result = self.quotas.lock().unwrap().charge_bundle(tip_lamports, now);
+        match result {
+            Ok(()) => {
+                self.bundles.fetch_add(1, Ordering::Relaxed);
+                self.tip_lamports.fetch_add(tip_lamports, Ordering::Relaxed);
+            }
+            Err(_) => {
+                self.quota_exceeded.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+        result
+    }
+
+    /// Gives back what [`Self::try_send`] charged at `charged_at` for a bundle that wasn't sent.
+    pub fn refund(&self, tip_lamports: u64, charged_at: Instant) {
+        self.quotas
+            .lock()
+            .unwrap()
+            .refund_bundle(tip_lamports, charged_at);
+        let _ = self
+            .bundles
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |n| n.checked_sub(1));
+        let _ = self
+            .tip_lamports
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |n| {
+                Some(n.saturating_sub(tip_lamports))
+            });
+    }
+
+    /// Reports the requests, bundles and tips since the last report, tagged with the client's
+    /// name.
+    pub fn report(&self, name: &'static str) {
+        let remaining = self
+            .quotas
+            .lock()
+            .unwrap()
+            .tips
+            .as_mut()
+            .map_or(-1, |tips| tips.remaining(Instant::now()) as i64);
+        datapoint_info!(
+            name,
+            "client" => self.name.clone(),
+            ("requests", self.requests.swap(0, Ordering::Relaxed), i64),
+            ("bundles", self.bundles.swap(0, Ordering::Relaxed), i64),
+            ("tip_lamports", self.tip_lamports.swap(0, Ordering::Relaxed), i64),
+            ("quota_exceeded", self.quota_exceeded.swap(0, Ordering::Relaxed), i64),
+            ("tip_budget_remaining_lamports", remaining, i64),
+        );
+    }
+}
+
+/// The clients allowed to use the gateway, read from a JSON file such as:
+///
+/// ```json
+/// {"clients": [
+///   {"name": "arb", "token": "...", "max_qps": 50, "max_bundles_per_minute": 600,
+///    "tip_budget_lamports": 100000000, "tip_budget_window_secs": 3600}
+/// ]}
+/// ```
+#[derive(Debug, Default)]
+pub struct GatewayClients {
+    // keyed by the token's digest so looking one up takes as long whatever the token presented
+    by_token: HashMap<[u8; 32], Arc<GatewayClient>>,
+}
+
+impl GatewayClients {
+    pub fn load(path: &Path) -> Result<Self, GatewayClientsError> {
+        Self::from_json(&fs::read_to_string(path)?)
+    }
+
+    pub fn from_json(json: &str) -> Result<Self, GatewayClientsError> {
+        let file: ClientsFile = serde_json::from_str(json)?;
+        Self::new(&file.clients)
+    }
+
+    pub fn new(configs: &[ClientConfig]) -> Result<Self, GatewayClientsError> {
+        let mut names = HashSet::new();
+        let mut by_token = HashMap::new();
+        for config in configs {
+            if config.name.is_empty() || config.token.is_empty() {
+                return Err(GatewayClientsError::Invalid(
+                    "every client needs a name and a token".to_string(),
+                ));
+            }
+            if !names.insert(config.name.as_str()) {
+                return Err(GatewayClientsError::Invalid(format!(
+                    "client {} is listed twice",
+                    config.name
+                )));
+            }
+            let digest: [u8; 32] = Sha256::digest(config.token.as_bytes()).into();
+            if by_token
+                .insert(digest, Arc::new(GatewayClient::new(config)))
+                .is_some()
+            {
+                return Err(GatewayClientsError::Invalid(format!(
+                    "client {} has the same token as another",
+                    config.name
+                )));
+            }
+        }
+        Ok(Self { by_token })
+    }
+
+    /// The client `token` belongs to.
+    pub fn authenticate(&self, token: &str) -> Option<Arc<GatewayClient>> {
+        let digest: [u8; 32] = Sha256::digest(token.as_bytes()).into();
+        self.by_token.get(&digest).cloned()
+    }
+
+    pub fn clients(&self) -> impl Iterator<Item = &Arc<GatewayClient>> {
+        self.by_token.values()
+    }
+}
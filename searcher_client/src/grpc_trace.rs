@@ -0,0 +1,193 @@
+use std::{
+    any::type_name,
+    fs::{File, OpenOptions},
+    future::Future,
+    io::{self, BufWriter, Write},
+    path::Path,
+    sync::{Mutex, OnceLock},
+    time::Instant,
+};
+
+use chrono::Utc;
+use jito_protos::{
+    auth::{
+        GenerateAuthChallengeRequest, GenerateAuthChallengeResponse, GenerateAuthTokensRequest,
+        GenerateAuthTokensResponse, RefreshAccessTokenRequest, RefreshAccessTokenResponse, Token,
+    },
+    bundle::BundleResult,
+    searcher::{
+        ConnectedLeadersRegionedRequest, ConnectedLeadersRegionedResponse, ConnectedLeadersRequest,
+        ConnectedLeadersResponse, GetRegionsRequest, GetRegionsResponse, GetTipAccountsRequest,
+        GetTipAccountsResponse, MempoolSubscription, NextScheduledLeaderRequest,
+        NextScheduledLeaderResponse, PendingTxNotification, SendBundleRequest, SendBundleResponse,
+        SubscribeBundleResultsRequest,
+    },
+};
+use log::warn;
+use serde_json::json;
+use tonic::{Response, Status, Streaming};
+
+const REDACTED: &str = "<redacted>";
+
+static TRACE: OnceLock<Mutex<BufWriter<File>>> = OnceLock::new();
+
+/// A message that can be written to the trace. Messages carrying credentials replace them
+/// with `<redacted>`.
+pub trait Traced {
+    fn redacted(&self) -> String;
+}
+
+macro_rules! traced_as_debug {
+    ($($message:ty),* $(,)?) => {
+        $(impl Traced for $message {
+            fn redacted(&self) -> String {
+                format!("{self:?}")
+            }
+        })*
+    };
+}
+
+traced_as_debug!(
+    SendBundleRequest,
+    SendBundleResponse,
+    NextScheduledLeaderRequest,
+    NextScheduledLeaderResponse,
+    ConnectedLeadersRequest,
+    ConnectedLeadersResponse,
+    ConnectedLeadersRegionedRequest,
+    ConnectedLeadersRegionedResponse,
+    GetTipAccountsRequest,
+    GetTipAccountsResponse,
+    GetRegionsRequest,
+    GetRegionsResponse,
+    SubscribeBundleResultsRequest,
+    MempoolSubscription,
+    PendingTxNotification,
+    BundleResult,
+    GenerateAuthChallengeRequest,
+    GenerateAuthChallengeResponse,
+);
+
+impl Traced for GenerateAuthTokensRequest {
+    fn redacted(&self) -> String {
+        format!(
+            "GenerateAuthTokensRequest {{ challenge: {:?}, client_pubkey: {:?}, signed_challenge: {REDACTED} }}",
+            self.challenge, self.client_pubkey
+        )
+    }
+}
+
+impl Traced for GenerateAuthTokensResponse {
+    fn redacted(&self) -> String {
+        format!(
+            "GenerateAuthTokensResponse {{ access_token: {:?}, refresh_token: {:?} }}",
+            self.access_token.as_ref().map(redact_token),
+            self.refresh_token.as_ref().map(redact_token)
+        )
+    }
+}
+
+impl Traced for RefreshAccessTokenRequest {
+    fn redacted(&self) -> String {
+        format!("RefreshAccessTokenRequest {{ refresh_token: {REDACTED} }}")
+    }
+}
+
+impl Traced for RefreshAccessTokenResponse {
+    fn redacted(&self) -> String {
+        format!(
+            "RefreshAccessTokenResponse {{ access_token: {:?} }}",
+            self.access_token.as_ref().map(redact_token)
+        )
+    }
+}
+
+/// Stream messages are traced one by one with `trace_message` as they're received.
+impl<T> Traced for Streaming<T> {
+    fn redacted(&self) -> String {
+        format!("stream of {}", type_name::<T>())
+    }
+}
+
+fn redact_token(token: &Token) -> Token {
+    Token {
+        value: REDACTED.to_string(),
+        ..token.clone()
+    }
+}
+
+/// Starts appending every traced block engine call to `path` as JSONL. Debug only: traces hold
+/// full bundles and aren't rotated.
+pub fn enable(path: &Path) -> io::Result<()> {
+    let file = OpenOptions::new().create(true).append(true).open(path)?;
+    if TRACE.set(Mutex::new(BufWriter::new(file))).is_err() {
+        warn!("gRPC tracing already enabled, not tracing to {path:?}");
+    }
+    Ok(())
+}
+
+pub fn is_enabled() -> bool {
+    TRACE.get().is_some()
+}
+
+/// Makes the call with `request`, tracing the request and its response or status if tracing is
+/// enabled, e.g. `traced("GetRegions", GetRegionsRequest {}, |r| client.get_regions(r))`.
+pub async fn traced<Req, Resp, F, Fut>(
+    method: &str,
+    request: Req,
+    call: F,
+) -> Result<Response<Resp>, Status>
+where
+    Req: Traced,
+    Resp: Traced,
+    F: FnOnce(Req) -> Fut,
+    Fut: Future<Output = Result<Response<Resp>, Status>>,
+{
+    #[cfg(feature = "fault-injection")]
+    let call = move |request| crate::fault_injection::faulted(method, call(request));
+    if !is_enabled() {
+        return call(request).await;
+    }
+    let request_dump = request.redacted();
+    let start = Instant::now();
+    let result = call(request).await;
+    let mut record = json!({
+        "at": Utc::now().to_rfc3339(),
+        "method": method,
+        "elapsed_us": start.elapsed().as_micros() as u64,
+        "request": request_dump,
+    });
+    match &result {
+        Ok(response) => record["response"] = json!(response.get_ref().redacted()),
+        Err(status) => {
+            record["status"] = json!({
+                "code": format!("{:?}", status.code()),
+                "message": status.message(),
+            })
+        }
+    }
+    write_record(&record);
+    result
+}
+
+/// Traces a message received on a stream opened by `method`.
+pub fn trace_message<M: Traced>(method: &str, message: &M) {
+    if !is_enabled() {
+        return;
+    }
+    write_record(&json!({
+        "at": Utc::now().to_rfc3339(),
+        "method": method,
+        "stream_message": message.redacted(),
+    }));
+}
+
+fn write_record(record: &serde_json::Value) {
+    let Some(trace) = TRACE.get() else {
+        return;
+    };
+    let mut writer = trace.lock().unwrap();
+    if let Err(e) = writeln!(writer, "{record}").and_then(|_| writer.flush()) {
+        warn!("failed to write gRPC trace: {e}");
+    }
+}
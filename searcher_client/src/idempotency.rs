@@ -0,0 +1,118 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    time::{Duration, Instant},
+};
+
+use solana_sdk::hash::{hashv, Hash};
+
+/// What a submission API should do with a request carrying an idempotency key.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum IdempotencyCheck {
+    /// The key is new, or its last use expired. It's now held for this request until
+    /// [`IdempotencyKeys::complete`] or [`IdempotencyKeys::release`].
+    New,
+    /// The same bundle was already sent with this key, as the bundle with this uuid.
+    Duplicate(String),
+    /// A different bundle was already sent with this key, as the bundle with this uuid.
+    Conflict(String),
+    /// A request with this key is still being submitted.
+    InFlight,
+}
+
+#[derive(Debug)]
+struct Entry {
+    /// When the key was taken or its bundle sent, whichever was last.
+    at: Instant,
+    fingerprint: Hash,
+    /// None while the bundle is being submitted.
+    uuid: Option<String>,
+}
+
+/// Idempotency keys supplied by the clients of a submission API, remembered for `ttl` after their
+/// bundle was sent, so a client retrying a request it didn't get an answer to is told the uuid of
+/// the bundle it already sent rather than sending it twice.
+#[derive(Debug)]
+pub struct IdempotencyKeys {
+    ttl: Duration,
+    entries: HashMap<String, Entry>,
+    expiry: VecDeque<(Instant, String)>,
+}
+
+impl IdempotencyKeys {
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            entries: HashMap::new(),
+            expiry: VecDeque::new(),
+        }
+    }
+
+    /// Looks up `key` for a request to send `transactions`, taking it if unused. Keys held by
+    /// requests that never completed nor released them are freed after `ttl` as well.
+    pub fn check(&mut self, key: &str, transactions: &[Vec<u8>], now: Instant) -> IdempotencyCheck {
+        self.expire(now);
+        let fingerprint = hashv(&transactions.iter().map(Vec::as_slice).collect::<Vec<_>>());
+        match self.entries.get(key) {
+            Some(Entry { uuid: None, .. }) => IdempotencyCheck::InFlight,
+            Some(Entry {
+                uuid: Some(uuid),
+                fingerprint: sent,
+                ..
+            }) if *sent == fingerprint => IdempotencyCheck::Duplicate(uuid.clone()),
+            Some(Entry {
+                uuid: Some(uuid), ..
+            }) => IdempotencyCheck::Conflict(uuid.clone()),
+            None => {
+                self.insert(key, now, fingerprint, None);
+                IdempotencyCheck::New
+            }
+        }
+    }
+
+    /// Records that the request holding `key` sent its bundle as `uuid`.
+    pub fn complete(&mut self, key: &str, uuid: String, now: Instant) {
+        if let Some(fingerprint) = self.entries.get(key).map(|entry| entry.fingerprint) {
+            self.insert(key, now, fingerprint, Some(uuid));
+        }
+    }
+
+    /// Frees `key` after its request failed without sending anything, so a retry can.
+    pub fn release(&mut self, key: &str) {
+        if matches!(self.entries.get(key), Some(Entry { uuid: None, .. })) {
+            self.entries.remove(key);
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    fn insert(&mut self, key: &str, at: Instant, fingerprint: Hash, uuid: Option<String>) {
+        self.entries.insert(
+            key.to_string(),
+            Entry {
+                at,
+                fingerprint,
+                uuid,
+            },
+        );
+        self.expiry.push_back((at, key.to_string()));
+    }
+
+    fn expire(&mut self, now: Instant) {
+        while let Some((at, _)) = self.expiry.front() {
+            if now.duration_since(*at) < self.ttl {
+                break;
+            }
+            let (at, key) = self.expiry.pop_front().expect("front exists");
+            // a key taken again since has a later entry of its own in the queue
+            if self.entries.get(&key).is_some_and(|entry| entry.at == at) {
+                self.entries.remove(&key);
+            }
+        }
+    }
+}
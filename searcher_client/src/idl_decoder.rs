@@ -0,0 +1,639 @@
+use std::{
+    collections::HashMap,
+    fmt::Write as _,
+    fs,
+    io::{self, Read},
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use flate2::read::ZlibDecoder;
+use serde::Deserialize;
+use serde_json::{json, Map, Value};
+use solana_client::{client_error::ClientError, nonblocking::rpc_client::RpcClient};
+use solana_sdk::{hash::hashv, pubkey::Pubkey, transaction::VersionedTransaction};
+use thiserror::Error;
+
+use crate::transaction_decoder::program_name;
+
+pub(crate) const DISCRIMINATOR_LEN: usize = 8;
+/// Seed of the account Anchor publishes a program's IDL to.
+const IDL_ACCOUNT_SEED: &str = "anchor:idl";
+/// Discriminator, authority and data length precede the compressed IDL.
+const IDL_ACCOUNT_HEADER_LEN: usize = DISCRIMINATOR_LEN + 32 + 4;
+
+#[derive(Debug, Error)]
+pub enum IdlError {
+    #[error("failed to read IDL {0:?}: {1}")]
+    Io(PathBuf, io::Error),
+    #[error("invalid IDL: {0}")]
+    Parse(#[from] serde_json::Error),
+    #[error("IDL {0:?} doesn't say which program it's for")]
+    MissingAddress(PathBuf),
+    #[error("no IDL published for {0}")]
+    NotPublished(Pubkey),
+    #[error("invalid IDL account for {0}: {1}")]
+    InvalidIdlAccount(Pubkey, String),
+    #[error("rpc error {0}")]
+    Rpc(#[from] ClientError),
+    #[error("unknown type {0:?}")]
+    UnknownType(String),
+    #[error("data ends at byte {0}, {1} more needed")]
+    ShortData(usize, usize),
+    #[error("invalid enum variant {1} of {0}")]
+    InvalidVariant(String, u8),
+}
+
+/// An Anchor IDL, either the legacy format or the one introduced in Anchor 0.30. Only what's
+/// needed to decode instructions and accounts is parsed.
+#[derive(Clone, Debug, Deserialize)]
+pub struct Idl {
+    #[serde(default)]
+    pub address: Option<String>,
+    #[serde(default)]
+    pub name: Option<String>,
+    #[serde(default)]
+    pub metadata: Option<IdlMetadata>,
+    #[serde(default)]
+    pub instructions: Vec<IdlInstruction>,
+    #[serde(default)]
+    pub accounts: Vec<IdlAccountDef>,
+    #[serde(default)]
+    pub types: Vec<IdlTypeDef>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct IdlMetadata {
+    #[serde(default)]
+    pub name: Option<String>,
+    #[serde(default)]
+    pub address: Option<String>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct IdlInstruction {
+    pub name: String,
+    #[serde(default)]
+    pub discriminator: Option<Vec<u8>>,
+    /// Accounts in order, legacy IDLs nest them in named groups.
+    #[serde(default)]
+    pub accounts: Vec<Value>,
+    #[serde(default)]
+    pub args: Vec<IdlField>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct IdlAccountDef {
+    pub name: String,
+    #[serde(default)]
+    pub discriminator: Option<Vec<u8>>,
+    /// Legacy IDLs define the layout here, newer ones in `types`.
+    #[serde(rename = "type", default)]
+    pub ty: Option<IdlTypeDefTy>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct IdlTypeDef {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub ty: IdlTypeDefTy,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+pub enum IdlTypeDefTy {
+    Struct {
+        #[serde(default)]
+        fields: Option<IdlFields>,
+    },
+    Enum {
+        variants: Vec<IdlVariant>,
+    },
+    Type {
+        alias: IdlType,
+    },
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(untagged)]
+pub enum IdlFields {
+    Named(Vec<IdlField>),
+    Tuple(Vec<IdlType>),
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct IdlVariant {
+    pub name: String,
+    #[serde(default)]
+    pub fields: Option<IdlFields>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct IdlField {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub ty: IdlType,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(untagged)]
+pub enum IdlType {
+    Primitive(String),
+    Compound(IdlCompoundType),
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum IdlCompoundType {
+    Vec(Box<IdlType>),
+    Option(Box<IdlType>),
+    COption(Box<IdlType>),
+    Array(Box<IdlType>, usize),
+    Defined(IdlDefined),
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(untagged)]
+pub enum IdlDefined {
+    Name(String),
+    Object { name: String },
+}
+
+impl IdlDefined {
+    pub(crate) fn name(&self) -> &str {
+        match self {
+            IdlDefined::Name(name) | IdlDefined::Object { name } => name,
+        }
+    }
+}
+
+impl IdlInstruction {
+    /// The IDL's discriminator, or the one Anchor derives from the name for legacy IDLs.
+    pub fn discriminator(&self) -> [u8; DISCRIMINATOR_LEN] {
+        explicit_discriminator(&self.discriminator)
+            .unwrap_or_else(|| sighash("global", &to_snake_case(&self.name)))
+    }
+}
+
+impl Idl {
+    pub fn program_name(&self) -> Option<&str> {
+        self.metadata
+            .as_ref()
+            .and_then(|m| m.name.as_deref())
+            .or(self.name.as_deref())
+    }
+
+    /// The program the IDL is for, if it says.
+    pub fn program_id(&self) -> Option<Pubkey> {
+        self.address
+            .as_deref()
+            .or(self.metadata.as_ref().and_then(|m| m.address.as_deref()))
+            .and_then(|address| Pubkey::from_str(address).ok())
+    }
+
+    /// The layout of a defined type, which legacy IDLs may give with the account instead.
+    pub(crate) fn type_def(&self, name: &str) -> Result<&IdlTypeDefTy, IdlError> {
+        self.types
+            .iter()
+            .find(|t| t.name == name)
+            .map(|t| &t.ty)
+            .or_else(|| {
+                self.accounts
+                    .iter()
+                    .find(|a| a.name == name)
+                    .and_then(|a| a.ty.as_ref())
+            })
+            .ok_or_else(|| IdlError::UnknownType(name.to_string()))
+    }
+}
+
+/// An instruction decoded against its program's IDL.
+#[derive(Clone, Debug)]
+pub struct DecodedInstruction {
+    pub program: String,
+    pub name: String,
+    /// Account names from the IDL paired with the keys passed. Accounts beyond the IDL's list
+    /// are named `remaining`.
+    pub accounts: Vec<(String, Pubkey)>,
+    pub args: Value,
+}
+
+/// Account data decoded against its owner's IDL.
+#[derive(Clone, Debug)]
+pub struct DecodedAccount {
+    pub program: String,
+    pub name: String,
+    pub fields: Value,
+}
+
+struct ProgramIdl {
+    name: String,
+    idl: Idl,
+    /// Instruction index by discriminator.
+    instructions: HashMap<[u8; DISCRIMINATOR_LEN], usize>,
+    /// Account index by discriminator.
+    accounts: HashMap<[u8; DISCRIMINATOR_LEN], usize>,
+}
+
+impl ProgramIdl {
+    fn new(program_id: &Pubkey, idl: Idl) -> Self {
+        let instructions = idl
+            .instructions
+            .iter()
+            .enumerate()
+            .map(|(i, ix)| (ix.discriminator(), i))
+            .collect();
+        let accounts = idl
+            .accounts
+            .iter()
+            .enumerate()
+            .map(|(i, account)| {
+                let discriminator = explicit_discriminator(&account.discriminator)
+                    .unwrap_or_else(|| sighash("account", &account.name));
+                (discriminator, i)
+            })
+            .collect();
+        Self {
+            name: idl
+                .program_name()
+                .map(str::to_string)
+                .unwrap_or_else(|| program_id.to_string()),
+            idl,
+            instructions,
+            accounts,
+        }
+    }
+
+    fn type_def(&self, name: &str) -> Result<&IdlTypeDefTy, IdlError> {
+        self.idl.type_def(name)
+    }
+}
+
+/// IDLs by program, loaded from files or fetched from where Anchor publishes them on-chain, to
+/// decode instructions and account data of any program that has one.
+#[derive(Default)]
+pub struct IdlRegistry {
+    programs: HashMap<Pubkey, ProgramIdl>,
+}
+
+impl IdlRegistry {
+    pub fn insert(&mut self, program_id: Pubkey, idl: Idl) {
+        self.programs
+            .insert(program_id, ProgramIdl::new(&program_id, idl));
+    }
+
+    pub fn contains(&self, program_id: &Pubkey) -> bool {
+        self.programs.contains_key(program_id)
+    }
+
+    /// Loads an IDL file, or every `.json` file in a directory. Returns the programs loaded.
+    pub fn load_path(&mut self, path: &Path) -> Result<Vec<Pubkey>, IdlError> {
+        let io_error = |e| IdlError::Io(path.to_path_buf(), e);
+        if !path.is_dir() {
+            return self.load_file(path).map(|program_id| vec![program_id]);
+        }
+        let mut loaded = vec![];
+        for entry in fs::read_dir(path).map_err(io_error)? {
+            let path = entry.map_err(io_error)?.path();
+            if path.extension().is_some_and(|ext| ext == "json") {
+                loaded.push(self.load_file(&path)?);
+            }
+        }
+        Ok(loaded)
+    }
+
+    fn load_file(&mut self, path: &Path) -> Result<Pubkey, IdlError> {
+        let bytes = fs::read(path).map_err(|e| IdlError::Io(path.to_path_buf(), e))?;
+        let idl: Idl = serde_json::from_slice(&bytes)?;
+        let program_id = idl
+            .program_id()
+            .ok_or_else(|| IdlError::MissingAddress(path.to_path_buf()))?;
+        self.insert(program_id, idl);
+        Ok(program_id)
+    }
+
+    /// Fetches the IDL the program published on-chain with `anchor idl init`.
+    pub async fn fetch(
+        &mut self,
+        rpc_client: &RpcClient,
+        program_id: &Pubkey,
+    ) -> Result<(), IdlError> {
+        let invalid = |reason: &str| IdlError::InvalidIdlAccount(*program_id, reason.into());
+        let base = Pubkey::find_program_address(&[], program_id).0;
+        let address = Pubkey::create_with_seed(&base, IDL_ACCOUNT_SEED, program_id)
+            .map_err(|e| invalid(&e.to_string()))?;
+        let account = rpc_client
+            .get_account_with_commitment(&address, rpc_client.commitment())
+            .await?
+            .value
+            .ok_or(IdlError::NotPublished(*program_id))?;
+        let data_len = account
+            .data
+            .get(IDL_ACCOUNT_HEADER_LEN - 4..IDL_ACCOUNT_HEADER_LEN)
+            .map(|b| u32::from_le_bytes(b.try_into().unwrap()) as usize)
+            .ok_or_else(|| invalid("short account"))?;
+        let compressed = account
+            .data
+            .get(IDL_ACCOUNT_HEADER_LEN..IDL_ACCOUNT_HEADER_LEN + data_len)
+            .ok_or_else(|| invalid("short account"))?;
+        let mut json = vec![];
+        ZlibDecoder::new(compressed)
+            .read_to_end(&mut json)
+            .map_err(|e| invalid(&e.to_string()))?;
+        self.insert(*program_id, serde_json::from_slice(&json)?);
+        Ok(())
+    }
+
+    /// Decodes an instruction of a program with a known IDL. None if the program has no IDL or
+    /// the discriminator matches none of its instructions.
+    pub fn decode_instruction(
+        &self,
+        program_id: &Pubkey,
+        data: &[u8],
+        accounts: &[Pubkey],
+    ) -> Option<Result<DecodedInstruction, IdlError>> {
+        let program = self.programs.get(program_id)?;
+        let index = program.instructions.get(data.get(..DISCRIMINATOR_LEN)?)?;
+        let ix = &program.idl.instructions[*index];
+
+        let mut names = vec![];
+        flatten_account_names(&ix.accounts, &mut names);
+        let accounts = accounts
+            .iter()
+            .enumerate()
+            .map(|(i, key)| {
+                let name = names.get(i).cloned().unwrap_or_else(|| "remaining".into());
+                (name, *key)
+            })
+            .collect();
+
+        let mut reader = Reader::new(&data[DISCRIMINATOR_LEN..]);
+        let args = decode_named_fields(program, &ix.args, &mut reader);
+        Some(args.map(|args| DecodedInstruction {
+            program: program.name.clone(),
+            name: ix.name.clone(),
+            accounts,
+            args,
+        }))
+    }
+
+    /// Decodes account data owned by a program with a known IDL. None if the owner has no IDL
+    /// or the discriminator matches none of its accounts.
+    pub fn decode_account(
+        &self,
+        owner: &Pubkey,
+        data: &[u8],
+    ) -> Option<Result<DecodedAccount, IdlError>> {
+        let program = self.programs.get(owner)?;
+        let index = program.accounts.get(data.get(..DISCRIMINATOR_LEN)?)?;
+        let account = &program.idl.accounts[*index];
+        let mut reader = Reader::new(&data[DISCRIMINATOR_LEN..]);
+        let fields = match &account.ty {
+            Some(ty) => decode_type_def(program, &account.name, ty, &mut reader),
+            None => program
+                .type_def(&account.name)
+                .and_then(|ty| decode_type_def(program, &account.name, ty, &mut reader)),
+        };
+        Some(fields.map(|fields| DecodedAccount {
+            program: program.name.clone(),
+            name: account.name.clone(),
+            fields,
+        }))
+    }
+
+    /// Describes every instruction of `tx` whose program has an IDL, one block per instruction.
+    /// Instructions referencing lookup table accounts show those as unresolved.
+    pub fn describe_transaction(&self, tx: &VersionedTransaction) -> String {
+        (0..tx.message.instructions().len())
+            .filter_map(|index| self.describe_instruction(tx, index))
+            .collect()
+    }
+
+    /// Describes the instruction at `index` of `tx`, None if its program has no IDL.
+    pub fn describe_instruction(&self, tx: &VersionedTransaction, index: usize) -> Option<String> {
+        let keys = tx.message.static_account_keys();
+        let ix = tx.message.instructions().get(index)?;
+        let program_id = keys.get(ix.program_id_index as usize)?;
+        let accounts: Vec<Pubkey> = ix
+            .accounts
+            .iter()
+            .map(|index| keys.get(*index as usize).copied().unwrap_or_default())
+            .collect();
+        let mut out = String::new();
+        match self.decode_instruction(program_id, &ix.data, &accounts)? {
+            Ok(decoded) => {
+                let _ = writeln!(out, "  [{index}] {}::{}", decoded.program, decoded.name);
+                for ((name, key), key_index) in decoded.accounts.iter().zip(&ix.accounts) {
+                    if (*key_index as usize) < keys.len() {
+                        let _ = writeln!(out, "      {name}: {key}");
+                    } else {
+                        let _ = writeln!(out, "      {name}: <lookup table>");
+                    }
+                }
+                let _ = writeln!(out, "      args: {}", decoded.args);
+            }
+            Err(e) => {
+                let name = program_name(program_id).unwrap_or("program");
+                let _ = writeln!(
+                    out,
+                    "  [{index}] {name} {program_id}: failed to decode: {e}"
+                );
+            }
+        }
+        Some(out)
+    }
+}
+
+fn explicit_discriminator(discriminator: &Option<Vec<u8>>) -> Option<[u8; DISCRIMINATOR_LEN]> {
+    discriminator.as_deref().and_then(|d| d.try_into().ok())
+}
+
+fn sighash(namespace: &str, name: &str) -> [u8; DISCRIMINATOR_LEN] {
+    let hash = hashv(&[namespace.as_bytes(), b":", name.as_bytes()]);
+    hash.to_bytes()[..DISCRIMINATOR_LEN].try_into().unwrap()
+}
+
+/// Legacy IDLs name instructions in camelCase, their discriminators hash the Rust snake_case
+/// name.
+pub(crate) fn to_snake_case(name: &str) -> String {
+    let mut snake = String::with_capacity(name.len() + 4);
+    for (i, c) in name.chars().enumerate() {
+        if c.is_ascii_uppercase() {
+            if i > 0 {
+                snake.push('_');
+            }
+            snake.push(c.to_ascii_lowercase());
+        } else {
+            snake.push(c);
+        }
+    }
+    snake
+}
+
+fn flatten_account_names(accounts: &[Value], names: &mut Vec<String>) {
+    for account in accounts {
+        match account.get("accounts").and_then(Value::as_array) {
+            Some(group) => flatten_account_names(group, names),
+            None => names.push(
+                account
+                    .get("name")
+                    .and_then(Value::as_str)
+                    .unwrap_or("unnamed")
+                    .to_string(),
+            ),
+        }
+    }
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Self { data, offset: 0 }
+    }
+
+    fn take(&mut self, len: usize) -> Result<&'a [u8], IdlError> {
+        let remaining = self.data.len() - self.offset;
+        if len > remaining {
+            return Err(IdlError::ShortData(self.offset, len - remaining));
+        }
+        let bytes = &self.data[self.offset..self.offset + len];
+        self.offset += len;
+        Ok(bytes)
+    }
+
+    fn array<const N: usize>(&mut self) -> Result<[u8; N], IdlError> {
+        Ok(self.take(N)?.try_into().unwrap())
+    }
+
+    fn read_len(&mut self) -> Result<usize, IdlError> {
+        let len = u32::from_le_bytes(self.array()?) as usize;
+        // every element takes at least a byte, a larger length means corrupt data
+        let remaining = self.data.len() - self.offset;
+        if len > remaining {
+            return Err(IdlError::ShortData(self.offset, len - remaining));
+        }
+        Ok(len)
+    }
+}
+
+fn decode_named_fields(
+    program: &ProgramIdl,
+    fields: &[IdlField],
+    reader: &mut Reader,
+) -> Result<Value, IdlError> {
+    let mut decoded = Map::new();
+    for field in fields {
+        decoded.insert(field.name.clone(), decode_type(program, &field.ty, reader)?);
+    }
+    Ok(Value::Object(decoded))
+}
+
+fn decode_fields(
+    program: &ProgramIdl,
+    fields: &IdlFields,
+    reader: &mut Reader,
+) -> Result<Value, IdlError> {
+    match fields {
+        IdlFields::Named(fields) => decode_named_fields(program, fields, reader),
+        IdlFields::Tuple(types) => types
+            .iter()
+            .map(|ty| decode_type(program, ty, reader))
+            .collect(),
+    }
+}
+
+fn decode_type_def(
+    program: &ProgramIdl,
+    name: &str,
+    ty: &IdlTypeDefTy,
+    reader: &mut Reader,
+) -> Result<Value, IdlError> {
+    match ty {
+        IdlTypeDefTy::Struct { fields: None } => Ok(json!({})),
+        IdlTypeDefTy::Struct {
+            fields: Some(fields),
+        } => decode_fields(program, fields, reader),
+        IdlTypeDefTy::Enum { variants } => {
+            let [index] = reader.array()?;
+            let variant = variants
+                .get(index as usize)
+                .ok_or_else(|| IdlError::InvalidVariant(name.to_string(), index))?;
+            match &variant.fields {
+                None => Ok(Value::String(variant.name.clone())),
+                Some(fields) => {
+                    let mut decoded = Map::new();
+                    decoded.insert(
+                        variant.name.clone(),
+                        decode_fields(program, fields, reader)?,
+                    );
+                    Ok(Value::Object(decoded))
+                }
+            }
+        }
+        IdlTypeDefTy::Type { alias } => decode_type(program, alias, reader),
+    }
+}
+
+fn decode_type(program: &ProgramIdl, ty: &IdlType, reader: &mut Reader) -> Result<Value, IdlError> {
+    let primitive = match ty {
+        IdlType::Primitive(primitive) => primitive.as_str(),
+        IdlType::Compound(IdlCompoundType::Vec(ty)) => {
+            let len = reader.read_len()?;
+            return (0..len).map(|_| decode_type(program, ty, reader)).collect();
+        }
+        IdlType::Compound(IdlCompoundType::Option(ty)) => {
+            let [tag] = reader.array()?;
+            return match tag {
+                0 => Ok(Value::Null),
+                _ => decode_type(program, ty, reader),
+            };
+        }
+        IdlType::Compound(IdlCompoundType::COption(ty)) => {
+            let tag = u32::from_le_bytes(reader.array()?);
+            return match tag {
+                0 => Ok(Value::Null),
+                _ => decode_type(program, ty, reader),
+            };
+        }
+        IdlType::Compound(IdlCompoundType::Array(ty, len)) => {
+            return (0..*len)
+                .map(|_| decode_type(program, ty, reader))
+                .collect();
+        }
+        IdlType::Compound(IdlCompoundType::Defined(defined)) => {
+            let ty = program.type_def(defined.name())?;
+            return decode_type_def(program, defined.name(), ty, reader);
+        }
+    };
+    Ok(match primitive {
+        "bool" => Value::Bool(reader.array::<1>()?[0] != 0),
+        "u8" => json!(u8::from_le_bytes(reader.array()?)),
+        "i8" => json!(i8::from_le_bytes(reader.array()?)),
+        "u16" => json!(u16::from_le_bytes(reader.array()?)),
+        "i16" => json!(i16::from_le_bytes(reader.array()?)),
+        "u32" => json!(u32::from_le_bytes(reader.array()?)),
+        "i32" => json!(i32::from_le_bytes(reader.array()?)),
+        "u64" => json!(u64::from_le_bytes(reader.array()?)),
+        "i64" => json!(i64::from_le_bytes(reader.array()?)),
+        // JSON numbers lose precision past 2^53
+        "u128" => json!(u128::from_le_bytes(reader.array()?).to_string()),
+        "i128" => json!(i128::from_le_bytes(reader.array()?).to_string()),
+        "f32" => json!(f32::from_le_bytes(reader.array()?)),
+        "f64" => json!(f64::from_le_bytes(reader.array()?)),
+        "publicKey" | "pubkey" => json!(Pubkey::new_from_array(reader.array()?).to_string()),
+        "string" => {
+            let len = reader.read_len()?;
+            json!(String::from_utf8_lossy(reader.take(len)?))
+        }
+        "bytes" => {
+            let len = reader.read_len()?;
+            json!(STANDARD.encode(reader.take(len)?))
+        }
+        other => return Err(IdlError::UnknownType(other.to_string())),
+    })
+}
@@ -0,0 +1,275 @@
+use std::{
+    collections::BTreeMap,
+    fmt::{self, Display, Formatter},
+    fs, io,
+    path::Path,
+    str::FromStr,
+};
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use serde::Deserialize;
+use solana_sdk::{
+    bs58,
+    instruction::{AccountMeta, Instruction},
+    pubkey::Pubkey,
+};
+use spl_associated_token_account::get_associated_token_address_with_program_id;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum TemplateError {
+    #[error("failed to read bundle spec: {0}")]
+    Io(#[from] io::Error),
+    #[error("invalid bundle spec: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("invalid pubkey {0:?}")]
+    InvalidPubkey(String),
+    #[error("unknown placeholder {0:?}")]
+    UnknownPlaceholder(String),
+    #[error("{{tip_account}} is used but no tip account was given")]
+    NoTipAccount,
+    #[error("address {0:?} is a reserved placeholder name")]
+    ReservedName(String),
+    #[error("invalid {0} instruction data: {1}")]
+    InvalidData(DataEncoding, String),
+}
+
+/// How an instruction template's data is written.
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum DataEncoding {
+    /// With or without a `0x` prefix.
+    #[default]
+    Hex,
+    Base58,
+    Base64,
+}
+
+impl DataEncoding {
+    pub fn decode(&self, data: &str) -> Result<Vec<u8>, TemplateError> {
+        let invalid = |e: String| TemplateError::InvalidData(*self, e);
+        match self {
+            Self::Hex => {
+                let hex = data.strip_prefix("0x").unwrap_or(data);
+                if !hex.len().is_multiple_of(2) {
+                    return Err(invalid("odd number of digits".to_string()));
+                }
+                (0..hex.len())
+                    .step_by(2)
+                    .map(|i| {
+                        hex.get(i..i + 2)
+                            .and_then(|byte| u8::from_str_radix(byte, 16).ok())
+                            .ok_or_else(|| invalid(format!("invalid digits at {i}")))
+                    })
+                    .collect()
+            }
+            Self::Base58 => bs58::decode(data)
+                .into_vec()
+                .map_err(|e| invalid(e.to_string())),
+            Self::Base64 => STANDARD.decode(data).map_err(|e| invalid(e.to_string())),
+        }
+    }
+}
+
+impl Display for DataEncoding {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            DataEncoding::Hex => write!(f, "hex"),
+            DataEncoding::Base58 => write!(f, "base58"),
+            DataEncoding::Base64 => write!(f, "base64"),
+        }
+    }
+}
+
+/// An account passed to an instruction template. `pubkey` is a base58 pubkey or a placeholder,
+/// see [`Placeholders::resolve`].
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AccountTemplate {
+    pub pubkey: String,
+    #[serde(default)]
+    pub is_signer: bool,
+    #[serde(default)]
+    pub is_writable: bool,
+}
+
+/// A raw instruction for a program no builder exists for: its program id, accounts and data
+/// as they go on the wire, with placeholders for the addresses only known when it's sent.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct InstructionTemplate {
+    pub program_id: String,
+    #[serde(default)]
+    pub accounts: Vec<AccountTemplate>,
+    #[serde(default)]
+    pub data: String,
+    #[serde(default)]
+    pub encoding: DataEncoding,
+}
+
+impl InstructionTemplate {
+    pub fn resolve(&self, placeholders: &Placeholders) -> Result<Instruction, TemplateError> {
+        Ok(Instruction {
+            program_id: placeholders.resolve(&self.program_id)?,
+            accounts: self
+                .accounts
+                .iter()
+                .map(|account| {
+                    Ok(AccountMeta {
+                        pubkey: placeholders.resolve(&account.pubkey)?,
+                        is_signer: account.is_signer,
+                        is_writable: account.is_writable,
+                    })
+                })
+                .collect::<Result<_, TemplateError>>()?,
+            data: self.encoding.decode(&self.data)?,
+        })
+    }
+}
+
+/// The addresses placeholders in instruction templates stand for.
+#[derive(Clone, Debug)]
+pub struct Placeholders {
+    payer: Pubkey,
+    tip_account: Option<Pubkey>,
+    addresses: BTreeMap<String, Pubkey>,
+}
+
+impl Placeholders {
+    pub fn new(payer: Pubkey) -> Self {
+        Self {
+            payer,
+            tip_account: None,
+            addresses: BTreeMap::new(),
+        }
+    }
+
+    pub fn with_tip_account(mut self, tip_account: Pubkey) -> Self {
+        self.tip_account = Some(tip_account);
+        self
+    }
+
+    /// Has `{name}` stand for `address`.
+    pub fn with_address(mut self, name: &str, address: Pubkey) -> Result<Self, TemplateError> {
+        if matches!(name, "payer" | "tip_account" | "ata" | "ata2022") {
+            return Err(TemplateError::ReservedName(name.to_string()));
+        }
+        self.addresses.insert(name.to_string(), address);
+        Ok(self)
+    }
+
+    /// Resolves a base58 pubkey or one of the placeholders:
+    ///
+    /// - `{payer}` and `{tip_account}`
+    /// - `{name}` for an address added with [`Self::with_address`]
+    /// - `{ata:MINT}` for the payer's associated token account of `MINT`, and `{ata:OWNER:MINT}`
+    ///   for another wallet's, with `ata2022` in place of `ata` for Token-2022 mints. `OWNER`
+    ///   and `MINT` are pubkeys or placeholder names themselves, e.g. `{ata:pool:usdc}`.
+    pub fn resolve(&self, pubkey: &str) -> Result<Pubkey, TemplateError> {
+        let Some(placeholder) = pubkey
+            .strip_prefix('{')
+            .and_then(|pubkey| pubkey.strip_suffix('}'))
+        else {
+            return Pubkey::from_str(pubkey)
+                .map_err(|_| TemplateError::InvalidPubkey(pubkey.to_string()));
+        };
+        let unknown = || TemplateError::UnknownPlaceholder(pubkey.to_string());
+        let mut parts = placeholder.split(':');
+        let token_program = match parts.next() {
+            Some("ata") => spl_token::id(),
+            Some("ata2022") => spl_token_2022::id(),
+            _ => return self.name(placeholder),
+        };
+        let (owner, mint) = match (parts.next(), parts.next(), parts.next()) {
+            (Some(mint), None, None) => (self.payer, self.address(mint)?),
+            (Some(owner), Some(mint), None) => (self.address(owner)?, self.address(mint)?),
+            _ => return Err(unknown()),
+        };
+        Ok(get_associated_token_address_with_program_id(
+            &owner,
+            &mint,
+            &token_program,
+        ))
+    }
+
+    /// A pubkey or placeholder name within an `ata` placeholder.
+    fn address(&self, address: &str) -> Result<Pubkey, TemplateError> {
+        Pubkey::from_str(address).or_else(|_| self.name(address))
+    }
+
+    fn name(&self, name: &str) -> Result<Pubkey, TemplateError> {
+        match name {
+            "payer" => Ok(self.payer),
+            "tip_account" => self.tip_account.ok_or(TemplateError::NoTipAccount),
+            name => self
+                .addresses
+                .get(name)
+                .copied()
+                .ok_or_else(|| TemplateError::UnknownPlaceholder(format!("{{{name}}}"))),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TransactionTemplate {
+    pub instructions: Vec<InstructionTemplate>,
+}
+
+/// A bundle spec file of raw instructions, grouped into the bundle's transactions, such as:
+///
+/// ```json
+/// {
+///   "addresses": {"pool": "58oQChx4yWmvKdwLLZzBi4ChoCc2fqCUWBkwMihLYQo2"},
+///   "transactions": [{"instructions": [{
+///     "program_id": "675kPX9MHTjS2zt1qfr1NYHuzeLXfQM9H24wFSUt1Mp8",
+///     "accounts": [
+///       {"pubkey": "{payer}", "is_signer": true, "is_writable": true},
+///       {"pubkey": "{pool}", "is_writable": true},
+///       {"pubkey": "{ata:So11111111111111111111111111111111111111112}", "is_writable": true}
+///     ],
+///     "data": "09a0860100000000000000000000000000"
+///   }]}]
+/// }
+/// ```
+///
+/// Names in `addresses` are used as `{name}` placeholders, next to the ones
+/// [`Placeholders::resolve`] lists.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct BundleTemplate {
+    #[serde(default)]
+    pub addresses: BTreeMap<String, String>,
+    pub transactions: Vec<TransactionTemplate>,
+}
+
+impl BundleTemplate {
+    pub fn load(path: &Path) -> Result<Self, TemplateError> {
+        Ok(serde_json::from_str(&fs::read_to_string(path)?)?)
+    }
+
+    /// The instructions of each transaction, with the spec's addresses added to `placeholders`.
+    pub fn resolve(
+        &self,
+        placeholders: &Placeholders,
+    ) -> Result<Vec<Vec<Instruction>>, TemplateError> {
+        let placeholders = self.addresses.iter().try_fold(
+            placeholders.clone(),
+            |placeholders, (name, address)| {
+                let address = Pubkey::from_str(address)
+                    .map_err(|_| TemplateError::InvalidPubkey(address.clone()))?;
+                placeholders.with_address(name, address)
+            },
+        )?;
+        self.transactions
+            .iter()
+            .map(|transaction| {
+                transaction
+                    .instructions
+                    .iter()
+                    .map(|instruction| instruction.resolve(&placeholders))
+                    .collect()
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,476 @@
+use std::collections::HashSet;
+
+use jito_protos::packet::Packet as ProtoPacket;
+use solana_metrics::datapoint_info;
+use solana_sdk::pubkey::Pubkey;
+
+/// A bloom filter over pubkeys. Most pubkeys are already uniformly distributed, as ed25519 points
+/// or sha256 outputs, so two words of the key are only mixed, to spread out vanity and
+/// sequential keys, instead of hashing all of it.
+#[derive(Clone, Debug)]
+pub struct PubkeyBloom {
+    bits: Vec<u64>,
+    /// Number of bits minus one, the number of bits is a power of two.
+    mask: u64,
+    num_hashes: u32,
+}
+
+impl PubkeyBloom {
+    /// Sized for `capacity` pubkeys at `false_positive_rate`.
+    pub fn new(capacity: usize, false_positive_rate: f64) -> Self {
+        let capacity = capacity.max(1) as f64;
+        let ln2 = std::f64::consts::LN_2;
+        let num_bits = (-capacity * false_positive_rate.ln() / (ln2 * ln2))
+            .ceil()
+            .max(64.0) as u64;
+        let num_bits = num_bits.next_power_of_two();
+        let num_hashes = ((num_bits as f64 / capacity) * ln2)
+            .round()
+            .clamp(1.0, 16.0) as u32;
+        Self {
+            bits: vec![0; (num_bits / 64) as usize],
+            mask: num_bits - 1,
+            num_hashes,
+        }
+    }
+
+    /// Bit positions of a key, by double hashing over two words of it.
+    fn positions(&self, key: &[u8; 32]) -> impl Iterator<Item = u64> + '_ {
+        let word =
+            |i: usize| u64::from_le_bytes(key[i * 8..i * 8 + 8].try_into().expect("8 bytes"));
+        let h1 = mix(word(0) ^ word(2));
+        let h2 = mix(h1 ^ word(1) ^ word(3)) | 1;
+        (0..self.num_hashes as u64).map(move |i| h1.wrapping_add(i.wrapping_mul(h2)) & self.mask)
+    }
+
+    pub fn insert(&mut self, key: &Pubkey) {
+        let positions: Vec<u64> = self.positions(&key.to_bytes()).collect();
+        for position in positions {
+            self.bits[(position / 64) as usize] |= 1 << (position % 64);
+        }
+    }
+
+    /// False if the key was never inserted, true if it probably was.
+    pub fn contains(&self, key: &[u8; 32]) -> bool {
+        self.positions(key)
+            .all(|position| self.bits[(position / 64) as usize] & (1 << (position % 64)) != 0)
+    }
+}
+
+/// The splitmix64 finalizer.
+fn mix(mut x: u64) -> u64 {
+    x = (x ^ (x >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    x ^ (x >> 31)
+}
+
+/// How mempool packets fared against an [`InterestFilter`].
+#[derive(Clone, Debug, Default)]
+pub struct InterestFilterStats {
+    /// Packets write-locking an account of interest.
+    pub hits: u64,
+    pub misses: u64,
+    /// Packets the bloom filter let through that write-lock no account of interest.
+    pub false_positives: u64,
+    /// Packets passed through because they write-lock accounts from lookup tables, which
+    /// can't be resolved from the packet alone.
+    pub unresolved: u64,
+    /// Packets passed through because they couldn't be read, left for conversion to report.
+    pub unparsed: u64,
+}
+
+/// Drops mempool packets that write-lock none of the accounts a strategy is interested in,
+/// before they're deserialized. The static account keys are read straight from the packet bytes
+/// and checked against a bloom filter, so a packet that touches nothing of interest costs a few
+/// bit lookups. Bloom filter hits are confirmed against the exact set.
+///
+/// Transactions that write-lock accounts through lookup tables are let through, since those
+/// accounts aren't in the packet.
+#[derive(Clone, Debug)]
+pub struct InterestFilter {
+    bloom: PubkeyBloom,
+    accounts: HashSet<Pubkey>,
+    false_positive_rate: f64,
+    stats: InterestFilterStats,
+}
+
+impl InterestFilter {
+    pub fn new(accounts: impl IntoIterator<Item = Pubkey>, false_positive_rate: f64) -> Self {
+        let accounts: HashSet<Pubkey> = accounts.into_iter().collect();
+        let mut filter = Self {
+            bloom: PubkeyBloom::new(accounts.len(), false_positive_rate),
+            accounts: HashSet::new(),
+            false_positive_rate,
+            stats: InterestFilterStats::default(),
+        };
+        filter.extend(accounts);
+        filter
+    }
+
+    /// Adds accounts of interest, resizing the bloom filter once it holds twice what it was
+    /// sized for.
+    pub fn extend(&mut self, accounts: impl IntoIterator<Item = Pubkey>) {
+        for account in accounts {
+            if self.accounts.insert(account) {
+                self.bloom.insert(&account);
+            }
+        }
+        let capacity = (self.bloom.mask + 1) as f64 * std::f64::consts::LN_2.powi(2)
+            / -self.false_positive_rate.ln();
+        if self.accounts.len() as f64 > 2.0 * capacity {
+            self.bloom = PubkeyBloom::new(self.accounts.len() * 2, self.false_positive_rate);
+            for account in &self.accounts {
+                self.bloom.insert(account);
+            }
+        }
+    }
+
+    pub fn accounts(&self) -> &HashSet<Pubkey> {
+        &self.accounts
+    }
+
+    /// True if the packet may write-lock an account of interest.
+    pub fn matches(&mut self, packet: &ProtoPacket) -> bool {
+        let size = packet
+            .meta
+            .as_ref()
+            .map_or(packet.data.len(), |meta| meta.size as usize);
+        let Some(keys) = packet.data.get(..size).and_then(StaticKeys::parse) else {
+            self.stats.unparsed += 1;
+            return true;
+        };
+        let mut bloom_hit = false;
+        for key in keys.writable() {
+            if !self.bloom.contains(key) {
+                continue;
+            }
+            bloom_hit = true;
+            if self.accounts.contains(&Pubkey::new_from_array(*key)) {
+                self.stats.hits += 1;
+                return true;
+            }
+        }
+        if bloom_hit {
+            self.stats.false_positives += 1;
+        }
+        if keys.has_lookup_writes {
+            self.stats.unresolved += 1;
+            return true;
+        }
+        self.stats.misses += 1;
+        false
+    }
+
+    pub fn stats(&self) -> &InterestFilterStats {
+        &self.stats
+    }
+
+    /// Reports the counters and resets them.
+    pub fn report(&mut self, name: &'static str) {
+        datapoint_info!(
+            name,
+            ("hits", self.stats.hits, i64),
+            ("misses", self.stats.misses, i64),
+            ("false_positives", self.stats.false_positives, i64),
+            ("unresolved", self.stats.unresolved, i64),
+            ("unparsed", self.stats.unparsed, i64),
+            ("accounts", self.accounts.len(), i64),
+        );
+        self.stats = InterestFilterStats::default();
+    }
+}
+
+/// The static account keys of a serialized transaction, read in place.
+struct StaticKeys<'a> {
+    /// The keys, 32 bytes each.
+    keys: &'a [u8],
+    num_required_signatures: usize,
+    num_readonly_signed: usize,
+    num_readonly_unsigned: usize,
+    has_lookup_writes: bool,
+}
+
+impl<'a> StaticKeys<'a> {
+    fn parse(data: &'a [u8]) -> Option<Self> {
+        let mut reader = Reader { data, offset: 0 };
+        let num_signatures = reader.compact_u16()?;
+        reader.skip(num_signatures * 64)?;
+        let versioned = reader.peek()? & 0x80 != 0;
+        if versioned {
+            reader.skip(1)?;
+        }
+        let header = reader.take(3)?;
+        let num_keys = reader.compact_u16()?;
+        let keys = reader.take(num_keys * 32)?;
+        let mut has_lookup_writes = false;
+        if versioned {
+            reader.skip(32)?;
+            let num_instructions = reader.compact_u16()?;
+            for _ in 0..num_instructions {
+                reader.skip(1)?;
+                let num_accounts = reader.compact_u16()?;
+                reader.skip(num_accounts)?;
+                let data_len = reader.compact_u16()?;
+                reader.skip(data_len)?;
+            }
+            let num_lookups = reader.compact_u16()?;
+            for _ in 0..num_lookups {
+                reader.skip(32)?;
+                let num_writable = reader.compact_u16()?;
+                reader.skip(num_writable)?;
+                let num_readonly = reader.compact_u16()?;
+                reader.skip(num_readonly)?;
+                has_lookup_writes |= num_writable > 0;
+            }
+        }
+        let (num_required_signatures, num_readonly_signed, num_readonly_unsigned) =
+            (header[0] as usize, header[1] as usize, header[2] as usize);
+        if num_required_signatures > num_keys
+            || num_readonly_signed > num_required_signatures
+            || num_readonly_unsigned > num_keys - num_required_signatures
+        {
+            return None;
+        }
+        Some(Self {
+            keys,
+            num_required_signatures,
+            num_readonly_signed,
+            num_readonly_unsigned,
+            has_lookup_writes,
+        })
+    }
+
+    /// Keys the header marks writable. The runtime demotes a few of these, such as invoked
+    /// programs, which only makes this a superset.
+    fn writable(&self) -> impl Iterator<Item = &'a [u8; 32]> + '_ {
+        let writable_signed = self.num_required_signatures - self.num_readonly_signed;
+        let writable_unsigned_end = self.keys.len() / 32 - self.num_readonly_unsigned;
+        self.keys
+            .chunks_exact(32)
+            .map(|key| <&[u8; 32]>::try_from(key).expect("32 bytes"))
+            .enumerate()
+            .filter(move |(i, _)| {
+                *i < writable_signed
+                    || (*i >= self.num_required_signatures && *i < writable_unsigned_end)
+            })
+            .map(|(_, key)| key)
+    }
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn peek(&self) -> Option<u8> {
+        self.data.get(self.offset).copied()
+    }
+
+    fn take(&mut self, len: usize) -> Option<&'a [u8]> {
+        let bytes = self.data.get(self.offset..self.offset.checked_add(len)?)?;
+        self.offset += len;
+        Some(bytes)
+    }
+
+    fn skip(&mut self, len: usize) -> Option<()> {
+        self.take(len).map(|_| ())
+    }
+
+    /// A `short_vec` length: up to three bytes of seven bits each.
+    fn compact_u16(&mut self) -> Option<usize> {
+        let mut value = 0usize;
+        for i in 0..3 {
+            let byte = self.take(1)?[0];
+            value |= ((byte & 0x7f) as usize) << (i * 7);
+            if byte & 0x80 == 0 {
+                return Some(value);
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use jito_protos::convert::proto_packet_from_versioned_tx;
+    use solana_sdk::{
+        address_lookup_table_account::AddressLookupTableAccount,
+        hash::Hash,
+        instruction::{AccountMeta, Instruction},
+        message::{v0, VersionedMessage},
+        pubkey::Pubkey,
+        signature::{Keypair, Signer},
+        transaction::{Transaction, VersionedTransaction},
+    };
+
+    use crate::interest_filter::{InterestFilter, PubkeyBloom, StaticKeys};
+
+    /// An instruction write-locking `writable` and reading `readonly`.
+    fn instruction(writable: Pubkey, readonly: Pubkey) -> Instruction {
+        Instruction::new_with_bytes(
+            Pubkey::new_unique(),
+            &[1, 2, 3],
+            vec![
+                AccountMeta::new(writable, false),
+                AccountMeta::new_readonly(readonly, false),
+            ],
+        )
+    }
+
+    fn legacy_tx(payer: &Keypair, ix: Instruction) -> VersionedTransaction {
+        VersionedTransaction::from(Transaction::new_signed_with_payer(
+            &[ix],
+            Some(&payer.pubkey()),
+            &[payer],
+            Hash::new_unique(),
+        ))
+    }
+
+    /// A v0 transaction loading the instruction's accounts from `lookup_table` where it can.
+    fn v0_tx(
+        payer: &Keypair,
+        ix: Instruction,
+        lookup_table: &[AddressLookupTableAccount],
+    ) -> VersionedTransaction {
+        let message =
+            v0::Message::try_compile(&payer.pubkey(), &[ix], lookup_table, Hash::new_unique())
+                .unwrap();
+        VersionedTransaction::try_new(VersionedMessage::V0(message), &[payer]).unwrap()
+    }
+
+    fn writable_keys(tx: &VersionedTransaction) -> HashSet<Pubkey> {
+        let data = bincode::serialize(tx).unwrap();
+        StaticKeys::parse(&data)
+            .unwrap()
+            .writable()
+            .map(|key| Pubkey::new_from_array(*key))
+            .collect()
+    }
+
+    #[test]
+    fn test_bloom() {
+        let keys: Vec<Pubkey> = (0..1_000).map(|_| Pubkey::new_unique()).collect();
+        let mut bloom = PubkeyBloom::new(keys.len(), 0.01);
+        for key in &keys {
+            bloom.insert(key);
+        }
+        assert!(keys.iter().all(|key| bloom.contains(&key.to_bytes())));
+
+        // sequential keys are the worst case, given only two words of a key are mixed
+        let false_positives = (0..10_000)
+            .filter(|_| bloom.contains(&Pubkey::new_unique().to_bytes()))
+            .count();
+        assert!(false_positives < 300, "{false_positives} false positives");
+    }
+
+    #[test]
+    fn test_static_keys() {
+        let payer = Keypair::new();
+        let (writable, readonly) = (Pubkey::new_unique(), Pubkey::new_unique());
+
+        let tx = legacy_tx(&payer, instruction(writable, readonly));
+        assert_eq!(
+            writable_keys(&tx),
+            HashSet::from([payer.pubkey(), writable])
+        );
+        let data = bincode::serialize(&tx).unwrap();
+        assert!(!StaticKeys::parse(&data).unwrap().has_lookup_writes);
+
+        let tx = v0_tx(&payer, instruction(writable, readonly), &[]);
+        assert_eq!(
+            writable_keys(&tx),
+            HashSet::from([payer.pubkey(), writable])
+        );
+    }
+
+    #[test]
+    fn test_static_keys_with_lookup_tables() {
+        let payer = Keypair::new();
+        let (writable, readonly) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let lookup_table = AddressLookupTableAccount {
+            key: Pubkey::new_unique(),
+            addresses: vec![writable, readonly],
+        };
+
+        let tx = v0_tx(&payer, instruction(writable, readonly), &[lookup_table]);
+        let data = bincode::serialize(&tx).unwrap();
+        let keys = StaticKeys::parse(&data).unwrap();
+        assert!(keys.has_lookup_writes);
+        assert_eq!(writable_keys(&tx), HashSet::from([payer.pubkey()]));
+    }
+
+    #[test]
+    fn test_static_keys_invalid() {
+        let payer = Keypair::new();
+        let tx = v0_tx(
+            &payer,
+            instruction(Pubkey::new_unique(), Pubkey::new_unique()),
+            &[],
+        );
+        let data = bincode::serialize(&tx).unwrap();
+        for len in 0..data.len() {
+            assert!(StaticKeys::parse(&data[..len]).is_none(), "{len} bytes");
+        }
+
+        // more readonly keys than there are keys
+        let mut tx = legacy_tx(
+            &payer,
+            instruction(Pubkey::new_unique(), Pubkey::new_unique()),
+        );
+        let VersionedMessage::Legacy(message) = &mut tx.message else {
+            unreachable!();
+        };
+        message.header.num_readonly_unsigned_accounts = message.account_keys.len() as u8;
+        assert!(StaticKeys::parse(&bincode::serialize(&tx).unwrap()).is_none());
+    }
+
+    #[test]
+    fn test_interest_filter() {
+        let payer = Keypair::new();
+        let (account, other) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let mut filter = InterestFilter::new([account], 0.01);
+        let packet = |tx: &VersionedTransaction| {
+            proto_packet_from_versioned_tx(&bincode::serialize(tx).unwrap())
+        };
+
+        assert!(filter.matches(&packet(&legacy_tx(&payer, instruction(account, other)))));
+        // reading the account isn't enough
+        assert!(!filter.matches(&packet(&legacy_tx(&payer, instruction(other, account)))));
+        let lookup_table = AddressLookupTableAccount {
+            key: Pubkey::new_unique(),
+            addresses: vec![other],
+        };
+        let tx = v0_tx(
+            &payer,
+            instruction(other, Pubkey::new_unique()),
+            &[lookup_table],
+        );
+        assert!(filter.matches(&packet(&tx)));
+        let mut garbage = packet(&tx);
+        garbage.data.truncate(10);
+        assert!(filter.matches(&garbage));
+
+        let stats = filter.stats();
+        assert_eq!(
+            (stats.hits, stats.misses, stats.unresolved, stats.unparsed),
+            (1, 1, 1, 1)
+        );
+    }
+
+    #[test]
+    fn test_interest_filter_extend() {
+        let mut filter = InterestFilter::new([], 0.01);
+        let accounts: Vec<Pubkey> = (0..1_000).map(|_| Pubkey::new_unique()).collect();
+        filter.extend(accounts.iter().copied());
+
+        // resized, or the filter would be all ones and match everything
+        assert!(filter.bloom.mask + 1 >= 8 * 1_000);
+        assert!(accounts
+            .iter()
+            .all(|account| filter.bloom.contains(&account.to_bytes())));
+        assert_eq!(filter.accounts().len(), 1_000);
+    }
+}
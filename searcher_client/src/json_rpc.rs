@@ -0,0 +1,230 @@
+use std::{
+    error::Error,
+    str::FromStr,
+    time::{Duration, Instant},
+};
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use log::{info, warn};
+use serde::{de::DeserializeOwned, Deserialize};
+use serde_json::{json, Value};
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::{clock::Slot, pubkey::Pubkey, signature::Signature};
+use thiserror::Error;
+use tokio::time::sleep;
+
+use crate::{signatures_landed, BundleRejectionError};
+
+/// Path of the bundle API under a block engine URL.
+pub const BUNDLES_PATH: &str = "/api/v1/bundles";
+
+/// How long `confirm_bundle` polls before giving up on a bundle.
+pub const BUNDLE_CONFIRM_TIMEOUT: Duration = Duration::from_secs(30);
+/// The public API allows about one request per second.
+const STATUS_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+#[derive(Debug, Error)]
+pub enum JsonRpcError {
+    #[error("http error: {0}")]
+    Http(#[from] reqwest::Error),
+    #[error("json-rpc error {code}: {message}")]
+    Rpc { code: i64, message: String },
+    #[error("invalid response: {0}")]
+    InvalidResponse(String),
+}
+
+#[derive(Debug, Deserialize)]
+struct RpcErrorObject {
+    code: i64,
+    message: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct RpcResponse<T> {
+    result: Option<T>,
+    error: Option<RpcErrorObject>,
+}
+
+#[derive(Debug, Deserialize)]
+struct WithContext<T> {
+    value: T,
+}
+
+/// A landed bundle as reported by `getBundleStatuses`.
+#[derive(Clone, Debug, Deserialize)]
+pub struct BundleStatus {
+    pub bundle_id: String,
+    pub transactions: Vec<String>,
+    pub slot: Slot,
+    /// processed, confirmed or finalized.
+    pub confirmation_status: Option<String>,
+    /// `{"Ok": null}` if the bundle executed successfully.
+    pub err: Value,
+}
+
+impl BundleStatus {
+    pub fn succeeded(&self) -> bool {
+        self.err.is_null() || self.err.get("Ok").is_some()
+    }
+}
+
+/// Which sources showed a bundle landed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ConfirmedBy {
+    BundleStatus,
+    Signatures,
+    Both,
+}
+
+/// Submits bundles through the block engine's public JSON-RPC API, which doesn't need an
+/// approved auth keypair. There's no bundle results stream, so landing is checked with RPC.
+#[derive(Clone)]
+pub struct JsonRpcBundleClient {
+    http: reqwest::Client,
+    url: String,
+}
+
+impl JsonRpcBundleClient {
+    /// `block_engine_url` is the same URL the gRPC client connects to.
+    pub fn new(block_engine_url: &str) -> Self {
+        Self {
+            http: reqwest::Client::new(),
+            url: format!("{}{BUNDLES_PATH}", block_engine_url.trim_end_matches('/')),
+        }
+    }
+
+    async fn call<T: DeserializeOwned>(
+        &self,
+        method: &str,
+        params: Value,
+    ) -> Result<T, JsonRpcError> {
+        let response: RpcResponse<T> = self
+            .http
+            .post(&self.url)
+            .json(&json!({
+                "jsonrpc": "2.0",
+                "id": 1,
+                "method": method,
+                "params": params,
+            }))
+            .send()
+            .await?
+            .json()
+            .await?;
+        if let Some(error) = response.error {
+            return Err(JsonRpcError::Rpc {
+                code: error.code,
+                message: error.message,
+            });
+        }
+        response
+            .result
+            .ok_or_else(|| JsonRpcError::InvalidResponse(format!("{method} returned no result")))
+    }
+
+    /// Sends bincode serialized transactions as a bundle and returns its id.
+    pub async fn send_bundle(&self, transactions: &[Vec<u8>]) -> Result<String, JsonRpcError> {
+        let encoded: Vec<String> = transactions.iter().map(|tx| STANDARD.encode(tx)).collect();
+        self.call("sendBundle", json!([encoded, { "encoding": "base64" }]))
+            .await
+    }
+
+    /// The status of each bundle, None for bundles that haven't landed or are too old.
+    pub async fn get_bundle_statuses(
+        &self,
+        bundle_ids: &[String],
+    ) -> Result<Vec<Option<BundleStatus>>, JsonRpcError> {
+        let statuses: WithContext<Vec<Option<BundleStatus>>> =
+            self.call("getBundleStatuses", json!([bundle_ids])).await?;
+        Ok(statuses.value)
+    }
+
+    pub async fn get_tip_accounts(&self) -> Result<Vec<Pubkey>, JsonRpcError> {
+        let accounts: Vec<String> = self.call("getTipAccounts", json!([])).await?;
+        accounts
+            .iter()
+            .map(|account| {
+                Pubkey::from_str(account).map_err(|e| {
+                    JsonRpcError::InvalidResponse(format!("invalid tip account {account}: {e}"))
+                })
+            })
+            .collect()
+    }
+
+    /// Like `send_bundle_with_confirmation` over gRPC: sends the bundle, then waits for it to land
+    /// with `confirm_bundle`.
+    pub async fn send_bundle_with_confirmation(
+        &self,
+        bundle_signatures: &[Signature],
+        transactions: &[Vec<u8>],
+        rpc_client: &RpcClient,
+    ) -> Result<(), Box<dyn Error>> {
+        let bundle_id = self.send_bundle(transactions).await?;
+        info!("Bundle sent. UUID: {bundle_id:?}");
+        let confirmed_by = self
+            .confirm_bundle(
+                &bundle_id,
+                bundle_signatures,
+                rpc_client,
+                BUNDLE_CONFIRM_TIMEOUT,
+            )
+            .await?;
+        info!("Bundle landed successfully, confirmed by {confirmed_by:?}");
+        for sig in bundle_signatures {
+            info!("https://solscan.io/tx/{sig}");
+        }
+        Ok(())
+    }
+
+    /// Polls the bundle status by id and the signature statuses together until either shows the
+    /// bundle landed, or `timeout` passes. Either source is enough: RPC nodes can lag behind in
+    /// indexing signatures, and the bundle status API forgets bundles after a while. A bundle
+    /// status with an error fails right away.
+    pub async fn confirm_bundle(
+        &self,
+        bundle_id: &str,
+        bundle_signatures: &[Signature],
+        rpc_client: &RpcClient,
+        timeout: Duration,
+    ) -> Result<ConfirmedBy, Box<dyn Error>> {
+        let deadline = Instant::now() + timeout;
+        let bundle_ids = [bundle_id.to_string()];
+        loop {
+            let (statuses, signatures_landed) = tokio::join!(
+                self.get_bundle_statuses(&bundle_ids),
+                signatures_landed(bundle_signatures, rpc_client),
+            );
+            let status = match statuses {
+                Ok(mut statuses) => statuses.pop().flatten(),
+                Err(e) => {
+                    warn!("failed to get status of bundle {bundle_id}: {e}");
+                    None
+                }
+            };
+            if let Some(status) = &status {
+                if !status.succeeded() {
+                    return Err(Box::new(BundleRejectionError::InternalError(format!(
+                        "bundle {bundle_id} landed in slot {} with error {}",
+                        status.slot, status.err
+                    ))));
+                }
+            }
+            match (status, signatures_landed) {
+                (Some(_), true) => return Ok(ConfirmedBy::Both),
+                (Some(status), false) => {
+                    info!(
+                        "bundle {bundle_id} landed in slot {}, RPC hasn't indexed its signatures yet",
+                        status.slot
+                    );
+                    return Ok(ConfirmedBy::BundleStatus);
+                }
+                (None, true) => return Ok(ConfirmedBy::Signatures),
+                (None, false) if Instant::now() >= deadline => {
+                    warn!("Transactions in bundle did not land");
+                    return Err(Box::new(BundleRejectionError::NotLanded));
+                }
+                (None, false) => sleep(STATUS_POLL_INTERVAL).await,
+            }
+        }
+    }
+}
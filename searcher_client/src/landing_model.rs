@@ -0,0 +1,141 @@
+use std::{collections::HashSet, fs, io, path::Path};
+
+use serde::{Deserialize, Serialize};
+use solana_sdk::{
+    pubkey::Pubkey, system_instruction::SystemInstruction, system_program,
+    transaction::VersionedTransaction,
+};
+
+const ITERATIONS: usize = 5_000;
+const LEARNING_RATE: f64 = 0.5;
+
+/// A submitted bundle and whether it landed.
+#[derive(Clone, Copy, Debug)]
+pub struct LandingObservation {
+    pub tip_lamports: u64,
+    pub slots_to_leader: u64,
+    pub landed: bool,
+}
+
+/// Total lamports a bundle transfers to the tip accounts.
+pub fn tip_lamports(transactions: &[VersionedTransaction], tip_accounts: &HashSet<Pubkey>) -> u64 {
+    let mut tip = 0;
+    for tx in transactions {
+        let keys = tx.message.static_account_keys();
+        for ix in tx.message.instructions() {
+            if keys.get(ix.program_id_index as usize) != Some(&system_program::id()) {
+                continue;
+            }
+            let Ok(SystemInstruction::Transfer { lamports }) = bincode::deserialize(&ix.data)
+            else {
+                continue;
+            };
+            let recipient = ix
+                .accounts
+                .get(1)
+                .and_then(|index| keys.get(*index as usize));
+            if recipient.is_some_and(|recipient| tip_accounts.contains(recipient)) {
+                tip += lamports;
+            }
+        }
+    }
+    tip
+}
+
+/// Logistic curve of landing probability over the tip's percentile among past tips and the
+/// number of slots until the leader:
+/// `p = 1 / (1 + e^-(intercept + tip_weight * percentile + slots_weight * slots / slots_scale))`.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct LandingModel {
+    /// Tips at each percentile from 0 to 100 of the observations the model was fit on.
+    pub tip_percentiles: Vec<u64>,
+    pub slots_scale: f64,
+    pub intercept: f64,
+    pub tip_weight: f64,
+    pub slots_weight: f64,
+    pub observations: usize,
+}
+
+impl LandingModel {
+    /// Fits the curve with gradient descent. Returns None without observations.
+    pub fn fit(observations: &[LandingObservation]) -> Option<Self> {
+        if observations.is_empty() {
+            return None;
+        }
+        let mut tips: Vec<u64> = observations.iter().map(|o| o.tip_lamports).collect();
+        tips.sort_unstable();
+        let tip_percentiles = (0..=100)
+            .map(|p| tips[(p * (tips.len() - 1)) / 100])
+            .collect();
+        let slots_scale = observations
+            .iter()
+            .map(|o| o.slots_to_leader)
+            .max()
+            .unwrap_or_default()
+            .max(1) as f64;
+
+        let mut model = Self {
+            tip_percentiles,
+            slots_scale,
+            intercept: 0.0,
+            tip_weight: 0.0,
+            slots_weight: 0.0,
+            observations: observations.len(),
+        };
+        let features: Vec<(f64, f64, f64)> = observations
+            .iter()
+            .map(|o| {
+                (
+                    model.tip_percentile(o.tip_lamports),
+                    o.slots_to_leader as f64 / slots_scale,
+                    if o.landed { 1.0 } else { 0.0 },
+                )
+            })
+            .collect();
+
+        let n = features.len() as f64;
+        for _ in 0..ITERATIONS {
+            let (mut d_intercept, mut d_tip, mut d_slots) = (0.0, 0.0, 0.0);
+            for (percentile, slots, landed) in &features {
+                let error = model.logistic(*percentile, *slots) - landed;
+                d_intercept += error;
+                d_tip += error * percentile;
+                d_slots += error * slots;
+            }
+            model.intercept -= LEARNING_RATE * d_intercept / n;
+            model.tip_weight -= LEARNING_RATE * d_tip / n;
+            model.slots_weight -= LEARNING_RATE * d_slots / n;
+        }
+        Some(model)
+    }
+
+    /// Fraction of past tips below `tip_lamports`, from 0 to 1.
+    pub fn tip_percentile(&self, tip_lamports: u64) -> f64 {
+        let below = self
+            .tip_percentiles
+            .partition_point(|tip| *tip < tip_lamports);
+        below as f64 / self.tip_percentiles.len().max(1) as f64
+    }
+
+    /// Probability a bundle tipping `tip_lamports` lands when sent `slots_ahead` slots before
+    /// the leader.
+    pub fn estimate_landing_probability(&self, tip_lamports: u64, slots_ahead: u64) -> f64 {
+        self.logistic(
+            self.tip_percentile(tip_lamports),
+            slots_ahead as f64 / self.slots_scale,
+        )
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        fs::write(path, serde_json::to_vec_pretty(self)?)
+    }
+
+    pub fn load(path: &Path) -> io::Result<Self> {
+        Ok(serde_json::from_slice(&fs::read(path)?)?)
+    }
+
+    fn logistic(&self, percentile: f64, scaled_slots: f64) -> f64 {
+        let z = self.intercept + self.tip_weight * percentile + self.slots_weight * scaled_slots;
+        1.0 / (1.0 + (-z).exp())
+    }
+}
@@ -0,0 +1,170 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet, VecDeque};
+
+use log::debug;
+use solana_metrics::datapoint_info;
+use solana_sdk::{clock::Slot, pubkey::Pubkey};
+
+/// Scheduled slots kept per leader to measure its skip rate from, 32 leader windows.
+pub const DEFAULT_WINDOW_SLOTS: usize = 128;
+
+/// Slots a block may arrive late by. Scheduled slots are only judged once blocks this far past
+/// them have arrived, so blocks confirmed out of order aren't counted as skipped.
+const SETTLE_SLOTS: Slot = 32;
+
+/// A jump between consecutive blocks longer than this is a gap in the block subscription, not
+/// skipped slots, and the scheduled slots in it aren't counted.
+const MAX_GAP_SLOTS: Slot = 64;
+
+/// Scheduled slots fewer than this leave a leader's skip rate unknown.
+const MIN_SAMPLES: usize = 8;
+
+/// How a leader did over its recent scheduled slots.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct SkipStats {
+    pub produced: usize,
+    pub skipped: usize,
+}
+
+impl SkipStats {
+    /// The share of scheduled slots skipped, None until enough slots were seen.
+    pub fn skip_rate(&self) -> Option<f64> {
+        let scheduled = self.produced + self.skipped;
+        (scheduled >= MIN_SAMPLES).then(|| self.skipped as f64 / scheduled as f64)
+    }
+}
+
+/// Tracks which leaders produced the slots they were scheduled for and which they skipped,
+/// judged by whether a confirmed block arrived for the slot. Feed it the leader schedule with
+/// `schedule` and every block with `block`.
+pub struct LeaderSkipTracker {
+    window_slots: usize,
+    /// Scheduled slots not judged yet, and their leaders.
+    scheduled: BTreeMap<Slot, Pubkey>,
+    /// Slots with blocks that may still be scheduled slots not judged yet.
+    produced: BTreeSet<Slot>,
+    /// The first block since blocks were last missed, earlier slots can't be judged.
+    watched_from: Option<Slot>,
+    latest_block: Option<Slot>,
+    /// Whether each leader's recent scheduled slots were produced, oldest first.
+    outcomes: HashMap<Pubkey, VecDeque<bool>>,
+    /// Leaders with slots judged since the last report.
+    updated: HashSet<Pubkey>,
+}
+
+impl Default for LeaderSkipTracker {
+    fn default() -> Self {
+        Self::new(DEFAULT_WINDOW_SLOTS)
+    }
+}
+
+impl LeaderSkipTracker {
+    pub fn new(window_slots: usize) -> Self {
+        Self {
+            window_slots: window_slots.max(MIN_SAMPLES),
+            scheduled: BTreeMap::new(),
+            produced: BTreeSet::new(),
+            watched_from: None,
+            latest_block: None,
+            outcomes: HashMap::new(),
+            updated: HashSet::new(),
+        }
+    }
+
+    /// Adds the slots of a leader schedule, such as the connected leaders. Slots already judged
+    /// or from before blocks were watched are ignored.
+    pub fn schedule(&mut self, leader_schedule: &HashMap<Pubkey, HashSet<Slot>>) {
+        let judged = self.judged_through();
+        for (leader, slots) in leader_schedule {
+            for slot in slots {
+                let watched = self.watched_from.is_none_or(|from| *slot >= from)
+                    && judged.is_none_or(|judged| *slot > judged);
+                if watched {
+                    self.scheduled.insert(*slot, *leader);
+                }
+            }
+        }
+    }
+
+    /// Records a confirmed block for `slot`, judging the scheduled slots it settles.
+    pub fn block(&mut self, slot: Slot) {
+        match self.latest_block {
+            None => {
+                self.scheduled.retain(|scheduled, _| *scheduled >= slot);
+                self.watched_from = Some(slot);
+            }
+            Some(latest) if slot > latest.saturating_add(MAX_GAP_SLOTS) => {
+                // the subscription dropped, nothing is known about the slots in between
+                debug!("no blocks between slots {latest} and {slot}, not judging their leaders");
+                self.scheduled
+                    .retain(|scheduled, _| *scheduled <= latest || *scheduled >= slot);
+                self.watched_from = Some(slot);
+            }
+            Some(_) => {}
+        }
+        self.produced.insert(slot);
+        let latest = self.latest_block.map_or(slot, |latest| latest.max(slot));
+        self.latest_block = Some(latest);
+
+        let Some(judged) = self.judged_through() else {
+            return;
+        };
+        while let Some(entry) = self.scheduled.first_entry() {
+            if *entry.key() > judged {
+                break;
+            }
+            let (slot, leader) = entry.remove_entry();
+            let outcomes = self.outcomes.entry(leader).or_default();
+            outcomes.push_back(self.produced.contains(&slot));
+            if outcomes.len() > self.window_slots {
+                outcomes.pop_front();
+            }
+            self.updated.insert(leader);
+        }
+        self.produced = self.produced.split_off(&judged.saturating_add(1));
+    }
+
+    /// The latest slot settled enough to judge.
+    fn judged_through(&self) -> Option<Slot> {
+        self.latest_block
+            .and_then(|latest| latest.checked_sub(SETTLE_SLOTS))
+    }
+
+    pub fn stats(&self, leader: &Pubkey) -> SkipStats {
+        let mut stats = SkipStats::default();
+        for produced in self.outcomes.get(leader).into_iter().flatten() {
+            if *produced {
+                stats.produced += 1;
+            } else {
+                stats.skipped += 1;
+            }
+        }
+        stats
+    }
+
+    /// The share of `leader`'s recent scheduled slots it skipped, None until enough were seen.
+    pub fn skip_rate(&self, leader: &Pubkey) -> Option<f64> {
+        self.stats(leader).skip_rate()
+    }
+
+    /// Every leader with a known skip rate.
+    pub fn skip_rates(&self) -> HashMap<Pubkey, f64> {
+        self.outcomes
+            .keys()
+            .filter_map(|leader| Some((*leader, self.skip_rate(leader)?)))
+            .collect()
+    }
+
+    /// Reports the skip rate of each leader with slots judged since the last report.
+    pub fn report(&mut self, name: &'static str) {
+        for leader in std::mem::take(&mut self.updated) {
+            let stats = self.stats(&leader);
+            datapoint_info!(
+                name,
+                ("leader", leader.to_string(), String),
+                ("produced", stats.produced, i64),
+                ("skipped", stats.skipped, i64),
+                ("skip_rate", stats.skip_rate().unwrap_or_default(), f64),
+            );
+        }
+    }
+}
@@ -0,0 +1,93 @@
+use std::collections::HashMap;
+
+use crate::bundle_store::{BundleRecord, BundleStatus};
+
+/// Outcomes of the resolved bundles attributed to one leader or region.
+#[derive(Clone, Debug, Default)]
+pub struct LandingStats {
+    /// Leader identity or region name.
+    pub key: String,
+    /// Resolved bundles, pending ones left out.
+    pub bundles: usize,
+    pub landed: usize,
+    /// On chain with the searcher's transaction failed, still included by the leader.
+    pub failed: usize,
+    pub dropped: usize,
+    pub top_of_block: usize,
+}
+
+impl LandingStats {
+    fn record(&mut self, record: &BundleRecord) {
+        self.bundles += 1;
+        match record.status {
+            BundleStatus::Landed => self.landed += 1,
+            BundleStatus::Failed => self.failed += 1,
+            BundleStatus::Dropped => self.dropped += 1,
+            BundleStatus::Pending => {}
+        }
+        if record.top_of_block() == Some(true) {
+            self.top_of_block += 1;
+        }
+    }
+
+    /// Bundles on chain, failed ones included.
+    pub fn included(&self) -> usize {
+        self.landed + self.failed
+    }
+
+    /// The share of bundles that made it on chain.
+    pub fn inclusion_rate(&self) -> f64 {
+        match self.bundles {
+            0 => 0.0,
+            bundles => self.included() as f64 / bundles as f64,
+        }
+    }
+}
+
+/// Bundle outcomes by leader, best inclusion rate first. Each bundle counts for the leader that
+/// included it if it's on chain, otherwise for the one it was aimed at; bundles with neither
+/// known are left out.
+pub fn by_leader<'a>(records: impl IntoIterator<Item = &'a BundleRecord>) -> Vec<LandingStats> {
+    aggregate(records, |record| {
+        record
+            .outcome_leader()
+            .map(str::to_string)
+            .into_iter()
+            .collect()
+    })
+}
+
+/// Bundle outcomes by the block engine region they were sent through, best inclusion rate
+/// first. A bundle raced through several regions counts for each.
+pub fn by_region<'a>(records: impl IntoIterator<Item = &'a BundleRecord>) -> Vec<LandingStats> {
+    aggregate(records, |record| record.regions.clone())
+}
+
+fn aggregate<'a>(
+    records: impl IntoIterator<Item = &'a BundleRecord>,
+    keys: impl Fn(&BundleRecord) -> Vec<String>,
+) -> Vec<LandingStats> {
+    let mut stats: HashMap<String, LandingStats> = HashMap::new();
+    for record in records {
+        if record.status == BundleStatus::Pending {
+            continue;
+        }
+        for key in keys(record) {
+            stats
+                .entry(key.clone())
+                .or_insert_with(|| LandingStats {
+                    key,
+                    ..LandingStats::default()
+                })
+                .record(record);
+        }
+    }
+    let mut stats: Vec<_> = stats.into_values().collect();
+    stats.sort_by(|a, b| {
+        b.inclusion_rate()
+            .total_cmp(&a.inclusion_rate())
+            .then(b.bundles.cmp(&a.bundles))
+            .then(a.key.cmp(&b.key))
+    });
+    stats
+}
@@ -0,0 +1,250 @@
+use std::{
+    fs::{self, OpenOptions},
+    io::{self, ErrorKind},
+    path::PathBuf,
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Arc,
+    },
+    thread,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use solana_metrics::datapoint_info;
+use tokio::{task::JoinHandle, time::sleep};
+
+/// A lock file older than this was left behind by a crashed instance.
+const STALE_LOCK: Duration = Duration::from_secs(2);
+const LOCK_ATTEMPTS: usize = 20;
+
+/// Who holds the lease and until when, in unix milliseconds of the clock of the instance that
+/// wrote it.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LeaseRecord {
+    pub holder: String,
+    pub expires_at_ms: u64,
+}
+
+/// Shared storage the instances of an HA pair coordinate through. Expiry is compared against the
+/// reading instance's own clock, so the pair's clocks have to agree to within a quarter of the
+/// ttl, the margin [`Lease`] stops submitting by before its lease expires.
+pub trait LeaseStore: Send + Sync {
+    /// Takes or renews the lease for `holder` for `ttl`, unless another holder's lease hasn't
+    /// expired. Returns the lease as it stands afterwards.
+    fn acquire(&self, holder: &str, ttl: Duration) -> io::Result<LeaseRecord>;
+
+    /// Gives the lease up if `holder` has it, so the standby takes over without waiting out
+    /// the ttl.
+    fn release(&self, holder: &str) -> io::Result<()>;
+}
+
+/// Lease kept in a JSON file, e.g. on storage both instances mount. Updates are serialized with
+/// a `<path>.lock` file created exclusively.
+pub struct FileLeaseStore {
+    path: PathBuf,
+    lock_path: PathBuf,
+}
+
+impl FileLeaseStore {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        let path = path.into();
+        let mut lock_path = path.clone().into_os_string();
+        lock_path.push(".lock");
+        Self {
+            path,
+            lock_path: lock_path.into(),
+        }
+    }
+
+    fn with_lock<T>(&self, f: impl FnOnce() -> io::Result<T>) -> io::Result<T> {
+        for _ in 0..LOCK_ATTEMPTS {
+            match OpenOptions::new()
+                .write(true)
+                .create_new(true)
+                .open(&self.lock_path)
+            {
+                Ok(_) => {
+                    let result = f();
+                    fs::remove_file(&self.lock_path)?;
+                    return result;
+                }
+                Err(e) if e.kind() == ErrorKind::AlreadyExists => {
+                    let stale = fs::metadata(&self.lock_path)
+                        .and_then(|m| m.modified())
+                        .ok()
+                        .and_then(|modified| modified.elapsed().ok())
+                        .is_some_and(|age| age > STALE_LOCK);
+                    if stale {
+                        let _ = fs::remove_file(&self.lock_path);
+                    } else {
+                        thread::sleep(Duration::from_millis(5));
+                    }
+                }
+                Err(e) => return Err(e),
+            }
+        }
+        Err(io::Error::new(
+            ErrorKind::WouldBlock,
+            format!("{:?} stayed locked", self.lock_path),
+        ))
+    }
+
+    fn read(&self) -> io::Result<Option<LeaseRecord>> {
+        match fs::read(&self.path) {
+            Ok(bytes) => Ok(serde_json::from_slice(&bytes).ok()),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    fn write(&self, record: &LeaseRecord) -> io::Result<()> {
+        let mut tmp_path = self.path.clone().into_os_string();
+        tmp_path.push(".tmp");
+        fs::write(&tmp_path, serde_json::to_vec(record)?)?;
+        fs::rename(&tmp_path, &self.path)
+    }
+}
+
+impl LeaseStore for FileLeaseStore {
+    fn acquire(&self, holder: &str, ttl: Duration) -> io::Result<LeaseRecord> {
+        self.with_lock(|| {
+            let now = unix_now_ms();
+            match self.read()? {
+                Some(record) if record.holder != holder && record.expires_at_ms > now => Ok(record),
+                _ => {
+                    let record = LeaseRecord {
+                        holder: holder.to_string(),
+                        expires_at_ms: now + ttl.as_millis() as u64,
+                    };
+                    self.write(&record)?;
+                    Ok(record)
+                }
+            }
+        })
+    }
+
+    fn release(&self, holder: &str) -> io::Result<()> {
+        self.with_lock(|| match self.read()? {
+            Some(record) if record.holder == holder => fs::remove_file(&self.path),
+            _ => Ok(()),
+        })
+    }
+}
+
+/// Whether this instance is the active one of an HA pair. The standby keeps its streams warm
+/// and only submits once it holds the lease. The active instance stops submitting a quarter of
+/// the ttl before its lease expires unless renewed, so it can't overlap with a standby taking
+/// over even if renewals stall. That holds as long as the instances' clocks are less than that
+/// quarter apart, see [`LeaseStore`].
+#[derive(Clone)]
+pub struct Lease {
+    holder: String,
+    store: Arc<dyn LeaseStore>,
+    active_until_ms: Arc<AtomicU64>,
+    was_active: Arc<AtomicBool>,
+    released: Arc<AtomicBool>,
+    takeovers: Arc<AtomicU64>,
+}
+
+impl Lease {
+    /// Starts renewing the lease every `ttl / 4`, until it's released.
+    pub fn spawn_renewal(
+        store: Arc<dyn LeaseStore>,
+        holder: String,
+        ttl: Duration,
+    ) -> (Self, JoinHandle<()>) {
+        let lease = Self {
+            holder,
+            store,
+            active_until_ms: Arc::default(),
+            was_active: Arc::default(),
+            released: Arc::default(),
+            takeovers: Arc::default(),
+        };
+        let renewing = lease.clone();
+        let handle = tokio::spawn(async move {
+            while !renewing.released.load(Ordering::SeqCst) {
+                let store = renewing.store.clone();
+                let holder = renewing.holder.clone();
+                let attempted_at = unix_now_ms();
+                let renewed =
+                    tokio::task::spawn_blocking(move || store.acquire(&holder, ttl)).await;
+                if renewing.released.load(Ordering::SeqCst) {
+                    // the renewal raced the release and may have taken the lease back
+                    if let Err(e) = renewing.release().await {
+                        warn!("failed to release lease: {e}");
+                    }
+                    break;
+                }
+                match renewed {
+                    Ok(Ok(record)) => renewing.update(&record, attempted_at, ttl),
+                    Ok(Err(e)) => warn!("failed to renew lease: {e}"),
+                    Err(e) => warn!("lease renewal panicked: {e}"),
+                }
+                sleep(ttl / 4).await;
+            }
+        });
+        (lease, handle)
+    }
+
+    /// Stops submitting and gives the lease up, so the standby takes over on its next renewal
+    /// instead of waiting out the ttl. The lease isn't renewed afterwards.
+    pub async fn release(&self) -> io::Result<()> {
+        self.released.store(true, Ordering::SeqCst);
+        self.active_until_ms.store(0, Ordering::Relaxed);
+        let store = self.store.clone();
+        let holder = self.holder.clone();
+        tokio::task::spawn_blocking(move || store.release(&holder))
+            .await
+            .map_err(io::Error::other)?
+    }
+
+    fn update(&self, record: &LeaseRecord, attempted_at: u64, ttl: Duration) {
+        let active = record.holder == self.holder;
+        if active {
+            // measured from before the attempt, the file may have been written late in it
+            let margin = ttl.as_millis() as u64 / 4;
+            self.active_until_ms.store(
+                attempted_at + ttl.as_millis() as u64 - margin,
+                Ordering::Relaxed,
+            );
+        }
+        if active != self.was_active.swap(active, Ordering::Relaxed) {
+            if active {
+                self.takeovers.fetch_add(1, Ordering::Relaxed);
+                info!("{} took the lease, now active", self.holder);
+            } else {
+                info!(
+                    "{} holds the lease, {} on standby",
+                    record.holder, self.holder
+                );
+            }
+        }
+    }
+
+    pub fn is_active(&self) -> bool {
+        !self.released.load(Ordering::SeqCst)
+            && unix_now_ms() < self.active_until_ms.load(Ordering::Relaxed)
+    }
+
+    pub fn holder(&self) -> &str {
+        &self.holder
+    }
+
+    pub fn report(&self, name: &'static str) {
+        datapoint_info!(
+            name,
+            ("active", self.is_active(), bool),
+            ("takeovers", self.takeovers.load(Ordering::Relaxed), i64),
+        );
+    }
+}
+
+fn unix_now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as u64
+}
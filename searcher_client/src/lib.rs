@@ -1,3 +1,6 @@
+// tonic's Status and the RPC client's ClientError are both large, and errors wrap them as is
+#![allow(clippy::result_large_err)]
+
 use std::{
     sync::Arc,
     time::{Duration, Instant},
@@ -21,7 +24,6 @@ use solana_client::nonblocking::rpc_client::RpcClient;
 use solana_sdk::{
     commitment_config::CommitmentConfig,
     signature::{Keypair, Signature},
-    transaction::VersionedTransaction,
 };
 use thiserror::Error;
 use tokio::time::timeout;
@@ -32,9 +34,96 @@ use tonic::{
     Response, Status, Streaming,
 };
 
-use crate::token_authenticator::ClientInterceptor;
-
+use crate::{client_headers::ClientHeaders, token_authenticator::ClientInterceptor};
+
+pub mod account_setup;
+pub mod account_snapshot;
+pub mod admin_server;
+pub mod amount;
+pub mod anchor;
+pub mod assertions;
+pub mod audit_log;
+pub mod auth_failover;
+pub mod balance_watch;
+pub mod bench;
+pub mod block_position;
+pub mod bundle_builder;
+pub mod bundle_expiry;
+pub mod bundle_optimizer;
+pub mod bundle_simulation;
+pub mod bundle_store;
+pub mod canary;
+pub mod capture_window;
+pub mod client_headers;
+pub mod clock_offset;
+pub mod cluster;
+pub mod compute_profile;
+pub mod config_validation;
+pub mod congestion;
+pub mod daily_report;
+pub mod dead_man_switch;
+pub mod decoder_plugin;
+pub mod echo_latency;
+pub mod efficiency;
+pub mod ephemeral_account;
+pub mod ev_gate;
+#[cfg(feature = "fault-injection")]
+pub mod fault_injection;
+pub mod gateway_clients;
+pub mod grpc_trace;
+pub mod idempotency;
+pub mod idl_decoder;
+pub mod instruction_template;
+pub mod interest_filter;
+pub mod json_rpc;
+pub mod landing_model;
+pub mod leader_skip_rate;
+pub mod leader_stats;
+pub mod lease;
+pub mod load_shedding;
+pub mod local_simulator;
+pub mod observation;
+pub mod opportunity_queue;
+pub mod packet_stats;
+pub mod pool_layouts;
+pub mod pool_registry;
+pub mod post_conditions;
+pub mod price_feed;
+pub mod priority;
+pub mod record_sink;
+pub mod recording;
+pub mod redis_state;
+pub mod region_race;
+pub mod route;
+pub mod rpc_fallback;
+pub mod rpc_latency;
+pub mod runtime_config;
+pub mod runtime_params;
+pub mod shadow;
+pub mod simulation_policy;
+pub mod simulation_report;
+pub mod slippage;
+pub mod state_snapshot;
+pub mod strategy_config;
+pub mod strategy_gateway;
+pub mod sweep;
+pub mod tip_budget;
+pub mod tip_monitor;
+pub mod tip_payment;
 pub mod token_authenticator;
+pub mod token_fees;
+pub mod token_guard;
+pub mod token_safety;
+pub mod tpu_fallback;
+pub mod transaction_decoder;
+#[cfg(unix)]
+pub mod uds_ipc;
+pub mod validator_metadata;
+#[cfg(feature = "wasm")]
+pub mod wasm_plugin;
+#[cfg(feature = "wasm")]
+pub mod wasm_strategy;
+pub mod write_lock_limiter;
 
 #[derive(Debug, Error)]
 pub enum BlockEngineConnectionError {
@@ -54,15 +143,41 @@ pub enum BundleRejectionError {
     SimulationFailure(String, Option<String>),
     #[error("internal error {0}")]
     InternalError(String),
+    #[error("bundle transactions did not land in time")]
+    NotLanded,
+}
+
+impl BundleRejectionError {
+    /// True if the bundle was outbid, as opposed to failing simulation or erroring.
+    pub fn is_auction_loss(&self) -> bool {
+        matches!(
+            self,
+            Self::StateAuctionBidRejected(..) | Self::WinningBatchBidRejected(..)
+        )
+    }
 }
 
 pub type BlockEngineConnectionResult<T> = Result<T, BlockEngineConnectionError>;
 
+/// How long a sent bundle is given before checking whether it landed.
+const BUNDLE_RESULT_WAIT: Duration = Duration::from_millis(2500);
+
 pub async fn get_searcher_client(
     block_engine_url: &str,
     auth_keypair: &Arc<Keypair>,
 ) -> BlockEngineConnectionResult<
     SearcherServiceClient<InterceptedService<Channel, ClientInterceptor>>,
+> {
+    get_searcher_client_with_headers(block_engine_url, auth_keypair, ClientHeaders::default()).await
+}
+
+/// Like `get_searcher_client`, attaching `headers` to every call.
+pub async fn get_searcher_client_with_headers(
+    block_engine_url: &str,
+    auth_keypair: &Arc<Keypair>,
+    headers: ClientHeaders,
+) -> BlockEngineConnectionResult<
+    SearcherServiceClient<InterceptedService<Channel, ClientInterceptor>>,
 > {
     let auth_channel = create_grpc_channel(block_engine_url).await?;
     let client_interceptor = ClientInterceptor::new(
@@ -70,7 +185,8 @@ pub async fn get_searcher_client(
         auth_keypair,
         Role::Searcher,
     )
-    .await?;
+    .await?
+    .with_headers(headers);
 
     let searcher_channel = create_grpc_channel(block_engine_url).await?;
     let searcher_client =
@@ -180,7 +296,7 @@ pub async fn send_bundle_with_confirmation(
 */
 
 pub async fn send_bundle_with_confirmation(
-    bundle_signatures: &Vec<Signature>,
+    bundle_signatures: &[Signature],
     transactions: &[Vec<u8>],
     rpc_client: &RpcClient,
     searcher_client: &mut SearcherServiceClient<InterceptedService<Channel, ClientInterceptor>>,
@@ -196,7 +312,7 @@ pub async fn send_bundle_with_confirmation(
     info!("Bundle sent. UUID: {:?}", uuid);
 
     info!("Waiting for 2.5 seconds to hear results...");
-    let mut time_left = 2500;
+    let mut time_left = BUNDLE_RESULT_WAIT.as_millis() as u64;
     while let Ok(Some(Ok(results))) = timeout(
         Duration::from_millis(time_left),
         bundle_results_subscription.next(),
@@ -204,6 +320,7 @@ pub async fn send_bundle_with_confirmation(
     .await
     {
         let instant = Instant::now();
+        grpc_trace::trace_message("SubscribeBundleResults", &results);
         info!("bundle results: {:?}", results);
         match results.result {
             Some(BundleResultType::Accepted(Accepted {
@@ -249,6 +366,11 @@ pub async fn send_bundle_with_confirmation(
         time_left -= instant.elapsed().as_millis() as u64;
     }
 
+    confirm_bundle_landed(bundle_signatures, rpc_client).await
+}
+
+/// True if RPC has every signature as processed without error.
+pub async fn signatures_landed(bundle_signatures: &[Signature], rpc_client: &RpcClient) -> bool {
     let futs: Vec<_> = bundle_signatures
         .iter()
         .map(|sig| {
@@ -256,11 +378,18 @@ pub async fn send_bundle_with_confirmation(
         })
         .collect();
     let results = futures_util::future::join_all(futs).await;
-    if !results.iter().all(|r| matches!(r, Ok(Some(Ok(()))))) {
+    results.iter().all(|r| matches!(r, Ok(Some(Ok(())))))
+}
+
+/// Checks every transaction of a sent bundle landed, with `BundleRejectionError::NotLanded` if
+/// any didn't.
+pub async fn confirm_bundle_landed(
+    bundle_signatures: &[Signature],
+    rpc_client: &RpcClient,
+) -> Result<(), Box<dyn std::error::Error>> {
+    if !signatures_landed(bundle_signatures, rpc_client).await {
         warn!("Transactions in bundle did not land");
-        return Err(Box::new(BundleRejectionError::InternalError(
-            "Searcher service did not provide bundle status in time".into(),
-        )));
+        return Err(Box::new(BundleRejectionError::NotLanded));
     }
     info!("Bundle landed successfully");
     for sig in bundle_signatures.iter() {
@@ -464,16 +593,19 @@ pub async fn send_bundle_no_wait(
     // convert them to packets + send over
     let packets: Vec<_> = transactions
         .iter()
-        .map(proto_packet_from_versioned_tx)
+        .map(|tx| proto_packet_from_versioned_tx(tx))
         .collect();
 
-    searcher_client
-        .send_bundle(SendBundleRequest {
-            bundle: Some(Bundle {
-                header: None,
-                packets,
-            }),
-        })
-        .await
+    let request = SendBundleRequest {
+        bundle: Some(Bundle {
+            header: None,
+            packets,
+        }),
+    };
+    grpc_trace::traced("SendBundle", request, |request| {
+        searcher_client.send_bundle(request)
+    })
+    .await
 }
 
+pub mod wsol;
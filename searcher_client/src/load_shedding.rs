@@ -0,0 +1,116 @@
+use std::{
+    sync::{
+        atomic::{AtomicU64, AtomicUsize, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+
+use thiserror::Error;
+
+use crate::priority::PriorityClass;
+
+/// Shortest retry hint given, so clients of an idle queue that just filled don't retry at once.
+const MIN_RETRY_AFTER: Duration = Duration::from_millis(20);
+
+/// How many submissions may be queued, in total across classes, when a bundle of each class is
+/// admitted. Lower limits for lower classes keep room for critical bundles under load.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct QueueLimits {
+    pub bulk: usize,
+    pub normal: usize,
+    pub critical: usize,
+}
+
+impl QueueLimits {
+    pub fn limit(&self, class: PriorityClass) -> usize {
+        match class {
+            PriorityClass::Bulk => self.bulk,
+            PriorityClass::Normal => self.normal,
+            PriorityClass::Critical => self.critical,
+        }
+    }
+}
+
+/// A submission turned away because the queue was at its class' limit.
+#[derive(Clone, Copy, Debug, Error, PartialEq, Eq)]
+#[error("{depth} bundles queued, at the limit of {limit} for {class} priority, retry after {}ms", .retry_after.as_millis())]
+pub struct Overloaded {
+    pub class: PriorityClass,
+    pub depth: usize,
+    pub limit: usize,
+    /// About how long the queued submissions take to clear.
+    pub retry_after: Duration,
+}
+
+/// Counts the submissions a service has taken on and not yet answered, and sheds new ones once
+/// there are more than their class allows, rather than letting them queue up behind the others.
+#[derive(Debug)]
+pub struct SubmissionQueue {
+    limits: QueueLimits,
+    depth: AtomicUsize,
+    /// Moving average of how long a submission stays queued, in microseconds.
+    latency_us: AtomicU64,
+}
+
+/// A submission's place in the queue, given up when dropped.
+#[derive(Debug)]
+pub struct QueueSlot {
+    queue: Arc<SubmissionQueue>,
+    admitted_at: Instant,
+}
+
+impl SubmissionQueue {
+    pub fn new(limits: QueueLimits) -> Arc<Self> {
+        Arc::new(Self {
+            limits,
+            depth: AtomicUsize::default(),
+            latency_us: AtomicU64::default(),
+        })
+    }
+
+    /// Takes a place in the queue for a bundle of `class`, or returns how long to wait if the
+    /// queue is at that class' limit.
+    pub fn admit(self: &Arc<Self>, class: PriorityClass) -> Result<QueueSlot, Overloaded> {
+        let limit = self.limits.limit(class);
+        self.depth
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |depth| {
+                (depth < limit).then_some(depth + 1)
+            })
+            .map_err(|depth| Overloaded {
+                class,
+                depth,
+                limit,
+                retry_after: self.retry_after(),
+            })?;
+        Ok(QueueSlot {
+            queue: self.clone(),
+            admitted_at: Instant::now(),
+        })
+    }
+
+    pub fn depth(&self) -> usize {
+        self.depth.load(Ordering::Acquire)
+    }
+
+    /// Queued submissions are sent concurrently, so the queue clears in about as long as one
+    /// takes.
+    pub fn retry_after(&self) -> Duration {
+        Duration::from_micros(self.latency_us.load(Ordering::Relaxed)).max(MIN_RETRY_AFTER)
+    }
+}
+
+impl Drop for QueueSlot {
+    fn drop(&mut self) {
+        let latency_us = self.admitted_at.elapsed().as_micros() as u64;
+        let average = |average: u64| match average {
+            0 => latency_us,
+            average => (average * 7 + latency_us) / 8,
+        };
+        let queue = &self.queue;
+        let _ = queue
+            .latency_us
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |a| Some(average(a)));
+        queue.depth.fetch_sub(1, Ordering::AcqRel);
+    }
+}
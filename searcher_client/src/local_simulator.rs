@@ -0,0 +1,417 @@
+use std::collections::HashMap;
+
+use solana_sdk::{
+    account::Account,
+    address_lookup_table::{self, state::AddressLookupTable},
+    compute_budget,
+    instruction::{CompiledInstruction, InstructionError},
+    message::VersionedMessage,
+    program_utils::limited_deserialize,
+    pubkey,
+    pubkey::Pubkey,
+    system_instruction::{SystemError, SystemInstruction, MAX_PERMITTED_DATA_LENGTH},
+    system_program,
+    transaction::{TransactionError, VersionedTransaction},
+};
+use thiserror::Error;
+
+use crate::{ev_gate::estimate_fee_lamports, simulation_report::SimulationReport};
+
+const MEMO_PROGRAM_IDS: [Pubkey; 2] = [
+    pubkey!("MemoSq4gqABAXKb96qnH8TysNcWxMyWCqXgDLGmfcHr"),
+    pubkey!("Memo1UhkJRfHyvLMcVucJwxXeuD728EqVDDwQDxFMNo"),
+];
+
+/// Why a bundle couldn't be simulated at all, as opposed to a transaction failing in it.
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum LocalSimulationError {
+    #[error("transaction {index} invokes {program_id}, which the local simulator can't execute")]
+    UnsupportedProgram { index: usize, program_id: Pubkey },
+    #[error(
+        "transaction {index} instruction {instruction} isn't supported by the local simulator"
+    )]
+    UnsupportedInstruction { index: usize, instruction: usize },
+}
+
+/// What happened to one transaction of a simulated bundle.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LocalTransactionResult {
+    pub err: Option<TransactionError>,
+    pub fee_lamports: u64,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct LocalBundleSimulation {
+    /// One result per transaction up to and including the first that failed, after which the
+    /// block engine wouldn't execute the rest.
+    pub results: Vec<LocalTransactionResult>,
+    /// Accounts the bundle wrote, as they are after it. Empty if a transaction failed, since a
+    /// bundle lands all or nothing.
+    pub post_accounts: HashMap<Pubkey, Account>,
+}
+
+impl LocalBundleSimulation {
+    pub fn succeeded(&self) -> bool {
+        self.results.iter().all(|result| result.err.is_none())
+    }
+}
+
+/// Executes bundles against accounts held in memory, for strategy tests that would otherwise
+/// wait on RPC simulation for every candidate. Seed it with the accounts a bundle touches and
+/// call [`LocalSimulator::simulate_bundle`].
+///
+/// Only the system, memo and compute budget programs run in process. A full bank such as
+/// LiteSVM or solana-program-test needs a newer Solana than the 1.17 this workspace is pinned
+/// to, so bundles calling on-chain programs return [`LocalSimulationError::UnsupportedProgram`]
+/// and have to be simulated over RPC. Like RPC simulation, signatures aren't verified. Accounts
+/// that weren't seeded are treated as empty system accounts, the way the bank treats accounts
+/// that don't exist yet.
+#[derive(Clone, Debug, Default)]
+pub struct LocalSimulator {
+    accounts: HashMap<Pubkey, Account>,
+}
+
+impl LocalSimulator {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn set_account(&mut self, pubkey: Pubkey, account: Account) {
+        self.accounts.insert(pubkey, account);
+    }
+
+    pub fn account(&self, pubkey: &Pubkey) -> Option<&Account> {
+        self.accounts.get(pubkey)
+    }
+
+    /// Keeps the state a successful simulation left behind, so the next bundle runs on top of it.
+    pub fn commit(&mut self, simulation: &LocalBundleSimulation) {
+        self.accounts.extend(
+            simulation
+                .post_accounts
+                .iter()
+                .map(|(pubkey, account)| (*pubkey, account.clone())),
+        );
+    }
+
+    /// Balance changes of a simulation against the accounts it ran on. Empty for a failed bundle.
+    pub fn report(&self, simulation: &LocalBundleSimulation) -> SimulationReport {
+        SimulationReport::from_states(
+            simulation
+                .post_accounts
+                .iter()
+                .map(|(pubkey, after)| (*pubkey, self.accounts.get(pubkey), Some(after))),
+        )
+    }
+
+    /// Executes the transactions in order, each on top of the state the previous left, and stops
+    /// at the first one that fails. The seeded accounts aren't changed, see [`Self::commit`].
+    pub fn simulate_bundle(
+        &self,
+        transactions: &[VersionedTransaction],
+    ) -> Result<LocalBundleSimulation, LocalSimulationError> {
+        let mut changed = HashMap::new();
+        let mut results = Vec::with_capacity(transactions.len());
+        for (index, transaction) in transactions.iter().enumerate() {
+            let fee_lamports = estimate_fee_lamports(std::slice::from_ref(transaction));
+            let err = self
+                .execute(index, &transaction.message, fee_lamports, &mut changed)?
+                .err();
+            let failed = err.is_some();
+            results.push(LocalTransactionResult { err, fee_lamports });
+            if failed {
+                return Ok(LocalBundleSimulation {
+                    results,
+                    post_accounts: HashMap::new(),
+                });
+            }
+        }
+        Ok(LocalBundleSimulation {
+            results,
+            post_accounts: changed,
+        })
+    }
+
+    fn load<'a>(
+        &'a self,
+        changed: &'a HashMap<Pubkey, Account>,
+        pubkey: &Pubkey,
+    ) -> Option<&'a Account> {
+        changed.get(pubkey).or_else(|| self.accounts.get(pubkey))
+    }
+
+    fn execute(
+        &self,
+        index: usize,
+        message: &VersionedMessage,
+        fee_lamports: u64,
+        changed: &mut HashMap<Pubkey, Account>,
+    ) -> Result<Result<(), TransactionError>, LocalSimulationError> {
+        let keys = match self.account_keys(message, changed) {
+            Ok(keys) => keys,
+            Err(e) => return Ok(Err(e)),
+        };
+        for ix in message.instructions() {
+            let Some((program_id, _)) = keys.get(ix.program_id_index as usize) else {
+                return Ok(Err(TransactionError::SanitizeFailure));
+            };
+            if !is_supported(program_id) {
+                return Err(LocalSimulationError::UnsupportedProgram {
+                    index,
+                    program_id: *program_id,
+                });
+            }
+        }
+
+        let Some(payer) = keys.first().and_then(|(key, _)| self.load(changed, key)) else {
+            return Ok(Err(TransactionError::AccountNotFound));
+        };
+        if payer.lamports < fee_lamports {
+            return Ok(Err(TransactionError::InsufficientFundsForFee));
+        }
+        let mut accounts: Vec<Account> = keys
+            .iter()
+            .map(|(key, _)| self.load(changed, key).cloned().unwrap_or_default())
+            .collect();
+        accounts[0].lamports -= fee_lamports;
+
+        let num_signers = message.header().num_required_signatures as usize;
+        for (instruction, ix) in message.instructions().iter().enumerate() {
+            let mut context = InstructionContext {
+                keys: &keys,
+                num_signers,
+                accounts: &mut accounts,
+                indexes: &ix.accounts,
+            };
+            match execute_instruction(&mut context, &keys[ix.program_id_index as usize].0, ix) {
+                Ok(Ok(())) => {}
+                Ok(Err(e)) => {
+                    return Ok(Err(TransactionError::InstructionError(
+                        instruction as u8,
+                        e,
+                    )))
+                }
+                Err(Unsupported) => {
+                    return Err(LocalSimulationError::UnsupportedInstruction { index, instruction })
+                }
+            }
+        }
+        for ((key, writable), account) in keys.into_iter().zip(accounts) {
+            if writable {
+                changed.insert(key, account);
+            }
+        }
+        Ok(Ok(()))
+    }
+
+    /// Static account keys followed by the writable then the readonly addresses loaded from
+    /// lookup tables, each with whether it's writable.
+    fn account_keys(
+        &self,
+        message: &VersionedMessage,
+        changed: &HashMap<Pubkey, Account>,
+    ) -> Result<Vec<(Pubkey, bool)>, TransactionError> {
+        let mut keys: Vec<(Pubkey, bool)> = message
+            .static_account_keys()
+            .iter()
+            .enumerate()
+            .map(|(i, key)| (*key, message.is_maybe_writable(i)))
+            .collect();
+        let Some(lookups) = message.address_table_lookups() else {
+            return Ok(keys);
+        };
+        let mut writable = Vec::new();
+        let mut readonly = Vec::new();
+        for lookup in lookups {
+            let table_account = self
+                .load(changed, &lookup.account_key)
+                .ok_or(TransactionError::AddressLookupTableNotFound)?;
+            if table_account.owner != address_lookup_table::program::id() {
+                return Err(TransactionError::InvalidAddressLookupTableOwner);
+            }
+            let table = AddressLookupTable::deserialize(&table_account.data)
+                .map_err(|_| TransactionError::InvalidAddressLookupTableData)?;
+            for (indexes, loaded) in [
+                (&lookup.writable_indexes, &mut writable),
+                (&lookup.readonly_indexes, &mut readonly),
+            ] {
+                for i in indexes {
+                    loaded.push(
+                        *table
+                            .addresses
+                            .get(*i as usize)
+                            .ok_or(TransactionError::InvalidAddressLookupTableIndex)?,
+                    );
+                }
+            }
+        }
+        keys.extend(writable.into_iter().map(|key| (key, true)));
+        keys.extend(readonly.into_iter().map(|key| (key, false)));
+        Ok(keys)
+    }
+}
+
+fn is_supported(program_id: &Pubkey) -> bool {
+    *program_id == system_program::id()
+        || *program_id == compute_budget::id()
+        || MEMO_PROGRAM_IDS.contains(program_id)
+}
+
+/// An instruction the simulator doesn't know how to execute.
+struct Unsupported;
+
+struct InstructionContext<'a> {
+    keys: &'a [(Pubkey, bool)],
+    num_signers: usize,
+    accounts: &'a mut [Account],
+    /// The instruction's accounts, as indexes into `keys`.
+    indexes: &'a [u8],
+}
+
+impl InstructionContext<'_> {
+    /// Index into `keys` of the instruction's `n`th account.
+    fn key_index(&self, n: usize) -> Result<usize, InstructionError> {
+        self.indexes
+            .get(n)
+            .map(|i| *i as usize)
+            .filter(|i| *i < self.keys.len())
+            .ok_or(InstructionError::NotEnoughAccountKeys)
+    }
+
+    fn signer(&self, n: usize) -> Result<usize, InstructionError> {
+        let i = self.key_index(n)?;
+        if i >= self.num_signers {
+            return Err(InstructionError::MissingRequiredSignature);
+        }
+        Ok(i)
+    }
+
+    /// The instruction's `n`th account, which it's about to change.
+    fn writable_signer(&self, n: usize) -> Result<usize, InstructionError> {
+        let i = self.signer(n)?;
+        if !self.keys[i].1 {
+            return Err(InstructionError::ReadonlyDataModified);
+        }
+        Ok(i)
+    }
+
+    fn transfer(&mut self, from: usize, to: usize, lamports: u64) -> Result<(), InstructionError> {
+        if !self.keys[to].1 {
+            return Err(InstructionError::ReadonlyLamportChange);
+        }
+        if !self.accounts[from].data.is_empty() {
+            return Err(InstructionError::InvalidArgument);
+        }
+        if self.accounts[from].owner != system_program::id() {
+            return Err(InstructionError::ExternalAccountLamportSpend);
+        }
+        if self.accounts[from].lamports < lamports {
+            return Err(InstructionError::Custom(
+                SystemError::ResultWithNegativeLamports as u32,
+            ));
+        }
+        self.accounts[from].lamports -= lamports;
+        self.accounts[to].lamports = self.accounts[to]
+            .lamports
+            .checked_add(lamports)
+            .ok_or(InstructionError::ArithmeticOverflow)?;
+        Ok(())
+    }
+
+    fn allocate(&mut self, i: usize, space: u64) -> Result<(), InstructionError> {
+        let account = &mut self.accounts[i];
+        if !account.data.is_empty() || account.owner != system_program::id() {
+            return Err(InstructionError::Custom(
+                SystemError::AccountAlreadyInUse as u32,
+            ));
+        }
+        if space > MAX_PERMITTED_DATA_LENGTH {
+            return Err(InstructionError::Custom(
+                SystemError::InvalidAccountDataLength as u32,
+            ));
+        }
+        account.data = vec![0; space as usize];
+        Ok(())
+    }
+}
+
+fn execute_instruction(
+    context: &mut InstructionContext,
+    program_id: &Pubkey,
+    ix: &CompiledInstruction,
+) -> Result<Result<(), InstructionError>, Unsupported> {
+    if *program_id == system_program::id() {
+        execute_system_instruction(context, &ix.data)
+    } else if MEMO_PROGRAM_IDS.contains(program_id) {
+        // the memo program only checks that the memo is text and every account signed it
+        if std::str::from_utf8(&ix.data).is_err() {
+            return Ok(Err(InstructionError::InvalidInstructionData));
+        }
+        Ok((0..ix.accounts.len()).try_for_each(|n| context.signer(n).map(|_| ())))
+    } else {
+        // compute budget instructions only affect fees, which are charged up front
+        Ok(Ok(()))
+    }
+}
+
+fn execute_system_instruction(
+    context: &mut InstructionContext,
+    data: &[u8],
+) -> Result<Result<(), InstructionError>, Unsupported> {
+    let instruction = match limited_deserialize(data) {
+        Ok(instruction) => instruction,
+        Err(e) => return Ok(Err(e)),
+    };
+    if !matches!(
+        instruction,
+        SystemInstruction::Transfer { .. }
+            | SystemInstruction::CreateAccount { .. }
+            | SystemInstruction::Assign { .. }
+            | SystemInstruction::Allocate { .. }
+    ) {
+        return Err(Unsupported);
+    }
+    Ok(apply_system_instruction(context, instruction))
+}
+
+fn apply_system_instruction(
+    context: &mut InstructionContext,
+    instruction: SystemInstruction,
+) -> Result<(), InstructionError> {
+    match instruction {
+        SystemInstruction::Transfer { lamports } => {
+            let from = context.writable_signer(0)?;
+            let to = context.key_index(1)?;
+            context.transfer(from, to, lamports)
+        }
+        SystemInstruction::CreateAccount {
+            lamports,
+            space,
+            owner,
+        } => {
+            let from = context.writable_signer(0)?;
+            let to = context.writable_signer(1)?;
+            if context.accounts[to].lamports > 0 {
+                return Err(InstructionError::Custom(
+                    SystemError::AccountAlreadyInUse as u32,
+                ));
+            }
+            context.allocate(to, space)?;
+            context.accounts[to].owner = owner;
+            context.transfer(from, to, lamports)
+        }
+        SystemInstruction::Assign { owner } => {
+            let i = context.writable_signer(0)?;
+            if context.accounts[i].owner != system_program::id() {
+                return Err(InstructionError::ModifiedProgramId);
+            }
+            context.accounts[i].owner = owner;
+            Ok(())
+        }
+        SystemInstruction::Allocate { space } => {
+            let i = context.writable_signer(0)?;
+            context.allocate(i, space)
+        }
+        _ => unreachable!("checked by execute_system_instruction"),
+    }
+}
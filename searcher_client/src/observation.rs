@@ -0,0 +1,52 @@
+use solana_metrics::datapoint_info;
+
+use crate::ev_gate::ProfitEstimate;
+
+/// What bundles held back by observe-only mode would have cost and earned, so a strategy can be
+/// validated against production traffic without submitting anything.
+#[derive(Clone, Debug, Default)]
+pub struct ObservationStats {
+    pub bundles: u64,
+    pub tip_lamports: u64,
+    pub fee_lamports: u64,
+    /// Bundles that came with a profit estimate, the ones the expected amounts cover.
+    pub estimated: u64,
+    pub expected_profit_lamports: u64,
+    pub expected_net_lamports: i64,
+}
+
+impl ObservationStats {
+    pub fn record(
+        &mut self,
+        tip_lamports: u64,
+        fee_lamports: u64,
+        estimate: Option<&ProfitEstimate>,
+    ) {
+        self.bundles += 1;
+        self.tip_lamports += tip_lamports;
+        self.fee_lamports += fee_lamports;
+        if let Some(estimate) = estimate {
+            self.estimated += 1;
+            self.expected_profit_lamports += estimate.expected_profit_lamports;
+            self.expected_net_lamports += estimate.net_lamports();
+        }
+    }
+
+    /// Reports the counters and resets them.
+    pub fn report(&mut self, name: &'static str) {
+        datapoint_info!(
+            name,
+            ("bundles", self.bundles, i64),
+            ("tip_lamports", self.tip_lamports, i64),
+            ("fee_lamports", self.fee_lamports, i64),
+            ("estimated", self.estimated, i64),
+            (
+                "expected_profit_lamports",
+                self.expected_profit_lamports,
+                i64
+            ),
+            ("expected_net_lamports", self.expected_net_lamports, i64),
+        );
+        *self = Self::default();
+    }
+}
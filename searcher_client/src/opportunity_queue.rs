@@ -0,0 +1,364 @@
+use std::{
+    cmp::{Ordering, Reverse},
+    collections::BTreeSet,
+    fmt::{self, Display, Formatter},
+    time::SystemTime,
+};
+
+use serde::{Deserialize, Serialize};
+use solana_metrics::datapoint_info;
+use solana_sdk::clock::Slot;
+
+/// Something a strategy wants submitted, ranked by priority then expected profit.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Opportunity<T> {
+    pub payload: T,
+    pub priority: u8,
+    /// Last slot the opportunity can land in.
+    pub expiry_slot: Slot,
+    /// Latest time the opportunity may still be submitted at, no limit if not set.
+    #[serde(default)]
+    pub deadline: Option<SystemTime>,
+    pub expected_profit_lamports: i64,
+}
+
+/// Why an opportunity was dropped before being submitted.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DropReason {
+    /// The chain moved past its expiry slot.
+    Expired,
+    /// Its submission deadline passed while it waited.
+    DeadlineMissed,
+}
+
+impl Display for DropReason {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            DropReason::Expired => write!(f, "expired"),
+            DropReason::DeadlineMissed => write!(f, "deadline_missed"),
+        }
+    }
+}
+
+/// Why a bundle expiring after `expiry_slot` with `deadline` can't be submitted now, if it can't.
+pub fn drop_reason(
+    expiry_slot: Slot,
+    deadline: Option<SystemTime>,
+    current_slot: Slot,
+    now: SystemTime,
+) -> Option<DropReason> {
+    if expiry_slot < current_slot {
+        Some(DropReason::Expired)
+    } else if deadline.is_some_and(|deadline| deadline < now) {
+        Some(DropReason::DeadlineMissed)
+    } else {
+        None
+    }
+}
+
+impl<T> Opportunity<T> {
+    fn rank(&self) -> (u8, i64) {
+        (self.priority, self.expected_profit_lamports)
+    }
+}
+
+/// An opportunity with the order it was pushed in, so equally ranked ones are told apart and
+/// the earliest pushed comes out first.
+struct Ranked<T> {
+    opportunity: Opportunity<T>,
+    sequence: u64,
+}
+
+impl<T> Ranked<T> {
+    fn key(&self) -> ((u8, i64), Reverse<u64>) {
+        (self.opportunity.rank(), Reverse(self.sequence))
+    }
+}
+
+impl<T> PartialEq for Ranked<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.key() == other.key()
+    }
+}
+
+impl<T> Eq for Ranked<T> {}
+
+impl<T> PartialOrd for Ranked<T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<T> Ord for Ranked<T> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.key().cmp(&other.key())
+    }
+}
+
+/// In-memory queue between strategies and the bundle sender. The sender always gets the best
+/// opportunity that hasn't expired or missed its deadline, others are dropped as they surface and
+/// kept for `take_dropped` so strategies can be told.
+pub struct OpportunityQueue<T> {
+    /// Ordered worst to best, so both ends are cheap to take.
+    opportunities: BTreeSet<Ranked<T>>,
+    dropped: Vec<(Opportunity<T>, DropReason)>,
+    max_len: usize,
+    sequence: u64,
+    pushed: u64,
+    popped: u64,
+    expired: u64,
+    deadline_missed: u64,
+    evicted: u64,
+}
+
+impl<T> OpportunityQueue<T> {
+    pub fn new(max_len: usize) -> Self {
+        Self {
+            opportunities: BTreeSet::new(),
+            dropped: Vec::new(),
+            max_len,
+            sequence: 0,
+            pushed: 0,
+            popped: 0,
+            expired: 0,
+            deadline_missed: 0,
+            evicted: 0,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.opportunities.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.opportunities.is_empty()
+    }
+
+    /// Adds an opportunity. When the queue is full, the opportunities no longer valid at
+    /// `current_slot` and `now` are dropped first, and if that frees no room the lowest ranked
+    /// opportunity is evicted, which may be the one being pushed.
+    pub fn push(&mut self, opportunity: Opportunity<T>, current_slot: Slot, now: SystemTime) {
+        self.pushed += 1;
+        if self.opportunities.len() >= self.max_len {
+            self.drop_invalid(current_slot, now);
+        }
+        let ranked = Ranked {
+            opportunity,
+            sequence: self.sequence,
+        };
+        self.sequence += 1;
+        if self.opportunities.len() >= self.max_len {
+            self.evicted += 1;
+            match self.opportunities.first() {
+                Some(lowest) if ranked > *lowest => {
+                    self.opportunities.pop_first();
+                }
+                _ => return,
+            }
+        }
+        self.opportunities.insert(ranked);
+    }
+
+    /// Returns the best opportunity still valid at `current_slot` and `now`, dropping the ones
+    /// that aren't.
+    pub fn pop_best(&mut self, current_slot: Slot, now: SystemTime) -> Option<Opportunity<T>> {
+        while let Some(Ranked { opportunity, .. }) = self.opportunities.pop_last() {
+            match drop_reason(
+                opportunity.expiry_slot,
+                opportunity.deadline,
+                current_slot,
+                now,
+            ) {
+                Some(reason) => self.drop(opportunity, reason),
+                None => {
+                    self.popped += 1;
+                    return Some(opportunity);
+                }
+            }
+        }
+        None
+    }
+
+    /// Drops every queued opportunity that's no longer valid at `current_slot` and `now`.
+    fn drop_invalid(&mut self, current_slot: Slot, now: SystemTime) {
+        let mut valid = Vec::with_capacity(self.opportunities.len());
+        for ranked in std::mem::take(&mut self.opportunities) {
+            let opportunity = &ranked.opportunity;
+            match drop_reason(
+                opportunity.expiry_slot,
+                opportunity.deadline,
+                current_slot,
+                now,
+            ) {
+                Some(reason) => self.drop(ranked.opportunity, reason),
+                None => valid.push(ranked),
+            }
+        }
+        // already in order, so the set is bulk built
+        self.opportunities = valid.into_iter().collect();
+    }
+
+    fn drop(&mut self, opportunity: Opportunity<T>, reason: DropReason) {
+        match reason {
+            DropReason::Expired => self.expired += 1,
+            DropReason::DeadlineMissed => self.deadline_missed += 1,
+        }
+        if self.dropped.len() < self.max_len {
+            self.dropped.push((opportunity, reason));
+        }
+    }
+
+    /// Takes the opportunities dropped by `pop_best` since the last call, up to the queue's max
+    /// length of them.
+    pub fn take_dropped(&mut self) -> Vec<(Opportunity<T>, DropReason)> {
+        std::mem::take(&mut self.dropped)
+    }
+
+    /// Copies of every queued opportunity, best first, such as for a periodic snapshot.
+    pub fn queued(&self) -> Vec<Opportunity<T>>
+    where
+        T: Clone,
+    {
+        self.opportunities
+            .iter()
+            .rev()
+            .map(|ranked| ranked.opportunity.clone())
+            .collect()
+    }
+
+    /// Takes every queued opportunity, best first, leaving the queue empty.
+    pub fn drain(&mut self) -> Vec<Opportunity<T>> {
+        std::mem::take(&mut self.opportunities)
+            .into_iter()
+            .rev()
+            .map(|ranked| ranked.opportunity)
+            .collect()
+    }
+
+    /// Reports the counters and resets them.
+    pub fn report(&mut self, name: &'static str) {
+        datapoint_info!(
+            name,
+            ("queued", self.opportunities.len(), i64),
+            ("pushed", self.pushed, i64),
+            ("popped", self.popped, i64),
+            ("expired", self.expired, i64),
+            ("deadline_missed", self.deadline_missed, i64),
+            ("evicted", self.evicted, i64),
+        );
+        self.pushed = 0;
+        self.popped = 0;
+        self.expired = 0;
+        self.deadline_missed = 0;
+        self.evicted = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, SystemTime};
+
+    use crate::opportunity_queue::{DropReason, Opportunity, OpportunityQueue};
+
+    fn opportunity(payload: u32, priority: u8, profit: i64, expiry_slot: u64) -> Opportunity<u32> {
+        Opportunity {
+            payload,
+            priority,
+            expiry_slot,
+            deadline: None,
+            expected_profit_lamports: profit,
+        }
+    }
+
+    fn payloads(queue: &OpportunityQueue<u32>) -> Vec<u32> {
+        queue.queued().into_iter().map(|o| o.payload).collect()
+    }
+
+    #[test]
+    fn test_pop_best() {
+        let now = SystemTime::now();
+        let mut queue = OpportunityQueue::new(10);
+        queue.push(opportunity(1, 0, 500, 100), 0, now);
+        queue.push(opportunity(2, 1, 100, 100), 0, now);
+        queue.push(opportunity(3, 0, 900, 100), 0, now);
+        // equally ranked ones come out in the order they were pushed
+        queue.push(opportunity(4, 0, 500, 100), 0, now);
+        assert_eq!(payloads(&queue), vec![2, 3, 1, 4]);
+
+        let popped: Vec<u32> = std::iter::from_fn(|| queue.pop_best(0, now))
+            .map(|o| o.payload)
+            .collect();
+        assert_eq!(popped, vec![2, 3, 1, 4]);
+        assert!(queue.is_empty());
+    }
+
+    #[test]
+    fn test_pop_best_drops_invalid() {
+        let now = SystemTime::now();
+        let mut queue = OpportunityQueue::new(10);
+        queue.push(opportunity(1, 0, 900, 5), 0, now);
+        queue.push(
+            Opportunity {
+                deadline: Some(now - Duration::from_secs(1)),
+                ..opportunity(2, 0, 800, 100)
+            },
+            0,
+            now,
+        );
+        queue.push(opportunity(3, 0, 100, 100), 0, now);
+
+        assert_eq!(queue.pop_best(10, now).map(|o| o.payload), Some(3));
+        let dropped: Vec<(u32, DropReason)> = queue
+            .take_dropped()
+            .into_iter()
+            .map(|(o, reason)| (o.payload, reason))
+            .collect();
+        assert_eq!(
+            dropped,
+            vec![(1, DropReason::Expired), (2, DropReason::DeadlineMissed)]
+        );
+    }
+
+    #[test]
+    fn test_push_evicts_lowest() {
+        let now = SystemTime::now();
+        let mut queue = OpportunityQueue::new(2);
+        queue.push(opportunity(1, 0, 500, 100), 0, now);
+        queue.push(opportunity(2, 0, 300, 100), 0, now);
+        queue.push(opportunity(3, 0, 400, 100), 0, now);
+        assert_eq!(payloads(&queue), vec![1, 3]);
+        // the pushed one is evicted if it ranks lowest
+        queue.push(opportunity(4, 0, 100, 100), 0, now);
+        assert_eq!(payloads(&queue), vec![1, 3]);
+        assert!(queue.take_dropped().is_empty());
+    }
+
+    #[test]
+    fn test_push_drops_expired_before_evicting() {
+        let now = SystemTime::now();
+        let mut queue = OpportunityQueue::new(2);
+        queue.push(opportunity(1, 0, 500, 5), 0, now);
+        queue.push(opportunity(2, 0, 300, 100), 0, now);
+        queue.push(opportunity(3, 0, 100, 100), 10, now);
+
+        assert_eq!(payloads(&queue), vec![2, 3]);
+        let dropped = queue.take_dropped();
+        assert_eq!(dropped.len(), 1);
+        assert_eq!(
+            (dropped[0].0.payload, dropped[0].1),
+            (1, DropReason::Expired)
+        );
+    }
+
+    #[test]
+    fn test_drain() {
+        let now = SystemTime::now();
+        let mut queue = OpportunityQueue::new(10);
+        queue.push(opportunity(1, 0, 100, 100), 0, now);
+        queue.push(opportunity(2, 0, 200, 100), 0, now);
+
+        let drained: Vec<u32> = queue.drain().into_iter().map(|o| o.payload).collect();
+        assert_eq!(drained, vec![2, 1]);
+        assert!(queue.is_empty());
+    }
+}
@@ -0,0 +1,77 @@
+use jito_protos::{
+    convert::{try_versioned_tx_from_packet, PacketConversionError},
+    packet::Packet as ProtoPacket,
+};
+use solana_metrics::datapoint_info;
+use solana_sdk::transaction::VersionedTransaction;
+
+/// Counts how mempool packets fared when converted to transactions, so stream quality issues
+/// show up in metrics instead of disappearing into a filter_map.
+#[derive(Clone, Debug, Default)]
+pub struct PacketConversionStats {
+    pub converted: u64,
+    pub oversized: u64,
+    pub malformed: u64,
+    pub deserialize_failures: u64,
+}
+
+impl PacketConversionStats {
+    pub fn record(&mut self, result: &Result<VersionedTransaction, PacketConversionError>) {
+        match result {
+            Ok(_) => self.converted += 1,
+            Err(PacketConversionError::Oversized(_)) => self.oversized += 1,
+            Err(PacketConversionError::Malformed { .. }) => self.malformed += 1,
+            Err(PacketConversionError::Deserialize(_)) => self.deserialize_failures += 1,
+        }
+    }
+
+    pub fn failed(&self) -> u64 {
+        self.oversized + self.malformed + self.deserialize_failures
+    }
+
+    /// Reports the counters and resets them.
+    pub fn report(&mut self, name: &'static str) {
+        datapoint_info!(
+            name,
+            ("converted", self.converted, i64),
+            ("oversized", self.oversized, i64),
+            ("malformed", self.malformed, i64),
+            ("deserialize_failures", self.deserialize_failures, i64),
+        );
+        *self = Self::default();
+    }
+}
+
+/// Converts packets to transactions, recording each outcome in `stats`.
+/// Packets that fail to convert are skipped, unless `strict` is set in which case the first
+/// failure is returned.
+pub fn convert_packets<'a>(
+    packets: impl IntoIterator<Item = &'a ProtoPacket>,
+    strict: bool,
+    stats: &mut PacketConversionStats,
+) -> Result<Vec<VersionedTransaction>, PacketConversionError> {
+    collect_converted(
+        packets.into_iter().map(try_versioned_tx_from_packet),
+        strict,
+        stats,
+    )
+}
+
+/// Like [`convert_packets`], for packets already converted elsewhere, such as on the blocking
+/// pool.
+pub fn collect_converted(
+    results: impl IntoIterator<Item = Result<VersionedTransaction, PacketConversionError>>,
+    strict: bool,
+    stats: &mut PacketConversionStats,
+) -> Result<Vec<VersionedTransaction>, PacketConversionError> {
+    let mut txs = vec![];
+    for result in results {
+        stats.record(&result);
+        match result {
+            Ok(tx) => txs.push(tx),
+            Err(e) if strict => return Err(e),
+            Err(_) => {}
+        }
+    }
+    Ok(txs)
+}
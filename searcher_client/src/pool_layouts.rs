@@ -0,0 +1,176 @@
+use solana_sdk::{pubkey, pubkey::Pubkey};
+
+pub const RAYDIUM_AMM_V4_PROGRAM_ID: Pubkey =
+    pubkey!("675kPX9MHTjS2zt1qfr1NYHuzeLXfQM9H24wFSUt1Mp8");
+pub const ORCA_WHIRLPOOL_PROGRAM_ID: Pubkey =
+    pubkey!("whirLbMiicVdio4qvUfM5KAg6Ct8VwpYzGff3uctyCc");
+pub const METEORA_DLMM_PROGRAM_ID: Pubkey = pubkey!("LBUZKhRxPF3XUpBCjp4YzTKgLccjZhTSDM9t66M3ZgP");
+
+fn read_pubkey(data: &[u8], offset: usize) -> Option<Pubkey> {
+    Some(Pubkey::new_from_array(
+        data.get(offset..offset + 32)?.try_into().ok()?,
+    ))
+}
+
+fn read_u64(data: &[u8], offset: usize) -> Option<u64> {
+    Some(u64::from_le_bytes(
+        data.get(offset..offset + 8)?.try_into().ok()?,
+    ))
+}
+
+fn read_u128(data: &[u8], offset: usize) -> Option<u128> {
+    Some(u128::from_le_bytes(
+        data.get(offset..offset + 16)?.try_into().ok()?,
+    ))
+}
+
+fn read_u16(data: &[u8], offset: usize) -> Option<u16> {
+    Some(u16::from_le_bytes(
+        data.get(offset..offset + 2)?.try_into().ok()?,
+    ))
+}
+
+fn read_i32(data: &[u8], offset: usize) -> Option<i32> {
+    Some(i32::from_le_bytes(
+        data.get(offset..offset + 4)?.try_into().ok()?,
+    ))
+}
+
+/// The parts of a Raydium AMM v4 pool account (`AmmInfo`) strategies need: its vaults, mints
+/// and LP mint.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RaydiumAmmV4Pool {
+    /// Coin side fees still in the coin vault that aren't part of the reserves.
+    pub need_take_pnl_coin: u64,
+    /// Pc side fees still in the pc vault that aren't part of the reserves.
+    pub need_take_pnl_pc: u64,
+    pub coin_vault: Pubkey,
+    pub pc_vault: Pubkey,
+    pub coin_mint: Pubkey,
+    pub pc_mint: Pubkey,
+    pub lp_mint: Pubkey,
+    pub open_orders: Pubkey,
+    pub market: Pubkey,
+    pub market_program: Pubkey,
+    pub target_orders: Pubkey,
+    pub amm_owner: Pubkey,
+    /// LP tokens the pool has minted and not had burned through withdrawals.
+    pub lp_amount: u64,
+}
+
+impl RaydiumAmmV4Pool {
+    pub const LEN: usize = 752;
+    pub const COIN_MINT_OFFSET: usize = 400;
+    pub const PC_MINT_OFFSET: usize = 432;
+
+    /// None if `data` isn't the size of an `AmmInfo` account.
+    pub fn parse(data: &[u8]) -> Option<Self> {
+        if data.len() != Self::LEN {
+            return None;
+        }
+        Some(Self {
+            need_take_pnl_coin: read_u64(data, 192)?,
+            need_take_pnl_pc: read_u64(data, 200)?,
+            coin_vault: read_pubkey(data, 336)?,
+            pc_vault: read_pubkey(data, 368)?,
+            coin_mint: read_pubkey(data, Self::COIN_MINT_OFFSET)?,
+            pc_mint: read_pubkey(data, Self::PC_MINT_OFFSET)?,
+            lp_mint: read_pubkey(data, 464)?,
+            open_orders: read_pubkey(data, 496)?,
+            market: read_pubkey(data, 528)?,
+            market_program: read_pubkey(data, 560)?,
+            target_orders: read_pubkey(data, 592)?,
+            amm_owner: read_pubkey(data, 688)?,
+            lp_amount: read_u64(data, 720)?,
+        })
+    }
+
+    pub fn has_mint(&self, mint: &Pubkey) -> bool {
+        self.coin_mint == *mint || self.pc_mint == *mint
+    }
+}
+
+/// The parts of an Orca Whirlpool account strategies need: its mints, vaults and current price.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct WhirlpoolPool {
+    pub tick_spacing: u16,
+    /// In hundredths of a basis point.
+    pub fee_rate: u16,
+    /// Liquidity in range at the current tick.
+    pub liquidity: u128,
+    /// Square root of the price of A in B, as a Q64.64 fixed point number.
+    pub sqrt_price: u128,
+    pub tick_current_index: i32,
+    pub mint_a: Pubkey,
+    pub vault_a: Pubkey,
+    pub mint_b: Pubkey,
+    pub vault_b: Pubkey,
+}
+
+impl WhirlpoolPool {
+    pub const LEN: usize = 653;
+    pub const MINT_A_OFFSET: usize = 101;
+    pub const MINT_B_OFFSET: usize = 181;
+
+    /// None if `data` isn't the size of a `Whirlpool` account.
+    pub fn parse(data: &[u8]) -> Option<Self> {
+        if data.len() != Self::LEN {
+            return None;
+        }
+        Some(Self {
+            tick_spacing: read_u16(data, 41)?,
+            fee_rate: read_u16(data, 45)?,
+            liquidity: read_u128(data, 49)?,
+            sqrt_price: read_u128(data, 65)?,
+            tick_current_index: read_i32(data, 81)?,
+            mint_a: read_pubkey(data, Self::MINT_A_OFFSET)?,
+            vault_a: read_pubkey(data, 133)?,
+            mint_b: read_pubkey(data, Self::MINT_B_OFFSET)?,
+            vault_b: read_pubkey(data, 213)?,
+        })
+    }
+
+    /// Price of one raw unit of A in raw units of B.
+    pub fn price(&self) -> f64 {
+        (self.sqrt_price as f64 / (1u128 << 64) as f64).powi(2)
+    }
+}
+
+/// The parts of a Meteora DLMM pair account (`LbPair`) strategies need: its mints, reserves and
+/// active bin.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MeteoraDlmmPool {
+    pub active_id: i32,
+    /// In basis points.
+    pub bin_step: u16,
+    pub token_x_mint: Pubkey,
+    pub token_y_mint: Pubkey,
+    pub reserve_x: Pubkey,
+    pub reserve_y: Pubkey,
+}
+
+impl MeteoraDlmmPool {
+    pub const LEN: usize = 904;
+    pub const TOKEN_X_MINT_OFFSET: usize = 88;
+    pub const TOKEN_Y_MINT_OFFSET: usize = 120;
+
+    /// None if `data` isn't the size of an `LbPair` account.
+    pub fn parse(data: &[u8]) -> Option<Self> {
+        if data.len() != Self::LEN {
+            return None;
+        }
+        Some(Self {
+            active_id: read_i32(data, 76)?,
+            bin_step: read_u16(data, 80)?,
+            token_x_mint: read_pubkey(data, Self::TOKEN_X_MINT_OFFSET)?,
+            token_y_mint: read_pubkey(data, Self::TOKEN_Y_MINT_OFFSET)?,
+            reserve_x: read_pubkey(data, 152)?,
+            reserve_y: read_pubkey(data, 184)?,
+        })
+    }
+
+    /// Price of one raw unit of X in raw units of Y in the active bin.
+    pub fn price(&self) -> f64 {
+        (1.0 + self.bin_step as f64 / 10_000.0).powi(self.active_id)
+    }
+}
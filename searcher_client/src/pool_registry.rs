@@ -0,0 +1,461 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::{self, Display, Formatter},
+    str::FromStr,
+};
+
+use serde::Deserialize;
+use solana_account_decoder::UiAccountEncoding;
+use solana_client::{
+    client_error::ClientError,
+    nonblocking::rpc_client::RpcClient,
+    rpc_config::{RpcAccountInfoConfig, RpcProgramAccountsConfig},
+    rpc_filter::{Memcmp, RpcFilterType},
+    rpc_request::MAX_MULTIPLE_ACCOUNTS,
+};
+use solana_metrics::datapoint_info;
+use solana_sdk::{account::Account, clock::Slot, pubkey::Pubkey};
+use spl_token_2022::{extension::StateWithExtensions, state::Account as TokenAccount};
+use thiserror::Error;
+
+use crate::pool_layouts::{
+    MeteoraDlmmPool, RaydiumAmmV4Pool, WhirlpoolPool, METEORA_DLMM_PROGRAM_ID,
+    ORCA_WHIRLPOOL_PROGRAM_ID, RAYDIUM_AMM_V4_PROGRAM_ID,
+};
+
+#[derive(Debug, Error)]
+pub enum PoolRegistryError {
+    #[error("rpc error: {0}")]
+    Rpc(#[from] ClientError),
+}
+
+/// An AMM program the registry can discover pools of.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Dex {
+    RaydiumAmmV4,
+    OrcaWhirlpool,
+    MeteoraDlmm,
+}
+
+impl Dex {
+    pub const ALL: [Dex; 3] = [Dex::RaydiumAmmV4, Dex::OrcaWhirlpool, Dex::MeteoraDlmm];
+
+    pub fn program_id(self) -> Pubkey {
+        match self {
+            Dex::RaydiumAmmV4 => RAYDIUM_AMM_V4_PROGRAM_ID,
+            Dex::OrcaWhirlpool => ORCA_WHIRLPOOL_PROGRAM_ID,
+            Dex::MeteoraDlmm => METEORA_DLMM_PROGRAM_ID,
+        }
+    }
+
+    fn account_len(self) -> usize {
+        match self {
+            Dex::RaydiumAmmV4 => RaydiumAmmV4Pool::LEN,
+            Dex::OrcaWhirlpool => WhirlpoolPool::LEN,
+            Dex::MeteoraDlmm => MeteoraDlmmPool::LEN,
+        }
+    }
+
+    /// Where the pool's two mints are in its account.
+    fn mint_offsets(self) -> (usize, usize) {
+        match self {
+            Dex::RaydiumAmmV4 => (
+                RaydiumAmmV4Pool::COIN_MINT_OFFSET,
+                RaydiumAmmV4Pool::PC_MINT_OFFSET,
+            ),
+            Dex::OrcaWhirlpool => (WhirlpoolPool::MINT_A_OFFSET, WhirlpoolPool::MINT_B_OFFSET),
+            Dex::MeteoraDlmm => (
+                MeteoraDlmmPool::TOKEN_X_MINT_OFFSET,
+                MeteoraDlmmPool::TOKEN_Y_MINT_OFFSET,
+            ),
+        }
+    }
+
+    fn parse(self, data: &[u8]) -> Option<PoolLayout> {
+        match self {
+            Dex::RaydiumAmmV4 => {
+                RaydiumAmmV4Pool::parse(data).map(|pool| PoolLayout::RaydiumAmmV4(Box::new(pool)))
+            }
+            Dex::OrcaWhirlpool => WhirlpoolPool::parse(data).map(PoolLayout::OrcaWhirlpool),
+            Dex::MeteoraDlmm => MeteoraDlmmPool::parse(data).map(PoolLayout::MeteoraDlmm),
+        }
+    }
+}
+
+impl Display for Dex {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Dex::RaydiumAmmV4 => write!(f, "raydium-amm-v4"),
+            Dex::OrcaWhirlpool => write!(f, "orca-whirlpool"),
+            Dex::MeteoraDlmm => write!(f, "meteora-dlmm"),
+        }
+    }
+}
+
+impl FromStr for Dex {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "raydium-amm-v4" => Ok(Dex::RaydiumAmmV4),
+            "orca-whirlpool" => Ok(Dex::OrcaWhirlpool),
+            "meteora-dlmm" => Ok(Dex::MeteoraDlmm),
+            _ => Err(format!(
+                "unknown dex {s:?}, expected raydium-amm-v4, orca-whirlpool or meteora-dlmm"
+            )),
+        }
+    }
+}
+
+/// Two mints, in no particular order: pools of either orientation belong to the pair.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Deserialize)]
+#[serde(try_from = "String")]
+pub struct TokenPair {
+    a: Pubkey,
+    b: Pubkey,
+}
+
+impl TokenPair {
+    pub fn new(a: Pubkey, b: Pubkey) -> Self {
+        if a <= b {
+            Self { a, b }
+        } else {
+            Self { a: b, b: a }
+        }
+    }
+
+    /// The mints, ordered by address.
+    pub fn mints(&self) -> (Pubkey, Pubkey) {
+        (self.a, self.b)
+    }
+}
+
+impl Display for TokenPair {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.a, self.b)
+    }
+}
+
+impl FromStr for TokenPair {
+    type Err = String;
+
+    /// Parses `MINT:MINT`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (a, b) = s
+            .split_once(':')
+            .ok_or_else(|| format!("expected MINT:MINT, got {s:?}"))?;
+        let parse = |mint: &str| {
+            Pubkey::from_str(mint.trim()).map_err(|e| format!("invalid mint {mint:?}: {e}"))
+        };
+        let (a, b) = (parse(a)?, parse(b)?);
+        if a == b {
+            return Err(format!("pair {s:?} has the same mint twice"));
+        }
+        Ok(Self::new(a, b))
+    }
+}
+
+impl TryFrom<String> for TokenPair {
+    type Error = String;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+/// A pool account's parsed layout.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum PoolLayout {
+    RaydiumAmmV4(Box<RaydiumAmmV4Pool>),
+    OrcaWhirlpool(WhirlpoolPool),
+    MeteoraDlmm(MeteoraDlmmPool),
+}
+
+impl PoolLayout {
+    pub fn dex(&self) -> Dex {
+        match self {
+            PoolLayout::RaydiumAmmV4(_) => Dex::RaydiumAmmV4,
+            PoolLayout::OrcaWhirlpool(_) => Dex::OrcaWhirlpool,
+            PoolLayout::MeteoraDlmm(_) => Dex::MeteoraDlmm,
+        }
+    }
+
+    /// The pool's mints, in the order the pool stores them.
+    pub fn mints(&self) -> (Pubkey, Pubkey) {
+        match self {
+            PoolLayout::RaydiumAmmV4(pool) => (pool.coin_mint, pool.pc_mint),
+            PoolLayout::OrcaWhirlpool(pool) => (pool.mint_a, pool.mint_b),
+            PoolLayout::MeteoraDlmm(pool) => (pool.token_x_mint, pool.token_y_mint),
+        }
+    }
+
+    /// The token accounts holding each side's liquidity, in the same order as `mints`.
+    pub fn vaults(&self) -> (Pubkey, Pubkey) {
+        match self {
+            PoolLayout::RaydiumAmmV4(pool) => (pool.coin_vault, pool.pc_vault),
+            PoolLayout::OrcaWhirlpool(pool) => (pool.vault_a, pool.vault_b),
+            PoolLayout::MeteoraDlmm(pool) => (pool.reserve_x, pool.reserve_y),
+        }
+    }
+
+    /// Vault balances less what the pool holds that isn't tradable liquidity.
+    fn reserves(&self, vault_balances: (u64, u64)) -> (u64, u64) {
+        match self {
+            PoolLayout::RaydiumAmmV4(pool) => (
+                vault_balances.0.saturating_sub(pool.need_take_pnl_coin),
+                vault_balances.1.saturating_sub(pool.need_take_pnl_pc),
+            ),
+            PoolLayout::OrcaWhirlpool(_) | PoolLayout::MeteoraDlmm(_) => vault_balances,
+        }
+    }
+}
+
+/// A discovered pool and its last read state.
+#[derive(Clone, Debug)]
+pub struct Pool {
+    pub address: Pubkey,
+    pub layout: PoolLayout,
+    /// Raw token amounts available to trade, in the same order as the layout's mints. For the
+    /// concentrated liquidity dexes this is everything in the vaults, not only the liquidity
+    /// around the current price. None until the first refresh.
+    pub reserves: Option<(u64, u64)>,
+    /// Slot the layout and reserves were read at.
+    pub slot: Slot,
+}
+
+impl Pool {
+    pub fn pair(&self) -> TokenPair {
+        let (a, b) = self.layout.mints();
+        TokenPair::new(a, b)
+    }
+
+    /// Reserves of `mint` and of the other side, None if the pool doesn't trade `mint` or
+    /// hasn't been refreshed.
+    pub fn reserves_of(&self, mint: &Pubkey) -> Option<(u64, u64)> {
+        let (first, second) = self.reserves?;
+        let (first_mint, second_mint) = self.layout.mints();
+        if *mint == first_mint {
+            Some((first, second))
+        } else if *mint == second_mint {
+            Some((second, first))
+        } else {
+            None
+        }
+    }
+
+    /// Spot price of one raw unit of `mint` in raw units of the other side: the reserve ratio
+    /// for Raydium, the current price for the concentrated liquidity dexes. None if the pool
+    /// doesn't trade `mint`, or for Raydium hasn't been refreshed or is empty.
+    pub fn price_of(&self, mint: &Pubkey) -> Option<f64> {
+        let (first_mint, second_mint) = self.layout.mints();
+        let first_price = match &self.layout {
+            PoolLayout::RaydiumAmmV4(_) => {
+                let (first, second) = self.reserves.filter(|(first, _)| *first > 0)?;
+                second as f64 / first as f64
+            }
+            PoolLayout::OrcaWhirlpool(pool) => pool.price(),
+            PoolLayout::MeteoraDlmm(pool) => pool.price(),
+        };
+        if *mint == first_mint {
+            Some(first_price)
+        } else if *mint == second_mint && first_price > 0.0 {
+            Some(1.0 / first_price)
+        } else {
+            None
+        }
+    }
+}
+
+/// Finds the AMM pools trading the configured token pairs on each dex and keeps their layouts
+/// and reserves up to date, for strategies to quote and build swaps from.
+///
+/// `discover` scans each dex program's accounts for pools holding both mints of a pair, which
+/// is an expensive `getProgramAccounts` call and meant to run rarely. `refresh` rereads the
+/// known pools and their vaults with `getMultipleAccounts`, and is meant to run every few slots.
+pub struct PoolRegistry {
+    pairs: Vec<TokenPair>,
+    dexes: Vec<Dex>,
+    pools: HashMap<Pubkey, Pool>,
+    scans: u64,
+    discovered: u64,
+    refreshes: u64,
+    closed: u64,
+}
+
+impl PoolRegistry {
+    pub fn new(pairs: Vec<TokenPair>, dexes: Vec<Dex>) -> Self {
+        Self {
+            pairs,
+            dexes,
+            pools: HashMap::new(),
+            scans: 0,
+            discovered: 0,
+            refreshes: 0,
+            closed: 0,
+        }
+    }
+
+    /// Scans for the pairs' pools, adding new ones and forgetting ones that are gone. Returns
+    /// how many pools are new.
+    pub async fn discover(&mut self, rpc_client: &RpcClient) -> Result<usize, PoolRegistryError> {
+        let mut found = HashMap::new();
+        for pair in &self.pairs {
+            for dex in &self.dexes {
+                for (first, second) in [(pair.a, pair.b), (pair.b, pair.a)] {
+                    let (first_offset, second_offset) = dex.mint_offsets();
+                    let config = RpcProgramAccountsConfig {
+                        filters: Some(vec![
+                            RpcFilterType::DataSize(dex.account_len() as u64),
+                            RpcFilterType::Memcmp(Memcmp::new_base58_encoded(
+                                first_offset,
+                                &first.to_bytes(),
+                            )),
+                            RpcFilterType::Memcmp(Memcmp::new_base58_encoded(
+                                second_offset,
+                                &second.to_bytes(),
+                            )),
+                        ]),
+                        account_config: RpcAccountInfoConfig {
+                            encoding: Some(UiAccountEncoding::Base64),
+                            commitment: Some(rpc_client.commitment()),
+                            ..RpcAccountInfoConfig::default()
+                        },
+                        ..RpcProgramAccountsConfig::default()
+                    };
+                    let accounts = rpc_client
+                        .get_program_accounts_with_config(&dex.program_id(), config)
+                        .await?;
+                    self.scans += 1;
+                    for (address, account) in accounts {
+                        if let Some(layout) = dex.parse(&account.data) {
+                            found.insert(address, layout);
+                        }
+                    }
+                }
+            }
+        }
+
+        let mut new = 0;
+        let mut pools = HashMap::with_capacity(found.len());
+        for (address, layout) in found {
+            let pool = match self.pools.remove(&address) {
+                Some(known) => Pool { layout, ..known },
+                None => {
+                    new += 1;
+                    Pool {
+                        address,
+                        layout,
+                        reserves: None,
+                        slot: 0,
+                    }
+                }
+            };
+            pools.insert(address, pool);
+        }
+        self.closed += self.pools.len() as u64;
+        self.pools = pools;
+        self.discovered += new as u64;
+        Ok(new)
+    }
+
+    /// Rereads every known pool and its vaults. Pools whose account is gone are forgotten.
+    /// Accounts are fetched in batches, so a pool and its vaults may be read a few slots apart.
+    pub async fn refresh(&mut self, rpc_client: &RpcClient) -> Result<(), PoolRegistryError> {
+        let addresses: Vec<Pubkey> = self
+            .pools
+            .values()
+            .flat_map(|pool| {
+                let (first, second) = pool.layout.vaults();
+                [pool.address, first, second]
+            })
+            .collect::<HashSet<_>>()
+            .into_iter()
+            .collect();
+        let mut accounts: HashMap<Pubkey, (Option<Account>, Slot)> = HashMap::new();
+        for chunk in addresses.chunks(MAX_MULTIPLE_ACCOUNTS) {
+            let response = rpc_client
+                .get_multiple_accounts_with_commitment(chunk, rpc_client.commitment())
+                .await?;
+            let slot = response.context.slot;
+            accounts.extend(
+                chunk
+                    .iter()
+                    .zip(response.value)
+                    .map(|(address, account)| (*address, (account, slot))),
+            );
+        }
+
+        let closed = self.pools.len();
+        self.pools.retain(|address, pool| {
+            let Some((Some(account), slot)) = accounts.get(address) else {
+                return false;
+            };
+            let Some(layout) = pool.layout.dex().parse(&account.data) else {
+                return false;
+            };
+            let (first, second) = layout.vaults();
+            let vault_balance = |vault| match accounts.get(&vault) {
+                Some((Some(account), _)) => {
+                    StateWithExtensions::<TokenAccount>::unpack(&account.data)
+                        .ok()
+                        .map(|state| state.base.amount)
+                }
+                _ => None,
+            };
+            pool.reserves = vault_balance(first)
+                .zip(vault_balance(second))
+                .map(|balances| layout.reserves(balances));
+            pool.layout = layout;
+            pool.slot = *slot;
+            true
+        });
+        self.closed += (closed - self.pools.len()) as u64;
+        self.refreshes += 1;
+        Ok(())
+    }
+
+    pub fn pool(&self, address: &Pubkey) -> Option<&Pool> {
+        self.pools.get(address)
+    }
+
+    pub fn pools(&self) -> impl Iterator<Item = &Pool> {
+        self.pools.values()
+    }
+
+    /// Known pools trading `pair`, on any dex.
+    pub fn pools_for(&self, pair: TokenPair) -> impl Iterator<Item = &Pool> {
+        self.pools.values().filter(move |pool| pool.pair() == pair)
+    }
+
+    /// Known pools trading `mint` against anything.
+    pub fn pools_with_mint<'a>(&'a self, mint: &'a Pubkey) -> impl Iterator<Item = &'a Pool> {
+        self.pools.values().filter(move |pool| {
+            let (first, second) = pool.layout.mints();
+            first == *mint || second == *mint
+        })
+    }
+
+    /// Reports the counters and resets them.
+    pub fn report(&mut self, name: &'static str) {
+        datapoint_info!(
+            name,
+            ("pools", self.pools.len(), i64),
+            (
+                "pools_without_reserves",
+                self.pools
+                    .values()
+                    .filter(|pool| pool.reserves.is_none())
+                    .count(),
+                i64
+            ),
+            ("scans", self.scans, i64),
+            ("discovered", self.discovered, i64),
+            ("refreshes", self.refreshes, i64),
+            ("closed", self.closed, i64),
+        );
+        self.scans = 0;
+        self.discovered = 0;
+        self.refreshes = 0;
+        self.closed = 0;
+    }
+}
@@ -0,0 +1,295 @@
+use std::{
+    collections::HashMap,
+    fmt::{self, Display, Formatter},
+    str::FromStr,
+};
+
+use log::error;
+use solana_client::{
+    client_error::{ClientErrorKind, Result as ClientResult},
+    nonblocking::rpc_client::RpcClient,
+    rpc_config::RpcTransactionConfig,
+};
+use solana_metrics::datapoint_info;
+use solana_sdk::{commitment_config::CommitmentConfig, pubkey::Pubkey, signature::Signature};
+use solana_transaction_status::{
+    option_serializer::OptionSerializer, UiTransactionEncoding, UiTransactionTokenBalance,
+};
+use spl_token_2022::{extension::StateWithExtensions, state::Account as TokenAccount};
+
+use crate::amount::parse_lamports;
+
+/// Kinds of condition [`PostCondition::from_str`] parses.
+const POST_CONDITION_KINDS: [&str; 4] = [
+    "min-lamports",
+    "lamports-delta",
+    "min-tokens",
+    "token-delta",
+];
+
+/// Something a bundle's author expects to hold once it lands, checked against the chain
+/// afterwards rather than enforced on-chain like the `assertions` module, so a strategy whose
+/// assumptions silently broke gets flagged even when its bundles keep landing.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PostCondition {
+    /// The account holds at least `min_lamports` after the bundle.
+    LamportsAtLeast { account: Pubkey, min_lamports: u64 },
+    /// The bundle changed the account's lamports by exactly `delta`.
+    LamportsDelta { account: Pubkey, delta: i64 },
+    /// The token account holds at least `min_amount` base units after the bundle.
+    TokenAmountAtLeast {
+        token_account: Pubkey,
+        min_amount: u64,
+    },
+    /// The bundle changed the token account's balance by exactly `delta` base units.
+    TokenAmountDelta { token_account: Pubkey, delta: i64 },
+}
+
+impl PostCondition {
+    pub fn account(&self) -> Pubkey {
+        match self {
+            Self::LamportsAtLeast { account, .. } | Self::LamportsDelta { account, .. } => *account,
+            Self::TokenAmountAtLeast { token_account, .. }
+            | Self::TokenAmountDelta { token_account, .. } => *token_account,
+        }
+    }
+
+    /// Whether `actual`, the balance or change the condition is about, satisfies it.
+    fn holds(&self, actual: i128) -> bool {
+        match *self {
+            Self::LamportsAtLeast { min_lamports, .. } => actual >= min_lamports as i128,
+            Self::LamportsDelta { delta, .. } | Self::TokenAmountDelta { delta, .. } => {
+                actual == delta as i128
+            }
+            Self::TokenAmountAtLeast { min_amount, .. } => actual >= min_amount as i128,
+        }
+    }
+}
+
+impl Display for PostCondition {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::LamportsAtLeast {
+                account,
+                min_lamports,
+            } => write!(f, "{account} holds at least {min_lamports} lamports"),
+            Self::LamportsDelta { account, delta } => {
+                write!(f, "{account} changes by {delta:+} lamports")
+            }
+            Self::TokenAmountAtLeast {
+                token_account,
+                min_amount,
+            } => write!(f, "{token_account} holds at least {min_amount} token units"),
+            Self::TokenAmountDelta {
+                token_account,
+                delta,
+            } => write!(f, "{token_account} changes by {delta:+} token units"),
+        }
+    }
+}
+
+impl FromStr for PostCondition {
+    type Err = String;
+
+    /// Parses `KIND:ACCOUNT:VALUE`, KIND being `min-lamports`, `lamports-delta`, `min-tokens` or
+    /// `token-delta`. Lamport values take units like `0.5sol`, token values are base units, and
+    /// deltas may be negative.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parts = s.splitn(3, ':');
+        let (Some(kind), Some(account), Some(value)) = (parts.next(), parts.next(), parts.next())
+        else {
+            return Err(format!("expected KIND:ACCOUNT:VALUE, got {s:?}"));
+        };
+        let kind = kind.trim();
+        if !POST_CONDITION_KINDS.contains(&kind) {
+            return Err(format!(
+                "unknown condition {kind:?}, use one of {}",
+                POST_CONDITION_KINDS.join(", ")
+            ));
+        }
+        let account = Pubkey::from_str(account.trim())
+            .map_err(|e| format!("invalid account {account:?}: {e}"))?;
+        let invalid = |e: &dyn Display| format!("invalid value {value:?}: {e}");
+        let signed = |parse: &dyn Fn(&str) -> Result<u64, String>| {
+            let (negative, magnitude) = match value.trim().strip_prefix('-') {
+                Some(magnitude) => (true, magnitude),
+                None => (false, value.trim().trim_start_matches('+')),
+            };
+            let magnitude = i64::try_from(parse(magnitude)?).map_err(|e| invalid(&e))?;
+            Ok::<_, String>(if negative { -magnitude } else { magnitude })
+        };
+        let lamports = |value: &str| parse_lamports(value).map_err(|e| invalid(&e));
+        let units = |value: &str| value.trim().parse::<u64>().map_err(|e| invalid(&e));
+        match kind {
+            "min-lamports" => Ok(Self::LamportsAtLeast {
+                account,
+                min_lamports: lamports(value)?,
+            }),
+            "lamports-delta" => Ok(Self::LamportsDelta {
+                account,
+                delta: signed(&lamports)?,
+            }),
+            "min-tokens" => Ok(Self::TokenAmountAtLeast {
+                token_account: account,
+                min_amount: units(value)?,
+            }),
+            "token-delta" => Ok(Self::TokenAmountDelta {
+                token_account: account,
+                delta: signed(&units)?,
+            }),
+            _ => unreachable!("checked above"),
+        }
+    }
+}
+
+/// A post-condition that didn't hold once the bundle landed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PostConditionViolation {
+    pub condition: PostCondition,
+    /// The balance, or change in balance, found instead.
+    pub actual: i128,
+}
+
+impl Display for PostConditionViolation {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let found = match self.condition {
+            PostCondition::LamportsDelta { .. } | PostCondition::TokenAmountDelta { .. } => {
+                format!("{:+}", self.actual)
+            }
+            _ => self.actual.to_string(),
+        };
+        write!(f, "expected {}, found {found}", self.condition)
+    }
+}
+
+/// An account's balance before a bundle's first transaction and after its last.
+#[derive(Clone, Copy, Debug)]
+struct BalanceChange {
+    before: i128,
+    after: i128,
+}
+
+/// Checks `conditions` against the landed transactions of a bundle. Balance changes come from
+/// the transactions' metadata, so they cover the bundle alone even if the accounts changed since.
+/// Minimums on accounts the bundle didn't touch are checked against their current state.
+pub async fn verify_post_conditions(
+    rpc_client: &RpcClient,
+    signatures: &[Signature],
+    conditions: &[PostCondition],
+) -> ClientResult<Vec<PostConditionViolation>> {
+    if conditions.is_empty() {
+        return Ok(vec![]);
+    }
+    let mut lamports: HashMap<Pubkey, BalanceChange> = HashMap::new();
+    let mut tokens: HashMap<Pubkey, BalanceChange> = HashMap::new();
+    for signature in signatures {
+        let confirmed = rpc_client
+            .get_transaction_with_config(
+                signature,
+                RpcTransactionConfig {
+                    encoding: Some(UiTransactionEncoding::Base64),
+                    commitment: Some(CommitmentConfig::confirmed()),
+                    max_supported_transaction_version: Some(0),
+                },
+            )
+            .await?;
+        let missing = || {
+            ClientErrorKind::Custom(format!("rpc returned transaction {signature} without meta"))
+        };
+        let meta = confirmed.transaction.meta.ok_or_else(missing)?;
+        let tx = confirmed
+            .transaction
+            .transaction
+            .decode()
+            .ok_or_else(missing)?;
+        let mut keys = tx.message.static_account_keys().to_vec();
+        if let OptionSerializer::Some(loaded) = &meta.loaded_addresses {
+            for address in loaded.writable.iter().chain(&loaded.readonly) {
+                keys.push(Pubkey::from_str(address).map_err(|_| missing())?);
+            }
+        }
+
+        for ((key, pre), post) in keys.iter().zip(&meta.pre_balances).zip(&meta.post_balances) {
+            lamports
+                .entry(*key)
+                .or_insert(BalanceChange {
+                    before: *pre as i128,
+                    after: 0,
+                })
+                .after = *post as i128;
+        }
+        let token_amounts = |balances: OptionSerializer<Vec<UiTransactionTokenBalance>>| {
+            Option::<Vec<_>>::from(balances)
+                .unwrap_or_default()
+                .into_iter()
+                .filter_map(|balance| {
+                    let key = *keys.get(balance.account_index as usize)?;
+                    Some((key, balance.ui_token_amount.amount.parse::<i128>().ok()?))
+                })
+                .collect::<HashMap<Pubkey, i128>>()
+        };
+        let pre_tokens = token_amounts(meta.pre_token_balances);
+        let post_tokens = token_amounts(meta.post_token_balances);
+        // accounts the transaction creates or closes are missing from one side, holding nothing
+        for key in pre_tokens.keys().chain(post_tokens.keys()) {
+            tokens
+                .entry(*key)
+                .or_insert(BalanceChange {
+                    before: pre_tokens.get(key).copied().unwrap_or_default(),
+                    after: 0,
+                })
+                .after = post_tokens.get(key).copied().unwrap_or_default();
+        }
+    }
+
+    let mut violations = vec![];
+    for condition in conditions {
+        let account = condition.account();
+        let actual = match condition {
+            PostCondition::LamportsAtLeast { .. } => match lamports.get(&account) {
+                Some(change) => change.after,
+                None => rpc_client.get_balance(&account).await? as i128,
+            },
+            PostCondition::TokenAmountAtLeast { .. } => match tokens.get(&account) {
+                Some(change) => change.after,
+                None => rpc_client
+                    .get_account_with_commitment(&account, rpc_client.commitment())
+                    .await?
+                    .value
+                    .and_then(|account| {
+                        let state = StateWithExtensions::<TokenAccount>::unpack(&account.data);
+                        state.ok().map(|state| state.base.amount as i128)
+                    })
+                    .unwrap_or_default(),
+            },
+            PostCondition::LamportsDelta { .. } => lamports
+                .get(&account)
+                .map_or(0, |change| change.after - change.before),
+            PostCondition::TokenAmountDelta { .. } => tokens
+                .get(&account)
+                .map_or(0, |change| change.after - change.before),
+        };
+        if !condition.holds(actual) {
+            violations.push(PostConditionViolation {
+                condition: *condition,
+                actual,
+            });
+        }
+    }
+    Ok(violations)
+}
+
+/// Logs each violation as an error and reports it as a `post_condition_violation` metric,
+/// `bundle` naming the bundle they were found in.
+pub fn report_violations(bundle: &str, violations: &[PostConditionViolation]) {
+    for violation in violations {
+        error!("bundle {bundle} broke a post-condition: {violation}");
+        datapoint_info!(
+            "post_condition_violation",
+            "account" => violation.condition.account().to_string(),
+            ("bundle", bundle, String),
+            ("condition", violation.condition.to_string(), String),
+            ("actual", violation.actual as i64, i64),
+        );
+    }
+}
@@ -0,0 +1,94 @@
+use std::{
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use solana_client::{client_error::ClientError, nonblocking::rpc_client::RpcClient};
+use solana_sdk::{native_token::lamports_to_sol, pubkey::Pubkey};
+use thiserror::Error;
+
+/// Pyth SOL/USD price account on mainnet-beta.
+pub const PYTH_SOL_USD_PRICE_ACCOUNT: &str = "H6ARHf6YXhGYeQfUzQNGk6rDNnLBQKrenN712K4AQJEG";
+
+// offsets into a pyth v2 price account
+const PYTH_MAGIC: u32 = 0xa1b2c3d4;
+const PYTH_EXPO_OFFSET: usize = 20;
+const PYTH_AGG_PRICE_OFFSET: usize = 208;
+
+#[derive(Debug, Error)]
+pub enum PriceFeedError {
+    #[error("rpc error {0}")]
+    RpcError(#[from] ClientError),
+    #[error("invalid price account: {0}")]
+    InvalidPriceAccount(String),
+}
+
+/// Reads the SOL/USD price from a Pyth price account, caching it so reports don't hit the RPC
+/// server on every line.
+pub struct SolPriceFeed {
+    price_account: Pubkey,
+    ttl: Duration,
+    cached: Mutex<Option<(f64, Instant)>>,
+}
+
+impl SolPriceFeed {
+    pub fn new(price_account: Pubkey, ttl: Duration) -> Self {
+        Self {
+            price_account,
+            ttl,
+            cached: Mutex::new(None),
+        }
+    }
+
+    pub async fn sol_usd(&self, rpc_client: &RpcClient) -> Result<f64, PriceFeedError> {
+        if let Some((price, fetched_at)) = *self.cached.lock().unwrap() {
+            if fetched_at.elapsed() < self.ttl {
+                return Ok(price);
+            }
+        }
+
+        let data = rpc_client.get_account_data(&self.price_account).await?;
+        let price = parse_pyth_price(&data)?;
+        *self.cached.lock().unwrap() = Some((price, Instant::now()));
+        Ok(price)
+    }
+
+    pub async fn lamports_to_usd(
+        &self,
+        rpc_client: &RpcClient,
+        lamports: u64,
+    ) -> Result<f64, PriceFeedError> {
+        Ok(lamports_to_sol(lamports) * self.sol_usd(rpc_client).await?)
+    }
+}
+
+fn parse_pyth_price(data: &[u8]) -> Result<f64, PriceFeedError> {
+    let read = |offset: usize, len: usize| {
+        data.get(offset..offset + len)
+            .ok_or_else(|| PriceFeedError::InvalidPriceAccount("account too small".into()))
+    };
+
+    let magic = u32::from_le_bytes(read(0, 4)?.try_into().unwrap());
+    if magic != PYTH_MAGIC {
+        return Err(PriceFeedError::InvalidPriceAccount(format!(
+            "bad magic {magic:#x}"
+        )));
+    }
+    let expo = i32::from_le_bytes(read(PYTH_EXPO_OFFSET, 4)?.try_into().unwrap());
+    let price = i64::from_le_bytes(read(PYTH_AGG_PRICE_OFFSET, 8)?.try_into().unwrap());
+    if price <= 0 {
+        return Err(PriceFeedError::InvalidPriceAccount(format!(
+            "non-positive price {price}"
+        )));
+    }
+    Ok(price as f64 * 10f64.powi(expo))
+}
+
+/// Formats a lamport amount for reports, with a USD value when a price is known.
+pub fn format_lamports(lamports: u64, sol_usd: Option<f64>) -> String {
+    let sol = lamports_to_sol(lamports);
+    match sol_usd {
+        Some(price) => format!("{lamports} lamports ({sol} SOL, ${:.2})", sol * price),
+        None => format!("{lamports} lamports ({sol} SOL)"),
+    }
+}
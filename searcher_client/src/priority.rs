@@ -0,0 +1,71 @@
+use std::{
+    fmt::{self, Display, Formatter},
+    str::FromStr,
+};
+
+use serde::{Deserialize, Serialize};
+
+/// How urgent a bundle is. Critical bundles are sent before queued normal and bulk ones, tip
+/// more and may go over the per window bundle limit by a reserved quota.
+#[derive(
+    Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum PriorityClass {
+    Bulk,
+    #[default]
+    Normal,
+    Critical,
+}
+
+impl PriorityClass {
+    /// The opportunity queue's priority, higher is sent first.
+    pub fn rank(self) -> u8 {
+        self as u8
+    }
+}
+
+impl Display for PriorityClass {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            PriorityClass::Bulk => write!(f, "bulk"),
+            PriorityClass::Normal => write!(f, "normal"),
+            PriorityClass::Critical => write!(f, "critical"),
+        }
+    }
+}
+
+impl FromStr for PriorityClass {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "bulk" => Ok(PriorityClass::Bulk),
+            "normal" => Ok(PriorityClass::Normal),
+            "critical" => Ok(PriorityClass::Critical),
+            _ => Err(format!(
+                "unknown priority class {s:?}, expected bulk, normal or critical"
+            )),
+        }
+    }
+}
+
+/// Scales tips by priority class, then all of them by `scale`. Normal bundles tip what they
+/// asked for at a scale of 1.
+#[derive(Clone, Copy, Debug)]
+pub struct TipMultipliers {
+    pub bulk: f64,
+    pub critical: f64,
+    pub scale: f64,
+}
+
+impl TipMultipliers {
+    pub fn apply(&self, class: PriorityClass, tip_lamports: u64) -> u64 {
+        let multiplier = match class {
+            PriorityClass::Bulk => self.bulk,
+            PriorityClass::Normal => 1.0,
+            PriorityClass::Critical => self.critical,
+        };
+        (tip_lamports as f64 * multiplier * self.scale).round() as u64
+    }
+}
@@ -0,0 +1,388 @@
+use std::{
+    fmt::{self, Display, Formatter},
+    io::{self, BufRead, Read, Write},
+    str::FromStr,
+};
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use chrono::{DateTime, TimeZone, Utc};
+use jito_protos::{
+    bundle::BundleResult,
+    recording::{record, MempoolTransaction, Record, RecordingHeader},
+};
+use prost::Message;
+use serde_json::{json, Value};
+use solana_sdk::{clock::Slot, transaction::VersionedTransaction};
+use thiserror::Error;
+
+/// Version of the recording schema written by this crate. Records without one are version 0,
+/// the mempool JSON lines written before the schema was versioned.
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// Starts a protobuf recording, before its length-delimited header.
+const MAGIC: &[u8; 4] = b"JREC";
+
+/// Length prefixes above this are a corrupt file rather than a record.
+const MAX_RECORD_BYTES: u64 = 64 * 1024 * 1024;
+
+#[derive(Debug, Error)]
+pub enum RecordingError {
+    #[error("failed to access recording: {0}")]
+    Io(#[from] io::Error),
+    #[error("invalid recording record: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("invalid protobuf recording: {0}")]
+    Decode(#[from] prost::DecodeError),
+    #[error("recording schema version {0} is newer than {SCHEMA_VERSION}, the newest supported")]
+    UnsupportedVersion(u32),
+    #[error("invalid recording record: {0}")]
+    Invalid(String),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RecordingFormat {
+    /// One JSON object per line, as record-mempool writes.
+    Jsonl,
+    /// The schema in `recording.proto`.
+    Protobuf,
+}
+
+impl Display for RecordingFormat {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            RecordingFormat::Jsonl => write!(f, "jsonl"),
+            RecordingFormat::Protobuf => write!(f, "protobuf"),
+        }
+    }
+}
+
+impl FromStr for RecordingFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "jsonl" => Ok(RecordingFormat::Jsonl),
+            "protobuf" => Ok(RecordingFormat::Protobuf),
+            _ => Err(format!(
+                "invalid recording format {s:?}, expected jsonl or protobuf"
+            )),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub enum RecordedData {
+    MempoolTransaction {
+        slot: Slot,
+        transaction: VersionedTransaction,
+    },
+    BundleResult(BundleResult),
+}
+
+/// One recorded piece of data and when the recorder received it.
+#[derive(Clone, Debug)]
+pub struct Recorded {
+    pub received_at: Option<DateTime<Utc>>,
+    pub data: RecordedData,
+}
+
+impl Recorded {
+    pub fn mempool_transaction(
+        received_at: DateTime<Utc>,
+        slot: Slot,
+        transaction: VersionedTransaction,
+    ) -> Self {
+        Self {
+            received_at: Some(received_at),
+            data: RecordedData::MempoolTransaction { slot, transaction },
+        }
+    }
+
+    /// The record as a JSON line object, at the current schema version. Transactions are base64
+    /// bincode and bundle results base64 protobuf, with their signature and bundle id alongside
+    /// for reading.
+    pub fn to_json(&self) -> Value {
+        let received_at = self.received_at.map(|at| at.to_rfc3339());
+        match &self.data {
+            RecordedData::MempoolTransaction { slot, transaction } => json!({
+                "schema_version": SCHEMA_VERSION,
+                "kind": "mempool_transaction",
+                "received_at": received_at,
+                "slot": slot,
+                "signature": transaction.signatures.first().map(|s| s.to_string()),
+                "transaction": STANDARD.encode(bincode::serialize(transaction).expect("serializes")),
+            }),
+            RecordedData::BundleResult(result) => json!({
+                "schema_version": SCHEMA_VERSION,
+                "kind": "bundle_result",
+                "received_at": received_at,
+                "bundle_id": result.bundle_id,
+                "bundle_result": STANDARD.encode(result.encode_to_vec()),
+            }),
+        }
+    }
+
+    /// Reads a JSON line object of any schema version up to the current one.
+    pub fn from_json(value: &Value) -> Result<Self, RecordingError> {
+        let version = match value.get("schema_version") {
+            None => 0,
+            Some(version) => version
+                .as_u64()
+                .and_then(|version| u32::try_from(version).ok())
+                .ok_or_else(|| RecordingError::Invalid("schema_version".to_string()))?,
+        };
+        if version > SCHEMA_VERSION {
+            return Err(RecordingError::UnsupportedVersion(version));
+        }
+        let received_at = match value.get("received_at").and_then(Value::as_str) {
+            Some(at) => Some(
+                DateTime::parse_from_rfc3339(at)
+                    .map_err(|e| RecordingError::Invalid(format!("received_at: {e}")))?
+                    .with_timezone(&Utc),
+            ),
+            None => None,
+        };
+        // version 0 only had mempool transactions
+        let kind = value
+            .get("kind")
+            .and_then(Value::as_str)
+            .unwrap_or("mempool_transaction");
+        let data = match kind {
+            "mempool_transaction" => RecordedData::MempoolTransaction {
+                slot: value
+                    .get("slot")
+                    .and_then(Value::as_u64)
+                    .unwrap_or_default(),
+                transaction: bincode::deserialize(&base64_field(value, "transaction")?)
+                    .map_err(|e| RecordingError::Invalid(format!("transaction: {e}")))?,
+            },
+            "bundle_result" => RecordedData::BundleResult(BundleResult::decode(
+                base64_field(value, "bundle_result")?.as_slice(),
+            )?),
+            kind => return Err(RecordingError::Invalid(format!("unknown kind {kind:?}"))),
+        };
+        Ok(Self { received_at, data })
+    }
+
+    pub fn to_proto(&self) -> Record {
+        let record = match &self.data {
+            RecordedData::MempoolTransaction { slot, transaction } => {
+                record::Record::MempoolTransaction(MempoolTransaction {
+                    slot: *slot,
+                    transaction: bincode::serialize(transaction).expect("serializes"),
+                })
+            }
+            RecordedData::BundleResult(result) => record::Record::BundleResult(result.clone()),
+        };
+        Record {
+            received_at_unix_micros: self
+                .received_at
+                .map(|at| at.timestamp_micros())
+                .unwrap_or_default(),
+            record: Some(record),
+        }
+    }
+
+    /// Reads a protobuf record, None for a kind added after this crate's schema.
+    pub fn from_proto(record: Record) -> Result<Option<Self>, RecordingError> {
+        let received_at = match record.received_at_unix_micros {
+            0 => None,
+            micros => Utc.timestamp_micros(micros).single(),
+        };
+        let data = match record.record {
+            Some(record::Record::MempoolTransaction(tx)) => RecordedData::MempoolTransaction {
+                slot: tx.slot,
+                transaction: bincode::deserialize(&tx.transaction)
+                    .map_err(|e| RecordingError::Invalid(format!("transaction: {e}")))?,
+            },
+            Some(record::Record::BundleResult(result)) => RecordedData::BundleResult(result),
+            None => return Ok(None),
+        };
+        Ok(Some(Self { received_at, data }))
+    }
+}
+
+fn base64_field(value: &Value, field: &str) -> Result<Vec<u8>, RecordingError> {
+    let encoded = value
+        .get(field)
+        .and_then(Value::as_str)
+        .ok_or_else(|| RecordingError::Invalid(format!("missing {field}")))?;
+    STANDARD
+        .decode(encoded)
+        .map_err(|e| RecordingError::Invalid(format!("{field}: {e}")))
+}
+
+/// Writes a recording at the current schema version.
+pub struct RecordingWriter<W: Write> {
+    writer: W,
+    format: RecordingFormat,
+}
+
+impl<W: Write> RecordingWriter<W> {
+    /// Starts a recording, writing the header of a protobuf one.
+    pub fn new(mut writer: W, format: RecordingFormat) -> Result<Self, RecordingError> {
+        if format == RecordingFormat::Protobuf {
+            let header = RecordingHeader {
+                schema_version: SCHEMA_VERSION,
+                writer: format!("{} {}", env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION")),
+                created_at_unix_micros: Utc::now().timestamp_micros(),
+            };
+            writer.write_all(MAGIC)?;
+            writer.write_all(&header.encode_length_delimited_to_vec())?;
+        }
+        Ok(Self { writer, format })
+    }
+
+    pub fn write(&mut self, recorded: &Recorded) -> Result<(), RecordingError> {
+        match self.format {
+            RecordingFormat::Jsonl => {
+                serde_json::to_writer(&mut self.writer, &recorded.to_json())?;
+                self.writer.write_all(b"\n")?;
+            }
+            RecordingFormat::Protobuf => {
+                self.writer
+                    .write_all(&recorded.to_proto().encode_length_delimited_to_vec())?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Flushes the recording and returns the writer.
+    pub fn finish(mut self) -> Result<W, RecordingError> {
+        self.writer.flush()?;
+        Ok(self.writer)
+    }
+}
+
+/// Reads a recording in either format and of any schema version up to the current one,
+/// detecting the format from the protobuf magic. Records are read one at a time by iterating.
+pub struct RecordingReader<R: BufRead> {
+    reader: R,
+    format: RecordingFormat,
+    /// Schema version of a protobuf recording's header. JSON lines carry theirs per record.
+    schema_version: Option<u32>,
+    /// The header's writer of a protobuf recording.
+    writer: Option<String>,
+    line: usize,
+}
+
+impl<R: BufRead> RecordingReader<R> {
+    pub fn new(mut reader: R) -> Result<Self, RecordingError> {
+        if !reader.fill_buf()?.starts_with(MAGIC) {
+            return Ok(Self {
+                reader,
+                format: RecordingFormat::Jsonl,
+                schema_version: None,
+                writer: None,
+                line: 0,
+            });
+        }
+        reader.consume(MAGIC.len());
+        let header = RecordingHeader::decode(
+            read_delimited(&mut reader)?
+                .ok_or_else(|| RecordingError::Invalid("missing header".to_string()))?
+                .as_slice(),
+        )?;
+        if header.schema_version > SCHEMA_VERSION {
+            return Err(RecordingError::UnsupportedVersion(header.schema_version));
+        }
+        Ok(Self {
+            reader,
+            format: RecordingFormat::Protobuf,
+            schema_version: Some(header.schema_version),
+            writer: Some(header.writer),
+            line: 0,
+        })
+    }
+
+    pub fn format(&self) -> RecordingFormat {
+        self.format
+    }
+
+    pub fn schema_version(&self) -> Option<u32> {
+        self.schema_version
+    }
+
+    pub fn writer(&self) -> Option<&str> {
+        self.writer.as_deref()
+    }
+
+    fn next_record(&mut self) -> Result<Option<Recorded>, RecordingError> {
+        match self.format {
+            RecordingFormat::Jsonl => loop {
+                let mut line = String::new();
+                if self.reader.read_line(&mut line)? == 0 {
+                    return Ok(None);
+                }
+                self.line += 1;
+                if line.trim().is_empty() {
+                    continue;
+                }
+                let value: Value = serde_json::from_str(&line)?;
+                return Recorded::from_json(&value).map(Some).map_err(|e| match e {
+                    RecordingError::Invalid(reason) => {
+                        RecordingError::Invalid(format!("line {}: {reason}", self.line))
+                    }
+                    e => e,
+                });
+            },
+            RecordingFormat::Protobuf => loop {
+                let Some(bytes) = read_delimited(&mut self.reader)? else {
+                    return Ok(None);
+                };
+                // kinds from a newer writer of the same schema version are skipped
+                if let Some(recorded) = Recorded::from_proto(Record::decode(bytes.as_slice())?)? {
+                    return Ok(Some(recorded));
+                }
+            },
+        }
+    }
+}
+
+impl<R: BufRead> Iterator for RecordingReader<R> {
+    type Item = Result<Recorded, RecordingError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_record().transpose()
+    }
+}
+
+/// Reads a varint length and that many bytes, None at the end of the input.
+fn read_delimited(reader: &mut impl Read) -> Result<Option<Vec<u8>>, RecordingError> {
+    let mut len = 0u64;
+    for shift in (0..64).step_by(7) {
+        let mut byte = [0u8];
+        if reader.read(&mut byte)? == 0 {
+            if shift == 0 {
+                return Ok(None);
+            }
+            return Err(RecordingError::Invalid("truncated length".to_string()));
+        }
+        len |= u64::from(byte[0] & 0x7f) << shift;
+        if byte[0] & 0x80 == 0 {
+            if len > MAX_RECORD_BYTES {
+                return Err(RecordingError::Invalid(format!("record of {len} bytes")));
+            }
+            let mut bytes = vec![0; len as usize];
+            reader.read_exact(&mut bytes)?;
+            return Ok(Some(bytes));
+        }
+    }
+    Err(RecordingError::Invalid("invalid length".to_string()))
+}
+
+/// Copies a recording into `format` at the current schema version, returning how many records
+/// were converted.
+pub fn convert<R: BufRead, W: Write>(
+    reader: RecordingReader<R>,
+    writer: W,
+    format: RecordingFormat,
+) -> Result<(u64, W), RecordingError> {
+    let mut writer = RecordingWriter::new(writer, format)?;
+    let mut converted = 0;
+    for recorded in reader {
+        writer.write(&recorded?)?;
+        converted += 1;
+    }
+    Ok((converted, writer.finish()?))
+}
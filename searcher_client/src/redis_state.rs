@@ -0,0 +1,358 @@
+use std::{
+    io,
+    sync::atomic::{AtomicU64, Ordering},
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use serde_json::json;
+use solana_metrics::datapoint_info;
+use solana_sdk::{clock::Slot, signature::Signature};
+use thiserror::Error;
+use tokio::{
+    io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader},
+    net::TcpStream,
+    sync::Mutex,
+    time::timeout,
+};
+
+const COMMAND_TIMEOUT: Duration = Duration::from_millis(250);
+/// How long commands fail right away after one found the server unreachable.
+const RECONNECT_BACKOFF: Duration = Duration::from_secs(1);
+
+#[derive(Debug, Error)]
+pub enum RedisError {
+    #[error("invalid redis url {0:?}, expected redis://[:password@]host:port[/db]")]
+    InvalidUrl(String),
+    #[error("io error {0}")]
+    Io(#[from] io::Error),
+    #[error("redis didn't answer within {0:?}")]
+    Timeout(Duration),
+    #[error("redis is unreachable, not retrying for another {0:?}")]
+    Down(Duration),
+    #[error("redis error {0}")]
+    Server(String),
+    #[error("unexpected redis reply {0}")]
+    UnexpectedReply(String),
+}
+
+/// A RESP reply. Commands used here never reply with arrays, and never with bulk strings whose
+/// contents matter.
+#[derive(Debug, PartialEq, Eq)]
+enum Reply {
+    Ok,
+    Integer(i64),
+    Bulk,
+    Nil,
+}
+
+/// Where to connect, parsed from `redis://[:password@]host:port[/db]`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RedisConfig {
+    pub address: String,
+    pub password: Option<String>,
+    pub db: Option<u32>,
+}
+
+impl RedisConfig {
+    pub fn from_url(url: &str) -> Result<Self, RedisError> {
+        let invalid = || RedisError::InvalidUrl(url.to_string());
+        let rest = url.strip_prefix("redis://").ok_or_else(invalid)?;
+        let (password, rest) = match rest.rsplit_once('@') {
+            Some((credentials, rest)) => {
+                let password = credentials.strip_prefix(':').unwrap_or(credentials);
+                (Some(password.to_string()), rest)
+            }
+            None => (None, rest),
+        };
+        let (address, db) = match rest.split_once('/') {
+            Some((address, "")) => (address, None),
+            Some((address, db)) => (address, Some(db.parse().map_err(|_| invalid())?)),
+            None => (rest, None),
+        };
+        if !address
+            .rsplit_once(':')
+            .is_some_and(|(host, port)| !host.is_empty() && port.parse::<u16>().is_ok())
+        {
+            return Err(invalid());
+        }
+        Ok(Self {
+            address: address.to_string(),
+            password,
+            db,
+        })
+    }
+}
+
+fn command(args: &[&str]) -> Vec<String> {
+    args.iter().map(|arg| arg.to_string()).collect()
+}
+
+struct Connection {
+    stream: BufReader<TcpStream>,
+}
+
+impl Connection {
+    async fn open(config: &RedisConfig) -> Result<Self, RedisError> {
+        let stream = TcpStream::connect(&config.address).await?;
+        stream.set_nodelay(true)?;
+        let mut connection = Self {
+            stream: BufReader::new(stream),
+        };
+        if let Some(password) = &config.password {
+            connection.command(&["AUTH", password]).await?;
+        }
+        if let Some(db) = config.db {
+            connection.command(&["SELECT", &db.to_string()]).await?;
+        }
+        Ok(connection)
+    }
+
+    async fn command(&mut self, args: &[&str]) -> Result<Reply, RedisError> {
+        let mut replies = self.pipeline(&[command(args)]).await?;
+        Ok(replies.remove(0))
+    }
+
+    /// Writes `commands` at once and reads their replies in order. A server error for one of
+    /// them fails the batch, once every reply was read so the connection stays usable.
+    async fn pipeline(&mut self, commands: &[Vec<String>]) -> Result<Vec<Reply>, RedisError> {
+        let mut request = String::new();
+        for args in commands {
+            request.push_str(&format!("*{}\r\n", args.len()));
+            for arg in args {
+                request.push_str(&format!("${}\r\n{arg}\r\n", arg.len()));
+            }
+        }
+        self.stream.get_mut().write_all(request.as_bytes()).await?;
+        let mut replies = Vec::with_capacity(commands.len());
+        let mut server_error = None;
+        for _ in commands {
+            match self.read_reply().await {
+                Ok(reply) => replies.push(reply),
+                Err(e @ RedisError::Server(_)) => server_error = server_error.or(Some(e)),
+                Err(e) => return Err(e),
+            }
+        }
+        match server_error {
+            Some(e) => Err(e),
+            None => Ok(replies),
+        }
+    }
+
+    async fn read_reply(&mut self) -> Result<Reply, RedisError> {
+        let mut line = String::new();
+        if self.stream.read_line(&mut line).await? == 0 {
+            return Err(io::Error::from(io::ErrorKind::UnexpectedEof).into());
+        }
+        let line = line.trim_end_matches("\r\n");
+        let (kind, value) = line.split_at(line.len().min(1));
+        let unexpected = || RedisError::UnexpectedReply(line.to_string());
+        match kind {
+            "+" => Ok(Reply::Ok),
+            "-" => Err(RedisError::Server(value.to_string())),
+            ":" => Ok(Reply::Integer(value.parse().map_err(|_| unexpected())?)),
+            "$" => {
+                let len: i64 = value.parse().map_err(|_| unexpected())?;
+                if len < 0 {
+                    return Ok(Reply::Nil);
+                }
+                let mut data = vec![0; len as usize + 2];
+                self.stream.read_exact(&mut data).await?;
+                Ok(Reply::Bulk)
+            }
+            _ => Err(unexpected()),
+        }
+    }
+}
+
+/// State shared by every instance of a fleet through Redis: which opportunities were already
+/// submitted, tips spent against shared budgets, and submitted bundles. Connections are opened
+/// lazily and reopened after any error, callers decide whether to fail open or closed. After the
+/// server stops answering, commands fail right away for a second instead of each waiting out a
+/// timeout.
+pub struct RedisState {
+    config: RedisConfig,
+    key_prefix: String,
+    connection: Mutex<Option<Connection>>,
+    /// Bundle records are written on their own connection so they don't hold up claims.
+    record_connection: Mutex<Option<Connection>>,
+    down_until: std::sync::Mutex<Option<Instant>>,
+    duplicates: AtomicU64,
+    over_budget: AtomicU64,
+    errors: AtomicU64,
+}
+
+impl RedisState {
+    pub fn new(config: RedisConfig, key_prefix: impl Into<String>) -> Self {
+        Self {
+            config,
+            key_prefix: key_prefix.into(),
+            connection: Mutex::new(None),
+            record_connection: Mutex::new(None),
+            down_until: std::sync::Mutex::new(None),
+            duplicates: AtomicU64::default(),
+            over_budget: AtomicU64::default(),
+            errors: AtomicU64::default(),
+        }
+    }
+
+    /// Sends `commands` in one round trip on `connection`, within a single timeout.
+    async fn pipeline(
+        &self,
+        connection: &Mutex<Option<Connection>>,
+        commands: &[Vec<String>],
+    ) -> Result<Vec<Reply>, RedisError> {
+        let mut connection = connection.lock().await;
+        let down_until = *self.down_until.lock().unwrap();
+        if let Some(remaining) = down_until.and_then(|t| t.checked_duration_since(Instant::now())) {
+            return Err(RedisError::Down(remaining));
+        }
+        let result = timeout(COMMAND_TIMEOUT, async {
+            if connection.is_none() {
+                *connection = Some(Connection::open(&self.config).await?);
+            }
+            connection.as_mut().unwrap().pipeline(commands).await
+        })
+        .await
+        .unwrap_or(Err(RedisError::Timeout(COMMAND_TIMEOUT)));
+        if result.is_err() {
+            self.errors.fetch_add(1, Ordering::Relaxed);
+        }
+        // the reply may be half read, start over on the next command
+        if matches!(
+            result,
+            Err(RedisError::Io(_) | RedisError::Timeout(_) | RedisError::UnexpectedReply(_))
+        ) {
+            *connection = None;
+        }
+        if matches!(result, Err(RedisError::Io(_) | RedisError::Timeout(_))) {
+            *self.down_until.lock().unwrap() = Some(Instant::now() + RECONNECT_BACKOFF);
+        }
+        result
+    }
+
+    /// Claims each of `keys` for `ttl`, in one round trip. An entry is false if any instance
+    /// claimed that key before, so the caller should skip the opportunity.
+    pub async fn claim_all(&self, keys: &[String], ttl: Duration) -> Result<Vec<bool>, RedisError> {
+        if keys.is_empty() {
+            return Ok(Vec::new());
+        }
+        let ttl_ms = ttl.as_millis().max(1).to_string();
+        let commands: Vec<_> = keys
+            .iter()
+            .map(|key| {
+                let key = format!("{}:dedupe:{key}", self.key_prefix);
+                command(&["SET", &key, "1", "NX", "PX", &ttl_ms])
+            })
+            .collect();
+        self.pipeline(&self.connection, &commands)
+            .await?
+            .into_iter()
+            .map(|reply| match reply {
+                Reply::Ok => Ok(true),
+                Reply::Nil => {
+                    self.duplicates.fetch_add(1, Ordering::Relaxed);
+                    Ok(false)
+                }
+                reply => Err(RedisError::UnexpectedReply(format!("{reply:?}"))),
+            })
+            .collect()
+    }
+
+    /// Adds each of `lamports`, in order, to what `budget` spent in the current fixed window of
+    /// `window` and returns whether each spend stays within `max_lamports`. Over-budget spends
+    /// are undone. Takes one round trip, and a second one to undo spends or expire a new window.
+    pub async fn try_spend_all(
+        &self,
+        budget: &str,
+        lamports: &[u64],
+        max_lamports: u64,
+        window: Duration,
+    ) -> Result<Vec<bool>, RedisError> {
+        if lamports.is_empty() {
+            return Ok(Vec::new());
+        }
+        let window_ms = window.as_millis().max(1);
+        let window_index = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis()
+            / window_ms;
+        let key = format!("{}:budget:{budget}:{window_index}", self.key_prefix);
+        let commands: Vec<_> = lamports
+            .iter()
+            .map(|lamports| command(&["INCRBY", &key, &lamports.to_string()]))
+            .collect();
+        let replies = self.pipeline(&self.connection, &commands).await?;
+        let mut follow_ups = Vec::new();
+        let mut refunded = 0;
+        let mut fits = Vec::with_capacity(lamports.len());
+        for (index, (reply, &lamports)) in replies.into_iter().zip(lamports).enumerate() {
+            let Reply::Integer(spent) = reply else {
+                return Err(RedisError::UnexpectedReply("INCRBY".into()));
+            };
+            if index == 0 && spent as u64 == lamports {
+                // first spend of the window, keep the key around for a window past it
+                follow_ups.push(command(&["PEXPIRE", &key, &(window_ms * 2).to_string()]));
+            }
+            // spends earlier in the batch that went over are undone, they don't count
+            if (spent as u64).saturating_sub(refunded) > max_lamports {
+                self.over_budget.fetch_add(1, Ordering::Relaxed);
+                refunded += lamports;
+                fits.push(false);
+            } else {
+                fits.push(true);
+            }
+        }
+        if refunded > 0 {
+            follow_ups.push(command(&["DECRBY", &key, &refunded.to_string()]));
+        }
+        if !follow_ups.is_empty() {
+            self.pipeline(&self.connection, &follow_ups).await?;
+        }
+        Ok(fits)
+    }
+
+    /// Appends submitted bundles, each with its uuid if the block engine accepted it, to a
+    /// capped list shared by the fleet.
+    pub async fn record_bundles(
+        &self,
+        bundles: &[(Option<String>, Vec<Signature>)],
+        submitted_slot: Slot,
+        max_bundles: usize,
+    ) -> Result<(), RedisError> {
+        if bundles.is_empty() {
+            return Ok(());
+        }
+        let key = format!("{}:bundles", self.key_prefix);
+        let mut push = vec!["RPUSH".to_string(), key.clone()];
+        push.extend(bundles.iter().map(|(bundle_uuid, signatures)| {
+            json!({
+                "bundle_uuid": bundle_uuid,
+                "submitted_slot": submitted_slot,
+                "signatures": signatures.iter().map(|s| s.to_string()).collect::<Vec<_>>(),
+            })
+            .to_string()
+        }));
+        let trim = command(&["LTRIM", &key, &format!("-{max_bundles}"), "-1"]);
+        self.pipeline(&self.record_connection, &[push, trim])
+            .await?;
+        Ok(())
+    }
+
+    pub fn report(&self, name: &'static str) {
+        datapoint_info!(
+            name,
+            (
+                "duplicates",
+                self.duplicates.swap(0, Ordering::Relaxed),
+                i64
+            ),
+            (
+                "over_budget",
+                self.over_budget.swap(0, Ordering::Relaxed),
+                i64
+            ),
+            ("errors", self.errors.swap(0, Ordering::Relaxed), i64),
+        );
+    }
+}
@@ -0,0 +1,328 @@
+use std::{
+    collections::{HashMap, HashSet},
+    str::FromStr,
+    sync::Arc,
+};
+
+use futures_util::future::join_all;
+use jito_protos::{
+    bundle::{bundle_result::Result as BundleResultType, BundleResult},
+    searcher::{
+        searcher_service_client::SearcherServiceClient, NextScheduledLeaderRequest,
+        SendBundleResponse,
+    },
+};
+use log::{debug, info, warn};
+use solana_metrics::datapoint_info;
+use solana_sdk::{clock::Slot, pubkey::Pubkey, signature::Keypair, signature::Signature};
+use tonic::{codegen::InterceptedService, transport::Channel, Response, Status};
+
+use crate::{
+    client_headers::ClientHeaders, get_searcher_client_with_headers, grpc_trace::traced,
+    send_bundle_no_wait, token_authenticator::ClientInterceptor, BlockEngineConnectionResult,
+};
+
+/// A block engine taking part in the race, with the next Jito leader it reported.
+pub struct RaceTarget {
+    pub block_engine_url: String,
+    pub client: SearcherServiceClient<InterceptedService<Channel, ClientInterceptor>>,
+    pub region: String,
+    pub current_slot: Slot,
+    pub next_leader_slot: Option<Slot>,
+    pub next_leader: Option<Pubkey>,
+    /// Share of its recent slots the next leader skipped, 0 if unknown.
+    pub next_leader_skip_rate: f64,
+}
+
+impl RaceTarget {
+    pub fn slots_to_leader(&self) -> Option<u64> {
+        self.next_leader_slot
+            .map(|slot| slot.saturating_sub(self.current_slot))
+    }
+
+    /// Slots to the next leader, stretched by how often it skips: a leader skipping half its
+    /// slots counts as twice as far away, since a bundle sent to it is as likely to wait for the
+    /// leader after.
+    pub fn expected_slots_to_leader(&self) -> Option<u64> {
+        let produce_rate = (1.0 - self.next_leader_skip_rate).max(0.01);
+        self.slots_to_leader()
+            .map(|slots| ((slots + 1) as f64 / produce_rate).round() as u64 - 1)
+    }
+}
+
+/// Damps switching between block engines when the next leader's region alternates quickly.
+#[derive(Clone, Copy, Debug)]
+pub struct RegionHysteresis {
+    /// A new block engine must have its leader at least this many slots sooner than the current
+    /// ones to replace them.
+    pub min_lead_slots: u64,
+    /// Refreshes in a row a new selection must lead for before switching to it.
+    pub confirmations: u32,
+}
+
+impl Default for RegionHysteresis {
+    fn default() -> Self {
+        Self {
+            min_lead_slots: 4,
+            confirmations: 2,
+        }
+    }
+}
+
+/// Sends the same signed bundle to the block engines whose leaders come up first. Every copy
+/// carries identical signatures, so the chain executes at most one of them no matter how many
+/// regions forward it.
+pub struct RegionRace {
+    targets: Vec<RaceTarget>,
+    fanout: usize,
+    hysteresis: RegionHysteresis,
+    /// Indexes into `targets` bundles are sent to.
+    selected: Vec<usize>,
+    /// A better selection and the refreshes in a row it has led for.
+    candidate: Option<(Vec<usize>, u32)>,
+    switches: u64,
+    damped: u64,
+    flaps: u64,
+}
+
+impl RegionRace {
+    pub async fn connect(
+        block_engine_urls: &[String],
+        auth_keypair: &Arc<Keypair>,
+        fanout: usize,
+        hysteresis: RegionHysteresis,
+        headers: ClientHeaders,
+    ) -> BlockEngineConnectionResult<Self> {
+        let mut targets = Vec::with_capacity(block_engine_urls.len());
+        for url in block_engine_urls {
+            targets.push(RaceTarget {
+                block_engine_url: url.clone(),
+                client: get_searcher_client_with_headers(url, auth_keypair, headers.clone())
+                    .await?,
+                region: String::new(),
+                current_slot: 0,
+                next_leader_slot: None,
+                next_leader: None,
+                next_leader_skip_rate: 0.0,
+            });
+        }
+        Ok(Self {
+            targets,
+            fanout: fanout.max(1),
+            hysteresis,
+            selected: Vec::new(),
+            candidate: None,
+            switches: 0,
+            damped: 0,
+            flaps: 0,
+        })
+    }
+
+    /// Asks every block engine for its next leader. A block engine that fails keeps no leader
+    /// until the next refresh, so it's skipped rather than raced blindly. `skip_rates` are the
+    /// known skip rates of leaders, block engines whose next leader often skips are ranked as if
+    /// it were further away.
+    pub async fn refresh_schedule(&mut self, skip_rates: &HashMap<Pubkey, f64>) {
+        let futs = self.targets.iter().map(|target| {
+            let mut client = target.client.clone();
+            async move {
+                traced(
+                    "GetNextScheduledLeader",
+                    NextScheduledLeaderRequest { regions: vec![] },
+                    |request| client.get_next_scheduled_leader(request),
+                )
+                .await
+            }
+        });
+        let responses = join_all(futs).await;
+        for (target, response) in self.targets.iter_mut().zip(responses) {
+            match response {
+                Ok(response) => {
+                    let leader = response.into_inner();
+                    target.region = leader.next_leader_region;
+                    target.current_slot = leader.current_slot;
+                    target.next_leader_slot = Some(leader.next_leader_slot);
+                    target.next_leader = Pubkey::from_str(&leader.next_leader_identity).ok();
+                    target.next_leader_skip_rate = target
+                        .next_leader
+                        .and_then(|leader| skip_rates.get(&leader).copied())
+                        .unwrap_or_default();
+                }
+                Err(e) => {
+                    warn!(
+                        "failed to get next leader from {}: {e}",
+                        target.block_engine_url
+                    );
+                    target.next_leader_slot = None;
+                }
+            }
+        }
+        self.update_selection();
+    }
+
+    /// Switches to the block engines with the soonest leaders only once they've led by
+    /// `min_lead_slots` for `confirmations` refreshes, or right away if a selected block engine
+    /// stopped reporting a leader.
+    fn update_selection(&mut self) {
+        let mut best: Vec<usize> = (0..self.targets.len())
+            .filter(|i| self.targets[*i].next_leader_slot.is_some())
+            .collect();
+        best.sort_by_key(|i| self.targets[*i].expected_slots_to_leader());
+        best.truncate(self.fanout);
+
+        let selection_broken = self.selected.len() < best.len()
+            || self
+                .selected
+                .iter()
+                .any(|i| self.targets[*i].next_leader_slot.is_none());
+        if selection_broken {
+            self.switch_to(best);
+            return;
+        }
+        if same_targets(&best, &self.selected) {
+            if self.candidate.take().is_some() {
+                self.flaps += 1;
+            }
+            return;
+        }
+
+        let soonest = |selection: &[usize]| {
+            selection
+                .iter()
+                .filter_map(|i| self.targets[*i].expected_slots_to_leader())
+                .min()
+                .unwrap_or(u64::MAX)
+        };
+        if soonest(&best).saturating_add(self.hysteresis.min_lead_slots) > soonest(&self.selected) {
+            self.damped += 1;
+            if self.candidate.take().is_some() {
+                self.flaps += 1;
+            }
+            return;
+        }
+        let confirmations = match self.candidate.take() {
+            Some((candidate, confirmations)) if same_targets(&candidate, &best) => {
+                confirmations + 1
+            }
+            Some(_) => {
+                self.flaps += 1;
+                1
+            }
+            None => 1,
+        };
+        if confirmations >= self.hysteresis.confirmations {
+            self.switch_to(best);
+        } else {
+            self.damped += 1;
+            self.candidate = Some((best, confirmations));
+        }
+    }
+
+    fn switch_to(&mut self, selection: Vec<usize>) {
+        if same_targets(&selection, &self.selected) {
+            return;
+        }
+        let urls: Vec<_> = selection
+            .iter()
+            .map(|i| self.targets[*i].block_engine_url.as_str())
+            .collect();
+        debug!("racing bundles to {urls:?}");
+        self.switches += 1;
+        self.candidate = None;
+        self.selected = selection;
+    }
+
+    /// The block engines with the soonest leaders, discounted by skip rate, at most `fanout`.
+    pub fn leading_targets(&self) -> Vec<&RaceTarget> {
+        self.selected.iter().map(|i| &self.targets[*i]).collect()
+    }
+
+    /// Reports how often the selection switched, was held back and changed its mind, then resets
+    /// the counters. Flaps count candidates dropped before they were confirmed.
+    pub fn report(&mut self, name: &'static str) {
+        datapoint_info!(
+            name,
+            ("switches", self.switches, i64),
+            ("damped", self.damped, i64),
+            ("flaps", self.flaps, i64),
+            ("selected", self.selected.len(), i64),
+        );
+        self.switches = 0;
+        self.damped = 0;
+        self.flaps = 0;
+    }
+
+    /// Sends the serialized transactions to the leading block engines concurrently, returning
+    /// each block engine's response.
+    pub async fn send(
+        &self,
+        transactions: &[Vec<u8>],
+    ) -> Vec<(String, Result<Response<SendBundleResponse>, Status>)> {
+        let futs = self.leading_targets().into_iter().map(|target| {
+            let mut client = target.client.clone();
+            let url = target.block_engine_url.clone();
+            async move { (url, send_bundle_no_wait(transactions, &mut client).await) }
+        });
+        join_all(futs).await
+    }
+}
+
+fn same_targets(a: &[usize], b: &[usize]) -> bool {
+    a.len() == b.len() && a.iter().all(|i| b.contains(i))
+}
+
+/// Folds the per-region responses of a raced send into one: the first accepted response, or the
+/// last error if no block engine accepted it.
+pub fn first_accepted(
+    responses: Vec<(String, Result<Response<SendBundleResponse>, Status>)>,
+) -> Result<Response<SendBundleResponse>, Status> {
+    let mut last_error = Status::unavailable("no block engine has an upcoming leader");
+    for (url, response) in responses {
+        match response {
+            Ok(response) => return Ok(response),
+            Err(e) => {
+                warn!("raced bundle rejected by {url}: {e}");
+                last_error = e;
+            }
+        }
+    }
+    Err(last_error)
+}
+
+/// Maps the uuids a raced bundle got from each block engine back to the bundle, identified by
+/// its first signature, so results from several regions count as one landing.
+#[derive(Debug, Default)]
+pub struct RaceLandings {
+    bundles: HashMap<String, Signature>,
+    landed: HashSet<Signature>,
+}
+
+impl RaceLandings {
+    pub fn record_sent(&mut self, uuid: String, bundle_signature: Signature) {
+        self.bundles.insert(uuid, bundle_signature);
+    }
+
+    /// Returns the bundle's signature the first time any of its copies lands, None for unknown
+    /// uuids, other results and copies that already landed.
+    pub fn record_result(&mut self, bundle_result: &BundleResult) -> Option<Signature> {
+        let landed = matches!(
+            bundle_result.result,
+            Some(BundleResultType::Processed(_)) | Some(BundleResultType::Finalized(_))
+        );
+        let signature = *self.bundles.get(&bundle_result.bundle_id)?;
+        if !landed || !self.landed.insert(signature) {
+            return None;
+        }
+        info!(
+            "raced bundle {signature} landed via {}",
+            bundle_result.bundle_id
+        );
+        Some(signature)
+    }
+
+    /// Forgets bundles, call periodically so the maps don't grow forever.
+    pub fn clear(&mut self) {
+        self.bundles.clear();
+        self.landed.clear();
+    }
+}
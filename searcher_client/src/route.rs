@@ -0,0 +1,140 @@
+use std::sync::Arc;
+
+use solana_sdk::{
+    compute_budget::ComputeBudgetInstruction,
+    instruction::Instruction,
+    message::Message,
+    packet::PACKET_DATA_SIZE,
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+    transaction::Transaction,
+};
+use thiserror::Error;
+
+use crate::bundle_builder::{BundleBuilder, TransactionSpec, MAX_BUNDLE_TRANSACTIONS};
+
+/// Maximum compute units a single transaction may request.
+pub const MAX_COMPUTE_UNITS_PER_TRANSACTION: u32 = 1_400_000;
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum RouteError {
+    #[error("route has no hops")]
+    Empty,
+    #[error("hop {hop} through pool {pool} doesn't fit in a transaction on its own")]
+    HopTooLarge { hop: usize, pool: Pubkey },
+    #[error("route needs {needed} transactions, max is {max}")]
+    TooManyTransactions { needed: usize, max: usize },
+}
+
+/// One swap through a pool. The instructions are provided by the caller for the pool's program,
+/// the amounts are kept for logging and sizing checks done by the caller.
+#[derive(Clone)]
+pub struct RouteHop {
+    pub pool: Pubkey,
+    pub instructions: Vec<Instruction>,
+    /// Compute units the hop's instructions consume.
+    pub compute_units: u32,
+    pub amount_in: u64,
+    pub min_amount_out: u64,
+    /// Signers the hop's instructions require other than the payer.
+    pub signers: Vec<Arc<Keypair>>,
+}
+
+/// Limits a packed transaction must stay within.
+#[derive(Clone, Copy, Debug)]
+pub struct RoutePackingLimits {
+    pub max_compute_units_per_transaction: u32,
+    /// Serialized size limit, lower it to leave room for instructions added afterwards such as
+    /// a tip or post assertions.
+    pub max_transaction_bytes: usize,
+    /// Lower it by one if the tip goes in a separate transaction.
+    pub max_transactions: usize,
+}
+
+impl Default for RoutePackingLimits {
+    fn default() -> Self {
+        Self {
+            max_compute_units_per_transaction: MAX_COMPUTE_UNITS_PER_TRANSACTION,
+            max_transaction_bytes: PACKET_DATA_SIZE,
+            max_transactions: MAX_BUNDLE_TRANSACTIONS,
+        }
+    }
+}
+
+/// Packs the hops of a route, in order, into as few transactions as the limits allow. Each
+/// transaction requests exactly the compute its hops need. The transactions land together as a
+/// bundle, so a route split across several of them still executes atomically.
+pub fn pack_route(
+    hops: &[RouteHop],
+    payer: &Arc<Keypair>,
+    limits: &RoutePackingLimits,
+) -> Result<BundleBuilder, RouteError> {
+    if hops.is_empty() {
+        return Err(RouteError::Empty);
+    }
+
+    let mut packed: Vec<Vec<&RouteHop>> = vec![];
+    let mut current: Vec<&RouteHop> = vec![];
+    for (i, hop) in hops.iter().enumerate() {
+        current.push(hop);
+        if fits(&current, payer, limits) {
+            continue;
+        }
+        current.pop();
+        if current.is_empty() {
+            return Err(RouteError::HopTooLarge {
+                hop: i,
+                pool: hop.pool,
+            });
+        }
+        packed.push(std::mem::replace(&mut current, vec![hop]));
+        if !fits(&current, payer, limits) {
+            return Err(RouteError::HopTooLarge {
+                hop: i,
+                pool: hop.pool,
+            });
+        }
+    }
+    packed.push(current);
+
+    if packed.len() > limits.max_transactions {
+        return Err(RouteError::TooManyTransactions {
+            needed: packed.len(),
+            max: limits.max_transactions,
+        });
+    }
+    Ok(packed.iter().fold(BundleBuilder::new(), |builder, hops| {
+        builder.add_transaction(transaction_spec(hops, payer))
+    }))
+}
+
+fn transaction_spec(hops: &[&RouteHop], payer: &Arc<Keypair>) -> TransactionSpec {
+    let compute_units = hops.iter().map(|hop| hop.compute_units).sum();
+    let mut instructions = vec![ComputeBudgetInstruction::set_compute_unit_limit(
+        compute_units,
+    )];
+    instructions.extend(hops.iter().flat_map(|hop| hop.instructions.iter().cloned()));
+
+    let mut spec = TransactionSpec::new(instructions, payer.clone());
+    for signer in hops.iter().flat_map(|hop| hop.signers.iter()) {
+        let duplicate = signer.pubkey() == payer.pubkey()
+            || spec.signers.iter().any(|s| s.pubkey() == signer.pubkey());
+        if !duplicate {
+            spec = spec.with_signer(signer.clone());
+        }
+    }
+    spec
+}
+
+fn fits(hops: &[&RouteHop], payer: &Arc<Keypair>, limits: &RoutePackingLimits) -> bool {
+    let compute_units: u64 = hops.iter().map(|hop| hop.compute_units as u64).sum();
+    if compute_units > limits.max_compute_units_per_transaction as u64 {
+        return false;
+    }
+    let spec = transaction_spec(hops, payer);
+    let message = Message::new(&spec.instructions, Some(&payer.pubkey()));
+    let size = bincode::serialized_size(&Transaction::new_unsigned(message))
+        .map(|size| size as usize)
+        .unwrap_or(usize::MAX);
+    size <= limits.max_transaction_bytes
+}
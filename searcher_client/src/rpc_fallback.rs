@@ -0,0 +1,128 @@
+use solana_client::{
+    client_error::Result as ClientResult, nonblocking::rpc_client::RpcClient,
+    rpc_config::RpcSendTransactionConfig,
+};
+use solana_sdk::{
+    commitment_config::CommitmentLevel, compute_budget::ComputeBudgetInstruction,
+    instruction::Instruction, signature::Signature, transaction::VersionedTransaction,
+};
+
+use crate::BundleRejectionError;
+
+/// Controls when and how the non-tip transactions of a bundle are sent over plain RPC after the
+/// bundle keeps losing the auction. Trades atomicity for inclusion.
+#[derive(Clone, Debug)]
+pub struct RpcFallbackConfig {
+    /// Number of consecutive auction losses before falling back to RPC, at least 1.
+    pub auction_losses_before_fallback: usize,
+    pub skip_preflight: bool,
+    /// Maximum number of times the RPC node retries sending to the leader.
+    /// None leaves it up to the RPC node.
+    pub max_retries: Option<usize>,
+    /// Priority fee in micro-lamports per compute unit added to fallback transactions.
+    pub compute_unit_price: u64,
+}
+
+/// Counts consecutive auction losses for a bundle that gets resubmitted.
+#[derive(Debug, Default)]
+pub struct AuctionLossTracker {
+    consecutive_losses: usize,
+}
+
+impl AuctionLossTracker {
+    /// Records the outcome of a bundle submission and returns true once the fallback threshold
+    /// is reached. Only auction losses count towards it, a threshold of 0 is taken as 1.
+    pub fn record(
+        &mut self,
+        rejection: Option<&BundleRejectionError>,
+        config: &RpcFallbackConfig,
+    ) -> bool {
+        match rejection {
+            Some(e) if e.is_auction_loss() => self.consecutive_losses += 1,
+            _ => self.consecutive_losses = 0,
+        }
+        self.consecutive_losses >= config.auction_losses_before_fallback.max(1)
+    }
+}
+
+/// Prepends a compute unit price instruction so the transaction pays a priority fee.
+pub fn with_priority_fee(
+    instructions: &[Instruction],
+    compute_unit_price: u64,
+) -> Vec<Instruction> {
+    let mut ixs = Vec::with_capacity(instructions.len() + 1);
+    if compute_unit_price > 0 {
+        ixs.push(ComputeBudgetInstruction::set_compute_unit_price(
+            compute_unit_price,
+        ));
+    }
+    ixs.extend_from_slice(instructions);
+    ixs
+}
+
+/// Sends each transaction through send_transaction, returning one result per transaction.
+pub async fn send_transactions_via_rpc(
+    rpc_client: &RpcClient,
+    transactions: &[VersionedTransaction],
+    config: &RpcFallbackConfig,
+) -> Vec<ClientResult<Signature>> {
+    let send_config = RpcSendTransactionConfig {
+        skip_preflight: config.skip_preflight,
+        preflight_commitment: Some(CommitmentLevel::Confirmed),
+        max_retries: config.max_retries,
+        ..RpcSendTransactionConfig::default()
+    };
+    let futs: Vec<_> = transactions
+        .iter()
+        .map(|tx| rpc_client.send_transaction_with_config(tx, send_config))
+        .collect();
+    futures_util::future::join_all(futs).await
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        rpc_fallback::{AuctionLossTracker, RpcFallbackConfig},
+        BundleRejectionError,
+    };
+
+    fn config(auction_losses_before_fallback: usize) -> RpcFallbackConfig {
+        RpcFallbackConfig {
+            auction_losses_before_fallback,
+            skip_preflight: false,
+            max_retries: None,
+            compute_unit_price: 0,
+        }
+    }
+
+    #[test]
+    fn test_auction_loss_tracker() {
+        let config = config(2);
+        let lost = BundleRejectionError::StateAuctionBidRejected("auction".to_string(), 1);
+        let mut tracker = AuctionLossTracker::default();
+
+        assert!(!tracker.record(Some(&lost), &config));
+        // anything but an auction loss starts the count over
+        assert!(!tracker.record(Some(&BundleRejectionError::NotLanded), &config));
+        assert!(!tracker.record(Some(&lost), &config));
+        assert!(tracker.record(Some(&lost), &config));
+        assert!(!tracker.record(None, &config));
+    }
+
+    #[test]
+    fn test_auction_loss_tracker_zero_threshold() {
+        let config = config(0);
+        let mut tracker = AuctionLossTracker::default();
+
+        assert!(!tracker.record(None, &config));
+        assert!(!tracker.record(
+            Some(&BundleRejectionError::SimulationFailure(
+                "tx".to_string(),
+                None
+            )),
+            &config
+        ));
+        let lost = BundleRejectionError::WinningBatchBidRejected("auction".to_string(), 1);
+        assert!(tracker.record(Some(&lost), &config));
+    }
+}
@@ -0,0 +1,102 @@
+use std::{
+    collections::BTreeMap,
+    future::Future,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use histogram::Histogram;
+use solana_metrics::datapoint_info;
+
+#[derive(Default)]
+struct MethodStats {
+    latency_us: Histogram,
+    calls: u64,
+    errors: u64,
+}
+
+/// Latency percentiles and error counts for one block engine method.
+#[derive(Clone, Debug)]
+pub struct MethodLatencySummary {
+    pub method: &'static str,
+    pub calls: u64,
+    pub errors: u64,
+    pub p50_us: u64,
+    pub p90_us: u64,
+    pub p99_us: u64,
+    pub max_us: u64,
+}
+
+impl MethodLatencySummary {
+    pub fn error_rate(&self) -> f64 {
+        if self.calls == 0 {
+            return 0.0;
+        }
+        self.errors as f64 / self.calls as f64
+    }
+}
+
+/// Per-method latency histograms and error counters for block engine RPCs, shared between
+/// clones so every task calling the block engine records into the same stats.
+#[derive(Clone, Default)]
+pub struct RpcLatencyTracker {
+    methods: Arc<Mutex<BTreeMap<&'static str, MethodStats>>>,
+}
+
+impl RpcLatencyTracker {
+    /// Awaits `fut`, recording its latency and whether it failed under `method`.
+    pub async fn time<T, E, Fut>(&self, method: &'static str, fut: Fut) -> Result<T, E>
+    where
+        Fut: Future<Output = Result<T, E>>,
+    {
+        let start = Instant::now();
+        let result = fut.await;
+        self.record(method, start.elapsed(), result.is_ok());
+        result
+    }
+
+    pub fn record(&self, method: &'static str, elapsed: Duration, ok: bool) {
+        let mut methods = self.methods.lock().unwrap();
+        let stats = methods.entry(method).or_default();
+        let _ = stats.latency_us.increment(elapsed.as_micros() as u64);
+        stats.calls += 1;
+        if !ok {
+            stats.errors += 1;
+        }
+    }
+
+    pub fn summaries(&self) -> Vec<MethodLatencySummary> {
+        self.methods
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(method, stats)| MethodLatencySummary {
+                method,
+                calls: stats.calls,
+                errors: stats.errors,
+                p50_us: stats.latency_us.percentile(50.0).unwrap_or_default(),
+                p90_us: stats.latency_us.percentile(90.0).unwrap_or_default(),
+                p99_us: stats.latency_us.percentile(99.0).unwrap_or_default(),
+                max_us: stats.latency_us.maximum().unwrap_or_default(),
+            })
+            .collect()
+    }
+
+    /// Reports one datapoint per method and resets the stats.
+    pub fn report(&self, name: &'static str) {
+        for summary in self.summaries() {
+            datapoint_info!(
+                name,
+                ("method", summary.method, String),
+                ("calls", summary.calls, i64),
+                ("errors", summary.errors, i64),
+                ("error_rate", summary.error_rate(), f64),
+                ("p50_us", summary.p50_us, i64),
+                ("p90_us", summary.p90_us, i64),
+                ("p99_us", summary.p99_us, i64),
+                ("max_us", summary.max_us, i64),
+            );
+        }
+        self.methods.lock().unwrap().clear();
+    }
+}
@@ -0,0 +1,60 @@
+use std::io;
+
+use tokio::runtime::{Builder, Runtime};
+
+/// Sizing of the tokio runtime. Anything not set keeps tokio's default: a worker per core and
+/// up to 512 blocking threads.
+#[derive(Clone, Debug, Default)]
+pub struct RuntimeConfig {
+    /// Threads driving async tasks and the I/O reactor.
+    pub worker_threads: Option<usize>,
+    /// Upper bound on the blocking pool that `spawn_blocking` work, such as packet decoding,
+    /// runs on.
+    pub max_blocking_threads: Option<usize>,
+}
+
+impl RuntimeConfig {
+    pub fn build(&self) -> io::Result<Runtime> {
+        let mut builder = Builder::new_multi_thread();
+        builder.enable_all();
+        if let Some(worker_threads) = self.worker_threads {
+            builder.worker_threads(worker_threads);
+        }
+        if let Some(max_blocking_threads) = self.max_blocking_threads {
+            builder.max_blocking_threads(max_blocking_threads);
+        }
+        builder.build()
+    }
+}
+
+/// Pins the calling thread to one CPU core, so a latency sensitive loop isn't migrated between
+/// cores and keeps its caches warm. Pair it with fewer worker threads than cores, so the loop
+/// doesn't compete with a busy worker for its core.
+#[cfg(target_os = "linux")]
+pub fn pin_current_thread(core: usize) -> io::Result<()> {
+    if core >= libc::CPU_SETSIZE as usize {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("core {core} is out of range"),
+        ));
+    }
+    // SAFETY: cpu_set_t is plain data, zeroed is the empty set, and `core` is within its size
+    let result = unsafe {
+        let mut set: libc::cpu_set_t = std::mem::zeroed();
+        libc::CPU_SET(core, &mut set);
+        libc::sched_setaffinity(0, std::mem::size_of::<libc::cpu_set_t>(), &set)
+    };
+    // fails with EINVAL for a core the process isn't allowed on
+    if result != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn pin_current_thread(_core: usize) -> io::Result<()> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "thread pinning is only supported on linux",
+    ))
+}
@@ -0,0 +1,197 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, RwLock,
+    },
+};
+
+use log::warn;
+use serde::{Deserialize, Serialize};
+use solana_metrics::datapoint_info;
+use thiserror::Error;
+
+use crate::priority::TipMultipliers;
+
+/// Highest `tip_multiplier` accepted, so a typo can't multiply every tip a hundredfold.
+pub const MAX_TIP_MULTIPLIER: f64 = 10.0;
+
+#[derive(Debug, Error)]
+pub enum RuntimeParamsError {
+    #[error("failed to read runtime params: {0}")]
+    Io(#[from] io::Error),
+    #[error("invalid runtime params: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("invalid runtime params: {0}")]
+    Invalid(String),
+}
+
+/// Tunables an operator can change on a running bot, read from a JSON file such as:
+///
+/// ```json
+/// {"paused": false, "tip_multiplier": 1.5}
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct RuntimeParams {
+    /// Drop bundles instead of sending them, while streams and strategies keep running.
+    #[serde(default)]
+    pub paused: bool,
+    /// Scales every tip, on top of the priority class multipliers.
+    #[serde(default = "default_tip_multiplier")]
+    pub tip_multiplier: f64,
+}
+
+fn default_tip_multiplier() -> f64 {
+    1.0
+}
+
+impl Default for RuntimeParams {
+    fn default() -> Self {
+        Self {
+            paused: false,
+            tip_multiplier: default_tip_multiplier(),
+        }
+    }
+}
+
+impl RuntimeParams {
+    pub fn validate(&self) -> Result<(), RuntimeParamsError> {
+        if !(self.tip_multiplier > 0.0 && self.tip_multiplier <= MAX_TIP_MULTIPLIER) {
+            return Err(RuntimeParamsError::Invalid(format!(
+                "tip_multiplier {} must be above 0 and at most {MAX_TIP_MULTIPLIER}",
+                self.tip_multiplier
+            )));
+        }
+        Ok(())
+    }
+
+    /// `base` with every tip scaled by `tip_multiplier`.
+    pub fn tip_multipliers(&self, base: TipMultipliers) -> TipMultipliers {
+        TipMultipliers {
+            scale: base.scale * self.tip_multiplier,
+            ..base
+        }
+    }
+}
+
+/// The current [`RuntimeParams`], changed by reloading their file or by setting them directly.
+/// Clones share the params, so one can be handed to whatever receives the operator's changes
+/// while the bot reads them on every use. Invalid changes are rejected and the current params
+/// kept. An operator's pause, such as from an admin endpoint, is kept apart from the params so
+/// reloading the file doesn't resume the bot.
+#[derive(Clone, Debug, Default)]
+pub struct ParamStore {
+    path: Option<PathBuf>,
+    params: Arc<RwLock<RuntimeParams>>,
+    operator_paused: Arc<AtomicBool>,
+}
+
+impl ParamStore {
+    pub fn new(params: RuntimeParams) -> Result<Self, RuntimeParamsError> {
+        params.validate()?;
+        Ok(Self {
+            path: None,
+            params: Arc::new(RwLock::new(params)),
+            operator_paused: Arc::default(),
+        })
+    }
+
+    /// A store backed by the file at `path`, which `reload` reads again.
+    pub fn load(path: &Path) -> Result<Self, RuntimeParamsError> {
+        Ok(Self {
+            path: Some(path.to_path_buf()),
+            ..Self::new(Self::read(path)?)?
+        })
+    }
+
+    fn read(path: &Path) -> Result<RuntimeParams, RuntimeParamsError> {
+        Ok(serde_json::from_str(&fs::read_to_string(path)?)?)
+    }
+
+    /// The current params, paused if either they or the operator say so.
+    pub fn get(&self) -> RuntimeParams {
+        let mut params = *self.params.read().unwrap();
+        params.paused |= self.operator_paused();
+        params
+    }
+
+    pub fn operator_paused(&self) -> bool {
+        self.operator_paused.load(Ordering::Relaxed)
+    }
+
+    /// Pauses or resumes on top of the params, returning true if that changed. The pause lasts
+    /// through reloads, and resuming doesn't lift a pause set in the file.
+    pub fn set_operator_paused(&self, paused: bool) -> bool {
+        let changed = self.operator_paused.swap(paused, Ordering::Relaxed) != paused;
+        if changed {
+            warn!("operator {}", if paused { "paused" } else { "resumed" });
+        }
+        changed
+    }
+
+    /// Replaces the params, returning true if they changed. Doesn't touch an operator's pause.
+    pub fn set(&self, params: RuntimeParams) -> Result<bool, RuntimeParamsError> {
+        params.validate()?;
+        let mut current = self.params.write().unwrap();
+        if *current == params {
+            return Ok(false);
+        }
+        warn!("runtime params changed from {:?} to {params:?}", *current);
+        *current = params;
+        Ok(true)
+    }
+
+    /// Reads the file the store was loaded from again, returning true if the params changed.
+    /// Stores without a file never change this way.
+    pub fn reload(&self) -> Result<bool, RuntimeParamsError> {
+        let Some(path) = &self.path else {
+            return Ok(false);
+        };
+        self.set(Self::read(path)?)
+    }
+
+    pub fn report(&self, name: &'static str) {
+        let params = self.get();
+        datapoint_info!(
+            name,
+            ("paused", params.paused, bool),
+            ("operator_paused", self.operator_paused(), bool),
+            ("tip_multiplier", params.tip_multiplier, f64),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use crate::runtime_params::ParamStore;
+
+    #[test]
+    fn test_operator_pause_survives_reload() {
+        let path = std::env::temp_dir().join(format!("runtime_params_{}.json", std::process::id()));
+        fs::write(&path, r#"{"paused": false, "tip_multiplier": 1.5}"#).unwrap();
+        let store = ParamStore::load(&path).unwrap();
+        assert!(!store.get().paused);
+
+        assert!(store.set_operator_paused(true));
+        assert!(!store.set_operator_paused(true));
+        fs::write(&path, r#"{"paused": false, "tip_multiplier": 2.0}"#).unwrap();
+        assert!(store.reload().unwrap());
+        assert!(store.get().paused);
+        assert_eq!(store.get().tip_multiplier, 2.0);
+
+        // resuming lifts the operator's pause, not one set in the file
+        fs::write(&path, r#"{"paused": true, "tip_multiplier": 2.0}"#).unwrap();
+        assert!(store.reload().unwrap());
+        assert!(store.set_operator_paused(false));
+        assert!(store.get().paused);
+        fs::write(&path, r#"{"paused": false, "tip_multiplier": 2.0}"#).unwrap();
+        assert!(store.reload().unwrap());
+        assert!(!store.get().paused);
+
+        fs::remove_file(&path).unwrap();
+    }
+}
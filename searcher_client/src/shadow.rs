@@ -0,0 +1,241 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    time::{Duration, Instant},
+};
+
+use jito_protos::bundle::{bundle_result::Result as BundleResultType, BundleResult};
+use solana_metrics::datapoint_info;
+use solana_sdk::signature::Signature;
+
+use crate::ev_gate::ProfitEstimate;
+
+/// What one strategy bundled for a trigger.
+#[derive(Clone, Copy, Debug, Default)]
+struct Bundled {
+    bundles: u64,
+    tip_lamports: u64,
+    expected_net_lamports: i64,
+}
+
+impl Bundled {
+    fn add(&mut self, tip_lamports: u64, estimate: Option<&ProfitEstimate>) {
+        self.bundles += 1;
+        self.tip_lamports += tip_lamports;
+        self.expected_net_lamports += estimate.map_or(0, ProfitEstimate::net_lamports);
+    }
+}
+
+#[derive(Default)]
+struct Trigger {
+    live: Option<Bundled>,
+    shadow: Option<Bundled>,
+    bundle_ids: Vec<String>,
+    landed: bool,
+}
+
+/// Totals for one side of the comparison.
+#[derive(Clone, Debug, Default)]
+pub struct StrategyTotals {
+    /// Transactions, or other events, the strategy bundled.
+    pub triggers: u64,
+    pub bundles: u64,
+    pub tip_lamports: u64,
+    /// Summed over bundles with a profit estimate.
+    pub expected_net_lamports: i64,
+}
+
+impl StrategyTotals {
+    fn add(&mut self, bundled: &Bundled) {
+        self.triggers += 1;
+        self.bundles += bundled.bundles;
+        self.tip_lamports += bundled.tip_lamports;
+        self.expected_net_lamports += bundled.expected_net_lamports;
+    }
+}
+
+/// How a shadow strategy's hypothetical bundles compare to the live strategy's, over the
+/// triggers settled since the last report.
+#[derive(Clone, Debug, Default)]
+pub struct ShadowStats {
+    pub live: StrategyTotals,
+    pub shadow: StrategyTotals,
+    /// Triggers both strategies bundled.
+    pub both: u64,
+    pub live_only: u64,
+    pub shadow_only: u64,
+    /// Triggers a live bundle landed for.
+    pub live_landed: u64,
+    /// Landed triggers the shadow strategy bundled too, tipping at least as much.
+    pub shadow_matched_landed: u64,
+    /// Landed triggers the shadow strategy skipped or tipped less for.
+    pub shadow_missed_landed: u64,
+}
+
+/// Compares a candidate strategy running in shadow, building bundles that are never sent,
+/// against the live strategy on the same events. Bundles are matched by the transaction that
+/// triggered them, and a trigger is settled once `settle_after` has passed, giving the live
+/// bundles' results time to arrive.
+///
+/// Shadow bundles are never sent, so whether they would have landed is estimated from the live
+/// outcome: a shadow bundle tipping at least as much as a live bundle that landed counts as a
+/// match.
+pub struct ShadowComparison {
+    settle_after: Duration,
+    triggers: HashMap<Signature, Trigger>,
+    /// Triggers in the order they were first seen, to settle them.
+    order: VecDeque<(Instant, Signature)>,
+    bundle_ids: HashMap<String, Signature>,
+    stats: ShadowStats,
+}
+
+impl ShadowComparison {
+    pub fn new(settle_after: Duration) -> Self {
+        Self {
+            settle_after,
+            triggers: HashMap::new(),
+            order: VecDeque::new(),
+            bundle_ids: HashMap::new(),
+            stats: ShadowStats::default(),
+        }
+    }
+
+    fn trigger(&mut self, trigger: Signature, now: Instant) -> &mut Trigger {
+        let order = &mut self.order;
+        self.triggers.entry(trigger).or_insert_with(|| {
+            order.push_back((now, trigger));
+            Trigger::default()
+        })
+    }
+
+    /// Records a bundle the live strategy sent, with the id the block engine gave it if it was
+    /// accepted.
+    pub fn record_live(
+        &mut self,
+        trigger: Signature,
+        bundle_id: Option<&str>,
+        tip_lamports: u64,
+        estimate: Option<&ProfitEstimate>,
+        now: Instant,
+    ) {
+        let entry = self.trigger(trigger, now);
+        entry
+            .live
+            .get_or_insert_with(Bundled::default)
+            .add(tip_lamports, estimate);
+        if let Some(bundle_id) = bundle_id {
+            entry.bundle_ids.push(bundle_id.to_string());
+            self.bundle_ids.insert(bundle_id.to_string(), trigger);
+        }
+    }
+
+    /// Records a bundle the shadow strategy would have sent.
+    pub fn record_shadow(
+        &mut self,
+        trigger: Signature,
+        tip_lamports: u64,
+        estimate: Option<&ProfitEstimate>,
+        now: Instant,
+    ) {
+        self.trigger(trigger, now)
+            .shadow
+            .get_or_insert_with(Bundled::default)
+            .add(tip_lamports, estimate);
+    }
+
+    /// Marks the trigger of a live bundle that landed.
+    pub fn record_result(&mut self, bundle_result: &BundleResult) {
+        let landed = matches!(
+            bundle_result.result,
+            Some(BundleResultType::Processed(_)) | Some(BundleResultType::Finalized(_))
+        );
+        if !landed {
+            return;
+        }
+        if let Some(trigger) = self.bundle_ids.get(&bundle_result.bundle_id) {
+            if let Some(entry) = self.triggers.get_mut(trigger) {
+                entry.landed = true;
+            }
+        }
+    }
+
+    /// Adds triggers first seen more than `settle_after` ago to the stats and forgets them.
+    pub fn settle(&mut self, now: Instant) {
+        while let Some((seen_at, trigger)) = self.order.front().copied() {
+            if now.saturating_duration_since(seen_at) < self.settle_after {
+                break;
+            }
+            self.order.pop_front();
+            let Some(entry) = self.triggers.remove(&trigger) else {
+                continue;
+            };
+            for bundle_id in &entry.bundle_ids {
+                self.bundle_ids.remove(bundle_id);
+            }
+            let stats = &mut self.stats;
+            match (&entry.live, &entry.shadow) {
+                (Some(live), Some(shadow)) => {
+                    stats.live.add(live);
+                    stats.shadow.add(shadow);
+                    stats.both += 1;
+                }
+                (Some(live), None) => {
+                    stats.live.add(live);
+                    stats.live_only += 1;
+                }
+                (None, Some(shadow)) => {
+                    stats.shadow.add(shadow);
+                    stats.shadow_only += 1;
+                }
+                (None, None) => {}
+            }
+            if entry.landed {
+                stats.live_landed += 1;
+                let live_tip = entry
+                    .live
+                    .map_or(0, |live| live.tip_lamports / live.bundles);
+                let shadow_tip = entry
+                    .shadow
+                    .map(|shadow| shadow.tip_lamports / shadow.bundles);
+                if shadow_tip.is_some_and(|shadow_tip| shadow_tip >= live_tip) {
+                    stats.shadow_matched_landed += 1;
+                } else {
+                    stats.shadow_missed_landed += 1;
+                }
+            }
+        }
+    }
+
+    pub fn stats(&self) -> &ShadowStats {
+        &self.stats
+    }
+
+    /// Reports the settled stats and resets them.
+    pub fn report(&mut self, name: &'static str) {
+        let stats = std::mem::take(&mut self.stats);
+        datapoint_info!(
+            name,
+            ("live_triggers", stats.live.triggers, i64),
+            ("live_bundles", stats.live.bundles, i64),
+            ("live_tip_lamports", stats.live.tip_lamports, i64),
+            (
+                "live_expected_net_lamports",
+                stats.live.expected_net_lamports,
+                i64
+            ),
+            ("shadow_triggers", stats.shadow.triggers, i64),
+            ("shadow_bundles", stats.shadow.bundles, i64),
+            ("shadow_tip_lamports", stats.shadow.tip_lamports, i64),
+            (
+                "shadow_expected_net_lamports",
+                stats.shadow.expected_net_lamports,
+                i64
+            ),
+            ("both", stats.both, i64),
+            ("live_only", stats.live_only, i64),
+            ("shadow_only", stats.shadow_only, i64),
+            ("live_landed", stats.live_landed, i64),
+            ("shadow_matched_landed", stats.shadow_matched_landed, i64),
+            ("shadow_missed_landed", stats.shadow_missed_landed, i64),
+        );
+    }
+}
@@ -0,0 +1,182 @@
+use std::{
+    collections::HashSet,
+    fmt::{self, Display, Formatter},
+    str::FromStr,
+};
+
+use solana_client::{
+    client_error::ClientError, nonblocking::rpc_client::RpcClient,
+    rpc_config::RpcSimulateTransactionConfig,
+};
+use solana_metrics::datapoint_info;
+use solana_sdk::{
+    instruction::InstructionError,
+    transaction::{TransactionError, VersionedTransaction},
+};
+use thiserror::Error;
+
+/// A simulation error a policy lets through: `custom:<code>` for a program's custom error, or
+/// the name of a `TransactionError` or `InstructionError` variant such as `AccountInUse` or
+/// `ComputationalBudgetExceeded`.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum AllowedSimulationError {
+    Custom(u32),
+    Named(String),
+}
+
+impl AllowedSimulationError {
+    pub fn matches(&self, error: &TransactionError) -> bool {
+        match (self, error) {
+            (
+                AllowedSimulationError::Custom(code),
+                TransactionError::InstructionError(_, InstructionError::Custom(actual)),
+            ) => code == actual,
+            (AllowedSimulationError::Custom(_), _) => false,
+            (AllowedSimulationError::Named(name), TransactionError::InstructionError(_, e)) => {
+                variant_name(error) == *name || variant_name(e) == *name
+            }
+            (AllowedSimulationError::Named(name), _) => variant_name(error) == *name,
+        }
+    }
+}
+
+/// The variant name from an error's debug output, e.g. `InstructionError` from
+/// `InstructionError(0, Custom(1))`.
+fn variant_name(error: &impl fmt::Debug) -> String {
+    let debug = format!("{error:?}");
+    debug
+        .split(|c: char| !c.is_alphanumeric())
+        .next()
+        .unwrap_or_default()
+        .to_string()
+}
+
+impl Display for AllowedSimulationError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            AllowedSimulationError::Custom(code) => write!(f, "custom:{code}"),
+            AllowedSimulationError::Named(name) => write!(f, "{name}"),
+        }
+    }
+}
+
+impl FromStr for AllowedSimulationError {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        if let Some(code) = s.strip_prefix("custom:") {
+            return code
+                .parse()
+                .map(AllowedSimulationError::Custom)
+                .map_err(|e| format!("invalid custom error code {code:?}: {e}"));
+        }
+        if s.is_empty() || !s.chars().all(char::is_alphanumeric) {
+            return Err(format!(
+                "invalid simulation error {s:?}, expected an error name such as AccountInUse or \
+                 custom:<code>"
+            ));
+        }
+        Ok(AllowedSimulationError::Named(s.to_string()))
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum SimulationRejection {
+    #[error("couldn't simulate: {0}")]
+    Rpc(#[from] ClientError),
+    #[error("transaction {index} failed simulation: {error}")]
+    Failed {
+        index: usize,
+        error: TransactionError,
+    },
+}
+
+/// Requires every transaction of a bundle to simulate successfully before it's sent, so nothing
+/// goes out unsimulated. Errors in `allowed_errors` still pass, for failures expected from
+/// simulating outside the bundle, and a bundle that can't be simulated is rejected.
+///
+/// Each transaction is simulated on its own against the RPC node's current state, not on top of
+/// the transactions before it in the bundle, since plain RPC nodes can't simulate bundles.
+/// Signatures aren't verified and the blockhash is replaced, so neither fails the simulation.
+pub struct SimulationPolicy {
+    allowed_errors: HashSet<AllowedSimulationError>,
+    passed: u64,
+    allowed: u64,
+    failed: u64,
+    rpc_errors: u64,
+}
+
+impl SimulationPolicy {
+    pub fn new(allowed_errors: impl IntoIterator<Item = AllowedSimulationError>) -> Self {
+        Self {
+            allowed_errors: allowed_errors.into_iter().collect(),
+            passed: 0,
+            allowed: 0,
+            failed: 0,
+            rpc_errors: 0,
+        }
+    }
+
+    pub fn allows(&self, error: &TransactionError) -> bool {
+        self.allowed_errors
+            .iter()
+            .any(|allowed| allowed.matches(error))
+    }
+
+    /// Simulates `transactions` in order, stopping at the first disallowed failure, and returns
+    /// how many failed with an allowed error. Takes `&self` so bundles can be simulated
+    /// concurrently, pass each result to `record`.
+    pub async fn simulate(
+        &self,
+        rpc_client: &RpcClient,
+        transactions: &[VersionedTransaction],
+    ) -> Result<usize, SimulationRejection> {
+        let mut allowed = 0;
+        for (index, transaction) in transactions.iter().enumerate() {
+            let config = RpcSimulateTransactionConfig {
+                sig_verify: false,
+                replace_recent_blockhash: true,
+                commitment: Some(rpc_client.commitment()),
+                ..RpcSimulateTransactionConfig::default()
+            };
+            let result = rpc_client
+                .simulate_transaction_with_config(transaction, config)
+                .await?
+                .value;
+            match result.err {
+                Some(error) if self.allows(&error) => allowed += 1,
+                Some(error) => return Err(SimulationRejection::Failed { index, error }),
+                None => {}
+            }
+        }
+        Ok(allowed)
+    }
+
+    /// Counts the outcome of a `simulate` call.
+    pub fn record(&mut self, result: &Result<usize, SimulationRejection>) {
+        match result {
+            Ok(allowed) => {
+                self.passed += 1;
+                self.allowed += *allowed as u64;
+            }
+            Err(SimulationRejection::Failed { .. }) => self.failed += 1,
+            Err(SimulationRejection::Rpc(_)) => self.rpc_errors += 1,
+        }
+    }
+
+    /// Reports the counters and resets them.
+    pub fn report(&mut self, name: &'static str) {
+        datapoint_info!(
+            name,
+            ("passed", self.passed, i64),
+            ("allowed", self.allowed, i64),
+            ("failed", self.failed, i64),
+            ("rpc_errors", self.rpc_errors, i64),
+        );
+        self.passed = 0;
+        self.allowed = 0;
+        self.failed = 0;
+        self.rpc_errors = 0;
+    }
+}
@@ -0,0 +1,140 @@
+use std::collections::HashMap;
+
+use solana_sdk::{account::Account, pubkey::Pubkey};
+use spl_token_2022::{extension::StateWithExtensions, native_mint, state::Account as TokenAccount};
+use thiserror::Error;
+
+#[derive(Debug, Error, PartialEq, Eq)]
+#[error(
+    "simulated profit of {simulated_lamports} lamports is more than {tolerance_lamports} below the \
+     expected {expected_lamports}"
+)]
+pub struct ProfitMismatch {
+    pub simulated_lamports: i64,
+    pub expected_lamports: u64,
+    pub tolerance_lamports: u64,
+}
+
+/// The change in balance of one token account.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TokenDelta {
+    pub token_account: Pubkey,
+    pub mint: Pubkey,
+    pub owner: Pubkey,
+    pub amount: i128,
+}
+
+/// Lamport and token balance changes of a simulated bundle, so a strategy can check the profit
+/// it expects is really there before paying a tip for it.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SimulationReport {
+    /// Every account whose lamports changed, token accounts included.
+    pub lamport_deltas: HashMap<Pubkey, i64>,
+    /// Every SPL Token or Token-2022 account whose balance changed.
+    pub token_deltas: Vec<TokenDelta>,
+}
+
+impl SimulationReport {
+    /// Compares each account's state before and after, None where the account doesn't exist.
+    pub fn from_states<'a>(
+        states: impl IntoIterator<Item = (Pubkey, Option<&'a Account>, Option<&'a Account>)>,
+    ) -> Self {
+        let mut report = Self::default();
+        for (pubkey, before, after) in states {
+            let lamports = |account: Option<&Account>| account.map_or(0, |a| a.lamports as i64);
+            let lamport_delta = lamports(after) - lamports(before);
+            if lamport_delta != 0 {
+                report.lamport_deltas.insert(pubkey, lamport_delta);
+            }
+
+            let before_token = before.and_then(token_account);
+            let after_token = after.and_then(token_account);
+            let Some((mint, owner)) = after_token
+                .or(before_token)
+                .map(|token| (token.mint, token.owner))
+            else {
+                continue;
+            };
+            let amount = |token: Option<TokenAccount>| token.map_or(0, |t| t.amount as i128);
+            let amount = amount(after_token) - amount(before_token);
+            if amount != 0 {
+                report.token_deltas.push(TokenDelta {
+                    token_account: pubkey,
+                    mint,
+                    owner,
+                    amount,
+                });
+            }
+        }
+        report
+    }
+
+    /// Adds the changes of another simulation on top of these.
+    pub fn merge(&mut self, other: &SimulationReport) {
+        for (pubkey, delta) in &other.lamport_deltas {
+            *self.lamport_deltas.entry(*pubkey).or_default() += delta;
+        }
+        self.lamport_deltas.retain(|_, delta| *delta != 0);
+        for delta in &other.token_deltas {
+            match self
+                .token_deltas
+                .iter_mut()
+                .find(|d| d.token_account == delta.token_account)
+            {
+                Some(existing) => existing.amount += delta.amount,
+                None => self.token_deltas.push(*delta),
+            }
+        }
+        self.token_deltas.retain(|delta| delta.amount != 0);
+    }
+
+    pub fn lamport_delta(&self, account: &Pubkey) -> i64 {
+        self.lamport_deltas
+            .get(account)
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// Change across every token account of `mint` owned by `owner`.
+    pub fn token_delta(&self, owner: &Pubkey, mint: &Pubkey) -> i128 {
+        self.token_deltas
+            .iter()
+            .filter(|delta| delta.owner == *owner && delta.mint == *mint)
+            .map(|delta| delta.amount)
+            .sum()
+    }
+
+    /// Lamports `owner` gained, counting wrapped SOL in its token accounts as lamports.
+    pub fn profit_lamports(&self, owner: &Pubkey) -> i64 {
+        self.lamport_delta(owner) + self.token_delta(owner, &native_mint::id()) as i64
+    }
+
+    /// Returns the simulated profit of `owner`, or an error if it falls short of
+    /// `expected_profit_lamports` by more than `tolerance_lamports`.
+    pub fn check_expected_profit(
+        &self,
+        owner: &Pubkey,
+        expected_profit_lamports: u64,
+        tolerance_lamports: u64,
+    ) -> Result<i64, ProfitMismatch> {
+        let simulated_lamports = self.profit_lamports(owner);
+        let floor = expected_profit_lamports as i128 - tolerance_lamports as i128;
+        if (simulated_lamports as i128) < floor {
+            return Err(ProfitMismatch {
+                simulated_lamports,
+                expected_lamports: expected_profit_lamports,
+                tolerance_lamports,
+            });
+        }
+        Ok(simulated_lamports)
+    }
+}
+
+fn token_account(account: &Account) -> Option<TokenAccount> {
+    if account.owner != spl_token::id() && account.owner != spl_token_2022::id() {
+        return None;
+    }
+    StateWithExtensions::<TokenAccount>::unpack(&account.data)
+        .ok()
+        .map(|state| state.base)
+}
@@ -0,0 +1,238 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    fs, io,
+    path::Path,
+    time::{Duration, Instant},
+};
+
+use serde::Deserialize;
+use thiserror::Error;
+
+use crate::pool_registry::{PoolRegistry, TokenPair};
+
+#[derive(Debug, Error)]
+pub enum SlippageError {
+    #[error("failed to read slippage config: {0}")]
+    Io(#[from] io::Error),
+    #[error("invalid slippage config: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("{0}: base_bps {1} is above max_bps {2}")]
+    BaseAboveMax(String, u16, u16),
+    #[error("{0}: max_bps {1} is above 10000")]
+    MaxTooHigh(String, u16),
+    #[error("volatility_multiplier must be a finite, non-negative number")]
+    InvalidMultiplier,
+}
+
+/// Slippage allowed on a pair, in basis points of the expected output.
+#[derive(Clone, Copy, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PairSlippage {
+    /// Allowed when the price is steady.
+    pub base_bps: u16,
+    /// Ceiling the allowance is widened to as the price moves.
+    pub max_bps: u16,
+}
+
+impl Default for PairSlippage {
+    fn default() -> Self {
+        Self {
+            base_bps: 50,
+            max_bps: 300,
+        }
+    }
+}
+
+/// Slippage settings, read from a JSON file such as:
+///
+/// ```json
+/// {
+///   "default": {"base_bps": 50, "max_bps": 300},
+///   "pairs": {
+///     "So11111111111111111111111111111111111111112:EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v": {
+///       "base_bps": 10, "max_bps": 100
+///     }
+///   },
+///   "volatility_window_secs": 60,
+///   "volatility_multiplier": 0.5
+/// }
+/// ```
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SlippageConfig {
+    /// For pairs without their own entry.
+    #[serde(default)]
+    pub default: PairSlippage,
+    #[serde(default)]
+    pub pairs: HashMap<TokenPair, PairSlippage>,
+    /// How far back prices are kept to measure volatility.
+    #[serde(default = "default_volatility_window_secs")]
+    pub volatility_window_secs: u64,
+    /// Basis points of slippage added per basis point the price ranged over the window.
+    #[serde(default = "default_volatility_multiplier")]
+    pub volatility_multiplier: f64,
+}
+
+fn default_volatility_window_secs() -> u64 {
+    60
+}
+
+fn default_volatility_multiplier() -> f64 {
+    0.5
+}
+
+impl Default for SlippageConfig {
+    fn default() -> Self {
+        Self {
+            default: PairSlippage::default(),
+            pairs: HashMap::new(),
+            volatility_window_secs: default_volatility_window_secs(),
+            volatility_multiplier: default_volatility_multiplier(),
+        }
+    }
+}
+
+impl SlippageConfig {
+    pub fn load(path: &Path) -> Result<Self, SlippageError> {
+        let config: Self = serde_json::from_str(&fs::read_to_string(path)?)?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    pub fn validate(&self) -> Result<(), SlippageError> {
+        let pairs = self
+            .pairs
+            .iter()
+            .map(|(pair, slippage)| (pair.to_string(), slippage));
+        for (name, slippage) in [("default".to_string(), &self.default)]
+            .into_iter()
+            .chain(pairs)
+        {
+            if slippage.max_bps > 10_000 {
+                return Err(SlippageError::MaxTooHigh(name, slippage.max_bps));
+            }
+            if slippage.base_bps > slippage.max_bps {
+                return Err(SlippageError::BaseAboveMax(
+                    name,
+                    slippage.base_bps,
+                    slippage.max_bps,
+                ));
+            }
+        }
+        if !self.volatility_multiplier.is_finite() || self.volatility_multiplier < 0.0 {
+            return Err(SlippageError::InvalidMultiplier);
+        }
+        Ok(())
+    }
+
+    pub fn pair(&self, pair: &TokenPair) -> PairSlippage {
+        self.pairs.get(pair).copied().unwrap_or(self.default)
+    }
+}
+
+/// The one place swap builders get their slippage from, so min-out values follow the same
+/// rules everywhere. Each pair is allowed its base slippage while its price is steady, widened
+/// in proportion to how far the price ranged over the volatility window, up to the pair's max.
+///
+/// Prices are fed in with `record_price`, or from a [`PoolRegistry`] with `observe_pools`.
+/// Without recent prices a pair gets its base slippage.
+pub struct SlippagePolicy {
+    config: SlippageConfig,
+    /// Recent prices of each pair's first mint, by address, in the other.
+    prices: HashMap<TokenPair, VecDeque<(Instant, f64)>>,
+    /// Set at runtime, see `cap_bps`.
+    cap_bps: Option<u16>,
+}
+
+impl SlippagePolicy {
+    pub fn new(config: SlippageConfig) -> Self {
+        Self {
+            config,
+            prices: HashMap::new(),
+            cap_bps: None,
+        }
+    }
+
+    /// Caps the slippage allowed on every pair, below each pair's own max, until the cap is
+    /// lifted with None. For tightening slippage on a running bot.
+    pub fn cap_bps(&mut self, cap_bps: Option<u16>) {
+        self.cap_bps = cap_bps;
+    }
+
+    fn window(&self) -> Duration {
+        Duration::from_secs(self.config.volatility_window_secs)
+    }
+
+    /// Records the price of `pair`'s first mint, by address, in the other.
+    pub fn record_price(&mut self, pair: TokenPair, price: f64, now: Instant) {
+        if !price.is_finite() || price <= 0.0 {
+            return;
+        }
+        let window = self.window();
+        let prices = self.prices.entry(pair).or_default();
+        prices.push_back((now, price));
+        while prices
+            .front()
+            .is_some_and(|(at, _)| now.saturating_duration_since(*at) > window)
+        {
+            prices.pop_front();
+        }
+    }
+
+    /// Records the price of every pair the registry knows pools for, taken from the pool
+    /// holding the most of the pair's first mint.
+    pub fn observe_pools(&mut self, registry: &PoolRegistry, now: Instant) {
+        let mut deepest: HashMap<TokenPair, (u64, f64)> = HashMap::new();
+        for pool in registry.pools() {
+            let pair = pool.pair();
+            let (mint, _) = pair.mints();
+            let (Some((depth, _)), Some(price)) = (pool.reserves_of(&mint), pool.price_of(&mint))
+            else {
+                continue;
+            };
+            if deepest.get(&pair).is_none_or(|(best, _)| depth > *best) {
+                deepest.insert(pair, (depth, price));
+            }
+        }
+        for (pair, (_, price)) in deepest {
+            self.record_price(pair, price, now);
+        }
+    }
+
+    /// How far `pair`'s price ranged over the window, in basis points of its latest price.
+    pub fn volatility_bps(&self, pair: &TokenPair, now: Instant) -> u32 {
+        let window = self.window();
+        let recent = self
+            .prices
+            .get(pair)
+            .into_iter()
+            .flatten()
+            .filter(|(at, _)| now.saturating_duration_since(*at) <= window);
+        let (mut low, mut high, mut latest) = (f64::MAX, 0.0f64, None);
+        for (_, price) in recent {
+            low = low.min(*price);
+            high = high.max(*price);
+            latest = Some(*price);
+        }
+        match latest {
+            Some(latest) => ((high - low) / latest * 10_000.0).round() as u32,
+            None => 0,
+        }
+    }
+
+    /// Slippage allowed on `pair` right now, in basis points.
+    pub fn slippage_bps(&self, pair: &TokenPair, now: Instant) -> u16 {
+        let slippage = self.config.pair(pair);
+        let widening = self.volatility_bps(pair, now) as f64 * self.config.volatility_multiplier;
+        let max_bps = slippage.max_bps.min(self.cap_bps.unwrap_or(u16::MAX));
+        (slippage.base_bps as f64 + widening)
+            .round()
+            .min(max_bps as f64) as u16
+    }
+
+    /// The least a swap on `pair` expecting `expected_out` may return.
+    pub fn min_out(&self, pair: &TokenPair, expected_out: u64, now: Instant) -> u64 {
+        let keep_bps = 10_000 - self.slippage_bps(pair, now).min(10_000) as u128;
+        (expected_out as u128 * keep_bps / 10_000) as u64
+    }
+}
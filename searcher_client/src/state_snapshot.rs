@@ -0,0 +1,69 @@
+use std::{
+    fs, io,
+    path::Path,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use thiserror::Error;
+
+/// Bumped whenever a snapshot written by an older build can't be read by a newer one.
+const SNAPSHOT_VERSION: u32 = 1;
+
+#[derive(Debug, Error)]
+pub enum SnapshotError {
+    #[error("io error {0}")]
+    Io(#[from] io::Error),
+    #[error("invalid snapshot {0}")]
+    Decode(#[from] bincode::Error),
+    #[error("snapshot version {0}, expected {SNAPSHOT_VERSION}")]
+    Version(u32),
+}
+
+#[derive(Serialize, Deserialize)]
+struct Snapshot<T> {
+    version: u32,
+    saved_at_ms: u64,
+    state: T,
+}
+
+/// Writes `state` to `path`, replacing the previous snapshot only once the new one is fully
+/// written.
+pub fn save_snapshot<T: Serialize>(path: &Path, state: &T) -> Result<(), SnapshotError> {
+    let snapshot = Snapshot {
+        version: SNAPSHOT_VERSION,
+        saved_at_ms: unix_now_ms(),
+        state,
+    };
+    let mut tmp_path = path.to_path_buf().into_os_string();
+    tmp_path.push(".tmp");
+    fs::write(&tmp_path, bincode::serialize(&snapshot)?)?;
+    fs::rename(&tmp_path, path)?;
+    Ok(())
+}
+
+/// Reads the state saved at `path` and how old it is. None if there's no snapshot.
+pub fn load_snapshot<T: DeserializeOwned>(
+    path: &Path,
+) -> Result<Option<(T, Duration)>, SnapshotError> {
+    let bytes = match fs::read(path) {
+        Ok(bytes) => bytes,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e.into()),
+    };
+    // the version comes first, check it before decoding a state laid out differently
+    let version: u32 = bincode::deserialize(&bytes)?;
+    if version != SNAPSHOT_VERSION {
+        return Err(SnapshotError::Version(version));
+    }
+    let snapshot: Snapshot<T> = bincode::deserialize(&bytes)?;
+    let age = Duration::from_millis(unix_now_ms().saturating_sub(snapshot.saved_at_ms));
+    Ok(Some((snapshot.state, age)))
+}
+
+fn unix_now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as u64
+}
@@ -0,0 +1,88 @@
+use std::{fs, io, path::Path};
+
+use serde::de::DeserializeOwned;
+use serde_json::{Map, Value};
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum StrategyConfigError {
+    #[error("failed to read strategy config: {0}")]
+    Io(#[from] io::Error),
+    #[error("invalid strategy config: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("invalid strategy config section {section:?}: {error}")]
+    Section { section: String, error: String },
+    #[error("strategy config has sections for unknown strategies: {}", .0.join(", "))]
+    UnknownSections(Vec<String>),
+}
+
+/// A strategy's own settings, read from its section of a strategy config file. Implementations
+/// are plain serde structs, which should use `#[serde(deny_unknown_fields)]` so a misspelled
+/// setting is rejected rather than silently left at its default.
+pub trait StrategyConfig: DeserializeOwned {
+    /// Name of the strategy's section.
+    const SECTION: &'static str;
+
+    /// Checks what the types alone can't, such as ranges and settings that conflict.
+    fn validate(&self) -> Result<(), String> {
+        Ok(())
+    }
+}
+
+/// Settings for several strategies, one section per strategy keyed by its name, read from a
+/// JSON file such as:
+///
+/// ```json
+/// {
+///   "memo-backrun": {"message": "usdc backrun"},
+///   "wasm": {"path": "strategy.wasm"}
+/// }
+/// ```
+///
+/// Each section is deserialized into its strategy's [`StrategyConfig`] type when asked for, so
+/// adding a strategy means adding a type rather than more flags.
+#[derive(Clone, Debug, Default)]
+pub struct StrategyConfigFile {
+    sections: Map<String, Value>,
+}
+
+impl StrategyConfigFile {
+    pub fn load(path: &Path) -> Result<Self, StrategyConfigError> {
+        Self::from_json(&fs::read_to_string(path)?)
+    }
+
+    pub fn from_json(json: &str) -> Result<Self, StrategyConfigError> {
+        Ok(Self {
+            sections: serde_json::from_str(json)?,
+        })
+    }
+
+    /// Fails if the file has a section none of `known` strategies reads, such as one for a
+    /// misspelled or removed strategy.
+    pub fn check_sections(&self, known: &[&str]) -> Result<(), StrategyConfigError> {
+        let unknown: Vec<String> = self
+            .sections
+            .keys()
+            .filter(|section| !known.contains(&section.as_str()))
+            .cloned()
+            .collect();
+        if !unknown.is_empty() {
+            return Err(StrategyConfigError::UnknownSections(unknown));
+        }
+        Ok(())
+    }
+
+    /// The strategy's settings, None if the file has no section for it.
+    pub fn section<T: StrategyConfig>(&self) -> Result<Option<T>, StrategyConfigError> {
+        let Some(value) = self.sections.get(T::SECTION) else {
+            return Ok(None);
+        };
+        let invalid = |error: String| StrategyConfigError::Section {
+            section: T::SECTION.to_string(),
+            error,
+        };
+        let config = T::deserialize(value).map_err(|e| invalid(e.to_string()))?;
+        config.validate().map_err(invalid)?;
+        Ok(Some(config))
+    }
+}
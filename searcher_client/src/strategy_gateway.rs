@@ -0,0 +1,676 @@
+use std::{
+    collections::HashMap,
+    pin::Pin,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex, RwLock, Weak,
+    },
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use futures_util::{stream, Stream, StreamExt};
+use jito_protos::{
+    bundle::{bundle_result::Result as BundleResultType, rejected::Reason, BundleResult},
+    searcher::{
+        mempool_subscription, searcher_service_client::SearcherServiceClient, MempoolSubscription,
+        ProgramSubscriptionV0, SubscribeBundleResultsRequest, WriteLockedAccountSubscriptionV0,
+    },
+    strategy_gateway::{
+        strategy_gateway_server::StrategyGateway, BundleRequest, BundleStatus, BundleUpdate,
+        MempoolEvent, MempoolFilter, Priority,
+    },
+};
+use log::{debug, warn};
+use solana_client::{client_error::ClientError, nonblocking::rpc_client::RpcClient};
+use solana_metrics::datapoint_info;
+use solana_sdk::{
+    hash::Hash,
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+    transaction::{Transaction, VersionedTransaction},
+};
+use tokio::{sync::mpsc, time::sleep};
+use tonic::{
+    codegen::InterceptedService, transport::Channel, Request, Response, Status, Streaming,
+};
+
+use crate::{
+    bundle_builder::MAX_BUNDLE_TRANSACTIONS,
+    gateway_clients::{GatewayClient, GatewayClients, QuotaExceeded},
+    idempotency::{IdempotencyCheck, IdempotencyKeys},
+    load_shedding::{Overloaded, QueueLimits, SubmissionQueue},
+    priority::PriorityClass,
+    send_bundle_no_wait,
+    tip_budget::TipBudget,
+    tip_payment::TipSplit,
+    token_authenticator::ClientInterceptor,
+};
+
+type Client = SearcherServiceClient<InterceptedService<Channel, ClientInterceptor>>;
+type UpdateSender = mpsc::Sender<Result<BundleUpdate, Status>>;
+
+const BLOCKHASH_REFRESH: Duration = Duration::from_secs(2);
+const RESUBSCRIBE_DELAY: Duration = Duration::from_secs(1);
+/// Bundles the block engine hasn't reported on by then stop getting updates.
+const PENDING_TTL: Duration = Duration::from_secs(120);
+const UPDATE_BUFFER: usize = 1_000;
+const CLIENT_REPORT_INTERVAL: Duration = Duration::from_secs(10);
+
+pub struct GatewayConfig {
+    /// Pays the tip transaction appended to each bundle.
+    pub payer: Arc<Keypair>,
+    pub tip_accounts: Vec<Pubkey>,
+    /// Largest tip a single bundle may ask for.
+    pub max_tip_lamports: u64,
+    /// Lamports each strategy may tip per window, unlimited if not set.
+    pub tip_budget: Option<(u64, Duration)>,
+    /// Clients allowed to connect, by their bearer token, and their quotas. Anyone may connect if
+    /// not set.
+    pub clients: Option<GatewayClients>,
+    /// Shares of each tip paid to accounts other than the tip account.
+    pub tip_split: TipSplit,
+    /// How long an idempotency key is remembered after its bundle was sent.
+    pub idempotency_ttl: Duration,
+    /// Bundles queued at once, past which new ones are refused by priority.
+    pub queue_limits: QueueLimits,
+}
+
+/// Serves the `StrategyGateway` gRPC service, so strategies running as separate processes share
+/// this client's block engine connection, tip payer and budgets.
+pub struct StrategyGatewayService {
+    gateway: Arc<Gateway>,
+}
+
+struct Pending {
+    request_id: String,
+    updates: UpdateSender,
+    submitted_at: Instant,
+}
+
+struct Gateway {
+    client: Client,
+    config: GatewayConfig,
+    budgets: Mutex<HashMap<String, TipBudget>>,
+    blockhash: RwLock<Hash>,
+    next_tip_account: AtomicUsize,
+    pending: Mutex<HashMap<String, Pending>>,
+    idempotency_keys: Mutex<IdempotencyKeys>,
+    queue: Arc<SubmissionQueue>,
+}
+
+impl StrategyGatewayService {
+    /// Fetches a blockhash for tip transactions and starts keeping it fresh, along with forwarding
+    /// bundle results to the strategies that submitted them.
+    pub async fn new(
+        client: Client,
+        rpc_client: RpcClient,
+        config: GatewayConfig,
+    ) -> Result<Self, ClientError> {
+        let blockhash = rpc_client.get_latest_blockhash().await?;
+        let gateway = Arc::new(Gateway {
+            client,
+            idempotency_keys: Mutex::new(IdempotencyKeys::new(config.idempotency_ttl)),
+            queue: SubmissionQueue::new(config.queue_limits),
+            config,
+            budgets: Mutex::default(),
+            blockhash: RwLock::new(blockhash),
+            next_tip_account: AtomicUsize::default(),
+            pending: Mutex::default(),
+        });
+        tokio::spawn(refresh_blockhash(Arc::downgrade(&gateway), rpc_client));
+        tokio::spawn(forward_bundle_results(Arc::downgrade(&gateway)));
+        if gateway.config.clients.is_some() {
+            tokio::spawn(report_clients(Arc::downgrade(&gateway)));
+        }
+        Ok(Self { gateway })
+    }
+}
+
+type EventStream<T> = Pin<Box<dyn Stream<Item = Result<T, Status>> + Send>>;
+
+#[tonic::async_trait]
+impl StrategyGateway for StrategyGatewayService {
+    type SubscribeMempoolStream = EventStream<MempoolEvent>;
+    type SubmitBundlesStream = EventStream<BundleUpdate>;
+
+    async fn subscribe_mempool(
+        &self,
+        request: Request<MempoolFilter>,
+    ) -> Result<Response<Self::SubscribeMempoolStream>, Status> {
+        self.gateway.authenticate(&request)?;
+        let filter = request.into_inner();
+        let msg = match (filter.accounts.is_empty(), filter.programs.is_empty()) {
+            (false, true) => {
+                mempool_subscription::Msg::WlaV0Sub(WriteLockedAccountSubscriptionV0 {
+                    accounts: filter.accounts,
+                })
+            }
+            (true, false) => mempool_subscription::Msg::ProgramV0Sub(ProgramSubscriptionV0 {
+                programs: filter.programs,
+            }),
+            _ => {
+                return Err(Status::invalid_argument(
+                    "exactly one of accounts or programs must be set",
+                ))
+            }
+        };
+        let pending_transactions = self
+            .gateway
+            .client
+            .clone()
+            .subscribe_mempool(MempoolSubscription {
+                regions: filter.regions,
+                msg: Some(msg),
+            })
+            .await?
+            .into_inner();
+        let events = pending_transactions.map(|notification| {
+            notification.map(|notification| MempoolEvent {
+                transactions: notification
+                    .transactions
+                    .into_iter()
+                    .map(|packet| packet.data)
+                    .collect(),
+                received_at_unix_micros: SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .unwrap_or_default()
+                    .as_micros() as i64,
+            })
+        });
+        Ok(Response::new(Box::pin(events)))
+    }
+
+    async fn submit_bundles(
+        &self,
+        request: Request<Streaming<BundleRequest>>,
+    ) -> Result<Response<Self::SubmitBundlesStream>, Status> {
+        let client = self.gateway.authenticate(&request)?;
+        let mut requests = request.into_inner();
+        let (updates, receiver) = mpsc::channel(UPDATE_BUFFER);
+        let gateway = self.gateway.clone();
+        tokio::spawn(async move {
+            while let Some(request) = requests.next().await {
+                let request = match request {
+                    Ok(request) => request,
+                    Err(status) => {
+                        debug!("strategy closed its bundle stream: {status}");
+                        break;
+                    }
+                };
+                if let Some(Err(exceeded)) = client
+                    .as_ref()
+                    .map(|client| client.check_request(Instant::now()))
+                {
+                    if updates
+                        .send(Ok(over_quota(request, exceeded)))
+                        .await
+                        .is_err()
+                    {
+                        break;
+                    }
+                    continue;
+                }
+                let slot = match gateway.queue.admit(priority_class(request.priority())) {
+                    Ok(slot) => slot,
+                    Err(overloaded) => {
+                        if updates.send(Ok(shed(request, overloaded))).await.is_err() {
+                            break;
+                        }
+                        continue;
+                    }
+                };
+                // sent concurrently, so one slow submission doesn't hold up the stream's others
+                let gateway = gateway.clone();
+                let client = client.clone();
+                let updates = updates.clone();
+                tokio::spawn(async move {
+                    let update = gateway.submit(request, client.as_deref(), &updates).await;
+                    drop(slot);
+                    let _ = updates.send(Ok(update)).await;
+                });
+            }
+        });
+        let updates = stream::unfold(receiver, |mut receiver| async move {
+            receiver.recv().await.map(|update| (update, receiver))
+        });
+        Ok(Response::new(Box::pin(updates)))
+    }
+}
+
+/// Why a bundle wasn't sent, and when to try again if it's only a matter of time.
+struct Refusal {
+    message: String,
+    retry_after: Option<Duration>,
+}
+
+impl From<String> for Refusal {
+    fn from(message: String) -> Self {
+        Self {
+            message,
+            retry_after: None,
+        }
+    }
+}
+
+impl From<QuotaExceeded> for Refusal {
+    fn from(exceeded: QuotaExceeded) -> Self {
+        Self {
+            message: exceeded.to_string(),
+            retry_after: exceeded.retry_after(),
+        }
+    }
+}
+
+impl Gateway {
+    /// The client presenting the request's bearer token, None if the gateway has no clients.
+    fn authenticate<T>(&self, request: &Request<T>) -> Result<Option<Arc<GatewayClient>>, Status> {
+        let Some(clients) = &self.config.clients else {
+            return Ok(None);
+        };
+        let client = request
+            .metadata()
+            .get("authorization")
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "))
+            .and_then(|token| clients.authenticate(token.trim()));
+        if client.is_none() {
+            warn!(
+                "rejected unauthenticated gateway request from {:?}",
+                request.remote_addr()
+            );
+            return Err(Status::unauthenticated("missing or invalid bearer token"));
+        }
+        Ok(client)
+    }
+
+    async fn submit(
+        &self,
+        request: BundleRequest,
+        client: Option<&GatewayClient>,
+        updates: &UpdateSender,
+    ) -> BundleUpdate {
+        let key = request.idempotency_key.clone();
+        if key.is_empty() {
+            return self.send(request, client, updates).await;
+        }
+        let check = self.idempotency_keys.lock().expect("not poisoned").check(
+            &key,
+            &request.transactions,
+            Instant::now(),
+        );
+        let (status, uuid, message) = match check {
+            IdempotencyCheck::New => {
+                let update = self.send(request, client, updates).await;
+                let mut keys = self.idempotency_keys.lock().expect("not poisoned");
+                if update.status() == BundleStatus::Submitted {
+                    keys.complete(&key, update.uuid.clone(), Instant::now());
+                } else {
+                    keys.release(&key);
+                }
+                return update;
+            }
+            IdempotencyCheck::Duplicate(uuid) => {
+                // the retry likely comes from a new stream, the one the bundle was sent on gone
+                if let Some(pending) = self.pending.lock().expect("not poisoned").get_mut(&uuid) {
+                    pending.request_id = request.request_id.clone();
+                    pending.updates = updates.clone();
+                }
+                (
+                    BundleStatus::Duplicate,
+                    uuid,
+                    "already sent with this idempotency key".to_string(),
+                )
+            }
+            IdempotencyCheck::Conflict(uuid) => (
+                BundleStatus::Conflict,
+                uuid,
+                "idempotency key was already used for a different bundle".to_string(),
+            ),
+            IdempotencyCheck::InFlight => (
+                BundleStatus::Conflict,
+                String::new(),
+                "a bundle with this idempotency key is still being submitted".to_string(),
+            ),
+        };
+        let counter = match status {
+            BundleStatus::Duplicate => "duplicate",
+            _ => "conflict",
+        };
+        datapoint_info!(
+            "strategy_gateway",
+            "strategy" => request.strategy,
+            (counter, 1, i64),
+        );
+        let mut update = BundleUpdate {
+            request_id: request.request_id,
+            uuid,
+            message,
+            ..BundleUpdate::default()
+        };
+        update.set_status(status);
+        update
+    }
+
+    async fn send(
+        &self,
+        request: BundleRequest,
+        client: Option<&GatewayClient>,
+        updates: &UpdateSender,
+    ) -> BundleUpdate {
+        let mut update = BundleUpdate {
+            request_id: request.request_id.clone(),
+            ..BundleUpdate::default()
+        };
+        let deadline = UNIX_EPOCH + Duration::from_millis(request.deadline_unix_ms);
+        if request.deadline_unix_ms > 0 && deadline < SystemTime::now() {
+            datapoint_info!(
+                "strategy_gateway",
+                "strategy" => request.strategy,
+                ("expired", 1, i64),
+            );
+            update.set_status(BundleStatus::Expired);
+            update.message = "deadline passed before the bundle could be sent".to_string();
+            return update;
+        }
+        let (transactions, charged_at) = match self.prepare(&request, client) {
+            Ok(prepared) => prepared,
+            Err(refusal) => {
+                datapoint_info!(
+                    "strategy_gateway",
+                    "strategy" => request.strategy,
+                    ("refused", 1, i64),
+                );
+                update.set_status(BundleStatus::Refused);
+                update.message = refusal.message;
+                update.retry_after_ms = refusal
+                    .retry_after
+                    .map_or(0, |retry_after| retry_after.as_millis() as u64);
+                return update;
+            }
+        };
+
+        match send_bundle_no_wait(&transactions, &mut self.client.clone()).await {
+            Ok(response) => {
+                update.uuid = response.into_inner().uuid;
+                update.set_status(BundleStatus::Submitted);
+                let now = Instant::now();
+                let mut pending = self.pending.lock().expect("not poisoned");
+                pending.retain(|_, p| now.duration_since(p.submitted_at) < PENDING_TTL);
+                pending.insert(
+                    update.uuid.clone(),
+                    Pending {
+                        request_id: request.request_id,
+                        updates: updates.clone(),
+                        submitted_at: now,
+                    },
+                );
+                datapoint_info!(
+                    "strategy_gateway",
+                    "strategy" => request.strategy,
+                    ("submitted", 1, i64),
+                    ("tip_lamports", request.tip_lamports, i64),
+                );
+            }
+            Err(status) => {
+                self.refund(&request, client, charged_at);
+                update.set_status(BundleStatus::SendFailed);
+                update.message = status.message().to_string();
+                datapoint_info!(
+                    "strategy_gateway",
+                    "strategy" => request.strategy,
+                    ("send_failed", 1, i64),
+                );
+            }
+        }
+        update
+    }
+
+    /// Checks the bundle, then charges the strategy's budget and the client's quotas and appends
+    /// the tip transaction. Returns when the charges were made, to refund them if the bundle
+    /// isn't sent.
+    fn prepare(
+        &self,
+        request: &BundleRequest,
+        client: Option<&GatewayClient>,
+    ) -> Result<(Vec<Vec<u8>>, Instant), Refusal> {
+        let tip_transactions = usize::from(request.tip_lamports > 0);
+        let num_transactions = request.transactions.len() + tip_transactions;
+        if request.transactions.is_empty() || num_transactions > MAX_BUNDLE_TRANSACTIONS {
+            return Err(format!(
+                "bundle has {num_transactions} transactions with the tip, max is {MAX_BUNDLE_TRANSACTIONS}"
+            )
+            .into());
+        }
+        if let Some(i) = request
+            .transactions
+            .iter()
+            .position(|tx| bincode::deserialize::<VersionedTransaction>(tx).is_err())
+        {
+            return Err(format!("transaction {i} isn't a bincode VersionedTransaction").into());
+        }
+        if request.tip_lamports > self.config.max_tip_lamports {
+            return Err(format!(
+                "tip of {} lamports is over the limit of {}",
+                request.tip_lamports, self.config.max_tip_lamports
+            )
+            .into());
+        }
+        // spread tips over the tip accounts to avoid contending on one write lock
+        let tip_account = match request.tip_lamports {
+            0 => None,
+            _ => Some(
+                self.config
+                    .tip_accounts
+                    .get(
+                        self.next_tip_account.fetch_add(1, Ordering::Relaxed)
+                            % self.config.tip_accounts.len().max(1),
+                    )
+                    .ok_or_else(|| "the gateway has no tip accounts".to_string())?,
+            ),
+        };
+
+        // the client's quotas go last, so a bundle refused for anything else doesn't use them up
+        let now = Instant::now();
+        if let (Some((max_lamports, window)), Some(_)) = (self.config.tip_budget, tip_account) {
+            let mut budgets = self.budgets.lock().expect("not poisoned");
+            let budget = budgets
+                .entry(request.strategy.clone())
+                .or_insert_with(|| TipBudget::new(max_lamports, window));
+            if !budget.try_spend(request.tip_lamports, now) {
+                return Err(format!(
+                    "strategy {} spent its tip budget of {max_lamports} lamports",
+                    request.strategy
+                )
+                .into());
+            }
+        }
+        if let Some(client) = client {
+            if let Err(exceeded) = client.try_send(request.tip_lamports, now) {
+                self.refund(request, None, now);
+                return Err(exceeded.into());
+            }
+        }
+
+        let mut transactions = request.transactions.clone();
+        let Some(tip_account) = tip_account else {
+            return Ok((transactions, now));
+        };
+        let payer = &self.config.payer;
+        let tip_tx = Transaction::new_signed_with_payer(
+            &self
+                .config
+                .tip_split
+                .transfers(&payer.pubkey(), tip_account, request.tip_lamports),
+            Some(&payer.pubkey()),
+            &[payer.as_ref()],
+            *self.blockhash.read().expect("not poisoned"),
+        );
+        transactions
+            .push(bincode::serialize(&VersionedTransaction::from(tip_tx)).expect("serializes"));
+        Ok((transactions, now))
+    }
+
+    /// Gives back what [`Self::prepare`] charged at `charged_at` to the strategy's budget and,
+    /// if given, the client's quotas.
+    fn refund(&self, request: &BundleRequest, client: Option<&GatewayClient>, charged_at: Instant) {
+        if request.tip_lamports > 0 {
+            if let Some(budget) = self
+                .budgets
+                .lock()
+                .expect("not poisoned")
+                .get_mut(&request.strategy)
+            {
+                budget.refund(request.tip_lamports, charged_at);
+            }
+        }
+        if let Some(client) = client {
+            client.refund(request.tip_lamports, charged_at);
+        }
+    }
+
+    fn forward(&self, result: BundleResult) {
+        let (status, message) = match result.result {
+            Some(BundleResultType::Accepted(_)) => (BundleStatus::Accepted, String::new()),
+            Some(BundleResultType::Processed(_)) => (BundleStatus::Processed, String::new()),
+            Some(BundleResultType::Finalized(_)) => (BundleStatus::Finalized, String::new()),
+            Some(BundleResultType::Rejected(rejected)) => {
+                let message = match rejected.reason {
+                    Some(Reason::StateAuctionBidRejected(r)) => r.msg.unwrap_or_default(),
+                    Some(Reason::WinningBatchBidRejected(r)) => r.msg.unwrap_or_default(),
+                    Some(Reason::SimulationFailure(r)) => r.msg.unwrap_or_default(),
+                    Some(Reason::InternalError(r)) => r.msg,
+                    Some(Reason::DroppedBundle(r)) => r.msg,
+                    None => String::new(),
+                };
+                (BundleStatus::Rejected, message)
+            }
+            Some(BundleResultType::Dropped(dropped)) => {
+                (BundleStatus::Dropped, format!("{:?}", dropped.reason()))
+            }
+            None => return,
+        };
+        let mut pending = self.pending.lock().expect("not poisoned");
+        let Some(entry) = pending.get(&result.bundle_id) else {
+            return;
+        };
+        let mut update = BundleUpdate {
+            request_id: entry.request_id.clone(),
+            uuid: result.bundle_id.clone(),
+            message,
+            ..BundleUpdate::default()
+        };
+        update.set_status(status);
+        // a strategy that stops reading its updates misses them rather than stalling the others
+        let _ = entry.updates.try_send(Ok(update));
+        if matches!(
+            status,
+            BundleStatus::Finalized | BundleStatus::Rejected | BundleStatus::Dropped
+        ) {
+            pending.remove(&result.bundle_id);
+        }
+    }
+}
+
+fn priority_class(priority: Priority) -> PriorityClass {
+    match priority {
+        Priority::Bulk => PriorityClass::Bulk,
+        Priority::Normal => PriorityClass::Normal,
+        Priority::Critical => PriorityClass::Critical,
+    }
+}
+
+fn shed(request: BundleRequest, overloaded: Overloaded) -> BundleUpdate {
+    datapoint_info!(
+        "strategy_gateway",
+        "strategy" => request.strategy,
+        ("overloaded", 1, i64),
+        ("priority", overloaded.class.to_string(), String),
+        ("queue_depth", overloaded.depth, i64),
+    );
+    let mut update = BundleUpdate {
+        request_id: request.request_id,
+        message: overloaded.to_string(),
+        retry_after_ms: overloaded.retry_after.as_millis() as u64,
+        ..BundleUpdate::default()
+    };
+    update.set_status(BundleStatus::Overloaded);
+    update
+}
+
+fn over_quota(request: BundleRequest, exceeded: QuotaExceeded) -> BundleUpdate {
+    datapoint_info!(
+        "strategy_gateway",
+        "strategy" => request.strategy,
+        ("refused", 1, i64),
+    );
+    let mut update = BundleUpdate {
+        request_id: request.request_id,
+        message: exceeded.to_string(),
+        retry_after_ms: exceeded
+            .retry_after()
+            .map_or(0, |retry_after| retry_after.as_millis() as u64),
+        ..BundleUpdate::default()
+    };
+    update.set_status(BundleStatus::Refused);
+    update
+}
+
+async fn report_clients(gateway: Weak<Gateway>) {
+    loop {
+        sleep(CLIENT_REPORT_INTERVAL).await;
+        let Some(gateway) = gateway.upgrade() else {
+            return;
+        };
+        for client in gateway
+            .config
+            .clients
+            .iter()
+            .flat_map(GatewayClients::clients)
+        {
+            client.report("strategy_gateway_client");
+        }
+    }
+}
+
+async fn refresh_blockhash(gateway: Weak<Gateway>, rpc_client: RpcClient) {
+    loop {
+        sleep(BLOCKHASH_REFRESH).await;
+        let Some(gateway) = gateway.upgrade() else {
+            return;
+        };
+        match rpc_client.get_latest_blockhash().await {
+            Ok(blockhash) => *gateway.blockhash.write().expect("not poisoned") = blockhash,
+            Err(e) => warn!("failed to refresh the gateway blockhash: {e}"),
+        }
+    }
+}
+
+async fn forward_bundle_results(gateway: Weak<Gateway>) {
+    loop {
+        let Some(mut client) = gateway.upgrade().map(|g| g.client.clone()) else {
+            return;
+        };
+        match client
+            .subscribe_bundle_results(SubscribeBundleResultsRequest {})
+            .await
+        {
+            Ok(response) => {
+                let mut results = response.into_inner();
+                while let Some(result) = results.next().await {
+                    let Some(gateway) = gateway.upgrade() else {
+                        return;
+                    };
+                    match result {
+                        Ok(result) => gateway.forward(result),
+                        Err(status) => {
+                            warn!("gateway bundle results stream failed: {status}");
+                            break;
+                        }
+                    }
+                }
+            }
+            Err(status) => warn!("gateway failed to subscribe to bundle results: {status}"),
+        }
+        sleep(RESUBSCRIBE_DELAY).await;
+    }
+}
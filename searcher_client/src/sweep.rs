@@ -0,0 +1,347 @@
+use std::str::FromStr;
+
+use solana_client::{client_error::ClientError, nonblocking::rpc_client::RpcClient};
+use solana_metrics::datapoint_info;
+use solana_sdk::{
+    instruction::Instruction,
+    packet::PACKET_DATA_SIZE,
+    pubkey::Pubkey,
+    signature::{Keypair, Signature, Signer},
+    system_instruction::transfer,
+    transaction::Transaction,
+};
+use spl_associated_token_account::{
+    get_associated_token_address_with_program_id,
+    instruction::create_associated_token_account_idempotent,
+};
+use spl_token_2022::{extension::StateWithExtensions, state::Account as TokenAccount};
+use thiserror::Error;
+
+use crate::{
+    amount::{AmountParseError, UiTokenAmount},
+    token_fees::{MintTransferInfo, TokenFeeError},
+};
+
+/// Fee of a sweep transaction, signed by the wallet alone, kept back from the SOL swept.
+pub const SWEEP_FEE_LAMPORTS: u64 = 5_000;
+
+#[derive(Debug, Error)]
+pub enum SweepError {
+    #[error("rpc error: {0}")]
+    Rpc(#[from] ClientError),
+    #[error(transparent)]
+    TokenFee(#[from] TokenFeeError),
+    #[error("float of mint {mint}: {error}")]
+    Amount {
+        mint: Pubkey,
+        error: AmountParseError,
+    },
+}
+
+/// A token to sweep and how much of it each wallet keeps, in UI units.
+#[derive(Clone, Copy, Debug)]
+pub struct TokenFloat {
+    pub mint: Pubkey,
+    pub float: UiTokenAmount,
+}
+
+impl FromStr for TokenFloat {
+    type Err = String;
+
+    /// Parses `MINT:AMOUNT`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (mint, float) = s
+            .split_once(':')
+            .ok_or_else(|| format!("expected MINT:AMOUNT, got {s:?}"))?;
+        Ok(Self {
+            mint: Pubkey::from_str(mint.trim())
+                .map_err(|e| format!("invalid mint {mint:?}: {e}"))?,
+            float: float
+                .parse()
+                .map_err(|e| format!("invalid amount {float:?}: {e}"))?,
+        })
+    }
+}
+
+/// How much hot wallets keep and where the rest goes.
+#[derive(Clone, Debug)]
+pub struct SweepConfig {
+    pub treasury: Pubkey,
+    /// Lamports each wallet keeps for fees and tips.
+    pub float_lamports: u64,
+    /// Smallest SOL sweep worth sending, so wallets hovering at their float aren't swept for dust.
+    pub min_sweep_lamports: u64,
+    pub tokens: Vec<SweptToken>,
+}
+
+/// A token to sweep, resolved against its mint.
+#[derive(Clone, Debug)]
+pub struct SweptToken {
+    pub mint: MintTransferInfo,
+    /// Base units each wallet keeps.
+    pub float: u64,
+}
+
+impl SweptToken {
+    pub async fn fetch(rpc_client: &RpcClient, float: &TokenFloat) -> Result<Self, SweepError> {
+        let mint = MintTransferInfo::fetch(rpc_client, float.mint).await?;
+        mint.check_transfer_hook(&[])?;
+        let float =
+            float
+                .float
+                .to_base_units(mint.decimals)
+                .map_err(|error| SweepError::Amount {
+                    mint: mint.mint,
+                    error,
+                })?;
+        Ok(Self { mint, float })
+    }
+}
+
+/// An amount a wallet holds above its float, in lamports or the token's base units.
+#[derive(Clone, Copy, Debug)]
+pub struct SweepTransfer {
+    /// None for SOL.
+    pub mint: Option<Pubkey>,
+    pub balance: u64,
+    pub amount: u64,
+}
+
+/// What a sweep moves out of a wallet. Sweeps of many tokens take several transactions, the SOL
+/// transfer comes last.
+#[derive(Clone, Debug)]
+pub struct SweepPlan {
+    pub wallet: Pubkey,
+    pub transfers: Vec<SweepTransfer>,
+    /// Paid by the wallet for the treasury token accounts the sweep creates.
+    pub rent_lamports: u64,
+    /// The instructions of each transaction, each fitting in a packet.
+    pub transactions: Vec<Vec<Instruction>>,
+}
+
+impl SweepPlan {
+    /// Lamports swept, 0 if the wallet's SOL is at or near its float.
+    pub fn lamports(&self) -> u64 {
+        self.transfers
+            .iter()
+            .filter(|transfer| transfer.mint.is_none())
+            .map(|transfer| transfer.amount)
+            .sum()
+    }
+}
+
+/// Plans sweeping `wallet` into the treasury: every listed token above its float into the
+/// treasury's associated token account, created if missing, then SOL above the float less the
+/// fees and that rent. Wallets without a token account for a mint are skipped for it. Tokens are
+/// packed into as few transactions as fit in a packet, each token's instructions staying in one.
+pub async fn plan_sweep(
+    rpc_client: &RpcClient,
+    wallet: &Pubkey,
+    config: &SweepConfig,
+) -> Result<SweepPlan, SweepError> {
+    let token_accounts: Vec<(Pubkey, Pubkey)> = config
+        .tokens
+        .iter()
+        .map(|token| {
+            let address = |owner| {
+                get_associated_token_address_with_program_id(
+                    owner,
+                    &token.mint.mint,
+                    &token.mint.token_program,
+                )
+            };
+            (address(wallet), address(&config.treasury))
+        })
+        .collect();
+    let mut addresses = vec![*wallet];
+    addresses.extend(
+        token_accounts
+            .iter()
+            .flat_map(|(source, destination)| [*source, *destination]),
+    );
+    let accounts = rpc_client.get_multiple_accounts(&addresses).await?;
+    let epoch = match config.tokens.is_empty() {
+        true => 0,
+        false => rpc_client.get_epoch_info().await?.epoch,
+    };
+
+    let mut plan = SweepPlan {
+        wallet: *wallet,
+        transfers: vec![],
+        rent_lamports: 0,
+        transactions: vec![],
+    };
+    for (i, (token, (source, destination))) in config.tokens.iter().zip(&token_accounts).enumerate()
+    {
+        let Some(source_account) = &accounts[1 + 2 * i] else {
+            continue;
+        };
+        let Ok(state) = StateWithExtensions::<TokenAccount>::unpack(&source_account.data) else {
+            continue;
+        };
+        let balance = state.base.amount;
+        let amount = balance.saturating_sub(token.float);
+        if amount == 0 {
+            continue;
+        }
+        let mut instructions = vec![];
+        if accounts[2 + 2 * i].is_none() {
+            // the treasury's account needs the same extensions as the wallet's
+            plan.rent_lamports += rpc_client
+                .get_minimum_balance_for_rent_exemption(source_account.data.len())
+                .await?;
+            instructions.push(create_associated_token_account_idempotent(
+                wallet,
+                &config.treasury,
+                &token.mint.mint,
+                &token.mint.token_program,
+            ));
+        }
+        instructions.push(token.mint.transfer_instruction(
+            epoch,
+            source,
+            destination,
+            wallet,
+            amount,
+        )?);
+        pack_instructions(wallet, &mut plan.transactions, instructions);
+        plan.transfers.push(SweepTransfer {
+            mint: Some(token.mint.mint),
+            balance,
+            amount,
+        });
+    }
+
+    // the transfer's size doesn't depend on the amount, so where it goes and the fees are known
+    // before the amount is
+    let mut with_sol = plan.transactions.clone();
+    pack_instructions(
+        wallet,
+        &mut with_sol,
+        vec![transfer(wallet, &config.treasury, 0)],
+    );
+    let fees = SWEEP_FEE_LAMPORTS * with_sol.len() as u64;
+    let balance = accounts[0].as_ref().map_or(0, |account| account.lamports);
+    let lamports = balance.saturating_sub(
+        config
+            .float_lamports
+            .saturating_add(fees)
+            .saturating_add(plan.rent_lamports),
+    );
+    if lamports > 0 && lamports >= config.min_sweep_lamports {
+        plan.transactions = with_sol;
+        let last = plan
+            .transactions
+            .last_mut()
+            .expect("holds the SOL transfer");
+        *last.last_mut().expect("holds the SOL transfer") =
+            transfer(wallet, &config.treasury, lamports);
+        plan.transfers.push(SweepTransfer {
+            mint: None,
+            balance,
+            amount: lamports,
+        });
+    }
+    Ok(plan)
+}
+
+/// Appends `instructions` to the last transaction if they fit in a packet with it, otherwise
+/// starts a new transaction with them.
+fn pack_instructions(
+    wallet: &Pubkey,
+    transactions: &mut Vec<Vec<Instruction>>,
+    instructions: Vec<Instruction>,
+) {
+    if let Some(last) = transactions.last_mut() {
+        let mut candidate = last.clone();
+        candidate.extend(instructions.iter().cloned());
+        if fits_in_packet(wallet, &candidate) {
+            *last = candidate;
+            return;
+        }
+    }
+    transactions.push(instructions);
+}
+
+fn fits_in_packet(wallet: &Pubkey, instructions: &[Instruction]) -> bool {
+    let tx = Transaction::new_with_payer(instructions, Some(wallet));
+    bincode::serialized_size(&tx).is_ok_and(|size| size as usize <= PACKET_DATA_SIZE)
+}
+
+/// Sends a planned sweep's transactions in order, signed and paid for by the wallet, and reports
+/// it as a `sweep` metric once all of them landed.
+pub async fn execute_sweep(
+    rpc_client: &RpcClient,
+    wallet: &Keypair,
+    plan: &SweepPlan,
+) -> Result<Vec<Signature>, SweepError> {
+    let mut signatures = Vec::with_capacity(plan.transactions.len());
+    for instructions in &plan.transactions {
+        let blockhash = rpc_client.get_latest_blockhash().await?;
+        let tx = Transaction::new_signed_with_payer(
+            instructions,
+            Some(&wallet.pubkey()),
+            &[wallet],
+            blockhash,
+        );
+        signatures.push(rpc_client.send_and_confirm_transaction(&tx).await?);
+    }
+    datapoint_info!(
+        "sweep",
+        "wallet" => plan.wallet.to_string(),
+        ("lamports", plan.lamports(), i64),
+        (
+            "token_transfers",
+            plan.transfers.iter().filter(|t| t.mint.is_some()).count(),
+            i64
+        ),
+    );
+    Ok(signatures)
+}
+
+#[cfg(test)]
+mod tests {
+    use solana_sdk::{instruction::Instruction, pubkey::Pubkey};
+    use spl_associated_token_account::instruction::create_associated_token_account_idempotent;
+
+    use crate::sweep::{fits_in_packet, pack_instructions};
+
+    #[test]
+    fn test_pack_instructions() {
+        let wallet = Pubkey::new_unique();
+        let treasury = Pubkey::new_unique();
+        // an account creation and a transfer per token, too many for one packet
+        let tokens: Vec<Vec<Instruction>> = (0..12)
+            .map(|_| {
+                let mint = Pubkey::new_unique();
+                let create = create_associated_token_account_idempotent(
+                    &wallet,
+                    &treasury,
+                    &mint,
+                    &spl_token::id(),
+                );
+                let transfer = spl_token::instruction::transfer(
+                    &spl_token::id(),
+                    &Pubkey::new_unique(),
+                    &Pubkey::new_unique(),
+                    &wallet,
+                    &[],
+                    1,
+                )
+                .unwrap();
+                vec![create, transfer]
+            })
+            .collect();
+        assert!(!fits_in_packet(&wallet, &tokens.concat()));
+
+        let mut transactions = vec![];
+        for instructions in tokens.clone() {
+            pack_instructions(&wallet, &mut transactions, instructions);
+        }
+        assert!(transactions.len() > 1);
+        assert!(transactions.iter().all(|tx| fits_in_packet(&wallet, tx)));
+        // in order, and no token's instructions split across transactions
+        assert_eq!(transactions.concat(), tokens.concat());
+        assert!(transactions.iter().all(|tx| tx.len() % 2 == 0));
+    }
+}
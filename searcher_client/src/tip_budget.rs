@@ -0,0 +1,88 @@
+use std::{
+    collections::VecDeque,
+    time::{Duration, Instant},
+};
+
+use solana_metrics::datapoint_info;
+
+/// Caps the lamports tipped within a sliding window, so one strategy can't spend more than its
+/// share of the payer's balance.
+#[derive(Debug)]
+pub struct TipBudget {
+    max_lamports: u64,
+    window: Duration,
+    spent: VecDeque<(Instant, u64)>,
+    spent_in_window: u64,
+    rejected: u64,
+}
+
+impl TipBudget {
+    pub fn new(max_lamports: u64, window: Duration) -> Self {
+        Self {
+            max_lamports,
+            window,
+            spent: VecDeque::new(),
+            spent_in_window: 0,
+            rejected: 0,
+        }
+    }
+
+    /// Records the tip and returns true if it fits in what's left of the budget.
+    pub fn try_spend(&mut self, lamports: u64, now: Instant) -> bool {
+        self.expire(now);
+        if self.spent_in_window.saturating_add(lamports) > self.max_lamports {
+            self.rejected += 1;
+            return false;
+        }
+        self.spent.push_back((now, lamports));
+        self.spent_in_window += lamports;
+        true
+    }
+
+    /// Gives back a tip spent at `spent_at` that was never sent.
+    pub fn refund(&mut self, lamports: u64, spent_at: Instant) {
+        if let Some(i) = self
+            .spent
+            .iter()
+            .rposition(|&spent| spent == (spent_at, lamports))
+        {
+            self.spent.remove(i);
+            self.spent_in_window -= lamports;
+        }
+    }
+
+    pub fn max_lamports(&self) -> u64 {
+        self.max_lamports
+    }
+
+    pub fn window(&self) -> Duration {
+        self.window
+    }
+
+    pub fn remaining(&mut self, now: Instant) -> u64 {
+        self.expire(now);
+        self.max_lamports.saturating_sub(self.spent_in_window)
+    }
+
+    fn expire(&mut self, now: Instant) {
+        while let Some((at, lamports)) = self.spent.front() {
+            if now.duration_since(*at) < self.window {
+                break;
+            }
+            self.spent_in_window -= lamports;
+            self.spent.pop_front();
+        }
+    }
+
+    pub fn report(&mut self, name: &'static str, pipeline: &str) {
+        let remaining = self.remaining(Instant::now());
+        datapoint_info!(
+            name,
+            "pipeline" => pipeline,
+            ("spent_lamports", self.spent_in_window, i64),
+            ("remaining_lamports", remaining, i64),
+            ("rejected", self.rejected, i64),
+        );
+        self.rejected = 0;
+    }
+}
@@ -0,0 +1,86 @@
+use std::collections::HashMap;
+
+use solana_metrics::datapoint_info;
+use solana_sdk::{clock::Slot, pubkey::Pubkey};
+
+/// What changed across the tip accounts between two balance snapshots.
+#[derive(Debug, Default)]
+pub struct TipSample {
+    pub slots_elapsed: u64,
+    /// Lamports tipped into the accounts since the last snapshot.
+    pub inflow_lamports: u64,
+    /// Accounts whose balance dropped, i.e. had their tips paid out to the tip distribution
+    /// accounts.
+    pub payouts: Vec<(Pubkey, u64)>,
+}
+
+impl TipSample {
+    pub fn lamports_per_slot(&self) -> f64 {
+        if self.slots_elapsed == 0 {
+            return 0.0;
+        }
+        self.inflow_lamports as f64 / self.slots_elapsed as f64
+    }
+}
+
+/// Tracks the tip account balances over time to estimate tip flow across the whole market.
+#[derive(Debug, Default)]
+pub struct TipAccountMonitor {
+    last_slot: Option<Slot>,
+    balances: HashMap<Pubkey, u64>,
+    pub total_inflow_lamports: u64,
+    pub total_slots: u64,
+    pub total_payouts: u64,
+}
+
+impl TipAccountMonitor {
+    /// Records a snapshot of tip account balances taken at `slot`.
+    /// Returns None for the first snapshot or if the slot didn't advance.
+    pub fn observe(&mut self, slot: Slot, balances: &[(Pubkey, u64)]) -> Option<TipSample> {
+        let sample = match self.last_slot {
+            Some(last_slot) if slot > last_slot => {
+                let mut sample = TipSample {
+                    slots_elapsed: slot - last_slot,
+                    ..TipSample::default()
+                };
+                for (pubkey, balance) in balances {
+                    let Some(previous) = self.balances.get(pubkey) else {
+                        continue;
+                    };
+                    if balance >= previous {
+                        sample.inflow_lamports += balance - previous;
+                    } else {
+                        sample.payouts.push((*pubkey, previous - balance));
+                    }
+                }
+                Some(sample)
+            }
+            Some(_) => return None,
+            None => None,
+        };
+
+        self.last_slot = Some(slot);
+        self.balances = balances.iter().cloned().collect();
+        if let Some(sample) = &sample {
+            self.total_inflow_lamports += sample.inflow_lamports;
+            self.total_slots += sample.slots_elapsed;
+            self.total_payouts += sample.payouts.len() as u64;
+            datapoint_info!(
+                "tip_account_monitor",
+                ("slot", slot, i64),
+                ("slots_elapsed", sample.slots_elapsed, i64),
+                ("inflow_lamports", sample.inflow_lamports, i64),
+                ("payouts", sample.payouts.len(), i64),
+                ("total_balance", self.balances.values().sum::<u64>(), i64),
+            );
+        }
+        sample
+    }
+
+    pub fn average_lamports_per_slot(&self) -> f64 {
+        if self.total_slots == 0 {
+            return 0.0;
+        }
+        self.total_inflow_lamports as f64 / self.total_slots as f64
+    }
+}
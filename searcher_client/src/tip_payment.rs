@@ -0,0 +1,430 @@
+use std::{str::FromStr, sync::Arc};
+
+use solana_sdk::{
+    instruction::{AccountMeta, Instruction},
+    pubkey,
+    pubkey::Pubkey,
+    signature::Keypair,
+    system_instruction::transfer,
+    system_program,
+};
+use thiserror::Error;
+
+/// Mainnet tip payment program.
+/// See: https://jito-foundation.gitbook.io/mev/mev-payment-and-distribution/on-chain-addresses
+pub const TIP_PAYMENT_PROGRAM_ID: Pubkey = pubkey!("T1pyyaTNZsKv2WcRAB8oVnk93mLJw2XzjtVYqCsaHqt");
+
+const TIP_ACCOUNT_SEEDS: [&[u8]; 8] = [
+    b"TIP_ACCOUNT_0",
+    b"TIP_ACCOUNT_1",
+    b"TIP_ACCOUNT_2",
+    b"TIP_ACCOUNT_3",
+    b"TIP_ACCOUNT_4",
+    b"TIP_ACCOUNT_5",
+    b"TIP_ACCOUNT_6",
+    b"TIP_ACCOUNT_7",
+];
+
+/// The eight tip account PDAs owned by the tip payment program.
+pub fn tip_accounts(tip_program_id: &Pubkey) -> Vec<Pubkey> {
+    TIP_ACCOUNT_SEEDS
+        .iter()
+        .map(|seed| Pubkey::find_program_address(&[seed], tip_program_id).0)
+        .collect()
+}
+
+/// Where a bundle pays its tip.
+///
+/// The tip payment program has no tip instruction, a tip is any lamport transfer into one of its
+/// tip accounts. Strategies that want the tip asserted inside their own transaction route it
+/// through their program, which pays it with a CPI to the system program.
+#[derive(Clone)]
+pub enum TipPlacement {
+    /// A separate transaction at the end of the bundle, paid by the given keypair.
+    SeparateTransaction(Arc<Keypair>),
+    /// A system transfer appended to the last transaction, paid by its payer.
+    LastTransaction,
+    /// An instruction to a strategy program that pays the tip via CPI, appended to the last
+    /// transaction. The tipper, tip account and system program are appended to `accounts`. The
+    /// tip is whatever `data` tells the program to pay, so it can't be split into tip shares.
+    Cpi {
+        program_id: Pubkey,
+        data: Vec<u8>,
+        accounts: Vec<AccountMeta>,
+    },
+}
+
+#[derive(Clone)]
+pub struct TipConfig {
+    pub tip_account: Pubkey,
+    /// The whole tip, including what `split` pays to other accounts. Not used by a CPI tip,
+    /// which pays what its instruction data says.
+    pub lamports: u64,
+    pub placement: TipPlacement,
+    pub split: TipSplit,
+}
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum TipSplitError {
+    #[error("invalid tip share {0:?}, expected PUBKEY:PERCENT")]
+    Invalid(String),
+    #[error(
+        "invalid tip share percentage {0:?}, expected up to 100 with at most 2 decimal places"
+    )]
+    Percent(String),
+    #[error("tip shares add up to {basis_points} basis points, leaving nothing for the Jito tip")]
+    TooLarge { basis_points: u32 },
+    #[error(
+        "a {lamports} lamport tip leaves {jito_lamports} for the Jito tip account after shares, \
+         below the {min_tip_lamports} lamport floor"
+    )]
+    BelowFloor {
+        lamports: u64,
+        jito_lamports: u64,
+        min_tip_lamports: u64,
+    },
+    #[error("a CPI tip pays what its instruction data says, it can't be split into tip shares")]
+    CpiShares,
+}
+
+/// A percentage of each tip paid to an account other than the Jito tip account, such as a team
+/// treasury. Parsed from `PUBKEY:PERCENT`, e.g. `Treasury1111...:2.5` or `Treasury1111...:10%`,
+/// with at most two decimal places.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TipShare {
+    pub recipient: Pubkey,
+    /// Hundredths of a percent of the tip.
+    pub basis_points: u16,
+}
+
+impl FromStr for TipShare {
+    type Err = TipSplitError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || TipSplitError::Invalid(s.to_string());
+        let (recipient, percent) = s.split_once(':').ok_or_else(invalid)?;
+        let recipient = Pubkey::from_str(recipient.trim()).map_err(|_| invalid())?;
+        let percent = percent.trim();
+        let percent = percent.strip_suffix('%').unwrap_or(percent);
+        let basis_points = parse_basis_points(percent)
+            .ok_or_else(|| TipSplitError::Percent(percent.to_string()))?;
+        Ok(Self {
+            recipient,
+            basis_points,
+        })
+    }
+}
+
+/// Parses a percentage of 100 or less with at most two decimal places, such as `2`, `2.5` or
+/// `.25`, into basis points.
+fn parse_basis_points(percent: &str) -> Option<u16> {
+    let (whole, fraction) = percent.split_once('.').unwrap_or((percent, ""));
+    let is_digits = |s: &str| s.bytes().all(|b| b.is_ascii_digit());
+    if (whole.is_empty() && fraction.is_empty())
+        || fraction.len() > 2
+        || !is_digits(whole)
+        || !is_digits(fraction)
+    {
+        return None;
+    }
+    let whole: u16 = match whole {
+        "" => 0,
+        whole => whole.parse().ok()?,
+    };
+    let fraction: u16 = format!("{fraction:0<2}").parse().ok()?;
+    let basis_points = whole.checked_mul(100)?.checked_add(fraction)?;
+    (basis_points <= 10_000).then_some(basis_points)
+}
+
+/// Splits a tip between the Jito tip account and [`TipShare`]s. The shares are rounded down and
+/// the Jito tip account gets the rest. A tip too small to pay the shares and still leave
+/// `min_tip_lamports` for the Jito tip account goes to the Jito tip account whole, so shares
+/// never cost a bundle its auction.
+#[derive(Clone, Debug, Default)]
+pub struct TipSplit {
+    shares: Vec<TipShare>,
+    min_tip_lamports: u64,
+}
+
+impl TipSplit {
+    /// Fails if the shares leave nothing for the Jito tip account.
+    pub fn new(shares: Vec<TipShare>, min_tip_lamports: u64) -> Result<Self, TipSplitError> {
+        let basis_points = shares
+            .iter()
+            .map(|share| share.basis_points as u32)
+            .sum::<u32>();
+        if basis_points >= 10_000 {
+            return Err(TipSplitError::TooLarge { basis_points });
+        }
+        Ok(Self {
+            shares,
+            min_tip_lamports,
+        })
+    }
+
+    pub fn shares(&self) -> &[TipShare] {
+        &self.shares
+    }
+
+    /// What each share gets out of `lamports`, and the rest, for the Jito tip account.
+    fn shares_of(&self, lamports: u64) -> (u64, Vec<(Pubkey, u64)>) {
+        let shares: Vec<(Pubkey, u64)> = self
+            .shares
+            .iter()
+            .map(|share| {
+                let share_lamports = lamports as u128 * share.basis_points as u128 / 10_000;
+                (share.recipient, share_lamports as u64)
+            })
+            .filter(|(_, share_lamports)| *share_lamports > 0)
+            .collect();
+        let jito_lamports = lamports - shares.iter().map(|(_, l)| l).sum::<u64>();
+        (jito_lamports, shares)
+    }
+
+    /// The Jito tip account's part of `lamports` and the transfers to each share. Everything
+    /// goes to the Jito tip account if the shares would leave it below the floor.
+    pub fn split(&self, lamports: u64) -> (u64, Vec<(Pubkey, u64)>) {
+        let (jito_lamports, shares) = self.shares_of(lamports);
+        if jito_lamports < self.min_tip_lamports {
+            return (lamports, Vec::new());
+        }
+        (jito_lamports, shares)
+    }
+
+    /// Fails if `lamports` is too small to pay the shares and leave the floor for the Jito tip
+    /// account, which [`TipSplit::split`] would handle by skipping the shares.
+    pub fn check_floor(&self, lamports: u64) -> Result<(), TipSplitError> {
+        let (jito_lamports, _) = self.shares_of(lamports);
+        if jito_lamports < self.min_tip_lamports {
+            return Err(TipSplitError::BelowFloor {
+                lamports,
+                jito_lamports,
+                min_tip_lamports: self.min_tip_lamports,
+            });
+        }
+        Ok(())
+    }
+
+    /// Transfers paying `lamports` from `tipper`: the Jito tip account's part first, then each
+    /// share.
+    pub fn transfers(
+        &self,
+        tipper: &Pubkey,
+        tip_account: &Pubkey,
+        lamports: u64,
+    ) -> Vec<Instruction> {
+        let (jito_lamports, shares) = self.split(lamports);
+        std::iter::once(transfer(tipper, tip_account, jito_lamports))
+            .chain(
+                shares.into_iter().map(|(recipient, share_lamports)| {
+                    transfer(tipper, &recipient, share_lamports)
+                }),
+            )
+            .collect()
+    }
+}
+
+/// Accounts a program needs to transfer a tip from `tipper` via CPI: the tipper as a writable
+/// signer, the writable tip account and the system program.
+pub fn tip_cpi_account_metas(tipper: &Pubkey, tip_account: &Pubkey) -> Vec<AccountMeta> {
+    vec![
+        AccountMeta::new(*tipper, true),
+        AccountMeta::new(*tip_account, false),
+        AccountMeta::new_readonly(system_program::id(), false),
+    ]
+}
+
+impl TipConfig {
+    /// The instruction that pays the Jito tip account's part of the tip from `tipper`. A CPI
+    /// tip pays whatever its instruction data says.
+    pub fn instruction(&self, tipper: &Pubkey) -> Instruction {
+        match &self.placement {
+            TipPlacement::SeparateTransaction(_) | TipPlacement::LastTransaction => {
+                transfer(tipper, &self.tip_account, self.split.split(self.lamports).0)
+            }
+            TipPlacement::Cpi {
+                program_id,
+                data,
+                accounts,
+            } => {
+                let mut accounts = accounts.clone();
+                accounts.extend(tip_cpi_account_metas(tipper, &self.tip_account));
+                Instruction::new_with_bytes(*program_id, data, accounts)
+            }
+        }
+    }
+
+    /// Fails if a CPI tip is given tip shares, which would be paid on top of the whole tip.
+    pub fn validate(&self) -> Result<(), TipSplitError> {
+        match self.placement {
+            TipPlacement::Cpi { .. } if !self.split.shares().is_empty() => {
+                Err(TipSplitError::CpiShares)
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// The tip instruction followed by transfers to the tip shares. A CPI tip is never split,
+    /// see [`TipConfig::validate`].
+    pub fn instructions(&self, tipper: &Pubkey) -> Vec<Instruction> {
+        let shares = match self.placement {
+            TipPlacement::Cpi { .. } => Vec::new(),
+            _ => self.split.split(self.lamports).1,
+        };
+        std::iter::once(self.instruction(tipper))
+            .chain(
+                shares.into_iter().map(|(recipient, share_lamports)| {
+                    transfer(tipper, &recipient, share_lamports)
+                }),
+            )
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use solana_sdk::pubkey::Pubkey;
+
+    use crate::tip_payment::{TipConfig, TipPlacement, TipShare, TipSplit, TipSplitError};
+
+    fn share(basis_points: u16) -> TipShare {
+        TipShare {
+            recipient: Pubkey::new_unique(),
+            basis_points,
+        }
+    }
+
+    #[test]
+    fn test_parse_tip_share() {
+        let recipient = Pubkey::new_unique();
+        for (percent, basis_points) in [
+            ("10", 1_000),
+            ("10%", 1_000),
+            (" 2.5 ", 250),
+            ("2.50%", 250),
+            (".25", 25),
+            ("0.01", 1),
+            ("100", 10_000),
+            ("100.00", 10_000),
+        ] {
+            assert_eq!(
+                TipShare::from_str(&format!("{recipient}:{percent}")),
+                Ok(TipShare {
+                    recipient,
+                    basis_points
+                }),
+                "{percent}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_parse_tip_share_invalid() {
+        let recipient = Pubkey::new_unique();
+        for percent in [
+            "", "%", ".", "1k", "1k%", "1_0", "-1", "+1", "0.001", "2.555", "100.01", "101", "1e2",
+            "70000",
+        ] {
+            assert_eq!(
+                TipShare::from_str(&format!("{recipient}:{percent}")),
+                Err(TipSplitError::Percent(
+                    percent.strip_suffix('%').unwrap_or(percent).to_string()
+                )),
+                "{percent}"
+            );
+        }
+        for s in ["10", "not a pubkey:10"] {
+            assert_eq!(
+                TipShare::from_str(s),
+                Err(TipSplitError::Invalid(s.to_string()))
+            );
+        }
+    }
+
+    #[test]
+    fn test_split_rounding() {
+        let (treasury, team) = (share(250), share(3_333));
+        let split = TipSplit::new(vec![treasury, team], 0).unwrap();
+
+        // shares are rounded down and the Jito tip account gets the rest
+        assert_eq!(
+            split.split(10_001),
+            (
+                6_418,
+                vec![(treasury.recipient, 250), (team.recipient, 3_333)]
+            )
+        );
+        assert_eq!(
+            split.split(999),
+            (643, vec![(treasury.recipient, 24), (team.recipient, 332)])
+        );
+        // shares rounded down to nothing get no transfer
+        assert_eq!(split.split(3), (3, vec![]));
+        // no overflow however large the tip
+        let (jito_lamports, shares) = split.split(u64::MAX);
+        assert_eq!(
+            jito_lamports + shares.iter().map(|(_, lamports)| lamports).sum::<u64>(),
+            u64::MAX
+        );
+    }
+
+    #[test]
+    fn test_split_floor() {
+        let treasury = share(1_000);
+        let split = TipSplit::new(vec![treasury], 1_000).unwrap();
+
+        assert_eq!(split.split(2_000), (1_800, vec![(treasury.recipient, 200)]));
+        assert!(split.check_floor(2_000).is_ok());
+        // paying the share would leave the Jito tip account below the floor
+        assert_eq!(split.split(1_100), (1_100, vec![]));
+        assert_eq!(
+            split.check_floor(1_100),
+            Err(TipSplitError::BelowFloor {
+                lamports: 1_100,
+                jito_lamports: 990,
+                min_tip_lamports: 1_000
+            })
+        );
+    }
+
+    #[test]
+    fn test_split_too_large() {
+        assert_eq!(
+            TipSplit::new(vec![share(5_000), share(5_000)], 0).unwrap_err(),
+            TipSplitError::TooLarge {
+                basis_points: 10_000
+            }
+        );
+        assert!(TipSplit::new(vec![share(5_000), share(4_999)], 0).is_ok());
+    }
+
+    #[test]
+    fn test_cpi_tip_shares() {
+        let program_id = Pubkey::new_unique();
+        let tipper = Pubkey::new_unique();
+        let mut tip = TipConfig {
+            tip_account: Pubkey::new_unique(),
+            lamports: 10_000,
+            placement: TipPlacement::Cpi {
+                program_id,
+                data: vec![1, 2, 3],
+                accounts: vec![],
+            },
+            split: TipSplit::new(vec![share(1_000)], 0).unwrap(),
+        };
+        assert_eq!(tip.validate(), Err(TipSplitError::CpiShares));
+        // the CPI pays the whole tip, nothing is paid on top of it
+        let instructions = tip.instructions(&tipper);
+        assert_eq!(instructions.len(), 1);
+        assert_eq!(instructions[0].program_id, program_id);
+
+        tip.split = TipSplit::default();
+        assert_eq!(tip.validate(), Ok(()));
+        tip.placement = TipPlacement::LastTransaction;
+        tip.split = TipSplit::new(vec![share(1_000)], 0).unwrap();
+        assert_eq!(tip.validate(), Ok(()));
+        assert_eq!(tip.instructions(&tipper).len(), 2);
+    }
+}
@@ -13,17 +13,18 @@ use solana_sdk::signature::{Keypair, Signer};
 use tokio::{task::JoinHandle, time::sleep};
 use tonic::{service::Interceptor, transport::Channel, Request, Status};
 
-use crate::BlockEngineConnectionResult;
+use crate::{client_headers::ClientHeaders, grpc_trace::traced, BlockEngineConnectionResult};
 
 const AUTHORIZATION_HEADER: &str = "authorization";
 const BEARER: &str = "Bearer ";
 
-/// Adds the token to each requests' authorization header.
+/// Adds the token to each requests' authorization header, along with the client headers.
 /// Manages refreshing the token in a separate thread.
 #[derive(Clone)]
 pub struct ClientInterceptor {
     /// The token added to each request header.
     bearer_token: Arc<RwLock<String>>,
+    headers: Arc<ClientHeaders>,
 }
 
 impl ClientInterceptor {
@@ -46,7 +47,15 @@ impl ClientInterceptor {
             role,
         );
 
-        Ok(Self { bearer_token })
+        Ok(Self {
+            bearer_token,
+            headers: Arc::default(),
+        })
+    }
+
+    pub fn with_headers(mut self, headers: ClientHeaders) -> Self {
+        self.headers = Arc::new(headers);
+        self
     }
 
     async fn auth(
@@ -54,24 +63,30 @@ impl ClientInterceptor {
         keypair: &Keypair,
         role: Role,
     ) -> BlockEngineConnectionResult<(Token, Token)> {
-        let challenge_resp = auth_service_client
-            .generate_auth_challenge(GenerateAuthChallengeRequest {
+        let challenge_resp = traced(
+            "GenerateAuthChallenge",
+            GenerateAuthChallengeRequest {
                 role: role as i32,
                 pubkey: keypair.pubkey().as_ref().to_vec(),
-            })
-            .await?
-            .into_inner();
+            },
+            |request| auth_service_client.generate_auth_challenge(request),
+        )
+        .await?
+        .into_inner();
         let challenge = format!("{}-{}", keypair.pubkey(), challenge_resp.challenge);
         let signed_challenge = keypair.sign_message(challenge.as_bytes()).as_ref().to_vec();
 
-        let tokens = auth_service_client
-            .generate_auth_tokens(GenerateAuthTokensRequest {
+        let tokens = traced(
+            "GenerateAuthTokens",
+            GenerateAuthTokensRequest {
                 challenge,
                 client_pubkey: keypair.pubkey().as_ref().to_vec(),
                 signed_challenge,
-            })
-            .await?
-            .into_inner();
+            },
+            |request| auth_service_client.generate_auth_tokens(request),
+        )
+        .await?
+        .into_inner();
 
         Ok((tokens.access_token.unwrap(), tokens.refresh_token.unwrap()))
     }
@@ -89,6 +104,11 @@ impl ClientInterceptor {
             let mut access_token_expiration = access_token_expiration;
 
             loop {
+                // every client using this token was dropped, e.g. after failing over to
+                // another keypair
+                if Arc::strong_count(&bearer_token) == 1 {
+                    return Ok(());
+                }
                 let access_token_ttl = SystemTime::try_from(access_token_expiration.clone())
                     .unwrap()
                     .duration_since(SystemTime::now())
@@ -126,11 +146,14 @@ impl ClientInterceptor {
                     // re-up the access token if it expires soon
                     (_, true) => {
                         let is_error = {
-                            if let Ok(refresh_resp) = auth_service_client
-                                .refresh_access_token(RefreshAccessTokenRequest {
+                            if let Ok(refresh_resp) = traced(
+                                "RefreshAccessToken",
+                                RefreshAccessTokenRequest {
                                     refresh_token: refresh_token.value.clone(),
-                                })
-                                .await
+                                },
+                                |request| auth_service_client.refresh_access_token(request),
+                            )
+                            .await
                             {
                                 let access_token = refresh_resp.into_inner().access_token.unwrap();
                                 *bearer_token.write().unwrap() = access_token.value.clone();
@@ -154,6 +177,7 @@ impl ClientInterceptor {
 
 impl Interceptor for ClientInterceptor {
     fn call(&mut self, mut request: Request<()>) -> Result<Request<()>, Status> {
+        self.headers.apply(request.metadata_mut());
         let l_token = self.bearer_token.read().unwrap();
         if !l_token.is_empty() {
             request.metadata_mut().insert(
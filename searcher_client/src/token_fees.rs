@@ -0,0 +1,150 @@
+use solana_client::{client_error::ClientError, nonblocking::rpc_client::RpcClient};
+use solana_sdk::{
+    clock::Epoch, instruction::Instruction, program_error::ProgramError, pubkey::Pubkey,
+};
+use spl_token_2022::{
+    extension::{
+        transfer_fee::{instruction::transfer_checked_with_fee, TransferFeeConfig},
+        transfer_hook, BaseStateWithExtensions, StateWithExtensions,
+    },
+    instruction::transfer_checked,
+    state::Mint,
+};
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum TokenFeeError {
+    #[error("rpc error: {0}")]
+    Rpc(#[from] ClientError),
+    #[error("mint {0} doesn't exist")]
+    MintNotFound(Pubkey),
+    #[error("account {0} isn't a token mint: {1}")]
+    InvalidMint(Pubkey, ProgramError),
+    #[error("mint {mint} has transfer hook program {program}, which isn't supported")]
+    UnsupportedTransferHook { mint: Pubkey, program: Pubkey },
+    #[error("transfer fee overflows for amount {0}")]
+    FeeOverflow(u64),
+    #[error("failed to build transfer instruction: {0}")]
+    Instruction(ProgramError),
+}
+
+/// The parts of a mint that change how much a transfer moves: decimals, the Token-2022 transfer
+/// fee and transfer hook. Legacy SPL Token mints have neither extension.
+#[derive(Clone, Debug)]
+pub struct MintTransferInfo {
+    pub mint: Pubkey,
+    pub token_program: Pubkey,
+    pub decimals: u8,
+    pub transfer_fee: Option<TransferFeeConfig>,
+    pub transfer_hook_program: Option<Pubkey>,
+}
+
+impl MintTransferInfo {
+    pub fn parse(mint: Pubkey, token_program: Pubkey, data: &[u8]) -> Result<Self, TokenFeeError> {
+        let state = StateWithExtensions::<Mint>::unpack(data)
+            .map_err(|e| TokenFeeError::InvalidMint(mint, e))?;
+        Ok(Self {
+            mint,
+            token_program,
+            decimals: state.base.decimals,
+            transfer_fee: state.get_extension::<TransferFeeConfig>().ok().copied(),
+            transfer_hook_program: transfer_hook::get_program_id(&state),
+        })
+    }
+
+    pub async fn fetch(rpc_client: &RpcClient, mint: Pubkey) -> Result<Self, TokenFeeError> {
+        let account = rpc_client
+            .get_account_with_commitment(&mint, rpc_client.commitment())
+            .await?
+            .value
+            .ok_or(TokenFeeError::MintNotFound(mint))?;
+        Self::parse(mint, account.owner, &account.data)
+    }
+
+    /// Fails if transfers invoke a hook program other than the allowed ones. Hooks need extra
+    /// accounts this crate doesn't resolve, so callers must opt in to the programs they handle.
+    pub fn check_transfer_hook(&self, supported_programs: &[Pubkey]) -> Result<(), TokenFeeError> {
+        match self.transfer_hook_program {
+            Some(program) if !supported_programs.contains(&program) => {
+                Err(TokenFeeError::UnsupportedTransferHook {
+                    mint: self.mint,
+                    program,
+                })
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Fee withheld from a transfer of `amount` in `epoch`.
+    pub fn transfer_fee(&self, epoch: Epoch, amount: u64) -> Result<u64, TokenFeeError> {
+        match &self.transfer_fee {
+            Some(config) => config
+                .calculate_epoch_fee(epoch, amount)
+                .ok_or(TokenFeeError::FeeOverflow(amount)),
+            None => Ok(0),
+        }
+    }
+
+    /// What the destination receives when `amount` is sent.
+    pub fn amount_received(&self, epoch: Epoch, amount: u64) -> Result<u64, TokenFeeError> {
+        Ok(amount - self.transfer_fee(epoch, amount)?)
+    }
+
+    /// What must be sent for the destination to receive `amount_received`.
+    pub fn amount_to_send(&self, epoch: Epoch, amount_received: u64) -> Result<u64, TokenFeeError> {
+        match &self.transfer_fee {
+            Some(config) => {
+                let fee = config
+                    .calculate_inverse_epoch_fee(epoch, amount_received)
+                    .ok_or(TokenFeeError::FeeOverflow(amount_received))?;
+                amount_received
+                    .checked_add(fee)
+                    .ok_or(TokenFeeError::FeeOverflow(amount_received))
+            }
+            None => Ok(amount_received),
+        }
+    }
+
+    /// Expected output of a swap quoted before transfer fees: the pool's transfer to us pays the
+    /// output mint's fee.
+    pub fn expected_out(&self, epoch: Epoch, quoted_out: u64) -> Result<u64, TokenFeeError> {
+        self.amount_received(epoch, quoted_out)
+    }
+
+    /// Builds a checked transfer, asserting the expected fee on-chain when the mint charges one.
+    /// Fails for mints with unsupported transfer hooks.
+    pub fn transfer_instruction(
+        &self,
+        epoch: Epoch,
+        source: &Pubkey,
+        destination: &Pubkey,
+        authority: &Pubkey,
+        amount: u64,
+    ) -> Result<Instruction, TokenFeeError> {
+        self.check_transfer_hook(&[])?;
+        let instruction = match &self.transfer_fee {
+            Some(_) => transfer_checked_with_fee(
+                &self.token_program,
+                source,
+                &self.mint,
+                destination,
+                authority,
+                &[],
+                amount,
+                self.decimals,
+                self.transfer_fee(epoch, amount)?,
+            ),
+            None => transfer_checked(
+                &self.token_program,
+                source,
+                &self.mint,
+                destination,
+                authority,
+                &[],
+                amount,
+                self.decimals,
+            ),
+        };
+        instruction.map_err(TokenFeeError::Instruction)
+    }
+}
@@ -0,0 +1,500 @@
+use std::{
+    collections::{HashMap, HashSet},
+    time::Instant,
+};
+
+use log::warn;
+use solana_client::{client_error::ClientError, nonblocking::rpc_client::RpcClient};
+use solana_metrics::datapoint_info;
+use solana_sdk::{
+    account::Account, compute_budget, program_option::COption, program_pack::Pack, pubkey,
+    pubkey::Pubkey, system_program, sysvar, transaction::VersionedTransaction,
+};
+use spl_token_2022::{
+    extension::StateWithExtensions,
+    state::{Account as TokenAccount, Mint},
+};
+use thiserror::Error;
+
+use crate::{
+    pool_layouts::{RaydiumAmmV4Pool, RAYDIUM_AMM_V4_PROGRAM_ID},
+    token_safety::TokenSafetyChecker,
+};
+
+/// Metaplex Token Metadata, whose metadata accounts say whether a mint's name, symbol and URI
+/// can still be changed.
+pub const TOKEN_METADATA_PROGRAM_ID: Pubkey =
+    pubkey!("metaqbxxUerdq28cj1RbAWkYQm3ybzjb6a8bt518x1s");
+
+/// Programs every bundle may use, even with a program allowlist: the system, compute budget,
+/// token, associated token account and memo programs.
+const BASE_PROGRAMS: [Pubkey; 6] = [
+    system_program::ID,
+    compute_budget::ID,
+    spl_token::ID,
+    spl_token_2022::ID,
+    spl_associated_token_account::ID,
+    pubkey!("MemoSq4gqABAXKb96qnH8TysNcWxMyWCqXgDLGmfcHr"),
+];
+
+/// `getMultipleAccounts` limit.
+const MAX_ACCOUNTS_PER_REQUEST: usize = 100;
+
+/// Resolved accounts kept before the cache is cleared and rebuilt.
+const MAX_CACHED_ACCOUNTS: usize = 200_000;
+
+/// Why the guard blocked a bundle.
+#[derive(Clone, Debug, Error, PartialEq, Eq)]
+pub enum TokenGuardViolation {
+    #[error("program {0} is denylisted")]
+    DeniedProgram(Pubkey),
+    #[error("program {0} isn't allowlisted")]
+    ProgramNotAllowed(Pubkey),
+    #[error("mint {0} is denylisted")]
+    DeniedMint(Pubkey),
+    #[error("mint {0} isn't allowlisted")]
+    MintNotAllowed(Pubkey),
+    #[error("mint {mint} has freeze authority {authority}")]
+    FreezeAuthority { mint: Pubkey, authority: Pubkey },
+    #[error("mint {0} has mutable metadata")]
+    MutableMetadata(Pubkey),
+    #[error("mint {mint} has risk score {score}, over {max_score}")]
+    Risky {
+        mint: Pubkey,
+        score: u8,
+        max_score: u8,
+    },
+    #[error("account {0} couldn't be fetched to check it")]
+    Unresolved(Pubkey),
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct TokenGuardConfig {
+    pub denied_mints: HashSet<Pubkey>,
+    pub denied_programs: HashSet<Pubkey>,
+    /// Only these mints may be touched, if set.
+    pub allowed_mints: Option<HashSet<Pubkey>>,
+    /// Only these programs, besides the system, compute budget, token, associated token account
+    /// and memo programs, may be invoked if set.
+    pub allowed_programs: Option<HashSet<Pubkey>>,
+    /// Exempt from the on-chain checks and risk scoring, such as USDC and USDT, which keep a
+    /// freeze authority. Wrapped SOL is always exempt.
+    pub trusted_mints: HashSet<Pubkey>,
+    /// Blocks mints that have a freeze authority, which can freeze the tokens a strategy buys.
+    pub deny_freeze_authority: bool,
+    /// Blocks mints with mutable Metaplex metadata, which can be changed to impersonate another
+    /// token.
+    pub deny_mutable_metadata: bool,
+}
+
+impl TokenGuardConfig {
+    pub fn is_enabled(&self) -> bool {
+        !self.denied_programs.is_empty() || self.allowed_programs.is_some() || self.checks_mints()
+    }
+
+    /// Whether any rule needs to know which mints a transaction touches.
+    fn checks_mints(&self) -> bool {
+        !self.denied_mints.is_empty()
+            || self.allowed_mints.is_some()
+            || self.deny_freeze_authority
+            || self.deny_mutable_metadata
+    }
+}
+
+/// What the guard knows about a mint from chain.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct MintSafety {
+    pub freeze_authority: Option<Pubkey>,
+    /// False if the mint has no Metaplex metadata, or it wasn't checked.
+    pub mutable_metadata: bool,
+}
+
+#[derive(Clone, Debug)]
+enum Resolved {
+    Mint(MintSafety),
+    TokenAccount { mint: Pubkey },
+    Pool(Box<RaydiumAmmV4Pool>),
+    Other,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct TokenGuardStats {
+    pub allowed: u64,
+    pub blocked_programs: u64,
+    pub blocked_mints: u64,
+    pub blocked_unsafe_mints: u64,
+    pub blocked_unresolved: u64,
+    pub fetched_accounts: u64,
+}
+
+/// Blocks bundles that invoke denied programs or touch denied, unlisted or unsafe mints, so
+/// automated strategies stay away from honeypot tokens. With a [`TokenSafetyChecker`], mints
+/// scoring over a risk limit are blocked too, using the Raydium pools found in the bundle to
+/// check their liquidity.
+///
+/// Mints are found among a transaction's static account keys, either directly or as the mint of
+/// a token account, so a swap that only passes token accounts is still caught. Accounts loaded
+/// from address lookup tables and programs only invoked through CPI aren't seen. Resolved
+/// accounts are cached: a mint's freeze authority can't be added back once removed and its
+/// metadata can't become mutable again, so a cached mint is never safer than it really is.
+pub struct TokenGuard {
+    config: TokenGuardConfig,
+    accounts: HashMap<Pubkey, Resolved>,
+    risk: Option<(TokenSafetyChecker, u8)>,
+    stats: TokenGuardStats,
+}
+
+fn is_trusted(config: &TokenGuardConfig, mint: &Pubkey) -> bool {
+    *mint == spl_token::native_mint::ID || config.trusted_mints.contains(mint)
+}
+
+impl TokenGuard {
+    pub fn new(config: TokenGuardConfig) -> Self {
+        Self {
+            config,
+            accounts: HashMap::new(),
+            risk: None,
+            stats: TokenGuardStats::default(),
+        }
+    }
+
+    /// Also blocks mints `checker` scores over `max_score`.
+    pub fn with_risk_limit(mut self, checker: TokenSafetyChecker, max_score: u8) -> Self {
+        self.risk = Some((checker, max_score));
+        self
+    }
+
+    fn checks_mints(&self) -> bool {
+        self.config.checks_mints() || self.risk.is_some()
+    }
+
+    /// Fetches the accounts of `transactions` the guard hasn't seen yet, and the mints and
+    /// metadata they lead to, so `check` can tell which mints the transactions touch.
+    pub async fn resolve<'a>(
+        &mut self,
+        rpc_client: &RpcClient,
+        transactions: impl IntoIterator<Item = &'a VersionedTransaction>,
+    ) -> Result<(), ClientError> {
+        if !self.checks_mints() {
+            return Ok(());
+        }
+        if self.accounts.len() > MAX_CACHED_ACCOUNTS {
+            self.accounts.clear();
+        }
+        let transactions: Vec<&VersionedTransaction> = transactions.into_iter().collect();
+        let mut unknown: Vec<Pubkey> = transactions
+            .iter()
+            .flat_map(|transaction| candidate_accounts(transaction))
+            .filter(|key| !self.accounts.contains_key(key))
+            .collect::<HashSet<_>>()
+            .into_iter()
+            .collect();
+        // token accounts lead to their mints, fetched in a second round
+        while !unknown.is_empty() {
+            let fetched = self.fetch(rpc_client, &unknown).await?;
+            let mut new_mints = vec![];
+            let mut next = HashSet::new();
+            for (key, account) in unknown.iter().zip(fetched) {
+                let resolved = account.as_ref().map_or(Resolved::Other, classify);
+                match resolved {
+                    Resolved::TokenAccount { mint } if !self.accounts.contains_key(&mint) => {
+                        next.insert(mint);
+                    }
+                    Resolved::Mint(_) => new_mints.push(*key),
+                    _ => {}
+                }
+                self.accounts.insert(*key, resolved);
+            }
+            if self.config.deny_mutable_metadata && !new_mints.is_empty() {
+                self.resolve_metadata(rpc_client, &new_mints).await?;
+            }
+            next.retain(|key| !self.accounts.contains_key(key));
+            unknown = next.into_iter().collect();
+        }
+        self.assess_mints(rpc_client, &transactions).await;
+        Ok(())
+    }
+
+    /// Scores the mints the transactions touch that haven't been scored recently. Mints that
+    /// fail to be scored stay unresolved, blocking the bundles touching them.
+    async fn assess_mints(
+        &mut self,
+        rpc_client: &RpcClient,
+        transactions: &[&VersionedTransaction],
+    ) {
+        let Some((checker, _)) = &mut self.risk else {
+            return;
+        };
+        let now = Instant::now();
+        let mut mints = HashSet::new();
+        let mut pools = vec![];
+        for key in transactions
+            .iter()
+            .flat_map(|transaction| candidate_accounts(transaction))
+        {
+            match self.accounts.get(&key) {
+                Some(Resolved::Mint(_)) => {
+                    mints.insert(key);
+                }
+                Some(Resolved::TokenAccount { mint }) => {
+                    mints.insert(*mint);
+                }
+                Some(Resolved::Pool(pool)) => pools.push((key, **pool)),
+                Some(Resolved::Other) | None => {}
+            }
+        }
+        for mint in mints {
+            if is_trusted(&self.config, &mint) || checker.cached(&mint, now).is_some() {
+                continue;
+            }
+            if let Err(e) = checker.assess(rpc_client, mint, &pools, now).await {
+                warn!("failed to score the risk of mint {mint}: {e}");
+            }
+        }
+    }
+
+    async fn fetch(
+        &mut self,
+        rpc_client: &RpcClient,
+        keys: &[Pubkey],
+    ) -> Result<Vec<Option<Account>>, ClientError> {
+        let mut accounts = Vec::with_capacity(keys.len());
+        for chunk in keys.chunks(MAX_ACCOUNTS_PER_REQUEST) {
+            accounts.extend(rpc_client.get_multiple_accounts(chunk).await?);
+        }
+        self.stats.fetched_accounts += keys.len() as u64;
+        Ok(accounts)
+    }
+
+    async fn resolve_metadata(
+        &mut self,
+        rpc_client: &RpcClient,
+        mints: &[Pubkey],
+    ) -> Result<(), ClientError> {
+        let metadata_keys: Vec<Pubkey> = mints.iter().map(metadata_address).collect();
+        let fetched = self.fetch(rpc_client, &metadata_keys).await?;
+        for (mint, account) in mints.iter().zip(fetched) {
+            let mutable = account
+                .filter(|account| account.owner == TOKEN_METADATA_PROGRAM_ID)
+                .and_then(|account| metadata_is_mutable(&account.data))
+                .unwrap_or(false);
+            if let Some(Resolved::Mint(safety)) = self.accounts.get_mut(mint) {
+                safety.mutable_metadata = mutable;
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks the transactions of a bundle against the rules, using what `resolve` fetched.
+    /// Accounts it couldn't fetch block the bundle while any mint rule is set.
+    pub fn check<'a>(
+        &mut self,
+        transactions: impl IntoIterator<Item = &'a VersionedTransaction>,
+    ) -> Result<(), TokenGuardViolation> {
+        let result = transactions
+            .into_iter()
+            .try_for_each(|transaction| self.check_transaction(transaction));
+        match &result {
+            Ok(()) => self.stats.allowed += 1,
+            Err(
+                TokenGuardViolation::DeniedProgram(_) | TokenGuardViolation::ProgramNotAllowed(_),
+            ) => self.stats.blocked_programs += 1,
+            Err(TokenGuardViolation::DeniedMint(_) | TokenGuardViolation::MintNotAllowed(_)) => {
+                self.stats.blocked_mints += 1
+            }
+            Err(
+                TokenGuardViolation::FreezeAuthority { .. }
+                | TokenGuardViolation::MutableMetadata(_)
+                | TokenGuardViolation::Risky { .. },
+            ) => self.stats.blocked_unsafe_mints += 1,
+            Err(TokenGuardViolation::Unresolved(_)) => self.stats.blocked_unresolved += 1,
+        }
+        result
+    }
+
+    fn check_transaction(
+        &self,
+        transaction: &VersionedTransaction,
+    ) -> Result<(), TokenGuardViolation> {
+        let keys = transaction.message.static_account_keys();
+        for instruction in transaction.message.instructions() {
+            let program_id = keys[instruction.program_id_index as usize];
+            if self.config.denied_programs.contains(&program_id) {
+                return Err(TokenGuardViolation::DeniedProgram(program_id));
+            }
+            if let Some(allowed) = &self.config.allowed_programs {
+                if !allowed.contains(&program_id) && !BASE_PROGRAMS.contains(&program_id) {
+                    return Err(TokenGuardViolation::ProgramNotAllowed(program_id));
+                }
+            }
+        }
+        if !self.checks_mints() {
+            return Ok(());
+        }
+        for key in candidate_accounts(transaction) {
+            if self.config.denied_mints.contains(&key) {
+                return Err(TokenGuardViolation::DeniedMint(key));
+            }
+            match self.accounts.get(&key) {
+                Some(Resolved::Mint(safety)) => self.check_mint(&key, safety)?,
+                Some(Resolved::TokenAccount { mint }) => match self.accounts.get(mint) {
+                    Some(Resolved::Mint(safety)) => self.check_mint(mint, safety)?,
+                    _ => return Err(TokenGuardViolation::Unresolved(*mint)),
+                },
+                Some(Resolved::Pool(_) | Resolved::Other) => {}
+                None => return Err(TokenGuardViolation::Unresolved(key)),
+            }
+        }
+        Ok(())
+    }
+
+    fn check_mint(&self, mint: &Pubkey, safety: &MintSafety) -> Result<(), TokenGuardViolation> {
+        if self.config.denied_mints.contains(mint) {
+            return Err(TokenGuardViolation::DeniedMint(*mint));
+        }
+        if self
+            .config
+            .allowed_mints
+            .as_ref()
+            .is_some_and(|allowed| !allowed.contains(mint))
+        {
+            return Err(TokenGuardViolation::MintNotAllowed(*mint));
+        }
+        if is_trusted(&self.config, mint) {
+            return Ok(());
+        }
+        if let Some((checker, max_score)) = &self.risk {
+            match checker.cached(mint, Instant::now()) {
+                Some(risk) if risk.score > *max_score => {
+                    return Err(TokenGuardViolation::Risky {
+                        mint: *mint,
+                        score: risk.score,
+                        max_score: *max_score,
+                    })
+                }
+                Some(_) => {}
+                None => return Err(TokenGuardViolation::Unresolved(*mint)),
+            }
+        }
+        match safety.freeze_authority {
+            Some(authority) if self.config.deny_freeze_authority => {
+                Err(TokenGuardViolation::FreezeAuthority {
+                    mint: *mint,
+                    authority,
+                })
+            }
+            _ if self.config.deny_mutable_metadata && safety.mutable_metadata => {
+                Err(TokenGuardViolation::MutableMetadata(*mint))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// What the guard knows about `mint`, if it has resolved it.
+    pub fn mint_safety(&self, mint: &Pubkey) -> Option<MintSafety> {
+        match self.accounts.get(mint) {
+            Some(Resolved::Mint(safety)) => Some(*safety),
+            _ => None,
+        }
+    }
+
+    pub fn stats(&self) -> &TokenGuardStats {
+        &self.stats
+    }
+
+    /// Reports the counters and resets them.
+    pub fn report(&mut self, name: &'static str) {
+        let stats = std::mem::take(&mut self.stats);
+        datapoint_info!(
+            name,
+            ("allowed", stats.allowed, i64),
+            ("blocked_programs", stats.blocked_programs, i64),
+            ("blocked_mints", stats.blocked_mints, i64),
+            ("blocked_unsafe_mints", stats.blocked_unsafe_mints, i64),
+            ("blocked_unresolved", stats.blocked_unresolved, i64),
+            ("fetched_accounts", stats.fetched_accounts, i64),
+            ("cached_accounts", self.accounts.len(), i64),
+        );
+    }
+}
+
+/// Static account keys that could be mints or token accounts: everything but the programs the
+/// transaction invokes and sysvars.
+fn candidate_accounts(transaction: &VersionedTransaction) -> impl Iterator<Item = Pubkey> + '_ {
+    let keys = transaction.message.static_account_keys();
+    let programs: HashSet<usize> = transaction
+        .message
+        .instructions()
+        .iter()
+        .map(|instruction| instruction.program_id_index as usize)
+        .collect();
+    keys.iter()
+        .enumerate()
+        .filter(move |(index, key)| {
+            !programs.contains(index) && !sysvar::is_sysvar_id(key) && **key != system_program::ID
+        })
+        .map(|(_, key)| *key)
+}
+
+fn classify(account: &Account) -> Resolved {
+    if account.owner == RAYDIUM_AMM_V4_PROGRAM_ID {
+        return RaydiumAmmV4Pool::parse(&account.data)
+            .map_or(Resolved::Other, |pool| Resolved::Pool(Box::new(pool)));
+    }
+    if account.owner != spl_token::ID && account.owner != spl_token_2022::ID {
+        return Resolved::Other;
+    }
+    // token accounts without extensions are exactly this long, mints are shorter and extended
+    // accounts of either kind are tagged with their type
+    if account.data.len() != TokenAccount::LEN {
+        if let Ok(mint) = StateWithExtensions::<Mint>::unpack(&account.data) {
+            return Resolved::Mint(MintSafety {
+                freeze_authority: match mint.base.freeze_authority {
+                    COption::Some(authority) => Some(authority),
+                    COption::None => None,
+                },
+                mutable_metadata: false,
+            });
+        }
+    }
+    match StateWithExtensions::<TokenAccount>::unpack(&account.data) {
+        Ok(token_account) => Resolved::TokenAccount {
+            mint: token_account.base.mint,
+        },
+        Err(_) => Resolved::Other,
+    }
+}
+
+/// The Metaplex metadata account of `mint`.
+pub fn metadata_address(mint: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(
+        &[
+            b"metadata",
+            TOKEN_METADATA_PROGRAM_ID.as_ref(),
+            mint.as_ref(),
+        ],
+        &TOKEN_METADATA_PROGRAM_ID,
+    )
+    .0
+}
+
+/// Reads `is_mutable` from a Metaplex metadata account: key, update authority, mint, then the
+/// borsh encoded name, symbol, uri, seller fee and optional creators before it.
+fn metadata_is_mutable(data: &[u8]) -> Option<bool> {
+    let mut offset = 1 + 32 + 32;
+    for _ in 0..3 {
+        let len = u32::from_le_bytes(data.get(offset..offset + 4)?.try_into().ok()?) as usize;
+        offset += 4 + len;
+    }
+    // seller fee basis points
+    offset += 2;
+    if *data.get(offset)? == 1 {
+        let creators =
+            u32::from_le_bytes(data.get(offset + 1..offset + 5)?.try_into().ok()?) as usize;
+        // address, verified and share
+        offset += 4 + creators * 34;
+    }
+    // creators option tag, then primary sale happened
+    offset += 2;
+    data.get(offset).map(|is_mutable| *is_mutable != 0)
+}
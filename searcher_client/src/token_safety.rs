@@ -0,0 +1,266 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::{self, Display, Formatter},
+    time::{Duration, Instant},
+};
+
+use solana_client::{
+    client_error::ClientError, nonblocking::rpc_client::RpcClient,
+    rpc_response::RpcTokenAccountBalance,
+};
+use solana_sdk::{program_error::ProgramError, program_option::COption, pubkey::Pubkey};
+use spl_token_2022::{
+    extension::StateWithExtensions,
+    state::{Account as TokenAccount, Mint},
+};
+use thiserror::Error;
+
+use crate::pool_layouts::RaydiumAmmV4Pool;
+
+/// Largest holders counted towards concentration.
+const TOP_HOLDERS: usize = 10;
+
+#[derive(Debug, Error)]
+pub enum TokenSafetyError {
+    #[error("rpc error: {0}")]
+    Rpc(#[from] ClientError),
+    #[error("mint {0} doesn't exist")]
+    MintNotFound(Pubkey),
+    #[error("account {0} isn't a token mint: {1}")]
+    InvalidMint(Pubkey, ProgramError),
+}
+
+/// How much each finding adds to the 0 to 100 risk score. Concentration and liquidity add their
+/// weight in proportion to how bad they are.
+#[derive(Clone, Copy, Debug)]
+pub struct RiskWeights {
+    pub mint_authority: u8,
+    pub freeze_authority: u8,
+    pub concentration: u8,
+    /// Share of supply the top holders may have before concentration adds anything, in basis
+    /// points.
+    pub concentration_threshold_bps: u16,
+    pub unlocked_liquidity: u8,
+}
+
+impl Default for RiskWeights {
+    fn default() -> Self {
+        Self {
+            mint_authority: 30,
+            freeze_authority: 30,
+            concentration: 20,
+            concentration_threshold_bps: 5_000,
+            unlocked_liquidity: 20,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum RiskFinding {
+    /// More tokens can be minted.
+    MintAuthority(Pubkey),
+    /// Holders' token accounts can be frozen.
+    FreezeAuthority(Pubkey),
+    /// The top holders, not counting pool vaults, hold this share of the supply.
+    HolderConcentration { holders: usize, basis_points: u16 },
+    /// This share of the pool's LP tokens is held outside the configured lockers and could be
+    /// withdrawn.
+    UnlockedLiquidity { pool: Pubkey, basis_points: u16 },
+}
+
+impl Display for RiskFinding {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            RiskFinding::MintAuthority(authority) => write!(f, "mint authority {authority}"),
+            RiskFinding::FreezeAuthority(authority) => write!(f, "freeze authority {authority}"),
+            RiskFinding::HolderConcentration {
+                holders,
+                basis_points,
+            } => write!(
+                f,
+                "top {holders} holders have {:.2}% of supply",
+                *basis_points as f64 / 100.0
+            ),
+            RiskFinding::UnlockedLiquidity { pool, basis_points } => write!(
+                f,
+                "{:.2}% of pool {pool} LP is unlocked",
+                *basis_points as f64 / 100.0
+            ),
+        }
+    }
+}
+
+/// A mint's risk score, from 0 for no findings to 100, and what it's made of.
+#[derive(Clone, Debug)]
+pub struct TokenRisk {
+    pub mint: Pubkey,
+    pub score: u8,
+    pub findings: Vec<RiskFinding>,
+}
+
+/// Scores how likely a token is a rug pull or honeypot before an automated swap buys it: whether
+/// its authorities can mint more or freeze holders, how concentrated its supply is and, for the
+/// Raydium AMM v4 pools given, how much of the liquidity could be pulled.
+///
+/// Liquidity counts as locked when the LP tokens are burned or held, directly or as the token
+/// account owner, by one of `lp_lockers`. Only the largest LP holders are inspected, so the
+/// unlocked share is a lower bound. Assessments are cached per mint for `ttl`.
+pub struct TokenSafetyChecker {
+    weights: RiskWeights,
+    lp_lockers: HashSet<Pubkey>,
+    ttl: Duration,
+    cache: HashMap<Pubkey, (Instant, TokenRisk)>,
+}
+
+impl TokenSafetyChecker {
+    pub fn new(weights: RiskWeights, lp_lockers: HashSet<Pubkey>, ttl: Duration) -> Self {
+        Self {
+            weights,
+            lp_lockers,
+            ttl,
+            cache: HashMap::new(),
+        }
+    }
+
+    /// The cached assessment of `mint`, unless it's older than the ttl.
+    pub fn cached(&self, mint: &Pubkey, now: Instant) -> Option<&TokenRisk> {
+        self.cache
+            .get(mint)
+            .filter(|(assessed_at, _)| now.saturating_duration_since(*assessed_at) < self.ttl)
+            .map(|(_, risk)| risk)
+    }
+
+    /// Assesses `mint`, or returns the cached assessment. `pools` are pools trading it, whose
+    /// vaults don't count as holders and whose LP tokens are checked.
+    pub async fn assess(
+        &mut self,
+        rpc_client: &RpcClient,
+        mint: Pubkey,
+        pools: &[(Pubkey, RaydiumAmmV4Pool)],
+        now: Instant,
+    ) -> Result<TokenRisk, TokenSafetyError> {
+        if let Some(risk) = self.cached(&mint, now) {
+            return Ok(risk.clone());
+        }
+        let risk = self.assess_uncached(rpc_client, mint, pools).await?;
+        self.cache
+            .retain(|_, (assessed_at, _)| now.saturating_duration_since(*assessed_at) < self.ttl);
+        self.cache.insert(mint, (now, risk.clone()));
+        Ok(risk)
+    }
+
+    async fn assess_uncached(
+        &self,
+        rpc_client: &RpcClient,
+        mint: Pubkey,
+        pools: &[(Pubkey, RaydiumAmmV4Pool)],
+    ) -> Result<TokenRisk, TokenSafetyError> {
+        let account = rpc_client
+            .get_account_with_commitment(&mint, rpc_client.commitment())
+            .await?
+            .value
+            .ok_or(TokenSafetyError::MintNotFound(mint))?;
+        let state = StateWithExtensions::<Mint>::unpack(&account.data)
+            .map_err(|e| TokenSafetyError::InvalidMint(mint, e))?;
+
+        let mut findings = vec![];
+        let mut score = 0.0;
+        if let COption::Some(authority) = state.base.mint_authority {
+            findings.push(RiskFinding::MintAuthority(authority));
+            score += self.weights.mint_authority as f64;
+        }
+        if let COption::Some(authority) = state.base.freeze_authority {
+            findings.push(RiskFinding::FreezeAuthority(authority));
+            score += self.weights.freeze_authority as f64;
+        }
+
+        let pools: Vec<_> = pools
+            .iter()
+            .filter(|(_, pool)| pool.has_mint(&mint))
+            .collect();
+        let vaults: HashSet<String> = pools
+            .iter()
+            .flat_map(|(_, pool)| [pool.coin_vault.to_string(), pool.pc_vault.to_string()])
+            .collect();
+        if state.base.supply > 0 {
+            let holders = rpc_client.get_token_largest_accounts(&mint).await?;
+            let held: u64 = holders
+                .iter()
+                .filter(|holder| !vaults.contains(&holder.address))
+                .take(TOP_HOLDERS)
+                .map(raw_amount)
+                .sum();
+            let basis_points = share_bps(held, state.base.supply);
+            let threshold = self.weights.concentration_threshold_bps.min(9_999);
+            if basis_points > threshold {
+                findings.push(RiskFinding::HolderConcentration {
+                    holders: TOP_HOLDERS,
+                    basis_points,
+                });
+                score += self.weights.concentration as f64 * (basis_points - threshold) as f64
+                    / (10_000 - threshold) as f64;
+            }
+        }
+
+        let mut worst_unlocked_bps = 0;
+        for (pool_address, pool) in pools {
+            let basis_points = self.unlocked_lp_bps(rpc_client, pool).await?;
+            if basis_points > 0 {
+                findings.push(RiskFinding::UnlockedLiquidity {
+                    pool: *pool_address,
+                    basis_points,
+                });
+            }
+            worst_unlocked_bps = worst_unlocked_bps.max(basis_points);
+        }
+        score += self.weights.unlocked_liquidity as f64 * worst_unlocked_bps as f64 / 10_000.0;
+
+        Ok(TokenRisk {
+            mint,
+            score: score.round().min(100.0) as u8,
+            findings,
+        })
+    }
+
+    /// Share of the pool's outstanding LP tokens held by its largest holders outside the
+    /// lockers. Burned LP tokens are no longer outstanding, so they count as locked.
+    async fn unlocked_lp_bps(
+        &self,
+        rpc_client: &RpcClient,
+        pool: &RaydiumAmmV4Pool,
+    ) -> Result<u16, TokenSafetyError> {
+        let supply = rpc_client.get_token_supply(&pool.lp_mint).await?;
+        let supply: u64 = supply.amount.parse().unwrap_or_default();
+        if supply == 0 {
+            return Ok(0);
+        }
+        let holders = rpc_client.get_token_largest_accounts(&pool.lp_mint).await?;
+        let addresses: Vec<Pubkey> = holders
+            .iter()
+            .filter_map(|holder| holder.address.parse().ok())
+            .collect();
+        let accounts = rpc_client.get_multiple_accounts(&addresses).await?;
+        let mut unlocked = 0;
+        for ((address, account), holder) in addresses.iter().zip(accounts).zip(&holders) {
+            let owner = account.and_then(|account| {
+                StateWithExtensions::<TokenAccount>::unpack(&account.data)
+                    .ok()
+                    .map(|state| state.base.owner)
+            });
+            let locked = self.lp_lockers.contains(address)
+                || owner.is_some_and(|owner| self.lp_lockers.contains(&owner));
+            if !locked {
+                unlocked += raw_amount(holder);
+            }
+        }
+        Ok(share_bps(unlocked, supply))
+    }
+}
+
+fn raw_amount(balance: &RpcTokenAccountBalance) -> u64 {
+    balance.amount.amount.parse().unwrap_or_default()
+}
+
+fn share_bps(amount: u64, total: u64) -> u16 {
+    (amount as u128 * 10_000 / total.max(1) as u128).min(10_000) as u16
+}
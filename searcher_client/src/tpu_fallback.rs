@@ -0,0 +1,85 @@
+use std::{str::FromStr, sync::Arc};
+
+use solana_client::{
+    nonblocking::{
+        rpc_client::RpcClient,
+        tpu_client::{TpuClient, TpuSenderError},
+    },
+    tpu_client::TpuClientConfig,
+};
+use solana_quic_client::{QuicConfig, QuicConnectionManager, QuicPool};
+use solana_sdk::{transaction::VersionedTransaction, transport::Result as TransportResult};
+use tonic::{Code, Status};
+
+pub type QuicTpuClient = TpuClient<QuicPool, QuicConnectionManager, QuicConfig>;
+
+/// How a strategy gets its transactions included.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum InclusionPath {
+    /// Bundles through the block engine only.
+    #[default]
+    BlockEngine,
+    /// Bundles through the block engine, falling back to sending the transactions straight to
+    /// the upcoming leaders' TPUs while the block engine is unreachable. Fallback sends are
+    /// neither atomic nor protected from being unbundled.
+    TpuFallback,
+}
+
+impl FromStr for InclusionPath {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "block-engine" => Ok(Self::BlockEngine),
+            "tpu-fallback" => Ok(Self::TpuFallback),
+            _ => Err(format!(
+                "unknown inclusion path {s:?}, expected block-engine or tpu-fallback"
+            )),
+        }
+    }
+}
+
+/// True if the status means the block engine couldn't be reached or didn't answer, rather than
+/// it rejecting the bundle.
+pub fn is_block_engine_unavailable(status: &Status) -> bool {
+    matches!(
+        status.code(),
+        Code::Unavailable | Code::DeadlineExceeded | Code::Aborted
+    )
+}
+
+/// Sends transactions over QUIC to the TPUs of the current and upcoming leaders. The underlying
+/// client tracks the leader schedule and keeps connections to upcoming leaders cached, so a
+/// fallback send doesn't pay for a handshake.
+pub struct TpuFallbackSender {
+    client: QuicTpuClient,
+}
+
+impl TpuFallbackSender {
+    /// `fanout_slots` is how many upcoming leader slots each transaction is sent to.
+    pub async fn connect(
+        rpc_url: String,
+        websocket_url: &str,
+        fanout_slots: u64,
+    ) -> Result<Self, TpuSenderError> {
+        let client = QuicTpuClient::new(
+            "searcher_tpu_fallback",
+            Arc::new(RpcClient::new(rpc_url)),
+            websocket_url,
+            TpuClientConfig { fanout_slots },
+        )
+        .await?;
+        Ok(Self { client })
+    }
+
+    /// Sends the transactions in order, returning the last error if every send failed.
+    pub async fn send(&self, transactions: &[VersionedTransaction]) -> TransportResult<()> {
+        let wire_transactions = transactions
+            .iter()
+            .map(|tx| bincode::serialize(tx).expect("serializes"))
+            .collect();
+        self.client
+            .try_send_wire_transaction_batch(wire_transactions)
+            .await
+    }
+}
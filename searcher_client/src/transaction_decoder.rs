@@ -0,0 +1,318 @@
+use std::fmt::Write;
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use solana_sdk::{
+    bs58, message::VersionedMessage, pubkey::Pubkey, transaction::VersionedTransaction,
+};
+use thiserror::Error;
+
+/// Well known programs, used to label instructions.
+const KNOWN_PROGRAMS: &[(&str, &str)] = &[
+    ("11111111111111111111111111111111", "System Program"),
+    (
+        "ComputeBudget111111111111111111111111111111",
+        "Compute Budget",
+    ),
+    ("TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA", "SPL Token"),
+    (
+        "TokenzQdBNbLqP5VEhdkAS6EPFLC1PHnBqCXEpPxuEb",
+        "SPL Token-2022",
+    ),
+    (
+        "ATokenGPvbdGVxr1b2hvZbsiqW5xWH25efTNsLJA8knL",
+        "Associated Token Account",
+    ),
+    ("MemoSq4gqABAXKb96qnH8TysNcWxMyWCqXgDLGmfcHr", "Memo"),
+    ("Memo1UhkJRfHyvLMcVucJwxXeuD728EqVDDwQDxFMNo", "Memo (v1)"),
+    (
+        "AddressLookupTab1e1111111111111111111111111",
+        "Address Lookup Table",
+    ),
+    (
+        "Vote111111111111111111111111111111111111111",
+        "Vote Program",
+    ),
+    (
+        "Stake11111111111111111111111111111111111111",
+        "Stake Program",
+    ),
+    (
+        "T1pyyaTNZsKv2WcRAB8oVnk93mLJw2XzjtVYqCsaHqt",
+        "Jito Tip Payment",
+    ),
+    (
+        "4R3gSG8BpU4t19KYj8CfnbtRpnT8gtk4dvTHxVRwc2r7",
+        "Jito Tip Distribution",
+    ),
+];
+
+#[derive(Debug, Error)]
+pub enum DecodeError {
+    #[error("input is neither base64 nor base58")]
+    InvalidEncoding,
+    #[error("failed to deserialize transaction: {0}")]
+    InvalidTransaction(#[from] bincode::Error),
+    #[error("{0} bytes left over after the transaction")]
+    TrailingBytes(u64),
+    #[error("transaction has no signatures")]
+    Unsigned,
+}
+
+pub fn program_name(program_id: &Pubkey) -> Option<&'static str> {
+    let program_id = program_id.to_string();
+    KNOWN_PROGRAMS
+        .iter()
+        .find(|(id, _)| *id == program_id)
+        .map(|(_, name)| *name)
+}
+
+/// Decodes a serialized transaction given as base64 or base58. Some strings are valid in both
+/// encodings, so each decoding is tried and the one holding exactly a transaction is kept.
+pub fn decode_transaction(encoded: &str) -> Result<VersionedTransaction, DecodeError> {
+    let encoded = encoded.trim();
+    let decodings = [
+        STANDARD.decode(encoded).ok(),
+        bs58::decode(encoded).into_vec().ok(),
+    ];
+    let mut error = DecodeError::InvalidEncoding;
+    for bytes in decodings.into_iter().flatten() {
+        match deserialize_transaction(&bytes) {
+            Ok(tx) => return Ok(tx),
+            Err(e) => error = e,
+        }
+    }
+    Err(error)
+}
+
+/// Deserializes a transaction, rejecting trailing bytes a different encoding would leave.
+fn deserialize_transaction(bytes: &[u8]) -> Result<VersionedTransaction, DecodeError> {
+    let tx: VersionedTransaction = bincode::deserialize(bytes)?;
+    let size = bincode::serialized_size(&tx)?;
+    if size < bytes.len() as u64 {
+        return Err(DecodeError::TrailingBytes(bytes.len() as u64 - size));
+    }
+    if tx.signatures.is_empty() {
+        return Err(DecodeError::Unsigned);
+    }
+    Ok(tx)
+}
+
+/// Compute budget settings requested by a transaction.
+#[derive(Debug, Default)]
+pub struct ComputeBudgetSettings {
+    pub unit_limit: Option<u32>,
+    pub unit_price_micro_lamports: Option<u64>,
+    pub heap_frame_bytes: Option<u32>,
+    pub loaded_accounts_data_size_limit: Option<u32>,
+}
+
+impl ComputeBudgetSettings {
+    pub fn from_transaction(tx: &VersionedTransaction) -> Self {
+        let keys = tx.message.static_account_keys();
+        let mut settings = Self::default();
+        for ix in tx.message.instructions() {
+            let is_compute_budget = keys
+                .get(ix.program_id_index as usize)
+                .map(|program_id| *program_id == solana_sdk::compute_budget::id())
+                .unwrap_or(false);
+            if !is_compute_budget {
+                continue;
+            }
+            let u32_at = |offset: usize| {
+                ix.data
+                    .get(offset..offset + 4)
+                    .map(|b| u32::from_le_bytes(b.try_into().unwrap()))
+            };
+            match ix.data.first() {
+                Some(1) => settings.heap_frame_bytes = u32_at(1),
+                Some(2) => settings.unit_limit = u32_at(1),
+                Some(3) => {
+                    settings.unit_price_micro_lamports = ix
+                        .data
+                        .get(1..9)
+                        .map(|b| u64::from_le_bytes(b.try_into().unwrap()))
+                }
+                Some(4) => settings.loaded_accounts_data_size_limit = u32_at(1),
+                _ => {}
+            }
+        }
+        settings
+    }
+}
+
+/// Renders a human readable description of a transaction: header, account keys, instructions
+/// and compute budget.
+pub fn describe_transaction(tx: &VersionedTransaction) -> Result<String, DecodeError> {
+    let signature = tx.signatures.first().ok_or(DecodeError::Unsigned)?;
+    let mut out = String::new();
+    let message = &tx.message;
+    let header = message.header();
+    let keys = message.static_account_keys();
+
+    let version = match message {
+        VersionedMessage::Legacy(_) => "legacy",
+        VersionedMessage::V0(_) => "v0",
+    };
+    let _ = writeln!(out, "signature: {signature}");
+    let _ = writeln!(out, "version: {version}");
+    let _ = writeln!(out, "recent blockhash: {}", message.recent_blockhash());
+    let _ = writeln!(
+        out,
+        "header: {} required signatures, {} readonly signed, {} readonly unsigned",
+        header.num_required_signatures,
+        header.num_readonly_signed_accounts,
+        header.num_readonly_unsigned_accounts
+    );
+
+    let _ = writeln!(out, "account keys:");
+    for (i, key) in keys.iter().enumerate() {
+        let mut flags = vec![];
+        if message.is_signer(i) {
+            flags.push("signer");
+        }
+        if message.is_maybe_writable(i) {
+            flags.push("writable");
+        }
+        let label = program_name(key)
+            .map(|n| format!(" ({n})"))
+            .unwrap_or_default();
+        let _ = writeln!(out, "  [{i}] {key}{label} {}", flags.join(" "));
+    }
+    if let Some(lookups) = message.address_table_lookups() {
+        for lookup in lookups {
+            let _ = writeln!(
+                out,
+                "  lookup table {}: writable {:?}, readonly {:?}",
+                lookup.account_key, lookup.writable_indexes, lookup.readonly_indexes
+            );
+        }
+    }
+
+    let _ = writeln!(out, "instructions:");
+    for (i, ix) in message.instructions().iter().enumerate() {
+        let program = keys
+            .get(ix.program_id_index as usize)
+            .map(|program_id| {
+                let name = program_name(program_id).unwrap_or("unknown program");
+                format!("{program_id} ({name})")
+            })
+            .unwrap_or_else(|| format!("lookup table index {}", ix.program_id_index));
+        let _ = writeln!(out, "  [{i}] {program}");
+        let _ = writeln!(out, "      accounts: {:?}", ix.accounts);
+        let _ = writeln!(out, "      data: {}", bs58::encode(&ix.data).into_string());
+    }
+
+    let budget = ComputeBudgetSettings::from_transaction(tx);
+    let _ = writeln!(out, "compute budget:");
+    let _ = writeln!(
+        out,
+        "  unit limit: {}",
+        budget
+            .unit_limit
+            .map(|l| l.to_string())
+            .unwrap_or_else(|| "default".into())
+    );
+    let _ = writeln!(
+        out,
+        "  unit price: {}",
+        budget
+            .unit_price_micro_lamports
+            .map(|p| format!("{p} micro-lamports"))
+            .unwrap_or_else(|| "none".into())
+    );
+    if let Some(heap) = budget.heap_frame_bytes {
+        let _ = writeln!(out, "  heap frame: {heap} bytes");
+    }
+    if let Some(limit) = budget.loaded_accounts_data_size_limit {
+        let _ = writeln!(out, "  loaded accounts data size limit: {limit} bytes");
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use base64::{engine::general_purpose::STANDARD, Engine};
+    use solana_sdk::{
+        bs58,
+        hash::Hash,
+        instruction::Instruction,
+        pubkey::Pubkey,
+        signature::{Keypair, Signer},
+        transaction::{Transaction, VersionedTransaction},
+    };
+
+    use crate::transaction_decoder::{decode_transaction, describe_transaction, DecodeError};
+
+    /// A signed transaction with one instruction carrying `data_len` bytes.
+    fn signed_tx(data_len: usize) -> VersionedTransaction {
+        let payer = Keypair::new();
+        let ix = Instruction::new_with_bytes(Pubkey::new_unique(), &vec![7; data_len], vec![]);
+        VersionedTransaction::from(Transaction::new_signed_with_payer(
+            &[ix],
+            Some(&payer.pubkey()),
+            &[&payer],
+            Hash::new_unique(),
+        ))
+    }
+
+    #[test]
+    fn test_decode_base64_and_base58() {
+        let tx = signed_tx(8);
+        let serialized = bincode::serialize(&tx).unwrap();
+
+        let base64 = STANDARD.encode(&serialized);
+        assert_eq!(decode_transaction(&format!(" {base64}\n")).unwrap(), tx);
+        let base58 = bs58::encode(&serialized).into_string();
+        assert_eq!(decode_transaction(&base58).unwrap(), tx);
+    }
+
+    #[test]
+    fn test_decode_base58_that_is_also_base64() {
+        // base58 strings whose length is a multiple of 4 are valid base64 too
+        let (tx, base58) = (0..64)
+            .map(signed_tx)
+            .map(|tx| {
+                let base58 = bs58::encode(bincode::serialize(&tx).unwrap()).into_string();
+                (tx, base58)
+            })
+            .find(|(_, base58)| STANDARD.decode(base58).is_ok())
+            .unwrap();
+        assert_eq!(decode_transaction(&base58).unwrap(), tx);
+    }
+
+    #[test]
+    fn test_decode_invalid() {
+        assert!(matches!(
+            decode_transaction("not a transaction!"),
+            Err(DecodeError::InvalidEncoding)
+        ));
+        assert!(matches!(
+            decode_transaction(&STANDARD.encode([1, 2, 3])),
+            Err(DecodeError::InvalidTransaction(_))
+        ));
+
+        let mut serialized = bincode::serialize(&signed_tx(8)).unwrap();
+        serialized.push(0);
+        assert!(matches!(
+            decode_transaction(&STANDARD.encode(&serialized)),
+            Err(DecodeError::TrailingBytes(1))
+        ));
+    }
+
+    #[test]
+    fn test_unsigned() {
+        let mut tx = signed_tx(8);
+        tx.signatures.clear();
+        assert!(matches!(
+            decode_transaction(&STANDARD.encode(bincode::serialize(&tx).unwrap())),
+            Err(DecodeError::Unsigned)
+        ));
+        assert!(matches!(
+            describe_transaction(&tx),
+            Err(DecodeError::Unsigned)
+        ));
+        assert!(describe_transaction(&signed_tx(8))
+            .unwrap()
+            .starts_with("signature: "));
+    }
+}
@@ -0,0 +1,270 @@
+use std::{
+    io,
+    path::Path,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use futures_util::future::BoxFuture;
+use log::{debug, warn};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use solana_sdk::transaction::VersionedTransaction;
+use thiserror::Error;
+use tokio::{
+    io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt},
+    net::{UnixListener, UnixStream},
+    sync::mpsc,
+};
+
+use crate::idempotency::{IdempotencyCheck, IdempotencyKeys};
+
+/// Five max size transactions plus framing fit well within this.
+pub const MAX_FRAME_LEN: usize = 64 * 1024;
+
+#[derive(Debug, Error)]
+pub enum IpcError {
+    #[error("io error: {0}")]
+    Io(#[from] io::Error),
+    #[error("invalid frame: {0}")]
+    Bincode(#[from] bincode::Error),
+    #[error("frame of {0} bytes is over the {MAX_FRAME_LEN} byte limit")]
+    FrameTooLarge(usize),
+    #[error("response is for request {0}, expected {1}")]
+    UnexpectedResponse(u64, u64),
+    #[error("bundle not sent: {0}")]
+    Rejected(String),
+    #[error("idempotency key was already used for a different bundle: {0}")]
+    IdempotencyConflict(String),
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SubmitRequest {
+    /// Echoed back in the response, lets a connection have several requests in flight.
+    pub id: u64,
+    /// Bincode serialized VersionedTransactions.
+    pub transactions: Vec<Vec<u8>>,
+    /// Unique per bundle the client means to send, such as a UUID. A request retried with the
+    /// same key isn't sent again.
+    pub idempotency_key: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SubmitResponse {
+    pub id: u64,
+    /// The bundle uuid, or why the block engine didn't take it.
+    pub result: Result<String, String>,
+    /// Set if the idempotency key was already used and nothing was sent: the uuid of the bundle
+    /// sent with it, which `result` repeats if it was the same bundle and is an error otherwise.
+    pub duplicate_of: Option<String>,
+}
+
+/// Writes `message` as a little-endian u32 length followed by that many bytes of bincode.
+pub async fn write_frame<T: Serialize>(
+    writer: &mut (impl AsyncWrite + Unpin),
+    message: &T,
+) -> Result<(), IpcError> {
+    let bytes = bincode::serialize(message)?;
+    if bytes.len() > MAX_FRAME_LEN {
+        return Err(IpcError::FrameTooLarge(bytes.len()));
+    }
+    writer.write_u32_le(bytes.len() as u32).await?;
+    writer.write_all(&bytes).await?;
+    writer.flush().await?;
+    Ok(())
+}
+
+/// Returns None once the other side closed the socket between frames.
+pub async fn read_frame<T: DeserializeOwned>(
+    reader: &mut (impl AsyncRead + Unpin),
+) -> Result<Option<T>, IpcError> {
+    let len = match reader.read_u32_le().await {
+        Ok(len) => len as usize,
+        Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+        Err(e) => return Err(e.into()),
+    };
+    if len > MAX_FRAME_LEN {
+        return Err(IpcError::FrameTooLarge(len));
+    }
+    let mut bytes = vec![0; len];
+    reader.read_exact(&mut bytes).await?;
+    Ok(Some(bincode::deserialize(&bytes)?))
+}
+
+/// Serves bundle submission on a Unix domain socket, for strategies on the same host that can't
+/// afford the HTTP/2 and protobuf overhead of the strategy gateway. Accepts connections on `path`
+/// until an accept fails, handing each bundle to `submit`. Requests on a connection are submitted
+/// concurrently and answered as they complete. Idempotency keys are shared by all connections
+/// and remembered for `idempotency_ttl` after their bundle was sent. A stale socket file left at
+/// `path` is replaced.
+pub async fn serve<F>(path: &Path, idempotency_ttl: Duration, submit: F) -> io::Result<()>
+where
+    F: Fn(Vec<Vec<u8>>) -> BoxFuture<'static, Result<String, String>> + Clone + Send + 'static,
+{
+    if path.exists() {
+        std::fs::remove_file(path)?;
+    }
+    let listener = UnixListener::bind(path)?;
+    let idempotency_keys = Arc::new(Mutex::new(IdempotencyKeys::new(idempotency_ttl)));
+    loop {
+        let (stream, _) = listener.accept().await?;
+        tokio::spawn(handle_connection(
+            stream,
+            idempotency_keys.clone(),
+            submit.clone(),
+        ));
+    }
+}
+
+async fn handle_connection<F>(
+    stream: UnixStream,
+    idempotency_keys: Arc<Mutex<IdempotencyKeys>>,
+    submit: F,
+) where
+    F: Fn(Vec<Vec<u8>>) -> BoxFuture<'static, Result<String, String>> + Send + 'static,
+{
+    let (mut reader, mut writer) = stream.into_split();
+    let (responses, mut pending_responses) = mpsc::unbounded_channel::<SubmitResponse>();
+    let write_task = tokio::spawn(async move {
+        while let Some(response) = pending_responses.recv().await {
+            if let Err(e) = write_frame(&mut writer, &response).await {
+                debug!("ipc client went away: {e}");
+                return;
+            }
+        }
+    });
+    loop {
+        let request: SubmitRequest = match read_frame(&mut reader).await {
+            Ok(Some(request)) => request,
+            Ok(None) => break,
+            Err(e) => {
+                warn!("closing ipc connection: {e}");
+                break;
+            }
+        };
+        let check = request.idempotency_key.as_ref().map(|key| {
+            idempotency_keys
+                .lock()
+                .unwrap()
+                .check(key, &request.transactions, Instant::now())
+        });
+        let (result, duplicate_of) = match check {
+            None | Some(IdempotencyCheck::New) => (None, None),
+            Some(IdempotencyCheck::Duplicate(uuid)) => (Some(Ok(uuid.clone())), Some(uuid)),
+            Some(IdempotencyCheck::Conflict(uuid)) => (
+                Some(Err(
+                    "idempotency key was already used for a different bundle".to_string(),
+                )),
+                Some(uuid),
+            ),
+            Some(IdempotencyCheck::InFlight) => (
+                Some(Err(
+                    "a bundle with this idempotency key is still being submitted".to_string(),
+                )),
+                None,
+            ),
+        };
+        if let Some(result) = result {
+            let _ = responses.send(SubmitResponse {
+                id: request.id,
+                result,
+                duplicate_of,
+            });
+            continue;
+        }
+        let responses = responses.clone();
+        let idempotency_keys = idempotency_keys.clone();
+        let submission = submit(request.transactions);
+        tokio::spawn(async move {
+            let result = submission.await;
+            if let Some(key) = &request.idempotency_key {
+                let mut keys = idempotency_keys.lock().unwrap();
+                match &result {
+                    Ok(uuid) => keys.complete(key, uuid.clone(), Instant::now()),
+                    Err(_) => keys.release(key),
+                }
+            }
+            let _ = responses.send(SubmitResponse {
+                id: request.id,
+                result,
+                duplicate_of: None,
+            });
+        });
+    }
+    // the write task finishes once every in flight submission has answered
+    drop(responses);
+    let _ = write_task.await;
+}
+
+/// Submits bundles to a `serve` endpoint one at a time.
+pub struct IpcClient {
+    stream: UnixStream,
+    next_id: u64,
+}
+
+impl IpcClient {
+    pub async fn connect(path: impl AsRef<Path>) -> Result<Self, IpcError> {
+        Ok(Self {
+            stream: UnixStream::connect(path).await?,
+            next_id: 0,
+        })
+    }
+
+    /// Sends the bundle and returns its uuid once the block engine took it.
+    pub async fn submit(
+        &mut self,
+        transactions: &[VersionedTransaction],
+    ) -> Result<String, IpcError> {
+        let transactions = transactions
+            .iter()
+            .map(bincode::serialize)
+            .collect::<Result<_, _>>()?;
+        self.submit_serialized(transactions).await
+    }
+
+    /// Like `submit`, for transactions that are already bincode serialized.
+    pub async fn submit_serialized(
+        &mut self,
+        transactions: Vec<Vec<u8>>,
+    ) -> Result<String, IpcError> {
+        self.request(transactions, None).await
+    }
+
+    /// Like `submit`, safe to retry with the same `key` after an error that left it unknown
+    /// whether the bundle was sent: if it was, its uuid is returned and it isn't sent again.
+    pub async fn submit_with_key(
+        &mut self,
+        key: &str,
+        transactions: &[VersionedTransaction],
+    ) -> Result<String, IpcError> {
+        let transactions = transactions
+            .iter()
+            .map(bincode::serialize)
+            .collect::<Result<_, _>>()?;
+        self.request(transactions, Some(key.to_string())).await
+    }
+
+    async fn request(
+        &mut self,
+        transactions: Vec<Vec<u8>>,
+        idempotency_key: Option<String>,
+    ) -> Result<String, IpcError> {
+        let id = self.next_id;
+        self.next_id += 1;
+        let request = SubmitRequest {
+            id,
+            transactions,
+            idempotency_key,
+        };
+        write_frame(&mut self.stream, &request).await?;
+        let response: SubmitResponse = read_frame(&mut self.stream).await?.ok_or_else(|| {
+            io::Error::new(io::ErrorKind::UnexpectedEof, "ipc server closed the socket")
+        })?;
+        if response.id != id {
+            return Err(IpcError::UnexpectedResponse(response.id, id));
+        }
+        match (response.result, response.duplicate_of) {
+            (Err(_), Some(uuid)) => Err(IpcError::IdempotencyConflict(uuid)),
+            (result, _) => result.map_err(IpcError::Rejected),
+        }
+    }
+}
@@ -0,0 +1,79 @@
+use std::collections::HashMap;
+
+use solana_account_decoder::parse_config::{parse_config, ConfigAccountType};
+use solana_client::{client_error::Result as ClientResult, nonblocking::rpc_client::RpcClient};
+
+/// Operator metadata for a validator, keyed by identity.
+/// Name and website come from the validator info published through the config program; data
+/// center and ASN aren't stored on-chain so they aren't included.
+#[derive(Clone, Debug, Default)]
+pub struct ValidatorMetadata {
+    pub identity: String,
+    pub name: Option<String>,
+    pub website: Option<String>,
+    pub vote_account: Option<String>,
+    pub commission: Option<u8>,
+    pub activated_stake: u64,
+    pub delinquent: bool,
+}
+
+impl ValidatorMetadata {
+    /// The published name, falling back to the identity.
+    pub fn display_name(&self) -> &str {
+        self.name.as_deref().unwrap_or(&self.identity)
+    }
+}
+
+/// Fetches vote accounts and validator info and merges them by validator identity.
+pub async fn fetch_validator_metadata(
+    rpc_client: &RpcClient,
+) -> ClientResult<HashMap<String, ValidatorMetadata>> {
+    let vote_accounts = rpc_client.get_vote_accounts().await?;
+    let mut metadata: HashMap<String, ValidatorMetadata> = HashMap::new();
+
+    let current = vote_accounts.current.into_iter().map(|v| (v, false));
+    let delinquent = vote_accounts.delinquent.into_iter().map(|v| (v, true));
+    for (vote_account, delinquent) in current.chain(delinquent) {
+        metadata.insert(
+            vote_account.node_pubkey.clone(),
+            ValidatorMetadata {
+                identity: vote_account.node_pubkey,
+                vote_account: Some(vote_account.vote_pubkey),
+                commission: Some(vote_account.commission),
+                activated_stake: vote_account.activated_stake,
+                delinquent,
+                ..ValidatorMetadata::default()
+            },
+        );
+    }
+
+    let config_accounts = rpc_client
+        .get_program_accounts(&solana_sdk::config::program::id())
+        .await?;
+    for (pubkey, account) in config_accounts {
+        let Ok(ConfigAccountType::ValidatorInfo(info)) = parse_config(&account.data, &pubkey)
+        else {
+            continue;
+        };
+        // the second key is the validator identity that signed the info
+        let Some(identity) = info.keys.get(1).map(|k| k.pubkey.clone()) else {
+            continue;
+        };
+        let field = |name: &str| {
+            info.config_data
+                .get(name)
+                .and_then(|v| v.as_str())
+                .map(str::to_string)
+        };
+        let entry = metadata
+            .entry(identity.clone())
+            .or_insert_with(|| ValidatorMetadata {
+                identity,
+                ..ValidatorMetadata::default()
+            });
+        entry.name = field("name");
+        entry.website = field("website");
+    }
+
+    Ok(metadata)
+}
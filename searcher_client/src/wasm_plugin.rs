@@ -0,0 +1,89 @@
+use std::path::Path;
+
+use thiserror::Error;
+use wasmtime::{Config, Engine, Instance, Memory, Module, Store, TypedFunc, Val};
+
+/// Fuel each call may burn, roughly one unit per instruction. Bounds how long a buggy plugin
+/// can stall the caller.
+const FUEL_PER_CALL: u64 = 50_000_000;
+
+#[derive(Debug, Error)]
+pub enum WasmPluginError {
+    #[error("wasm error {0}")]
+    Wasm(#[from] wasmtime::Error),
+    #[error("plugin doesn't export {0}")]
+    MissingExport(&'static str),
+    #[error("plugin returned an out of bounds buffer at {0}, {1} bytes")]
+    OutOfBounds(u32, u32),
+}
+
+/// A WASM module loaded for a plugin. Plugins import nothing and export:
+/// - `memory`
+/// - `alloc(len: i32) -> i32`, returning a buffer the host writes inputs to
+/// - the functions called with [`WasmPlugin::call`], taking a `(ptr: i32, len: i32)` pair per
+///   input and returning `(ptr << 32) | len` of their output, or 0 for none.
+pub struct WasmPlugin {
+    store: Store<()>,
+    instance: Instance,
+    memory: Memory,
+    alloc: TypedFunc<i32, i32>,
+}
+
+impl WasmPlugin {
+    pub fn load(path: &Path) -> Result<Self, WasmPluginError> {
+        let engine = Engine::new(Config::new().consume_fuel(true))?;
+        let module = Module::from_file(&engine, path)?;
+        let mut store = Store::new(&engine, ());
+        store.set_fuel(FUEL_PER_CALL)?;
+        let instance = Instance::new(&mut store, &module, &[])?;
+        let memory = instance
+            .get_memory(&mut store, "memory")
+            .ok_or(WasmPluginError::MissingExport("memory"))?;
+        let alloc = instance.get_typed_func(&mut store, "alloc")?;
+        Ok(Self {
+            store,
+            instance,
+            memory,
+            alloc,
+        })
+    }
+
+    pub fn exports(&mut self, name: &str) -> bool {
+        self.instance.get_func(&mut self.store, name).is_some()
+    }
+
+    /// Calls `export` with `inputs` copied into the plugin's memory. None if the plugin
+    /// returned no output.
+    pub fn call(
+        &mut self,
+        export: &'static str,
+        inputs: &[&[u8]],
+    ) -> Result<Option<Vec<u8>>, WasmPluginError> {
+        self.store.set_fuel(FUEL_PER_CALL)?;
+        let func = self
+            .instance
+            .get_func(&mut self.store, export)
+            .ok_or(WasmPluginError::MissingExport(export))?;
+        let mut args = Vec::with_capacity(inputs.len() * 2);
+        for input in inputs {
+            let ptr = self.alloc.call(&mut self.store, input.len() as i32)?;
+            self.memory
+                .write(&mut self.store, ptr as u32 as usize, input)
+                .map_err(wasmtime::Error::from)?;
+            args.push(Val::I32(ptr));
+            args.push(Val::I32(input.len() as i32));
+        }
+        let mut result = [Val::I64(0)];
+        func.call(&mut self.store, &args, &mut result)?;
+        let packed = result[0].unwrap_i64() as u64;
+        if packed == 0 {
+            return Ok(None);
+        }
+        let (ptr, len) = ((packed >> 32) as u32, packed as u32);
+        let mut output = vec![0; len as usize];
+        self.memory
+            .read(&self.store, ptr as usize, &mut output)
+            .map_err(|_| WasmPluginError::OutOfBounds(ptr, len))?;
+        Ok(Some(output))
+    }
+}
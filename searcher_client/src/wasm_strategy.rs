@@ -0,0 +1,275 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    str::FromStr,
+    time::SystemTime,
+};
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use jito_protos::bundle::{bundle_result::Result as BundleResultType, BundleResult};
+use serde::{Deserialize, Serialize};
+use solana_metrics::datapoint_info;
+use solana_sdk::{
+    clock::Slot,
+    instruction::{AccountMeta, Instruction},
+    pubkey::Pubkey,
+    transaction::VersionedTransaction,
+};
+use thiserror::Error;
+
+use crate::{
+    priority::PriorityClass,
+    strategy_config::StrategyConfig,
+    wasm_plugin::{WasmPlugin, WasmPluginError},
+};
+
+#[derive(Debug, Error)]
+pub enum WasmStrategyError {
+    #[error("{0}")]
+    Plugin(#[from] WasmPluginError),
+    #[error("io error {0}")]
+    Io(#[from] std::io::Error),
+    #[error("strategy returned invalid bundles: {0}")]
+    InvalidJson(#[from] serde_json::Error),
+    #[error("strategy returned an invalid pubkey {0:?}")]
+    InvalidPubkey(String),
+    #[error("strategy returned invalid instruction data: {0}")]
+    InvalidData(#[from] base64::DecodeError),
+}
+
+/// What a strategy is handed, serialized as JSON with a `kind` tag.
+#[derive(Debug, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum StrategyEvent {
+    /// A pending transaction from the mempool. `transaction` is the bincode serialized
+    /// transaction in base64, the rest is decoded from it for strategies that don't parse it.
+    MempoolTx {
+        slot: Slot,
+        signature: String,
+        account_keys: Vec<String>,
+        transaction: String,
+    },
+    Slot {
+        slot: Slot,
+    },
+    /// `outcome` is one of accepted, processed, finalized, rejected or dropped.
+    BundleResult {
+        bundle_id: String,
+        outcome: String,
+    },
+    /// A bundle the strategy asked for was dropped instead of submitted. `id` is the bundle's
+    /// `id` if it had one, `reason` is expired or deadline_missed.
+    BundleDropped {
+        id: Option<String>,
+        trigger_signature: String,
+        reason: String,
+    },
+}
+
+impl StrategyEvent {
+    pub fn mempool_tx(slot: Slot, tx: &VersionedTransaction) -> Self {
+        Self::MempoolTx {
+            slot,
+            signature: tx.signatures[0].to_string(),
+            account_keys: tx
+                .message
+                .static_account_keys()
+                .iter()
+                .map(Pubkey::to_string)
+                .collect(),
+            transaction: STANDARD.encode(bincode::serialize(tx).expect("serializes")),
+        }
+    }
+
+    pub fn bundle_result(bundle_result: &BundleResult) -> Self {
+        let outcome = match &bundle_result.result {
+            Some(BundleResultType::Accepted(_)) => "accepted",
+            Some(BundleResultType::Processed(_)) => "processed",
+            Some(BundleResultType::Finalized(_)) => "finalized",
+            Some(BundleResultType::Rejected(_)) => "rejected",
+            Some(BundleResultType::Dropped(_)) | None => "dropped",
+        };
+        Self::BundleResult {
+            bundle_id: bundle_result.bundle_id.clone(),
+            outcome: outcome.to_string(),
+        }
+    }
+}
+
+/// A bundle a strategy asks for. The host signs it with the payer, appends the tip transfer and
+/// places the mempool transaction first if `include_trigger` is set.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct BundleSpec {
+    pub instructions: Vec<InstructionSpec>,
+    pub tip_lamports: u64,
+    #[serde(default)]
+    pub expected_profit_lamports: Option<u64>,
+    /// Only meaningful in response to a mempool transaction event.
+    #[serde(default)]
+    pub include_trigger: bool,
+    /// Echoed back in a bundle_dropped event if the bundle isn't submitted.
+    #[serde(default)]
+    pub id: Option<String>,
+    /// Last slot the bundle may be submitted in, capped by the host's own expiry.
+    #[serde(default)]
+    pub expiry_slot: Option<Slot>,
+    /// Unix time in milliseconds after which the bundle is dropped instead of submitted.
+    #[serde(default)]
+    pub deadline_unix_ms: Option<u64>,
+    /// bulk, normal or critical, the pipeline's class if not set. The tip is scaled by the
+    /// class's multiplier.
+    #[serde(default)]
+    pub priority: Option<PriorityClass>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct InstructionSpec {
+    pub program_id: String,
+    #[serde(default)]
+    pub accounts: Vec<AccountMetaSpec>,
+    /// Base64 encoded.
+    #[serde(default)]
+    pub data: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AccountMetaSpec {
+    pub pubkey: String,
+    #[serde(default)]
+    pub is_signer: bool,
+    #[serde(default)]
+    pub is_writable: bool,
+}
+
+impl InstructionSpec {
+    pub fn to_instruction(&self) -> Result<Instruction, WasmStrategyError> {
+        Ok(Instruction {
+            program_id: parse_pubkey(&self.program_id)?,
+            accounts: self
+                .accounts
+                .iter()
+                .map(|meta| {
+                    Ok(AccountMeta {
+                        pubkey: parse_pubkey(&meta.pubkey)?,
+                        is_signer: meta.is_signer,
+                        is_writable: meta.is_writable,
+                    })
+                })
+                .collect::<Result<_, WasmStrategyError>>()?,
+            data: STANDARD.decode(&self.data)?,
+        })
+    }
+}
+
+fn parse_pubkey(s: &str) -> Result<Pubkey, WasmStrategyError> {
+    Pubkey::from_str(s).map_err(|_| WasmStrategyError::InvalidPubkey(s.to_string()))
+}
+
+/// Settings of [`WasmStrategy`], its `wasm` section in a strategy config.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct WasmStrategyConfig {
+    pub path: PathBuf,
+    /// Candidate module run in shadow next to the live one, whose bundles are compared but
+    /// never sent.
+    #[serde(default)]
+    pub shadow_path: Option<PathBuf>,
+}
+
+impl StrategyConfig for WasmStrategyConfig {
+    const SECTION: &'static str = "wasm";
+
+    fn validate(&self) -> Result<(), String> {
+        if self.shadow_path.as_ref() == Some(&self.path) {
+            return Err("shadow_path is the live module's path".to_string());
+        }
+        Ok(())
+    }
+}
+
+/// A strategy compiled to WASM from any language. The module follows the [`WasmPlugin`]
+/// conventions and exports `on_event(ptr: i32, len: i32) -> i64`, taking a [`StrategyEvent`] and
+/// returning a JSON array of [`BundleSpec`]s, or 0 for none.
+///
+/// The module is swapped for a new build when the file changes, without restarting.
+pub struct WasmStrategy {
+    path: PathBuf,
+    plugin: WasmPlugin,
+    modified: Option<SystemTime>,
+    events: u64,
+    bundles: u64,
+    errors: u64,
+    reloads: u64,
+}
+
+impl WasmStrategy {
+    pub fn load(path: &Path) -> Result<Self, WasmStrategyError> {
+        let modified = fs::metadata(path)?.modified().ok();
+        let mut plugin = WasmPlugin::load(path)?;
+        if !plugin.exports("on_event") {
+            return Err(WasmPluginError::MissingExport("on_event").into());
+        }
+        Ok(Self {
+            path: path.to_path_buf(),
+            plugin,
+            modified,
+            events: 0,
+            bundles: 0,
+            errors: 0,
+            reloads: 0,
+        })
+    }
+
+    /// Hands `event` to the strategy and returns the bundles it asked for.
+    pub fn on_event(
+        &mut self,
+        event: &StrategyEvent,
+    ) -> Result<Vec<BundleSpec>, WasmStrategyError> {
+        self.events += 1;
+        let result = self.call(event);
+        match &result {
+            Ok(bundles) => self.bundles += bundles.len() as u64,
+            Err(_) => self.errors += 1,
+        }
+        result
+    }
+
+    fn call(&mut self, event: &StrategyEvent) -> Result<Vec<BundleSpec>, WasmStrategyError> {
+        let event = serde_json::to_vec(event).expect("serializes");
+        match self.plugin.call("on_event", &[&event])? {
+            Some(output) => Ok(serde_json::from_slice(&output)?),
+            None => Ok(Vec::new()),
+        }
+    }
+
+    /// Loads the module again if the file changed since it was last checked. Returns true if it
+    /// was swapped. The running module is kept if the new one fails to load, until the file
+    /// changes again.
+    pub fn reload_if_changed(&mut self) -> Result<bool, WasmStrategyError> {
+        let modified = fs::metadata(&self.path)?.modified().ok();
+        if modified == self.modified {
+            return Ok(false);
+        }
+        self.modified = modified;
+        self.plugin = Self::load(&self.path)?.plugin;
+        self.reloads += 1;
+        Ok(true)
+    }
+
+    pub fn report(&mut self, name: &'static str) {
+        datapoint_info!(
+            name,
+            ("events", self.events, i64),
+            ("bundles", self.bundles, i64),
+            ("errors", self.errors, i64),
+            ("reloads", self.reloads, i64),
+        );
+        self.events = 0;
+        self.bundles = 0;
+        self.errors = 0;
+        self.reloads = 0;
+    }
+}
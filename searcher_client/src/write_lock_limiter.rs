@@ -0,0 +1,356 @@
+use std::collections::{HashMap, HashSet};
+
+use solana_metrics::datapoint_info;
+use solana_sdk::{
+    clock::{Slot, NUM_CONSECUTIVE_LEADER_SLOTS},
+    pubkey::Pubkey,
+    transaction::VersionedTransaction,
+};
+use thiserror::Error;
+
+use crate::priority::PriorityClass;
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum WriteLockConflict {
+    #[error("{0} bundles already sent this leader window")]
+    WindowFull(usize),
+    #[error("{account} is already write-locked by {bundles} bundles this leader window")]
+    AccountBusy { account: Pubkey, bundles: usize },
+}
+
+/// Limits on how many bundles may write-lock the same account within one leader window.
+#[derive(Clone, Debug)]
+pub struct WriteLockLimits {
+    /// Default limit for every account.
+    pub max_bundles_per_account: usize,
+    /// Overrides the default limit for specific accounts.
+    pub account_limits: HashMap<Pubkey, usize>,
+    /// Limit on bundles sent per leader window regardless of accounts.
+    pub max_bundles_per_window: Option<usize>,
+    /// Critical bundles may go over `max_bundles_per_window` by this many per leader window.
+    pub critical_reserve: usize,
+    /// Accounts every bundle write-locks, such as the payer and tip accounts, which would
+    /// otherwise conflict with everything.
+    pub ignored_accounts: HashSet<Pubkey>,
+}
+
+impl WriteLockLimits {
+    fn account_limit(&self, account: &Pubkey) -> usize {
+        self.account_limits
+            .get(account)
+            .copied()
+            .unwrap_or(self.max_bundles_per_account)
+    }
+}
+
+/// Tracks the accounts write-locked by bundles sent in the current leader window, so the bot
+/// doesn't bid against itself in the state auction.
+#[derive(Debug)]
+pub struct WriteLockLimiter {
+    limits: WriteLockLimits,
+    window: u64,
+    bundles_in_window: usize,
+    reserve_in_window: usize,
+    locks: HashMap<Pubkey, usize>,
+    accepted: u64,
+    conflicts: u64,
+    reserve_used: u64,
+}
+
+impl WriteLockLimiter {
+    pub fn new(limits: WriteLockLimits) -> Self {
+        Self {
+            limits,
+            window: 0,
+            bundles_in_window: 0,
+            reserve_in_window: 0,
+            locks: HashMap::new(),
+            accepted: 0,
+            conflicts: 0,
+            reserve_used: 0,
+        }
+    }
+
+    /// Reserves the bundle's write-locked accounts for the leader window containing `slot`, or
+    /// returns the first limit it would exceed. A critical bundle that finds the window full
+    /// takes a slot from the reserve instead, the per account limits still apply.
+    pub fn try_acquire(
+        &mut self,
+        slot: Slot,
+        transactions: &[VersionedTransaction],
+        class: PriorityClass,
+    ) -> Result<(), WriteLockConflict> {
+        let window = slot / NUM_CONSECUTIVE_LEADER_SLOTS;
+        if window != self.window {
+            self.window = window;
+            self.bundles_in_window = 0;
+            self.reserve_in_window = 0;
+            self.locks.clear();
+        }
+
+        let mut from_reserve = false;
+        let mut result = self.check(transactions);
+        if matches!(result, Err(WriteLockConflict::WindowFull(_)))
+            && class == PriorityClass::Critical
+            && self.reserve_in_window < self.limits.critical_reserve
+        {
+            from_reserve = true;
+            result = self.check_accounts(transactions);
+        }
+        match &result {
+            Ok(accounts) => {
+                if from_reserve {
+                    self.reserve_in_window += 1;
+                    self.reserve_used += 1;
+                } else {
+                    self.bundles_in_window += 1;
+                }
+                for account in accounts {
+                    *self.locks.entry(*account).or_default() += 1;
+                }
+                self.accepted += 1;
+            }
+            Err(_) => self.conflicts += 1,
+        }
+        result.map(|_| ())
+    }
+
+    fn check(
+        &self,
+        transactions: &[VersionedTransaction],
+    ) -> Result<HashSet<Pubkey>, WriteLockConflict> {
+        if let Some(max_bundles) = self.limits.max_bundles_per_window {
+            if self.bundles_in_window >= max_bundles {
+                return Err(WriteLockConflict::WindowFull(self.bundles_in_window));
+            }
+        }
+        self.check_accounts(transactions)
+    }
+
+    fn check_accounts(
+        &self,
+        transactions: &[VersionedTransaction],
+    ) -> Result<HashSet<Pubkey>, WriteLockConflict> {
+        let accounts: HashSet<Pubkey> = write_locked_accounts(transactions)
+            .into_iter()
+            .filter(|account| !self.limits.ignored_accounts.contains(account))
+            .collect();
+        for account in &accounts {
+            let bundles = self.locks.get(account).copied().unwrap_or(0);
+            if bundles >= self.limits.account_limit(account) {
+                return Err(WriteLockConflict::AccountBusy {
+                    account: *account,
+                    bundles,
+                });
+            }
+        }
+        Ok(accounts)
+    }
+
+    /// Reports the counters and resets them.
+    pub fn report(&mut self, name: &'static str) {
+        datapoint_info!(
+            name,
+            ("accepted", self.accepted, i64),
+            ("conflicts", self.conflicts, i64),
+            ("reserve_used", self.reserve_used, i64),
+            ("locked_accounts", self.locks.len(), i64),
+        );
+        self.accepted = 0;
+        self.conflicts = 0;
+        self.reserve_used = 0;
+    }
+}
+
+/// Accounts the transactions may write-lock. Only static account keys are considered, accounts
+/// loaded through address lookup tables aren't known without fetching the tables.
+pub fn write_locked_accounts(transactions: &[VersionedTransaction]) -> HashSet<Pubkey> {
+    transactions
+        .iter()
+        .flat_map(|tx| {
+            tx.message
+                .static_account_keys()
+                .iter()
+                .enumerate()
+                .filter(|(i, _)| tx.message.is_maybe_writable(*i))
+                .map(|(_, account)| *account)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::{HashMap, HashSet};
+
+    use solana_sdk::{
+        clock::NUM_CONSECUTIVE_LEADER_SLOTS,
+        hash::Hash,
+        instruction::{AccountMeta, Instruction},
+        pubkey::Pubkey,
+        signature::{Keypair, Signer},
+        transaction::{Transaction, VersionedTransaction},
+    };
+
+    use crate::{
+        priority::PriorityClass,
+        write_lock_limiter::{WriteLockConflict, WriteLockLimiter, WriteLockLimits},
+    };
+
+    /// A transaction from `payer` write-locking `account` and reading `readonly`.
+    fn bundle(payer: &Keypair, account: Pubkey, readonly: Pubkey) -> Vec<VersionedTransaction> {
+        let ix = Instruction::new_with_bytes(
+            Pubkey::new_unique(),
+            &[],
+            vec![
+                AccountMeta::new(account, false),
+                AccountMeta::new_readonly(readonly, false),
+            ],
+        );
+        vec![VersionedTransaction::from(
+            Transaction::new_signed_with_payer(
+                &[ix],
+                Some(&payer.pubkey()),
+                &[payer],
+                Hash::new_unique(),
+            ),
+        )]
+    }
+
+    fn limits(payer: &Keypair) -> WriteLockLimits {
+        WriteLockLimits {
+            max_bundles_per_account: 1,
+            account_limits: HashMap::new(),
+            max_bundles_per_window: None,
+            critical_reserve: 0,
+            ignored_accounts: HashSet::from([payer.pubkey()]),
+        }
+    }
+
+    #[test]
+    fn test_account_limit() {
+        let payer = Keypair::new();
+        let (account, other, readonly) = (
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+        );
+        let mut limiter = WriteLockLimiter::new(limits(&payer));
+
+        assert_eq!(
+            limiter.try_acquire(0, &bundle(&payer, account, readonly), PriorityClass::Normal),
+            Ok(())
+        );
+        assert_eq!(
+            limiter.try_acquire(1, &bundle(&payer, account, other), PriorityClass::Critical),
+            Err(WriteLockConflict::AccountBusy {
+                account,
+                bundles: 1
+            })
+        );
+        // the payer is ignored and the readonly account isn't locked
+        assert_eq!(
+            limiter.try_acquire(1, &bundle(&payer, other, readonly), PriorityClass::Normal),
+            Ok(())
+        );
+
+        // locks are released with the next leader window
+        assert_eq!(
+            limiter.try_acquire(
+                NUM_CONSECUTIVE_LEADER_SLOTS,
+                &bundle(&payer, account, readonly),
+                PriorityClass::Normal
+            ),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn test_account_limit_override() {
+        let payer = Keypair::new();
+        let (account, readonly) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let mut limiter = WriteLockLimiter::new(WriteLockLimits {
+            account_limits: HashMap::from([(account, 2)]),
+            ..limits(&payer)
+        });
+
+        let bundle = bundle(&payer, account, readonly);
+        assert_eq!(
+            limiter.try_acquire(0, &bundle, PriorityClass::Normal),
+            Ok(())
+        );
+        assert_eq!(
+            limiter.try_acquire(0, &bundle, PriorityClass::Normal),
+            Ok(())
+        );
+        assert_eq!(
+            limiter.try_acquire(0, &bundle, PriorityClass::Normal),
+            Err(WriteLockConflict::AccountBusy {
+                account,
+                bundles: 2
+            })
+        );
+    }
+
+    #[test]
+    fn test_window_limit_and_critical_reserve() {
+        let payer = Keypair::new();
+        let mut limiter = WriteLockLimiter::new(WriteLockLimits {
+            max_bundles_per_window: Some(1),
+            critical_reserve: 1,
+            ..limits(&payer)
+        });
+        let next_bundle = || bundle(&payer, Pubkey::new_unique(), Pubkey::new_unique());
+
+        assert_eq!(
+            limiter.try_acquire(0, &next_bundle(), PriorityClass::Normal),
+            Ok(())
+        );
+        assert_eq!(
+            limiter.try_acquire(0, &next_bundle(), PriorityClass::Normal),
+            Err(WriteLockConflict::WindowFull(1))
+        );
+        assert_eq!(
+            limiter.try_acquire(0, &next_bundle(), PriorityClass::Critical),
+            Ok(())
+        );
+        assert_eq!(
+            limiter.try_acquire(0, &next_bundle(), PriorityClass::Critical),
+            Err(WriteLockConflict::WindowFull(1))
+        );
+
+        // the reserve is refilled with the next leader window
+        let slot = NUM_CONSECUTIVE_LEADER_SLOTS;
+        assert_eq!(
+            limiter.try_acquire(slot, &next_bundle(), PriorityClass::Normal),
+            Ok(())
+        );
+        assert_eq!(
+            limiter.try_acquire(slot, &next_bundle(), PriorityClass::Critical),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn test_critical_reserve_keeps_account_limits() {
+        let payer = Keypair::new();
+        let (account, readonly) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let mut limiter = WriteLockLimiter::new(WriteLockLimits {
+            max_bundles_per_window: Some(1),
+            critical_reserve: 1,
+            ..limits(&payer)
+        });
+
+        let bundle = bundle(&payer, account, readonly);
+        assert_eq!(
+            limiter.try_acquire(0, &bundle, PriorityClass::Normal),
+            Ok(())
+        );
+        assert_eq!(
+            limiter.try_acquire(0, &bundle, PriorityClass::Critical),
+            Err(WriteLockConflict::AccountBusy {
+                account,
+                bundles: 1
+            })
+        );
+    }
+}
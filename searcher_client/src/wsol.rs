@@ -0,0 +1,133 @@
+use std::sync::Arc;
+
+use solana_sdk::{
+    instruction::Instruction,
+    program_pack::Pack,
+    pubkey::Pubkey,
+    rent::Rent,
+    signature::{Keypair, Signer},
+    system_instruction,
+};
+use spl_associated_token_account::{
+    get_associated_token_address, instruction::create_associated_token_account_idempotent,
+};
+use spl_token::{instruction as token_instruction, native_mint, state::Account as TokenAccount};
+
+/// Rent a wSOL token account holds on top of the wrapped SOL, returned when it's closed.
+pub fn wsol_account_rent() -> u64 {
+    Rent::default().minimum_balance(TokenAccount::LEN)
+}
+
+/// The wSOL associated token account of `owner`.
+pub fn wsol_address(owner: &Pubkey) -> Pubkey {
+    get_associated_token_address(owner, &native_mint::id())
+}
+
+/// Wraps `lamports` into `owner`'s wSOL associated token account: creates it if needed, moves
+/// the lamports in and syncs the token balance with them. `payer` pays the rent if the account
+/// is created, `owner` must sign for the transfer.
+pub fn wrap_sol(payer: &Pubkey, owner: &Pubkey, lamports: u64) -> Vec<Instruction> {
+    let wsol_account = wsol_address(owner);
+    vec![
+        create_associated_token_account_idempotent(
+            payer,
+            owner,
+            &native_mint::id(),
+            &spl_token::id(),
+        ),
+        system_instruction::transfer(owner, &wsol_account, lamports),
+        sync_native(&wsol_account),
+    ]
+}
+
+/// Unwraps all of `owner`'s wSOL by closing its associated token account, sending the wrapped
+/// SOL and the rent to `destination`. Partial unwraps aren't possible with wSOL, transfer the
+/// rest to another wSOL account first to keep it.
+pub fn unwrap_sol(owner: &Pubkey, destination: &Pubkey) -> Instruction {
+    close_wsol_account(&wsol_address(owner), owner, destination)
+}
+
+/// Tells the token program to count lamports sent straight to a wSOL account as wrapped SOL.
+/// Needed after every system transfer into one, or the token balance won't include them.
+pub fn sync_native(wsol_account: &Pubkey) -> Instruction {
+    token_instruction::sync_native(&spl_token::id(), wsol_account).expect("valid token program")
+}
+
+/// Closes a wSOL account owned by `owner`, sending everything it holds to `destination`.
+pub fn close_wsol_account(
+    wsol_account: &Pubkey,
+    owner: &Pubkey,
+    destination: &Pubkey,
+) -> Instruction {
+    token_instruction::close_account(&spl_token::id(), wsol_account, destination, owner, &[])
+        .expect("valid token program")
+}
+
+/// A wSOL account that lives for one transaction: opened and funded before a swap, closed
+/// after it, so any unspent SOL and the rent come back in the same transaction and nothing is
+/// left behind if the bundle doesn't land. Its address is a new keypair, which must sign the
+/// transaction along with the payer, e.g. with [`TransactionSpec::with_signer`].
+///
+/// [`TransactionSpec::with_signer`]: crate::bundle_builder::TransactionSpec::with_signer
+pub struct TemporaryWsolAccount {
+    account: Arc<Keypair>,
+    owner: Pubkey,
+}
+
+impl TemporaryWsolAccount {
+    pub fn new(owner: Pubkey) -> Self {
+        Self {
+            account: Arc::new(Keypair::new()),
+            owner,
+        }
+    }
+
+    pub fn address(&self) -> Pubkey {
+        self.account.pubkey()
+    }
+
+    /// The keypair that must sign the transaction opening the account.
+    pub fn signer(&self) -> Arc<Keypair> {
+        self.account.clone()
+    }
+
+    /// Creates the account funded with `lamports` of wSOL on top of its rent, paid by `payer`.
+    pub fn open(&self, payer: &Pubkey, lamports: u64) -> Vec<Instruction> {
+        let address = self.address();
+        vec![
+            system_instruction::create_account(
+                payer,
+                &address,
+                wsol_account_rent().saturating_add(lamports),
+                TokenAccount::LEN as u64,
+                &spl_token::id(),
+            ),
+            token_instruction::initialize_account3(
+                &spl_token::id(),
+                &address,
+                &native_mint::id(),
+                &self.owner,
+            )
+            .expect("valid token program"),
+        ]
+    }
+
+    /// Closes the account, sending what's left of the wSOL and the rent to `destination`.
+    pub fn close(&self, destination: &Pubkey) -> Instruction {
+        close_wsol_account(&self.address(), &self.owner, destination)
+    }
+
+    /// `instructions`, such as a swap spending or receiving wSOL through this account, between
+    /// opening it with `lamports` paid by `payer` and closing it back to `payer`.
+    pub fn wrap_around(
+        &self,
+        payer: &Pubkey,
+        lamports: u64,
+        instructions: impl IntoIterator<Item = Instruction>,
+    ) -> Vec<Instruction> {
+        let mut wrapped = self.open(payer, lamports);
+        wrapped.extend(instructions);
+        wrapped.push(self.close(payer));
+        wrapped
+    }
+}